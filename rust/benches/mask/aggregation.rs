@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use paste::paste;
+
+use xaynet_core::mask::{
+    Aggregation,
+    BoundType,
+    DataType,
+    FromPrimitives,
+    GroupType,
+    MaskConfig,
+    MaskObject,
+    MaskVect,
+    Masker,
+    Model,
+    ModelType,
+    Scalar,
+};
+
+fn make_masked_models(n_elements: usize) -> (MaskConfig, Vec<MaskObject>) {
+    let config = MaskConfig {
+        group_type: GroupType::Prime,
+        data_type: DataType::F32,
+        bound_type: BoundType::B0,
+        model_type: ModelType::M3,
+    };
+    let model = Model::from_primitives(vec![0_i32; n_elements].into_iter()).unwrap();
+    let masked_models = (0..2)
+        .map(|_| Masker::new(config.into()).mask(Scalar::unit(), &model).1)
+        .collect();
+    (config, masked_models)
+}
+
+/// Aggregates the mask vectors with the per element `BigUint` arithmetic as a reference.
+fn aggregate_biguint(mut aggregated: MaskVect, vect: &MaskVect) -> MaskVect {
+    let order = aggregated.config.order();
+    for (i, j) in aggregated.data.iter_mut().zip(vect.data.iter()) {
+        *i = (&*i + j) % &order;
+    }
+    aggregated
+}
+
+macro_rules! fn_aggregate {
+    ($name: ident, $size: expr) => {
+        paste! {
+            #[allow(non_snake_case)]
+            fn [<aggregate $name>](crit: &mut Criterion) {
+                let (config, masked_models) = make_masked_models($size);
+                let name = &stringify!($name)[1..];
+
+                let mut aggregation = Aggregation::new(config.into(), $size);
+                aggregation.aggregate(masked_models[0].clone());
+                crit.bench_function(
+                    format!("aggregate {} masked model into flat residues", name).as_str(),
+                    |bench| {
+                        bench.iter_batched(
+                            || (aggregation.clone(), masked_models[1].clone()),
+                            |(mut aggregation, masked_model)| {
+                                aggregation.aggregate(black_box(masked_model))
+                            },
+                            BatchSize::LargeInput,
+                        )
+                    },
+                );
+
+                crit.bench_function(
+                    format!("aggregate {} masked model into biguints", name).as_str(),
+                    |bench| {
+                        bench.iter_batched(
+                            || masked_models[0].vect.clone(),
+                            |aggregated| {
+                                aggregate_biguint(aggregated, black_box(&masked_models[1].vect))
+                            },
+                            BatchSize::LargeInput,
+                        )
+                    },
+                );
+            }
+        }
+    };
+}
+
+// 1 weight
+fn_aggregate!(_tiny, 1);
+
+// 25_600 weights, i.e. 100kB of f32 weights
+fn_aggregate!(_100kB, 25_600);
+
+// 256_000 weights, i.e. 1MB of f32 weights
+fn_aggregate!(_1MB, 256_000);
+
+criterion_group!(
+    name = bench_mask_aggregation;
+    config = Criterion::default().sample_size(20).measurement_time(Duration::new(10, 0));
+    targets =
+        aggregate_tiny,
+        aggregate_100kB,
+        aggregate_1MB,
+);
+criterion_main!(bench_mask_aggregation);
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use futures::future::join_all;
+use tokio::{
+    runtime::{Builder, Runtime},
+    time::{interval, Instant, MissedTickBehavior},
+};
+
+use xaynet_core::{
+    common::{RoundParameters, RoundSeed},
+    crypto::{ByteObject, CryptoSuite, EncryptKeyPair, SigningKeyPair},
+    mask::{BoundType, DataType, GroupType, MaskConfig, ModelType},
+    message::Message,
+    testutils::multipart as helpers,
+};
+use xaynet_server::{
+    services::messages::{MessageThreadPool, PetMessageHandler},
+    state_machine::{
+        events::{EventPublisher, ModelUpdate},
+        phases::PhaseName,
+        requests::RequestReceiver,
+    },
+};
+
+/// The number of messages of a flood.
+const FLOOD_SIZE: usize = 64;
+
+/// The period of the timer whose latency is measured.
+const TICK: Duration = Duration::from_millis(1);
+
+fn round_params(keys: &EncryptKeyPair) -> RoundParameters {
+    RoundParameters {
+        pk: keys.public,
+        sum: 0.0,
+        // no-one is eligible for the update task, hence the messages are rejected after all the
+        // CPU-intensive checks passed and the state machine can be left out
+        update: 0.0,
+        seed: RoundSeed::generate(),
+        mask_config: MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        }
+        .into(),
+        model_length: 0,
+        next_commitment: None,
+        round_bound_masks: false,
+        eligibility_salt: Vec::new(),
+        allow_sum_eligible_updates: false,
+        timeline: None,
+        crypto_suite: CryptoSuite::SHA256,
+    }
+}
+
+/// Creates a flood of encrypted update messages of roughly 100kB each.
+fn flood(params: &RoundParameters) -> Vec<Vec<u8>> {
+    (0..FLOOD_SIZE)
+        .map(|_| {
+            let keys = SigningKeyPair::generate();
+            let update = helpers::update(112_004, 6_018);
+            let message = Message::new_update(keys.public, params.pk, update);
+            let mut buffer = vec![0; message.buffer_length()];
+            message.to_bytes(&mut buffer, &keys.secret);
+            params.pk.encrypt(&buffer)
+        })
+        .collect()
+}
+
+/// Handles a flood of messages and returns the p99 latency of a timer during the flood.
+fn p99_timer_latency(
+    runtime: &Runtime,
+    handler: &PetMessageHandler,
+    flood: &[Vec<u8>],
+) -> Duration {
+    runtime.block_on(async {
+        let messages = flood
+            .iter()
+            .cloned()
+            .map(|enc_data| {
+                let mut handler = handler.clone();
+                tokio::spawn(async move { handler.handle_message(enc_data).await })
+            })
+            .collect::<Vec<_>>();
+        let flood = tokio::spawn(join_all(messages));
+
+        let mut timer = interval(TICK);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut latencies = Vec::new();
+        while !flood.is_finished() {
+            let scheduled = timer.tick().await;
+            latencies.push(Instant::now().saturating_duration_since(scheduled));
+        }
+        flood.await.unwrap();
+
+        latencies.sort_unstable();
+        latencies
+            .get(latencies.len() * 99 / 100)
+            .copied()
+            .unwrap_or_default()
+    })
+}
+
+fn timer_latency(crit: &mut Criterion) {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_time()
+        .build()
+        .unwrap();
+
+    let keys = EncryptKeyPair::generate();
+    let params = round_params(&keys);
+    let (_publisher, subscriber) = EventPublisher::init(
+        0,
+        keys,
+        params.clone(),
+        PhaseName::Update,
+        ModelUpdate::Invalidate,
+    );
+    let (_request_rx, request_tx) = RequestReceiver::new();
+    let thread_pool = MessageThreadPool::new(None).unwrap();
+    // the message handler spawns its buffers on the runtime
+    let handler = {
+        let _runtime = runtime.enter();
+        PetMessageHandler::with_thread_pool(&subscriber, request_tx, thread_pool)
+    };
+    let flood = flood(&params);
+
+    let mut crit = crit.benchmark_group("timer latency during a flood of update messages");
+    crit.bench_function("p99 latency of a 1ms timer", |bench| {
+        bench.iter_custom(|iters| {
+            (0..iters)
+                .map(|_| p99_timer_latency(&runtime, &handler, &flood))
+                .sum()
+        })
+    });
+}
+
+criterion_group!(
+    name = bench_message_flood;
+    // Every iteration handles a whole flood of messages, which takes
+    // a while. The measured time is the p99 latency of the timer
+    // instead of the wall time of the iterations.
+    config = Criterion::default().sample_size(10);
+    targets = timer_latency,
+);
+criterion_main!(bench_message_flood);
@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use xaynet_core::{
+    common::RoundSeed,
+    crypto::{ByteObject, CryptoSuite, SigningKeyPair},
+};
+use xaynet_server::services::messages::{verify_signatures, SignatureCheck};
+
+/// Creates the sum signature checks of `count` participants.
+fn checks(count: usize) -> Vec<SignatureCheck> {
+    let seed = RoundSeed::generate();
+    let payload = CryptoSuite::SHA256.task_signature_payload(seed.as_slice(), b"sum");
+    (0..count)
+        .map(|_| {
+            let keys = SigningKeyPair::generate();
+            SignatureCheck {
+                pk: keys.public,
+                signature: keys.secret.sign_detached(&payload),
+                payload: payload.clone(),
+            }
+        })
+        .collect()
+}
+
+fn signature_verification(crit: &mut Criterion) {
+    let mut crit = crit.benchmark_group("verify task signatures");
+    for count in [16, 64] {
+        let checks = checks(count);
+        crit.bench_with_input(
+            BenchmarkId::new("individually", count),
+            &checks,
+            |bench, checks| {
+                bench.iter(|| {
+                    black_box(checks)
+                        .iter()
+                        .map(SignatureCheck::verify)
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        crit.bench_with_input(
+            BenchmarkId::new("batched", count),
+            &checks,
+            |bench, checks| bench.iter(|| verify_signatures(black_box(checks))),
+        );
+    }
+}
+
+criterion_group!(
+    name = bench_signature_verification;
+    config = Criterion::default();
+    targets = signature_verification,
+);
+criterion_main!(bench_signature_verification);
@@ -9,7 +9,7 @@ use xaynet_core::{
 fn make_update(dict_len: usize, mask_len: usize, total_expected_len: usize) -> (Update, Vec<u8>) {
     let update = helpers::update(dict_len, mask_len);
     // just check that we made our calculation right
-    // message size = dict_len + mask_len + 64*2
+    // message size = dict_len + mask_len + 64*2 + 1 + 32 + 3
     assert_eq!(update.buffer_length(), total_expected_len);
     let mut bytes = vec![0; update.buffer_length()];
     update.to_bytes(&mut bytes);
@@ -49,27 +49,27 @@ macro_rules! fn_from_bytes {
 // Get an update that corresponds to:
 // - 1 sum participant (1 entry in the seed dict)
 // - a 42 bytes serialized masked model
-fn_from_bytes!(_tiny, 116, 42, 286);
+fn_from_bytes!(_tiny, 116, 42, 322);
 
 // Get an update that corresponds to:
 // - 1k sum participants (1k entries in the seed dict)
 // - a 6kB serialized masked model
-fn_from_bytes!(_100kB, 112_004, 6_018, 118_150);
+fn_from_bytes!(_100kB, 112_004, 6_018, 118_186);
 
 // Get an update that corresponds to:
 // - 10k sum participants (10k entries in the seed dict)
 // - a 60kB serialized masked model
-fn_from_bytes!(_1MB, 1_120_004, 60_018, 1_180_150);
+fn_from_bytes!(_1MB, 1_120_004, 60_018, 1_180_186);
 
 // Get an update that corresponds to:
 // - 10k sum participants (10k entries in the seed dict)
 // - a ~1MB serialized masked model
-fn_from_bytes!(_2MB, 1_120_004, 1_000_020, 2_120_152);
+fn_from_bytes!(_2MB, 1_120_004, 1_000_020, 2_120_188);
 
 // Get an update that corresponds to:
 // - 10k sum participants (10k entries in the seed dict)
 // - a ~9MB serialized masked model
-fn_from_bytes!(_10MB, 1_120_004, 9_000_018, 10_120_150);
+fn_from_bytes!(_10MB, 1_120_004, 9_000_018, 10_120_186);
 
 criterion_group!(
     name = bench_update_message;
@@ -109,7 +109,8 @@ impl Participant {
 struct Notifier(mpsc::Sender<Event>);
 
 impl Notify for Notifier {
-    fn new_round(&mut self) {
+    fn new_round(&mut self, round_token: &str) {
+        info!("new round {}", round_token);
         if let Err(e) = self.0.try_send(Event::NewRound) {
             warn!("failed to notify participant: {}", e);
         }
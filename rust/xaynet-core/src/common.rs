@@ -1,7 +1,56 @@
+use std::{
+    convert::TryInto,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context};
+use num::traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use sodiumoxide::{self, crypto::box_};
 
-use crate::{crypto::ByteObject, mask::MaskConfigPair, CoordinatorPublicKey};
+use crate::{
+    crypto::{ByteObject, CryptoSuite, Sha256},
+    mask::{
+        config::serialization::MASK_CONFIG_BUFFER_LEN,
+        DataType,
+        Endianness,
+        MaskConfig,
+        MaskConfigPair,
+        Model,
+        ModelCastError,
+    },
+    message::{DecodeError, FromBytes, ToBytes},
+    CoordinatorPublicKey,
+    SumDict,
+};
+
+/// The number of basis points in a whole, i.e. a basis point is a fraction of `1 / 10000`.
+pub const BASIS_POINTS: u32 = 10_000;
+
+/// Converts a `fraction` to basis points.
+///
+/// The fraction is rounded to the nearest basis point and clamped to `0..=BASIS_POINTS`, `NaN`
+/// converts to `0`. Fractions which only differ in the last bits of their floating point
+/// representation, e.g. because they have been parsed by different languages, convert to the same
+/// basis points.
+pub fn to_basis_points(fraction: f64) -> u32 {
+    let basis_points = (fraction * f64::from(BASIS_POINTS)).round();
+    if basis_points >= f64::from(BASIS_POINTS) {
+        BASIS_POINTS
+    } else if basis_points > 0. {
+        basis_points as u32
+    } else {
+        // also catches NaN
+        0
+    }
+}
+
+/// Converts `basis_points` to a fraction.
+///
+/// This is the inverse of [`to_basis_points()`] for `basis_points` in `0..=BASIS_POINTS`.
+pub fn from_basis_points(basis_points: u32) -> f64 {
+    f64::from(basis_points) / f64::from(BASIS_POINTS)
+}
 
 /// The round parameters.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -18,6 +67,470 @@ pub struct RoundParameters {
     pub mask_config: MaskConfigPair,
     /// The length of the model.
     pub model_length: usize,
+    /// The commitment to the seed and fractions of the next round, if the coordinator commits to
+    /// them in advance.
+    pub next_commitment: Option<Sha256>,
+    /// Whether the masks of the round are bound to the round seed, i.e. derived with
+    /// [`MaskSeed::derive_mask_for_round()`] instead of [`MaskSeed::derive_mask()`].
+    ///
+    /// [`MaskSeed::derive_mask_for_round()`]: crate::mask::MaskSeed::derive_mask_for_round
+    /// [`MaskSeed::derive_mask()`]: crate::mask::MaskSeed::derive_mask
+    pub round_bound_masks: bool,
+    /// The salt which is mixed into the eligibility computation, see
+    /// [`Signature::is_eligible_salted()`]. It is empty if the coordinator doesn't salt the
+    /// eligibility.
+    ///
+    /// [`Signature::is_eligible_salted()`]: crate::crypto::Signature::is_eligible_salted
+    pub eligibility_salt: Vec<u8>,
+    /// Whether a participant which is eligible for both tasks may choose the update task instead
+    /// of the sum task. Otherwise, the sum task takes precedence and the update messages of
+    /// sum-eligible participants are rejected.
+    pub allow_sum_eligible_updates: bool,
+    /// The timeline of the phases of the round, if the coordinator publishes it.
+    pub timeline: Option<PhaseTimeline>,
+    /// The crypto suite of the round, which determines the hash function of the protocol.
+    #[serde(default)]
+    pub crypto_suite: CryptoSuite,
+}
+
+impl RoundParameters {
+    /// Computes the commitment to the given round seed and fractions.
+    ///
+    /// The commitment is the hash of the concatenation of the seed and the little-endian
+    /// encoded sum and update fractions in [basis points].
+    ///
+    /// [basis points]: to_basis_points
+    pub fn commit(seed: &RoundSeed, sum: f64, update: f64) -> Sha256 {
+        Sha256::hash(
+            &[
+                seed.as_slice(),
+                &to_basis_points(sum).to_le_bytes(),
+                &to_basis_points(update).to_le_bytes(),
+            ]
+            .concat(),
+        )
+    }
+
+    /// Gets the fraction of participants to be selected for the sum task in basis points.
+    pub fn sum_basis_points(&self) -> u32 {
+        to_basis_points(self.sum)
+    }
+
+    /// Gets the fraction of participants to be selected for the update task in basis points.
+    pub fn update_basis_points(&self) -> u32 {
+        to_basis_points(self.update)
+    }
+
+    /// Computes the commitment to the seed and fractions of these round parameters.
+    pub fn commitment(&self) -> Sha256 {
+        Self::commit(&self.seed, self.sum, self.update)
+    }
+
+    /// Checks whether the seed and fractions of these round parameters match the `commitment`,
+    /// which has been published by the coordinator in the previous round.
+    pub fn verify_commitment(&self, commitment: &Sha256) -> bool {
+        &self.commitment() == commitment
+    }
+
+    /// Serializes the round parameters canonically, independent of any serde format. This is the
+    /// form which is to be signed or hashed.
+    ///
+    /// The fields are laid out in big endian as follows:
+    /// - the public key of the coordinator
+    /// - the sum and update fractions in [basis points] as `u32`s
+    /// - the `u32` length of the round seed followed by the seed
+    /// - the vector and unit masking configurations
+    /// - the model length as `u64`
+    /// - a `u8` flag whether a commitment follows, followed by the commitment if any
+    /// - a `u8` flag whether the masks are bound to the round
+    /// - the `u32` length of the eligibility salt followed by the salt
+    /// - a `u8` flag whether sum-eligible participants may update
+    /// - a `u8` flag whether a timeline follows, followed by the round start and the sum, update
+    ///   and sum2 deadlines as `u64`s if any
+    /// - the `u8` identifier of the crypto suite, unless it is [`CryptoSuite::SHA256`]
+    ///
+    /// Equal round parameters always serialize to identical bytes. The fractions are only
+    /// preserved up to basis points.
+    ///
+    /// [basis points]: to_basis_points
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(self.pk.as_slice());
+        bytes.extend_from_slice(&self.sum_basis_points().to_be_bytes());
+        bytes.extend_from_slice(&self.update_basis_points().to_be_bytes());
+        bytes.extend_from_slice(&(self.seed.as_slice().len() as u32).to_be_bytes());
+        bytes.extend_from_slice(self.seed.as_slice());
+        for config in [self.mask_config.vect, self.mask_config.unit].iter() {
+            let mut buffer = vec![0; config.buffer_length()];
+            config.to_bytes(&mut buffer);
+            bytes.extend_from_slice(&buffer);
+        }
+        bytes.extend_from_slice(&(self.model_length as u64).to_be_bytes());
+        match self.next_commitment {
+            Some(ref commitment) => {
+                bytes.push(1);
+                bytes.extend_from_slice(commitment.as_slice());
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(self.round_bound_masks as u8);
+        bytes.extend_from_slice(&(self.eligibility_salt.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.eligibility_salt);
+        bytes.push(self.allow_sum_eligible_updates as u8);
+        match self.timeline {
+            Some(ref timeline) => {
+                bytes.push(1);
+                for timestamp in [
+                    timeline.round_start,
+                    timeline.sum_deadline,
+                    timeline.update_deadline,
+                    timeline.sum2_deadline,
+                ]
+                .iter()
+                {
+                    bytes.extend_from_slice(&timestamp.to_be_bytes());
+                }
+            }
+            None => bytes.push(0),
+        }
+        // omitted for the default suite, which keeps the form of the first protocol versions
+        if self.crypto_suite != CryptoSuite::SHA256 {
+            bytes.push(self.crypto_suite.id());
+        }
+        bytes
+    }
+
+    /// Deserializes round parameters from their canonical form, see [`to_bytes()`].
+    ///
+    /// # Errors
+    /// Fails if the bytes are truncated or trailed by other bytes, or if a field is invalid.
+    ///
+    /// [`to_bytes()`]: RoundParameters::to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = CanonicalReader(bytes);
+        let pk = CoordinatorPublicKey::from_slice(reader.take(CoordinatorPublicKey::LENGTH)?)
+            .context("invalid public key")?;
+        let sum = from_basis_points(reader.u32()?);
+        let update = from_basis_points(reader.u32()?);
+        let seed_length = reader.u32()? as usize;
+        let seed = RoundSeed::from_slice(reader.take(seed_length)?)
+            .context("invalid round seed length")?;
+        let vect = MaskConfig::from_byte_slice(&reader.take(MASK_CONFIG_BUFFER_LEN)?)
+            .context("invalid vector masking configuration")?;
+        let unit = MaskConfig::from_byte_slice(&reader.take(MASK_CONFIG_BUFFER_LEN)?)
+            .context("invalid unit masking configuration")?;
+        let model_length = reader.u64()?.try_into().context("invalid model length")?;
+        let next_commitment = match reader.u8()? {
+            0 => None,
+            1 => Some(
+                Sha256::from_slice(reader.take(Sha256::LENGTH)?).context("invalid commitment")?,
+            ),
+            flag => return Err(anyhow!("invalid commitment flag {}", flag)),
+        };
+        let round_bound_masks = match reader.u8()? {
+            0 => false,
+            1 => true,
+            flag => return Err(anyhow!("invalid mask binding flag {}", flag)),
+        };
+        let salt_length = reader.u32()? as usize;
+        let eligibility_salt = reader.take(salt_length)?.to_vec();
+        let allow_sum_eligible_updates = match reader.u8()? {
+            0 => false,
+            1 => true,
+            flag => return Err(anyhow!("invalid sum-eligible updates flag {}", flag)),
+        };
+        let timeline = match reader.u8()? {
+            0 => None,
+            1 => Some(PhaseTimeline {
+                round_start: reader.u64()?,
+                sum_deadline: reader.u64()?,
+                update_deadline: reader.u64()?,
+                sum2_deadline: reader.u64()?,
+            }),
+            flag => return Err(anyhow!("invalid timeline flag {}", flag)),
+        };
+        let crypto_suite = match reader.0.is_empty() {
+            true => CryptoSuite::SHA256,
+            false => match CryptoSuite::new(reader.u8()?) {
+                // the default suite is never serialized explicitly
+                CryptoSuite::SHA256 => return Err(anyhow!("explicit default crypto suite")),
+                suite => suite,
+            },
+        };
+        if !reader.0.is_empty() {
+            return Err(anyhow!("{} trailing bytes", reader.0.len()));
+        }
+
+        Ok(Self {
+            pk,
+            sum,
+            update,
+            seed,
+            mask_config: MaskConfigPair { vect, unit },
+            model_length,
+            next_commitment,
+            round_bound_masks,
+            eligibility_salt,
+            allow_sum_eligible_updates,
+            timeline,
+            crypto_suite,
+        })
+    }
+}
+
+/// A reader of the canonical form of the [`RoundParameters`].
+struct CanonicalReader<'a>(&'a [u8]);
+
+impl<'a> CanonicalReader<'a> {
+    /// Takes the next `length` bytes.
+    fn take(&mut self, length: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < length {
+            return Err(anyhow!(
+                "truncated round parameters: expected {} more bytes, but {} are left",
+                length,
+                self.0.len()
+            ));
+        }
+        let (head, tail) = self.0.split_at(length);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        // safe unwrap: the slice is 4 bytes long
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        // safe unwrap: the slice is 8 bytes long
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+/// The timeline of the phases of a round.
+///
+/// The timestamps are milliseconds since the unix epoch, as measured by the coordinator. Each
+/// deadline is the latest point in time at which the respective phase ends, i.e. after the maximal
+/// times of the phase and of all previous phases have passed since the start of the round. A phase
+/// ends earlier as soon as it has processed enough messages, and it is extended while the
+/// coordinator is paused. A phase which the coordinator skips has the deadline of the previous
+/// phase.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PhaseTimeline {
+    /// The start of the round.
+    pub round_start: u64,
+    /// The deadline of the sum phase.
+    pub sum_deadline: u64,
+    /// The deadline of the update phase.
+    pub update_deadline: u64,
+    /// The deadline of the sum2 phase.
+    pub sum2_deadline: u64,
+}
+
+impl PhaseTimeline {
+    /// Creates the timeline of a round which starts at `round_start` from the maximal times of
+    /// its sum, update and sum2 phases.
+    pub fn new(
+        round_start: SystemTime,
+        sum_max: Duration,
+        update_max: Duration,
+        sum2_max: Duration,
+    ) -> Self {
+        let round_start = unix_millis(round_start);
+        let sum_deadline = round_start.saturating_add(sum_max.as_millis() as u64);
+        let update_deadline = sum_deadline.saturating_add(update_max.as_millis() as u64);
+        let sum2_deadline = update_deadline.saturating_add(sum2_max.as_millis() as u64);
+        Self {
+            round_start,
+            sum_deadline,
+            update_deadline,
+            sum2_deadline,
+        }
+    }
+
+    /// Gets the time which is left at `now` until the sum phase ends at the latest.
+    pub fn sum_time_left(&self, now: SystemTime) -> Duration {
+        time_left(self.sum_deadline, now)
+    }
+
+    /// Gets the time which is left at `now` until the update phase ends at the latest.
+    ///
+    /// An update participant should only train its local model if the training finishes before.
+    pub fn update_time_left(&self, now: SystemTime) -> Duration {
+        time_left(self.update_deadline, now)
+    }
+
+    /// Gets the time which is left at `now` until the sum2 phase ends at the latest.
+    pub fn sum2_time_left(&self, now: SystemTime) -> Duration {
+        time_left(self.sum2_deadline, now)
+    }
+}
+
+/// Converts a point in time to milliseconds since the unix epoch.
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Gets the time which is left at `now` until the `deadline` in milliseconds since the unix epoch.
+fn time_left(deadline: u64, now: SystemTime) -> Duration {
+    Duration::from_millis(deadline.saturating_sub(unix_millis(now)))
+}
+
+/// The version of the layout of the [`RoundSummary`].
+///
+/// The layout of version 1 lacks the [`RoundSummary::crypto_suite`]. The coordinator serves it to
+/// participants which don't request a version.
+pub const ROUND_SUMMARY_VERSION: u16 = 2;
+
+/// A summary of the current round.
+///
+/// It contains everything a participant needs to check locally whether it is selected for a
+/// task in the round, without fetching the full [`RoundParameters`]. Its layout is versioned,
+/// see [`ROUND_SUMMARY_VERSION`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoundSummary {
+    /// The id of the round.
+    pub round_id: u64,
+    /// The random round seed.
+    pub seed: RoundSeed,
+    /// The token of the round seed, see [`RoundSeed::token()`].
+    pub round_token: String,
+    /// The name of the current phase of the round.
+    pub phase: String,
+    /// Fraction of participants to be selected for the sum task.
+    pub sum: f64,
+    /// Fraction of participants to be selected for the update task.
+    pub update: f64,
+    /// The salt which is mixed into the eligibility computation, see
+    /// [`RoundParameters::eligibility_salt`].
+    pub eligibility_salt: Vec<u8>,
+    /// The crypto suite of the round, see [`RoundParameters::crypto_suite`].
+    ///
+    /// This is missing from the layout of version 1 of the summary.
+    pub crypto_suite: CryptoSuite,
+}
+
+/// The metadata of a global model.
+///
+/// It is published by the coordinator alongside the global model of a round and describes how the
+/// weights of the model are to be interpreted, in particular the original primitive data type of
+/// the weights, which is required to reconstruct integer models exactly.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GlobalModelMetadata {
+    /// The id of the round in which the model was aggregated.
+    pub round_id: u64,
+    /// The original primitive data type of the weights.
+    pub data_type: DataType,
+    /// The length of the model.
+    pub model_length: usize,
+    /// The masking configuration of the round.
+    pub mask_config: MaskConfigPair,
+    /// The number of aggregated local models.
+    pub nb_models: usize,
+    /// An upper bound of the absolute error of each aggregated weight before the division by the
+    /// sum of the scalars, which stems from the fixed-point representation of the scaled weights
+    /// during masking.
+    pub error_bound: f64,
+}
+
+impl GlobalModelMetadata {
+    /// Creates the metadata of a global model of the given length, which has been aggregated from
+    /// `nb_models` local models masked wrt the `mask_config` in the round with the given id.
+    ///
+    /// The data type is the one of the vector masking configuration. Each scaled weight is
+    /// truncated to a multiple of `1 / exp_shift` during masking, hence the error bound is
+    /// `nb_models / exp_shift`.
+    pub fn new(
+        round_id: u64,
+        mask_config: MaskConfigPair,
+        model_length: usize,
+        nb_models: usize,
+    ) -> Self {
+        let error_bound = mask_config
+            .vect
+            .max_absolute_error(nb_models)
+            .to_f64()
+            .unwrap_or_default();
+        Self {
+            round_id,
+            data_type: mask_config.vect.data_type,
+            model_length,
+            mask_config,
+            nb_models,
+            error_bound,
+        }
+    }
+
+    /// Converts the `model` into a buffer of raw primitive values of the declared data type, each
+    /// encoded with the given `endianness`.
+    ///
+    /// # Errors
+    /// Fails if a weight can't be converted into the declared data type.
+    pub fn to_primitives_bytes(
+        &self,
+        model: &Model,
+        endianness: Endianness,
+    ) -> Result<Vec<u8>, ModelCastError> {
+        model.to_primitives_bytes(self.data_type, endianness)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The sum dictionary of a round together with the seed of that round.
+///
+/// The update participants encrypt their mask seeds with the ephemeral keys of the sum dictionary,
+/// hence a sum dictionary of another round renders their seeds useless. The round seed ties the sum
+/// dictionary to the [`RoundParameters`] of its round.
+pub struct RoundSumDict {
+    /// The seed of the round of the sum dictionary.
+    pub seed: RoundSeed,
+    /// The sum dictionary.
+    pub sum_dict: SumDict,
+}
+
+impl RoundSumDict {
+    /// Checks whether the sum dictionary belongs to the round with the given parameters.
+    pub fn is_for(&self, round_params: &RoundParameters) -> bool {
+        self.seed == round_params.seed
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// The messages of a participant which the coordinator recorded in the current round.
+pub struct SubmissionStatus {
+    /// Whether the sum dictionary contains an entry of the participant.
+    pub sum: bool,
+    /// Whether the seed dictionary contains the seeds of the participant.
+    pub update: bool,
+    /// Whether the mask dictionary contains a mask of the participant.
+    pub sum2: bool,
+    /// Whether the participant has been evicted from the bounded sum dictionary.
+    pub evicted: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The submission status of a participant together with the seed of the round it belongs to.
+///
+/// The public key of a participant is the same in every round, hence the round seed tells
+/// whether the recorded messages belong to the round the participant sent its messages in.
+pub struct RoundSubmissionStatus {
+    /// The seed of the round of the submission status.
+    pub seed: RoundSeed,
+    /// The submission status.
+    pub status: SubmissionStatus,
+}
+
+impl RoundSubmissionStatus {
+    /// Checks whether the submission status belongs to the round with the given parameters.
+    pub fn is_for(&self, round_params: &RoundParameters) -> bool {
+        self.seed == round_params.seed
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -45,3 +558,290 @@ impl ByteObject for RoundSeed {
         self.0.as_ref()
     }
 }
+
+impl RoundSeed {
+    /// The number of bytes of the seed hash in a [`token()`].
+    ///
+    /// [`token()`]: RoundSeed::token
+    pub const TOKEN_BYTES: usize = 8;
+
+    /// Gets a short token which identifies the round.
+    ///
+    /// The token is the hex encoding of the first [`TOKEN_BYTES`] of the SHA256 hash of the seed.
+    /// It is stable for the round and doesn't reveal the seed, hence it is safe to log and to
+    /// join round data across services.
+    ///
+    /// [`TOKEN_BYTES`]: RoundSeed::TOKEN_BYTES
+    pub fn token(&self) -> String {
+        Sha256::hash(self.as_slice()).as_slice()[..Self::TOKEN_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use crate::{
+        crypto::EncryptKeyPair,
+        mask::{BoundType, FromPrimitives, GroupType, MaskConfig, ModelType},
+    };
+
+    fn round_params() -> RoundParameters {
+        RoundParameters {
+            pk: EncryptKeyPair::generate().public,
+            sum: 0.1,
+            update: 0.5,
+            seed: RoundSeed::generate(),
+            mask_config: MaskConfig {
+                group_type: GroupType::Prime,
+                data_type: DataType::F32,
+                bound_type: BoundType::B0,
+                model_type: ModelType::M3,
+            }
+            .into(),
+            model_length: 4,
+            next_commitment: None,
+            round_bound_masks: false,
+            eligibility_salt: Vec::new(),
+            allow_sum_eligible_updates: false,
+            timeline: None,
+            crypto_suite: CryptoSuite::SHA256,
+        }
+    }
+
+    #[test]
+    fn test_round_params_bytes_roundtrip() {
+        let mut params = round_params();
+        params.sum = 0.25;
+        assert_eq!(RoundParameters::from_bytes(&params.to_bytes()).unwrap(), params);
+
+        params.next_commitment = Some(params.commitment());
+        params.round_bound_masks = true;
+        params.eligibility_salt = b"deployment".to_vec();
+        params.allow_sum_eligible_updates = true;
+        params.timeline = Some(PhaseTimeline::new(
+            SystemTime::now(),
+            Duration::from_secs(10),
+            Duration::from_secs(20),
+            Duration::from_secs(30),
+        ));
+        let bytes = params.to_bytes();
+        assert_eq!(RoundParameters::from_bytes(&bytes).unwrap(), params);
+
+        // truncated and trailing bytes
+        assert!(RoundParameters::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(RoundParameters::from_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_round_params_bytes_crypto_suite() {
+        let mut params = round_params();
+        let default_bytes = params.to_bytes();
+
+        params.crypto_suite = CryptoSuite::BLAKE3;
+        let bytes = params.to_bytes();
+        assert_eq!(bytes[..bytes.len() - 1], default_bytes[..]);
+        assert_eq!(bytes.last(), Some(&CryptoSuite::BLAKE3.id()));
+        assert_eq!(RoundParameters::from_bytes(&bytes).unwrap(), params);
+
+        // the default suite is implicit only
+        let mut explicit = default_bytes;
+        explicit.push(CryptoSuite::SHA256.id());
+        assert!(RoundParameters::from_bytes(&explicit).is_err());
+    }
+
+    #[test]
+    fn test_round_params_bytes_are_deterministic() {
+        let params = round_params();
+        assert_eq!(params.to_bytes(), params.clone().to_bytes());
+
+        // fractions which only differ in the last bits serialize alike
+        let params2 = RoundParameters {
+            sum: 0.1 + f64::EPSILON,
+            ..params.clone()
+        };
+        assert_eq!(params.to_bytes(), params2.to_bytes());
+
+        let params3 = RoundParameters {
+            seed: RoundSeed::generate(),
+            ..params.clone()
+        };
+        assert_ne!(params.to_bytes(), params3.to_bytes());
+    }
+
+    #[test]
+    fn test_phase_timeline() {
+        let round_start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let timeline = PhaseTimeline::new(
+            round_start,
+            Duration::from_secs(10),
+            Duration::from_millis(20_500),
+            Duration::from_secs(30),
+        );
+        assert_eq!(
+            timeline,
+            PhaseTimeline {
+                round_start: 1_000_000,
+                sum_deadline: 1_010_000,
+                update_deadline: 1_030_500,
+                sum2_deadline: 1_060_500,
+            }
+        );
+
+        let now = round_start + Duration::from_secs(15);
+        assert_eq!(timeline.sum_time_left(now), Duration::from_secs(0));
+        assert_eq!(
+            timeline.update_time_left(now),
+            Duration::from_millis(15_500)
+        );
+        assert_eq!(timeline.sum2_time_left(now), Duration::from_millis(45_500));
+    }
+
+    #[test]
+    fn test_global_model_metadata() {
+        let mask_config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::I64,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        };
+        let metadata = GlobalModelMetadata::new(1, mask_config.into(), 3, 2);
+        assert_eq!(metadata.data_type, DataType::I64);
+        assert_eq!(metadata.model_length, 3);
+        assert!((metadata.error_bound / 2e-10 - 1.).abs() < 1e-9);
+
+        let primitives = [-1_i64, 0, i64::MAX];
+        let model = Model::from_primitives(primitives.iter().cloned()).unwrap();
+        let bytes = metadata
+            .to_primitives_bytes(&model, Endianness::Big)
+            .unwrap();
+        assert_eq!(
+            bytes,
+            primitives
+                .iter()
+                .flat_map(|i| i.to_be_bytes())
+                .collect::<Vec<u8>>(),
+        );
+
+        let metadata = GlobalModelMetadata::new(
+            1,
+            MaskConfig {
+                data_type: DataType::F64,
+                ..mask_config
+            }
+            .into(),
+            3,
+            2,
+        );
+        assert!((metadata.error_bound / 2e-20 - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_round_sum_dict_is_for() {
+        let params = round_params();
+        let round_sum_dict = RoundSumDict {
+            seed: params.seed.clone(),
+            sum_dict: SumDict::new(),
+        };
+        assert!(round_sum_dict.is_for(&params));
+        assert!(!round_sum_dict.is_for(&round_params()));
+    }
+
+    #[test]
+    fn test_round_submission_status_is_for() {
+        let params = round_params();
+        let status = RoundSubmissionStatus {
+            seed: params.seed.clone(),
+            status: SubmissionStatus {
+                sum: true,
+                ..SubmissionStatus::default()
+            },
+        };
+        assert!(status.is_for(&params));
+        assert!(!status.is_for(&round_params()));
+    }
+
+    #[test]
+    fn test_round_token_is_stable() {
+        let seed = RoundSeed::zeroed();
+        assert_eq!(seed.token(), "66687aadf862bd77");
+        assert_eq!(seed.token(), seed.clone().token());
+
+        let seed = RoundSeed::generate();
+        assert_eq!(seed.token().len(), 2 * RoundSeed::TOKEN_BYTES);
+        assert_eq!(seed.token(), seed.token());
+    }
+
+    #[test]
+    fn test_round_token_collisions() {
+        let tokens = (0..10_000)
+            .map(|_| RoundSeed::generate().token())
+            .collect::<HashSet<_>>();
+        assert_eq!(tokens.len(), 10_000);
+    }
+
+    #[test]
+    fn test_basis_points() {
+        assert_eq!(to_basis_points(0.), 0);
+        assert_eq!(to_basis_points(0.01), 100);
+        assert_eq!(to_basis_points(0.1), 1_000);
+        assert_eq!(to_basis_points(1.), BASIS_POINTS);
+        assert_eq!(to_basis_points(-0.5), 0);
+        assert_eq!(to_basis_points(1.5), BASIS_POINTS);
+        assert_eq!(to_basis_points(f64::NAN), 0);
+        for basis_points in 0..=BASIS_POINTS {
+            assert_eq!(
+                to_basis_points(from_basis_points(basis_points)),
+                basis_points
+            );
+        }
+    }
+
+    #[test]
+    fn test_commitment_is_stable() {
+        let params = round_params();
+        // the fractions differ in the last bit of their floating point representation
+        let sum = f64::from_bits(params.sum.to_bits() + 1);
+        let update = f64::from_bits(params.update.to_bits() - 1);
+        assert!((params.sum - sum).abs() > 0.);
+        assert!(params.verify_commitment(&RoundParameters::commit(&params.seed, sum, update)));
+    }
+
+    #[test]
+    fn test_verify_commitment() {
+        let params = round_params();
+        let commitment = RoundParameters::commit(&params.seed, params.sum, params.update);
+        assert!(params.verify_commitment(&commitment));
+
+        // the commitment only covers the seed and fractions
+        let mut revealed = params.clone();
+        revealed.pk = EncryptKeyPair::generate().public;
+        revealed.model_length = 8;
+        assert!(revealed.verify_commitment(&commitment));
+    }
+
+    #[test]
+    fn test_verify_altered_commitment() {
+        let params = round_params();
+        let commitment = params.commitment();
+
+        let mut altered = params.clone();
+        altered.seed = RoundSeed::generate();
+        assert!(!altered.verify_commitment(&commitment));
+
+        let mut altered = params.clone();
+        altered.sum = 0.2;
+        assert!(!altered.verify_commitment(&commitment));
+
+        let mut altered = params;
+        altered.update = 0.6;
+        assert!(!altered.verify_commitment(&commitment));
+    }
+}
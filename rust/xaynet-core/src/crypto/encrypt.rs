@@ -7,9 +7,9 @@
 
 use derive_more::{AsMut, AsRef, From};
 use serde::{Deserialize, Serialize};
-use sodiumoxide::crypto::{box_, sealedbox};
+use sodiumoxide::crypto::{box_, hash::sha256, sealedbox};
 
-use super::ByteObject;
+use super::{ByteObject, SecretSigningKey};
 
 /// Number of additional bytes in a ciphertext compared to the corresponding plaintext.
 pub const SEALBYTES: usize = sealedbox::SEALBYTES;
@@ -41,6 +41,28 @@ impl EncryptKeyPair {
             secret: sk,
         }
     }
+
+    /// Deterministically derives a new `C25519` key pair for encryption from a secret signing key
+    /// and a `context`, like the seed of a round.
+    ///
+    /// The same key pair is derived for the same secret key and context, hence it can be derived
+    /// again instead of being stored.
+    ///
+    /// # Security
+    /// The derived key pair is only as secret as the signing key: whoever learns the signing key
+    /// can derive the key pairs for all contexts, including past ones. Unlike [`generate()`]d key
+    /// pairs, derived key pairs thus don't provide forward secrecy.
+    ///
+    /// [`generate()`]: EncryptKeyPair::generate
+    pub fn derive_from_signing_key(sk: &SecretSigningKey, context: &[u8]) -> Self {
+        let mut hasher = sha256::State::new();
+        hasher.update(b"xaynet encrypt key pair");
+        hasher.update(sk.as_slice());
+        hasher.update(context);
+        // safe unwrap: the digest and the seed have the same length
+        let seed = EncryptKeySeed::from_slice(hasher.finalize().as_ref()).unwrap();
+        Self::derive_from_seed(&seed)
+    }
 }
 
 #[derive(
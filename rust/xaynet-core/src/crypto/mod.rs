@@ -3,7 +3,7 @@
 //! The wrappers provide methods defined on structs instead of the sodiumoxide functions. This is
 //! done for the `C25519` encryption and `Ed25519` signature key pairs and their corresponding seeds
 //! as well as the `SHA256` hash function. Additionally, some methods for slicing and signature
-//! eligibility are available.
+//! eligibility are available. The [`CryptoSuite`] versions the hash function of the PET protocol.
 //!
 //! # Examples
 //! ## Encryption of messages
@@ -30,6 +30,7 @@ pub(crate) mod encrypt;
 pub(crate) mod hash;
 pub(crate) mod prng;
 pub(crate) mod sign;
+pub(crate) mod suite;
 
 use sodiumoxide::randombytes::randombytes;
 
@@ -38,6 +39,7 @@ pub use self::{
     hash::Sha256,
     prng::generate_integer,
     sign::{PublicSigningKey, SecretSigningKey, Signature, SigningKeyPair, SigningKeySeed},
+    suite::{CryptoSuite, UnsupportedCryptoSuite},
 };
 
 /// An interface for slicing into cryptographic byte objects.
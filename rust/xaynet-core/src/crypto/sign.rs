@@ -16,6 +16,7 @@ use serde::{Deserialize, Serialize};
 use sodiumoxide::crypto::{hash::sha256, sign};
 
 use super::ByteObject;
+use crate::common::{to_basis_points, BASIS_POINTS};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A `Ed25519` key pair for signatures.
@@ -183,21 +184,62 @@ impl Signature {
     /// ```no_rust
     /// int(hash(signature)) / (2**hashbits - 1) <= threshold.
     /// ```
+    ///
+    /// The threshold is converted to [basis points] first, see [`is_eligible_basis_points()`].
+    /// Hence, the threshold is quantized to multiples of `0.0001`, e.g. a threshold below
+    /// `0.00005` rounds to `0` and a threshold of at least `0.99995` rounds to `1`.
+    ///
+    /// [basis points]: crate::common::to_basis_points
+    /// [`is_eligible_basis_points()`]: Signature::is_eligible_basis_points
     pub fn is_eligible(&self, threshold: f64) -> bool {
+        self.is_eligible_salted(threshold, &[])
+    }
+
+    /// Computes the rational representation of the hashed signature and ensures that it is below
+    /// the given threshold in basis points:
+    /// ```no_rust
+    /// int(hash(signature)) / (2**hashbits - 1) <= threshold / 10000.
+    /// ```
+    ///
+    /// Unlike a comparison with a floating point threshold, this is exact and hence platform
+    /// independent.
+    pub fn is_eligible_basis_points(&self, threshold: u32) -> bool {
+        self.is_eligible_basis_points_salted(threshold, &[])
+    }
+
+    /// Checks the eligibility like [`is_eligible()`], but mixes the `salt` into the hash:
+    /// ```no_rust
+    /// int(hash(salt || signature)) / (2**hashbits - 1) <= threshold.
+    /// ```
+    ///
+    /// A coordinator specific salt separates the selection of participants between deployments,
+    /// even if they share round seeds and participant keys. An empty salt is the same as no salt.
+    /// The threshold is quantized to multiples of `0.0001` like in [`is_eligible()`].
+    ///
+    /// [`is_eligible()`]: Signature::is_eligible
+    pub fn is_eligible_salted(&self, threshold: f64, salt: &[u8]) -> bool {
         if threshold < 0_f64 {
             return false;
-        } else if threshold > 1_f64 {
+        }
+        self.is_eligible_basis_points_salted(to_basis_points(threshold), salt)
+    }
+
+    /// Checks the eligibility like [`is_eligible_basis_points()`], but mixes the `salt` into the
+    /// hash, see [`is_eligible_salted()`].
+    ///
+    /// [`is_eligible_basis_points()`]: Signature::is_eligible_basis_points
+    /// [`is_eligible_salted()`]: Signature::is_eligible_salted
+    pub fn is_eligible_basis_points_salted(&self, threshold: u32, salt: &[u8]) -> bool {
+        if threshold >= BASIS_POINTS {
             return true;
         }
+        let hash = sha256::hash(&[salt, self.as_slice()].concat());
         // safe unwraps: `to_bigint` never fails for `BigUint`s
-        let numer = BigUint::from_bytes_le(sha256::hash(self.as_slice()).as_ref())
-            .to_bigint()
-            .unwrap();
+        let numer = BigUint::from_bytes_le(hash.as_ref()).to_bigint().unwrap();
         let denom = BigUint::from_bytes_le([u8::MAX; sha256::DIGESTBYTES].as_ref())
             .to_bigint()
             .unwrap();
-        // safe unwrap: `threshold` is guaranteed to be finite
-        Ratio::new(numer, denom) <= Ratio::from_float(threshold).unwrap()
+        Ratio::new(numer, denom) <= Ratio::new(threshold.into(), BASIS_POINTS.into())
     }
 }
 
@@ -245,6 +287,7 @@ mod tests {
             43, 89, 28, 242, 194, 4, 0,
         ]);
         assert!(sig.is_eligible(0.5_f64));
+        assert!(sig.is_eligible_basis_points(5_000));
 
         // ineligible signature
         let sig = Signature::from_slice_unchecked(&[
@@ -254,5 +297,37 @@ mod tests {
             234, 100, 40, 199, 248, 23, 147, 172, 0,
         ]);
         assert!(!sig.is_eligible(0.5_f64));
+        assert!(!sig.is_eligible_basis_points(5_000));
+        assert!(sig.is_eligible_basis_points(BASIS_POINTS));
+
+        // the thresholds are quantized to basis points
+        assert!(!sig.is_eligible(0.00004_f64));
+        assert!(sig.is_eligible(0.99996_f64));
+    }
+
+    #[test]
+    fn test_signature_is_eligible_salted() {
+        let signatures = (0..64_u8)
+            .map(|i| {
+                let (_, sk) = SigningKeySeed::from_slice_unchecked(&[i; sign::SEEDBYTES])
+                    .derive_signing_key_pair();
+                sk.sign_detached(b"round seed")
+            })
+            .collect::<Vec<_>>();
+
+        // an empty salt is the same as no salt
+        for sig in signatures.iter() {
+            assert_eq!(sig.is_eligible(0.5), sig.is_eligible_salted(0.5, &[]));
+        }
+
+        // the same signatures are selected differently under different salts
+        let selected = |salt: &[u8]| {
+            signatures
+                .iter()
+                .map(|sig| sig.is_eligible_salted(0.5, salt))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(selected(b"deployment a"), selected(b"deployment a"));
+        assert_ne!(selected(b"deployment a"), selected(b"deployment b"));
     }
 }
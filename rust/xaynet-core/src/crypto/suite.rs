@@ -0,0 +1,168 @@
+//! Versioned choices of the hash function of the PET protocol.
+//!
+//! See the [crypto module] documentation since this is a private module anyways.
+//!
+//! [crypto module]: crate::crypto
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::hash::sha256;
+
+/// The domain of the task signature preimages of the crypto suites other than
+/// [`CryptoSuite::SHA256`].
+const TASK_SIGNATURE_DOMAIN: &[u8] = b"xaynet-task-signature";
+
+/// The identifier of the hash function which the coordinator and the participants of a round
+/// use to derive the round seed, to sign the tasks and to hash the masks.
+///
+/// The coordinator publishes the suite in the [`RoundParameters`]. A participant takes only
+/// part in rounds of a suite it supports, which allows to migrate to another hash function
+/// without updating the coordinator and all participants at once.
+///
+/// The suites are identified by a `u8`. Only [`CryptoSuite::SHA256`] is always supported,
+/// [`CryptoSuite::BLAKE3`] requires the `blake3` feature. Every suite has digests of
+/// [`CryptoSuite::DIGEST_LENGTH`] bytes, which is the length of the round seeds.
+///
+/// [`RoundParameters`]: crate::common::RoundParameters
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct CryptoSuite(u8);
+
+impl CryptoSuite {
+    /// The `SHA256` hash function, with the task signature preimages of the first versions of
+    /// the protocol.
+    pub const SHA256: Self = Self(0);
+
+    /// The `BLAKE3` hash function, with domain separated task signature preimages.
+    pub const BLAKE3: Self = Self(1);
+
+    /// The length in bytes of the digests of all suites.
+    pub const DIGEST_LENGTH: usize = 32;
+
+    /// Gets the suite with the given identifier, whether it is supported or not.
+    pub const fn new(id: u8) -> Self {
+        Self(id)
+    }
+
+    /// Gets the identifier of the suite.
+    pub const fn id(self) -> u8 {
+        self.0
+    }
+
+    /// Gets the suites which are supported by this build.
+    pub fn supported() -> &'static [Self] {
+        #[cfg(feature = "blake3")]
+        {
+            &[Self::SHA256, Self::BLAKE3]
+        }
+        #[cfg(not(feature = "blake3"))]
+        {
+            &[Self::SHA256]
+        }
+    }
+
+    /// Checks whether the suite is supported by this build.
+    pub fn is_supported(self) -> bool {
+        Self::supported().contains(&self)
+    }
+
+    /// Computes the digest of the message `m` with the hash function of the suite.
+    ///
+    /// # Errors
+    /// Fails if the suite is not supported by this build.
+    pub fn hash(self, m: &[u8]) -> Result<[u8; Self::DIGEST_LENGTH], UnsupportedCryptoSuite> {
+        let mut digest = [0_u8; Self::DIGEST_LENGTH];
+        match self {
+            Self::SHA256 => digest.copy_from_slice(sha256::hash(m).as_ref()),
+            #[cfg(feature = "blake3")]
+            Self::BLAKE3 => digest.copy_from_slice(blake3::hash(m).as_bytes()),
+            _ => return Err(UnsupportedCryptoSuite(self)),
+        }
+        Ok(digest)
+    }
+
+    /// Gets the bytes to be signed for the `task` in the round with the given `seed`.
+    ///
+    /// For [`CryptoSuite::SHA256`] this is the seed concatenated with the task name `b"sum"` or
+    /// `b"update"`. For every other suite, the seed and task are prefixed with a domain and the
+    /// identifier of the suite, such that a task signature of one suite is never valid in another.
+    pub fn task_signature_payload(self, seed: &[u8], task: &[u8]) -> Vec<u8> {
+        match self {
+            Self::SHA256 => [seed, task].concat(),
+            _ => [TASK_SIGNATURE_DOMAIN, &[self.0], seed, task].concat(),
+        }
+    }
+}
+
+impl Default for CryptoSuite {
+    fn default() -> Self {
+        Self::SHA256
+    }
+}
+
+impl fmt::Display for CryptoSuite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::SHA256 => write!(f, "SHA256 (0)"),
+            Self::BLAKE3 => write!(f, "BLAKE3 (1)"),
+            Self(id) => write!(f, "unknown ({})", id),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("the crypto suite {0} is not supported")]
+/// An error related to a crypto suite which is not supported by this build.
+pub struct UnsupportedCryptoSuite(pub CryptoSuite);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_suite() {
+        let suite = CryptoSuite::default();
+        assert_eq!(suite, CryptoSuite::SHA256);
+        assert!(suite.is_supported());
+        assert_eq!(
+            suite.hash(b"message").unwrap().as_ref(),
+            sha256::hash(b"message").as_ref(),
+        );
+        // the preimages of the first versions of the protocol
+        assert_eq!(
+            suite.task_signature_payload(b"seed", b"sum"),
+            b"seedsum".to_vec(),
+        );
+    }
+
+    #[test]
+    fn test_unsupported_suite() {
+        let suite = CryptoSuite::new(u8::MAX);
+        assert!(!suite.is_supported());
+        assert_eq!(suite.hash(b"message"), Err(UnsupportedCryptoSuite(suite)));
+        assert_ne!(
+            suite.task_signature_payload(b"seed", b"sum"),
+            CryptoSuite::SHA256.task_signature_payload(b"seed", b"sum"),
+        );
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_suite() {
+        let suite = CryptoSuite::BLAKE3;
+        assert!(suite.is_supported());
+        assert_eq!(
+            &suite.hash(b"message").unwrap(),
+            blake3::hash(b"message").as_bytes(),
+        );
+        assert_ne!(
+            suite.hash(b"message").unwrap(),
+            CryptoSuite::SHA256.hash(b"message").unwrap(),
+        );
+        assert_eq!(
+            suite.task_signature_payload(b"seed", b"sum"),
+            b"xaynet-task-signature\x01seedsum".to_vec(),
+        );
+    }
+}
@@ -19,6 +19,7 @@ pub mod common;
 pub mod crypto;
 pub mod mask;
 pub mod message;
+mod seed_dict;
 #[cfg(any(feature = "testutils", test))]
 #[cfg_attr(docsrs, doc(cfg(feature = "testutils")))]
 pub mod testutils;
@@ -32,6 +33,14 @@ use self::crypto::{
     sign::{PublicSigningKey, SecretSigningKey, Signature},
 };
 
+pub use self::seed_dict::{
+    CompressedSeedDict,
+    LocalSeedDict,
+    SeedDictSource,
+    UpdateSeedDict,
+    VersionedSeeds,
+};
+
 #[derive(Error, Debug)]
 #[error("initialization failed: insufficient system entropy to generate secrets")]
 /// An error related to insufficient system entropy for secrets at program startup.
@@ -80,14 +89,7 @@ pub type ParticipantTaskSignature = Signature;
 /// participant to the ephemeral public key generated by that sum participant.
 pub type SumDict = HashMap<SumParticipantPublicKey, SumParticipantEphemeralPublicKey>;
 
-/// Local seed dictionaries are sent by update participants. They contain the participant's masking
-/// seed, encrypted with the ephemeral public key of each sum participant.
-pub type LocalSeedDict = HashMap<SumParticipantPublicKey, mask::seed::EncryptedMaskSeed>;
-
 /// A dictionary created during the update phase of the protocol. The global seed dictionary is
 /// built from the local seed dictionaries sent by the update participants. It maps each sum
 /// participant to the encrypted masking seeds of all the update participants.
 pub type SeedDict = HashMap<SumParticipantPublicKey, UpdateSeedDict>;
-
-/// Values of [`SeedDict`]. Sent to sum participants.
-pub type UpdateSeedDict = HashMap<UpdateParticipantPublicKey, mask::seed::EncryptedMaskSeed>;
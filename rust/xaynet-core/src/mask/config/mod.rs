@@ -118,7 +118,7 @@ impl TryFrom<u8> for BoundType {
             4 => Ok(BoundType::B4),
             6 => Ok(BoundType::B6),
             255 => Ok(BoundType::Bmax),
-            _ => Err(InvalidMaskConfigError::ModelType),
+            _ => Err(InvalidMaskConfigError::BoundType),
         }
     }
 }
@@ -173,12 +173,61 @@ pub struct MaskConfig {
     pub model_type: ModelType,
 }
 
+impl TryFrom<[u8; 4]> for MaskConfig {
+    type Error = InvalidMaskConfigError;
+
+    /// Converts the serialized group, data, bound and model types, in this order.
+    fn try_from(bytes: [u8; 4]) -> Result<Self, Self::Error> {
+        let [group_type, data_type, bound_type, model_type] = bytes;
+        Ok(Self {
+            group_type: GroupType::try_from(group_type)?,
+            data_type: DataType::try_from(data_type)?,
+            bound_type: BoundType::try_from(bound_type)?,
+            model_type: ModelType::try_from(model_type)?,
+        })
+    }
+}
+
+impl From<MaskConfig> for [u8; 4] {
+    /// Converts into the serialized group, data, bound and model types, in this order.
+    fn from(config: MaskConfig) -> Self {
+        [
+            config.group_type as u8,
+            config.data_type as u8,
+            config.bound_type as u8,
+            config.model_type as u8,
+        ]
+    }
+}
+
 impl MaskConfig {
+    /// Gets all catalogued masking configurations for the given data type.
+    pub fn catalogue(data_type: DataType) -> Vec<MaskConfig> {
+        use BoundType::{Bmax, B0, B2, B4, B6};
+        use GroupType::{Integer, Power2, Prime};
+        use ModelType::{M12, M3, M6, M9};
+
+        let mut catalogue = Vec::new();
+        for &group_type in [Integer, Prime, Power2].iter() {
+            for &bound_type in [B0, B2, B4, B6, Bmax].iter() {
+                for &model_type in [M3, M6, M9, M12].iter() {
+                    catalogue.push(MaskConfig {
+                        group_type,
+                        data_type,
+                        bound_type,
+                        model_type,
+                    });
+                }
+            }
+        }
+        catalogue
+    }
+
     /// Returns the number of bytes needed for an element of a mask object.
     ///
     /// # Panics
     /// Panics if the bytes per number can't be represented as usize.
-    pub(crate) fn bytes_per_number(&self) -> usize {
+    pub fn bytes_per_number(&self) -> usize {
         let max_number = self.order() - BigUint::from(1_u8);
         let bpn = (max_number.bits() + 7) / 8;
 
@@ -192,6 +241,21 @@ impl MaskConfig {
         bpn as usize
     }
 
+    /// Returns the number of bytes of a serialized element of a mask object.
+    ///
+    /// This is the same as the number of bytes needed for an element of a mask object, i.e. the
+    /// byte length of the largest element of the finite group. A serialized [`MaskVect`] of `n`
+    /// elements takes `n * element_len()` bytes plus a header for the masking configuration and the
+    /// number of elements, which allows to preallocate buffers for masked models.
+    ///
+    /// # Panics
+    /// Panics if the bytes per number can't be represented as usize.
+    ///
+    /// [`MaskVect`]: crate::mask::MaskVect
+    pub fn element_len(&self) -> usize {
+        self.bytes_per_number()
+    }
+
     /// Gets the additional shift value for masking/unmasking.
     pub fn add_shift(&self) -> Ratio<BigInt> {
         use BoundType::{Bmax, B0, B2, B4, B6};
@@ -230,6 +294,15 @@ impl MaskConfig {
         }
     }
 
+    /// Gets an upper bound of the absolute error of an aggregation of `nb_models` masked values.
+    ///
+    /// Each scaled weight resp. scalar is truncated to a multiple of `1 / exp_shift` during
+    /// masking, hence the aggregated values deviate by less than `nb_models / exp_shift` from the
+    /// exact sums. The bound applies before the division by the sum of the scalars.
+    pub fn max_absolute_error(&self, nb_models: usize) -> Ratio<BigInt> {
+        Ratio::new(BigInt::from(nb_models), self.exp_shift())
+    }
+
     /// Gets the finite group order value for masking/unmasking.
     pub fn order(&self) -> BigUint {
         use BoundType::{Bmax, B0, B2, B4, B6};
@@ -632,6 +705,42 @@ impl MaskConfig {
         // safe unwrap: string and radix are valid
         BigUint::from_str_radix(order_str, 10).unwrap()
     }
+
+    /// Gets the number of bits of the finite group order.
+    pub fn order_bit_length(&self) -> u64 {
+        self.order().bits()
+    }
+
+    /// Gets the number of decimal digits of the finite group order.
+    ///
+    /// This is exact, but cheaper than formatting the order as a decimal string.
+    pub fn order_decimal_digits(&self) -> usize {
+        let order = self.order();
+        // the order has either `floor((bits - 1) * log10(2)) + 1` or one more decimal digits,
+        // because `2^(bits - 1) <= order < 2^bits`
+        let bits = order.bits();
+        let digits = ((bits - 1) as f64 * std::f64::consts::LOG10_2).floor() as usize + 1;
+        if order >= BigUint::from(10_u8).pow(digits as u32) {
+            digits + 1
+        } else {
+            digits
+        }
+    }
+
+    /// Checks whether masks and masked models of this and the `other` configuration are
+    /// interchangeable.
+    ///
+    /// This is the case if the group orders and the shift values of both configurations agree,
+    /// even if the configurations are not equal. For example, the integer groups for `F32`, `I32`
+    /// and `I64` data types with a `B0` bound share the same order and shifts. Note that the data
+    /// type of the configurations may still differ, which determines the primitive values of an
+    /// unmasked model.
+    pub fn is_compatible_with(&self, other: &MaskConfig) -> bool {
+        self == other
+            || (self.order() == other.order()
+                && self.add_shift() == other.add_shift()
+                && self.exp_shift() == other.exp_shift())
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -653,3 +762,188 @@ impl From<MaskConfig> for MaskConfigPair {
         }
     }
 }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A contiguous section of a model vector with its own masking configuration.
+pub struct MaskSection {
+    /// The number of weights/parameters of the section.
+    pub len: usize,
+    /// The masking configuration of the section.
+    pub config: MaskConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The masking configurations of the consecutive sections of a model vector.
+///
+/// Masking the sections of a model wrt their own configurations avoids that all weights pay for
+/// the worst-case bound of the model. A model vector with a single masking configuration
+/// corresponds to a single section.
+pub struct ModelConfig {
+    sections: Vec<MaskSection>,
+}
+
+impl From<Vec<MaskSection>> for ModelConfig {
+    fn from(sections: Vec<MaskSection>) -> Self {
+        Self { sections }
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl ModelConfig {
+    /// Creates the configuration of a model vector of length `len` with a single section.
+    pub fn single(config: MaskConfig, len: usize) -> Self {
+        vec![MaskSection { len, config }].into()
+    }
+
+    /// Gets the sections of the model vector.
+    pub fn sections(&self) -> &[MaskSection] {
+        &self.sections
+    }
+
+    /// Gets the total length of the model vector.
+    pub fn len(&self) -> usize {
+        self.sections.iter().map(|section| section.len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogue() {
+        let catalogue = MaskConfig::catalogue(DataType::I32);
+        assert_eq!(catalogue.len(), 3 * 5 * 4);
+        assert!(catalogue
+            .iter()
+            .all(|config| config.data_type == DataType::I32));
+        let unique = catalogue.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), catalogue.len());
+    }
+
+    #[test]
+    fn test_max_absolute_error() {
+        let config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        };
+        assert_eq!(
+            config.max_absolute_error(3),
+            Ratio::new(BigInt::from(3), BigInt::from(10_000_000_000_u64)),
+        );
+        assert_eq!(config.max_absolute_error(0), Ratio::from_integer(BigInt::from(0)));
+    }
+
+    #[test]
+    fn test_order_sizes() {
+        let configs = [
+            MaskConfig {
+                group_type: GroupType::Integer,
+                data_type: DataType::F32,
+                bound_type: BoundType::B0,
+                model_type: ModelType::M3,
+            },
+            MaskConfig {
+                group_type: GroupType::Prime,
+                data_type: DataType::I32,
+                bound_type: BoundType::B6,
+                model_type: ModelType::M9,
+            },
+            MaskConfig {
+                group_type: GroupType::Power2,
+                data_type: DataType::F64,
+                bound_type: BoundType::B2,
+                model_type: ModelType::M6,
+            },
+            MaskConfig {
+                group_type: GroupType::Prime,
+                data_type: DataType::F64,
+                bound_type: BoundType::Bmax,
+                model_type: ModelType::M12,
+            },
+        ];
+        for config in configs.iter() {
+            let order = config.order();
+            assert_eq!(config.order_decimal_digits(), order.to_string().len());
+            assert_eq!(config.order_bit_length(), order.bits());
+        }
+        assert_eq!(configs[0].order_decimal_digits(), 14);
+
+        let catalogue = [DataType::F32, DataType::F64, DataType::I32, DataType::I64]
+            .iter()
+            .flat_map(|data_type| MaskConfig::catalogue(*data_type));
+        for config in catalogue {
+            assert_eq!(
+                config.order_decimal_digits(),
+                config.order().to_string().len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_from_bytes() {
+        let config = MaskConfig::try_from([0, 0, 0, 3]).unwrap();
+        assert_eq!(<[u8; 4]>::from(config), [0, 0, 0, 3]);
+        assert_eq!(
+            config,
+            MaskConfig {
+                group_type: GroupType::Integer,
+                data_type: DataType::F32,
+                bound_type: BoundType::B0,
+                model_type: ModelType::M3,
+            }
+        );
+        assert!(matches!(
+            MaskConfig::try_from([3, 0, 0, 3]),
+            Err(InvalidMaskConfigError::GroupType)
+        ));
+        assert!(matches!(
+            MaskConfig::try_from([0, 4, 0, 3]),
+            Err(InvalidMaskConfigError::DataType)
+        ));
+        assert!(matches!(
+            MaskConfig::try_from([0, 0, 5, 3]),
+            Err(InvalidMaskConfigError::BoundType)
+        ));
+        assert!(matches!(
+            MaskConfig::try_from([0, 0, 0, 1]),
+            Err(InvalidMaskConfigError::ModelType)
+        ));
+    }
+
+    #[test]
+    fn test_is_compatible_with() {
+        let config = MaskConfig {
+            group_type: GroupType::Integer,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        };
+        assert!(config.is_compatible_with(&config));
+
+        // same order and shifts, but a different data type
+        let other = MaskConfig {
+            data_type: DataType::I64,
+            ..config
+        };
+        assert_ne!(config, other);
+        assert!(config.is_compatible_with(&other));
+        assert!(other.is_compatible_with(&config));
+
+        // different orders
+        let other = MaskConfig {
+            model_type: ModelType::M6,
+            ..config
+        };
+        assert!(!config.is_compatible_with(&other));
+
+        // same exponential shift, but different orders and additional shifts
+        let other = MaskConfig {
+            bound_type: BoundType::B2,
+            ..config
+        };
+        assert!(!config.is_compatible_with(&other));
+    }
+}
@@ -4,27 +4,40 @@
 //!
 //! [mask module]: crate::mask
 
-use std::iter::{self, Iterator};
+use std::{
+    io::{self, Write},
+    iter::{self, Iterator},
+    mem,
+};
 
 use num::{
     bigint::{BigInt, BigUint, ToBigInt},
     clamp,
     rational::Ratio,
-    traits::clamp_max,
+    traits::{clamp_max, Zero},
 };
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
+use sodiumoxide::crypto::hash::sha256;
 use thiserror::Error;
 
 use crate::{
-    crypto::{prng::generate_integer, ByteObject},
+    crypto::{prng::generate_integer, ByteObject, Sha256},
     mask::{
-        config::MaskConfigPair,
-        model::Model,
-        object::{MaskObject, MaskUnit, MaskVect},
+        config::{DataType, MaskConfig, MaskConfigPair, ModelConfig},
+        model::{write_primitive, Model},
+        object::{
+            serialization::vect::MaskVectBuffer,
+            MaskObject,
+            MaskSections,
+            MaskUnit,
+            MaskVect,
+        },
+        residues::Residues,
         scalar::Scalar,
         seed::MaskSeed,
     },
+    message::traits::ToBytes,
 };
 
 #[derive(Debug, Error, Eq, PartialEq)]
@@ -67,29 +80,108 @@ pub enum AggregationError {
 
     #[error("the scalar to aggregate is incompatible with the current aggregated scalar")]
     ScalarMismatch,
+
+    #[error("the aggregations belong to different rounds")]
+    RoundMismatch,
+
+    #[error("the positions of the sparse object are invalid")]
+    InvalidPositions,
 }
 
 #[derive(Debug, Clone)]
 /// An aggregator for masks and masked models.
+///
+/// The aggregated vector is kept as flat residues of a fixed width instead of [`BigUint`]s, the
+/// conversion from and into [`MaskObject`]s happens at the boundary of the aggregator.
+///
+/// [`BigUint`]: num::bigint::BigUint
 pub struct Aggregation {
     nb_models: usize,
-    object: MaskObject,
+    vect: Residues,
+    unit: MaskUnit,
     object_size: usize,
+    round_id: Option<u64>,
+    /// The number of contributions per element, which is only tracked once a sparse object was
+    /// aggregated. Otherwise, every element has `nb_models` contributions.
+    counts: Option<Vec<usize>>,
 }
 
 impl From<MaskObject> for Aggregation {
     fn from(object: MaskObject) -> Self {
+        let object_size = object.vect.data.len();
+        let mut vect = Residues::with_capacity(object.vect.config, object_size);
+        vect.assign(&object.vect);
         Self {
             nb_models: 1,
-            object_size: object.vect.data.len(),
-            object,
+            vect,
+            unit: object.unit,
+            object_size,
+            round_id: None,
+            counts: None,
         }
     }
 }
 
 impl From<Aggregation> for MaskObject {
     fn from(aggr: Aggregation) -> Self {
-        aggr.object
+        MaskObject::new_unchecked(MaskVect::from(&aggr.vect), aggr.unit)
+    }
+}
+
+/// A borrowed view of the aggregated mask object of an [`Aggregation`], see
+/// [`Aggregation::as_mask_object()`].
+///
+/// The aggregation keeps the aggregated numbers back to back in a flat buffer instead of a
+/// [`MaskObject`], hence the view reads them in place.
+#[derive(Debug, Clone, Copy)]
+pub struct MaskObjectView<'a> {
+    vect: &'a Residues,
+    unit: &'a MaskUnit,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl MaskObjectView<'_> {
+    /// Gets the masking configurations of the viewed mask object.
+    pub fn config(&self) -> MaskConfigPair {
+        MaskConfigPair {
+            vect: self.vect.config(),
+            unit: self.unit.config,
+        }
+    }
+
+    /// Gets the number of elements of the vector of the viewed mask object.
+    pub fn len(&self) -> usize {
+        self.vect.len()
+    }
+
+    /// Gets the element of the vector of the viewed mask object at the given `index`, or `None`
+    /// if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<BigUint> {
+        self.vect.get(index)
+    }
+
+    /// Gets the unit of the viewed mask object.
+    pub fn unit(&self) -> &MaskUnit {
+        self.unit
+    }
+
+    /// Gets a copy of the viewed mask object.
+    pub fn to_mask_object(&self) -> MaskObject {
+        MaskObject::new_unchecked(MaskVect::from(self.vect), self.unit.clone())
+    }
+}
+
+impl PartialEq<MaskObject> for MaskObjectView<'_> {
+    fn eq(&self, other: &MaskObject) -> bool {
+        self.unit == &other.unit
+            && self.vect.config() == other.vect.config
+            && self.vect.len() == other.vect.data.len()
+            && other
+                .vect
+                .data
+                .iter()
+                .enumerate()
+                .all(|(index, number)| self.vect.get(index).as_ref() == Some(number))
     }
 }
 
@@ -99,24 +191,167 @@ impl Aggregation {
     pub fn new(config: MaskConfigPair, object_size: usize) -> Self {
         Self {
             nb_models: 0,
-            object: MaskObject::empty(config, object_size),
+            vect: Residues::with_capacity(config.vect, object_size),
+            unit: MaskUnit::default(config.unit),
             object_size,
+            round_id: None,
+            counts: None,
+        }
+    }
+
+    /// Restores an aggregator from a checkpoint of a previous aggregator.
+    ///
+    /// The checkpoint consists of the aggregated mask `object` as given by [`to_mask_object()`]
+    /// and the number of aggregated masks or masked models `nb_models`. The restored aggregator
+    /// continues as if the aggregation had never been interrupted. The contribution counts of
+    /// sparse aggregations are not part of the checkpoint.
+    ///
+    /// [`to_mask_object()`]: Aggregation::to_mask_object
+    pub fn from_checkpoint(
+        config: MaskConfigPair,
+        object_size: usize,
+        object: MaskObject,
+        nb_models: usize,
+    ) -> Self {
+        if nb_models == 0 {
+            // the aggregated mask object of an empty aggregator is empty
+            return Self::new(config, object_size);
+        }
+        Self {
+            nb_models,
+            ..Self::from(object)
         }
     }
 
+    /// Tags the aggregator with the round it belongs to.
+    ///
+    /// Only aggregators of the same round can be [merged].
+    ///
+    /// [merged]: Aggregation::merge
+    pub fn with_round_id(mut self, round_id: u64) -> Self {
+        self.round_id = Some(round_id);
+        self
+    }
+
+    /// Discards the aggregated masks or masked models.
+    ///
+    /// Afterwards, the aggregator behaves like a freshly created, untagged aggregator with the
+    /// same masking configurations and object size, but the memory of the aggregated vector is
+    /// kept for the next aggregation.
+    pub fn reset(&mut self) {
+        self.nb_models = 0;
+        self.vect.clear();
+        self.unit = MaskUnit::default(self.unit.config);
+        self.round_id = None;
+        self.counts = None;
+    }
+
+    /// Gets the round which the aggregator belongs to, if it is tagged.
+    pub fn round_id(&self) -> Option<u64> {
+        self.round_id
+    }
+
+    /// Predicts the peak memory in bytes which an aggregator for mask objects of the given length
+    /// and masking configurations occupies.
+    ///
+    /// The residues of the aggregation are materialized as a [`MaskObject`] of big integers next
+    /// to them whenever the aggregation is checkpointed via [`to_mask_object()`] or unmasked via
+    /// [`unmask()`], hence the peak covers both the residues and the materialized mask object.
+    ///
+    /// This is an approximation based on the number of elements and the bytes per number of the
+    /// masking configurations, which ignores the constant overhead of the aggregator.
+    ///
+    /// [`to_mask_object()`]: Aggregation::to_mask_object
+    /// [`unmask()`]: Aggregation::unmask
+    pub fn predicted_memory_usage(config: MaskConfigPair, object_size: usize) -> usize {
+        let residues =
+            object_size * config.vect.bytes_per_number() + config.unit.bytes_per_number();
+        let materialized = object_size * materialized_number_size(config.vect)
+            + materialized_number_size(config.unit);
+        residues + materialized
+    }
+
+    /// Gets the approximate memory in bytes which the aggregator occupies.
+    ///
+    /// See [`predicted_memory_usage()`] for details.
+    ///
+    /// [`predicted_memory_usage()`]: Aggregation::predicted_memory_usage
+    pub fn memory_usage(&self) -> usize {
+        Self::predicted_memory_usage(self.config(), self.object_size)
+    }
+
     /// Gets the length of the aggregated mask object.
     pub fn len(&self) -> usize {
         self.object_size
     }
 
+    /// Gets the number of aggregated masks or masked models.
+    pub fn nb_models(&self) -> usize {
+        self.nb_models
+    }
+
+    /// Gets the number of contributions to each element of the aggregated mask object.
+    ///
+    /// Every aggregated dense object contributes to all elements, whereas an object aggregated
+    /// via [`aggregate_sparse()`] only contributes to its positions.
+    ///
+    /// [`aggregate_sparse()`]: Aggregation::aggregate_sparse
+    pub fn contribution_counts(&self) -> Vec<usize> {
+        match self.counts {
+            Some(ref counts) => counts.clone(),
+            None => vec![self.nb_models; self.object_size],
+        }
+    }
+
     /// Gets the masking configurations of the aggregator.
     pub fn config(&self) -> MaskConfigPair {
         MaskConfigPair {
-            vect: self.object.vect.config,
-            unit: self.object.unit.config,
+            vect: self.vect.config(),
+            unit: self.unit.config,
         }
     }
 
+    /// Gets a view of the aggregated mask object.
+    ///
+    /// This gives read access to the current state of the aggregation without ending or copying
+    /// it.
+    pub fn as_mask_object(&self) -> MaskObjectView<'_> {
+        MaskObjectView {
+            vect: &self.vect,
+            unit: &self.unit,
+        }
+    }
+
+    /// Gets a copy of the aggregated mask object.
+    ///
+    /// Unlike the conversion into a [`MaskObject`], this leaves the aggregation usable.
+    pub fn to_mask_object(&self) -> MaskObject {
+        self.as_mask_object().to_mask_object()
+    }
+
+    /// Computes the `SHA256` digest of the serialized aggregated mask object.
+    ///
+    /// The digest equals the hash of the serialization of [`to_mask_object()`], but the residues
+    /// are hashed in place, hence neither the mask object nor its serialization is materialized.
+    ///
+    /// [`to_mask_object()`]: Aggregation::to_mask_object
+    pub fn digest(&self) -> Sha256 {
+        let config = self.vect.config();
+        let mut header = vec![0; MaskVect::serialized_length(config, 0)];
+        let mut writer = MaskVectBuffer::new_unchecked(&mut header);
+        config.to_bytes(&mut writer.config_mut());
+        writer.set_numbers(self.vect.len());
+
+        let mut unit = vec![0; self.unit.buffer_length()];
+        self.unit.to_bytes(&mut unit);
+
+        let mut hasher = sha256::State::new();
+        hasher.update(&header);
+        hasher.update(self.vect.as_bytes());
+        hasher.update(&unit);
+        Sha256::from(hasher.finalize())
+    }
+
     /// Validates if unmasking of the aggregated masked model with the given `mask` may be
     /// safely performed.
     ///
@@ -145,19 +380,19 @@ impl Aggregation {
             return Err(UnmaskingError::NoModel);
         }
 
-        if self.nb_models > self.object.vect.config.model_type.max_nb_models() {
+        if self.nb_models > self.vect.config().model_type.max_nb_models() {
             return Err(UnmaskingError::TooManyModels);
         }
 
-        if self.nb_models > self.object.unit.config.model_type.max_nb_models() {
+        if self.nb_models > self.unit.config.model_type.max_nb_models() {
             return Err(UnmaskingError::TooManyScalars);
         }
 
-        if self.object.vect.config != mask.vect.config || self.object_size != mask.vect.data.len() {
+        if self.vect.config() != mask.vect.config || self.object_size != mask.vect.data.len() {
             return Err(UnmaskingError::MaskManyMismatch);
         }
 
-        if self.object.unit.config != mask.unit.config {
+        if self.unit.config != mask.unit.config {
             return Err(UnmaskingError::MaskOneMismatch);
         }
 
@@ -174,6 +409,10 @@ impl Aggregation {
     /// unmasking may return garbage values otherwise. The unmasking is performed in opposite order
     /// as described for [`mask()`].
     ///
+    /// Each element is averaged wrt its own [contribution count], where every contribution to an
+    /// element is assumed to be scaled by the average of the aggregated scalars. Elements without
+    /// contributions are unmasked as zero.
+    ///
     /// # Panics
     /// This may only panic if [`validate_unmasking()`] fails.
     ///
@@ -187,47 +426,50 @@ impl Aggregation {
     ///
     /// [`validate_unmasking()`]: Aggregation::validate_unmasking
     /// [`mask()`]: Masker::mask
+    /// [contribution count]: Aggregation::contribution_counts
     pub fn unmask(self, mask_obj: MaskObject) -> Model {
-        let MaskObject { vect, unit } = self.object;
-        let (masked_n, config_n) = (vect.data, vect.config);
-        let (masked_1, config_1) = (unit.data, unit.config);
-        let mask_n = mask_obj.vect.data;
-        let mask_1 = mask_obj.unit.data;
-
-        // unmask scalar sum
-        let scaled_add_shift_1 = config_1.add_shift() * BigInt::from(self.nb_models);
-        let exp_shift_1 = config_1.exp_shift();
-        let order_1 = config_1.order();
-        let n = (masked_1 + &order_1 - mask_1) % &order_1;
-        let ratio = Ratio::<BigInt>::from(n.to_bigint().unwrap());
-        let scalar_sum = ratio / &exp_shift_1 - &scaled_add_shift_1;
-
-        // unmask global model
-        let scaled_add_shift_n = config_n.add_shift() * BigInt::from(self.nb_models);
-        let exp_shift_n = config_n.exp_shift();
-        let order_n = config_n.order();
-        masked_n
-            .into_iter()
-            .zip(mask_n)
-            .map(|(masked, mask)| {
-                // PANIC_SAFE: The substraction panics if it
-                // underflows, which can only happen if:
-                //
-                //     mask > order_n
-                //
-                // If the mask is valid, we are guaranteed that this
-                // cannot happen. Thus this method may panic only if
-                // given an invalid mask.
-                let n = (masked + &order_n - mask) % &order_n;
-
-                // UNWRAP_SAFE: to_bigint never fails for BigUint
-                let ratio = Ratio::<BigInt>::from(n.to_bigint().unwrap());
-                let unmasked = ratio / &exp_shift_n - &scaled_add_shift_n;
+        let vect = MaskVect::from(&self.vect);
+        let scalar_sum = unmask_unit(self.unit, mask_obj.unit, self.nb_models);
+        unmask_vect(
+            vect,
+            mask_obj.vect,
+            self.nb_models,
+            self.counts,
+            &scalar_sum,
+        )
+        .collect()
+    }
 
-                // scaling correction
-                unmasked / &scalar_sum
-            })
-            .collect()
+    /// Unmasks the aggregated masked model with the given `mask_obj` and writes the weights to
+    /// the `writer`.
+    ///
+    /// The weights are unmasked and written one by one, each as a primitive value of the given
+    /// `data_type` in native byte order, hence the unmasked model never resides in memory as a
+    /// whole. The `writer` may be positioned at the end of an existing file to append the model.
+    /// The same preconditions as for [`unmask()`] apply.
+    ///
+    /// # Errors
+    /// Fails with [`io::ErrorKind::InvalidData`] if a weight can't be converted into the
+    /// primitive data type, in which case the preceding weights have already been written, or if
+    /// the `writer` fails.
+    ///
+    /// [`unmask()`]: Aggregation::unmask
+    pub fn unmask_to_writer<W: Write>(
+        self,
+        mask_obj: MaskObject,
+        writer: &mut W,
+        data_type: DataType,
+    ) -> io::Result<()> {
+        let vect = MaskVect::from(&self.vect);
+        let scalar_sum = unmask_unit(self.unit, mask_obj.unit, self.nb_models);
+        let mut weights = unmask_vect(
+            vect,
+            mask_obj.vect,
+            self.nb_models,
+            self.counts,
+            &scalar_sum,
+        );
+        weights.try_for_each(|weight| write_primitive(writer, weight, data_type))
     }
 
     /// Validates if aggregation of the aggregated mask object with the given `object` may be safely
@@ -251,11 +493,11 @@ impl Aggregation {
     ///
     /// [`aggregate()`]: Aggregation::aggregate
     pub fn validate_aggregation(&self, object: &MaskObject) -> Result<(), AggregationError> {
-        if self.object.vect.config != object.vect.config {
+        if self.vect.config() != object.vect.config {
             return Err(AggregationError::ModelMismatch);
         }
 
-        if self.object.unit.config != object.unit.config {
+        if self.unit.config != object.unit.config {
             return Err(AggregationError::ScalarMismatch);
         }
 
@@ -263,11 +505,11 @@ impl Aggregation {
             return Err(AggregationError::ModelMismatch);
         }
 
-        if self.nb_models >= self.object.vect.config.model_type.max_nb_models() {
+        if self.nb_models >= self.vect.config().model_type.max_nb_models() {
             return Err(AggregationError::TooManyModels);
         }
 
-        if self.nb_models >= self.object.unit.config.model_type.max_nb_models() {
+        if self.nb_models >= self.unit.config.model_type.max_nb_models() {
             return Err(AggregationError::TooManyScalars);
         }
 
@@ -291,49 +533,522 @@ impl Aggregation {
     /// [`validate_aggregation()`]: Aggregation::validate_aggregation
     pub fn aggregate(&mut self, object: MaskObject) {
         if self.nb_models == 0 {
-            self.object = object;
+            // the reserved memory is reused, e.g. after a reset
+            self.object_size = object.vect.data.len();
+            self.vect.assign(&object.vect);
+            self.unit = object.unit;
             self.nb_models = 1;
             return;
         }
 
-        let order_n = self.object.vect.config.order();
-        for (i, j) in self
-            .object
-            .vect
-            .data
-            .iter_mut()
-            .zip(object.vect.data.into_iter())
+        self.vect.add_assign(&object.vect);
+        aggregate_unit(&mut self.unit, object.unit);
+        self.nb_models += 1;
+        if let Some(ref mut counts) = self.counts {
+            counts.iter_mut().for_each(|count| *count += 1);
+        }
+    }
+
+    /// Validates if aggregation of the aggregated mask object with the given sparse `object` may
+    /// be safely performed.
+    ///
+    /// This is the sparse analogue of [`validate_aggregation()`], where the `object` only holds
+    /// the elements at the given `positions` of the aggregated mask object.
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`validate_aggregation()`], where the length of the `object`
+    /// must coincide with the number of `positions` instead. Also fails if the `positions` are
+    /// not strictly increasing or exceed the length of the aggregated mask object.
+    ///
+    /// [`validate_aggregation()`]: Aggregation::validate_aggregation
+    pub fn validate_sparse_aggregation(
+        &self,
+        object: &MaskObject,
+        positions: &[usize],
+    ) -> Result<(), AggregationError> {
+        if self.vect.config() != object.vect.config {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        if self.unit.config != object.unit.config {
+            return Err(AggregationError::ScalarMismatch);
+        }
+
+        if positions.len() != object.vect.data.len() {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        let increasing = positions.windows(2).all(|pair| pair[0] < pair[1]);
+        let in_bounds = positions
+            .iter()
+            .all(|position| *position < self.object_size);
+        if !increasing || !in_bounds {
+            return Err(AggregationError::InvalidPositions);
+        }
+
+        if self.nb_models >= self.vect.config().model_type.max_nb_models() {
+            return Err(AggregationError::TooManyModels);
+        }
+
+        if self.nb_models >= self.unit.config.model_type.max_nb_models() {
+            return Err(AggregationError::TooManyScalars);
+        }
+
+        if !object.is_valid() {
+            return Err(AggregationError::InvalidObject);
+        }
+
+        Ok(())
+    }
+
+    /// Aggregates the aggregated mask object with the given sparse `object`, whose elements
+    /// belong to the given `positions` of the aggregated mask object.
+    ///
+    /// Masks and masked models must be aggregated at the same positions, such that the elements
+    /// can be unmasked. Afterwards, the contributions per element are tracked, see
+    /// [`contribution_counts()`].
+    ///
+    /// It should be checked that [`validate_sparse_aggregation()`] succeeds before calling this,
+    /// since aggregation may return garbage values otherwise.
+    ///
+    /// [`contribution_counts()`]: Aggregation::contribution_counts
+    /// [`validate_sparse_aggregation()`]: Aggregation::validate_sparse_aggregation
+    pub fn aggregate_sparse(&mut self, object: MaskObject, positions: &[usize]) {
+        let (nb_models, object_size) = (self.nb_models, self.object_size);
+        let counts = self
+            .counts
+            .get_or_insert_with(|| vec![nb_models; object_size]);
+        if nb_models == 0 {
+            self.vect.assign_zeros(self.object_size);
+        }
+        for (position, number) in positions.iter().zip(object.vect.data.iter()) {
+            self.vect.add_assign_at(*position, number);
+            counts[*position] += 1;
+        }
+        if nb_models == 0 {
+            self.unit = object.unit;
+        } else {
+            aggregate_unit(&mut self.unit, object.unit);
+        }
+        self.nb_models += 1;
+    }
+
+    /// Derives the mask of the given `seed` and aggregates it with the aggregated mask object.
+    ///
+    /// This is equivalent to aggregating the mask of [`MaskSeed::derive_mask()`] or, if a
+    /// `round_seed` is given, of [`MaskSeed::derive_mask_for_round()`]. However, the numbers of
+    /// the mask are folded into the aggregation one by one, hence the mask is never materialized.
+    ///
+    /// # Errors
+    /// Fails if the aggregator already aggregated as many masks as the masking configurations
+    /// allow.
+    pub fn aggregate_seed(
+        &mut self,
+        seed: &MaskSeed,
+        round_seed: Option<&[u8]>,
+    ) -> Result<(), AggregationError> {
+        if self.nb_models >= self.vect.config().model_type.max_nb_models() {
+            return Err(AggregationError::TooManyModels);
+        }
+        if self.nb_models >= self.unit.config.model_type.max_nb_models() {
+            return Err(AggregationError::TooManyScalars);
+        }
+
+        let seed = match round_seed {
+            Some(round_seed) => seed.bind_to_round(round_seed),
+            None => seed.clone(),
+        };
+        let config = self.config();
+        if self.nb_models == 0 {
+            self.vect.assign_zeros(self.object_size);
+        }
+        let vect = &mut self.vect;
+        let mut index = 0;
+        let unit = seed.derive_mask_into(self.object_size, config, |number| {
+            vect.add_assign_at(index, &number);
+            index += 1;
+        });
+        if self.nb_models == 0 {
+            self.unit = unit;
+        } else {
+            aggregate_unit(&mut self.unit, unit);
+        }
+        self.nb_models += 1;
+        if let Some(ref mut counts) = self.counts {
+            counts.iter_mut().for_each(|count| *count += 1);
+        }
+        Ok(())
+    }
+
+    /// Merges the aggregated mask object of the `other` aggregator into this one, e.g. to combine
+    /// the shards of a sharded aggregation.
+    ///
+    /// # Errors
+    /// Fails in one of the following cases:
+    /// - The aggregators are tagged with different rounds or only one of them is tagged.
+    /// - The masking configurations of the aggregators don't coincide.
+    /// - The lengths of the aggregated mask objects don't coincide. Empty aggregators can be
+    ///   merged regardless of their length.
+    /// - The combined number of aggregated masks or masked models would exceed the number that
+    ///   the chosen masking configuration allows.
+    pub fn merge(&mut self, other: Aggregation) -> Result<(), AggregationError> {
+        if self.round_id != other.round_id {
+            return Err(AggregationError::RoundMismatch);
+        }
+
+        if self.vect.config() != other.vect.config() {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        if self.unit.config != other.unit.config {
+            return Err(AggregationError::ScalarMismatch);
+        }
+
+        if other.nb_models == 0 {
+            return Ok(());
+        }
+
+        if self.nb_models == 0 {
+            *self = other;
+            return Ok(());
+        }
+
+        if self.object_size != other.object_size {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        let nb_models = self.nb_models + other.nb_models;
+        if nb_models > self.vect.config().model_type.max_nb_models() {
+            return Err(AggregationError::TooManyModels);
+        }
+
+        if nb_models > self.unit.config.model_type.max_nb_models() {
+            return Err(AggregationError::TooManyScalars);
+        }
+
+        if self.counts.is_some() || other.counts.is_some() {
+            let counts = self
+                .contribution_counts()
+                .into_iter()
+                .zip(other.contribution_counts())
+                .map(|(count, other_count)| count + other_count)
+                .collect();
+            self.counts = Some(counts);
+        }
+        self.vect.add_assign(&MaskVect::from(&other.vect));
+        aggregate_unit(&mut self.unit, other.unit);
+        self.nb_models = nb_models;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An aggregator for masks and masked models with multiple sections.
+///
+/// This aggregates section-wise wrt the masking configurations of the sections, otherwise it
+/// behaves like an [`Aggregation`].
+pub struct SectionAggregation {
+    nb_models: usize,
+    object: MaskSections,
+    config: ModelConfig,
+}
+
+impl From<MaskSections> for SectionAggregation {
+    fn from(object: MaskSections) -> Self {
+        Self {
+            nb_models: 1,
+            config: object.config(),
+            object,
+        }
+    }
+}
+
+impl From<SectionAggregation> for MaskSections {
+    fn from(aggr: SectionAggregation) -> Self {
+        aggr.object
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl SectionAggregation {
+    /// Creates a new, empty aggregator for masks or masked models with the sections of the model
+    /// `config`uration and the `unit` masking configuration.
+    pub fn new(config: ModelConfig, unit: MaskConfig) -> Self {
+        Self {
+            nb_models: 0,
+            object: MaskSections::empty(&config, unit),
+            config,
+        }
+    }
+
+    /// Gets the total length of the aggregated sectioned mask object.
+    pub fn len(&self) -> usize {
+        self.config.len()
+    }
+
+    /// Gets the number of aggregated masks or masked models.
+    pub fn nb_models(&self) -> usize {
+        self.nb_models
+    }
+
+    /// Gets the configuration of the sections of the aggregator.
+    pub fn config(&self) -> &ModelConfig {
+        &self.config
+    }
+
+    /// Gets a reference to the aggregated sectioned mask object.
+    pub fn as_mask_sections(&self) -> &MaskSections {
+        &self.object
+    }
+
+    /// Checks whether the model part of the `object` has the sections of the aggregator.
+    fn has_sections_of(&self, object: &MaskSections) -> bool {
+        self.config.sections().len() == object.vects.len()
+            && self
+                .config
+                .sections()
+                .iter()
+                .zip(object.vects.iter())
+                .all(|(section, vect)| {
+                    section.config == vect.config && section.len == vect.data.len()
+                })
+    }
+
+    /// Checks whether the aggregated number of models exceeds any of the masking configurations.
+    fn exceeds_max_nb_models(&self, nb_models: usize) -> Option<bool> {
+        let max_nb_models = self
+            .config
+            .sections()
+            .iter()
+            .map(|section| section.config.model_type.max_nb_models())
+            .min()?;
+        Some(nb_models > max_nb_models)
+    }
+
+    /// Validates if unmasking of the aggregated masked model with the given `mask` may be
+    /// safely performed.
+    ///
+    /// This is the section-wise analogue of [`Aggregation::validate_unmasking()`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Aggregation::validate_unmasking()`], where the sections of
+    /// the aggregator and of the `mask` must coincide in their masking configurations and lengths.
+    pub fn validate_unmasking(&self, mask: &MaskSections) -> Result<(), UnmaskingError> {
+        // We cannot perform unmasking without at least one real model
+        if self.nb_models == 0 {
+            return Err(UnmaskingError::NoModel);
+        }
+
+        if self.exceeds_max_nb_models(self.nb_models).unwrap_or(false) {
+            return Err(UnmaskingError::TooManyModels);
+        }
+
+        if self.nb_models > self.object.unit.config.model_type.max_nb_models() {
+            return Err(UnmaskingError::TooManyScalars);
+        }
+
+        if !self.has_sections_of(mask) {
+            return Err(UnmaskingError::MaskManyMismatch);
+        }
+
+        if self.object.unit.config != mask.unit.config {
+            return Err(UnmaskingError::MaskOneMismatch);
+        }
+
+        if !mask.is_valid() {
+            return Err(UnmaskingError::InvalidMask);
+        }
+
+        Ok(())
+    }
+
+    /// Unmasks the aggregated masked model with the given `mask` section-wise.
+    ///
+    /// The unmasked sections are joined into a single model. It should be checked that
+    /// [`validate_unmasking()`] succeeds before calling this, since unmasking may return garbage
+    /// values otherwise.
+    ///
+    /// # Panics
+    /// This may only panic if [`validate_unmasking()`] fails.
+    ///
+    /// [`validate_unmasking()`]: SectionAggregation::validate_unmasking
+    pub fn unmask(self, mask: MaskSections) -> Model {
+        let MaskSections { vects, unit } = self.object;
+        let nb_models = self.nb_models;
+        let scalar_sum = unmask_unit(unit, mask.unit, nb_models);
+        vects
+            .into_iter()
+            .zip(mask.vects)
+            .flat_map(|(masked, mask)| unmask_vect(masked, mask, nb_models, None, &scalar_sum))
+            .collect()
+    }
+
+    /// Validates if aggregation of the aggregated mask object with the given `object` may be safely
+    /// performed.
+    ///
+    /// This is the section-wise analogue of [`Aggregation::validate_aggregation()`].
+    ///
+    /// # Errors
+    /// Fails in the same cases as [`Aggregation::validate_aggregation()`], where the sections of
+    /// the aggregator and of the `object` must coincide in their masking configurations and
+    /// lengths.
+    pub fn validate_aggregation(&self, object: &MaskSections) -> Result<(), AggregationError> {
+        if !self.has_sections_of(object) {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        if self.object.unit.config != object.unit.config {
+            return Err(AggregationError::ScalarMismatch);
+        }
+
+        if self
+            .exceeds_max_nb_models(self.nb_models + 1)
+            .unwrap_or(false)
         {
-            *i = (&*i + j) % &order_n
+            return Err(AggregationError::TooManyModels);
+        }
+
+        if self.nb_models >= self.object.unit.config.model_type.max_nb_models() {
+            return Err(AggregationError::TooManyScalars);
+        }
+
+        if !object.is_valid() {
+            return Err(AggregationError::InvalidObject);
         }
 
-        let order_1 = self.object.unit.config.order();
-        let a = &mut self.object.unit.data;
-        let b = object.unit.data;
-        *a = (&*a + b) % &order_1;
+        Ok(())
+    }
 
+    /// Aggregates the aggregated sectioned mask object with the given `object` section-wise.
+    ///
+    /// It should be checked that [`validate_aggregation()`] succeeds before calling this, since
+    /// aggregation may return garbage values otherwise.
+    ///
+    /// [`validate_aggregation()`]: SectionAggregation::validate_aggregation
+    pub fn aggregate(&mut self, object: MaskSections) {
+        if self.nb_models == 0 {
+            self.object = object;
+            self.nb_models = 1;
+            return;
+        }
+
+        for (aggregated, vect) in self.object.vects.iter_mut().zip(object.vects) {
+            aggregate_vect(aggregated, vect);
+        }
+        aggregate_unit(&mut self.object.unit, object.unit);
         self.nb_models += 1;
     }
 }
 
+/// Aggregates the `aggregated` mask vector with the given `vect` wrt the group order.
+fn aggregate_vect(aggregated: &mut MaskVect, vect: MaskVect) {
+    let order = aggregated.config.order();
+    for (i, j) in aggregated.data.iter_mut().zip(vect.data.into_iter()) {
+        *i = (&*i + j) % &order
+    }
+}
+
+/// Aggregates the `aggregated` mask unit with the given `unit` wrt the group order.
+fn aggregate_unit(aggregated: &mut MaskUnit, unit: MaskUnit) {
+    let order = aggregated.config.order();
+    let a = &mut aggregated.data;
+    *a = (&*a + unit.data) % &order;
+}
+
+/// Unmasks the sum of `nb_models` masked scalars with the given `mask`.
+pub(crate) fn unmask_unit(masked: MaskUnit, mask: MaskUnit, nb_models: usize) -> Ratio<BigInt> {
+    let config = masked.config;
+    let scaled_add_shift = config.add_shift() * BigInt::from(nb_models);
+    let exp_shift = config.exp_shift();
+    let order = config.order();
+    let n = (masked.data + &order - mask.data) % &order;
+    let ratio = Ratio::<BigInt>::from(n.to_bigint().unwrap());
+    ratio / &exp_shift - &scaled_add_shift
+}
+
+/// Unmasks the aggregation of `nb_models` masked vectors with the given `mask` and corrects the
+/// scaling by the unmasked `scalar_sum`.
+///
+/// If the contribution `counts` per element are given, each element is corrected wrt its own
+/// count instead of `nb_models`.
+fn unmask_vect(
+    masked: MaskVect,
+    mask: MaskVect,
+    nb_models: usize,
+    counts: Option<Vec<usize>>,
+    scalar_sum: &Ratio<BigInt>,
+) -> impl Iterator<Item = Ratio<BigInt>> + '_ {
+    let config = masked.config;
+    let add_shift = config.add_shift();
+    let scaled_add_shift = &add_shift * BigInt::from(nb_models);
+    let exp_shift = config.exp_shift();
+    let order = config.order();
+    masked
+        .data
+        .into_iter()
+        .zip(mask.data)
+        .enumerate()
+        .map(move |(index, (masked, mask))| {
+            // PANIC_SAFE: The substraction panics if it
+            // underflows, which can only happen if:
+            //
+            //     mask > order
+            //
+            // If the mask is valid, we are guaranteed that this
+            // cannot happen. Thus this method may panic only if
+            // given an invalid mask.
+            let n = (masked + &order - mask) % &order;
+
+            // UNWRAP_SAFE: to_bigint never fails for BigUint
+            let ratio = Ratio::<BigInt>::from(n.to_bigint().unwrap());
+
+            let count = counts.as_ref().map_or(nb_models, |counts| counts[index]);
+            if count == nb_models {
+                let unmasked = ratio / &exp_shift - &scaled_add_shift;
+
+                // scaling correction
+                unmasked / scalar_sum
+            } else if count == 0 {
+                Ratio::zero()
+            } else {
+                let unmasked = ratio / &exp_shift - &add_shift * BigInt::from(count);
+
+                // scaling correction wrt the average scalar of the contributions
+                unmasked * BigInt::from(nb_models) / (scalar_sum * BigInt::from(count))
+            }
+        })
+}
+
 /// A masker for models.
 pub struct Masker {
     config: MaskConfigPair,
     seed: MaskSeed,
+    /// The seed the random elements are actually derived from.
+    prng_seed: MaskSeed,
 }
 
 impl Masker {
     /// Creates a new masker with the given masking `config`uration with a randomly generated seed.
     pub fn new(config: MaskConfigPair) -> Self {
+        Self::with_seed(config, MaskSeed::generate())
+    }
+
+    /// Creates a new masker with the given masking `config`uration and `seed`.
+    pub fn with_seed(config: MaskConfigPair, seed: MaskSeed) -> Self {
         Self {
             config,
-            seed: MaskSeed::generate(),
+            prng_seed: seed.clone(),
+            seed,
         }
     }
 
-    /// Creates a new masker with the given masking `config`uration and `seed`.
-    pub fn with_seed(config: MaskConfigPair, seed: MaskSeed) -> Self {
-        Self { config, seed }
+    /// Binds the mask to the round with the given `round_seed`.
+    ///
+    /// The mask is then derived as in [`MaskSeed::derive_mask_for_round()`] instead of
+    /// [`MaskSeed::derive_mask()`].
+    pub fn for_round(mut self, round_seed: &[u8]) -> Self {
+        self.prng_seed = self.seed.bind_to_round(round_seed);
+        self
     }
 }
 
@@ -356,67 +1071,120 @@ impl Masker {
     ///
     /// [`unmask()`]: Aggregation::unmask
     pub fn mask(self, scalar: Scalar, model: &Model) -> (MaskSeed, MaskObject) {
-        let (random_int, mut random_ints) = self.random_ints();
-        let Self { config, seed } = self;
-        let MaskConfigPair {
-            vect: config_n,
-            unit: config_1,
-        } = config;
-
-        // clamp the scalar
-        let add_shift_1 = config_1.add_shift();
-        let scalar_ratio = scalar.into();
-        let scalar_clamped = clamp_max(&scalar_ratio, &add_shift_1);
-
-        let exp_shift_n = config_n.exp_shift();
-        let add_shift_n = config_n.add_shift();
-        let order_n = config_n.order();
-        let higher_bound = &add_shift_n;
-        let lower_bound = -&add_shift_n;
-
-        // mask the (scaled) weights
-        let masked_weights = model
-            .iter()
-            .zip(&mut random_ints)
-            .map(|(weight, rand_int)| {
-                let scaled = scalar_clamped * weight;
-                let scaled_clamped = clamp(&scaled, &lower_bound, higher_bound);
-                // PANIC_SAFE: shifted weight is guaranteed to be non-negative
-                let shifted = ((scaled_clamped + &add_shift_n) * &exp_shift_n)
-                    .to_integer()
-                    .to_biguint()
-                    .unwrap();
-                (shifted + rand_int) % &order_n
-            })
-            .collect();
-        let masked_model = MaskVect::new_unchecked(config_n, masked_weights);
-
-        // mask the scalar
-        // PANIC_SAFE: shifted scalar is guaranteed to be non-negative
-        let shifted = ((scalar_clamped + &add_shift_1) * config_1.exp_shift())
-            .to_integer()
-            .to_biguint()
-            .unwrap();
-        let masked = (shifted + random_int) % config_1.order();
-        let masked_scalar = MaskUnit::new_unchecked(config_1, masked);
-
-        (seed, MaskObject::new_unchecked(masked_model, masked_scalar))
+        let config = ModelConfig::single(self.config.vect, model.len());
+        let (seed, mut masked) = self.mask_sections(scalar, model, &config);
+        // safe unwrap: the model config has exactly one section
+        let masked_model = masked.vects.pop().unwrap();
+        (seed, MaskObject::new_unchecked(masked_model, masked.unit))
     }
 
-    /// Randomly generates integers wrt the masking configurations.
+    /// Masks the given `model` section-wise wrt the masking configurations of the sections of the
+    /// model `config`uration. Enforces bounds on the scalar and weights.
+    ///
+    /// The weights of each section are masked as described for [`mask()`] wrt the masking
+    /// configuration of their section, which replaces the vector masking configuration of the
+    /// masker. The scalar is masked wrt the unit masking configuration of the masker. Masking a
+    /// model with a single section is equivalent to [`mask()`].
+    ///
+    /// # Panics
+    /// Panics if the length of the `model` and of the model `config`uration don't coincide.
     ///
-    /// The first is generated wrt the scalar configuration, while the rest are
-    /// wrt the vector configuration and returned as an iterator.
-    fn random_ints(&self) -> (BigUint, impl Iterator<Item = BigUint>) {
-        let order_n = self.config.vect.order();
-        let order_1 = self.config.unit.order();
-        let mut prng = ChaCha20Rng::from_seed(self.seed.as_array());
-        let int = generate_integer(&mut prng, &order_1);
-        let ints = iter::from_fn(move || Some(generate_integer(&mut prng, &order_n)));
-        (int, ints)
+    /// [`mask()`]: Masker::mask
+    pub fn mask_sections(
+        self,
+        scalar: Scalar,
+        model: &Model,
+        config: &ModelConfig,
+    ) -> (MaskSeed, MaskSections) {
+        assert_eq!(
+            model.len(),
+            config.len(),
+            "the model doesn't match the model config"
+        );
+        let mut prng = ChaCha20Rng::from_seed(self.prng_seed.as_array());
+        let masked = encode_sections(self.config, scalar, model, config, |order| {
+            generate_integer(&mut prng, order)
+        });
+        (self.seed, masked)
     }
 }
 
+/// Encodes the `model` section-wise as described for [`Masker::mask()`] and adds a `random`
+/// element of the finite group to each encoded weight and to the encoded scalar.
+///
+/// The random elements are drawn in the order of the scalar and the weights. The `random` source
+/// gets the order of the finite group to draw an element from.
+pub(crate) fn encode_sections<R>(
+    pair: MaskConfigPair,
+    scalar: Scalar,
+    model: &Model,
+    config: &ModelConfig,
+    mut random: R,
+) -> MaskSections
+where
+    R: FnMut(&BigUint) -> BigUint,
+{
+    let config_1 = pair.unit;
+    let random_int = random(&config_1.order());
+
+    // clamp the scalar
+    let add_shift_1 = config_1.add_shift();
+    let scalar_ratio = scalar.into();
+    let scalar_clamped = clamp_max(&scalar_ratio, &add_shift_1);
+
+    // mask the (scaled) weights section by section
+    let mut weights = model.iter();
+    let masked_model = config
+        .sections()
+        .iter()
+        .map(|section| {
+            let config_n = section.config;
+            let exp_shift_n = config_n.exp_shift();
+            let add_shift_n = config_n.add_shift();
+            let order_n = config_n.order();
+            let higher_bound = &add_shift_n;
+            let lower_bound = -&add_shift_n;
+            let random_ints = iter::repeat_with(|| random(&order_n)).take(section.len);
+
+            let masked_weights = weights
+                .by_ref()
+                .take(section.len)
+                .zip(random_ints)
+                .map(|(weight, rand_int)| {
+                    let scaled = scalar_clamped * weight;
+                    let scaled_clamped = clamp(&scaled, &lower_bound, higher_bound);
+                    // PANIC_SAFE: shifted weight is guaranteed to be non-negative
+                    let shifted = ((scaled_clamped + &add_shift_n) * &exp_shift_n)
+                        .to_integer()
+                        .to_biguint()
+                        .unwrap();
+                    (shifted + rand_int) % &order_n
+                })
+                .collect();
+            MaskVect::new_unchecked(config_n, masked_weights)
+        })
+        .collect();
+
+    // mask the scalar
+    // PANIC_SAFE: shifted scalar is guaranteed to be non-negative
+    let shifted = ((scalar_clamped + &add_shift_1) * config_1.exp_shift())
+        .to_integer()
+        .to_biguint()
+        .unwrap();
+    let masked = (shifted + random_int) % config_1.order();
+    let masked_scalar = MaskUnit::new_unchecked(config_1, masked);
+
+    MaskSections::new_unchecked(masked_model, masked_scalar)
+}
+
+/// Gets the approximate memory in bytes which a number of the masking configuration occupies as
+/// a [`BigUint`], i.e. the integer itself and its heap allocated digits.
+fn materialized_number_size(config: MaskConfig) -> usize {
+    let digit_size = mem::size_of::<usize>();
+    let digits = (config.bytes_per_number() + digit_size - 1) / digit_size;
+    mem::size_of::<BigUint>() + digits * digit_size
+}
+
 #[cfg(test)]
 mod tests {
     use std::iter;
@@ -429,16 +1197,20 @@ mod tests {
     use rand_chacha::ChaCha20Rng;
 
     use super::*;
-    use crate::mask::{
-        config::{
-            BoundType::{Bmax, B0, B2, B4, B6},
-            DataType::{F32, F64, I32, I64},
-            GroupType::{Integer, Power2, Prime},
-            MaskConfig,
-            ModelType::M3,
+    use crate::{
+        common::RoundSeed,
+        mask::{
+            config::{
+                BoundType::{Bmax, B0, B2, B4, B6},
+                DataType::{F32, F64, I32, I64},
+                GroupType::{Integer, Power2, Prime},
+                MaskConfig,
+                MaskSection,
+                ModelType::M3,
+            },
+            model::{Endianness, FromPrimitives, IntoPrimitives},
+            scalar::FromPrimitive,
         },
-        model::FromPrimitives,
-        scalar::FromPrimitive,
     };
 
     /// Generate tests for masking and unmasking of a single model:
@@ -517,6 +1289,767 @@ mod tests {
         };
     }
 
+    /// Masks the integer `models` with the same `scalar`, aggregates and unmasks them.
+    fn masked_average(config: MaskConfig, scalar: &Scalar, models: &[Vec<i64>]) -> Model {
+        let vect_len = models[0].len();
+        let mut aggregated_masked_model = Aggregation::new(config.into(), vect_len);
+        let mut aggregated_mask = Aggregation::new(config.into(), vect_len);
+        for weights in models {
+            let model = Model::from_primitives(weights.iter().copied()).unwrap();
+            let (mask_seed, masked_model) = Masker::new(config.into()).mask(scalar.clone(), &model);
+            aggregated_masked_model.aggregate(masked_model);
+            aggregated_mask.aggregate(mask_seed.derive_mask(vect_len, config.into()));
+        }
+        let mask = aggregated_mask.into();
+        assert!(aggregated_masked_model.validate_unmasking(&mask).is_ok());
+        aggregated_masked_model.unmask(mask)
+    }
+
+    #[test]
+    fn test_masking_rational_scalar() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: I64,
+            bound_type: B2,
+            model_type: M3,
+        };
+        let models = vec![vec![1, -100, 7, 0], vec![2, 50, -7, 99], vec![4, 100, 3, 98]];
+        let averages = (0..4)
+            .map(|i| {
+                let sum = models.iter().map(|model| model[i]).sum::<i64>();
+                Ratio::new(BigInt::from(sum), BigInt::from(3))
+            })
+            .collect::<Vec<_>>();
+
+        // the exact scalar is only quantized by the masking configuration
+        let exact = masked_average(config, &Scalar::new(1_u8, 3_u8), &models);
+        let exp_shift = config.exp_shift();
+        let tolerance = Ratio::new(BigInt::from(3 + 100), &exp_shift - 1);
+        assert!(exact
+            .iter()
+            .zip(averages.iter())
+            .all(|(weight, average)| (weight - average).abs() <= tolerance));
+
+        // the lossy float scalar agrees with the exact scalar up to the rounding of the scaled
+        // weights of each model
+        let lossy = masked_average(
+            config,
+            &Scalar::from_primitive(1_f64 / 3_f64).unwrap(),
+            &models,
+        );
+        let tolerance = Ratio::new(BigInt::from(3), exp_shift - 1);
+        assert!(lossy
+            .iter()
+            .zip(exact.iter())
+            .all(|(lossy, exact)| (lossy - exact).abs() <= tolerance));
+    }
+
+    #[test]
+    fn test_masking_for_round() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let mut prng = ChaCha20Rng::from_seed(MaskSeed::generate().as_array());
+        let random_weights = Uniform::new_inclusive(-1_f32, 1_f32)
+            .sample_iter(&mut prng)
+            .take(10);
+        let model = Model::from_primitives(random_weights).unwrap();
+        let round_seed = RoundSeed::generate();
+
+        // mask the model with a round-bound mask
+        let (seed, masked_model) = Masker::new(config.into())
+            .for_round(round_seed.as_slice())
+            .mask(Scalar::unit(), &model);
+        let mask = seed.derive_mask_for_round(model.len(), config.into(), round_seed.as_slice());
+        assert_ne!(mask, seed.derive_mask(model.len(), config.into()));
+
+        // the mask derived from the seed and the round seed unmasks the model
+        let mut aggregation = Aggregation::new(config.into(), model.len());
+        assert!(aggregation.validate_aggregation(&masked_model).is_ok());
+        aggregation.aggregate(masked_model.clone());
+        assert!(aggregation.validate_unmasking(&mask).is_ok());
+        let unmasked_model = aggregation.unmask(mask);
+        let tolerance = Ratio::from_integer(config.exp_shift()).recip();
+        assert!(model
+            .iter()
+            .zip(unmasked_model.iter())
+            .all(|(weight, unmasked_weight)| (weight - unmasked_weight).abs() <= tolerance));
+
+        // the same seed masks the model differently in another round
+        let (_, remasked_model) = Masker::with_seed(config.into(), seed.clone())
+            .for_round(round_seed.as_slice())
+            .mask(Scalar::unit(), &model);
+        assert_eq!(remasked_model, masked_model);
+        let (_, other_masked_model) = Masker::with_seed(config.into(), seed)
+            .for_round(RoundSeed::generate().as_slice())
+            .mask(Scalar::unit(), &model);
+        assert_ne!(other_masked_model, masked_model);
+    }
+
+    #[test]
+    fn test_replay_masking_from_seeds() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let len = 10;
+        let models = (0..3)
+            .map(|i| Model::from_primitives_bounded(vec![i as f32 / 3.; len].into_iter()))
+            .collect::<Vec<_>>();
+
+        // the update participants mask their models
+        let (seeds, masked_models): (Vec<_>, Vec<_>) = models
+            .iter()
+            .map(|model| Masker::new(config.into()).mask(Scalar::new(1, 3_u8), model))
+            .unzip();
+        let mut model_aggregation = Aggregation::new(config.into(), len);
+        for masked_model in masked_models {
+            model_aggregation.aggregate(masked_model);
+        }
+
+        // the coordinator unmasks the aggregated model with the global mask of the sum participants
+        let mut mask_aggregation = Aggregation::new(config.into(), len);
+        for seed in seeds.iter() {
+            mask_aggregation.aggregate(seed.derive_mask(len, config.into()));
+        }
+        let coordinator_mask = MaskObject::from(mask_aggregation);
+        let global_model = model_aggregation.clone().unmask(coordinator_mask.clone());
+
+        // an auditor recomputes the global mask from the revealed seeds
+        let mut mask_aggregation = Aggregation::new(config.into(), len);
+        for seed in seeds.iter() {
+            let mask = MaskObject::from_seed_and_config(seed, len, config.into());
+            assert!(mask_aggregation.validate_aggregation(&mask).is_ok());
+            mask_aggregation.aggregate(mask);
+        }
+        let auditor_mask = MaskObject::from(mask_aggregation);
+        assert_eq!(auditor_mask, coordinator_mask);
+        assert!(model_aggregation.validate_unmasking(&auditor_mask).is_ok());
+        assert_eq!(model_aggregation.unmask(auditor_mask), global_model);
+    }
+
+    #[test]
+    fn test_read_aggregation() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let model = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+        let objects = (0..3)
+            .map(|_| Masker::new(config).mask(Scalar::unit(), &model).1)
+            .collect::<Vec<_>>();
+
+        let mut aggregation = Aggregation::new(config, model.len());
+        aggregation.aggregate(objects[0].clone());
+        assert_eq!(aggregation.as_mask_object(), objects[0]);
+        aggregation.aggregate(objects[1].clone());
+        let checkpoint = aggregation.to_mask_object();
+
+        // the checkpoint reflects the aggregation of the first two objects
+        let mut expected = Aggregation::new(config, model.len());
+        expected.aggregate(objects[0].clone());
+        expected.aggregate(objects[1].clone());
+        assert_eq!(checkpoint, MaskObject::from(expected));
+
+        // the aggregation can go on after reading it
+        assert!(aggregation.validate_aggregation(&objects[2]).is_ok());
+        aggregation.aggregate(objects[2].clone());
+        assert_eq!(aggregation.nb_models(), 3);
+        assert_ne!(aggregation.as_mask_object(), checkpoint);
+    }
+
+    #[test]
+    fn test_predicted_memory_usage() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let model = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+        let (_, object) = Masker::new(config).mask(Scalar::unit(), &model);
+        let mut aggregation = Aggregation::new(config, model.len());
+        aggregation.aggregate(object);
+
+        // the prediction covers the residues and the materialized mask object of a checkpoint
+        let residues = aggregation.vect.as_bytes().len();
+        let materialized = aggregation
+            .to_mask_object()
+            .vect
+            .data
+            .iter()
+            .map(|number| mem::size_of::<BigUint>() + number.to_u64_digits().len() * 8)
+            .sum::<usize>();
+        let predicted = Aggregation::predicted_memory_usage(config, model.len());
+        assert_eq!(aggregation.memory_usage(), predicted);
+        assert!(predicted >= residues + materialized);
+    }
+
+    #[test]
+    fn test_reset_aggregation() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let model = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+        let objects = (0..3)
+            .map(|_| Masker::new(config).mask(Scalar::unit(), &model).1)
+            .collect::<Vec<_>>();
+
+        let mut aggregation = Aggregation::new(config, model.len()).with_round_id(1);
+        aggregation.aggregate(objects[0].clone());
+        aggregation.aggregate(objects[1].clone());
+        let memory = aggregation.vect.as_bytes().as_ptr();
+        aggregation.reset();
+        assert_eq!(aggregation.nb_models(), 0);
+        assert!(aggregation.round_id().is_none());
+
+        // the reset aggregation validates like a fresh one
+        let fresh = Aggregation::new(config, model.len());
+        let short_model = Model::from_primitives(vec![0_f32].into_iter()).unwrap();
+        let (_, short) = Masker::new(config).mask(Scalar::unit(), &short_model);
+        assert!(aggregation.validate_aggregation(&objects[2]).is_ok());
+        assert!(matches!(
+            aggregation.validate_aggregation(&short),
+            Err(AggregationError::ModelMismatch)
+        ));
+        assert!(matches!(
+            fresh.validate_aggregation(&short),
+            Err(AggregationError::ModelMismatch)
+        ));
+        assert_eq!(aggregation.to_mask_object(), fresh.to_mask_object());
+
+        // the aggregation after the reset matches a fresh one and reuses the memory
+        let mut fresh = fresh;
+        aggregation.aggregate(objects[2].clone());
+        fresh.aggregate(objects[2].clone());
+        assert_eq!(aggregation.nb_models(), 1);
+        assert_eq!(aggregation.to_mask_object(), fresh.to_mask_object());
+        assert_eq!(aggregation.vect.as_bytes().as_ptr(), memory);
+    }
+
+    #[test]
+    fn test_aggregation_digest() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let model = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+        let mut aggregation = Aggregation::new(config, model.len());
+        for _ in 0..3 {
+            let (_, masked_model) = Masker::new(config).mask(Scalar::unit(), &model);
+            aggregation.aggregate(masked_model);
+
+            let object = aggregation.to_mask_object();
+            let mut bytes = vec![0; object.buffer_length()];
+            object.to_bytes(&mut bytes);
+            assert_eq!(aggregation.digest(), Sha256::hash(&bytes));
+        }
+    }
+
+    #[test]
+    fn test_unmask_to_writer() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let model = Model::from_primitives(vec![0.5_f32, -0.25, 1., 0.].into_iter()).unwrap();
+        let (seed, masked_model) = Masker::new(config).mask(Scalar::unit(), &model);
+        let mask = seed.derive_mask(model.len(), config);
+        let unmasked_model = Aggregation::from(masked_model.clone()).unmask(mask.clone());
+
+        // append the model to an existing file
+        let mut file = vec![1, 2, 3];
+        Aggregation::from(masked_model)
+            .unmask_to_writer(mask, &mut file, F32)
+            .unwrap();
+        assert_eq!(file.len(), 3 + 4 * model.len());
+        assert_eq!(file[..3], [1, 2, 3]);
+
+        let endianness = if cfg!(target_endian = "little") {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+        let written_model = Model::from_primitives_bytes(&file[3..], F32, endianness).unwrap();
+        let expected_model = Model::from_primitives(
+            IntoPrimitives::<f32>::into_primitives(unmasked_model).map(Result::unwrap),
+        )
+        .unwrap();
+        assert_eq!(written_model, expected_model);
+    }
+
+    #[test]
+    fn test_merge_aggregations() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let objects = (0..4)
+            .map(|_| MaskSeed::generate().derive_mask(10, config))
+            .collect::<Vec<_>>();
+
+        let mut expected = Aggregation::new(config, 10).with_round_id(1);
+        let mut shard1 = Aggregation::new(config, 10).with_round_id(1);
+        let mut shard2 = Aggregation::new(config, 10).with_round_id(1);
+        for (i, object) in objects.into_iter().enumerate() {
+            expected.aggregate(object.clone());
+            if i % 2 == 0 {
+                shard1.aggregate(object);
+            } else {
+                shard2.aggregate(object);
+            }
+        }
+        assert_eq!(shard1.round_id(), Some(1));
+
+        shard1.merge(shard2).unwrap();
+        assert_eq!(shard1.nb_models(), 4);
+        assert_eq!(shard1.round_id(), Some(1));
+        assert_eq!(shard1.to_mask_object(), expected.to_mask_object());
+    }
+
+    #[test]
+    fn test_merge_aggregations_of_different_rounds() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let object = MaskSeed::generate().derive_mask(10, config);
+
+        let mut aggregation = Aggregation::new(config, 10).with_round_id(1);
+        aggregation.aggregate(object.clone());
+        let mut other = Aggregation::new(config, 10).with_round_id(2);
+        other.aggregate(object.clone());
+        assert!(matches!(
+            aggregation.clone().merge(other),
+            Err(AggregationError::RoundMismatch),
+        ));
+
+        let untagged = Aggregation::from(object);
+        assert!(matches!(
+            aggregation.merge(untagged),
+            Err(AggregationError::RoundMismatch),
+        ));
+    }
+
+    #[test]
+    fn test_sparse_aggregation() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let model_1 = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+        let model_2 = Model::from_primitives(vec![0.25_f32, 0.75, -1.].into_iter()).unwrap();
+        let positions_1 = [0, 1, 2];
+        let positions_2 = [1, 2, 3];
+
+        let mut aggregation = Aggregation::new(config, 5);
+        let mut masks = Aggregation::new(config, 5);
+        for (model, positions) in [(&model_1, &positions_1), (&model_2, &positions_2)] {
+            let (seed, masked_model) = Masker::new(config).mask(Scalar::unit(), model);
+            let mask = seed.derive_mask(model.len(), config);
+            assert!(aggregation
+                .validate_sparse_aggregation(&masked_model, positions)
+                .is_ok());
+            aggregation.aggregate_sparse(masked_model, positions);
+            masks.aggregate_sparse(mask, positions);
+        }
+        assert_eq!(aggregation.nb_models(), 2);
+        assert_eq!(aggregation.contribution_counts(), vec![1, 2, 2, 1, 0]);
+
+        // each position is averaged by its own number of contributions
+        let w1 = model_1.iter().collect::<Vec<_>>();
+        let w2 = model_2.iter().collect::<Vec<_>>();
+        let half = |weight: Ratio<BigInt>| weight / BigInt::from(2);
+        let expected = [
+            w1[0].clone(),
+            half(w1[1] + w2[0]),
+            half(w1[2] + w2[1]),
+            w2[2].clone(),
+            Ratio::zero(),
+        ];
+        assert!(aggregation
+            .validate_unmasking(&masks.to_mask_object())
+            .is_ok());
+        let unmasked_model = aggregation.unmask(masks.into());
+        let tolerance = Ratio::from_integer(config.vect.exp_shift()).recip();
+        assert_eq!(unmasked_model.len(), expected.len());
+        assert!(expected
+            .iter()
+            .zip(unmasked_model.iter())
+            .all(|(weight, unmasked_weight)| (weight - unmasked_weight).abs() <= tolerance));
+    }
+
+    #[test]
+    fn test_sparse_aggregation_counts() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let dense = MaskSeed::generate().derive_mask(4, config);
+        let sparse = MaskSeed::generate().derive_mask(2, config);
+
+        let mut aggregation = Aggregation::new(config, 4);
+        aggregation.aggregate(dense.clone());
+        assert_eq!(aggregation.contribution_counts(), vec![1; 4]);
+        assert!(matches!(
+            aggregation.validate_sparse_aggregation(&sparse, &[2, 1]),
+            Err(AggregationError::InvalidPositions),
+        ));
+        assert!(matches!(
+            aggregation.validate_sparse_aggregation(&sparse, &[1, 4]),
+            Err(AggregationError::InvalidPositions),
+        ));
+        assert!(matches!(
+            aggregation.validate_sparse_aggregation(&sparse, &[1]),
+            Err(AggregationError::ModelMismatch),
+        ));
+        aggregation.aggregate_sparse(sparse, &[1, 3]);
+        assert_eq!(aggregation.contribution_counts(), vec![1, 2, 1, 2]);
+        aggregation.aggregate(dense.clone());
+        assert_eq!(aggregation.contribution_counts(), vec![2, 3, 2, 3]);
+
+        let mut other = Aggregation::from(dense);
+        other.merge(aggregation.clone()).unwrap();
+        assert_eq!(other.contribution_counts(), vec![3, 4, 3, 4]);
+
+        aggregation.reset();
+        assert_eq!(aggregation.contribution_counts(), vec![0; 4]);
+    }
+
+    #[test]
+    fn test_aggregation_from_checkpoint() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let objects = (0..3)
+            .map(|_| MaskSeed::generate().derive_mask(4, config))
+            .collect::<Vec<_>>();
+
+        let mut aggregation = Aggregation::new(config, 4);
+        let mut restored = Aggregation::from_checkpoint(
+            config,
+            4,
+            aggregation.to_mask_object(),
+            aggregation.nb_models(),
+        );
+        for object in objects {
+            aggregation.aggregate(object.clone());
+            restored.aggregate(object);
+            restored = Aggregation::from_checkpoint(
+                config,
+                4,
+                restored.to_mask_object(),
+                restored.nb_models(),
+            );
+            assert_eq!(restored.nb_models(), aggregation.nb_models());
+            assert_eq!(restored.len(), aggregation.len());
+            assert_eq!(restored.to_mask_object(), aggregation.to_mask_object());
+        }
+    }
+
+    #[test]
+    fn test_aggregate_seeds() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        }
+        .into();
+        let round_seed = RoundSeed::generate();
+        let seeds = (0..3).map(|_| MaskSeed::generate()).collect::<Vec<_>>();
+
+        let mut expected = Aggregation::new(config, 10);
+        let mut aggregation = Aggregation::new(config, 10);
+        for seed in seeds.iter() {
+            expected.aggregate(seed.derive_mask(10, config));
+            aggregation.aggregate_seed(seed, None).unwrap();
+        }
+        assert_eq!(aggregation.nb_models(), 3);
+        assert_eq!(aggregation.to_mask_object(), expected.to_mask_object());
+
+        let mut expected = Aggregation::new(config, 10);
+        let mut aggregation = Aggregation::new(config, 10);
+        for seed in seeds.iter() {
+            expected.aggregate(seed.derive_mask_for_round(10, config, round_seed.as_slice()));
+            aggregation
+                .aggregate_seed(seed, Some(round_seed.as_slice()))
+                .unwrap();
+        }
+        assert_eq!(aggregation.to_mask_object(), expected.to_mask_object());
+    }
+
+    #[test]
+    fn test_masking_shards() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let mut prng = ChaCha20Rng::from_seed(MaskSeed::generate().as_array());
+        let random_weights = Uniform::new_inclusive(-1_f32, 1_f32)
+            .sample_iter(&mut prng)
+            .take(10);
+        let model = Model::from_primitives(random_weights).unwrap();
+
+        // mask each shard with the seed derived for it
+        let seed = MaskSeed::generate();
+        let shards = model.shard(4);
+        let shard_seeds = seed.derive_shard_seeds(shards.len());
+        let masked_shards = shards
+            .iter()
+            .zip(shard_seeds.iter())
+            .map(|(shard, shard_seed)| {
+                Masker::with_seed(config.into(), shard_seed.clone())
+                    .mask(Scalar::unit(), shard)
+                    .1
+            })
+            .collect::<Vec<_>>();
+
+        // the masking is reproducible from the base seed alone
+        let remasked_shards = shards
+            .iter()
+            .zip(seed.derive_shard_seeds(shards.len()))
+            .map(|(shard, shard_seed)| {
+                Masker::with_seed(config.into(), shard_seed)
+                    .mask(Scalar::unit(), shard)
+                    .1
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(masked_shards, remasked_shards);
+
+        // the joined shards are the whole model masked with the concatenation of the shard
+        // masks, hence unmasking them shard by shard yields the whole model
+        let unmasked_model = Model::join(
+            masked_shards
+                .into_iter()
+                .zip(shards.iter().zip(shard_seeds.iter()))
+                .map(|(masked_shard, (shard, shard_seed))| {
+                    let mask = shard_seed.derive_mask(shard.len(), config.into());
+                    Aggregation::from(masked_shard).unmask(mask)
+                })
+                .collect(),
+        );
+        assert_eq!(unmasked_model.len(), model.len());
+        let tolerance = Ratio::from_integer(config.exp_shift()).recip();
+        assert!(model
+            .iter()
+            .zip(unmasked_model.iter())
+            .all(|(weight, unmasked_weight)| (weight - unmasked_weight).abs() <= tolerance));
+    }
+
+    /// Creates a model config with a section of `B0` bounded weights followed by a section of
+    /// `B6` bounded weights.
+    fn two_section_config() -> (ModelConfig, MaskConfig) {
+        let narrow = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let wide = MaskConfig {
+            bound_type: B6,
+            ..narrow
+        };
+        let config = vec![
+            MaskSection {
+                len: 4,
+                config: narrow,
+            },
+            MaskSection {
+                len: 6,
+                config: wide,
+            },
+        ]
+        .into();
+        (config, narrow)
+    }
+
+    #[test]
+    fn test_masking_sections() {
+        let (config, unit) = two_section_config();
+        let mut prng = ChaCha20Rng::from_seed(MaskSeed::generate().as_array());
+        let narrow_weights = Uniform::new_inclusive(-1_f32, 1_f32)
+            .sample_iter(&mut prng)
+            .take(4)
+            .collect::<Vec<_>>();
+        let wide_weights = Uniform::new_inclusive(-1_000_000_f32, 1_000_000_f32)
+            .sample_iter(&mut prng)
+            .take(6)
+            .collect::<Vec<_>>();
+        let model = Model::from_primitives(narrow_weights.into_iter().chain(wide_weights)).unwrap();
+
+        // mask the model three times, aggregate the masked models and their masks
+        let mut model_aggregation = SectionAggregation::new(config.clone(), unit);
+        let mut mask_aggregation = SectionAggregation::new(config.clone(), unit);
+        for _ in 0..3 {
+            let (seed, masked_model) =
+                Masker::new(unit.into()).mask_sections(Scalar::unit(), &model, &config);
+            assert_eq!(masked_model.config(), config);
+            assert!(masked_model.is_valid());
+            assert!(model_aggregation
+                .validate_aggregation(&masked_model)
+                .is_ok());
+            model_aggregation.aggregate(masked_model);
+
+            let mask = seed.derive_mask_sections(&config, unit);
+            assert!(mask_aggregation.validate_aggregation(&mask).is_ok());
+            mask_aggregation.aggregate(mask);
+        }
+        assert_eq!(model_aggregation.nb_models(), 3);
+        assert_eq!(model_aggregation.len(), model.len());
+
+        // the averaged model is the original one up to the tolerance of each section
+        let mask = mask_aggregation.into();
+        assert!(model_aggregation.validate_unmasking(&mask).is_ok());
+        let unmasked_model = model_aggregation.unmask(mask);
+        assert_eq!(unmasked_model.len(), model.len());
+        let mut weights = model.iter().zip(unmasked_model.iter());
+        for section in config.sections() {
+            let tolerance =
+                Ratio::from_integer(section.config.exp_shift()).recip() * BigInt::from(3);
+            assert!(weights
+                .by_ref()
+                .take(section.len)
+                .all(|(weight, unmasked_weight)| (weight - unmasked_weight).abs() <= tolerance));
+        }
+    }
+
+    #[test]
+    fn test_masking_single_section() {
+        let (config, unit) = two_section_config();
+        let model = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+        let seed = MaskSeed::generate();
+
+        // a single section masks like the masking config pair
+        let (_, masked_model) =
+            Masker::with_seed(unit.into(), seed.clone()).mask(Scalar::unit(), &model);
+        let single = ModelConfig::single(unit, model.len());
+        let (_, masked_sections) = Masker::with_seed(unit.into(), seed.clone()).mask_sections(
+            Scalar::unit(),
+            &model,
+            &single,
+        );
+        assert_eq!(MaskSections::from(masked_model), masked_sections);
+        assert_eq!(
+            MaskSections::from(seed.derive_mask(model.len(), unit.into())),
+            seed.derive_mask_sections(&single, unit),
+        );
+
+        // objects with other sections can't be aggregated or unmasked
+        let aggregation = SectionAggregation::new(config.clone(), unit);
+        assert!(matches!(
+            aggregation.validate_aggregation(&masked_sections),
+            Err(AggregationError::ModelMismatch)
+        ));
+        let aggregation = SectionAggregation::from(masked_sections);
+        assert_eq!(
+            aggregation.validate_unmasking(&seed.derive_mask_sections(&config, unit)),
+            Err(UnmaskingError::MaskManyMismatch),
+        );
+    }
+
+    #[test]
+    fn test_aggregating_masked_shards() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let mut prng = ChaCha20Rng::from_seed(MaskSeed::generate().as_array());
+        let models = (0..3)
+            .map(|_| {
+                let random_weights = Uniform::new_inclusive(-1_f32, 1_f32)
+                    .sample_iter(&mut prng)
+                    .take(10);
+                Model::from_primitives(random_weights).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // mask and aggregate the whole models
+        let mut aggregated_masked_model = Aggregation::new(config.into(), 10);
+        let mut aggregated_mask = Aggregation::new(config.into(), 10);
+        for model in models.iter() {
+            let (mask_seed, masked_model) = Masker::new(config.into()).mask(Scalar::unit(), model);
+            aggregated_masked_model.aggregate(masked_model);
+            aggregated_mask.aggregate(mask_seed.derive_mask(10, config.into()));
+        }
+        let global_model = aggregated_masked_model.unmask(aggregated_mask.into());
+
+        // mask and aggregate the models shard by shard, with the seeds derived from a base seed
+        let shard_lens = models[0]
+            .shard(4)
+            .iter()
+            .map(Model::len)
+            .collect::<Vec<_>>();
+        let new_aggregations = || {
+            shard_lens
+                .iter()
+                .map(|len| Aggregation::new(config.into(), *len))
+                .collect::<Vec<_>>()
+        };
+        let mut aggregated_masked_shards = new_aggregations();
+        let mut aggregated_shard_masks = new_aggregations();
+        for model in models.iter() {
+            let shards = model.shard(4);
+            let shard_seeds = MaskSeed::generate().derive_shard_seeds(shards.len());
+            for (i, (shard, shard_seed)) in shards.iter().zip(shard_seeds).enumerate() {
+                let mask = shard_seed.derive_mask(shard.len(), config.into());
+                let (_, masked_shard) =
+                    Masker::with_seed(config.into(), shard_seed).mask(Scalar::unit(), shard);
+                aggregated_masked_shards[i].aggregate(masked_shard);
+                aggregated_shard_masks[i].aggregate(mask);
+            }
+        }
+        let sharded_global_model = Model::join(
+            aggregated_masked_shards
+                .into_iter()
+                .zip(aggregated_shard_masks)
+                .map(|(masked_shard, mask)| masked_shard.unmask(mask.into()))
+                .collect(),
+        );
+
+        // masking and aggregating by shard yields the same global model as for the whole models
+        assert_eq!(sharded_global_model, global_model);
+    }
+
     test_masking!(int_f32_b0, Integer, f32, 1, 10);
     test_masking!(int_f32_b2, Integer, f32, 100, 10);
     test_masking!(int_f32_b4, Integer, f32, 10_000, 10);
@@ -752,10 +2285,11 @@ mod tests {
                         aggregated_masked_model.aggregate(masked_model);
 
                         assert_eq!(aggregated_masked_model.nb_models, nb);
-                        assert_eq!(aggregated_masked_model.object.vect.data.len(), vect_len);
-                        assert_eq!(aggregated_masked_model.object.vect.config, config);
-                        assert_eq!(aggregated_masked_model.object.unit.config, config);
-                        assert!(aggregated_masked_model.object.is_valid());
+                        let object = aggregated_masked_model.to_mask_object();
+                        assert_eq!(object.vect.data.len(), vect_len);
+                        assert_eq!(object.vect.config, config);
+                        assert_eq!(object.unit.config, config);
+                        assert!(object.is_valid());
                     }
                 }
             }
@@ -9,6 +9,12 @@
 //! Currently, the primitive data types [`f32`], [`f64`], [`i32`] and [`i64`] are supported and
 //! this might be extended in the future.
 //!
+//! With the `ndarray` feature enabled, a model can also be converted from and into an
+//! `ndarray::Array1` of [`f32`] or [`f64`] values via [`TryFrom`], where the conversion into a
+//! model fails for values which are not finite.
+//!
+//! [`TryFrom`]: std::convert::TryFrom
+//!
 //! ```
 //! # use xaynet_core::mask::{FromPrimitives, IntoPrimitives, Model};
 //! let weights = vec![0_f32; 10];
@@ -73,6 +79,14 @@
 //! - M9: at most 1,000,000,000 masked models may be aggregated.
 //! - M12: at most 1,000,000,000,000 masked models may be aggregated.
 //!
+//! ## Model configurations
+//! A single masking configuration for a whole model forces all weights to pay for the worst-case
+//! bound, eg. when a few layers require a much larger bound than the rest of the model. Instead,
+//! a [`ModelConfig`] describes consecutive [`MaskSection`]s of a model with their own masking
+//! configurations. Such models are masked via [`Masker::mask_sections()`] into [`MaskSections`]
+//! and aggregated section-wise via a [`SectionAggregation`]. A model with a single section is
+//! masked exactly like a model with a single masking configuration.
+//!
 //! # Masking, aggregation and unmasking
 //! Local models should be masked (i.e. encrypted) before they are communicated somewhere else to
 //! protect the possibly sensitive information learned from local data. The masking should allow
@@ -180,13 +194,77 @@
 //!     );
 //! };
 //! ```
+//!
+//! ## Trimmed mean aggregation
+//! The mean of the models is sensitive to outliers, since a single (possibly malicious) model
+//! can shift it arbitrarily far. A [`TrimmedMeanAggregation`] removes the `k` lowest and `k`
+//! highest weights of each coordinate before averaging the remaining ones wrt their scalars.
+//!
+//! This is only feasible on unmasked models: an [`Aggregation`] of masked models only knows the
+//! sum of the masked weights, which reveals nothing about their order, and removing a single
+//! contribution from it would require the mask of that very contribution. Robust aggregation of
+//! masked models therefore needs either a party which is trusted with the individual masks and
+//! unmasks each masked model on its own, see [`TrimmedMeanAggregation::aggregate_masked()`], or a
+//! secure comparison protocol between the participants, which is not supported.
+//!
+//! ```
+//! # use xaynet_core::mask::{FromPrimitives, Model, Scalar, TrimmedMeanAggregation};
+//! let mut aggregation = TrimmedMeanAggregation::new(1);
+//! for weight in &[1_f32, 2., 3., 1000.] {
+//!     let model = Model::from_primitives_bounded(vec![*weight; 10].into_iter());
+//!     if let Ok(_) = aggregation.validate_aggregation(&model) {
+//!         aggregation.aggregate(model, Scalar::unit());
+//!     }
+//! }
+//! assert_eq!(
+//!     aggregation.trimmed_mean().unwrap(),
+//!     Model::from_primitives_bounded(vec![2.5_f32; 10].into_iter()),
+//! );
+//! ```
+//!
+//! ## Verification
+//! The masking of a round can be replayed to verify that a global model was correctly derived
+//! from the aggregated masked model. An auditor who knows the revealed [`MaskSeed`]s of the
+//! participants recomputes their masks via [`MaskObject::from_seed_and_config()`] wrt the
+//! masking configurations of the round, aggregates them into the global mask and unmasks the
+//! aggregated masked model with it. The result must equal the claimed global model, otherwise the
+//! global model wasn't derived from the aggregated masked model or the seeds are incomplete.
+//!
+//! ```
+//! # use xaynet_core::mask::{Aggregation, BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, MaskObject, Model, ModelType, Scalar};
+//! # let number_weights = 10;
+//! # let scalar = Scalar::new(1, 2_u8);
+//! # let local_model_1 = Model::from_primitives_bounded(vec![0_f32; number_weights].into_iter());
+//! # let local_model_2 = Model::from_primitives_bounded(vec![1_f32; number_weights].into_iter());
+//! # let config = MaskConfig { group_type: GroupType::Prime, data_type: DataType::F32, bound_type: BoundType::B0, model_type: ModelType::M3};
+//! # let (local_mask_seed_1, masked_local_model_1) = Masker::new(config.into()).mask(scalar.clone(), &local_model_1);
+//! # let (local_mask_seed_2, masked_local_model_2) = Masker::new(config.into()).mask(scalar, &local_model_2);
+//! # let mut model_aggregator = Aggregation::new(config.into(), number_weights);
+//! # model_aggregator.aggregate(masked_local_model_1);
+//! # model_aggregator.aggregate(masked_local_model_2);
+//! # let claimed_global_model = Model::from_primitives_bounded(vec![0.5_f32; number_weights].into_iter());
+//! // recompute the global mask from the revealed seeds
+//! let mut mask_aggregator = Aggregation::new(config.into(), number_weights);
+//! for seed in &[local_mask_seed_1, local_mask_seed_2] {
+//!     let mask = MaskObject::from_seed_and_config(seed, number_weights, config.into());
+//!     assert!(mask_aggregator.validate_aggregation(&mask).is_ok());
+//!     mask_aggregator.aggregate(mask);
+//! }
+//! let global_mask: MaskObject = mask_aggregator.into();
+//!
+//! // unmask the aggregated masked model and compare it with the claimed global model
+//! assert!(model_aggregator.validate_unmasking(&global_mask).is_ok());
+//! assert_eq!(model_aggregator.unmask(global_mask), claimed_global_model);
+//! ```
 
 pub(crate) mod config;
 pub(crate) mod masking;
 pub(crate) mod model;
 pub(crate) mod object;
+pub(crate) mod residues;
 pub(crate) mod scalar;
 pub(crate) mod seed;
+pub(crate) mod trimmed;
 
 pub use self::{
     config::{
@@ -197,17 +275,38 @@ pub use self::{
         InvalidMaskConfigError,
         MaskConfig,
         MaskConfigPair,
+        MaskSection,
+        ModelConfig,
         ModelType,
     },
-    masking::{Aggregation, AggregationError, Masker, UnmaskingError},
-    model::{FromPrimitives, IntoPrimitives, Model, ModelCastError, PrimitiveCastError},
+    masking::{
+        Aggregation,
+        AggregationError,
+        MaskObjectView,
+        Masker,
+        SectionAggregation,
+        UnmaskingError,
+    },
+    model::{
+        Endianness,
+        FromPrimitives,
+        IntoPrimitives,
+        Model,
+        ModelCastError,
+        ModelDelta,
+        ModelDeltaError,
+        ModelError,
+        PrimitiveCastError,
+    },
     object::{
-        serialization::vect::MaskVectBuffer,
+        serialization::{sections::MASK_SECTIONS_VERSION, vect::MaskVectBuffer},
         InvalidMaskObjectError,
         MaskObject,
+        MaskSections,
         MaskUnit,
         MaskVect,
     },
     scalar::{FromPrimitive, IntoPrimitive, Scalar, ScalarCastError},
-    seed::{EncryptedMaskSeed, MaskSeed},
+    seed::{EncryptedMaskSeed, MaskSeed, SealedBox, SeedCipher},
+    trimmed::{TrimmedMeanAggregation, TrimmedMeanError},
 };
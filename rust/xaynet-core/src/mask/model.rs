@@ -5,7 +5,9 @@
 //! [mask module]: crate::mask
 
 use std::{
-    fmt::Debug,
+    convert::{TryFrom, TryInto},
+    fmt::{Debug, Display},
+    io::{self, Write},
     iter::{FromIterator, IntoIterator},
     slice::{Iter, IterMut},
 };
@@ -18,8 +20,11 @@ use num::{
     traits::{float::FloatCore, identities::Zero, ToPrimitive},
 };
 use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::hash::sha256;
 use thiserror::Error;
 
+use crate::{crypto::Sha256, mask::config::DataType};
+
 #[derive(Debug, Clone, PartialEq, Hash, From, Index, IndexMut, Into, Serialize, Deserialize)]
 /// A numerical representation of a machine learning model.
 pub struct Model(Vec<Ratio<BigInt>>);
@@ -30,13 +35,17 @@ impl std::convert::AsRef<Model> for Model {
     }
 }
 
-#[allow(clippy::len_without_is_empty)]
 impl Model {
     /// Gets the number of weights/parameters of this model.
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    /// Checks whether this model has no weights/parameters.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Creates an iterator that yields references to the weights/parameters of this model.
     pub fn iter(&self) -> Iter<Ratio<BigInt>> {
         self.0.iter()
@@ -46,6 +55,277 @@ impl Model {
     pub fn iter_mut(&mut self) -> IterMut<Ratio<BigInt>> {
         self.0.iter_mut()
     }
+
+    /// Splits this model into contiguous shards of `shard_size` weights/parameters.
+    ///
+    /// The last shard holds the remaining weights and may hence be smaller. The shards can be
+    /// masked independently, eg. with the seeds from [`MaskSeed::derive_shard_seeds()`], and
+    /// reassembled with [`join()`].
+    ///
+    /// # Panics
+    /// Panics if `shard_size` is zero.
+    ///
+    /// [`MaskSeed::derive_shard_seeds()`]: crate::mask::MaskSeed::derive_shard_seeds
+    /// [`join()`]: Model::join
+    pub fn shard(&self, shard_size: usize) -> Vec<Model> {
+        self.0
+            .chunks(shard_size)
+            .map(|shard| Model(shard.to_vec()))
+            .collect()
+    }
+
+    /// Computes the `SHA256` checksum of the weights/parameters of this model.
+    ///
+    /// The numerator and the denominator of each weight are hashed as length-prefixed big-endian
+    /// two's complement bytes, in the order of the weights.
+    pub fn checksum(&self) -> Sha256 {
+        let mut hasher = sha256::State::new();
+        hasher.update(&(self.0.len() as u64).to_be_bytes());
+        for weight in self.0.iter() {
+            for part in [weight.numer(), weight.denom()].iter() {
+                let bytes = part.to_signed_bytes_be();
+                hasher.update(&(bytes.len() as u64).to_be_bytes());
+                hasher.update(&bytes);
+            }
+        }
+        Sha256::from(hasher.finalize())
+    }
+
+    /// Reassembles a model from its contiguous `shards`.
+    ///
+    /// This is the inverse of [`shard()`].
+    ///
+    /// [`shard()`]: Model::shard
+    pub fn join(shards: Vec<Model>) -> Model {
+        shards.into_iter().flat_map(|shard| shard.0).collect()
+    }
+
+    /// Creates a model from a buffer of raw primitive values.
+    ///
+    /// The `bytes` are decoded as a sequence of primitive values of the given `data_type`, each
+    /// encoded with the given `endianness`. This is useful to ingest weights that were exported
+    /// as raw buffers by another system.
+    ///
+    /// # Errors
+    /// Fails if the length of `bytes` is not a multiple of the size of the primitive data type or
+    /// if a decoded primitive value can't be converted into a numerical value due to not being
+    /// finite, in which case the index of the offending value is reported.
+    pub fn from_primitives_bytes(
+        bytes: &[u8],
+        data_type: DataType,
+        endianness: Endianness,
+    ) -> Result<Self, ModelError> {
+        match data_type {
+            DataType::F32 => Self::from_primitives(decode_primitives(
+                bytes,
+                endianness,
+                f32::from_le_bytes,
+                f32::from_be_bytes,
+            )?),
+            DataType::F64 => Self::from_primitives(decode_primitives(
+                bytes,
+                endianness,
+                f64::from_le_bytes,
+                f64::from_be_bytes,
+            )?),
+            DataType::I32 => Self::from_primitives(decode_primitives(
+                bytes,
+                endianness,
+                i32::from_le_bytes,
+                i32::from_be_bytes,
+            )?),
+            DataType::I64 => Self::from_primitives(decode_primitives(
+                bytes,
+                endianness,
+                i64::from_le_bytes,
+                i64::from_be_bytes,
+            )?),
+        }
+    }
+
+    /// Converts the model into a buffer of raw primitive values.
+    ///
+    /// The weights are converted into primitive values of the given `data_type`, each encoded with
+    /// the given `endianness`. This is the inverse of [`from_primitives_bytes()`].
+    ///
+    /// # Errors
+    /// Fails if a weight can't be converted into the primitive data type.
+    ///
+    /// [`from_primitives_bytes()`]: Model::from_primitives_bytes
+    pub fn to_primitives_bytes(
+        &self,
+        data_type: DataType,
+        endianness: Endianness,
+    ) -> Result<Vec<u8>, ModelCastError> {
+        match data_type {
+            DataType::F32 => encode_primitives(
+                self.to_primitives(),
+                endianness,
+                f32::to_le_bytes,
+                f32::to_be_bytes,
+            ),
+            DataType::F64 => encode_primitives(
+                self.to_primitives(),
+                endianness,
+                f64::to_le_bytes,
+                f64::to_be_bytes,
+            ),
+            DataType::I32 => encode_primitives(
+                self.to_primitives(),
+                endianness,
+                i32::to_le_bytes,
+                i32::to_be_bytes,
+            ),
+            DataType::I64 => encode_primitives(
+                self.to_primitives(),
+                endianness,
+                i64::to_le_bytes,
+                i64::to_be_bytes,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The difference between a global model and the global model of a previous round.
+///
+/// The delta records the element-wise differences of the weights which changed, hence it is much
+/// smaller than the model itself if the models barely differ. It applies only to the model of the
+/// base round, a participant without that model must download the full global model instead.
+pub struct ModelDelta {
+    /// The id of the round of the model which the delta applies to.
+    pub base_round_id: u64,
+    /// The id of the round of the model which the delta yields.
+    pub round_id: u64,
+    /// The length of the models.
+    pub model_length: usize,
+    /// The differences of the changed weights together with their indices.
+    pub changes: Vec<(u64, Ratio<BigInt>)>,
+    /// The checksum of the model which the delta yields, see [`Model::checksum()`].
+    pub checksum: Sha256,
+}
+
+impl ModelDelta {
+    /// Computes the delta from the `base` model of the round `base_round_id` to the `model` of
+    /// the round `round_id`.
+    ///
+    /// # Errors
+    /// Fails if the lengths of the models differ.
+    pub fn new(
+        base_round_id: u64,
+        base: &Model,
+        round_id: u64,
+        model: &Model,
+    ) -> Result<Self, ModelDeltaError> {
+        if base.len() != model.len() {
+            return Err(ModelDeltaError::LengthMismatch(base.len(), model.len()));
+        }
+        let changes = base
+            .iter()
+            .zip(model.iter())
+            .enumerate()
+            .filter(|(_, (base_weight, weight))| base_weight != weight)
+            .map(|(index, (base_weight, weight))| (index as u64, weight - base_weight))
+            .collect();
+        Ok(Self {
+            base_round_id,
+            round_id,
+            model_length: model.len(),
+            changes,
+            checksum: model.checksum(),
+        })
+    }
+
+    /// Applies the delta to the `base` model, which must be the model of the base round.
+    ///
+    /// # Errors
+    /// Fails if the `base` model doesn't match the delta, in which case the full model must be
+    /// downloaded instead.
+    pub fn apply(&self, base: &Model) -> Result<Model, ModelDeltaError> {
+        if base.len() != self.model_length {
+            return Err(ModelDeltaError::LengthMismatch(
+                base.len(),
+                self.model_length,
+            ));
+        }
+        let mut model = base.clone();
+        for (index, difference) in self.changes.iter() {
+            let weight = usize::try_from(*index)
+                .ok()
+                .and_then(|index| model.0.get_mut(index))
+                .ok_or(ModelDeltaError::InvalidIndex(*index))?;
+            *weight += difference;
+        }
+        if model.checksum() != self.checksum {
+            return Err(ModelDeltaError::ChecksumMismatch);
+        }
+        Ok(model)
+    }
+}
+
+#[derive(Error, Debug)]
+/// Errors related to model deltas.
+pub enum ModelDeltaError {
+    #[error("model of length {0} doesn't match the model length {1} of the delta")]
+    LengthMismatch(usize, usize),
+    #[error("the delta changes the weight at the invalid index {0}")]
+    InvalidIndex(u64),
+    #[error("the delta doesn't yield the expected model")]
+    ChecksumMismatch,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The byte order of raw primitive values.
+pub enum Endianness {
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+#[derive(Error, Debug)]
+/// Errors related to model creation from raw primitive values.
+pub enum ModelError {
+    #[error("byte buffer of length {0} is not a multiple of the primitive size {1}")]
+    InvalidLength(usize, usize),
+    #[error("primitive value {value} at index {index} is not finite")]
+    NonFinite { index: usize, value: String },
+}
+
+/// Decodes `bytes` into an iterator of primitive values of size `N` with the given `endianness`.
+fn decode_primitives<'a, P: 'a, const N: usize>(
+    bytes: &'a [u8],
+    endianness: Endianness,
+    from_le_bytes: fn([u8; N]) -> P,
+    from_be_bytes: fn([u8; N]) -> P,
+) -> Result<impl Iterator<Item = P> + 'a, ModelError> {
+    let chunks = bytes.chunks_exact(N);
+    if !chunks.remainder().is_empty() {
+        return Err(ModelError::InvalidLength(bytes.len(), N));
+    }
+    let decode = match endianness {
+        Endianness::Little => from_le_bytes,
+        Endianness::Big => from_be_bytes,
+    };
+    // safe unwrap: the chunks have exactly N bytes
+    Ok(chunks.map(move |chunk| decode(chunk.try_into().unwrap())))
+}
+
+/// Encodes the `primitives` into bytes with the given `endianness`.
+fn encode_primitives<P, const N: usize>(
+    mut primitives: impl Iterator<Item = Result<P, ModelCastError>>,
+    endianness: Endianness,
+    to_le_bytes: fn(P) -> [u8; N],
+    to_be_bytes: fn(P) -> [u8; N],
+) -> Result<Vec<u8>, ModelCastError> {
+    let encode = match endianness {
+        Endianness::Little => to_le_bytes,
+        Endianness::Big => to_be_bytes,
+    };
+    primitives.try_fold(Vec::new(), |mut bytes, primitive| {
+        bytes.extend_from_slice(&encode(primitive?));
+        Ok(bytes)
+    })
 }
 
 impl FromIterator<Ratio<BigInt>> for Model {
@@ -126,8 +406,15 @@ pub trait FromPrimitives<P: Debug>: Sized {
     ///
     /// # Errors
     /// Yields an error for the first encountered primitive value that can't be converted into a
-    /// numerical value due to not being finite.
-    fn from_primitives<I: Iterator<Item = P>>(iter: I) -> Result<Self, PrimitiveCastError<P>>;
+    /// numerical value due to not being finite. The error reports the index of the value.
+    fn from_primitives<I: Iterator<Item = P>>(iter: I) -> Result<Self, ModelError>;
+
+    /// Creates an iterator from primitive values that yields converted numerical values.
+    ///
+    /// If a primitive value cannot be directly converted into a numerical value due to not being
+    /// finite, it is replaced by zero. Returns the converted values together with the number of
+    /// replaced primitive values.
+    fn from_primitives_lossy<I: Iterator<Item = P>>(iter: I) -> (Self, usize);
 
     /// Creates an iterator from primitive values that yields converted numerical values.
     ///
@@ -158,10 +445,15 @@ impl IntoPrimitives<i32> for Model {
 }
 
 impl FromPrimitives<i32> for Model {
-    fn from_primitives<I: Iterator<Item = i32>>(iter: I) -> Result<Self, PrimitiveCastError<i32>> {
+    fn from_primitives<I: Iterator<Item = i32>>(iter: I) -> Result<Self, ModelError> {
         Ok(iter.map(|p| Ratio::from_integer(BigInt::from(p))).collect())
     }
 
+    fn from_primitives_lossy<I: Iterator<Item = i32>>(iter: I) -> (Self, usize) {
+        // integers are always finite
+        (Self::from_primitives(iter).unwrap(), 0)
+    }
+
     fn from_primitives_bounded<I: Iterator<Item = i32>>(iter: I) -> Self {
         Self::from_primitives(iter).unwrap()
     }
@@ -189,10 +481,15 @@ impl IntoPrimitives<i64> for Model {
 }
 
 impl FromPrimitives<i64> for Model {
-    fn from_primitives<I: Iterator<Item = i64>>(iter: I) -> Result<Self, PrimitiveCastError<i64>> {
+    fn from_primitives<I: Iterator<Item = i64>>(iter: I) -> Result<Self, ModelError> {
         Ok(iter.map(|p| Ratio::from_integer(BigInt::from(p))).collect())
     }
 
+    fn from_primitives_lossy<I: Iterator<Item = i64>>(iter: I) -> (Self, usize) {
+        // integers are always finite
+        (Self::from_primitives(iter).unwrap(), 0)
+    }
+
     fn from_primitives_bounded<I: Iterator<Item = i64>>(iter: I) -> Self {
         Self::from_primitives(iter).unwrap()
     }
@@ -222,9 +519,21 @@ impl IntoPrimitives<f32> for Model {
 }
 
 impl FromPrimitives<f32> for Model {
-    fn from_primitives<I: Iterator<Item = f32>>(iter: I) -> Result<Self, PrimitiveCastError<f32>> {
-        iter.map(|f| Ratio::from_float(f).ok_or(PrimitiveCastError(f)))
-            .collect()
+    fn from_primitives<I: Iterator<Item = f32>>(iter: I) -> Result<Self, ModelError> {
+        iter.enumerate().map(float_to_ratio::<f32>).collect()
+    }
+
+    fn from_primitives_lossy<I: Iterator<Item = f32>>(iter: I) -> (Self, usize) {
+        let mut replaced = 0;
+        let model = iter
+            .map(|f| {
+                Ratio::from_float(f).unwrap_or_else(|| {
+                    replaced += 1;
+                    Ratio::zero()
+                })
+            })
+            .collect();
+        (model, replaced)
     }
 
     fn from_primitives_bounded<I: Iterator<Item = f32>>(iter: I) -> Self {
@@ -256,9 +565,21 @@ impl IntoPrimitives<f64> for Model {
 }
 
 impl FromPrimitives<f64> for Model {
-    fn from_primitives<I: Iterator<Item = f64>>(iter: I) -> Result<Self, PrimitiveCastError<f64>> {
-        iter.map(|f| Ratio::from_float(f).ok_or(PrimitiveCastError(f)))
-            .collect()
+    fn from_primitives<I: Iterator<Item = f64>>(iter: I) -> Result<Self, ModelError> {
+        iter.enumerate().map(float_to_ratio::<f64>).collect()
+    }
+
+    fn from_primitives_lossy<I: Iterator<Item = f64>>(iter: I) -> (Self, usize) {
+        let mut replaced = 0;
+        let model = iter
+            .map(|f| {
+                Ratio::from_float(f).unwrap_or_else(|| {
+                    replaced += 1;
+                    Ratio::zero()
+                })
+            })
+            .collect();
+        (model, replaced)
     }
 
     fn from_primitives_bounded<I: Iterator<Item = f64>>(iter: I) -> Self {
@@ -266,6 +587,43 @@ impl FromPrimitives<f64> for Model {
     }
 }
 
+/// Writes a weight as a primitive value of the given `data_type` in native byte order.
+///
+/// # Errors
+/// Fails with [`io::ErrorKind::InvalidData`] if the weight can't be converted into the primitive
+/// data type or if the `writer` fails.
+pub(crate) fn write_primitive<W: Write>(
+    writer: &mut W,
+    weight: Ratio<BigInt>,
+    data_type: DataType,
+) -> io::Result<()> {
+    let (bytes, target) = match data_type {
+        DataType::F32 => (
+            ratio_to_float::<f32>(&weight).map(|p| p.to_ne_bytes().to_vec()),
+            PrimitiveType::F32,
+        ),
+        DataType::F64 => (
+            ratio_to_float::<f64>(&weight).map(|p| p.to_ne_bytes().to_vec()),
+            PrimitiveType::F64,
+        ),
+        DataType::I32 => (
+            weight.to_integer().to_i32().map(|p| p.to_ne_bytes().to_vec()),
+            PrimitiveType::I32,
+        ),
+        DataType::I64 => (
+            weight.to_integer().to_i64().map(|p| p.to_ne_bytes().to_vec()),
+            PrimitiveType::I64,
+        ),
+    };
+    match bytes {
+        Some(bytes) => writer.write_all(&bytes),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            ModelCastError { weight, target },
+        )),
+    }
+}
+
 /// Converts a numerical value into a primitive floating point value.
 ///
 /// # Errors
@@ -297,6 +655,19 @@ pub(crate) fn ratio_to_float<F: FloatCore>(ratio: &Ratio<BigInt>) -> Option<F> {
     }
 }
 
+/// Converts the primitive floating point value at the given index into a numerical value.
+///
+/// # Errors
+/// Fails if the primitive value is not finite.
+fn float_to_ratio<F: FloatCore + Display>(
+    (index, f): (usize, F),
+) -> Result<Ratio<BigInt>, ModelError> {
+    Ratio::from_float(f).ok_or_else(|| ModelError::NonFinite {
+        index,
+        value: f.to_string(),
+    })
+}
+
 /// Converts the primitive floating point value into a numerical value.
 ///
 /// Maps positive/negative infinity to max/min of the primitive data type and NaN to zero.
@@ -310,6 +681,42 @@ pub(crate) fn float_to_ratio_bounded<F: FloatCore>(f: F) -> Ratio<BigInt> {
     }
 }
 
+/// Implements the conversions between a [`Model`] and an [`ndarray::Array1`] of primitive
+/// floating point values.
+#[cfg(feature = "ndarray")]
+macro_rules! impl_ndarray_conversions {
+    ($($float:ty),*) => {$(
+        #[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+        impl std::convert::TryFrom<Model> for ndarray::Array1<$float> {
+            type Error = ModelCastError;
+
+            /// Converts the model into an array of primitive values.
+            ///
+            /// # Errors
+            /// Fails if a weight can't be converted into a primitive value.
+            fn try_from(model: Model) -> Result<Self, Self::Error> {
+                model.into_primitives().collect()
+            }
+        }
+
+        #[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+        impl std::convert::TryFrom<ndarray::Array1<$float>> for Model {
+            type Error = ModelError;
+
+            /// Converts the array of primitive values into a model.
+            ///
+            /// # Errors
+            /// Fails if a primitive value is not finite.
+            fn try_from(array: ndarray::Array1<$float>) -> Result<Self, Self::Error> {
+                Model::from_primitives(array.iter().copied())
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "ndarray")]
+impl_ndarray_conversions!(f32, f64);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -403,6 +810,189 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_model_from_primitives_bytes_f32() {
+        let primitives = vec![-1.5_f32, 0_f32, 0.25_f32, 3_f32];
+        let le_bytes: Vec<u8> = primitives.iter().flat_map(|f| f.to_le_bytes()).collect();
+        let be_bytes: Vec<u8> = primitives.iter().flat_map(|f| f.to_be_bytes()).collect();
+        assert_ne!(le_bytes, be_bytes);
+
+        let expected = Model::from_primitives(primitives.into_iter()).unwrap();
+        let le_model =
+            Model::from_primitives_bytes(&le_bytes, DataType::F32, Endianness::Little).unwrap();
+        let be_model =
+            Model::from_primitives_bytes(&be_bytes, DataType::F32, Endianness::Big).unwrap();
+        assert_eq!(le_model, expected);
+        assert_eq!(be_model, expected);
+    }
+
+    #[test]
+    fn test_model_from_primitives_bytes_i64() {
+        let primitives = vec![-1_i64, 0_i64, i64::MAX];
+        let be_bytes: Vec<u8> = primitives.iter().flat_map(|i| i.to_be_bytes()).collect();
+        assert_eq!(
+            Model::from_primitives_bytes(&be_bytes, DataType::I64, Endianness::Big).unwrap(),
+            Model::from_primitives(primitives.into_iter()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_model_to_primitives_bytes() {
+        let primitives = [i64::MIN, -1_i64, 0_i64, i64::MAX];
+        let model = Model::from_primitives(primitives.iter().cloned()).unwrap();
+        let le_bytes = model
+            .to_primitives_bytes(DataType::I64, Endianness::Little)
+            .unwrap();
+        assert_eq!(
+            le_bytes,
+            primitives
+                .iter()
+                .flat_map(|i| i.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        );
+        assert_eq!(
+            Model::from_primitives_bytes(&le_bytes, DataType::I64, Endianness::Little).unwrap(),
+            model,
+        );
+
+        // the weights exceed the range of i32
+        assert!(model
+            .to_primitives_bytes(DataType::I32, Endianness::Big)
+            .is_err());
+    }
+
+    #[test]
+    fn test_model_from_invalid_primitives_bytes() {
+        assert!(matches!(
+            Model::from_primitives_bytes(&[0; 7], DataType::F64, Endianness::Little),
+            Err(ModelError::InvalidLength(7, 8)),
+        ));
+        assert!(matches!(
+            Model::from_primitives_bytes(&f32::NAN.to_be_bytes(), DataType::F32, Endianness::Big),
+            Err(ModelError::NonFinite { index: 0, .. }),
+        ));
+    }
+
+    #[test]
+    fn test_model_from_non_finite_primitives() {
+        let primitives = vec![1_f32, f32::INFINITY, -1_f32, f32::NAN];
+
+        // strict
+        assert!(matches!(
+            Model::from_primitives(primitives.iter().copied()),
+            Err(ModelError::NonFinite { index: 1, ref value }) if value == "inf",
+        ));
+
+        // lossy
+        let (model, replaced) = Model::from_primitives_lossy(primitives.into_iter());
+        assert_eq!(replaced, 2);
+        assert_eq!(
+            model,
+            vec![
+                R::from_float(1_f32).unwrap(),
+                R::zero(),
+                R::from_float(-1_f32).unwrap(),
+                R::zero(),
+            ]
+            .into()
+        );
+
+        let (model, replaced) = Model::from_primitives_lossy(vec![1_i64, 2].into_iter());
+        assert_eq!(replaced, 0);
+        assert_eq!(model.len(), 2);
+    }
+
+    #[test]
+    fn test_model_accessors() {
+        let mut model = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        assert_eq!(model.len(), 3);
+        assert!(!model.is_empty());
+        assert_eq!(
+            model.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                R::from_integer(1.into()),
+                R::from_integer(2.into()),
+                R::from_integer(3.into())
+            ]
+        );
+
+        for weight in model.iter_mut() {
+            *weight *= R::from_integer(2.into());
+        }
+        assert_eq!(
+            model,
+            Model::from_primitives(vec![2_i32, 4, 6].into_iter()).unwrap()
+        );
+
+        let empty = Model::from(Vec::new());
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_model_shard_join() {
+        let model = Model::from_primitives(vec![1_i32, 2, 3, 4, 5, 6, 7].into_iter()).unwrap();
+
+        let shards = model.shard(3);
+        assert_eq!(shards.len(), 3);
+        assert_eq!(
+            shards.iter().map(Model::len).collect::<Vec<_>>(),
+            vec![3, 3, 1]
+        );
+        assert_eq!(shards[1][0], model[3]);
+        assert_eq!(Model::join(shards), model);
+
+        let shards = model.shard(10);
+        assert_eq!(shards.len(), 1);
+        assert_eq!(Model::join(shards), model);
+    }
+
+    #[test]
+    fn test_model_delta() {
+        let base = Model::from_primitives(vec![1_f32, 2., 3., 4.].into_iter()).unwrap();
+        let model = Model::from_primitives(vec![1_f32, 2.5, 3., -4.].into_iter()).unwrap();
+
+        let delta = ModelDelta::new(1, &base, 2, &model).unwrap();
+        assert_eq!(delta.base_round_id, 1);
+        assert_eq!(delta.round_id, 2);
+        assert_eq!(delta.model_length, 4);
+        assert_eq!(
+            delta.changes,
+            vec![
+                (1, R::from_float(0.5_f32).unwrap()),
+                (3, R::from_integer(BigInt::from(-8))),
+            ]
+        );
+        assert_eq!(delta.apply(&base).unwrap(), model);
+
+        let unchanged = ModelDelta::new(2, &model, 3, &model).unwrap();
+        assert!(unchanged.changes.is_empty());
+        assert_eq!(unchanged.apply(&model).unwrap(), model);
+    }
+
+    #[test]
+    fn test_model_delta_wrong_base() {
+        let base = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        let model = Model::from_primitives(vec![1_i32, 5, 3].into_iter()).unwrap();
+        let delta = ModelDelta::new(1, &base, 2, &model).unwrap();
+
+        let other = Model::from_primitives(vec![0_i32, 2, 3].into_iter()).unwrap();
+        assert!(matches!(
+            delta.apply(&other),
+            Err(ModelDeltaError::ChecksumMismatch)
+        ));
+        let shorter = Model::from_primitives(vec![1_i32, 2].into_iter()).unwrap();
+        assert!(matches!(
+            delta.apply(&shorter),
+            Err(ModelDeltaError::LengthMismatch(2, 3))
+        ));
+        assert!(matches!(
+            ModelDelta::new(1, &shorter, 2, &model),
+            Err(ModelDeltaError::LengthMismatch(2, 3))
+        ));
+    }
+
     #[test]
     fn test_model_i32() {
         let expected_primitives = vec![-1_i32, 0_i32, 1_i32];
@@ -462,4 +1052,45 @@ mod tests {
         let ratio = &f64_max * BigInt::from(10_usize) / (f64_max * BigInt::from(100_usize));
         assert_eq!(ratio_to_float::<f64>(&ratio).unwrap(), 0.1_f64);
     }
+
+    #[test]
+    fn test_model_checksum() {
+        let model = Model::from_primitives(vec![-1_f32, 0.5, 1_f32].into_iter()).unwrap();
+        assert_eq!(model.checksum(), model.clone().checksum());
+
+        // any change of a weight or of the order of the weights changes the checksum
+        let changed = Model::from_primitives(vec![-1_f32, 0.25, 1_f32].into_iter()).unwrap();
+        assert_ne!(model.checksum(), changed.checksum());
+        let reordered = Model::from_primitives(vec![0.5, -1_f32, 1_f32].into_iter()).unwrap();
+        assert_ne!(model.checksum(), reordered.checksum());
+
+        // the weights are not just concatenated
+        let split = Model::from(vec![R::new(BigInt::from(1), BigInt::from(258))]);
+        let joined = Model::from(vec![R::new(BigInt::from(258), BigInt::from(1))]);
+        assert_ne!(split.checksum(), joined.checksum());
+        assert_ne!(Model::from(vec![]).checksum(), split.checksum());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_model_ndarray() {
+        use std::convert::TryFrom;
+
+        let array = ndarray::arr1(&[-1_f32, 0.5, 1_f32]);
+        let model = Model::try_from(array.clone()).unwrap();
+        assert_eq!(model.len(), 3);
+        assert_eq!(ndarray::Array1::<f32>::try_from(model).unwrap(), array);
+
+        let array = ndarray::arr1(&[-1_f64, 0.5, 1_f64]);
+        let model = Model::try_from(array.clone()).unwrap();
+        assert_eq!(ndarray::Array1::<f64>::try_from(model).unwrap(), array);
+
+        // non-finite values are rejected
+        assert!(Model::try_from(ndarray::arr1(&[0_f32, f32::NAN])).is_err());
+        assert!(Model::try_from(ndarray::arr1(&[f64::INFINITY])).is_err());
+
+        // weights beyond the primitive range are rejected
+        let model = Model::from_primitives(iter::once(f64::MAX)).unwrap();
+        assert!(ndarray::Array1::<f32>::try_from(model).is_err());
+    }
 }
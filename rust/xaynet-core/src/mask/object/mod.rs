@@ -12,7 +12,10 @@ use num::bigint::BigUint;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::mask::config::{MaskConfig, MaskConfigPair};
+use crate::mask::{
+    config::{MaskConfig, MaskConfigPair, MaskSection, ModelConfig},
+    seed::MaskSeed,
+};
 
 #[derive(Error, Debug)]
 #[error("the mask object is invalid: data is incompatible with the masking configuration")]
@@ -126,6 +129,10 @@ impl MaskObject {
     }
 
     /// Creates a new mask object from the given vector, unit and masking configurations.
+    ///
+    /// # Errors
+    /// Fails if any element of the mask object doesn't conform to the given masking
+    /// configurations, i.e. if it is not smaller than the group order.
     pub fn new(
         config: MaskConfigPair,
         data_vect: Vec<BigUint>,
@@ -144,8 +151,167 @@ impl MaskObject {
         }
     }
 
+    /// Creates the mask of given length which is derived from the `seed` wrt the masking
+    /// configurations.
+    ///
+    /// This is exactly the mask which a model masked with this seed is unmasked with, which is
+    /// the same as [`MaskSeed::derive_mask()`]. It allows auditors to replay the masking of a round
+    /// from the revealed seeds, see the [verification recipe] in the mask module.
+    ///
+    /// [verification recipe]: crate::mask#verification
+    pub fn from_seed_and_config(seed: &MaskSeed, len: usize, config: MaskConfigPair) -> Self {
+        seed.derive_mask(len, config)
+    }
+
     /// Checks if this mask object conforms to the masking configurations.
     pub fn is_valid(&self) -> bool {
         self.vect.is_valid() && self.unit.is_valid()
     }
 }
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// A mask object whose vector part consists of consecutive sections with their own masking
+/// configurations.
+///
+/// This represents a model masked wrt a [`ModelConfig`] or its corresponding mask. A
+/// [`MaskObject`] corresponds to a single section.
+pub struct MaskSections {
+    pub vects: Vec<MaskVect>,
+    pub unit: MaskUnit,
+}
+
+impl From<MaskObject> for MaskSections {
+    fn from(object: MaskObject) -> Self {
+        Self {
+            vects: vec![object.vect],
+            unit: object.unit,
+        }
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl MaskSections {
+    /// Creates a new sectioned mask object from the given vectors and unit.
+    pub fn new_unchecked(vects: Vec<MaskVect>, unit: MaskUnit) -> Self {
+        Self { vects, unit }
+    }
+
+    /// Creates a new sectioned mask object from the given vectors and unit.
+    ///
+    /// # Errors
+    /// Fails if any element of the sectioned mask object doesn't conform to its masking
+    /// configuration.
+    pub fn new(vects: Vec<MaskVect>, unit: MaskUnit) -> Result<Self, InvalidMaskObjectError> {
+        let obj = Self::new_unchecked(vects, unit);
+        if obj.is_valid() {
+            Ok(obj)
+        } else {
+            Err(InvalidMaskObjectError)
+        }
+    }
+
+    /// Creates a new empty sectioned mask object of given model and unit masking configurations.
+    pub fn empty(config: &ModelConfig, unit: MaskConfig) -> Self {
+        Self {
+            vects: config
+                .sections()
+                .iter()
+                .map(|section| MaskVect::empty(section.config, section.len))
+                .collect(),
+            unit: MaskUnit::default(unit),
+        }
+    }
+
+    /// Gets the configuration of the sections of this mask object.
+    pub fn config(&self) -> ModelConfig {
+        self.vects
+            .iter()
+            .map(|vect| MaskSection {
+                len: vect.data.len(),
+                config: vect.config,
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Gets the total length of the vector part of this mask object.
+    pub fn len(&self) -> usize {
+        self.vects.iter().map(|vect| vect.data.len()).sum()
+    }
+
+    /// Checks if this mask object conforms to the masking configurations.
+    pub fn is_valid(&self) -> bool {
+        self.vects.iter().all(MaskVect::is_valid) && self.unit.is_valid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mask::config::{BoundType, DataType, GroupType, ModelType};
+
+    fn mask_config() -> MaskConfig {
+        MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        }
+    }
+
+    #[test]
+    fn test_new_mask_object() {
+        let config = mask_config();
+        let data = vec![BigUint::from(0_u8), config.order() - 1_u8];
+        let obj = MaskObject::new(config.into(), data, BigUint::from(1_u8)).unwrap();
+        assert!(obj.is_valid());
+    }
+
+    #[test]
+    fn test_new_mask_object_invalid_vect() {
+        let config = mask_config();
+        let data = vec![BigUint::from(0_u8), config.order(), BigUint::from(1_u8)];
+        assert!(MaskVect::new(config, data.clone()).is_err());
+        assert!(MaskObject::new(config.into(), data, BigUint::from(1_u8)).is_err());
+    }
+
+    #[test]
+    fn test_new_mask_object_invalid_unit() {
+        let config = mask_config();
+        assert!(MaskUnit::new(config, config.order()).is_err());
+        assert!(MaskObject::new(config.into(), vec![], config.order()).is_err());
+    }
+
+    #[test]
+    fn test_new_mask_sections() {
+        let config = mask_config();
+        let wide_config = MaskConfig {
+            bound_type: BoundType::B6,
+            ..config
+        };
+        let vects = vec![
+            MaskVect::new(config, vec![config.order() - 1_u8]).unwrap(),
+            MaskVect::new(wide_config, vec![BigUint::from(0_u8); 2]).unwrap(),
+        ];
+        let unit = MaskUnit::default(config);
+        let obj = MaskSections::new(vects.clone(), unit.clone()).unwrap();
+        assert_eq!(obj.len(), 3);
+        assert_eq!(
+            obj.config(),
+            vec![
+                MaskSection { len: 1, config },
+                MaskSection {
+                    len: 2,
+                    config: wide_config,
+                },
+            ]
+            .into(),
+        );
+
+        // the order of the first section is too small for the elements of the second one
+        let mut invalid = vects;
+        invalid[0].data.push(wide_config.order() - 1_u8);
+        assert!(MaskSections::new(invalid, unit).is_err());
+    }
+}
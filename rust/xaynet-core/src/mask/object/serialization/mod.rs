@@ -4,17 +4,21 @@
 //!
 //! [mask module]: crate::mask
 
+pub(crate) mod sections;
 pub(crate) mod unit;
 pub(crate) mod vect;
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 
 use crate::{
-    mask::object::{
-        serialization::{unit::MaskUnitBuffer, vect::MaskVectBuffer},
-        MaskObject,
-        MaskUnit,
-        MaskVect,
+    mask::{
+        config::MaskConfigPair,
+        object::{
+            serialization::{unit::MaskUnitBuffer, vect::MaskVectBuffer},
+            MaskObject,
+            MaskUnit,
+            MaskVect,
+        },
     },
     message::{
         traits::{FromBytes, ToBytes},
@@ -22,10 +26,6 @@ use crate::{
     },
 };
 
-// target dependent maximum number of mask object elements
-#[cfg(target_pointer_width = "16")]
-const MAX_NB: u32 = u16::MAX as u32;
-
 /// A buffer for serialized mask objects.
 pub struct MaskObjectBuffer<T> {
     inner: T,
@@ -116,6 +116,41 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskObjectBuffer<T> {
     }
 }
 
+impl MaskObject {
+    /// Gets the length of a serialized mask object with the given masking configurations and
+    /// number of elements.
+    pub fn serialized_length(config: MaskConfigPair, numbers: usize) -> usize {
+        MaskVect::serialized_length(config.vect, numbers) + MaskUnit::serialized_length(config.unit)
+    }
+
+    /// Deserializes a mask object from a buffer, which is expected to hold the given number of
+    /// elements.
+    ///
+    /// Unlike [`from_byte_slice()`], this catches a buffer which is consistent in itself but
+    /// holds fewer or more elements than expected, e.g. a truncated model, right at parse time
+    /// instead of at aggregation time.
+    ///
+    /// # Errors
+    /// Fails if the buffer is invalid or if the number of elements differs from `numbers`.
+    ///
+    /// [`from_byte_slice()`]: FromBytes::from_byte_slice
+    pub fn from_byte_slice_with_numbers<T: AsRef<[u8]>>(
+        buffer: &T,
+        numbers: usize,
+    ) -> Result<Self, DecodeError> {
+        let reader = MaskObjectBuffer::new(buffer.as_ref())?;
+        let actual = MaskVectBuffer::new_unchecked(reader.vect()).numbers();
+        if actual != numbers {
+            return Err(anyhow!(
+                "invalid number of mask object elements: expected {} but got {}",
+                numbers,
+                actual
+            ));
+        }
+        Self::from_byte_slice(buffer)
+    }
+}
+
 impl ToBytes for MaskObject {
     fn buffer_length(&self) -> usize {
         self.vect.buffer_length() + self.unit.buffer_length()
@@ -190,6 +225,28 @@ pub(crate) mod tests {
         assert_eq!(MaskObject::from_byte_slice(&&bytes[..]).unwrap(), expected);
     }
 
+    #[test]
+    fn deserialize_mask_object_with_numbers() {
+        let (expected, bytes) = mask_object();
+        assert_eq!(
+            MaskObject::from_byte_slice_with_numbers(&bytes, 4).unwrap(),
+            expected
+        );
+
+        // a truncated buffer is invalid in itself
+        assert!(MaskObject::from_byte_slice_with_numbers(&&bytes[..bytes.len() - 6], 4).is_err());
+
+        // a mask object truncated to fewer elements is valid, but not of the expected length
+        let truncated = MaskObject::new_unchecked(
+            MaskVect::new_unchecked(expected.vect.config, expected.vect.data[..3].to_vec()),
+            expected.unit,
+        );
+        let mut bytes = vec![0; truncated.buffer_length()];
+        truncated.to_bytes(&mut bytes);
+        assert_eq!(MaskObject::from_byte_slice(&bytes).unwrap(), truncated);
+        assert!(MaskObject::from_byte_slice_with_numbers(&bytes, 4).is_err());
+    }
+
     #[test]
     fn deserialize_mask_object_from_stream() {
         let (expected, bytes) = mask_object();
@@ -0,0 +1,149 @@
+//! Serialization of sectioned mask objects.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: crate::mask
+
+use anyhow::{anyhow, Context};
+
+use crate::{
+    mask::object::{MaskSections, MaskUnit, MaskVect},
+    message::{
+        traits::{FromBytes, ToBytes},
+        utils::wire::{self, LEN_BYTES},
+        DecodeError,
+    },
+};
+
+/// The version of the encoding of sectioned mask objects.
+///
+/// The encoding of single-section [`MaskObject`]s is unversioned and counts as the first version.
+///
+/// [`MaskObject`]: crate::mask::MaskObject
+pub const MASK_SECTIONS_VERSION: u8 = 2;
+
+/// The length of the header of a serialized sectioned mask object: the version and the number of
+/// sections.
+const HEADER_LEN: usize = 1 + LEN_BYTES;
+
+impl ToBytes for MaskSections {
+    fn buffer_length(&self) -> usize {
+        HEADER_LEN
+            + self
+                .vects
+                .iter()
+                .map(|vect| vect.buffer_length())
+                .sum::<usize>()
+            + self.unit.buffer_length()
+    }
+
+    fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
+        let buffer = buffer.as_mut();
+        buffer[0] = MASK_SECTIONS_VERSION;
+        wire::write_len(&mut buffer[1..HEADER_LEN], self.vects.len())
+            .expect("number of sections exceeds the length field");
+        let mut offset = HEADER_LEN;
+        for vect in self.vects.iter() {
+            let len = vect.buffer_length();
+            vect.to_bytes(&mut &mut buffer[offset..offset + len]);
+            offset += len;
+        }
+        self.unit.to_bytes(&mut &mut buffer[offset..]);
+    }
+}
+
+impl FromBytes for MaskSections {
+    fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
+        Self::from_byte_stream(&mut buffer.as_ref().iter().copied())
+    }
+
+    fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
+        iter: &mut I,
+    ) -> Result<Self, DecodeError> {
+        if iter.len() < HEADER_LEN {
+            return Err(anyhow!("byte stream exhausted"));
+        }
+        // safe unwrap: the length has just been checked
+        let version = iter.next().unwrap();
+        if version != MASK_SECTIONS_VERSION {
+            return Err(anyhow!(
+                "unsupported sectioned mask object version: {}",
+                version
+            ));
+        }
+        let nb_sections = wire::read_len_from_stream(iter)
+            .context("failed to parse the number of sections in mask object")?;
+        let vects = (0..nb_sections)
+            .map(|i| {
+                MaskVect::from_byte_stream(iter).with_context(|| format!("invalid section {}", i))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let unit = MaskUnit::from_byte_stream(iter).context("invalid unit part")?;
+        Ok(Self { vects, unit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::{
+        config::{BoundType, MaskConfig},
+        object::serialization::{unit::tests::mask_unit, vect::tests::mask_vect},
+    };
+
+    fn mask_sections() -> (MaskSections, Vec<u8>) {
+        let (mask_vect, mask_vect_bytes) = mask_vect();
+        let (mask_unit, mask_unit_bytes) = mask_unit();
+        let config = MaskConfig {
+            bound_type: BoundType::B6,
+            ..mask_vect.config
+        };
+        let wide_vect = MaskVect::new_unchecked(config, mask_vect.data.clone());
+        let mut wide_vect_bytes = vec![0; wide_vect.buffer_length()];
+        wide_vect.to_bytes(&mut wide_vect_bytes);
+
+        let obj = MaskSections::new_unchecked(vec![mask_vect, wide_vect], mask_unit);
+        let bytes = [
+            &[MASK_SECTIONS_VERSION, 0x00, 0x00, 0x00, 0x02],
+            mask_vect_bytes.as_slice(),
+            wide_vect_bytes.as_slice(),
+            mask_unit_bytes.as_slice(),
+        ]
+        .concat();
+        (obj, bytes)
+    }
+
+    #[test]
+    fn serialize_mask_sections() {
+        let (mask_sections, expected) = mask_sections();
+        assert_eq!(mask_sections.buffer_length(), expected.len());
+        let mut buf = vec![0xff; expected.len()];
+        mask_sections.to_bytes(&mut buf);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn deserialize_mask_sections() {
+        let (expected, bytes) = mask_sections();
+        assert_eq!(
+            MaskSections::from_byte_slice(&bytes.as_slice()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            MaskSections::from_byte_stream(&mut bytes.into_iter()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn deserialize_invalid_mask_sections() {
+        let (_, mut bytes) = mask_sections();
+        // the unversioned encoding of single-section mask objects is rejected
+        bytes[0] = 1;
+        assert!(MaskSections::from_byte_slice(&bytes.as_slice()).is_err());
+        bytes[0] = MASK_SECTIONS_VERSION;
+        // a truncated section
+        bytes.truncate(bytes.len() - 1);
+        assert!(MaskSections::from_byte_slice(&bytes.as_slice()).is_err());
+    }
+}
@@ -129,9 +129,16 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskUnitBuffer<T> {
     }
 }
 
+impl MaskUnit {
+    /// Gets the length of a serialized mask unit with the given masking configuration.
+    pub fn serialized_length(config: MaskConfig) -> usize {
+        MASK_CONFIG_FIELD.end + config.bytes_per_number()
+    }
+}
+
 impl ToBytes for MaskUnit {
     fn buffer_length(&self) -> usize {
-        MASK_CONFIG_FIELD.end + self.config.bytes_per_number()
+        Self::serialized_length(self.config)
     }
 
     fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
@@ -163,7 +170,7 @@ impl FromBytes for MaskUnit {
         let config = MaskConfig::from_byte_slice(&reader.config())?;
         let data = BigUint::from_bytes_le(reader.data());
 
-        Ok(MaskUnit { data, config })
+        Ok(MaskUnit::new(config, data)?)
     }
 
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
@@ -188,7 +195,7 @@ impl FromBytes for MaskUnit {
         }
         let data = BigUint::from_bytes_le(buf.as_slice());
 
-        Ok(MaskUnit { data, config })
+        Ok(MaskUnit::new(config, data)?)
     }
 }
 
@@ -231,4 +238,14 @@ pub(crate) mod tests {
             expected
         );
     }
+
+    #[test]
+    fn deserialize_invalid_mask_unit() {
+        let (_, mut bytes) = mask_unit();
+        // the data exceeds the group order of the config
+        let len = bytes.len();
+        bytes[len - 6..].copy_from_slice(&[0xff; 6]);
+        assert!(MaskUnit::from_byte_slice(&&bytes[..]).is_err());
+        assert!(MaskUnit::from_byte_stream(&mut bytes.into_iter()).is_err());
+    }
 }
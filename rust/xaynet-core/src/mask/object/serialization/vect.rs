@@ -4,7 +4,7 @@
 //!
 //! [mask module]: crate::mask
 
-use std::{convert::TryInto, ops::Range};
+use std::ops::Range;
 
 use anyhow::{anyhow, Context};
 use num::bigint::BigUint;
@@ -16,17 +16,17 @@ use crate::{
     },
     message::{
         traits::{FromBytes, ToBytes},
-        utils::{range, ChunkableIterator},
+        utils::{
+            range,
+            wire::{self, LEN_BYTES},
+            ChunkableIterator,
+        },
         DecodeError,
     },
 };
 
 const MASK_CONFIG_FIELD: Range<usize> = range(0, MASK_CONFIG_BUFFER_LEN);
-const NUMBERS_FIELD: Range<usize> = range(MASK_CONFIG_FIELD.end, 4);
-
-// target dependent maximum number of mask object elements
-#[cfg(target_pointer_width = "16")]
-const MAX_NB: u32 = u16::MAX as u32;
+const NUMBERS_FIELD: Range<usize> = range(MASK_CONFIG_FIELD.end, LEN_BYTES);
 
 /// A buffer for serialized mask vectors.
 pub struct MaskVectBuffer<T> {
@@ -86,13 +86,11 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
         let config =
             MaskConfig::from_byte_slice(&self.config()).context("invalid mask vector buffer")?;
         let bytes_per_number = config.bytes_per_number();
-        let (data_length, overflows) = self.numbers().overflowing_mul(bytes_per_number);
-        if overflows {
-            return Err(anyhow!(
-                "invalid MaskObject buffer: invalid masking config or numbers field"
-            ));
-        }
-        Ok(NUMBERS_FIELD.end + data_length)
+        let numbers = wire::read_len(&self.inner.as_ref()[NUMBERS_FIELD])?;
+        let data_length = numbers.checked_mul(bytes_per_number).ok_or_else(|| {
+            anyhow!("invalid MaskObject buffer: invalid masking config or numbers field")
+        })?;
+        Ok(wire::checked_range(NUMBERS_FIELD.end, data_length)?.end)
     }
 
     /// Gets the expected number of bytes of this buffer wrt to the masking configuration.
@@ -113,16 +111,7 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
     ///
     /// Panics if the number can't be represented as usize on targets smaller than 32 bits.
     pub fn numbers(&self) -> usize {
-        // UNWRAP SAFE: the slice is exactly 4 bytes long
-        let nb = u32::from_be_bytes(self.inner.as_ref()[NUMBERS_FIELD].try_into().unwrap());
-
-        // smaller targets than 32 bits are currently not of interest
-        #[cfg(target_pointer_width = "16")]
-        if nb > MAX_NB {
-            panic!("16 bit targets or smaller are currently not fully supported")
-        }
-
-        nb as usize
+        wire::read_len(&self.inner.as_ref()[NUMBERS_FIELD]).unwrap()
     }
 
     /// Gets the serialized masking configuration.
@@ -146,9 +135,11 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskVectBuffer<T> {
     /// Sets the number of serialized mask vector elements.
     ///
     /// # Panics
-    /// May panic if this buffer is unchecked.
-    pub fn set_numbers(&mut self, value: u32) {
-        self.inner.as_mut()[NUMBERS_FIELD].copy_from_slice(&value.to_be_bytes());
+    /// May panic if this buffer is unchecked. Panics if the number doesn't fit into the numbers
+    /// field.
+    pub fn set_numbers(&mut self, value: usize) {
+        wire::write_len(&mut self.inner.as_mut()[NUMBERS_FIELD], value)
+            .expect("number of mask vector elements exceeds the numbers field");
     }
 
     /// Gets the serialized masking configuration.
@@ -169,15 +160,23 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskVectBuffer<T> {
     }
 }
 
+impl MaskVect {
+    /// Gets the length of a serialized mask vector with the given masking configuration and
+    /// number of elements.
+    pub fn serialized_length(config: MaskConfig, numbers: usize) -> usize {
+        NUMBERS_FIELD.end + config.bytes_per_number() * numbers
+    }
+}
+
 impl ToBytes for MaskVect {
     fn buffer_length(&self) -> usize {
-        NUMBERS_FIELD.end + self.config.bytes_per_number() * self.data.len()
+        Self::serialized_length(self.config, self.data.len())
     }
 
     fn to_bytes<T: AsMut<[u8]>>(&self, buffer: &mut T) {
         let mut writer = MaskVectBuffer::new_unchecked(buffer.as_mut());
         self.config.to_bytes(&mut writer.config_mut());
-        writer.set_numbers(self.data.len() as u32);
+        writer.set_numbers(self.data.len());
 
         let mut data = writer.data_mut();
         let bytes_per_number = self.config.bytes_per_number();
@@ -210,21 +209,20 @@ impl FromBytes for MaskVect {
             data.push(BigUint::from_bytes_le(chunk));
         }
 
-        Ok(MaskVect { data, config })
+        Ok(MaskVect::new(config, data)?)
     }
 
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
         iter: &mut I,
     ) -> Result<Self, DecodeError> {
         let config = MaskConfig::from_byte_stream(iter)?;
-        if iter.len() < 4 {
-            return Err(anyhow!("byte stream exhausted"));
-        }
-        let numbers = u32::from_byte_stream(iter)
+        let numbers = wire::read_len_from_stream(iter)
             .context("failed to parse the number of items in mask vector")?;
         let bytes_per_number = config.bytes_per_number();
 
-        let data_len = numbers as usize * bytes_per_number;
+        let data_len = numbers
+            .checked_mul(bytes_per_number)
+            .ok_or_else(|| anyhow!("invalid mask vector: too many items"))?;
         if iter.len() < data_len {
             return Err(anyhow!(
                 "mask vector is {} bytes long but byte stream only has {} bytes",
@@ -233,7 +231,7 @@ impl FromBytes for MaskVect {
             ));
         }
 
-        let mut data = Vec::with_capacity(numbers as usize);
+        let mut data = Vec::with_capacity(numbers);
         let mut buf = vec![0; bytes_per_number];
         for chunk in iter.take(data_len).chunks(bytes_per_number).into_iter() {
             for (i, b) in chunk.enumerate() {
@@ -242,7 +240,7 @@ impl FromBytes for MaskVect {
             data.push(BigUint::from_bytes_le(buf.as_slice()));
         }
 
-        Ok(MaskVect { data, config })
+        Ok(MaskVect::new(config, data)?)
     }
 }
 
@@ -296,4 +294,50 @@ pub(crate) mod tests {
             expected
         );
     }
+
+    #[test]
+    fn mask_vect_length_from_element_len() {
+        use crate::mask::config::{
+            BoundType::{Bmax, B0, B6},
+            DataType::{F32, F64, I64},
+            GroupType::{Integer, Power2, Prime},
+            ModelType::{M12, M3},
+        };
+
+        let configs = [
+            (Integer, F32, B0, M3),
+            (Prime, F64, B6, M12),
+            (Power2, I64, Bmax, M3),
+            (Prime, F32, Bmax, M12),
+        ];
+        for &(group_type, data_type, bound_type, model_type) in configs.iter() {
+            let config = MaskConfig {
+                group_type,
+                data_type,
+                bound_type,
+                model_type,
+            };
+            for &model_len in [0, 1, 10].iter() {
+                let mask_vect =
+                    MaskVect::new_unchecked(config, vec![BigUint::from(1_u8); model_len]);
+                let mut buf = vec![0; mask_vect.buffer_length()];
+                mask_vect.to_bytes(&mut buf);
+                assert_eq!(
+                    buf.len(),
+                    MASK_CONFIG_BUFFER_LEN + 4 + config.element_len() * model_len
+                );
+                assert_eq!(MaskVect::from_byte_slice(&&buf[..]).unwrap(), mask_vect);
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_invalid_mask_vect() {
+        let (_, mut bytes) = mask_vect();
+        // the last element exceeds the group order of the config
+        let len = bytes.len();
+        bytes[len - 6..].copy_from_slice(&[0xff; 6]);
+        assert!(MaskVect::from_byte_slice(&&bytes[..]).is_err());
+        assert!(MaskVect::from_byte_stream(&mut bytes.into_iter()).is_err());
+    }
 }
@@ -0,0 +1,282 @@
+//! Flat storage of residues for the aggregation of masked models.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: crate::mask
+
+use std::cmp::Ordering;
+
+use num::bigint::BigUint;
+
+use crate::mask::{config::MaskConfig, object::MaskVect};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A vector of residues wrt the group order of a masking configuration.
+///
+/// The residues are stored back to back as little-endian numbers of a fixed width, which is the
+/// number of bytes per number of the masking configuration. Compared to a vector of [`BigUint`]s,
+/// this avoids a heap allocation and the spare capacity per residue.
+pub(crate) struct Residues {
+    config: MaskConfig,
+    /// The number of bytes per residue.
+    width: usize,
+    /// The group order as little-endian number of `width + 1` bytes.
+    order: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Residues {
+    /// Creates an empty vector of residues with reserved memory for `len` residues.
+    pub(crate) fn with_capacity(config: MaskConfig, len: usize) -> Self {
+        let width = config.bytes_per_number();
+        let mut order = config.order().to_bytes_le();
+        order.resize(width + 1, 0);
+        Self {
+            config,
+            width,
+            order,
+            data: Vec::with_capacity(len * width),
+        }
+    }
+
+    /// Gets the masking configuration of the residues.
+    pub(crate) fn config(&self) -> MaskConfig {
+        self.config
+    }
+
+    /// Gets the number of residues.
+    pub(crate) fn len(&self) -> usize {
+        self.data.len() / self.width
+    }
+
+    /// Gets the residue at the given `index`, or `None` if the index is out of bounds.
+    pub(crate) fn get(&self, index: usize) -> Option<BigUint> {
+        self.data
+            .chunks_exact(self.width)
+            .nth(index)
+            .map(BigUint::from_bytes_le)
+    }
+
+    /// Gets the residues as little-endian numbers of a fixed width, which is their serialized
+    /// form in a mask vector.
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Removes all residues, but keeps the reserved memory.
+    pub(crate) fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Replaces the residues with `len` zeros.
+    pub(crate) fn assign_zeros(&mut self, len: usize) {
+        self.data.clear();
+        self.data.resize(len * self.width, 0);
+    }
+
+    /// Replaces the residues with the numbers of the given mask `vect`.
+    ///
+    /// # Panics
+    /// Panics if a number doesn't fit into the width of the residues, which can't happen for a
+    /// valid mask vector.
+    pub(crate) fn assign(&mut self, vect: &MaskVect) {
+        let width = self.width;
+        self.data.clear();
+        self.data.resize(vect.data.len() * width, 0);
+        for (residue, number) in self.data.chunks_exact_mut(width).zip(vect.data.iter()) {
+            let bytes = number.to_bytes_le();
+            residue[..bytes.len()].copy_from_slice(&bytes);
+        }
+    }
+
+    /// Adds the numbers of the given mask `vect` to the residues wrt the group order.
+    ///
+    /// The addition happens in place. Both summands must be smaller than the group order, then a
+    /// single conditional subtraction of the order suffices for the reduction.
+    ///
+    /// # Panics
+    /// Panics if a number doesn't fit into the width of the residues, which can't happen for a
+    /// valid mask vector.
+    pub(crate) fn add_assign(&mut self, vect: &MaskVect) {
+        for (index, number) in vect.data.iter().enumerate() {
+            self.add_assign_at(index, number);
+        }
+    }
+
+    /// Adds the `number` to the residue at the given `index` wrt the group order.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds or if the number doesn't fit into the width of the
+    /// residues, which can't happen for a number smaller than the group order.
+    pub(crate) fn add_assign_at(&mut self, index: usize, number: &BigUint) {
+        let width = self.width;
+        let residue = &mut self.data[index * width..(index + 1) * width];
+        let bytes = number.to_bytes_le();
+        assert!(
+            bytes.len() <= residue.len(),
+            "the number exceeds the residue width"
+        );
+        let carry = add_le(residue, &bytes);
+        if compare_le(residue, carry, &self.order) != Ordering::Less {
+            sub_le(residue, &self.order);
+        }
+    }
+}
+
+impl From<&Residues> for MaskVect {
+    fn from(residues: &Residues) -> Self {
+        let data = residues
+            .data
+            .chunks_exact(residues.width)
+            .map(BigUint::from_bytes_le)
+            .collect();
+        MaskVect::new_unchecked(residues.config, data)
+    }
+}
+
+/// Adds the little-endian `summand` to the little-endian `number` in place and returns the carry.
+fn add_le(number: &mut [u8], summand: &[u8]) -> u8 {
+    let mut carry = 0_u16;
+    for (i, byte) in number.iter_mut().enumerate() {
+        let sum = *byte as u16 + *summand.get(i).unwrap_or(&0) as u16 + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+    carry as u8
+}
+
+/// Compares the little-endian `number` extended by the `carry` byte with the little-endian `order`
+/// of one more byte.
+fn compare_le(number: &[u8], carry: u8, order: &[u8]) -> Ordering {
+    let (order, order_top) = order.split_at(number.len());
+    carry.cmp(&order_top[0]).then_with(|| {
+        number
+            .iter()
+            .rev()
+            .zip(order.iter().rev())
+            .map(|(n, o)| n.cmp(o))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+/// Subtracts the little-endian `order` from the little-endian `number` in place.
+///
+/// The most significant byte of the `order` is dropped together with the carry of the number.
+fn sub_le(number: &mut [u8], order: &[u8]) {
+    let mut borrow = 0_i16;
+    for (byte, o) in number.iter_mut().zip(order.iter()) {
+        let diff = *byte as i16 - *o as i16 - borrow;
+        *byte = diff as u8;
+        borrow = (diff < 0) as i16;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::traits::{One, Zero};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+    use crate::{
+        crypto::prng::generate_integer,
+        mask::config::{
+            BoundType::{Bmax, B0, B6},
+            DataType::{F32, F64, I64},
+            GroupType::{Integer, Power2, Prime},
+            ModelType::{M12, M3},
+        },
+    };
+
+    /// Aggregates the vectors with the per element [`BigUint`] arithmetic as a reference.
+    fn aggregate_biguint(config: MaskConfig, vects: &[MaskVect]) -> MaskVect {
+        let order = config.order();
+        let mut aggregated = vects[0].clone();
+        for vect in &vects[1..] {
+            for (i, j) in aggregated.data.iter_mut().zip(vect.data.iter()) {
+                *i = (&*i + j) % &order;
+            }
+        }
+        aggregated
+    }
+
+    /// Aggregates the vectors with the flat residues.
+    fn aggregate_residues(config: MaskConfig, vects: &[MaskVect]) -> MaskVect {
+        let mut residues = Residues::with_capacity(config, vects[0].data.len());
+        residues.assign(&vects[0]);
+        for vect in &vects[1..] {
+            residues.add_assign(vect);
+        }
+        MaskVect::from(&residues)
+    }
+
+    fn random_vect(config: MaskConfig, len: usize, prng: &mut ChaCha20Rng) -> MaskVect {
+        let order = config.order();
+        let data = (0..len).map(|_| generate_integer(prng, &order)).collect();
+        MaskVect::new_unchecked(config, data)
+    }
+
+    fn check_equal_aggregation(config: MaskConfig) {
+        let mut prng = ChaCha20Rng::from_seed([0_u8; 32]);
+        let vects = (0..10)
+            .map(|_| random_vect(config, 100, &mut prng))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            aggregate_residues(config, &vects),
+            aggregate_biguint(config, &vects),
+        );
+    }
+
+    #[test]
+    fn test_aggregation_equals_biguint() {
+        for &group_type in &[Integer, Prime, Power2] {
+            for &(data_type, bound_type) in &[(F32, B0), (F64, B6), (I64, Bmax)] {
+                for &model_type in &[M3, M12] {
+                    check_equal_aggregation(MaskConfig {
+                        group_type,
+                        data_type,
+                        bound_type,
+                        model_type,
+                    });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_aggregation_equals_biguint_at_bounds() {
+        let config = MaskConfig {
+            group_type: Power2,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let max = config.order() - BigUint::one();
+        let vects = vec![
+            MaskVect::new_unchecked(config, vec![max.clone(), max.clone(), BigUint::zero()]),
+            MaskVect::new_unchecked(config, vec![max.clone(), BigUint::one(), BigUint::zero()]),
+            MaskVect::new_unchecked(config, vec![BigUint::one(), max, BigUint::zero()]),
+        ];
+        assert_eq!(
+            aggregate_residues(config, &vects),
+            aggregate_biguint(config, &vects),
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+        };
+        let vect = random_vect(config, 10, &mut ChaCha20Rng::from_seed([1_u8; 32]));
+        let mut residues = Residues::with_capacity(config, 10);
+        residues.assign(&vect);
+        assert_eq!(residues.len(), 10);
+        assert_eq!(residues.data.len(), 10 * config.bytes_per_number());
+        assert_eq!(MaskVect::from(&residues), vect);
+    }
+}
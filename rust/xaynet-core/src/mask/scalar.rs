@@ -28,6 +28,19 @@ use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Hash, From, Into, Serialize, Deserialize)]
 /// A numerical representation of a machine learning scalar.
+///
+/// The scalar is an exact non-negative rational number, hence scalars like `1/3` don't suffer
+/// from binary rounding and two scalars are equal if and only if their reduced fractions are
+/// equal. Prefer [`Scalar::new()`] or [`Scalar::checked_new()`] over the conversions from floats
+/// via [`FromPrimitive`], which are lossy: `1/3` as `f64` converts to the nearest dyadic
+/// fraction, which differs from the exact scalar in its last bits.
+///
+/// Note that masking quantizes the scalar to the precision of the unit masking configuration
+/// anyways, which bounds the deviation of an unmasked average by the number of models and the
+/// magnitude of the weights divided by its [`exp_shift()`]. An exact scalar guarantees that the
+/// participants and the coordinator agree on its value, e.g. for a declared scalar.
+///
+/// [`exp_shift()`]: crate::mask::MaskConfig::exp_shift
 pub struct Scalar(Ratio<BigUint>);
 
 impl From<Scalar> for Ratio<BigInt> {
@@ -55,6 +68,20 @@ impl Scalar {
         Self(Ratio::new(numer.into(), denom.into()))
     }
 
+    /// Constructs a new `Scalar` from the given numerator and denominator.
+    ///
+    /// Returns `None` if the denominator is zero, unlike [`Scalar::new()`] which panics.
+    pub fn checked_new<U>(numer: U, denom: U) -> Option<Self>
+    where
+        U: Unsigned + Into<BigUint>,
+    {
+        if denom.is_zero() {
+            None
+        } else {
+            Some(Self::new(numer, denom))
+        }
+    }
+
     /// Constructs a `Scalar` representing the given integer.
     pub fn from_integer<U>(u: U) -> Self
     where
@@ -264,6 +291,19 @@ mod tests {
         assert_eq!(sc_res.unwrap(), Scalar::new(numer, denom));
     }
 
+    #[test]
+    fn test_scalar_reduced_equality() {
+        assert_eq!(Scalar::new(2_u64, 6_u64), Scalar::new(1_u64, 3_u64));
+        assert_eq!(Scalar::checked_new(2_u64, 6_u64), Some(Scalar::new(1_u8, 3_u8)));
+        assert!(Scalar::checked_new(1_u64, 0_u64).is_none());
+
+        // the conversion from a float is lossy, but the exact scalar converts back to it
+        let lossy = Scalar::from_primitive(1_f64 / 3_f64).unwrap();
+        assert_ne!(lossy, Scalar::new(1_u8, 3_u8));
+        let exact: f64 = Scalar::new(1_u8, 3_u8).into_primitive().unwrap();
+        assert_eq!(exact.to_bits(), (1_f64 / 3_f64).to_bits());
+    }
+
     #[test]
     fn test_ratio_conversion_err() {
         let neg_ratio = Ratio::new(BigInt::from(-1), BigInt::from(2));
@@ -7,6 +7,7 @@
 use std::iter;
 
 use derive_more::{AsMut, AsRef};
+use num::bigint::BigUint;
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
@@ -14,15 +15,19 @@ use sodiumoxide::crypto::box_;
 use thiserror::Error;
 
 use crate::{
-    crypto::{encrypt::SEALBYTES, prng::generate_integer, ByteObject},
+    crypto::{encrypt::SEALBYTES, prng::generate_integer, ByteObject, Sha256},
     mask::{
-        object::{MaskObject, MaskUnit, MaskVect},
+        config::{MaskConfig, ModelConfig},
+        object::{MaskObject, MaskSections, MaskUnit, MaskVect},
         MaskConfigPair,
     },
     SumParticipantEphemeralPublicKey,
     SumParticipantEphemeralSecretKey,
 };
 
+/// The domain separation label which is hashed into the seeds of round-bound masks.
+const ROUND_MASK_DOMAIN: &[u8] = b"xaynet-round-mask";
+
 #[derive(AsRef, AsMut, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 /// A seed to generate a mask.
 ///
@@ -52,29 +57,122 @@ impl MaskSeed {
     }
 
     /// Encrypts this seed with the given public key as an [`EncryptedMaskSeed`].
+    ///
+    /// The seed is sealed with the default [`SealedBox`] cipher.
     pub fn encrypt(&self, pk: &SumParticipantEphemeralPublicKey) -> EncryptedMaskSeed {
-        // safe unwrap: length of slice is guaranteed by constants
-        EncryptedMaskSeed::from_slice_unchecked(pk.encrypt(self.as_slice()).as_slice())
+        self.encrypt_with::<SealedBox>(pk)
+    }
+
+    /// Encrypts this seed with the given public key and the [`SeedCipher`] `C`.
+    pub fn encrypt_with<C: SeedCipher>(
+        &self,
+        pk: &SumParticipantEphemeralPublicKey,
+    ) -> EncryptedMaskSeed {
+        C::seal(self, pk)
+    }
+
+    /// Derives `nb_shards` seeds from this seed, one for each shard of a [`Model`].
+    ///
+    /// The derivation is deterministic, hence the masks of the shards of a model can be
+    /// reproduced from this seed alone. The seed of the `i`-th shard is the SHA256 hash of this
+    /// seed and the big endian bytes of `i`.
+    ///
+    /// [`Model`]: crate::mask::Model
+    pub fn derive_shard_seeds(&self, nb_shards: usize) -> Vec<MaskSeed> {
+        (0..nb_shards as u64)
+            .map(|i| {
+                let hash = Sha256::hash(&[self.as_slice(), &i.to_be_bytes()].concat());
+                MaskSeed::from_slice_unchecked(hash.as_slice())
+            })
+            .collect()
+    }
+
+    /// Derives the seed of the mask bound to the round with the given `round_seed`.
+    ///
+    /// The derived seed is the SHA256 hash of a domain separation label, this seed and the round
+    /// seed.
+    pub(crate) fn bind_to_round(&self, round_seed: &[u8]) -> MaskSeed {
+        let hash = Sha256::hash(&[ROUND_MASK_DOMAIN, self.as_slice(), round_seed].concat());
+        MaskSeed::from_slice_unchecked(hash.as_slice())
+    }
+
+    /// Derives a mask of given length from this seed and the `round_seed` wrt the masking
+    /// configurations.
+    ///
+    /// Unlike [`derive_mask()`], the mask is bound to the round: a seed which is accidentally
+    /// reused in another round yields an unrelated mask, hence the masked models of both rounds
+    /// don't leak their difference.
+    ///
+    /// [`derive_mask()`]: MaskSeed::derive_mask
+    pub fn derive_mask_for_round(
+        &self,
+        len: usize,
+        config: MaskConfigPair,
+        round_seed: &[u8],
+    ) -> MaskObject {
+        self.bind_to_round(round_seed).derive_mask(len, config)
     }
 
     /// Derives a mask of given length from this seed wrt the masking configurations.
     pub fn derive_mask(&self, len: usize, config: MaskConfigPair) -> MaskObject {
-        let MaskConfigPair {
-            vect: config_n,
-            unit: config_1,
-        } = config;
+        let model_config = ModelConfig::single(config.vect, len);
+        let mut mask = self.derive_mask_sections(&model_config, config.unit);
+        // safe unwrap: the model config has exactly one section
+        let model_mask = mask.vects.pop().unwrap();
+        MaskObject::new_unchecked(model_mask, mask.unit)
+    }
+
+    /// Derives a mask of given length from this seed wrt the masking configurations and feeds the
+    /// numbers of its vector one by one into the `sink`.
+    ///
+    /// This yields the same mask as [`derive_mask()`], but the mask vector is never materialized.
+    /// Returns the mask unit.
+    ///
+    /// [`derive_mask()`]: MaskSeed::derive_mask
+    pub fn derive_mask_into(
+        &self,
+        len: usize,
+        config: MaskConfigPair,
+        mut sink: impl FnMut(BigUint),
+    ) -> MaskUnit {
         let mut prng = ChaCha20Rng::from_seed(self.as_array());
 
-        let rand_int = generate_integer(&mut prng, &config_1.order());
-        let scalar_mask = MaskUnit::new_unchecked(config_1, rand_int);
+        let rand_int = generate_integer(&mut prng, &config.unit.order());
+        let scalar_mask = MaskUnit::new_unchecked(config.unit, rand_int);
+
+        let order_n = config.vect.order();
+        for _ in 0..len {
+            sink(generate_integer(&mut prng, &order_n));
+        }
+
+        scalar_mask
+    }
 
-        let order_n = config_n.order();
-        let rand_ints = iter::repeat_with(|| generate_integer(&mut prng, &order_n))
-            .take(len)
+    /// Derives a mask from this seed wrt the masking configurations of the sections of the model
+    /// `config`uration and the `unit` masking configuration.
+    ///
+    /// This is the mask of a model masked via [`Masker::mask_sections()`].
+    ///
+    /// [`Masker::mask_sections()`]: crate::mask::Masker::mask_sections
+    pub fn derive_mask_sections(&self, config: &ModelConfig, unit: MaskConfig) -> MaskSections {
+        let mut prng = ChaCha20Rng::from_seed(self.as_array());
+
+        let rand_int = generate_integer(&mut prng, &unit.order());
+        let scalar_mask = MaskUnit::new_unchecked(unit, rand_int);
+
+        let model_mask = config
+            .sections()
+            .iter()
+            .map(|section| {
+                let order_n = section.config.order();
+                let rand_ints = iter::repeat_with(|| generate_integer(&mut prng, &order_n))
+                    .take(section.len)
+                    .collect();
+                MaskVect::new_unchecked(section.config, rand_ints)
+            })
             .collect();
-        let model_mask = MaskVect::new_unchecked(config_n, rand_ints);
 
-        MaskObject::new_unchecked(model_mask, scalar_mask)
+        MaskSections::new_unchecked(model_mask, scalar_mask)
     }
 }
 
@@ -88,8 +186,72 @@ impl From<Vec<u8>> for EncryptedMaskSeed {
     }
 }
 
+/// A cipher which encrypts the mask seeds for the sum participants.
+///
+/// The update participants encrypt their mask seed with the ephemeral public key of every sum
+/// participant, see [`LocalSeedDict::new_with()`]. The ciphertexts of a cipher have a fixed
+/// length, which allows to serialize the seed dictionaries as entries of fixed length.
+///
+/// [`LocalSeedDict::new_with()`]: crate::LocalSeedDict::new_with
+pub trait SeedCipher {
+    /// The identifier of the cipher in the update payload.
+    const ID: u8;
+    /// The length of an encrypted mask seed.
+    ///
+    /// The length must be positive and fit into the `u16` seed length field of the update payload.
+    const CIPHERTEXT_LENGTH: usize;
+
+    /// Encrypts the `seed` with the ephemeral public key `pk` of a sum participant.
+    ///
+    /// The ciphertext must be [`CIPHERTEXT_LENGTH`] bytes long.
+    ///
+    /// [`CIPHERTEXT_LENGTH`]: SeedCipher::CIPHERTEXT_LENGTH
+    fn seal(seed: &MaskSeed, pk: &SumParticipantEphemeralPublicKey) -> EncryptedMaskSeed;
+
+    /// Decrypts the `seed` with the ephemeral key pair of a sum participant.
+    ///
+    /// # Errors
+    /// Fails if the decryption fails or if the plaintext is not a mask seed.
+    fn open(
+        seed: &EncryptedMaskSeed,
+        pk: &SumParticipantEphemeralPublicKey,
+        sk: &SumParticipantEphemeralSecretKey,
+    ) -> Result<MaskSeed, InvalidMaskSeed>;
+}
+
+/// The default [`SeedCipher`], which seals the mask seeds in a libsodium sealed box.
+#[derive(Clone, Copy, Debug)]
+pub struct SealedBox;
+
+impl SeedCipher for SealedBox {
+    const ID: u8 = 0;
+    const CIPHERTEXT_LENGTH: usize = SEALBYTES + MaskSeed::LENGTH;
+
+    fn seal(seed: &MaskSeed, pk: &SumParticipantEphemeralPublicKey) -> EncryptedMaskSeed {
+        EncryptedMaskSeed(pk.encrypt(seed.as_slice()))
+    }
+
+    fn open(
+        seed: &EncryptedMaskSeed,
+        pk: &SumParticipantEphemeralPublicKey,
+        sk: &SumParticipantEphemeralSecretKey,
+    ) -> Result<MaskSeed, InvalidMaskSeed> {
+        MaskSeed::from_slice(
+            sk.decrypt(seed.as_slice(), pk)
+                .or(Err(InvalidMaskSeed::DecryptionFailed))?
+                .as_slice(),
+        )
+        .ok_or(InvalidMaskSeed::InvalidLength)
+    }
+}
+
+/// The [`ByteObject`] implementation assumes the default [`SealedBox`] cipher, i.e. its `LENGTH`
+/// is the length of a sealed mask seed. Mask seeds of other ciphers are created via
+/// [`from_slice_for()`].
+///
+/// [`from_slice_for()`]: EncryptedMaskSeed::from_slice_for
 impl ByteObject for EncryptedMaskSeed {
-    const LENGTH: usize = SEALBYTES + MaskSeed::LENGTH;
+    const LENGTH: usize = SealedBox::CIPHERTEXT_LENGTH;
 
     fn from_slice(bytes: &[u8]) -> Option<Self> {
         if bytes.len() == Self::LENGTH {
@@ -117,8 +279,27 @@ pub enum InvalidMaskSeed {
 }
 
 impl EncryptedMaskSeed {
+    /// Creates an encrypted mask seed of the [`SeedCipher`] `C` from a slice of bytes.
+    ///
+    /// Returns `None` if the length of the slice is not the ciphertext length of the cipher.
+    pub fn from_slice_for<C: SeedCipher>(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() == C::CIPHERTEXT_LENGTH {
+            Some(Self(bytes.to_vec()))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the length of this encrypted seed.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
     /// Decrypts this seed as a [`MaskSeed`].
     ///
+    /// The seed is opened with the default [`SealedBox`] cipher.
+    ///
     /// # Errors
     /// Fails if the decryption fails.
     pub fn decrypt(
@@ -126,19 +307,62 @@ impl EncryptedMaskSeed {
         pk: &SumParticipantEphemeralPublicKey,
         sk: &SumParticipantEphemeralSecretKey,
     ) -> Result<MaskSeed, InvalidMaskSeed> {
-        MaskSeed::from_slice(
-            sk.decrypt(self.as_slice(), pk)
-                .or(Err(InvalidMaskSeed::DecryptionFailed))?
-                .as_slice(),
-        )
-        .ok_or(InvalidMaskSeed::InvalidLength)
+        self.decrypt_with::<SealedBox>(pk, sk)
+    }
+
+    /// Decrypts this seed as a [`MaskSeed`] with the [`SeedCipher`] `C`.
+    ///
+    /// # Errors
+    /// Fails if the decryption fails.
+    pub fn decrypt_with<C: SeedCipher>(
+        &self,
+        pk: &SumParticipantEphemeralPublicKey,
+        sk: &SumParticipantEphemeralSecretKey,
+    ) -> Result<MaskSeed, InvalidMaskSeed> {
+        C::open(self, pk, sk)
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod cipher {
+    //! A dummy [`SeedCipher`] with large ciphertexts for testing.
+
     use super::*;
+
+    /// A cipher which pads the sealed mask seed to 1 KiB.
+    ///
+    /// The padding stands for the larger ciphertexts of e.g. a post-quantum KEM.
+    pub struct PaddedSealedBox;
+
+    impl SeedCipher for PaddedSealedBox {
+        const ID: u8 = 0xfe;
+        const CIPHERTEXT_LENGTH: usize = 1024;
+
+        fn seal(seed: &MaskSeed, pk: &SumParticipantEphemeralPublicKey) -> EncryptedMaskSeed {
+            let mut ciphertext = SealedBox::seal(seed, pk).0;
+            ciphertext.resize(Self::CIPHERTEXT_LENGTH, 0xaa);
+            EncryptedMaskSeed(ciphertext)
+        }
+
+        fn open(
+            seed: &EncryptedMaskSeed,
+            pk: &SumParticipantEphemeralPublicKey,
+            sk: &SumParticipantEphemeralSecretKey,
+        ) -> Result<MaskSeed, InvalidMaskSeed> {
+            if seed.len() != Self::CIPHERTEXT_LENGTH {
+                return Err(InvalidMaskSeed::InvalidLength);
+            }
+            let sealed = EncryptedMaskSeed(seed.0[..SealedBox::CIPHERTEXT_LENGTH].to_vec());
+            SealedBox::open(&sealed, pk, sk)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cipher::PaddedSealedBox, *};
     use crate::{
+        common::RoundSeed,
         crypto::encrypt::EncryptKeyPair,
         mask::config::{BoundType, DataType, GroupType, MaskConfig, ModelType},
     };
@@ -175,6 +399,64 @@ mod tests {
             .all(|integer| integer < &config.order()));
     }
 
+    #[test]
+    fn test_derive_mask_for_round() {
+        let config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        }
+        .into();
+        let seed = MaskSeed::generate();
+        let round_seed_1 = RoundSeed::generate();
+        let round_seed_2 = RoundSeed::generate();
+
+        let mask = seed.derive_mask_for_round(10, config, round_seed_1.as_slice());
+        assert_eq!(mask.vect.data.len(), 10);
+        assert!(mask.is_valid());
+        assert_eq!(
+            mask,
+            seed.derive_mask_for_round(10, config, round_seed_1.as_slice()),
+        );
+        // the same seed yields different masks in different rounds
+        assert_ne!(
+            mask,
+            seed.derive_mask_for_round(10, config, round_seed_2.as_slice()),
+        );
+        assert_ne!(mask, seed.derive_mask(10, config));
+    }
+
+    #[test]
+    fn test_derive_mask_into() {
+        let config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        }
+        .into();
+        let seed = MaskSeed::generate();
+        let mut numbers = Vec::new();
+        let unit = seed.derive_mask_into(10, config, |number| numbers.push(number));
+
+        let mask = seed.derive_mask(10, config);
+        assert_eq!(numbers, mask.vect.data);
+        assert_eq!(unit, mask.unit);
+    }
+
+    #[test]
+    fn test_derive_shard_seeds() {
+        let seed = MaskSeed::generate();
+        let shard_seeds = seed.derive_shard_seeds(3);
+        assert_eq!(shard_seeds.len(), 3);
+        assert_eq!(shard_seeds, seed.derive_shard_seeds(3));
+        assert_eq!(shard_seeds[..2], seed.derive_shard_seeds(2)[..]);
+        assert_ne!(shard_seeds[0], shard_seeds[1]);
+        assert!(!shard_seeds.contains(&seed));
+        assert_ne!(shard_seeds, MaskSeed::generate().derive_shard_seeds(3));
+    }
+
     #[test]
     fn test_encryption() {
         let seed = MaskSeed::generate();
@@ -186,4 +468,29 @@ mod tests {
         let decr_seed = encr_seed.decrypt(&public, &secret).unwrap();
         assert_eq!(seed, decr_seed);
     }
+
+    #[test]
+    fn test_encryption_with_cipher() {
+        let seed = MaskSeed::generate();
+        let EncryptKeyPair { public, secret } = EncryptKeyPair::generate();
+        let encr_seed = seed.encrypt_with::<PaddedSealedBox>(&public);
+        assert_eq!(encr_seed.len(), PaddedSealedBox::CIPHERTEXT_LENGTH);
+        assert_eq!(
+            encr_seed
+                .decrypt_with::<PaddedSealedBox>(&public, &secret)
+                .unwrap(),
+            seed,
+        );
+        // the ciphers are not interchangeable
+        assert!(encr_seed.decrypt(&public, &secret).is_err());
+        assert!(seed
+            .encrypt(&public)
+            .decrypt_with::<PaddedSealedBox>(&public, &secret)
+            .is_err());
+
+        assert!(
+            EncryptedMaskSeed::from_slice_for::<PaddedSealedBox>(encr_seed.as_slice()).is_some()
+        );
+        assert!(EncryptedMaskSeed::from_slice(encr_seed.as_slice()).is_none());
+    }
 }
@@ -0,0 +1,344 @@
+//! Trimmed mean aggregation of unmasked models.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: crate::mask
+
+use num::{bigint::BigInt, rational::Ratio, Zero};
+use thiserror::Error;
+
+use crate::mask::{
+    masking::{unmask_unit, Aggregation, UnmaskingError},
+    MaskObject,
+    Model,
+    Scalar,
+};
+
+#[derive(Debug, Error, Eq, PartialEq)]
+/// Errors related to the trimmed mean aggregation of models.
+pub enum TrimmedMeanError {
+    #[error("the model length {actual} doesn't match the aggregated model length {expected}")]
+    ModelMismatch { expected: usize, actual: usize },
+
+    #[error("{models} models are too few to trim {trim} models from each end")]
+    TooFewModels { models: usize, trim: usize },
+
+    #[error("the scalars of the remaining models sum up to zero")]
+    ZeroScalar,
+
+    #[error("the masked model can't be unmasked: {0}")]
+    Unmasking(UnmaskingError),
+}
+
+#[derive(Debug, Clone)]
+/// An aggregator for the coordinate-wise trimmed mean of unmasked models.
+///
+/// The weights of all contributions are kept per coordinate, such that the `trim` lowest and
+/// `trim` highest weights of each coordinate can be removed before the remaining weights are
+/// averaged wrt the scalars of their contributions. This bounds the influence of an outlier on
+/// the aggregated model, whereas a single outlier can shift a mean arbitrarily far.
+///
+/// The trimming compares individual weights, hence it requires the unmasked contributions. It
+/// can't be applied to an [`Aggregation`] of masked models, since the sum of masked models
+/// doesn't reveal the order of their weights. This needs a party which is trusted with the
+/// individual masks, see [`aggregate_masked()`], or a secure comparison protocol between the
+/// participants.
+///
+/// [`Aggregation`]: crate::mask::Aggregation
+/// [`aggregate_masked()`]: Self::aggregate_masked
+pub struct TrimmedMeanAggregation {
+    /// The number of weights trimmed from each end of a coordinate.
+    trim: usize,
+    /// The number of aggregated models.
+    nb_models: usize,
+    /// The weights of each coordinate together with the scalars of their contributions.
+    coordinates: Vec<Vec<(Ratio<BigInt>, Ratio<BigInt>)>>,
+}
+
+impl TrimmedMeanAggregation {
+    /// Creates a new, empty aggregator which trims `trim` weights from each end of a coordinate.
+    pub fn new(trim: usize) -> Self {
+        Self {
+            trim,
+            nb_models: 0,
+            coordinates: Vec::new(),
+        }
+    }
+
+    /// Gets the number of aggregated models.
+    pub fn len(&self) -> usize {
+        self.nb_models
+    }
+
+    /// Checks whether no models have been aggregated yet.
+    pub fn is_empty(&self) -> bool {
+        self.nb_models == 0
+    }
+
+    /// Validates if aggregation of the aggregated models with the given `model` is possible.
+    ///
+    /// # Errors
+    /// Fails if the length of the model differs from the length of the aggregated models.
+    pub fn validate_aggregation(&self, model: &Model) -> Result<(), TrimmedMeanError> {
+        if self.nb_models > 0 && model.len() != self.coordinates.len() {
+            return Err(TrimmedMeanError::ModelMismatch {
+                expected: self.coordinates.len(),
+                actual: model.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Aggregates the `model` with the `scalar` of its contribution.
+    ///
+    /// It should be checked via [`validate_aggregation()`] whether the aggregation is possible.
+    ///
+    /// # Panics
+    /// Panics if the length of the model differs from the length of the aggregated models.
+    ///
+    /// [`validate_aggregation()`]: Self::validate_aggregation
+    pub fn aggregate(&mut self, model: Model, scalar: Scalar) {
+        self.push(model, Ratio::<BigInt>::from(scalar));
+    }
+
+    /// Unmasks the `masked_model` of a single contribution with its own `mask` and aggregates
+    /// the model with its unmasked scalar.
+    ///
+    /// This requires a party which is trusted with the masks of the individual contributions,
+    /// e.g. a coordinator which acts as the trusted aggregator of a round. A contribution with a
+    /// zero scalar doesn't affect the mean and is skipped.
+    ///
+    /// # Errors
+    /// Fails if the masked model can't be unmasked with the mask or if the length of the model
+    /// differs from the length of the aggregated models.
+    pub fn aggregate_masked(
+        &mut self,
+        masked_model: MaskObject,
+        mask: MaskObject,
+    ) -> Result<(), TrimmedMeanError> {
+        let masked_scalar = masked_model.unit.clone();
+        let masked_model = Aggregation::from(masked_model);
+        masked_model
+            .validate_unmasking(&mask)
+            .map_err(TrimmedMeanError::Unmasking)?;
+        let scalar = unmask_unit(masked_scalar, mask.unit.clone(), 1);
+        if scalar.is_zero() {
+            return Ok(());
+        }
+
+        let model = masked_model.unmask(mask);
+        self.validate_aggregation(&model)?;
+        self.push(model, scalar);
+        Ok(())
+    }
+
+    /// Pushes the weights of the `model` with the `scalar` of its contribution.
+    fn push(&mut self, model: Model, scalar: Ratio<BigInt>) {
+        if self.nb_models == 0 {
+            self.coordinates = vec![Vec::new(); model.len()];
+        }
+        assert_eq!(model.len(), self.coordinates.len());
+
+        for (coordinate, weight) in self.coordinates.iter_mut().zip(model) {
+            coordinate.push((weight, scalar.clone()));
+        }
+        self.nb_models += 1;
+    }
+
+    /// Computes the coordinate-wise trimmed mean of the aggregated models.
+    ///
+    /// # Errors
+    /// Fails if there are not more than `2 * trim` aggregated models or if the scalars of the
+    /// remaining contributions of a coordinate sum up to zero.
+    pub fn trimmed_mean(&self) -> Result<Model, TrimmedMeanError> {
+        if self.nb_models <= 2 * self.trim {
+            return Err(TrimmedMeanError::TooFewModels {
+                models: self.nb_models,
+                trim: self.trim,
+            });
+        }
+
+        self.coordinates
+            .iter()
+            .map(|coordinate| {
+                let mut sorted = coordinate.iter().collect::<Vec<_>>();
+                sorted.sort_unstable_by(|(weight1, _), (weight2, _)| weight1.cmp(weight2));
+                let (weighted_sum, scalar_sum) = sorted[self.trim..self.nb_models - self.trim]
+                    .iter()
+                    .fold(
+                        (Ratio::<BigInt>::zero(), Ratio::<BigInt>::zero()),
+                        |(weighted_sum, scalar_sum), (weight, scalar)| {
+                            (weighted_sum + weight * scalar, scalar_sum + scalar)
+                        },
+                    );
+                if scalar_sum.is_zero() {
+                    Err(TrimmedMeanError::ZeroScalar)
+                } else {
+                    Ok(weighted_sum / scalar_sum)
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::{
+        BoundType,
+        DataType,
+        FromPrimitives,
+        GroupType,
+        IntoPrimitives,
+        MaskConfig,
+        Masker,
+        ModelType,
+    };
+
+    fn model(weights: Vec<f64>) -> Model {
+        Model::from_primitives(weights.into_iter()).unwrap()
+    }
+
+    fn primitives(model: Model) -> Vec<f64> {
+        <Model as IntoPrimitives<f64>>::into_primitives(model)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_trimmed_mean_removes_outlier() {
+        let mut aggregation = TrimmedMeanAggregation::new(1);
+        for weights in vec![
+            vec![1., -1.],
+            vec![2., -2.],
+            vec![3., -3.],
+            vec![4., -4.],
+            // an injected outlier in both coordinates
+            vec![1000., 1000.],
+        ] {
+            let model = model(weights);
+            assert!(aggregation.validate_aggregation(&model).is_ok());
+            aggregation.aggregate(model, Scalar::unit());
+        }
+        assert_eq!(aggregation.len(), 5);
+
+        // the lowest and highest weight of each coordinate are removed
+        let trimmed_mean = primitives(aggregation.trimmed_mean().unwrap());
+        assert_eq!(trimmed_mean, vec![3., -2.]);
+
+        // whereas the mean is dominated by the outlier
+        let mut mean = TrimmedMeanAggregation::new(0);
+        for weights in vec![vec![1.], vec![2.], vec![3.], vec![4.], vec![1000.]] {
+            mean.aggregate(model(weights), Scalar::unit());
+        }
+        assert_eq!(primitives(mean.trimmed_mean().unwrap()), vec![202.]);
+    }
+
+    #[test]
+    fn test_trimmed_mean_is_weighted() {
+        let mut aggregation = TrimmedMeanAggregation::new(1);
+        aggregation.aggregate(model(vec![0.]), Scalar::unit());
+        aggregation.aggregate(model(vec![1.]), Scalar::from_integer(1_u8));
+        aggregation.aggregate(model(vec![4.]), Scalar::from_integer(3_u8));
+        aggregation.aggregate(model(vec![8.]), Scalar::from_integer(2_u8));
+        aggregation.aggregate(model(vec![9.]), Scalar::unit());
+        // (1 * 1 + 4 * 3 + 8 * 2) / (1 + 3 + 2)
+        assert_eq!(
+            aggregation.trimmed_mean().unwrap(),
+            Model::from(vec![Ratio::new(BigInt::from(29), BigInt::from(6))]),
+        );
+    }
+
+    #[test]
+    fn test_trimmed_mean_of_masked_models() {
+        let config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        };
+        let mut aggregation = TrimmedMeanAggregation::new(1);
+        for (weights, scalar) in vec![
+            (vec![0.25, -0.25], Scalar::unit()),
+            (vec![0.5, -0.5], Scalar::new(1_u8, 2_u8)),
+            (vec![0.75, -0.75], Scalar::unit()),
+            // an injected outlier in both coordinates
+            (vec![1., 1.], Scalar::unit()),
+            // a contribution without weight is skipped
+            (vec![-1., -1.], Scalar::from_integer(0_u8)),
+        ] {
+            let (seed, masked_model) = Masker::new(config.into()).mask(scalar, &model(weights));
+            let mask = seed.derive_mask(2, config.into());
+            aggregation.aggregate_masked(masked_model, mask).unwrap();
+        }
+        assert_eq!(aggregation.len(), 4);
+
+        // the lowest and highest unmasked weight of each coordinate are removed, i.e.
+        // (0.5 * 0.5 + 0.75 * 1) / (0.5 + 1) and (-0.5 * 0.5 - 0.25 * 1) / (0.5 + 1)
+        assert_eq!(
+            aggregation.trimmed_mean().unwrap(),
+            Model::from(vec![
+                Ratio::new(BigInt::from(2), BigInt::from(3)),
+                Ratio::new(BigInt::from(-1), BigInt::from(3)),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_trimmed_mean_of_masked_models_errors() {
+        let config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        };
+        let mut aggregation = TrimmedMeanAggregation::new(1);
+        let (seed, masked_model) =
+            Masker::new(config.into()).mask(Scalar::unit(), &model(vec![0.5, -0.5]));
+        assert_eq!(
+            aggregation.aggregate_masked(masked_model.clone(), seed.derive_mask(3, config.into())),
+            Err(TrimmedMeanError::Unmasking(
+                UnmaskingError::MaskManyMismatch
+            )),
+        );
+        aggregation
+            .aggregate_masked(masked_model, seed.derive_mask(2, config.into()))
+            .unwrap();
+
+        let (seed, masked_model) =
+            Masker::new(config.into()).mask(Scalar::unit(), &model(vec![0.5]));
+        assert_eq!(
+            aggregation.aggregate_masked(masked_model, seed.derive_mask(1, config.into())),
+            Err(TrimmedMeanError::ModelMismatch {
+                expected: 2,
+                actual: 1,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_trimmed_mean_errors() {
+        let mut aggregation = TrimmedMeanAggregation::new(1);
+        aggregation.aggregate(model(vec![1., 2.]), Scalar::unit());
+        assert_eq!(
+            aggregation.validate_aggregation(&model(vec![1.])),
+            Err(TrimmedMeanError::ModelMismatch {
+                expected: 2,
+                actual: 1,
+            }),
+        );
+
+        aggregation.aggregate(model(vec![3., 4.]), Scalar::unit());
+        assert_eq!(
+            aggregation.trimmed_mean(),
+            Err(TrimmedMeanError::TooFewModels { models: 2, trim: 1 }),
+        );
+
+        let mut aggregation = TrimmedMeanAggregation::new(0);
+        aggregation.aggregate(model(vec![1., 2.]), Scalar::from_integer(0_u8));
+        assert_eq!(
+            aggregation.trimmed_mean(),
+            Err(TrimmedMeanError::ZeroScalar)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Message extensions.
+//!
+//! See the [message module] documentation since this is a private module anyways.
+//!
+//! [message module]: crate::message
+
+use std::convert::TryInto;
+
+use anyhow::{anyhow, Context};
+use num::{bigint::BigUint, rational::Ratio, Zero};
+
+use crate::{mask::Scalar, message::DecodeError};
+
+/// The registry of the assigned extension ids.
+///
+/// An extension id is a `u16`. If its highest bit is set, the extension is critical, i.e. a
+/// parser which doesn't know the extension must reject the message. Unknown non-critical
+/// extensions are skipped.
+pub mod ids {
+    /// The bit of an extension id which marks the extension as critical.
+    pub const CRITICAL: u16 = 1 << 15;
+
+    /// The scalar which an update participant declares for its masked model, see
+    /// [`Message::declared_scalar()`].
+    ///
+    /// [`Message::declared_scalar()`]: crate::message::Message::declared_scalar
+    pub const DECLARED_SCALAR: u16 = 0x0001;
+
+    /// The extension ids which are known to this version of the message format.
+    pub const KNOWN: &[u16] = &[DECLARED_SCALAR];
+}
+
+/// Length in bytes of the id and length fields of an extension record.
+pub(crate) const RECORD_HEADER_LENGTH: usize = 4;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+/// An optional extension of a message.
+///
+/// Extensions are appended to the payload of a message as a list of records, each made of a
+/// `u16` id, a `u16` length and the data, in big endian:
+///
+/// ```no_rust
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |              id               |            length             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +                     data (variable length)                    +
+/// |                                                               |
+/// ```
+///
+/// The extensions are part of the signed data of the message.
+pub struct Extension {
+    /// The id of the extension, see the [registry].
+    ///
+    /// [registry]: crate::message::extension_ids
+    pub id: u16,
+    /// The data of the extension.
+    pub data: Vec<u8>,
+}
+
+impl Extension {
+    /// Checks whether a parser must reject a message with this extension if it doesn't know it.
+    pub fn is_critical(&self) -> bool {
+        self.id & ids::CRITICAL != 0
+    }
+
+    /// Gets the number of bytes of the serialized extension record.
+    pub(crate) fn buffer_length(&self) -> usize {
+        RECORD_HEADER_LENGTH + self.data.len()
+    }
+
+    /// Serializes the extension record into the beginning of the `buffer`.
+    ///
+    /// # Panics
+    /// Panics if the buffer is too small or if the data is longer than `u16::MAX` bytes.
+    pub(crate) fn to_bytes(&self, buffer: &mut [u8]) {
+        let length: u16 = self.data.len().try_into().unwrap();
+        buffer[..2].copy_from_slice(&self.id.to_be_bytes());
+        buffer[2..4].copy_from_slice(&length.to_be_bytes());
+        buffer[RECORD_HEADER_LENGTH..self.buffer_length()].copy_from_slice(&self.data);
+    }
+}
+
+/// Parses the extension records in `bytes`.
+///
+/// Extensions whose ids are not in `known` are skipped if they are non-critical.
+///
+/// # Errors
+/// Fails if a record is truncated or if an extension is critical and unknown.
+pub(crate) fn parse_extensions(
+    bytes: &[u8],
+    known: &[u16],
+) -> Result<Vec<Extension>, DecodeError> {
+    let mut extensions = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        if rest.len() < RECORD_HEADER_LENGTH {
+            return Err(anyhow!(
+                "invalid extension record: {} bytes left for the record header",
+                rest.len()
+            ));
+        }
+        // safe unwraps: the slices are 2 bytes long
+        let id = u16::from_be_bytes(rest[..2].try_into().unwrap());
+        let length = u16::from_be_bytes(rest[2..4].try_into().unwrap()) as usize;
+        let end = RECORD_HEADER_LENGTH + length;
+        if rest.len() < end {
+            return Err(anyhow!(
+                "invalid extension record {:#06x}: length field says {}, but {} bytes are left",
+                id,
+                length,
+                rest.len() - RECORD_HEADER_LENGTH
+            ));
+        }
+
+        let extension = Extension {
+            id,
+            data: rest[RECORD_HEADER_LENGTH..end].to_vec(),
+        };
+        if known.contains(&id) {
+            extensions.push(extension);
+        } else if extension.is_critical() {
+            return Err(anyhow!("unknown critical extension {:#06x}", id));
+        }
+        rest = &rest[end..];
+    }
+    Ok(extensions)
+}
+
+/// Encodes a scalar as the `u16` length of its numerator followed by its numerator and
+/// denominator, in big endian.
+pub(crate) fn encode_scalar(scalar: &Scalar) -> Vec<u8> {
+    let ratio: Ratio<BigUint> = scalar.clone().into();
+    let numer = ratio.numer().to_bytes_be();
+    let denom = ratio.denom().to_bytes_be();
+    let numer_length: u16 = numer.len().try_into().unwrap();
+    [&numer_length.to_be_bytes()[..], &numer, &denom].concat()
+}
+
+/// Decodes a scalar encoded by [`encode_scalar()`].
+///
+/// # Errors
+/// Fails if the bytes are truncated or if the denominator is zero.
+pub(crate) fn decode_scalar(bytes: &[u8]) -> Result<Scalar, DecodeError> {
+    let numer_length = bytes
+        .get(..2)
+        // safe unwrap: the slice is 2 bytes long
+        .map(|length| u16::from_be_bytes(length.try_into().unwrap()) as usize)
+        .context("invalid scalar: missing numerator length")?;
+    let numer = bytes
+        .get(2..2 + numer_length)
+        .context("invalid scalar: truncated numerator")?;
+    let denom = BigUint::from_bytes_be(&bytes[2 + numer_length..]);
+    if denom.is_zero() {
+        return Err(anyhow!("invalid scalar: zero denominator"));
+    }
+    Ok(Scalar::new(BigUint::from_bytes_be(numer), denom))
+}
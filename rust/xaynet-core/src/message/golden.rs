@@ -0,0 +1,228 @@
+//! Golden-file tests of the wire format.
+//!
+//! The canonical messages below are built from fixed keys and seeds, hence they serialize to the
+//! same bytes on every run. The bytes are checked in as golden files and the tests assert that
+//! the current serialization still produces exactly these bytes and that the golden files still
+//! parse to the canonical messages. This turns the wire format described in the [message module]
+//! into an enforced contract: any change of the serialization fails these tests.
+//!
+//! If a change of the wire format is intended, the golden files are regenerated with
+//!
+//! ```text
+//! XAYNET_UPDATE_GOLDEN_FILES=1 cargo test -p xaynet-core golden
+//! ```
+//!
+//! and the wire format documentation is updated accordingly. Changing a golden file breaks the
+//! compatibility with participants and coordinators of older versions.
+//!
+//! [message module]: crate::message
+
+use std::{env, fs, path::Path};
+
+use crate::{
+    crypto::{
+        ByteObject,
+        EncryptKeyPair,
+        EncryptKeySeed,
+        PublicEncryptKey,
+        PublicSigningKey,
+        SigningKeyPair,
+        SigningKeySeed,
+    },
+    mask::{
+        BoundType,
+        DataType,
+        EncryptedMaskSeed,
+        FromPrimitives,
+        GroupType,
+        MaskConfig,
+        MaskConfigPair,
+        MaskObject,
+        MaskSeed,
+        Masker,
+        Model,
+        ModelType,
+        Scalar,
+        SealedBox,
+        SeedCipher,
+    },
+    message::{FromBytes, Message, MessageBuffer, Sum, Sum2, ToBytes, Update},
+    LocalSeedDict,
+};
+
+/// The environment variable which regenerates the golden files instead of checking them.
+const UPDATE_GOLDEN_FILES: &str = "XAYNET_UPDATE_GOLDEN_FILES";
+
+const SUM_MESSAGE: &[u8] = include_bytes!("golden/sum_message.bin");
+const UPDATE_MESSAGE: &[u8] = include_bytes!("golden/update_message.bin");
+const SUM2_MESSAGE: &[u8] = include_bytes!("golden/sum2_message.bin");
+const MASKED_MODEL: &[u8] = include_bytes!("golden/masked_model.bin");
+
+/// Asserts that the `actual` bytes are equal to the `golden` bytes of the golden file `name`.
+///
+/// Overwrites the golden file instead if [`UPDATE_GOLDEN_FILES`] is set.
+fn assert_golden(name: &str, actual: &[u8], golden: &[u8]) {
+    if env::var_os(UPDATE_GOLDEN_FILES).is_some() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/message/golden")
+            .join(name);
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    assert_eq!(
+        actual.len(),
+        golden.len(),
+        "the length of {} changed from {} to {} bytes",
+        name,
+        golden.len(),
+        actual.len(),
+    );
+    if let Some(offset) = actual.iter().zip(golden).position(|(a, g)| a != g) {
+        panic!(
+            "{} differs from its golden file at byte {}: {:#04x} != {:#04x}",
+            name, offset, actual[offset], golden[offset],
+        );
+    }
+}
+
+fn participant_keys() -> SigningKeyPair {
+    SigningKeyPair::derive_from_seed(&SigningKeySeed::from_slice(&[0x01; 32]).unwrap())
+}
+
+fn coordinator_pk() -> PublicEncryptKey {
+    EncryptKeyPair::derive_from_seed(&EncryptKeySeed::from_slice(&[0x02; 32]).unwrap()).public
+}
+
+fn ephm_pk() -> PublicEncryptKey {
+    EncryptKeyPair::derive_from_seed(&EncryptKeySeed::from_slice(&[0x03; 32]).unwrap()).public
+}
+
+fn sum_participant_pk() -> PublicSigningKey {
+    SigningKeyPair::derive_from_seed(&SigningKeySeed::from_slice(&[0x04; 32]).unwrap()).public
+}
+
+fn mask_config() -> MaskConfigPair {
+    MaskConfig {
+        group_type: GroupType::Prime,
+        data_type: DataType::F32,
+        bound_type: BoundType::B0,
+        model_type: ModelType::M3,
+    }
+    .into()
+}
+
+fn mask_seed() -> MaskSeed {
+    MaskSeed::from_slice(&[0x05; 32]).unwrap()
+}
+
+fn model() -> Model {
+    Model::from_primitives(vec![0.5_f32, -0.25, 1., 0.].into_iter()).unwrap()
+}
+
+/// The masked model of the canonical update message.
+fn masked_model() -> MaskObject {
+    let (_, masked_model) =
+        Masker::with_seed(mask_config(), mask_seed()).mask(Scalar::new(1_u8, 2_u8), &model());
+    masked_model
+}
+
+/// The canonical sum message.
+fn sum_message() -> Message {
+    let keys = participant_keys();
+    let payload = Sum {
+        sum_signature: keys.secret.sign_detached(b"sum"),
+        ephm_pk: ephm_pk(),
+    };
+    Message::new_sum(keys.public, coordinator_pk(), payload)
+}
+
+/// The canonical update message.
+///
+/// The entries of a seed dictionary are serialized in no particular order, hence the local seed
+/// dictionary has a single entry. The encrypted mask seed is fixed, because sealed boxes are
+/// randomized.
+fn update_message() -> Message {
+    let keys = participant_keys();
+    let local_seed_dict = LocalSeedDict::from_decoded(vec![(
+        sum_participant_pk(),
+        EncryptedMaskSeed::from_slice(&[0x06; EncryptedMaskSeed::LENGTH]).unwrap(),
+    )]);
+    let payload = Update {
+        sum_signature: keys.secret.sign_detached(b"sum"),
+        update_signature: keys.secret.sign_detached(b"update"),
+        model_checksum: model().checksum(),
+        seed_cipher: SealedBox::ID,
+        masked_model: masked_model(),
+        local_seed_dict,
+    };
+    Message::new_update(keys.public, coordinator_pk(), payload)
+}
+
+/// The canonical sum2 message.
+fn sum2_message() -> Message {
+    let keys = participant_keys();
+    let payload = Sum2 {
+        sum_signature: keys.secret.sign_detached(b"sum"),
+        seed_dict_version: Some(3),
+        model_mask: mask_seed().derive_mask(model().len(), mask_config()),
+    };
+    Message::new_sum2(keys.public, coordinator_pk(), payload)
+}
+
+fn serialize_message(message: &Message) -> Vec<u8> {
+    let mut buffer = vec![0; message.buffer_length()];
+    message.to_bytes(&mut buffer, &participant_keys().secret);
+    buffer
+}
+
+/// Asserts that the `message` serializes to the `golden` bytes and vice versa.
+fn check_message(name: &str, message: Message, golden: &[u8]) {
+    let bytes = serialize_message(&message);
+    assert_golden(name, &bytes, golden);
+
+    if env::var_os(UPDATE_GOLDEN_FILES).is_none() {
+        MessageBuffer::new(golden)
+            .unwrap()
+            .check_signature()
+            .unwrap();
+        let parsed = Message::from_byte_slice(&golden).unwrap();
+        assert_eq!(
+            Message {
+                signature: None,
+                ..parsed
+            },
+            message
+        );
+    }
+}
+
+#[test]
+fn test_golden_sum_message() {
+    check_message("sum_message.bin", sum_message(), SUM_MESSAGE);
+}
+
+#[test]
+fn test_golden_update_message() {
+    check_message("update_message.bin", update_message(), UPDATE_MESSAGE);
+}
+
+#[test]
+fn test_golden_sum2_message() {
+    check_message("sum2_message.bin", sum2_message(), SUM2_MESSAGE);
+}
+
+#[test]
+fn test_golden_masked_model() {
+    let masked_model = masked_model();
+    let mut bytes = vec![0; masked_model.buffer_length()];
+    masked_model.to_bytes(&mut bytes);
+    assert_golden("masked_model.bin", &bytes, MASKED_MODEL);
+
+    if env::var_os(UPDATE_GOLDEN_FILES).is_none() {
+        assert_eq!(
+            MaskObject::from_byte_slice(&MASKED_MODEL).unwrap(),
+            masked_model
+        );
+    }
+}
@@ -4,14 +4,30 @@
 //!
 //! [message module]: crate::mask
 
-use std::convert::{TryFrom, TryInto};
+use std::{
+    convert::{TryFrom, TryInto},
+    io::Read,
+};
 
 use anyhow::{anyhow, Context};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     crypto::{ByteObject, PublicEncryptKey, PublicSigningKey, SecretSigningKey, Signature},
-    message::{Chunk, DecodeError, FromBytes, Payload, Sum, Sum2, ToBytes, Update},
+    mask::Scalar,
+    message::{
+        extension::{decode_scalar, encode_scalar, ids, parse_extensions},
+        utils::wire,
+        Chunk,
+        DecodeError,
+        Extension,
+        FromBytes,
+        Payload,
+        Sum,
+        Sum2,
+        ToBytes,
+        Update,
+    },
 };
 
 /// The minimum number of accepted `sum`/`sum2` messages for the PET protocol to function correctly.
@@ -24,7 +40,7 @@ pub(crate) mod ranges {
     use std::ops::Range;
 
     use super::*;
-    use crate::message::utils::range;
+    use crate::message::utils::{range, wire::LEN_BYTES};
 
     /// Byte range corresponding to the signature in a message in a
     /// message header
@@ -36,17 +52,17 @@ pub(crate) mod ranges {
     /// message header
     pub const COORDINATOR_PK: Range<usize> = range(PARTICIPANT_PK.end, PublicEncryptKey::LENGTH);
     /// Byte range corresponding to the length field in a message header
-    pub const LENGTH: Range<usize> = range(COORDINATOR_PK.end, 4);
+    pub const LENGTH: Range<usize> = range(COORDINATOR_PK.end, LEN_BYTES);
     /// Byte range corresponding to the tag in a message header
     pub const TAG: usize = LENGTH.end;
     /// Byte range corresponding to the flags in a message header
     pub const FLAGS: usize = TAG + 1;
-    /// Byte range reserved for future use
-    pub const RESERVED: Range<usize> = range(FLAGS + 1, 2);
+    /// Byte range corresponding to the extensions length field in a message header
+    pub const EXTENSIONS_LENGTH: Range<usize> = range(FLAGS + 1, 2);
 }
 
 /// Length in bytes of a message header
-pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
+pub const HEADER_LENGTH: usize = ranges::EXTENSIONS_LENGTH.end;
 
 /// A wrapper around a buffer that contains a [`Message`].
 ///
@@ -123,11 +139,15 @@ pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// |                             length                            |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-/// |      tag      |     flags     |          reserved             |
+/// |      tag      |     flags     |       extensions_length       |
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// |                                                               |
 /// +                    payload (variable length)                  +
 /// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// +               extensions (extensions_length bytes)            +
+/// |                                                               |
 /// ```
 ///
 /// - `signature` contains the signature of the entire message
@@ -144,8 +164,13 @@ pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 ///   messages can be as big as 2^32 = 4,294,967,296 bytes.
 /// - `tag` indicates the type of message (sum, update, sum2 or
 ///   multipart message)
-/// - the `flags` field currently supports a single flag, that
-///   indicates whether this is a multipart message
+/// - the `flags` field currently supports two flags, that indicate
+///   whether this is a multipart message and whether the masks of the
+///   message are bound to the round seed
+/// - `extensions_length` is the length in bytes of the [`Extension`]
+///   records at the end of the message. It is `0` for a message
+///   without extensions, which is the layout of messages before
+///   extensions were introduced.
 ///
 /// # Examples
 /// ## Reading a sum message
@@ -160,7 +185,7 @@ pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 /// bytes.extend(&200_u32.to_be_bytes()); // Length field
 /// bytes.push(0x01); // tag (sum message)
 /// bytes.push(0x00); // flags (not a multipart message)
-/// bytes.extend(vec![0x00, 0x00]); // reserved
+/// bytes.extend(vec![0x00, 0x00]); // extensions length
 ///
 /// // Payload: a sum message contains a signature and an ephemeral public key
 /// bytes.extend(vec![0xaa; 32]); // signature
@@ -190,7 +215,7 @@ pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 /// expected.extend(&200_u32.to_be_bytes()); // length field
 /// expected.push(0x01); // tag (sum message)
 /// expected.push(0x00); // flags (not a multipart message)
-/// expected.extend(vec![0x00, 0x00]); // reserved
+/// expected.extend(vec![0x00, 0x00]); // extensions length
 ///
 /// // Payload: a sum message contains a signature and an ephemeral public key
 /// expected.extend(vec![0xaa; 32]); // signature
@@ -207,7 +232,7 @@ pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 /// buffer
 ///     .coordinator_pk_mut()
 ///     .copy_from_slice(vec![0x33; 32].as_slice());
-/// buffer.set_length(200 as u32);
+/// buffer.set_length(200);
 /// buffer.set_tag(Tag::Sum.into());
 /// buffer.set_flags(Flags::empty());
 /// buffer
@@ -259,7 +284,7 @@ impl<T: AsRef<[u8]>> MessageBuffer<T> {
                 HEADER_LENGTH
             ));
         }
-        let expected_len = self.length() as usize;
+        let expected_len = self.length();
         let actual_len = self.inner.as_ref().len();
         if actual_len < expected_len {
             return Err(anyhow!(
@@ -268,6 +293,14 @@ impl<T: AsRef<[u8]>> MessageBuffer<T> {
                 actual_len
             ));
         }
+        let extensions_len = self.extensions_length() as usize;
+        if expected_len < HEADER_LENGTH + extensions_len {
+            return Err(anyhow!(
+                "invalid extensions length: length field says {}, but message is {} bytes long",
+                extensions_len,
+                expected_len
+            ));
+        }
         Ok(())
     }
 
@@ -291,10 +324,29 @@ impl<T: AsRef<[u8]>> MessageBuffer<T> {
     ///
     /// # Panics
     /// Accessing the field may panic if the buffer has not been checked before.
-    pub fn length(&self) -> u32 {
+    pub fn length(&self) -> usize {
         // Unwrapping is OK, as the slice is guaranteed to be 4 bytes
         // long
-        u32::from_be_bytes(self.inner.as_ref()[ranges::LENGTH].try_into().unwrap())
+        wire::read_len(&self.inner.as_ref()[ranges::LENGTH]).unwrap()
+    }
+
+    /// Gets the extensions length field
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn extensions_length(&self) -> u16 {
+        // Unwrapping is OK, as the slice is guaranteed to be 2 bytes
+        // long
+        u16::from_be_bytes(
+            self.inner.as_ref()[ranges::EXTENSIONS_LENGTH]
+                .try_into()
+                .unwrap(),
+        )
+    }
+
+    /// Gets the offset of the extensions, i.e. the end of the payload.
+    fn extensions_offset(&self) -> usize {
+        self.length() - self.extensions_length() as usize
     }
 }
 
@@ -323,12 +375,21 @@ impl<'a, T: AsRef<[u8]> + ?Sized> MessageBuffer<&'a T> {
         &self.inner.as_ref()[ranges::COORDINATOR_PK]
     }
 
-    /// Gets the rest of the message.
+    /// Gets the payload of the message, i.e. the message after the header and before the
+    /// extensions.
     ///
     /// # Panics
     /// Accessing the field may panic if the buffer has not been checked before.
     pub fn payload(&self) -> &'a [u8] {
-        &self.inner.as_ref()[HEADER_LENGTH..]
+        &self.inner.as_ref()[HEADER_LENGTH..self.extensions_offset()]
+    }
+
+    /// Gets the extension records of the message.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn extensions(&self) -> &'a [u8] {
+        &self.inner.as_ref()[self.extensions_offset()..self.length()]
     }
 
     /// Parse the signature and public signing key, and check the
@@ -353,7 +414,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized> MessageBuffer<&'a T> {
     /// # Panics
     /// Accessing the field may panic if the buffer has not been checked before.
     pub fn signed_data(&self) -> &'a [u8] {
-        let signed_data_range = ranges::SIGNATURE.end..self.length() as usize;
+        let signed_data_range = ranges::SIGNATURE.end..self.length();
         &self.inner.as_ref()[signed_data_range]
     }
 }
@@ -378,10 +439,20 @@ impl<T: AsMut<[u8]> + AsRef<[u8]>> MessageBuffer<T> {
     /// Sets the length field.
     ///
     /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before. Panics if the
+    /// length doesn't fit into the length field.
+    pub fn set_length(&mut self, value: usize) {
+        wire::write_len(&mut self.inner.as_mut()[ranges::LENGTH], value)
+            .expect("message length exceeds the length field");
+    }
+
+    /// Sets the extensions length field.
+    ///
+    /// # Panics
     /// Accessing the field may panic if the buffer has not been checked before.
-    pub fn set_length(&mut self, value: u32) {
+    pub fn set_extensions_length(&mut self, value: u16) {
         let bytes = value.to_be_bytes();
-        self.inner.as_mut()[ranges::LENGTH].copy_from_slice(&bytes[..]);
+        self.inner.as_mut()[ranges::EXTENSIONS_LENGTH].copy_from_slice(&bytes[..]);
     }
 
     /// Gets a mutable reference to the message signature field.
@@ -408,12 +479,22 @@ impl<T: AsMut<[u8]> + AsRef<[u8]>> MessageBuffer<T> {
         &mut self.inner.as_mut()[ranges::COORDINATOR_PK]
     }
 
-    /// Gets a mutable reference to the rest of the message.
+    /// Gets a mutable reference to the payload of the message.
     ///
     /// # Panics
     /// Accessing the field may panic if the buffer has not been checked before.
     pub fn payload_mut(&mut self) -> &mut [u8] {
-        &mut self.inner.as_mut()[HEADER_LENGTH..]
+        let end = self.extensions_offset();
+        &mut self.inner.as_mut()[HEADER_LENGTH..end]
+    }
+
+    /// Gets a mutable reference to the extension records of the message.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn extensions_mut(&mut self) -> &mut [u8] {
+        let range = self.extensions_offset()..self.length();
+        &mut self.inner.as_mut()[range]
     }
 
     /// Gets a mutable reference to the portion of the message used to
@@ -423,7 +504,7 @@ impl<T: AsMut<[u8]> + AsRef<[u8]>> MessageBuffer<T> {
     /// # Panics
     /// Accessing the field may panic if the buffer has not been checked before.
     pub fn signed_data_mut(&mut self) -> &mut [u8] {
-        let signed_data_range = ranges::SIGNATURE.end..self.length() as usize;
+        let signed_data_range = ranges::SIGNATURE.end..self.length();
         &mut self.inner.as_mut()[signed_data_range]
     }
 }
@@ -433,6 +514,8 @@ bitflags::bitflags! {
     pub struct Flags: u8 {
         /// Indicates whether this message is a multipart message
         const MULTIPART = 1 << 0;
+        /// Indicates whether the masks of this message are bound to the round seed
+        const ROUND_BOUND_MASKS = 1 << 1;
     }
 }
 
@@ -483,6 +566,11 @@ pub struct Message {
     pub coordinator_pk: PublicEncryptKey,
     /// Wether this is a multipart message
     pub is_multipart: bool,
+    /// Whether the masks of an update or sum2 message are bound to the round seed. See
+    /// [`RoundParameters::round_bound_masks`].
+    ///
+    /// [`RoundParameters::round_bound_masks`]: crate::common::RoundParameters::round_bound_masks
+    pub round_bound_masks: bool,
     /// The type of message. This information is partially redundant
     /// with the `payload` field. So when serializing the message,
     /// this field is ignored if the payload is a [`Payload::Sum`],
@@ -491,6 +579,9 @@ pub struct Message {
     pub tag: Tag,
     /// Message payload
     pub payload: Payload,
+    /// The known extensions of the message. Unknown non-critical extensions are dropped while
+    /// parsing.
+    pub extensions: Vec<Extension>,
 }
 
 impl Message {
@@ -506,8 +597,10 @@ impl Message {
             participant_pk,
             coordinator_pk,
             is_multipart: false,
+            round_bound_masks: false,
             tag: Tag::Sum,
             payload: message.into(),
+            extensions: Vec::new(),
         }
     }
 
@@ -523,8 +616,10 @@ impl Message {
             participant_pk,
             coordinator_pk,
             is_multipart: false,
+            round_bound_masks: false,
             tag: Tag::Sum2,
             payload: message.into(),
+            extensions: Vec::new(),
         }
     }
 
@@ -540,8 +635,10 @@ impl Message {
             participant_pk,
             coordinator_pk,
             is_multipart: false,
+            round_bound_masks: false,
             tag: Tag::Update,
             payload: message.into(),
+            extensions: Vec::new(),
         }
     }
 
@@ -558,15 +655,32 @@ impl Message {
             participant_pk,
             coordinator_pk,
             is_multipart: true,
+            round_bound_masks: false,
             tag,
             payload: message.into(),
+            extensions: Vec::new(),
         }
     }
 
     /// Parse the given message **without** verifying the
     /// signature. If you need to check the signature, call
     /// [`MessageBuffer.verify_signature`] before parsing the message.
+    ///
+    /// Unknown non-critical extensions are skipped.
+    ///
+    /// # Errors
+    /// Fails if the message is invalid or if it has an unknown critical extension.
     pub fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
+        Self::from_byte_slice_with_extensions(buffer, ids::KNOWN)
+    }
+
+    /// Parse the given message like [`from_byte_slice()`], but only with the `known` extensions.
+    ///
+    /// [`from_byte_slice()`]: Message::from_byte_slice
+    fn from_byte_slice_with_extensions<T: AsRef<[u8]>>(
+        buffer: &T,
+        known: &[u16],
+    ) -> Result<Self, DecodeError> {
         let reader = MessageBuffer::new(buffer.as_ref())?;
         let signature =
             Signature::from_byte_slice(&reader.signature()).context("failed to parse signature")?;
@@ -577,6 +691,7 @@ impl Message {
 
         let tag = reader.tag().try_into()?;
         let is_multipart = reader.flags().contains(Flags::MULTIPART);
+        let round_bound_masks = reader.flags().contains(Flags::ROUND_BOUND_MASKS);
 
         let payload = if is_multipart {
             Chunk::from_byte_slice(&reader.payload()).map(Into::into)
@@ -588,6 +703,8 @@ impl Message {
             }
         }
         .context("failed to parse message payload")?;
+        let extensions = parse_extensions(reader.extensions(), known)
+            .context("failed to parse message extensions")?;
 
         Ok(Self {
             participant_pk,
@@ -595,7 +712,80 @@ impl Message {
             signature: Some(signature),
             payload,
             is_multipart,
+            round_bound_masks,
             tag,
+            extensions,
+        })
+    }
+
+    /// Parse a message from the given reader **without** verifying the signature.
+    ///
+    /// Unlike [`from_byte_slice()`], the payload is parsed while it is read, hence the peak
+    /// memory is bounded by the parsed message instead of the parsed message plus its bytes. This
+    /// is useful for large update messages. Since the signature is computed over all bytes of the
+    /// message, the caller has to verify it while reading if needed.
+    ///
+    /// No more bytes are read from the reader than the length field of the message header says.
+    ///
+    /// # Errors
+    /// Fails if reading fails or if the message is invalid.
+    ///
+    /// [`from_byte_slice()`]: Message::from_byte_slice
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, DecodeError> {
+        let mut header = [0_u8; HEADER_LENGTH];
+        reader
+            .read_exact(&mut header)
+            .context("failed to read message header")?;
+        let header = MessageBuffer::new_unchecked(&header[..]);
+        let length = header.length();
+        let extensions_length = header.extensions_length() as usize;
+        if length < HEADER_LENGTH + extensions_length {
+            return Err(anyhow!(
+                "invalid message length: length field says {}, but header and extensions need {}",
+                length,
+                HEADER_LENGTH + extensions_length
+            ));
+        }
+
+        let signature =
+            Signature::from_byte_slice(&header.signature()).context("failed to parse signature")?;
+        let participant_pk = PublicSigningKey::from_byte_slice(&header.participant_pk())
+            .context("failed to parse public key")?;
+        let coordinator_pk = PublicEncryptKey::from_byte_slice(&header.coordinator_pk())
+            .context("failed to parse public key")?;
+
+        let tag = header.tag().try_into()?;
+        let is_multipart = header.flags().contains(Flags::MULTIPART);
+        let round_bound_masks = header.flags().contains(Flags::ROUND_BOUND_MASKS);
+
+        let payload_length = length - HEADER_LENGTH - extensions_length;
+        let payload = if is_multipart {
+            Chunk::from_reader(&mut reader, payload_length).map(Into::into)
+        } else {
+            match tag {
+                Tag::Sum => Sum::from_reader(&mut reader, payload_length).map(Into::into),
+                Tag::Update => Update::from_reader(&mut reader, payload_length).map(Into::into),
+                Tag::Sum2 => Sum2::from_reader(&mut reader, payload_length).map(Into::into),
+            }
+        }
+        .context("failed to parse message payload")?;
+
+        let mut extensions = vec![0_u8; extensions_length];
+        reader
+            .read_exact(&mut extensions)
+            .context("failed to read message extensions")?;
+        let extensions = parse_extensions(&extensions, ids::KNOWN)
+            .context("failed to parse message extensions")?;
+
+        Ok(Self {
+            participant_pk,
+            coordinator_pk,
+            signature: Some(signature),
+            payload,
+            is_multipart,
+            round_bound_masks,
+            tag,
+            extensions,
         })
     }
 
@@ -606,25 +796,94 @@ impl Message {
     /// # Panic
     ///
     /// This method panics if the given buffer is too small for the
-    /// message to fit.
+    /// message to fit or if the extensions exceed `u16::MAX` bytes.
     pub fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]> + ?Sized>(
         &self,
         buffer: &mut T,
         sk: &SecretSigningKey,
     ) {
-        let mut writer = MessageBuffer::new(buffer.as_mut()).unwrap();
+        let mut writer = self.write_unsigned(buffer.as_mut());
+        // insert the signature last. If the message contains one, use
+        // it. Otherwise compute it.
+        let signature = match self.signature {
+            Some(signature) => signature,
+            None => sk.sign_detached(writer.signed_data_mut()),
+        };
+        signature.to_bytes(&mut writer.signature_mut());
+    }
+
+    /// Serialize this message with an externally produced
+    /// `signature`, e.g. by a hardware security module which doesn't
+    /// expose the secret signing key. The `signature` attribute of
+    /// the message is ignored.
+    ///
+    /// The signature must be computed over the
+    /// [`signing_payload()`](Message::signing_payload).
+    ///
+    /// # Panic
+    ///
+    /// This method panics if the given buffer is too small for the
+    /// message to fit or if the extensions exceed `u16::MAX` bytes.
+    pub fn to_bytes_with_signature<T: AsMut<[u8]> + AsRef<[u8]> + ?Sized>(
+        &self,
+        buffer: &mut T,
+        signature: &Signature,
+    ) {
+        let mut writer = self.write_unsigned(buffer.as_mut());
+        signature.to_bytes(&mut writer.signature_mut());
+    }
+
+    /// Gets the bytes to be signed for this message, ie the entire
+    /// serialized message except the signature field itself.
+    ///
+    /// This allows to sign a message without access to the secret
+    /// signing key. The signature is inserted afterwards with
+    /// [`to_bytes_with_signature()`] or [`seal_with_signature()`].
+    ///
+    /// [`to_bytes_with_signature()`]: Message::to_bytes_with_signature
+    /// [`seal_with_signature()`]: Message::seal_with_signature
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut buffer = vec![0; self.buffer_length()];
+        let mut writer = self.write_unsigned(&mut buffer);
+        writer.signed_data_mut().to_vec()
+    }
+
+    /// Serializes this message with an externally produced
+    /// `signature` and encrypts it for the `recipient_pk`, ie the
+    /// public key of the coordinator of the round.
+    pub fn seal_with_signature(
+        &self,
+        signature: &Signature,
+        recipient_pk: &PublicEncryptKey,
+    ) -> Vec<u8> {
+        let mut buffer = vec![0; self.buffer_length()];
+        self.to_bytes_with_signature(&mut buffer, signature);
+        recipient_pk.encrypt(&buffer)
+    }
+
+    /// Serializes everything but the signature of this message into
+    /// the `buffer`.
+    fn write_unsigned<'b>(&self, buffer: &'b mut [u8]) -> MessageBuffer<&'b mut [u8]> {
+        let mut writer = MessageBuffer::new_unchecked(buffer);
+        writer.set_length(self.buffer_length());
+        writer.set_extensions_length(self.extensions_length().try_into().unwrap());
 
         self.participant_pk
             .to_bytes(&mut writer.participant_pk_mut());
         self.coordinator_pk
             .to_bytes(&mut writer.coordinator_pk_mut());
-        let flags = if self.is_multipart {
-            Flags::MULTIPART
-        } else {
-            Flags::empty()
-        };
+        let mut flags = Flags::empty();
+        flags.set(Flags::MULTIPART, self.is_multipart);
+        flags.set(Flags::ROUND_BOUND_MASKS, self.round_bound_masks);
         writer.set_flags(flags);
         self.payload.to_bytes(&mut writer.payload_mut());
+        let mut extensions = writer.extensions_mut();
+        for extension in self.extensions.iter() {
+            let (record, rest) =
+                std::mem::take(&mut extensions).split_at_mut(extension.buffer_length());
+            extension.to_bytes(record);
+            extensions = rest;
+        }
         // Determine the tag from the payload type if
         // possible. Otherwise, use the self.tag field.
         let tag = match self.payload {
@@ -634,18 +893,58 @@ impl Message {
             Payload::Chunk(_) => self.tag,
         };
         writer.set_tag(tag.into());
-        writer.set_length(self.buffer_length() as u32);
-        // insert the signature last. If the message contains one, use
-        // it. Otherwise compute it.
-        let signature = match self.signature {
-            Some(signature) => signature,
-            None => sk.sign_detached(writer.signed_data_mut()),
-        };
-        signature.to_bytes(&mut writer.signature_mut());
+        writer
     }
 
     pub fn buffer_length(&self) -> usize {
-        self.payload.buffer_length() + HEADER_LENGTH
+        self.payload.buffer_length() + HEADER_LENGTH + self.extensions_length()
+    }
+
+    /// Gets the length in bytes of the serialized extensions.
+    fn extensions_length(&self) -> usize {
+        self.extensions.iter().map(Extension::buffer_length).sum()
+    }
+
+    /// Appends an extension with the given `id` and `data` to the message.
+    ///
+    /// # Panics
+    /// Panics if the `data` is longer than `u16::MAX` bytes.
+    pub fn push_extension(&mut self, id: u16, data: Vec<u8>) {
+        assert!(
+            data.len() <= u16::MAX as usize,
+            "the extension data is too long"
+        );
+        self.extensions.push(Extension { id, data });
+    }
+
+    /// Gets the data of the first extension with the given `id`, if any.
+    pub fn extension(&self, id: u16) -> Option<&[u8]> {
+        self.extensions
+            .iter()
+            .find(|extension| extension.id == id)
+            .map(|extension| extension.data.as_slice())
+    }
+
+    /// Declares the scalar of the masked model of an update message in the
+    /// [`DECLARED_SCALAR`] extension.
+    ///
+    /// [`DECLARED_SCALAR`]: crate::message::extension_ids::DECLARED_SCALAR
+    pub fn set_declared_scalar(&mut self, scalar: &Scalar) {
+        self.extensions
+            .retain(|extension| extension.id != ids::DECLARED_SCALAR);
+        self.push_extension(ids::DECLARED_SCALAR, encode_scalar(scalar));
+    }
+
+    /// Gets the scalar of the [`DECLARED_SCALAR`] extension, if the message has one.
+    ///
+    /// # Errors
+    /// Fails if the extension is invalid.
+    ///
+    /// [`DECLARED_SCALAR`]: crate::message::extension_ids::DECLARED_SCALAR
+    pub fn declared_scalar(&self) -> Result<Option<Scalar>, DecodeError> {
+        self.extension(ids::DECLARED_SCALAR)
+            .map(decode_scalar)
+            .transpose()
     }
 }
 
@@ -656,7 +955,7 @@ mod tests {
     use super::*;
     use crate::{
         message::{Message, Tag},
-        testutils::messages as helpers,
+        testutils::{messages as helpers, multipart},
     };
 
     fn sum_message() -> (Message, Vec<u8>) {
@@ -677,7 +976,7 @@ mod tests {
             buffer.coordinator_pk(),
             helpers::coordinator_pk().1.as_slice()
         );
-        assert_eq!(buffer.length() as usize, bytes.len());
+        assert_eq!(buffer.length(), bytes.len());
         assert_eq!(buffer.payload(), helpers::sum::payload().1.as_slice());
     }
 
@@ -697,10 +996,176 @@ mod tests {
             .coordinator_pk_mut()
             .copy_from_slice(helpers::coordinator_pk().1.as_slice());
         buffer.set_tag(Tag::Sum.into());
-        buffer.set_length(expected.len() as u32);
+        buffer.set_length(expected.len());
         buffer
             .payload_mut()
             .copy_from_slice(helpers::sum::payload().1.as_slice());
         assert_eq!(bytes, expected);
     }
+
+    #[test]
+    fn round_bound_masks_flag() {
+        let (mut message, _) = helpers::message(helpers::update::payload);
+        let sk = crate::crypto::SigningKeyPair::generate().secret;
+        for round_bound_masks in [false, true].iter().copied() {
+            message.round_bound_masks = round_bound_masks;
+            let mut bytes = vec![0; message.buffer_length()];
+            message.to_bytes(&mut bytes, &sk);
+
+            let buffer = MessageBuffer::new(&bytes).unwrap();
+            assert_eq!(
+                buffer.flags().contains(Flags::ROUND_BOUND_MASKS),
+                round_bound_masks
+            );
+            assert!(!buffer.flags().contains(Flags::MULTIPART));
+            let parsed = Message::from_byte_slice(&bytes).unwrap();
+            assert_eq!(parsed, message);
+        }
+    }
+
+    /// Serializes and signs the message.
+    fn serialize(message: &Message) -> Vec<u8> {
+        let keys = crate::crypto::SigningKeyPair::generate();
+        let message = Message {
+            participant_pk: keys.public,
+            ..message.clone()
+        };
+        let mut bytes = vec![0; message.buffer_length()];
+        message.to_bytes(&mut bytes, &keys.secret);
+        bytes
+    }
+
+    #[test]
+    fn parse_without_extensions() {
+        // messages from before the extensions have a zero extensions length
+        let (message, bytes) = sum_message();
+        let buffer = MessageBuffer::new(&bytes).unwrap();
+        assert_eq!(buffer.extensions_length(), 0);
+        assert!(buffer.extensions().is_empty());
+        let parsed = Message::from_byte_slice(&bytes).unwrap();
+        assert!(parsed.extensions.is_empty());
+        assert_eq!(parsed, message);
+    }
+
+    #[test]
+    fn parse_message_extensions() {
+        let (mut message, _) = helpers::message(helpers::sum::payload);
+        message.signature = None;
+        message.set_declared_scalar(&Scalar::new(1_u8, 3_u8));
+        message.push_extension(0x7001, vec![0xab; 5]);
+        let bytes = serialize(&message);
+
+        let buffer = MessageBuffer::new(&bytes).unwrap();
+        assert_eq!(buffer.length(), bytes.len());
+        assert_eq!(buffer.extensions_length() as usize, 4 + 4 + 4 + 5);
+        assert_eq!(buffer.payload(), helpers::sum::payload().1.as_slice());
+        buffer.check_signature().unwrap();
+
+        // the unknown non-critical extension is skipped
+        let parsed = Message::from_byte_slice(&bytes).unwrap();
+        assert_eq!(parsed.extensions.len(), 1);
+        assert_eq!(parsed.extension(0x7001), None);
+        assert_eq!(
+            parsed.declared_scalar().unwrap(),
+            Some(Scalar::new(1_u8, 3_u8))
+        );
+        assert_eq!(parsed.payload, message.payload);
+        assert_eq!(Message::from_reader(bytes.as_slice()).unwrap(), parsed);
+
+        // a parser which doesn't know any extension still parses the message
+        let parsed = Message::from_byte_slice_with_extensions(&bytes, &[]).unwrap();
+        assert!(parsed.extensions.is_empty());
+        assert_eq!(parsed.payload, message.payload);
+    }
+
+    #[test]
+    fn reject_unknown_critical_extension() {
+        let (mut message, _) = helpers::message(helpers::sum::payload);
+        message.signature = None;
+        message.push_extension(ids::CRITICAL | 0x7001, vec![0xab; 5]);
+        let bytes = serialize(&message);
+
+        assert!(Message::from_byte_slice(&bytes).is_err());
+        assert!(Message::from_reader(bytes.as_slice()).is_err());
+        let parsed =
+            Message::from_byte_slice_with_extensions(&bytes, &[ids::CRITICAL | 0x7001]).unwrap();
+        assert_eq!(parsed.extension(ids::CRITICAL | 0x7001), Some(&[0xab; 5][..]));
+    }
+
+    #[test]
+    fn signature_covers_extensions() {
+        let (mut message, _) = helpers::message(helpers::sum::payload);
+        message.signature = None;
+        message.push_extension(0x7001, vec![0xab; 5]);
+        let mut bytes = serialize(&message);
+        MessageBuffer::new(&bytes).unwrap().check_signature().unwrap();
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(MessageBuffer::new(&bytes).unwrap().check_signature().is_err());
+    }
+
+    #[test]
+    fn externally_signed_message() {
+        let (mut message, _) = helpers::message(helpers::sum::payload);
+        message.signature = None;
+        let keys = crate::crypto::SigningKeyPair::generate();
+        message.participant_pk = keys.public;
+
+        let mut expected = vec![0; message.buffer_length()];
+        message.to_bytes(&mut expected, &keys.secret);
+
+        // simulate a signature produced outside of the library
+        let signature: Signature = sodiumoxide::crypto::sign::sign_detached(
+            &message.signing_payload(),
+            keys.secret.as_ref(),
+        )
+        .into();
+        let mut bytes = vec![0; message.buffer_length()];
+        message.to_bytes_with_signature(&mut bytes, &signature);
+        assert_eq!(bytes, expected);
+        MessageBuffer::new(&bytes).unwrap().check_signature().unwrap();
+
+        let coordinator_keys = crate::crypto::EncryptKeyPair::generate();
+        let sealed = message.seal_with_signature(&signature, &coordinator_keys.public);
+        let opened = coordinator_keys.secret.decrypt(&sealed, &coordinator_keys.public).unwrap();
+        assert_eq!(opened, expected);
+    }
+
+    #[test]
+    fn reject_invalid_extensions() {
+        let (message, mut bytes) = sum_message();
+        // the extensions length exceeds the message
+        let mut buffer = MessageBuffer::new_unchecked(&mut bytes);
+        buffer.set_extensions_length(message.buffer_length() as u16);
+        assert!(MessageBuffer::new(&bytes).is_err());
+
+        // truncated extension records
+        assert!(parse_extensions(&[0x00, 0x01, 0x00], ids::KNOWN).is_err());
+        assert!(parse_extensions(&[0x00, 0x01, 0x00, 0x02, 0xff], ids::KNOWN).is_err());
+        assert!(parse_extensions(&[0x00, 0x01, 0x00, 0x00], ids::KNOWN).is_ok());
+    }
+
+    #[test]
+    fn parse_from_reader() {
+        let message = multipart::message(4 + 112 * 100, 18 + 6 * 10_000);
+        let sk = crate::crypto::SigningKeyPair::generate().secret;
+        let mut bytes = vec![0; message.buffer_length()];
+        message.to_bytes(&mut bytes, &sk);
+
+        let expected = Message::from_byte_slice(&bytes).unwrap();
+        let parsed = Message::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(parsed, expected);
+
+        // truncated payload
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(Message::from_reader(truncated).is_err());
+
+        // the reader is not read beyond the message
+        let mut extended = bytes.clone();
+        extended.extend(vec![0xff; 10]);
+        let mut reader = extended.as_slice();
+        assert_eq!(Message::from_reader(&mut reader).unwrap(), expected);
+        assert_eq!(reader, &[0xff; 10][..]);
+    }
 }
@@ -12,17 +12,88 @@
 //! to XayNet during the update phase of the PET protocol. It contains the following values:
 //! - The sum signature proves the ineligibility of the participant for the sum task.
 //! - The update signature proves the eligibility of the participant for the update task.
+//! - The model checksum commits to the local model before it was masked. It can't be verified by
+//!   XayNet, but it is recorded for auditing.
 //! - The masked model is the encrypted local update to the global model, which is trained on the
 //!   local data of the update participant.
 //! - The local seed dictionary stores the encrypted mask seed, which generates the local mask for
 //!   the local model, which is encrypted by the ephemeral public keys of the sum participants.
+//! - The seed cipher identifies the [`SeedCipher`] which encrypted the mask seeds. The default is
+//!   a libsodium sealed box, other ciphers may have larger ciphertexts.
+//!
+//! [`SeedCipher`]: crate::mask::SeedCipher
+//!
+//! For large sum dictionaries, the [`UpdateWriter`] serializes an update payload while building the
+//! local seed dictionary on the fly, without holding it in memory.
+//! Likewise, [`Message::from_reader()`] parses a large update message while reading it, without
+//! holding its bytes in memory.
 //!
 //! # The sum2 message
 //! The [`Sum2`] message is an abstraction for the values which a sum participant communicates to
 //! XayNet during the sum2 phase of the PET protocol. It contains the following values:
 //! - The sum signature proves the eligibility of the participant for the sum task.
+//! - The seed dictionary version identifies the seed dictionary the global mask was derived from.
+//!   Payloads of the legacy layout lack it, see [`SUM2_PAYLOAD_VERSION`].
 //! - The global mask is used by XayNet to unmask the aggregated global model.
+//!
+//! # Extensions
+//! A [`Message`] may carry optional [`Extension`]s after its payload, which allow to add fields
+//! to the messages without breaking older parsers. Unknown extensions are skipped, unless they
+//! are marked as critical. The assigned extension ids are listed in the [`extension_ids`]
+//! registry.
+//!
+//! # Wire format
+//! The wire format is stable: a change breaks the compatibility between participants and
+//! coordinators of different versions. It is enforced by golden-file tests of a canonical sum,
+//! update and sum2 message and of a masked model. All integers are big endian, unless stated
+//! otherwise.
+//!
+//! A message is made of a header, the payload and the extensions:
+//!
+//! | bytes | field |
+//! |-------|-------|
+//! | 64 | signature of the remaining message by the participant |
+//! | 32 | participant public key |
+//! | 32 | coordinator public key |
+//! | 4 | length of the whole message |
+//! | 1 | tag: `1` sum, `2` update, `3` sum2 |
+//! | 1 | flags: bit 0 multipart, bit 1 round bound masks |
+//! | 2 | length of the extensions |
+//! | * | payload |
+//! | * | extensions, as described for [`Extension`] |
+//!
+//! The payload of a sum message is the sum signature (64 bytes) followed by the ephemeral public
+//! key (32 bytes).
+//!
+//! The payload of an update message (version 2) is:
+//!
+//! | bytes | field |
+//! |-------|-------|
+//! | 64 | sum signature |
+//! | 64 | update signature |
+//! | 1 | version of the update payload |
+//! | 32 | model checksum |
+//! | 1 | seed cipher id |
+//! | 2 | length of an encrypted mask seed |
+//! | * | masked model |
+//! | * | local seed dictionary |
+//!
+//! A seed dictionary starts with its total length, including the 4 bytes of the length field
+//! itself, followed by its entries in no particular order. An entry is a public key (32 bytes)
+//! followed by an encrypted mask seed of the length given in the update payload.
+//!
+//! The payload of a sum2 message is the sum signature (64 bytes), the seed dictionary version
+//! (8 bytes) and the model mask.
+//!
+//! A masked model resp. a mask is a vector followed by a unit. The vector is its masking
+//! configuration (group, data, bound and model type as 1 byte each), the number of its elements
+//! (4 bytes) and the elements. The unit is its masking configuration followed by a single
+//! element. An element is a little endian integer, zero-padded to the number of bytes per
+//! element of the masking configuration.
 
+pub(crate) mod extension;
+#[cfg(test)]
+mod golden;
 #[allow(clippy::module_inception)]
 pub(crate) mod message;
 pub(crate) mod payload;
@@ -30,6 +101,7 @@ pub(crate) mod traits;
 pub(crate) mod utils;
 
 pub use self::{
+    extension::{ids as extension_ids, Extension},
     message::{
         Flags,
         Message,
@@ -42,11 +114,11 @@ pub use self::{
     payload::{
         chunk::{Chunk, ChunkBuffer},
         sum::{Sum, SumBuffer},
-        sum2::{Sum2, Sum2Buffer},
-        update::{Update, UpdateBuffer},
+        sum2::{Sum2, Sum2Buffer, SUM2_PAYLOAD_VERSION},
+        update::{Update, UpdateBuffer, UpdateWriter, UpdateWriterError, UPDATE_PAYLOAD_VERSION},
         Payload,
     },
-    traits::{FromBytes, LengthValueBuffer, ToBytes},
+    traits::{seed_dict_length, seed_dict_length_for, FromBytes, LengthValueBuffer, ToBytes},
 };
 
 /// An error that signals a failure when trying to decrypt and parse a message.
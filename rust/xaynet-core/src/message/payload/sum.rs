@@ -182,9 +182,16 @@ pub struct Sum {
     pub ephm_pk: SumParticipantEphemeralPublicKey,
 }
 
+impl Sum {
+    /// Gets the length of a serialized sum payload.
+    pub fn serialized_length() -> usize {
+        EPHM_PK_RANGE.end
+    }
+}
+
 impl ToBytes for Sum {
     fn buffer_length(&self) -> usize {
-        EPHM_PK_RANGE.end
+        Self::serialized_length()
     }
 
     fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
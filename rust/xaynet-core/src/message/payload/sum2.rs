@@ -4,13 +4,16 @@
 //!
 //! [message module]: crate::message
 
-use std::ops::Range;
+use std::{convert::TryInto, ops::Range};
 
 use anyhow::{anyhow, Context};
 
 use crate::{
     crypto::ByteObject,
-    mask::object::{serialization::MaskObjectBuffer, MaskObject},
+    mask::{
+        config::MaskConfigPair,
+        object::{serialization::MaskObjectBuffer, MaskObject},
+    },
     message::{
         traits::{FromBytes, ToBytes},
         utils::range,
@@ -19,7 +22,19 @@ use crate::{
     ParticipantTaskSignature,
 };
 
+/// The version of the layout of [`Sum2`] payloads.
+///
+/// The version field takes the place of the first byte of the model mask in payloads of the
+/// legacy layout, which is the group type of its masking configuration. Hence, versions count
+/// down from `u8::MAX` to never collide with a group type. Version `255` introduced the version
+/// field itself and the seed dictionary version field.
+///
+/// Payloads of the legacy layout are still decoded, they lack a seed dictionary version.
+pub const SUM2_PAYLOAD_VERSION: u8 = u8::MAX;
+
 const SUM_SIGNATURE_RANGE: Range<usize> = range(0, ParticipantTaskSignature::LENGTH);
+const VERSION_FIELD: usize = SUM_SIGNATURE_RANGE.end;
+const SEED_DICT_VERSION_RANGE: Range<usize> = range(VERSION_FIELD + 1, 8);
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 /// A wrapper around a buffer that contains a [`Sum2`] message.
@@ -60,6 +75,13 @@ impl<T: AsRef<[u8]>> Sum2Buffer<T> {
                 SUM_SIGNATURE_RANGE.end
             ));
         }
+        if !self.is_legacy() && len < SEED_DICT_VERSION_RANGE.end {
+            return Err(anyhow!(
+                "invalid buffer length: {} < {}",
+                len,
+                SEED_DICT_VERSION_RANGE.end
+            ));
+        }
 
         // check the length of the mask field
         MaskObjectBuffer::new(&self.inner.as_ref()[self.model_mask_offset()..])
@@ -68,9 +90,36 @@ impl<T: AsRef<[u8]>> Sum2Buffer<T> {
         Ok(())
     }
 
+    /// Checks whether the payload has the legacy layout, which lacks the version fields.
+    fn is_legacy(&self) -> bool {
+        self.inner.as_ref().get(VERSION_FIELD) != Some(&SUM2_PAYLOAD_VERSION)
+    }
+
     /// Gets the offset of the model mask field.
     fn model_mask_offset(&self) -> usize {
-        SUM_SIGNATURE_RANGE.end
+        if self.is_legacy() {
+            SUM_SIGNATURE_RANGE.end
+        } else {
+            SEED_DICT_VERSION_RANGE.end
+        }
+    }
+
+    /// Gets the seed dictionary version field.
+    ///
+    /// This is `None` for payloads of the legacy layout, which lack the field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn seed_dict_version(&self) -> Option<u64> {
+        if self.is_legacy() {
+            return None;
+        }
+        // UNWRAP_SAFE: the slice is exactly 8 bytes long
+        Some(u64::from_be_bytes(
+            self.inner.as_ref()[SEED_DICT_VERSION_RANGE]
+                .try_into()
+                .unwrap(),
+        ))
     }
 }
 
@@ -83,6 +132,26 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Sum2Buffer<T> {
         &mut self.inner.as_mut()[SUM_SIGNATURE_RANGE]
     }
 
+    /// Sets the version field.
+    ///
+    /// The model mask field starts at the version field for any value other than
+    /// [`SUM2_PAYLOAD_VERSION`], as in payloads of the legacy layout.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn set_version(&mut self, value: u8) {
+        self.inner.as_mut()[VERSION_FIELD] = value;
+    }
+
+    /// Sets the seed dictionary version field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn set_seed_dict_version(&mut self, value: u64) {
+        let bytes = value.to_be_bytes();
+        self.inner.as_mut()[SEED_DICT_VERSION_RANGE].copy_from_slice(&bytes[..]);
+    }
+
     /// Gets a mutable reference to the model mask field.
     ///
     /// # Panics
@@ -122,18 +191,49 @@ pub struct Sum2 {
     /// This is used to determine whether a participant is selected for the sum task.
     pub sum_signature: ParticipantTaskSignature,
 
+    /// The version of the seed dictionary the model mask was derived from.
+    ///
+    /// Masks derived from an outdated seed dictionary are rejected by the coordinator. This is
+    /// `None` for payloads of the legacy layout, see [`SUM2_PAYLOAD_VERSION`].
+    pub seed_dict_version: Option<u64>,
+
     /// A model mask computed by the participant.
     pub model_mask: MaskObject,
 }
 
+impl Sum2 {
+    /// Gets the length of a serialized sum2 payload with the given masking configurations and
+    /// model length.
+    pub fn serialized_length(config: MaskConfigPair, model_length: usize) -> usize {
+        SEED_DICT_VERSION_RANGE.end + MaskObject::serialized_length(config, model_length)
+    }
+
+    /// Gets the length of the fields which precede the model mask.
+    fn header_length(&self) -> usize {
+        if self.seed_dict_version.is_some() {
+            SEED_DICT_VERSION_RANGE.end
+        } else {
+            SUM_SIGNATURE_RANGE.end
+        }
+    }
+}
+
 impl ToBytes for Sum2 {
     fn buffer_length(&self) -> usize {
-        SUM_SIGNATURE_RANGE.end + self.model_mask.buffer_length()
+        self.header_length() + self.model_mask.buffer_length()
     }
 
     fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
         let mut writer = Sum2Buffer::new_unchecked(buffer.as_mut());
         self.sum_signature.to_bytes(&mut writer.sum_signature_mut());
+        match self.seed_dict_version {
+            Some(seed_dict_version) => {
+                writer.set_version(SUM2_PAYLOAD_VERSION);
+                writer.set_seed_dict_version(seed_dict_version);
+            }
+            // the group type of the mask takes the place of the version field
+            None => writer.set_version(self.model_mask.vect.config.group_type as u8),
+        }
         self.model_mask.to_bytes(&mut writer.model_mask_mut());
     }
 }
@@ -144,6 +244,7 @@ impl FromBytes for Sum2 {
         Ok(Self {
             sum_signature: ParticipantTaskSignature::from_byte_slice(&reader.sum_signature())
                 .context("invalid sum signature")?,
+            seed_dict_version: reader.seed_dict_version(),
             model_mask: MaskObject::from_byte_slice(&reader.model_mask())
                 .context("invalid mask")?,
         })
@@ -152,14 +253,60 @@ impl FromBytes for Sum2 {
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
         iter: &mut I,
     ) -> Result<Self, DecodeError> {
+        let sum_signature =
+            ParticipantTaskSignature::from_byte_stream(iter).context("invalid sum signature")?;
+        let version = iter
+            .next()
+            .ok_or_else(|| anyhow!("missing sum2 payload version"))?;
+        let (seed_dict_version, model_mask) = if version == SUM2_PAYLOAD_VERSION {
+            let seed_dict_version =
+                u64::from_byte_stream(iter).context("invalid seed dictionary version")?;
+            let model_mask = MaskObject::from_byte_stream(iter).context("invalid mask object")?;
+            (Some(seed_dict_version), model_mask)
+        } else {
+            // the byte is the first one of the mask in payloads of the legacy layout
+            let mut iter = Prepended {
+                first: Some(version),
+                rest: iter,
+            };
+            let model_mask =
+                MaskObject::from_byte_stream(&mut iter).context("invalid mask object")?;
+            (None, model_mask)
+        };
         Ok(Self {
-            sum_signature: ParticipantTaskSignature::from_byte_stream(iter)
-                .context("invalid sum signature")?,
-            model_mask: MaskObject::from_byte_stream(iter).context("invalid mask object")?,
+            sum_signature,
+            seed_dict_version,
+            model_mask,
         })
     }
 }
 
+/// A byte stream which yields a byte that was taken from the stream already, before the rest of
+/// the stream.
+struct Prepended<'a, I> {
+    first: Option<u8>,
+    rest: &'a mut I,
+}
+
+impl<I: Iterator<Item = u8> + ExactSizeIterator> Iterator for Prepended<'_, I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        self.first.take().or_else(|| self.rest.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<I: Iterator<Item = u8> + ExactSizeIterator> ExactSizeIterator for Prepended<'_, I> {
+    fn len(&self) -> usize {
+        self.first.iter().count() + self.rest.len()
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::testutils::messages::sum2 as helpers;
@@ -171,6 +318,10 @@ pub mod tests {
         let bytes = helpers::payload().1;
         let buffer = Sum2Buffer::new(&bytes).unwrap();
         assert_eq!(buffer.sum_signature(), &helpers::sum_task_signature().1[..]);
+        assert_eq!(
+            buffer.seed_dict_version(),
+            Some(helpers::seed_dict_version().0)
+        );
 
         let expected_mask = helpers::mask_object().1;
         let expected_length = expected_mask.len();
@@ -180,13 +331,15 @@ pub mod tests {
 
     #[test]
     fn buffer_write() {
-        // length = 64 (signature) + 42 (mask) = 106
-        let mut bytes = vec![0xff; 106];
+        // length = 64 (signature) + 1 (version) + 8 (seed dict version) + 42 (mask) = 115
+        let mut bytes = vec![0xff; 115];
         {
             let mut buffer = Sum2Buffer::new_unchecked(&mut bytes);
             buffer
                 .sum_signature_mut()
                 .copy_from_slice(&helpers::sum_task_signature().1[..]);
+            buffer.set_version(SUM2_PAYLOAD_VERSION);
+            buffer.set_seed_dict_version(helpers::seed_dict_version().0);
             let mask = helpers::mask_object().1;
             buffer.model_mask_mut()[..mask.len()].copy_from_slice(&mask[..]);
         }
@@ -203,6 +356,17 @@ pub mod tests {
         assert_eq!(buf, bytes);
     }
 
+    #[test]
+    fn serialized_length() {
+        let (sum2, bytes) = helpers::payload();
+        let config = MaskConfigPair {
+            vect: sum2.model_mask.vect.config,
+            unit: sum2.model_mask.unit.config,
+        };
+        let model_length = sum2.model_mask.vect.data.len();
+        assert_eq!(Sum2::serialized_length(config, model_length), bytes.len());
+    }
+
     #[test]
     fn decode() {
         let (sum2, bytes) = helpers::payload();
@@ -216,4 +380,21 @@ pub mod tests {
         let parsed = Sum2::from_byte_stream(&mut bytes.into_iter()).unwrap();
         assert_eq!(parsed, sum2);
     }
+
+    #[test]
+    fn legacy_layout() {
+        let (sum2, bytes) = helpers::legacy_payload();
+        let buffer = Sum2Buffer::new(&bytes).unwrap();
+        assert_eq!(buffer.seed_dict_version(), None);
+        assert_eq!(buffer.model_mask(), &helpers::mask_object().1[..]);
+
+        assert_eq!(Sum2::from_byte_slice(&bytes).unwrap(), sum2);
+        let parsed = Sum2::from_byte_stream(&mut bytes.clone().into_iter()).unwrap();
+        assert_eq!(parsed, sum2);
+
+        assert_eq!(sum2.buffer_length(), bytes.len());
+        let mut buf = vec![0xff; sum2.buffer_length()];
+        sum2.to_bytes(&mut buf);
+        assert_eq!(buf, bytes);
+    }
 }
@@ -4,25 +4,50 @@
 //!
 //! [message module]: crate::message
 
-use std::ops::Range;
+use std::{convert::TryInto, ops::Range};
 
 use anyhow::{anyhow, Context};
+use thiserror::Error;
 
 use crate::{
-    crypto::ByteObject,
-    mask::object::{serialization::MaskObjectBuffer, MaskObject},
+    crypto::{ByteObject, Sha256},
+    mask::{
+        config::MaskConfigPair,
+        object::{serialization::MaskObjectBuffer, MaskObject},
+        seed::{EncryptedMaskSeed, MaskSeed, SealedBox, SeedCipher},
+    },
     message::{
-        traits::{FromBytes, LengthValueBuffer, ToBytes},
+        traits::{seed_dict_length, FromBytes, LengthValueBuffer, ToBytes, ENTRY_LENGTH},
         utils::range,
         DecodeError,
     },
     LocalSeedDict,
     ParticipantTaskSignature,
+    SumParticipantEphemeralPublicKey,
+    SumParticipantPublicKey,
 };
 
+/// The version of the layout of [`Update`] payloads.
+///
+/// Version `1` introduced the version field itself and the model checksum field. Version `2`
+/// introduced the seed cipher and seed length fields, which allow to encrypt the mask seeds with
+/// other [`SeedCipher`]s than the default [`SealedBox`].
+///
+/// Payloads of version `1` are still decoded, their seeds are sealed boxes. Payloads of other
+/// versions are rejected.
+pub const UPDATE_PAYLOAD_VERSION: u8 = 2;
+
+/// The version of the layout of [`Update`] payloads without the seed cipher and seed length
+/// fields.
+const LEGACY_UPDATE_PAYLOAD_VERSION: u8 = 1;
+
 const SUM_SIGNATURE_RANGE: Range<usize> = range(0, ParticipantTaskSignature::LENGTH);
 const UPDATE_SIGNATURE_RANGE: Range<usize> =
     range(SUM_SIGNATURE_RANGE.end, ParticipantTaskSignature::LENGTH);
+const VERSION_FIELD: usize = UPDATE_SIGNATURE_RANGE.end;
+const MODEL_CHECKSUM_RANGE: Range<usize> = range(VERSION_FIELD + 1, Sha256::LENGTH);
+const SEED_CIPHER_FIELD: usize = MODEL_CHECKSUM_RANGE.end;
+const SEED_LENGTH_RANGE: Range<usize> = range(SEED_CIPHER_FIELD + 1, 2);
 
 #[derive(Clone, Debug)]
 /// A wrapper around a buffer that contains an [`Update`] message.
@@ -57,12 +82,28 @@ impl<T: AsRef<[u8]>> UpdateBuffer<T> {
     pub fn check_buffer_length(&self) -> Result<(), DecodeError> {
         let len = self.inner.as_ref().len();
         // First, check the fixed size portion of the
-        // header. UPDATE_SIGNATURE_RANGE is the last field
-        if len < UPDATE_SIGNATURE_RANGE.end {
+        // header. MODEL_CHECKSUM_RANGE is the last field
+        if len < MODEL_CHECKSUM_RANGE.end {
             return Err(anyhow!(
                 "invalid buffer length: {} < {}",
                 len,
-                UPDATE_SIGNATURE_RANGE.end
+                MODEL_CHECKSUM_RANGE.end
+            ));
+        }
+
+        let version = self.version();
+        if version != UPDATE_PAYLOAD_VERSION && version != LEGACY_UPDATE_PAYLOAD_VERSION {
+            return Err(anyhow!(
+                "unsupported update payload version: {} != {}",
+                version,
+                UPDATE_PAYLOAD_VERSION
+            ));
+        }
+        if !self.is_legacy() && len < SEED_LENGTH_RANGE.end {
+            return Err(anyhow!(
+                "invalid buffer length: {} < {}",
+                len,
+                SEED_LENGTH_RANGE.end
             ));
         }
 
@@ -77,9 +118,56 @@ impl<T: AsRef<[u8]>> UpdateBuffer<T> {
         Ok(())
     }
 
+    /// Gets the version field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn version(&self) -> u8 {
+        self.inner.as_ref()[VERSION_FIELD]
+    }
+
+    /// Gets the seed cipher field.
+    ///
+    /// This is the [`SealedBox`] cipher for payloads of the legacy version, which lack the field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn seed_cipher(&self) -> u8 {
+        if self.is_legacy() {
+            SealedBox::ID
+        } else {
+            self.inner.as_ref()[SEED_CIPHER_FIELD]
+        }
+    }
+
+    /// Gets the seed length field, i.e. the length of the encrypted seeds of the local seed
+    /// dictionary.
+    ///
+    /// This is the length of a sealed box for payloads of the legacy version, which lack the field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn seed_length(&self) -> usize {
+        if self.is_legacy() {
+            EncryptedMaskSeed::LENGTH
+        } else {
+            // safe unwrap: the slice has exactly two bytes
+            u16::from_be_bytes(self.inner.as_ref()[SEED_LENGTH_RANGE].try_into().unwrap()) as usize
+        }
+    }
+
+    /// Checks whether the payload has the legacy layout.
+    fn is_legacy(&self) -> bool {
+        self.version() == LEGACY_UPDATE_PAYLOAD_VERSION
+    }
+
     /// Gets the offset of the masked model field.
     fn masked_model_offset(&self) -> usize {
-        UPDATE_SIGNATURE_RANGE.end
+        if self.is_legacy() {
+            MODEL_CHECKSUM_RANGE.end
+        } else {
+            SEED_LENGTH_RANGE.end
+        }
     }
 
     /// Gets the offset of the local seed dictionary field.
@@ -110,6 +198,14 @@ impl<'a, T: AsRef<[u8]> + ?Sized> UpdateBuffer<&'a T> {
         &self.inner.as_ref()[UPDATE_SIGNATURE_RANGE]
     }
 
+    /// Gets the model checksum field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn model_checksum(&self) -> &'a [u8] {
+        &self.inner.as_ref()[MODEL_CHECKSUM_RANGE]
+    }
+
     /// Gets a slice that starts at the beginning of the masked model field.
     ///
     /// # Panics
@@ -146,6 +242,40 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> UpdateBuffer<T> {
         &mut self.inner.as_mut()[UPDATE_SIGNATURE_RANGE]
     }
 
+    /// Sets the version field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn set_version(&mut self, value: u8) {
+        self.inner.as_mut()[VERSION_FIELD] = value;
+    }
+
+    /// Gets a mutable reference to the model checksum field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn model_checksum_mut(&mut self) -> &mut [u8] {
+        &mut self.inner.as_mut()[MODEL_CHECKSUM_RANGE]
+    }
+
+    /// Sets the seed cipher field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before or if the payload
+    /// has the legacy layout.
+    pub fn set_seed_cipher(&mut self, value: u8) {
+        self.inner.as_mut()[SEED_CIPHER_FIELD] = value;
+    }
+
+    /// Sets the seed length field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before or if the payload
+    /// has the legacy layout.
+    pub fn set_seed_length(&mut self, value: u16) {
+        self.inner.as_mut()[SEED_LENGTH_RANGE].copy_from_slice(&value.to_be_bytes());
+    }
+
     /// Gets a mutable slice that starts at the beginning of the masked model field.
     ///
     /// # Panics
@@ -178,19 +308,56 @@ pub struct Update {
     ///
     /// This is used to determine whether a participant is selected for the update task.
     pub update_signature: ParticipantTaskSignature,
+    /// The checksum of the local model before it was masked, see [`Model::checksum()`].
+    ///
+    /// The coordinator can't verify the checksum, but it records the checksum for auditing.
+    ///
+    /// [`Model::checksum()`]: crate::mask::Model::checksum
+    pub model_checksum: Sha256,
+    /// The identifier of the [`SeedCipher`] which encrypted the seeds of the `local_seed_dict`.
+    ///
+    /// The coordinator doesn't interpret the identifier, it tells the sum participants how to
+    /// decrypt the seeds.
+    pub seed_cipher: u8,
     /// A model trained by an update participant.
     ///
     /// The model is masked with randomness derived from the participant seed.
     pub masked_model: MaskObject,
     /// A dictionary that contains the seed used to mask `masked_model`.
     ///
-    /// The seed is encrypted with the ephemeral public key of each sum participant.
+    /// The seed is encrypted with the ephemeral public key of each sum participant. All seeds
+    /// must have the same length.
     pub local_seed_dict: LocalSeedDict,
 }
 
+impl Update {
+    /// Gets the length of a serialized update payload with the given masking configurations,
+    /// model length and number of sum participants, whose seeds are encrypted with the default
+    /// [`SealedBox`] cipher.
+    pub fn serialized_length(
+        config: MaskConfigPair,
+        model_length: usize,
+        sum_participants: usize,
+    ) -> usize {
+        SEED_LENGTH_RANGE.end
+            + MaskObject::serialized_length(config, model_length)
+            + seed_dict_length(sum_participants)
+    }
+
+    /// Gets the length of the encrypted seeds of the local seed dictionary.
+    ///
+    /// An empty dictionary has the length of the default [`SealedBox`] cipher.
+    fn seed_length(&self) -> usize {
+        self.local_seed_dict
+            .values()
+            .next()
+            .map_or(EncryptedMaskSeed::LENGTH, EncryptedMaskSeed::len)
+    }
+}
+
 impl ToBytes for Update {
     fn buffer_length(&self) -> usize {
-        UPDATE_SIGNATURE_RANGE.end
+        SEED_LENGTH_RANGE.end
             + self.masked_model.buffer_length()
             + self.local_seed_dict.buffer_length()
     }
@@ -200,6 +367,11 @@ impl ToBytes for Update {
         self.sum_signature.to_bytes(&mut writer.sum_signature_mut());
         self.update_signature
             .to_bytes(&mut writer.update_signature_mut());
+        writer.set_version(UPDATE_PAYLOAD_VERSION);
+        self.model_checksum
+            .to_bytes(&mut writer.model_checksum_mut());
+        writer.set_seed_cipher(self.seed_cipher);
+        writer.set_seed_length(self.seed_length() as u16);
         self.masked_model.to_bytes(&mut writer.masked_model_mut());
         self.local_seed_dict
             .to_bytes(&mut writer.local_seed_dict_mut());
@@ -214,32 +386,172 @@ impl FromBytes for Update {
                 .context("invalid sum signature")?,
             update_signature: ParticipantTaskSignature::from_byte_slice(&reader.update_signature())
                 .context("invalid update signature")?,
+            model_checksum: Sha256::from_byte_slice(&reader.model_checksum())
+                .context("invalid model checksum")?,
+            seed_cipher: reader.seed_cipher(),
             masked_model: MaskObject::from_byte_slice(&reader.masked_model())
                 .context("invalid masked model")?,
-            local_seed_dict: LocalSeedDict::from_byte_slice(&reader.local_seed_dict())
-                .context("invalid local seed dictionary")?,
+            local_seed_dict: LocalSeedDict::from_byte_slice_with_seed_length(
+                &reader.local_seed_dict(),
+                reader.seed_length(),
+            )
+            .context("invalid local seed dictionary")?,
         })
     }
 
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
         iter: &mut I,
     ) -> Result<Self, DecodeError> {
+        let sum_signature =
+            ParticipantTaskSignature::from_byte_stream(iter).context("invalid sum signature")?;
+        let update_signature =
+            ParticipantTaskSignature::from_byte_stream(iter).context("invalid update signature")?;
+        let version = iter
+            .next()
+            .ok_or_else(|| anyhow!("missing update payload version"))?;
+        if version != UPDATE_PAYLOAD_VERSION && version != LEGACY_UPDATE_PAYLOAD_VERSION {
+            return Err(anyhow!(
+                "unsupported update payload version: {} != {}",
+                version,
+                UPDATE_PAYLOAD_VERSION
+            ));
+        }
+        let model_checksum = Sha256::from_byte_stream(iter).context("invalid model checksum")?;
+        let (seed_cipher, seed_length) = if version == LEGACY_UPDATE_PAYLOAD_VERSION {
+            (SealedBox::ID, EncryptedMaskSeed::LENGTH)
+        } else {
+            let seed_cipher = iter.next().ok_or_else(|| anyhow!("missing seed cipher"))?;
+            let seed_length = u16::from_byte_stream(iter).context("invalid seed length")?;
+            (seed_cipher, seed_length as usize)
+        };
         Ok(Self {
-            sum_signature: ParticipantTaskSignature::from_byte_stream(iter)
-                .context("invalid sum signature")?,
-            update_signature: ParticipantTaskSignature::from_byte_stream(iter)
-                .context("invalid update signature")?,
+            sum_signature,
+            update_signature,
+            model_checksum,
+            seed_cipher,
             masked_model: MaskObject::from_byte_stream(iter).context("invalid masked model")?,
-            local_seed_dict: LocalSeedDict::from_byte_stream(iter)
+            local_seed_dict: LocalSeedDict::from_byte_stream_with_seed_length(iter, seed_length)
                 .context("invalid local seed dictionary")?,
         })
     }
 }
 
+/// Errors of the streaming serialization of an update payload.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UpdateWriterError {
+    #[error("expected {expected} sum participants, but got only {actual}")]
+    MissingEntries { expected: usize, actual: usize },
+    #[error("expected {expected} sum participants, but got more")]
+    ExcessEntries { expected: usize },
+}
+
+#[derive(Clone, Debug)]
+/// A serializer of [`Update`] payloads which builds the local seed dictionary on the fly.
+///
+/// Serializing an [`Update`] requires to hold the whole local seed dictionary in memory, which is
+/// built from the whole sum dictionary. Instead, this serializer encrypts the mask seed for each
+/// sum participant while consuming the entries of the sum dictionary and writes them to the
+/// buffer one after another. Since the local seed dictionary is unordered, the entries keep the
+/// order in which they are consumed. The seeds are encrypted with the default [`SealedBox`]
+/// cipher.
+pub struct UpdateWriter<'a> {
+    sum_signature: ParticipantTaskSignature,
+    update_signature: ParticipantTaskSignature,
+    model_checksum: Sha256,
+    masked_model: &'a MaskObject,
+    mask_seed: &'a MaskSeed,
+    expected_count: usize,
+}
+
+impl<'a> UpdateWriter<'a> {
+    /// Creates a serializer of an update payload whose local seed dictionary has `expected_count`
+    /// entries, i.e. the number of sum participants.
+    pub fn new(
+        sum_signature: ParticipantTaskSignature,
+        update_signature: ParticipantTaskSignature,
+        model_checksum: Sha256,
+        masked_model: &'a MaskObject,
+        mask_seed: &'a MaskSeed,
+        expected_count: usize,
+    ) -> Self {
+        Self {
+            sum_signature,
+            update_signature,
+            model_checksum,
+            masked_model,
+            mask_seed,
+            expected_count,
+        }
+    }
+
+    /// The length of the buffer for encoding the update payload.
+    pub fn buffer_length(&self) -> usize {
+        SEED_LENGTH_RANGE.end
+            + self.masked_model.buffer_length()
+            + seed_dict_length(self.expected_count)
+    }
+
+    /// Serializes the update payload in the given buffer, where the local seed dictionary is built
+    /// from the entries of the `sum_dict`.
+    ///
+    /// The entries are not checked for duplicates, which the coordinator rejects.
+    ///
+    /// # Errors
+    /// Fails if the `sum_dict` doesn't have exactly the expected number of entries. The buffer is
+    /// left partially written in this case.
+    ///
+    /// # Panics
+    /// This method may panic if the given buffer is too small. Thus, [`buffer_length()`] must be
+    /// called prior to calling this, and a large enough buffer must be provided.
+    ///
+    /// [`buffer_length()`]: UpdateWriter::buffer_length
+    pub fn write<T, I>(&self, sum_dict: I, buffer: &mut T) -> Result<(), UpdateWriterError>
+    where
+        T: AsMut<[u8]> + AsRef<[u8]>,
+        I: IntoIterator<Item = (SumParticipantPublicKey, SumParticipantEphemeralPublicKey)>,
+    {
+        let mut writer = UpdateBuffer::new_unchecked(buffer.as_mut());
+        self.sum_signature.to_bytes(&mut writer.sum_signature_mut());
+        self.update_signature
+            .to_bytes(&mut writer.update_signature_mut());
+        writer.set_version(UPDATE_PAYLOAD_VERSION);
+        self.model_checksum
+            .to_bytes(&mut writer.model_checksum_mut());
+        writer.set_seed_cipher(SealedBox::ID);
+        writer.set_seed_length(EncryptedMaskSeed::LENGTH as u16);
+        self.masked_model.to_bytes(&mut writer.masked_model_mut());
+
+        let mut local_seed_dict = LengthValueBuffer::new_unchecked(writer.local_seed_dict_mut());
+        let length = seed_dict_length(self.expected_count);
+        local_seed_dict.set_length(length);
+
+        let expected = self.expected_count;
+        let mut sum_dict = sum_dict.into_iter();
+        let entries = local_seed_dict.value_mut().chunks_exact_mut(ENTRY_LENGTH);
+        for (actual, entry) in entries.enumerate() {
+            let (pk, ephm_pk) = sum_dict
+                .next()
+                .ok_or(UpdateWriterError::MissingEntries { expected, actual })?;
+            let (key, value) = entry.split_at_mut(SumParticipantPublicKey::LENGTH);
+            key.copy_from_slice(pk.as_slice());
+            value.copy_from_slice(self.mask_seed.encrypt(&ephm_pk).as_slice());
+        }
+        if sum_dict.next().is_some() {
+            return Err(UpdateWriterError::ExcessEntries { expected });
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::testutils::messages::update as helpers;
+    use crate::{
+        crypto::{EncryptKeyPair, SigningKeyPair},
+        mask::seed::cipher::PaddedSealedBox,
+        testutils::{messages::update as helpers, multipart},
+        SumDict,
+    };
 
     #[test]
     fn buffer_read() {
@@ -253,6 +565,13 @@ pub mod tests {
             buffer.update_signature(),
             helpers::update_task_signature().1.as_slice()
         );
+        assert_eq!(buffer.version(), UPDATE_PAYLOAD_VERSION);
+        assert_eq!(
+            buffer.model_checksum(),
+            helpers::model_checksum().1.as_slice()
+        );
+        assert_eq!(buffer.seed_cipher(), SealedBox::ID);
+        assert_eq!(buffer.seed_length(), EncryptedMaskSeed::LENGTH);
         let expected = helpers::mask_object().1;
         assert_eq!(&buffer.masked_model()[..expected.len()], &expected[..]);
         assert_eq!(buffer.local_seed_dict(), &helpers::local_seed_dict().1[..]);
@@ -266,6 +585,9 @@ pub mod tests {
         let mut bytes = vec![];
         bytes.extend(helpers::sum_task_signature().1);
         bytes.extend(helpers::update_task_signature().1);
+        bytes.push(UPDATE_PAYLOAD_VERSION);
+        bytes.extend(helpers::model_checksum().1);
+        bytes.extend(helpers::seed_cipher().1);
         bytes.extend(helpers::mask_object().1);
         bytes.extend(invalid);
 
@@ -277,6 +599,15 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn decode_unsupported_version() {
+        let (_, mut bytes) = helpers::payload();
+        bytes[VERSION_FIELD] = UPDATE_PAYLOAD_VERSION + 1;
+        assert!(UpdateBuffer::new(&bytes).is_err());
+        assert!(Update::from_byte_slice(&bytes).is_err());
+        assert!(Update::from_byte_stream(&mut bytes.into_iter()).is_err());
+    }
+
     #[test]
     fn decode() {
         let (update, bytes) = helpers::payload();
@@ -284,6 +615,73 @@ pub mod tests {
         assert_eq!(parsed, update);
     }
 
+    #[test]
+    fn decode_legacy_version() {
+        // a payload of version 1 lacks the seed cipher and seed length fields
+        let mut bytes = helpers::sum_task_signature().1;
+        bytes.extend(helpers::update_task_signature().1);
+        bytes.push(LEGACY_UPDATE_PAYLOAD_VERSION);
+        bytes.extend(helpers::model_checksum().1);
+        bytes.extend(helpers::mask_object().1);
+        bytes.extend(helpers::local_seed_dict().1);
+
+        let buffer = UpdateBuffer::new(&bytes).unwrap();
+        assert_eq!(buffer.seed_cipher(), SealedBox::ID);
+        assert_eq!(buffer.seed_length(), EncryptedMaskSeed::LENGTH);
+        assert_eq!(buffer.local_seed_dict(), &helpers::local_seed_dict().1[..]);
+
+        let (update, _) = helpers::payload();
+        assert_eq!(Update::from_byte_slice(&bytes).unwrap(), update);
+        assert_eq!(
+            Update::from_byte_stream(&mut bytes.into_iter()).unwrap(),
+            update
+        );
+    }
+
+    #[test]
+    fn decode_zero_seed_length() {
+        let (_, mut bytes) = helpers::payload();
+        bytes[SEED_LENGTH_RANGE].copy_from_slice(&[0x00, 0x00]);
+        assert!(Update::from_byte_slice(&bytes).is_err());
+        assert!(Update::from_byte_stream(&mut bytes.into_iter()).is_err());
+    }
+
+    #[test]
+    fn encode_with_cipher() {
+        let (sum_dict, ephm_keys) = sum_dict_and_keys(3);
+        let mask_seed = MaskSeed::generate();
+        let (update, _) = helpers::payload();
+        let update = Update {
+            seed_cipher: PaddedSealedBox::ID,
+            local_seed_dict: LocalSeedDict::new_with::<PaddedSealedBox>(&sum_dict, &mask_seed),
+            ..update
+        };
+        let mut bytes = vec![0xff; update.buffer_length()];
+        update.to_bytes(&mut bytes);
+
+        let buffer = UpdateBuffer::new(&bytes).unwrap();
+        assert_eq!(buffer.version(), UPDATE_PAYLOAD_VERSION);
+        assert_eq!(buffer.seed_cipher(), PaddedSealedBox::ID);
+        assert_eq!(buffer.seed_length(), PaddedSealedBox::CIPHERTEXT_LENGTH);
+
+        let parsed = Update::from_byte_slice(&bytes).unwrap();
+        assert_eq!(parsed, update);
+        assert_eq!(
+            Update::from_byte_stream(&mut bytes.into_iter()).unwrap(),
+            update
+        );
+        for keys in ephm_keys {
+            let (pk, _) = sum_dict
+                .iter()
+                .find(|(_, ephm_pk)| **ephm_pk == keys.public)
+                .unwrap();
+            let seed = parsed.local_seed_dict[pk]
+                .decrypt_with::<PaddedSealedBox>(&keys.public, &keys.secret)
+                .unwrap();
+            assert_eq!(seed, mask_seed);
+        }
+    }
+
     #[test]
     fn stream_parse() {
         let (update, bytes) = helpers::payload();
@@ -291,6 +689,21 @@ pub mod tests {
         assert_eq!(parsed, update);
     }
 
+    #[test]
+    fn parse_from_reader() {
+        let update = multipart::update(4 + 112 * 1_000, 18 + 6 * 100_000);
+        let mut bytes = vec![0; update.buffer_length()];
+        update.to_bytes(&mut bytes);
+
+        let expected = Update::from_byte_slice(&bytes).unwrap();
+        let parsed = Update::from_reader(bytes.as_slice(), bytes.len()).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed, update);
+
+        assert!(Update::from_reader(&bytes[..bytes.len() - 1], bytes.len()).is_err());
+        assert!(Update::from_reader(bytes.as_slice(), bytes.len() - 1).is_err());
+    }
+
     #[test]
     fn encode() {
         let (update, bytes) = helpers::payload();
@@ -302,11 +715,119 @@ pub mod tests {
         // sorted.
         //
         // First compute the offset at which the local seed dict value
-        // starts: two signature (64 bytes), the masked model (32
-        // bytes), the length field (4 bytes), the masked scalar (10 bytes)
-        let offset = 64 * 2 + 32 + 4 + 10;
+        // starts: two signature (64 bytes), the version (1 byte), the
+        // model checksum (32 bytes), the seed cipher (1 byte), the seed
+        // length (2 bytes), the masked model (32 bytes), the length
+        // field (4 bytes), the masked scalar (10 bytes)
+        let offset = 64 * 2 + 1 + 32 + 3 + 32 + 4 + 10;
         // Sort the end of the buffer
         (&mut buf[offset..]).sort_unstable();
         assert_eq!(buf, bytes);
     }
+
+    #[test]
+    fn serialized_length() {
+        let (update, bytes) = helpers::payload();
+        let config = MaskConfigPair {
+            vect: update.masked_model.vect.config,
+            unit: update.masked_model.unit.config,
+        };
+        let model_length = update.masked_model.vect.data.len();
+        let sum_participants = update.local_seed_dict.len();
+        assert_eq!(
+            Update::serialized_length(config, model_length, sum_participants),
+            bytes.len()
+        );
+    }
+
+    fn sum_dict_and_keys(len: usize) -> (SumDict, Vec<EncryptKeyPair>) {
+        let ephm_keys = (0..len)
+            .map(|_| EncryptKeyPair::generate())
+            .collect::<Vec<_>>();
+        let sum_dict = ephm_keys
+            .iter()
+            .map(|keys| (SigningKeyPair::generate().public, keys.public))
+            .collect();
+        (sum_dict, ephm_keys)
+    }
+
+    #[test]
+    fn encode_streaming() {
+        let (sum_dict, ephm_keys) = sum_dict_and_keys(5);
+        let mask_seed = MaskSeed::generate();
+        let (update, _) = helpers::payload();
+        let update = Update {
+            local_seed_dict: LocalSeedDict::new(&sum_dict, &mask_seed),
+            ..update
+        };
+        let mut expected = vec![0xff; update.buffer_length()];
+        update.to_bytes(&mut expected);
+
+        let writer = UpdateWriter::new(
+            update.sum_signature,
+            update.update_signature,
+            update.model_checksum,
+            &update.masked_model,
+            &mask_seed,
+            sum_dict.len(),
+        );
+        assert_eq!(writer.buffer_length(), update.buffer_length());
+        let mut buf = vec![0xff; writer.buffer_length()];
+        writer.write(sum_dict.clone(), &mut buf).unwrap();
+
+        // the encryption of the seeds is randomized, hence only the bytes up to the entries of the
+        // local seed dictionary are the same
+        let offset = buf.len() - sum_dict.len() * ENTRY_LENGTH;
+        assert_eq!(buf[..offset], expected[..offset]);
+        let parsed = Update::from_byte_slice(&buf).unwrap();
+        assert_eq!(parsed.sum_signature, update.sum_signature);
+        assert_eq!(parsed.update_signature, update.update_signature);
+        assert_eq!(parsed.model_checksum, update.model_checksum);
+        assert_eq!(parsed.masked_model, update.masked_model);
+        assert_eq!(parsed.local_seed_dict.len(), sum_dict.len());
+        for keys in ephm_keys {
+            let (pk, _) = sum_dict
+                .iter()
+                .find(|(_, ephm_pk)| **ephm_pk == keys.public)
+                .unwrap();
+            let seed = parsed.local_seed_dict[pk]
+                .decrypt(&keys.public, &keys.secret)
+                .unwrap();
+            assert_eq!(seed, mask_seed);
+        }
+    }
+
+    #[test]
+    fn encode_streaming_unexpected_count() {
+        let (sum_dict, _) = sum_dict_and_keys(3);
+        let mask_seed = MaskSeed::generate();
+        let (update, _) = helpers::payload();
+        let writer = |expected_count| {
+            UpdateWriter::new(
+                update.sum_signature,
+                update.update_signature,
+                update.model_checksum,
+                &update.masked_model,
+                &mask_seed,
+                expected_count,
+            )
+        };
+
+        let missing = writer(4);
+        let mut buf = vec![0xff; missing.buffer_length()];
+        assert_eq!(
+            missing.write(sum_dict.clone(), &mut buf).unwrap_err(),
+            UpdateWriterError::MissingEntries {
+                expected: 4,
+                actual: 3
+            },
+        );
+
+        let excess = writer(2);
+        let mut buf = vec![0xff; excess.buffer_length()];
+        assert_eq!(
+            excess.write(sum_dict, &mut buf).unwrap_err(),
+            UpdateWriterError::ExcessEntries { expected: 2 },
+        );
+    }
 }
@@ -5,8 +5,9 @@
 //! [message module]: crate::message
 
 use std::{
+    collections::HashMap,
     convert::TryInto,
-    io::{Cursor, Write},
+    io::{Cursor, Read, Write},
     iter::{ExactSizeIterator, Iterator},
     ops::Range,
 };
@@ -16,9 +17,20 @@ use anyhow::{anyhow, Context};
 use crate::{
     crypto::ByteObject,
     mask::seed::EncryptedMaskSeed,
-    message::{utils::ChunkableIterator, DecodeError},
+    message::{
+        utils::{
+            wire::{self, LEN_BYTES},
+            ChunkableIterator,
+            ReadIterator,
+        },
+        DecodeError,
+    },
+    CompressedSeedDict,
     LocalSeedDict,
+    SeedDict,
     SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+    UpdateSeedDict,
 };
 
 /// An interface for serializable message types.
@@ -51,6 +63,25 @@ pub trait FromBytes: Sized {
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
         iter: &mut I,
     ) -> Result<Self, DecodeError>;
+
+    /// Deserialize the type from the next `length` bytes of the given reader.
+    ///
+    /// The bytes are parsed while they are read, hence they are never held in memory all at once.
+    /// No more than `length` bytes are read from the reader.
+    ///
+    /// # Errors
+    /// Fails if reading fails, if the reader yields less than `length` bytes, if the bytes are
+    /// not entirely consumed by the type or if the deserialization of the type fails.
+    fn from_reader<R: Read>(reader: R, length: usize) -> Result<Self, DecodeError> {
+        let mut iter = ReadIterator::new(reader, length);
+        let parsed = Self::from_byte_stream(&mut iter);
+        let trailing = iter.finish().context("failed to read bytes")?;
+        let parsed = parsed?;
+        if trailing > 0 {
+            return Err(anyhow!("{} trailing bytes", trailing));
+        }
+        Ok(parsed)
+    }
 }
 
 impl<T> FromBytes for T
@@ -128,7 +159,7 @@ pub struct LengthValueBuffer<T> {
 }
 
 /// The size of the length field for encoding a Length-Value item.
-const LENGTH_FIELD: Range<usize> = 0..4;
+pub(crate) const LENGTH_FIELD: Range<usize> = 0..LEN_BYTES;
 
 impl<T: AsRef<[u8]>> LengthValueBuffer<T> {
     /// Returns a new [`LengthValueBuffer`].
@@ -177,28 +208,18 @@ impl<T: AsRef<[u8]>> LengthValueBuffer<T> {
     /// Check that the buffer is a valid Length-Value item.
     pub fn check_buffer_length(&self) -> Result<(), DecodeError> {
         let len = self.inner.as_ref().len();
-        if len < LENGTH_FIELD.end {
-            return Err(anyhow!(
-                "invalid buffer length: {} < {}",
-                len,
-                LENGTH_FIELD.end
-            ));
-        }
+        let length = wire::read_len(self.inner.as_ref())?;
 
-        if (self.length() as usize) < LENGTH_FIELD.end {
+        if length < LENGTH_FIELD.end {
             return Err(anyhow!(
                 "invalid length value: {} (should be >= {})",
-                len,
+                length,
                 LENGTH_FIELD.end
             ));
         }
 
-        if len < self.length() as usize {
-            return Err(anyhow!(
-                "invalid buffer length: {} < {}",
-                len,
-                self.length(),
-            ));
+        if len < length {
+            return Err(anyhow!("invalid buffer length: {} < {}", len, length));
         }
         Ok(())
     }
@@ -208,14 +229,13 @@ impl<T: AsRef<[u8]>> LengthValueBuffer<T> {
     ///
     /// # Panics
     /// This method may panic if buffer is not a valid Length-Value item.
-    pub fn length(&self) -> u32 {
-        // unwrap safe: the slice is exactly 4 bytes long
-        u32::from_be_bytes(self.inner.as_ref()[LENGTH_FIELD].try_into().unwrap())
+    pub fn length(&self) -> usize {
+        wire::read_len(self.inner.as_ref()).unwrap()
     }
 
     /// Returns the length of the value.
     pub fn value_length(&self) -> usize {
-        self.length() as usize - LENGTH_FIELD.end
+        self.length() - LENGTH_FIELD.end
     }
 
     /// Returns the range corresponding to the value.
@@ -230,9 +250,10 @@ impl<T: AsMut<[u8]>> LengthValueBuffer<T> {
     /// Sets the length field to the given value.
     ///
     /// # Panics
-    /// This method may panic if buffer is not a valid Length-Value item.
-    pub fn set_length(&mut self, value: u32) {
-        self.inner.as_mut()[LENGTH_FIELD].copy_from_slice(&value.to_be_bytes());
+    /// This method may panic if buffer is not a valid Length-Value item or if the length doesn't
+    /// fit into the length field.
+    pub fn set_length(&mut self, value: usize) {
+        wire::write_len(self.inner.as_mut(), value).expect("length exceeds the length field");
     }
 }
 
@@ -274,81 +295,369 @@ impl<'a, T: AsRef<[u8]> + ?Sized> LengthValueBuffer<&'a T> {
     }
 }
 
-const ENTRY_LENGTH: usize = SumParticipantPublicKey::LENGTH + EncryptedMaskSeed::LENGTH;
+/// The length of an entry of a serialized local seed dictionary whose seeds are encrypted with the
+/// default [`SealedBox`] cipher.
+///
+/// [`SealedBox`]: crate::mask::SealedBox
+pub(crate) const ENTRY_LENGTH: usize = SumParticipantPublicKey::LENGTH + EncryptedMaskSeed::LENGTH;
+
+/// Gets the length of a serialized local seed dictionary with the given number of entries.
+///
+/// This is also the length of a serialized update seed dictionary, which is sent to a sum
+/// participant. The seeds are assumed to be encrypted with the default [`SealedBox`] cipher, see
+/// [`seed_dict_length_for()`] for other ciphers.
+///
+/// [`SealedBox`]: crate::mask::SealedBox
+pub fn seed_dict_length(entries: usize) -> usize {
+    seed_dict_length_for(entries, EncryptedMaskSeed::LENGTH)
+}
+
+/// Gets the length of a serialized seed dictionary with the given number of entries, whose
+/// encrypted seeds are `seed_length` bytes long.
+pub fn seed_dict_length_for(entries: usize, seed_length: usize) -> usize {
+    LENGTH_FIELD.end + entries * (SumParticipantPublicKey::LENGTH + seed_length)
+}
 
-impl ToBytes for LocalSeedDict {
+/// Implements the serialization of a seed dictionary.
+///
+/// The entries of a serialized seed dictionary have a fixed length, hence all encrypted seeds of
+/// a dictionary must have the same length. The length is not part of the serialization, the
+/// [`FromBytes`] implementation assumes the length of the default cipher and the
+/// `*_with_seed_length()` methods decode the seeds of other ciphers.
+macro_rules! impl_traits_for_seed_dict {
+    ($dict:ident) => {
+        impl ToBytes for $dict {
+            fn buffer_length(&self) -> usize {
+                LENGTH_FIELD.end
+                    + self
+                        .values()
+                        .map(|value| SumParticipantPublicKey::LENGTH + value.len())
+                        .sum::<usize>()
+            }
+
+            fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
+                let mut writer = Cursor::new(buffer.as_mut());
+                let length = wire::encode_len(self.buffer_length())
+                    .expect("seed dictionary exceeds the length field");
+                let _ = writer.write(&length).unwrap();
+                for (key, value) in self {
+                    let _ = writer.write(key.as_slice()).unwrap();
+                    let _ = writer.write(value.as_ref()).unwrap();
+                }
+            }
+        }
+
+        impl FromBytes for $dict {
+            fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
+                Self::from_byte_slice_with_seed_length(buffer, EncryptedMaskSeed::LENGTH)
+            }
+
+            fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
+                iter: &mut I,
+            ) -> Result<Self, DecodeError> {
+                Self::from_byte_stream_with_seed_length(iter, EncryptedMaskSeed::LENGTH)
+            }
+        }
+
+        impl $dict {
+            /// Deserializes the dictionary from a buffer, where the encrypted seeds are
+            /// `seed_length` bytes long.
+            ///
+            /// # Errors
+            /// Fails if the buffer is not a valid seed dictionary for the seed length.
+            pub fn from_byte_slice_with_seed_length<T: AsRef<[u8]>>(
+                buffer: &T,
+                seed_length: usize,
+            ) -> Result<Self, DecodeError> {
+                if seed_length == 0 {
+                    return Err(anyhow!("invalid seed length: 0"));
+                }
+                let reader = LengthValueBuffer::new(buffer.as_ref())?;
+                let mut dict = HashMap::new();
+
+                let key_length = SumParticipantPublicKey::LENGTH;
+                let mut entries = reader.value().chunks_exact(key_length + seed_length);
+                for chunk in &mut entries {
+                    // safe unwrap: length of slice is guaranteed by constants.
+                    let key = SumParticipantPublicKey::from_slice(&chunk[..key_length]).unwrap();
+                    let value = EncryptedMaskSeed::from(chunk[key_length..].to_vec());
+                    if dict.insert(key, value).is_some() {
+                        return Err(anyhow!("invalid local seed dictionary: duplicated key"));
+                    }
+                }
+                if !entries.remainder().is_empty() {
+                    return Err(anyhow!("invalid local seed dictionary: trailing bytes"));
+                }
+                Ok($dict(dict))
+            }
+
+            /// Deserializes the dictionary from a stream of bytes, where the encrypted seeds are
+            /// `seed_length` bytes long.
+            ///
+            /// # Errors
+            /// Fails if the stream is not a valid seed dictionary for the seed length.
+            pub fn from_byte_stream_with_seed_length<I: Iterator<Item = u8> + ExactSizeIterator>(
+                iter: &mut I,
+                seed_length: usize,
+            ) -> Result<Self, DecodeError> {
+                if seed_length == 0 {
+                    return Err(anyhow!("invalid seed length: 0"));
+                }
+                let len = wire::read_len_from_stream(iter).context("cannot parse length field")?;
+                let value_len = len
+                    .checked_sub(LENGTH_FIELD.end)
+                    .ok_or_else(|| anyhow!("invalid length field"))?;
+                if iter.len() < value_len {
+                    return Err(anyhow!(
+                        "expected {} bytes, but only {} left",
+                        value_len,
+                        iter.len()
+                    ));
+                }
+
+                let mut dict = HashMap::new();
+                let entries = iter
+                    .take(value_len)
+                    .chunks(SumParticipantPublicKey::LENGTH + seed_length);
+                for mut chunk in entries.into_iter() {
+                    let key = SumParticipantPublicKey::from_byte_stream(&mut chunk)
+                        .context("invalid entry: cannot parse public key")?;
+                    let value = (&mut chunk).take(seed_length).collect::<Vec<u8>>();
+                    if value.len() != seed_length {
+                        return Err(anyhow!("invalid entry: cannot parse encrypted mask seed"));
+                    }
+                    // This should really not happen, but it's worth checking
+                    // because our chunkable iterator panics if the chunks are
+                    // not fully consumed.
+                    if chunk.len() > 0 {
+                        return Err(anyhow!(
+                            "unknown error while parsing seed dict entry: entry buffer not fully consumed"
+                        ));
+                    }
+                    if dict.insert(key, EncryptedMaskSeed::from(value)).is_some() {
+                        return Err(anyhow!("duplicated key"));
+                    }
+                }
+                Ok($dict(dict))
+            }
+        }
+    };
+}
+
+impl_traits_for_seed_dict!(LocalSeedDict);
+impl_traits_for_seed_dict!(UpdateSeedDict);
+
+impl CompressedSeedDict {
+    /// Gets the sorted public keys of all update participants of the seed dictionary.
+    fn update_pks(&self) -> Vec<UpdateParticipantPublicKey> {
+        let mut update_pks = self
+            .0
+            .values()
+            .flat_map(|update_seed_dict| update_seed_dict.keys().copied())
+            .collect::<Vec<_>>();
+        update_pks.sort_unstable();
+        update_pks.dedup();
+        update_pks
+    }
+
+    /// Gets the length of the encrypted seeds of the seed dictionary.
+    fn seed_length(&self) -> usize {
+        self.0
+            .values()
+            .flat_map(|update_seed_dict| update_seed_dict.values())
+            .map(EncryptedMaskSeed::len)
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// The length of the header of a compressed seed dictionary: the length field, the number of
+/// update participants and the length of an encrypted seed.
+const COMPRESSED_HEADER_LENGTH: usize = LENGTH_FIELD.end + LEN_BYTES + 2;
+
+/// Serializes a seed dictionary in the compressed format:
+///
+/// | bytes | field |
+/// |-------|-------|
+/// | 4 | length of the whole dictionary |
+/// | 4 | number of update participants `U` |
+/// | 2 | length of an encrypted mask seed `L` |
+/// | `U` * 32 | public keys of the update participants in ascending order |
+/// | * | update seed dictionaries |
+///
+/// An update seed dictionary is the public key of its sum participant (32 bytes) followed by the
+/// number of its entries `n` (4 bytes) and the entries. If `n` equals `U`, the entries are the
+/// encrypted mask seeds (`L` bytes each) in the order of the public keys of the update
+/// participants. Otherwise, an entry is the index of the public key of its update participant
+/// (4 bytes) followed by the encrypted mask seed.
+///
+/// All encrypted mask seeds of the dictionary must have the same length.
+impl ToBytes for CompressedSeedDict {
     fn buffer_length(&self) -> usize {
-        LENGTH_FIELD.end + self.len() * ENTRY_LENGTH
+        let update_count = self.update_pks().len();
+        let seed_length = self.seed_length();
+        COMPRESSED_HEADER_LENGTH
+            + update_count * UpdateParticipantPublicKey::LENGTH
+            + self
+                .0
+                .values()
+                .map(|update_seed_dict| {
+                    let entry_length = if update_seed_dict.len() == update_count {
+                        seed_length
+                    } else {
+                        LEN_BYTES + seed_length
+                    };
+                    SumParticipantPublicKey::LENGTH
+                        + LEN_BYTES
+                        + update_seed_dict.len() * entry_length
+                })
+                .sum::<usize>()
     }
 
     fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
+        let update_pks = self.update_pks();
+        let indices = update_pks
+            .iter()
+            .enumerate()
+            .map(|(index, update_pk)| (update_pk, encode_len(index)))
+            .collect::<HashMap<_, _>>();
+
         let mut writer = Cursor::new(buffer.as_mut());
-        let length = self.buffer_length() as u32;
-        let _ = writer.write(&length.to_be_bytes()).unwrap();
-        for (key, value) in self {
-            let _ = writer.write(key.as_slice()).unwrap();
-            let _ = writer.write(value.as_ref()).unwrap();
+        let _ = writer.write(&encode_len(self.buffer_length())).unwrap();
+        let _ = writer.write(&encode_len(update_pks.len())).unwrap();
+        let _ = writer
+            .write(&(self.seed_length() as u16).to_be_bytes())
+            .unwrap();
+        for update_pk in update_pks.iter() {
+            let _ = writer.write(update_pk.as_slice()).unwrap();
         }
+
+        for (sum_pk, update_seed_dict) in self.0.iter() {
+            let _ = writer.write(sum_pk.as_slice()).unwrap();
+            let _ = writer.write(&encode_len(update_seed_dict.len())).unwrap();
+            if update_seed_dict.len() == update_pks.len() {
+                for update_pk in update_pks.iter() {
+                    let _ = writer.write(update_seed_dict[update_pk].as_ref()).unwrap();
+                }
+            } else {
+                for (update_pk, seed) in update_seed_dict {
+                    let _ = writer.write(&indices[update_pk]).unwrap();
+                    let _ = writer.write(seed.as_ref()).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// Takes the next `length` bytes of a compressed seed dictionary.
+fn take_bytes<'a>(bytes: &mut &'a [u8], length: usize) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < length {
+        return Err(anyhow!(
+            "invalid compressed seed dictionary: expected {} bytes, but only {} left",
+            length,
+            bytes.len()
+        ));
     }
+    let (taken, rest) = bytes.split_at(length);
+    *bytes = rest;
+    Ok(taken)
+}
+
+/// Encodes a length, count or index of a compressed seed dictionary.
+///
+/// # Panics
+/// Panics if the value doesn't fit into a length field.
+fn encode_len(value: usize) -> [u8; LEN_BYTES] {
+    wire::encode_len(value).expect("compressed seed dictionary exceeds the length field")
+}
+
+/// Takes the next length, count or index of a compressed seed dictionary.
+fn take_len(bytes: &mut &[u8]) -> Result<usize, DecodeError> {
+    take_bytes(bytes, LEN_BYTES).and_then(wire::read_len)
 }
 
-impl FromBytes for LocalSeedDict {
+impl FromBytes for CompressedSeedDict {
     fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
         let reader = LengthValueBuffer::new(buffer.as_ref())?;
-        let mut dict = LocalSeedDict::new();
-
-        let key_length = SumParticipantPublicKey::LENGTH;
-        let mut entries = reader.value().chunks_exact(ENTRY_LENGTH);
-        for chunk in &mut entries {
-            // safe unwraps: lengths of slices are guaranteed
-            // by constants.
-            let key = SumParticipantPublicKey::from_slice(&chunk[..key_length]).unwrap();
-            let value = EncryptedMaskSeed::from_slice(&chunk[key_length..]).unwrap();
-            if dict.insert(key, value).is_some() {
-                return Err(anyhow!("invalid local seed dictionary: duplicated key"));
-            }
+        let mut bytes = reader.value();
+        let update_count = take_len(&mut bytes)?;
+        let seed_length =
+            take_bytes(&mut bytes, 2).and_then(|bytes| u16::from_byte_slice(&bytes))? as usize;
+        if update_count > 0 && seed_length == 0 {
+            return Err(anyhow!("invalid seed length: 0"));
         }
-        if !entries.remainder().is_empty() {
-            return Err(anyhow!("invalid local seed dictionary: trailing bytes"));
+
+        let key_length = UpdateParticipantPublicKey::LENGTH;
+        let update_pks_length = update_count.checked_mul(key_length).ok_or_else(|| {
+            anyhow!("invalid compressed seed dictionary: too many update participants")
+        })?;
+        let update_pks = take_bytes(&mut bytes, update_pks_length)?
+            .chunks_exact(key_length)
+            // safe unwrap: length of slice is guaranteed by constants.
+            .map(|chunk| UpdateParticipantPublicKey::from_slice(chunk).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut seed_dict = SeedDict::new();
+        while !bytes.is_empty() {
+            let sum_pk =
+                SumParticipantPublicKey::from_byte_slice(&take_bytes(&mut bytes, key_length)?)?;
+            let entry_count = take_len(&mut bytes)?;
+            if entry_count > update_count {
+                return Err(anyhow!(
+                    "invalid compressed seed dictionary: more entries than update participants"
+                ));
+            }
+
+            let mut update_seed_dict = HashMap::with_capacity(entry_count);
+            for position in 0..entry_count {
+                let index = if entry_count == update_count {
+                    position
+                } else {
+                    take_len(&mut bytes)?
+                };
+                let update_pk = update_pks.get(index).ok_or_else(|| {
+                    anyhow!("invalid compressed seed dictionary: unknown update participant")
+                })?;
+                let seed = EncryptedMaskSeed::from(take_bytes(&mut bytes, seed_length)?.to_vec());
+                if update_seed_dict.insert(*update_pk, seed).is_some() {
+                    return Err(anyhow!(
+                        "invalid compressed seed dictionary: duplicated key"
+                    ));
+                }
+            }
+            if seed_dict
+                .insert(sum_pk, UpdateSeedDict(update_seed_dict))
+                .is_some()
+            {
+                return Err(anyhow!(
+                    "invalid compressed seed dictionary: duplicated key"
+                ));
+            }
         }
-        Ok(dict)
+        Ok(Self(seed_dict))
     }
 
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
         iter: &mut I,
     ) -> Result<Self, DecodeError> {
-        let len = u32::from_byte_stream(iter).context("cannot parse length field")? as usize;
-        if len < 4 {
+        let len = wire::read_len_from_stream(iter).context("cannot parse length field")?;
+        if len < COMPRESSED_HEADER_LENGTH {
             return Err(anyhow!("invalid length field"));
         }
-        if iter.len() < len - 4 {
+        let value_len = len - LENGTH_FIELD.end;
+        if iter.len() < value_len {
             return Err(anyhow!(
                 "expected {} bytes, but only {} left",
-                len - 4,
+                value_len,
                 iter.len()
             ));
         }
-
-        let mut dict = LocalSeedDict::new();
-        let entries = iter.take(len - 4).chunks(ENTRY_LENGTH);
-        for mut chunk in entries.into_iter() {
-            let key = SumParticipantPublicKey::from_byte_stream(&mut chunk)
-                .context("invalid entry: cannot parse public key")?;
-            let value = EncryptedMaskSeed::from_byte_stream(&mut chunk)
-                .context("invalid entry: cannot parse encrypted mask seed")?;
-            // This should really not happen, but it's worth checking
-            // because our chunkable iterator panics if the chunks are
-            // not fully consumed.
-            if chunk.len() > 0 {
-                return Err(anyhow!(
-                    "unknown error while parsing seed dict entry: entry buffer not fully consumed"
-                ));
-            }
-            if dict.insert(key, value).is_some() {
-                return Err(anyhow!("duplicated key"));
-            }
-        }
-        Ok(dict)
+        let buffer = encode_len(len)
+            .iter()
+            .copied()
+            .chain(iter.take(value_len))
+            .collect::<Vec<_>>();
+        Self::from_byte_slice(&buffer)
     }
 }
 
@@ -398,9 +707,34 @@ impl FromBytes for u32 {
     }
 }
 
+impl FromBytes for u64 {
+    fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
+        Ok(u64::from_be_bytes(
+            buffer
+                .as_ref()
+                .try_into()
+                .context("failed to parse u64: invalid length")?,
+        ))
+    }
+
+    fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
+        iter: &mut I,
+    ) -> Result<Self, DecodeError> {
+        fn err() -> DecodeError {
+            anyhow!("cannot read u64: byte stream exhausted")
+        }
+        let mut value = 0_u64;
+        for _ in 0..8 {
+            value = (value << 8) | iter.next().ok_or_else(err)? as u64;
+        }
+        Ok(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crypto::{PublicSigningKey, SigningKeyPair};
 
     #[test]
     fn decode_length_value_buffer() {
@@ -477,4 +811,180 @@ mod tests {
         assert_eq!(u16::from_byte_slice(&buf.as_slice()).unwrap(), 0x1234);
         assert_eq!(u16::from_byte_stream(&mut buf.into_iter()).unwrap(), 0x1234);
     }
+
+    #[test]
+    fn encode_seed_dicts() {
+        let pk = PublicSigningKey::from_slice(&[0x55; PublicSigningKey::LENGTH]).unwrap();
+        let seed = EncryptedMaskSeed::from(vec![0x66; EncryptedMaskSeed::LENGTH]);
+        let mut expected = vec![0x00, 0x00, 0x00, 0x74]; // Length = 4 + 32 + 80
+        expected.extend(vec![0x55; PublicSigningKey::LENGTH]);
+        expected.extend(vec![0x66; EncryptedMaskSeed::LENGTH]);
+
+        // both dictionaries keep the encoding of the former `HashMap` aliases
+        let local_seed_dict = LocalSeedDict(vec![(pk, seed.clone())].into_iter().collect());
+        let mut bytes = vec![0xff; local_seed_dict.buffer_length()];
+        local_seed_dict.to_bytes(&mut bytes);
+        assert_eq!(bytes, expected);
+        assert_eq!(
+            LocalSeedDict::from_byte_slice(&expected).unwrap(),
+            local_seed_dict
+        );
+
+        let update_seed_dict = UpdateSeedDict(vec![(pk, seed)].into_iter().collect());
+        let mut bytes = vec![0xff; update_seed_dict.buffer_length()];
+        update_seed_dict.to_bytes(&mut bytes);
+        assert_eq!(bytes, expected);
+        assert_eq!(
+            UpdateSeedDict::from_byte_stream(&mut expected.into_iter()).unwrap(),
+            update_seed_dict
+        );
+    }
+
+    #[test]
+    fn encode_seed_dicts_with_seed_length() {
+        let pk = PublicSigningKey::from_slice(&[0x55; PublicSigningKey::LENGTH]).unwrap();
+        let seed = EncryptedMaskSeed::from(vec![0x66; 1024]);
+        let mut expected = vec![0x00, 0x00, 0x04, 0x24]; // Length = 4 + 32 + 1024
+        expected.extend(vec![0x55; PublicSigningKey::LENGTH]);
+        expected.extend(vec![0x66; 1024]);
+
+        let local_seed_dict = LocalSeedDict(vec![(pk, seed)].into_iter().collect());
+        assert_eq!(
+            local_seed_dict.buffer_length(),
+            seed_dict_length_for(1, 1024)
+        );
+        let mut bytes = vec![0xff; local_seed_dict.buffer_length()];
+        local_seed_dict.to_bytes(&mut bytes);
+        assert_eq!(bytes, expected);
+        assert_eq!(
+            LocalSeedDict::from_byte_slice_with_seed_length(&expected, 1024).unwrap(),
+            local_seed_dict
+        );
+        assert_eq!(
+            LocalSeedDict::from_byte_stream_with_seed_length(
+                &mut expected.clone().into_iter(),
+                1024
+            )
+            .unwrap(),
+            local_seed_dict
+        );
+
+        // the seeds don't have the length of the default cipher
+        assert!(LocalSeedDict::from_byte_slice(&expected).is_err());
+        assert!(LocalSeedDict::from_byte_stream(&mut expected.clone().into_iter()).is_err());
+        assert!(LocalSeedDict::from_byte_slice_with_seed_length(&expected, 0).is_err());
+    }
+
+    /// Creates a global seed dictionary where every update participant has an entry for every
+    /// sum participant.
+    fn make_seed_dict(sum_count: usize, update_count: usize) -> SeedDict {
+        let update_pks = (0..update_count)
+            .map(|_| SigningKeyPair::generate().public)
+            .collect::<Vec<_>>();
+        (0..sum_count)
+            .map(|_| {
+                let update_seed_dict =
+                    UpdateSeedDict::from_storage(update_pks.iter().enumerate().map(
+                        |(i, update_pk)| {
+                            let seed = vec![i as u8; EncryptedMaskSeed::LENGTH];
+                            (*update_pk, EncryptedMaskSeed::from(seed))
+                        },
+                    ));
+                (SigningKeyPair::generate().public, update_seed_dict)
+            })
+            .collect()
+    }
+
+    fn serialize_compressed(seed_dict: &SeedDict) -> Vec<u8> {
+        let compressed = CompressedSeedDict(seed_dict.clone());
+        let mut bytes = vec![0xff; compressed.buffer_length()];
+        compressed.to_bytes(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn encode_compressed_seed_dict() {
+        for &(sum_count, update_count) in &[(0, 0), (1, 1), (10, 100), (100, 10)] {
+            let seed_dict = make_seed_dict(sum_count, update_count);
+            let bytes = serialize_compressed(&seed_dict);
+            assert_eq!(
+                CompressedSeedDict::from_byte_slice(&bytes).unwrap().0,
+                seed_dict
+            );
+            assert_eq!(
+                CompressedSeedDict::from_byte_stream(&mut bytes.into_iter())
+                    .unwrap()
+                    .0,
+                seed_dict
+            );
+        }
+    }
+
+    #[test]
+    fn encode_compressed_seed_dict_with_missing_entries() {
+        let mut seed_dict = make_seed_dict(3, 5);
+        let (_, update_seed_dict) = seed_dict.iter_mut().next().unwrap();
+        let update_pk = *update_seed_dict.keys().next().unwrap();
+        update_seed_dict.0.remove(&update_pk);
+        seed_dict.insert(SigningKeyPair::generate().public, UpdateSeedDict::default());
+
+        let bytes = serialize_compressed(&seed_dict);
+        assert_eq!(
+            CompressedSeedDict::from_byte_slice(&bytes).unwrap().0,
+            seed_dict
+        );
+    }
+
+    #[test]
+    fn compressed_seed_dict_is_smaller() {
+        // the naive serialization is the update seed dictionary of each sum participant
+        let naive_length = |seed_dict: &SeedDict| {
+            seed_dict
+                .values()
+                .map(|update_seed_dict| PublicSigningKey::LENGTH + update_seed_dict.buffer_length())
+                .sum::<usize>()
+        };
+
+        for &(sum_count, update_count) in &[(10, 100), (100, 1000)] {
+            let seed_dict = make_seed_dict(sum_count, update_count);
+            let compressed_length = serialize_compressed(&seed_dict).len();
+            assert!(compressed_length < naive_length(&seed_dict));
+            // the public keys of the update participants are stored once instead of once per sum
+            // participant
+            assert_eq!(
+                naive_length(&seed_dict) - compressed_length,
+                (sum_count - 1) * update_count * PublicSigningKey::LENGTH
+                    - COMPRESSED_HEADER_LENGTH
+            );
+        }
+    }
+
+    #[test]
+    fn decode_invalid_compressed_seed_dict() {
+        let bytes = serialize_compressed(&make_seed_dict(2, 3));
+        // truncated
+        let mut truncated = bytes[..bytes.len() - 1].to_vec();
+        let length = truncated.len() as u32;
+        truncated[..4].copy_from_slice(&length.to_be_bytes());
+        assert!(CompressedSeedDict::from_byte_slice(&truncated).is_err());
+
+        // an entry refers to an unknown update participant
+        let mut bytes = vec![0x00, 0x00, 0x00, 0xc2]; // Length = 10 + 2 * 32 + 32 + 4 + 4 + 80
+        bytes.extend(vec![0x00, 0x00, 0x00, 0x02]); // 2 update participants
+        bytes.extend(vec![0x00, 0x50]); // Seed length = 80
+        bytes.extend(vec![0x11; PublicSigningKey::LENGTH]);
+        bytes.extend(vec![0x22; PublicSigningKey::LENGTH]);
+        bytes.extend(vec![0x33; PublicSigningKey::LENGTH]);
+        bytes.extend(vec![0x00, 0x00, 0x00, 0x01]); // 1 entry
+        bytes.extend(vec![0x00, 0x00, 0x00, 0x01]); // Index = 1
+        bytes.extend(vec![0x44; EncryptedMaskSeed::LENGTH]);
+        let seed_dict = CompressedSeedDict::from_byte_slice(&bytes).unwrap().0;
+        let update_pk = PublicSigningKey::from_slice(&[0x22; PublicSigningKey::LENGTH]).unwrap();
+        let sum_pk = PublicSigningKey::from_slice(&[0x33; PublicSigningKey::LENGTH]).unwrap();
+        assert_eq!(seed_dict[&sum_pk].len(), 1);
+        assert!(seed_dict[&sum_pk].contains_key(&update_pk));
+
+        bytes[110..114].copy_from_slice(&[0x00, 0x00, 0x00, 0x02]); // Index = 2
+        assert!(CompressedSeedDict::from_byte_slice(&bytes).is_err());
+    }
 }
@@ -6,10 +6,7 @@
 
 mod chunkable_iterator;
 pub use chunkable_iterator::{Chunk, ChunkableIterator, Chunks, IntoChunks};
-
-use std::ops::Range;
-
-/// Creates a range from `start` to `start + length`.
-pub(crate) const fn range(start: usize, length: usize) -> Range<usize> {
-    start..(start + length)
-}
+mod read_iterator;
+pub(crate) use read_iterator::ReadIterator;
+pub(crate) mod wire;
+pub(crate) use wire::range;
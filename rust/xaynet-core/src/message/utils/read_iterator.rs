@@ -0,0 +1,169 @@
+//! This module provides an adapter which turns a reader into an iterator over a known number of
+//! bytes. The iterator implements [`ExactSizeIterator`], hence it can be parsed with the
+//! [`FromBytes`] trait while the bytes are read.
+//!
+//! [`ExactSizeIterator`]: std::iter::ExactSizeIterator
+//! [`FromBytes`]: crate::message::FromBytes
+
+use std::{
+    cmp,
+    io::{self, ErrorKind, Read},
+    iter::{ExactSizeIterator, Iterator},
+};
+
+/// The maximal number of bytes which are read from the reader at once.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// An iterator over the next `length` bytes of a reader.
+///
+/// The bytes are read in blocks of bounded size, hence the memory of the iterator doesn't depend
+/// on the number of bytes. No more than `length` bytes are read from the reader.
+///
+/// If reading fails or the reader ends prematurely, the iterator stops and the error is reported
+/// by [`finish()`]. The reported length of the iterator is meaningless in this case.
+///
+/// [`finish()`]: ReadIterator::finish
+pub(crate) struct ReadIterator<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    remaining: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> ReadIterator<R> {
+    /// Creates an iterator over the next `length` bytes of the `reader`.
+    pub(crate) fn new(reader: R, length: usize) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+            position: 0,
+            remaining: length,
+            error: None,
+        }
+    }
+
+    /// Ends the iteration and returns the number of bytes which have not been consumed.
+    ///
+    /// # Errors
+    /// Fails if reading from the reader failed or if the reader ended prematurely.
+    pub(crate) fn finish(self) -> Result<usize, io::Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.remaining),
+        }
+    }
+
+    /// Reads the next block of bytes into the buffer and returns whether this succeeded.
+    fn fill_buffer(&mut self) -> bool {
+        self.buffer.resize(cmp::min(BUFFER_SIZE, self.remaining), 0);
+        self.position = 0;
+        loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.error = Some(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        format!("reader ended {} bytes too early", self.remaining),
+                    ));
+                    break;
+                }
+                Ok(read) => {
+                    self.buffer.truncate(read);
+                    return true;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.error = Some(error);
+                    break;
+                }
+            }
+        }
+        self.buffer.clear();
+        false
+    }
+}
+
+impl<R: Read> Iterator for ReadIterator<R> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.error.is_some() {
+            return None;
+        }
+        if self.position == self.buffer.len() && !self.fill_buffer() {
+            return None;
+        }
+
+        let byte = self.buffer[self.position];
+        self.position += 1;
+        self.remaining -= 1;
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<R: Read> ExactSizeIterator for ReadIterator<R> {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A reader which yields at most `max` bytes per read and is interrupted in between.
+    struct SlowReader {
+        inner: Cursor<Vec<u8>>,
+        max: usize,
+        interrupt: bool,
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(ErrorKind::Interrupted.into());
+            }
+            let len = cmp::min(self.max, buf.len());
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    fn slow_reader(bytes: Vec<u8>) -> SlowReader {
+        SlowReader {
+            inner: Cursor::new(bytes),
+            max: 7,
+            interrupt: false,
+        }
+    }
+
+    #[test]
+    fn test_read_all() {
+        let bytes = (0..3 * BUFFER_SIZE as u32)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let mut iter = ReadIterator::new(slow_reader(bytes.clone()), bytes.len());
+        assert_eq!(iter.len(), bytes.len());
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), bytes);
+        assert_eq!(iter.finish().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_no_more_than_length() {
+        let mut reader = Cursor::new(vec![1, 2, 3, 4, 5]);
+        let mut iter = ReadIterator::new(&mut reader, 3);
+        assert_eq!(iter.by_ref().take(2).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.finish().unwrap(), 1);
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn test_read_premature_end() {
+        let mut iter = ReadIterator::new(slow_reader(vec![1, 2, 3]), 5);
+        assert_eq!(iter.by_ref().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(iter.finish().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+}
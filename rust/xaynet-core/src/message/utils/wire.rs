@@ -0,0 +1,244 @@
+//! Reading and writing of the length fields of the wire format.
+//!
+//! All the length fields of the messages and of their payloads are [`LEN_BYTES`] bytes long and
+//! encoded as big-endian unsigned integers, independently of the width of `usize` on the platform.
+//!
+//! See the [message module] documentation since this is a private module anyways.
+//!
+//! [message module]: crate::message
+
+use std::{convert::TryFrom, ops::Range};
+
+use anyhow::{anyhow, Context};
+use thiserror::Error;
+
+use crate::message::DecodeError;
+
+/// The number of bytes of a length field.
+pub(crate) const LEN_BYTES: usize = 4;
+
+/// An error related to the encoding of a length field.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(crate) enum EncodeError {
+    #[error("length {0} doesn't fit into a {} bytes length field", LEN_BYTES)]
+    LengthOverflow(usize),
+    #[error("buffer of {0} bytes is too short for a length field")]
+    BufferTooShort(usize),
+}
+
+/// Creates a range from `start` to `start + length`.
+pub(crate) const fn range(start: usize, length: usize) -> Range<usize> {
+    start..(start + length)
+}
+
+/// Creates a range from `start` to `start + length`.
+///
+/// # Errors
+/// Fails if the end of the range overflows.
+pub(crate) fn checked_range(start: usize, length: usize) -> Result<Range<usize>, DecodeError> {
+    start
+        .checked_add(length)
+        .map(|end| start..end)
+        .ok_or_else(|| anyhow!("invalid range: {} + {} overflows", start, length))
+}
+
+/// Reads the length field at the beginning of the `buffer`.
+///
+/// # Errors
+/// Fails if the buffer is shorter than a length field or if the length doesn't fit into a `usize`.
+pub(crate) fn read_len(buffer: &[u8]) -> Result<usize, DecodeError> {
+    let bytes = buffer.get(..LEN_BYTES).ok_or_else(|| {
+        anyhow!(
+            "cannot read length field: buffer is {} bytes long",
+            buffer.len()
+        )
+    })?;
+    // safe unwrap: the slice is exactly `LEN_BYTES` long
+    let len = u32::from_be_bytes(<[u8; LEN_BYTES]>::try_from(bytes).unwrap());
+    usize::try_from(len).context("cannot read length field: length exceeds the platform width")
+}
+
+/// Reads a length field from the byte stream.
+///
+/// # Errors
+/// Fails if the byte stream is exhausted or if the length doesn't fit into a `usize`.
+pub(crate) fn read_len_from_stream<I: Iterator<Item = u8>>(
+    iter: &mut I,
+) -> Result<usize, DecodeError> {
+    let mut bytes = [0_u8; LEN_BYTES];
+    for byte in bytes.iter_mut() {
+        *byte = iter
+            .next()
+            .ok_or_else(|| anyhow!("cannot read length field: byte stream exhausted"))?;
+    }
+    read_len(&bytes)
+}
+
+/// Encodes the length `len` as a length field.
+///
+/// # Errors
+/// Fails if the length doesn't fit into a length field.
+pub(crate) fn encode_len(len: usize) -> Result<[u8; LEN_BYTES], EncodeError> {
+    u32::try_from(len)
+        .map(u32::to_be_bytes)
+        .map_err(|_| EncodeError::LengthOverflow(len))
+}
+
+/// Writes the length `len` into the length field at the beginning of the `buffer`.
+///
+/// # Errors
+/// Fails if the buffer is shorter than a length field or if the length doesn't fit into a length
+/// field.
+pub(crate) fn write_len(buffer: &mut [u8], len: usize) -> Result<(), EncodeError> {
+    let buffer_len = buffer.len();
+    let field = buffer
+        .get_mut(..LEN_BYTES)
+        .ok_or(EncodeError::BufferTooShort(buffer_len))?;
+    field.copy_from_slice(&encode_len(len)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    const ITERATIONS: usize = 10_000;
+
+    #[test]
+    fn test_len_roundtrip() {
+        let mut rng = thread_rng();
+        for _ in 0..ITERATIONS {
+            let len = rng.gen::<u32>() as usize;
+            let mut buffer = vec![0; LEN_BYTES + rng.gen_range(0..8)];
+            write_len(&mut buffer, len).unwrap();
+            assert_eq!(buffer[..LEN_BYTES], (len as u32).to_be_bytes());
+            assert_eq!(read_len(&buffer).unwrap(), len);
+            assert_eq!(
+                read_len_from_stream(&mut buffer.iter().copied()).unwrap(),
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_len_big_endian() {
+        let mut buffer = [0; LEN_BYTES];
+        write_len(&mut buffer, 0x0102_0304).unwrap();
+        assert_eq!(buffer, [0x01, 0x02, 0x03, 0x04]);
+    }
+
+    #[test]
+    fn test_len_short_buffer() {
+        let mut rng = thread_rng();
+        for _ in 0..ITERATIONS {
+            let mut buffer = vec![0; rng.gen_range(0..LEN_BYTES)];
+            rng.fill(buffer.as_mut_slice());
+            assert!(read_len(&buffer).is_err());
+            assert!(read_len_from_stream(&mut buffer.iter().copied()).is_err());
+            assert_eq!(
+                write_len(&mut buffer, rng.gen::<u32>() as usize).unwrap_err(),
+                EncodeError::BufferTooShort(buffer.len()),
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_len_overflow() {
+        let mut rng = thread_rng();
+        for _ in 0..ITERATIONS {
+            let len = rng.gen_range(u32::MAX as usize + 1..=usize::MAX);
+            let mut buffer = [0; LEN_BYTES];
+            assert_eq!(
+                write_len(&mut buffer, len).unwrap_err(),
+                EncodeError::LengthOverflow(len),
+            );
+            assert_eq!(
+                encode_len(len).unwrap_err(),
+                EncodeError::LengthOverflow(len)
+            );
+            assert_eq!(buffer, [0; LEN_BYTES]);
+        }
+    }
+
+    #[test]
+    fn test_checked_range() {
+        let mut rng = thread_rng();
+        for _ in 0..ITERATIONS {
+            let start = rng.gen::<usize>();
+            let length = rng.gen::<usize>();
+            match start.checked_add(length) {
+                Some(end) => assert_eq!(checked_range(start, length).unwrap(), start..end),
+                None => assert!(checked_range(start, length).is_err()),
+            }
+        }
+        assert_eq!(checked_range(3, 4).unwrap(), range(3, 4));
+        assert!(checked_range(usize::MAX, 1).is_err());
+    }
+
+    /// The sources which serialize messages and mask objects, except this module.
+    const SOURCES: &[(&str, &str)] = &[
+        ("message/message.rs", include_str!("../message.rs")),
+        ("message/traits.rs", include_str!("../traits.rs")),
+        (
+            "message/payload/chunk.rs",
+            include_str!("../payload/chunk.rs"),
+        ),
+        ("message/payload/sum.rs", include_str!("../payload/sum.rs")),
+        (
+            "message/payload/sum2.rs",
+            include_str!("../payload/sum2.rs"),
+        ),
+        (
+            "message/payload/update.rs",
+            include_str!("../payload/update.rs"),
+        ),
+        (
+            "mask/object/serialization/mod.rs",
+            include_str!("../../mask/object/serialization/mod.rs"),
+        ),
+        (
+            "mask/object/serialization/sections.rs",
+            include_str!("../../mask/object/serialization/sections.rs"),
+        ),
+        (
+            "mask/object/serialization/unit.rs",
+            include_str!("../../mask/object/serialization/unit.rs"),
+        ),
+        (
+            "mask/object/serialization/vect.rs",
+            include_str!("../../mask/object/serialization/vect.rs"),
+        ),
+    ];
+
+    #[test]
+    fn test_no_direct_length_reads() {
+        for (path, source) in SOURCES {
+            // only the non-test code is relevant
+            let code = source.split("#[cfg(test)]").next().unwrap();
+            for (number, line) in code.lines().enumerate() {
+                assert!(
+                    !line.contains("from_le_bytes"),
+                    "{}:{} decodes little-endian bytes: {}",
+                    path,
+                    number + 1,
+                    line.trim(),
+                );
+                // the length fields are 4 bytes long, other integer fields are read directly
+                let lower = line.to_lowercase();
+                let reads_len = ["len", "numbers", "sections"]
+                    .iter()
+                    .any(|name| lower.contains(name));
+                assert!(
+                    !(line.contains("u32::from_") && reads_len),
+                    "{}:{} reads a length field directly: {}",
+                    path,
+                    number + 1,
+                    line.trim(),
+                );
+            }
+        }
+    }
+}
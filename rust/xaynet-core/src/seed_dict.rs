@@ -0,0 +1,339 @@
+//! The seed dictionaries which carry the encrypted masking seeds from the update participants to
+//! the sum participants.
+
+use std::{
+    collections::{hash_map, HashMap},
+    ops::Deref,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mask::seed::{EncryptedMaskSeed, MaskSeed, SeedCipher},
+    SeedDict,
+    SumDict,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+};
+
+/// Implements the read-only access and the iteration over the entries of a seed dictionary.
+macro_rules! impl_seed_dict {
+    ($dict:ty, $key:ty) => {
+        impl Deref for $dict {
+            type Target = HashMap<$key, EncryptedMaskSeed>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl IntoIterator for $dict {
+            type Item = ($key, EncryptedMaskSeed);
+            type IntoIter = hash_map::IntoIter<$key, EncryptedMaskSeed>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.into_iter()
+            }
+        }
+
+        impl<'a> IntoIterator for &'a $dict {
+            type Item = (&'a $key, &'a EncryptedMaskSeed);
+            type IntoIter = hash_map::Iter<'a, $key, EncryptedMaskSeed>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.0.iter()
+            }
+        }
+
+        impl $dict {
+            /// Creates a dictionary from the given entries without any checks.
+            pub(crate) fn from_entries<I>(entries: I) -> Self
+            where
+                I: IntoIterator<Item = ($key, EncryptedMaskSeed)>,
+            {
+                Self(entries.into_iter().collect())
+            }
+        }
+    };
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Local seed dictionaries are sent by update participants. They contain the participant's masking
+/// seed, encrypted with the ephemeral public key of each sum participant.
+///
+/// A local seed dictionary is built from the sum dictionary with [`LocalSeedDict::new()`], which
+/// guarantees that it has exactly one entry per sum participant. It can't be modified afterwards:
+///
+/// ```compile_fail
+/// # use xaynet_core::{crypto::{ByteObject, SigningKeyPair}, mask::EncryptedMaskSeed, LocalSeedDict};
+/// let mut local_seed_dict = LocalSeedDict::default();
+/// local_seed_dict.insert(SigningKeyPair::generate().public, EncryptedMaskSeed::zeroed());
+/// ```
+///
+/// And it can't be mistaken for an [`UpdateSeedDict`], although both map public signing keys to
+/// encrypted mask seeds:
+///
+/// ```compile_fail
+/// # use xaynet_core::{LocalSeedDict, UpdateSeedDict};
+/// fn send_to_sum_participant(_seed_dict: UpdateSeedDict) {}
+/// send_to_sum_participant(LocalSeedDict::default());
+/// ```
+///
+/// Dictionaries which were built elsewhere, e.g. decoded from an update message, are created with
+/// [`LocalSeedDict::from_decoded()`].
+pub struct LocalSeedDict(pub(crate) HashMap<SumParticipantPublicKey, EncryptedMaskSeed>);
+
+impl LocalSeedDict {
+    /// Creates the local seed dictionary of a masking `seed` for the sum participants of the
+    /// `sum_dict`.
+    ///
+    /// The seed is encrypted with the ephemeral public key of each sum participant.
+    pub fn new(sum_dict: &SumDict, seed: &MaskSeed) -> Self {
+        Self::from_entries(
+            sum_dict
+                .iter()
+                .map(|(sum_pk, ephm_pk)| (*sum_pk, seed.encrypt(ephm_pk))),
+        )
+    }
+
+    /// Creates the local seed dictionary of a masking `seed` for the sum participants of the
+    /// `sum_dict`, where the seed is encrypted with the [`SeedCipher`] `C`.
+    pub fn new_with<C: SeedCipher>(sum_dict: &SumDict, seed: &MaskSeed) -> Self {
+        Self::from_entries(
+            sum_dict
+                .iter()
+                .map(|(sum_pk, ephm_pk)| (*sum_pk, seed.encrypt_with::<C>(ephm_pk))),
+        )
+    }
+
+    /// Creates a local seed dictionary from the entries of a dictionary which was decoded
+    /// elsewhere, e.g. from an update message.
+    ///
+    /// Unlike [`LocalSeedDict::new()`], this doesn't guarantee an entry per sum participant, see
+    /// [`LocalSeedDict::matches()`].
+    pub fn from_decoded<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (SumParticipantPublicKey, EncryptedMaskSeed)>,
+    {
+        Self::from_entries(entries)
+    }
+
+    /// Checks whether this dictionary has exactly one entry for each sum participant of the
+    /// `sum_dict`.
+    pub fn matches(&self, sum_dict: &SumDict) -> bool {
+        self.len() == sum_dict.len() && sum_dict.keys().all(|pk| self.contains_key(pk))
+    }
+}
+
+impl_seed_dict!(LocalSeedDict, SumParticipantPublicKey);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+/// Values of [`SeedDict`]. Sent to sum participants.
+///
+/// An update seed dictionary is the projection of the local seed dictionaries onto a single sum
+/// participant, see [`UpdateSeedDict::project()`]. It maps the public key of every update
+/// participant to its masking seed, encrypted with the ephemeral public key of the sum
+/// participant.
+///
+/// The global seed dictionary can't be built from the local seed dictionaries directly:
+///
+/// ```compile_fail
+/// # use xaynet_core::{crypto::SigningKeyPair, LocalSeedDict, SeedDict};
+/// let sum_pk = SigningKeyPair::generate().public;
+/// let seed_dict = vec![(sum_pk, LocalSeedDict::default())]
+///     .into_iter()
+///     .collect::<SeedDict>();
+/// ```
+///
+/// But from their projections:
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use xaynet_core::{
+/// #     crypto::SigningKeyPair,
+/// #     LocalSeedDict,
+/// #     SeedDict,
+/// #     UpdateParticipantPublicKey,
+/// #     UpdateSeedDict,
+/// # };
+/// let local_seed_dicts = HashMap::<UpdateParticipantPublicKey, LocalSeedDict>::new();
+/// let sum_pk = SigningKeyPair::generate().public;
+/// let seed_dict = vec![(sum_pk, UpdateSeedDict::project(&sum_pk, &local_seed_dicts))]
+///     .into_iter()
+///     .collect::<SeedDict>();
+/// ```
+///
+/// Dictionaries which were projected elsewhere, e.g. read back from the storage of the
+/// coordinator, are created with [`UpdateSeedDict::from_storage()`].
+pub struct UpdateSeedDict(pub(crate) HashMap<UpdateParticipantPublicKey, EncryptedMaskSeed>);
+
+impl UpdateSeedDict {
+    /// Projects the local seed dictionaries of the update participants onto the sum participant
+    /// `sum_pk`.
+    ///
+    /// Local seed dictionaries without an entry for the sum participant are skipped.
+    pub fn project<'a, I>(sum_pk: &SumParticipantPublicKey, local_seed_dicts: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a UpdateParticipantPublicKey, &'a LocalSeedDict)>,
+    {
+        Self::from_entries(local_seed_dicts.into_iter().filter_map(
+            |(update_pk, local_seed_dict)| {
+                local_seed_dict
+                    .get(sum_pk)
+                    .map(|seed| (*update_pk, seed.clone()))
+            },
+        ))
+    }
+
+    /// Creates an update seed dictionary from the entries of a dictionary which was projected
+    /// elsewhere, e.g. read back from the storage of the coordinator.
+    pub fn from_storage<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (UpdateParticipantPublicKey, EncryptedMaskSeed)>,
+    {
+        Self::from_entries(entries)
+    }
+
+    /// Gets the update seed dictionary of the sum participant `sum_pk` from the global `seed_dict`.
+    ///
+    /// Returns an empty dictionary if the sum participant is unknown.
+    pub fn from_seed_dict(seed_dict: &SeedDict, sum_pk: &SumParticipantPublicKey) -> Self {
+        seed_dict.get(sum_pk).cloned().unwrap_or_default()
+    }
+}
+
+impl_seed_dict!(UpdateSeedDict, UpdateParticipantPublicKey);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The source of the [`UpdateSeedDict`] of a sum participant.
+///
+/// The coordinator may export the seed dictionary of a round to a blob storage, from where the sum
+/// participants can fetch it independently of the availability of the coordinator. The exported
+/// blob holds the canonical binary encoding of the [`UpdateSeedDict`], see
+/// [`ToBytes`](crate::message::ToBytes).
+pub enum SeedDictSource {
+    /// The seed dictionary itself.
+    Inline(UpdateSeedDict),
+    /// The location of the exported seed dictionary.
+    Location(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Seeds taken from a version of the seed dictionary, e.g. the [`UpdateSeedDict`] of a sum
+/// participant or its [`SeedDictSource`].
+///
+/// The coordinator bumps the version of the seed dictionary of a round whenever it accepts an
+/// update message. A sum participant echoes the version in its sum2 message, such that the
+/// coordinator can reject masks which were derived from an outdated seed dictionary.
+pub struct VersionedSeeds<S = UpdateSeedDict> {
+    /// The version of the seed dictionary.
+    pub version: u64,
+    /// The seeds.
+    pub seeds: S,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// A global [`SeedDict`] with a compressed serialization.
+///
+/// The update seed dictionaries of the sum participants usually have entries for the same update
+/// participants. Serializing each of them on its own repeats the public keys of the update
+/// participants once per sum participant. The compressed serialization stores the list of the
+/// public keys of the update participants once instead, and each update seed dictionary refers
+/// to the keys by their index in this list. The serialization is described in
+/// [`ToBytes`](crate::message::ToBytes) for this type.
+///
+/// ```
+/// # use xaynet_core::{message::{FromBytes, ToBytes}, CompressedSeedDict, SeedDict};
+/// let compressed = CompressedSeedDict::from(SeedDict::new());
+/// let mut bytes = vec![0; compressed.buffer_length()];
+/// compressed.to_bytes(&mut bytes);
+/// let seed_dict = SeedDict::from(CompressedSeedDict::from_byte_slice(&bytes).unwrap());
+/// assert!(seed_dict.is_empty());
+/// ```
+pub struct CompressedSeedDict(pub SeedDict);
+
+impl From<SeedDict> for CompressedSeedDict {
+    fn from(seed_dict: SeedDict) -> Self {
+        Self(seed_dict)
+    }
+}
+
+impl From<CompressedSeedDict> for SeedDict {
+    fn from(compressed: CompressedSeedDict) -> Self {
+        compressed.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{ByteObject, EncryptKeyPair, SigningKeyPair};
+
+    fn make_sum_dict(len: usize) -> (SumDict, Vec<EncryptKeyPair>) {
+        let ephm_keys = (0..len)
+            .map(|_| EncryptKeyPair::generate())
+            .collect::<Vec<_>>();
+        let sum_dict = ephm_keys
+            .iter()
+            .map(|keys| (SigningKeyPair::generate().public, keys.public))
+            .collect();
+        (sum_dict, ephm_keys)
+    }
+
+    #[test]
+    fn test_local_seed_dict_new() {
+        let (sum_dict, ephm_keys) = make_sum_dict(3);
+        let seed = MaskSeed::generate();
+        let local_seed_dict = LocalSeedDict::new(&sum_dict, &seed);
+
+        assert!(local_seed_dict.matches(&sum_dict));
+        for keys in ephm_keys {
+            let (sum_pk, _) = sum_dict
+                .iter()
+                .find(|(_, ephm_pk)| **ephm_pk == keys.public)
+                .unwrap();
+            let decrypted = local_seed_dict[sum_pk]
+                .decrypt(&keys.public, &keys.secret)
+                .unwrap();
+            assert_eq!(decrypted, seed);
+        }
+
+        let (other_sum_dict, _) = make_sum_dict(3);
+        assert!(!local_seed_dict.matches(&other_sum_dict));
+        assert!(!LocalSeedDict::default().matches(&sum_dict));
+    }
+
+    #[test]
+    fn test_update_seed_dict_project() {
+        let (sum_dict, _) = make_sum_dict(2);
+        let local_seed_dicts = (0..3)
+            .map(|_| {
+                let update_pk = SigningKeyPair::generate().public;
+                (
+                    update_pk,
+                    LocalSeedDict::new(&sum_dict, &MaskSeed::generate()),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let seed_dict = sum_dict
+            .keys()
+            .map(|sum_pk| (*sum_pk, UpdateSeedDict::project(sum_pk, &local_seed_dicts)))
+            .collect::<SeedDict>();
+
+        for (sum_pk, update_seed_dict) in seed_dict.iter() {
+            assert_eq!(update_seed_dict.len(), 3);
+            for (update_pk, seed) in update_seed_dict {
+                assert_eq!(seed, &local_seed_dicts[update_pk][sum_pk]);
+            }
+            assert_eq!(
+                &UpdateSeedDict::from_seed_dict(&seed_dict, sum_pk),
+                update_seed_dict,
+            );
+        }
+        let unknown_pk = SigningKeyPair::generate().public;
+        assert!(UpdateSeedDict::project(&unknown_pk, &local_seed_dicts).is_empty());
+        assert!(UpdateSeedDict::from_seed_dict(&seed_dict, &unknown_pk).is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+//! Differential testing of the masked aggregation against the plaintext weighted average.
+//!
+//! The coordinator aggregates the masked models of the update participants and unmasks the
+//! aggregation with the aggregated masks. [`simulate_aggregation()`] runs this pipeline locally,
+//! without a coordinator, and compares its result to the plaintext weighted average of the
+//! models. This allows to check that a masking configuration is suitable for the models of an
+//! application, e.g. in its CI.
+//!
+//! ```
+//! use xaynet_core::{
+//!     mask::{BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Model, ModelType, Scalar},
+//!     testutils::aggregation::{assert_aggregation, simulate_aggregation},
+//! };
+//!
+//! let config = MaskConfig {
+//!     group_type: GroupType::Prime,
+//!     data_type: DataType::F32,
+//!     bound_type: BoundType::B0,
+//!     model_type: ModelType::M3,
+//! };
+//! let models = vec![
+//!     Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap(),
+//!     Model::from_primitives(vec![-0.5_f32, 0.75, 0.].into_iter()).unwrap(),
+//! ];
+//! let scalars = vec![Scalar::new(1_u8, 4_u8), Scalar::new(3_u8, 4_u8)];
+//!
+//! let (_average, _unmasked, deviation) = simulate_aggregation(&models, &scalars, config);
+//! assert!(deviation < 1e-9);
+//! assert_aggregation(&models, &scalars, config);
+//! ```
+
+use num::{
+    bigint::BigInt,
+    rational::Ratio,
+    traits::{One, Signed, ToPrimitive, Zero},
+};
+
+use crate::mask::{Aggregation, MaskConfig, Masker, Model, Scalar};
+
+/// Aggregates the `models` weighted by the `scalars` in plaintext and via the PET pipeline.
+///
+/// Each model is masked with its scalar by a [`Masker`] and its mask is derived from the mask
+/// seed, as done by the sum participants. The masked models resp. masks are aggregated and the
+/// aggregated masked model is unmasked with the aggregated mask. The plaintext weighted average
+/// is `sum(scalar * model) / sum(scalar)`.
+///
+/// Returns the plaintext weighted average, the unmasked model and the maximum absolute deviation
+/// between them. The weights scaled by their scalars must be within the bound of the masking
+/// configuration, otherwise they are clamped during masking and the deviation is arbitrary.
+///
+/// # Panics
+/// Panics if there are no models, if the numbers of models and scalars differ, if the models
+/// have different lengths, if the sum of the scalars is zero or if the masking configuration
+/// doesn't support the number of models.
+pub fn simulate_aggregation(
+    models: &[Model],
+    scalars: &[Scalar],
+    config: MaskConfig,
+) -> (Model, Model, f64) {
+    let average = weighted_average(models, scalars);
+    let unmasked = masked_average(models, scalars, config);
+    let deviation = max_deviation(&average, &unmasked)
+        .to_f64()
+        .unwrap_or(f64::INFINITY);
+    (average, unmasked, deviation)
+}
+
+/// Gets the tolerance for the deviation of the unmasked model from the plaintext weighted
+/// `average` of models with the given `scalars`.
+///
+/// The aggregated scaled weights and the aggregated scalars each deviate by less than the
+/// [`max_absolute_error()`] of the masking configuration, hence each unmasked weight deviates by
+/// at most `(|average| + 1) * max_absolute_error / (sum(scalar) - max_absolute_error)`.
+///
+/// # Panics
+/// Panics if the sum of the scalars doesn't exceed the maximum absolute error, i.e. if the
+/// scalars are too small for the masking configuration.
+///
+/// [`max_absolute_error()`]: MaskConfig::max_absolute_error
+pub fn aggregation_tolerance(average: &Model, scalars: &[Scalar], config: MaskConfig) -> f64 {
+    tolerance(average, scalars, config)
+        .to_f64()
+        .unwrap_or(f64::INFINITY)
+}
+
+/// Asserts that the masked aggregation of the `models` deviates from their plaintext weighted
+/// average by at most the [`aggregation_tolerance()`].
+///
+/// The comparison is exact, i.e. it doesn't suffer from the rounding of the deviation and the
+/// tolerance to floats.
+///
+/// # Panics
+/// Panics if the deviation exceeds the tolerance and in the cases described for
+/// [`simulate_aggregation()`] and [`aggregation_tolerance()`].
+pub fn assert_aggregation(models: &[Model], scalars: &[Scalar], config: MaskConfig) {
+    let average = weighted_average(models, scalars);
+    let unmasked = masked_average(models, scalars, config);
+    let deviation = max_deviation(&average, &unmasked);
+    let tolerance = tolerance(&average, scalars, config);
+    assert!(
+        deviation <= tolerance,
+        "the masked aggregation deviates by {} from the plaintext average, the tolerance is {}",
+        deviation.to_f64().unwrap_or(f64::INFINITY),
+        tolerance.to_f64().unwrap_or(f64::INFINITY),
+    );
+}
+
+/// Asserts that the masked aggregation of the `models` deviates from their plaintext weighted
+/// average by at most the given `tolerance`.
+///
+/// # Panics
+/// Panics if the deviation exceeds the tolerance and in the cases described for
+/// [`simulate_aggregation()`].
+pub fn assert_aggregation_within(
+    models: &[Model],
+    scalars: &[Scalar],
+    config: MaskConfig,
+    tolerance: f64,
+) {
+    let (_, _, deviation) = simulate_aggregation(models, scalars, config);
+    assert!(
+        deviation <= tolerance,
+        "the masked aggregation deviates by {} from the plaintext average, the tolerance is {}",
+        deviation,
+        tolerance,
+    );
+}
+
+/// Computes the plaintext weighted average of the `models`.
+fn weighted_average(models: &[Model], scalars: &[Scalar]) -> Model {
+    assert!(!models.is_empty(), "there are no models to aggregate");
+    assert_eq!(
+        models.len(),
+        scalars.len(),
+        "the numbers of models and scalars differ"
+    );
+    let model_length = models[0].len();
+    assert!(
+        models.iter().all(|model| model.len() == model_length),
+        "the models have different lengths"
+    );
+    let scalars = scalars
+        .iter()
+        .cloned()
+        .map(Ratio::<BigInt>::from)
+        .collect::<Vec<_>>();
+    let scalar_sum = scalars.iter().sum::<Ratio<BigInt>>();
+    assert!(!scalar_sum.is_zero(), "the sum of the scalars is zero");
+
+    (0..model_length)
+        .map(|i| {
+            models
+                .iter()
+                .zip(scalars.iter())
+                .map(|(model, scalar)| scalar * &model[i])
+                .sum::<Ratio<BigInt>>()
+                / &scalar_sum
+        })
+        .collect()
+}
+
+/// Masks, aggregates and unmasks the `models`.
+fn masked_average(models: &[Model], scalars: &[Scalar], config: MaskConfig) -> Model {
+    let model_length = models[0].len();
+    let mut aggregated_masked_model = Aggregation::new(config.into(), model_length);
+    let mut aggregated_mask = Aggregation::new(config.into(), model_length);
+    for (model, scalar) in models.iter().zip(scalars) {
+        let (mask_seed, masked_model) = Masker::new(config.into()).mask(scalar.clone(), model);
+        let mask = mask_seed.derive_mask(model_length, config.into());
+
+        aggregated_masked_model
+            .validate_aggregation(&masked_model)
+            .expect("failed to aggregate the masked model");
+        aggregated_masked_model.aggregate(masked_model);
+        aggregated_mask
+            .validate_aggregation(&mask)
+            .expect("failed to aggregate the mask");
+        aggregated_mask.aggregate(mask);
+    }
+
+    let mask = aggregated_mask.into();
+    aggregated_masked_model
+        .validate_unmasking(&mask)
+        .expect("failed to unmask the aggregated masked model");
+    aggregated_masked_model.unmask(mask)
+}
+
+/// Gets the maximum absolute deviation of the weights of two models.
+fn max_deviation(model: &Model, other: &Model) -> Ratio<BigInt> {
+    model
+        .iter()
+        .zip(other.iter())
+        .map(|(weight, other)| (weight - other).abs())
+        .max()
+        .unwrap_or_else(Ratio::zero)
+}
+
+/// Gets the exact tolerance, see [`aggregation_tolerance()`].
+fn tolerance(average: &Model, scalars: &[Scalar], config: MaskConfig) -> Ratio<BigInt> {
+    let error = config.max_absolute_error(scalars.len());
+    let scalar_sum = scalars
+        .iter()
+        .cloned()
+        .map(Ratio::<BigInt>::from)
+        .sum::<Ratio<BigInt>>();
+    assert!(
+        scalar_sum > error,
+        "the scalars are too small for the masking configuration"
+    );
+    let max_weight = average
+        .iter()
+        .map(Signed::abs)
+        .max()
+        .unwrap_or_else(Ratio::zero);
+    (max_weight + Ratio::one()) * &error / (scalar_sum - &error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mask::{BoundType, DataType, FromPrimitives, GroupType, ModelType};
+
+    fn models() -> Vec<Model> {
+        vec![
+            vec![0.5_f32, -1., 0.125, 0.],
+            vec![-0.75_f32, 1., 0.375, 1.],
+            vec![1_f32, 0.25, -0.5, -1.],
+        ]
+        .into_iter()
+        .map(|weights| Model::from_primitives(weights.into_iter()).unwrap())
+        .collect()
+    }
+
+    fn scalars() -> Vec<Scalar> {
+        vec![
+            Scalar::new(1_u8, 2_u8),
+            Scalar::new(1_u8, 3_u8),
+            Scalar::new(1_u8, 6_u8),
+        ]
+    }
+
+    fn config() -> MaskConfig {
+        MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        }
+    }
+
+    #[test]
+    fn test_weighted_average() {
+        let (average, _, _) = simulate_aggregation(&models(), &scalars(), config());
+        let expected = vec![
+            Ratio::new(BigInt::from(1), BigInt::from(6)),
+            Ratio::new(BigInt::from(-1), BigInt::from(8)),
+            Ratio::new(BigInt::from(5), BigInt::from(48)),
+            Ratio::new(BigInt::from(1), BigInt::from(6)),
+        ];
+        assert_eq!(average, expected.into_iter().collect::<Model>());
+    }
+
+    #[test]
+    fn test_catalogue() {
+        let models = models();
+        let scalars = scalars();
+        for &data_type in [DataType::F32, DataType::F64, DataType::I32, DataType::I64].iter() {
+            for config in MaskConfig::catalogue(data_type) {
+                let (average, _, deviation) = simulate_aggregation(&models, &scalars, config);
+                assert!(deviation <= aggregation_tolerance(&average, &scalars, config));
+                assert_aggregation(&models, &scalars, config);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unit_scalars() {
+        let models = models();
+        let scalars = vec![Scalar::unit(); models.len()];
+        assert_aggregation(&models, &scalars, config());
+        assert_aggregation_within(&models, &scalars, config(), 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "deviates")]
+    fn test_clamped_weights() {
+        // the weights exceed the bound of the masking configuration and are clamped
+        let models = vec![
+            Model::from_primitives(vec![10_f32, -10.].into_iter()).unwrap(),
+            Model::from_primitives(vec![20_f32, 0.].into_iter()).unwrap(),
+        ];
+        let scalars = vec![Scalar::new(1_u8, 2_u8); 2];
+        assert_aggregation(&models, &scalars, config());
+    }
+
+    #[test]
+    #[should_panic(expected = "the numbers of models and scalars differ")]
+    fn test_missing_scalar() {
+        simulate_aggregation(&models(), &scalars()[1..], config());
+    }
+}
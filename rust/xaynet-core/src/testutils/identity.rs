@@ -0,0 +1,162 @@
+//! Masking without random elements, to test the aggregation apart from the masking.
+//!
+//! A [`Masker`] encodes the weights of a model into a finite group and adds random elements
+//! derived from its mask seed. The [`IdentityMasker`] encodes the weights in the same way, but
+//! doesn't add any random elements. Hence, an identity masked model is the plain integer
+//! encoding of the model and the aggregation of such models is unmasked by the
+//! [`IdentityMasker::neutral_mask()`]. A bug which shows up with the identity masker lies in the
+//! encoding, aggregation or unmasking, not in the derivation of the masks.
+//!
+//! ```
+//! use xaynet_core::{
+//!     mask::{
+//!         Aggregation,
+//!         BoundType,
+//!         DataType,
+//!         FromPrimitives,
+//!         GroupType,
+//!         MaskConfig,
+//!         Model,
+//!         ModelType,
+//!         Scalar,
+//!     },
+//!     testutils::identity::IdentityMasker,
+//! };
+//!
+//! let config = MaskConfig {
+//!     group_type: GroupType::Prime,
+//!     data_type: DataType::F32,
+//!     bound_type: BoundType::B0,
+//!     model_type: ModelType::M3,
+//! }
+//! .into();
+//! let model = Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap();
+//!
+//! let masker = IdentityMasker::new(config);
+//! let mut aggregation = Aggregation::new(config, model.len());
+//! aggregation.aggregate(masker.mask(Scalar::unit(), &model));
+//! assert_eq!(aggregation.unmask(masker.neutral_mask(model.len())), model);
+//! ```
+//!
+//! [`Masker`]: crate::mask::Masker
+
+use num::{bigint::BigUint, traits::Zero};
+
+use crate::mask::{
+    masking::encode_sections,
+    MaskConfigPair,
+    MaskObject,
+    MaskUnit,
+    MaskVect,
+    Model,
+    ModelConfig,
+    Scalar,
+};
+
+/// A masker which encodes models like a [`Masker`], but doesn't mask them.
+///
+/// [`Masker`]: crate::mask::Masker
+#[derive(Clone, Copy, Debug)]
+pub struct IdentityMasker {
+    config: MaskConfigPair,
+}
+
+impl IdentityMasker {
+    /// Creates a new identity masker with the given masking `config`uration.
+    pub fn new(config: MaskConfigPair) -> Self {
+        Self { config }
+    }
+
+    /// Encodes the `model` and the `scalar` wrt the masking configuration as in
+    /// [`Masker::mask()`], without adding random elements. The bounds on the scalar and the
+    /// weights are enforced as well.
+    ///
+    /// [`Masker::mask()`]: crate::mask::Masker::mask
+    pub fn mask(&self, scalar: Scalar, model: &Model) -> MaskObject {
+        let config = ModelConfig::single(self.config.vect, model.len());
+        let mut encoded = encode_sections(self.config, scalar, model, &config, |_| BigUint::zero());
+        // safe unwrap: the model config has exactly one section
+        let vect = encoded.vects.pop().unwrap();
+        MaskObject::new_unchecked(vect, encoded.unit)
+    }
+
+    /// Gets the mask which unmasks an aggregation of identity masked models of the given length.
+    ///
+    /// The neutral mask consists of zeros only, independently of the number of aggregated models.
+    pub fn neutral_mask(&self, len: usize) -> MaskObject {
+        MaskObject::new_unchecked(
+            MaskVect::new_unchecked(self.config.vect, vec![BigUint::zero(); len]),
+            MaskUnit::new_unchecked(self.config.unit, BigUint::zero()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::{
+        Aggregation,
+        BoundType,
+        DataType,
+        FromPrimitives,
+        GroupType,
+        MaskConfig,
+        ModelType,
+    };
+
+    fn config(data_type: DataType) -> MaskConfigPair {
+        MaskConfig {
+            group_type: GroupType::Prime,
+            data_type,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_identity_masking_roundtrip() {
+        let cases = vec![
+            (
+                config(DataType::F32),
+                Model::from_primitives(vec![0.5_f32, -0.25, 1., 0., -1.].into_iter()).unwrap(),
+            ),
+            (
+                config(DataType::F64),
+                Model::from_primitives(vec![0.125_f64, -0.875, 0.0625].into_iter()).unwrap(),
+            ),
+            (
+                config(DataType::I32),
+                Model::from_primitives(vec![1_i32, -1, 0, 1, -1].into_iter()).unwrap(),
+            ),
+        ];
+        for (config, model) in cases {
+            let masker = IdentityMasker::new(config);
+            let masked = masker.mask(Scalar::unit(), &model);
+            assert!(masked.is_valid());
+            // the encoding is deterministic
+            assert_eq!(masked, masker.mask(Scalar::unit(), &model));
+
+            let mut aggregation = Aggregation::new(config, model.len());
+            aggregation.aggregate(masked);
+            assert_eq!(aggregation.unmask(masker.neutral_mask(model.len())), model);
+        }
+    }
+
+    #[test]
+    fn test_identity_masking_aggregation() {
+        let config = config(DataType::F32);
+        let models = [
+            Model::from_primitives(vec![0.5_f32, -0.25, 1.].into_iter()).unwrap(),
+            Model::from_primitives(vec![-0.5_f32, 0.75, 0.].into_iter()).unwrap(),
+        ];
+        let scalar = Scalar::new(1_u8, 2_u8);
+        let masker = IdentityMasker::new(config);
+        let mut aggregation = Aggregation::new(config, 3);
+        for model in models.iter() {
+            aggregation.aggregate(masker.mask(scalar.clone(), model));
+        }
+        let expected = Model::from_primitives(vec![0_f32, 0.25, 0.5].into_iter()).unwrap();
+        assert_eq!(aggregation.unmask(masker.neutral_mask(3)), expected);
+    }
+}
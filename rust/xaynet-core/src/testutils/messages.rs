@@ -6,9 +6,18 @@ use std::convert::TryFrom;
 use num::BigUint;
 
 use crate::{
-    crypto::{ByteObject, PublicEncryptKey, PublicSigningKey, Signature},
-    mask::EncryptedMaskSeed,
-    message::{Message, Payload, Sum, Sum2, Tag, Update},
+    crypto::{ByteObject, PublicEncryptKey, PublicSigningKey, Sha256, Signature},
+    mask::{EncryptedMaskSeed, SealedBox, SeedCipher},
+    message::{
+        Message,
+        Payload,
+        Sum,
+        Sum2,
+        Tag,
+        Update,
+        SUM2_PAYLOAD_VERSION,
+        UPDATE_PAYLOAD_VERSION,
+    },
     LocalSeedDict,
 };
 
@@ -60,7 +69,9 @@ where
         coordinator_pk: coordinator_pk().0,
         payload,
         is_multipart: false,
+        round_bound_masks: false,
         tag,
+        extensions: Vec::new(),
     };
 
     let mut buf = signature().1;
@@ -121,10 +132,17 @@ pub mod update {
         (signature, bytes)
     }
 
+    /// Return a fake model checksum and its serialized version
+    pub fn model_checksum() -> (Sha256, Vec<u8>) {
+        let bytes = vec![0x15; 32];
+        let checksum = Sha256::from_slice(&bytes[..]).unwrap();
+        (checksum, bytes)
+    }
+
     /// Return a local seed dictionary with two entries with its
     /// expected serialized version
     pub fn local_seed_dict() -> (LocalSeedDict, Vec<u8>) {
-        let mut local_seed_dict = LocalSeedDict::new();
+        let mut local_seed_dict = LocalSeedDict::default();
         let mut bytes = vec![];
 
         // Length (32+80) * 2 + 4 = 228
@@ -132,7 +150,7 @@ pub mod update {
 
         bytes.extend(vec![0x55; PublicSigningKey::LENGTH]);
         bytes.extend(vec![0x66; EncryptedMaskSeed::LENGTH]);
-        local_seed_dict.insert(
+        local_seed_dict.0.insert(
             PublicSigningKey::from_slice(vec![0x55; 32].as_slice()).unwrap(),
             EncryptedMaskSeed::try_from(vec![0x66; EncryptedMaskSeed::LENGTH]).unwrap(),
         );
@@ -140,7 +158,7 @@ pub mod update {
         // Second entry
         bytes.extend(vec![0x77; PublicSigningKey::LENGTH]);
         bytes.extend(vec![0x88; EncryptedMaskSeed::LENGTH]);
-        local_seed_dict.insert(
+        local_seed_dict.0.insert(
             PublicSigningKey::from_slice(vec![0x77; 32].as_slice()).unwrap(),
             EncryptedMaskSeed::try_from(vec![0x88; EncryptedMaskSeed::LENGTH]).unwrap(),
         );
@@ -148,16 +166,28 @@ pub mod update {
         (local_seed_dict, bytes)
     }
 
+    /// Return the seed cipher and seed length fields of an update payload whose seeds are
+    /// sealed boxes
+    pub fn seed_cipher() -> (u8, Vec<u8>) {
+        // Seed length = 80
+        (SealedBox::ID, vec![SealedBox::ID, 0x00, 0x50])
+    }
+
     /// Return an update payload with its serialized version
     pub fn payload() -> (Update, Vec<u8>) {
         let mut bytes = sum_task_signature().1;
         bytes.extend(update_task_signature().1);
+        bytes.push(UPDATE_PAYLOAD_VERSION);
+        bytes.extend(model_checksum().1);
+        bytes.extend(seed_cipher().1);
         bytes.extend(mask_object().1);
         bytes.extend(local_seed_dict().1);
 
         let update = Update {
             sum_signature: sum_task_signature().0,
             update_signature: update_task_signature().0,
+            model_checksum: model_checksum().0,
+            seed_cipher: seed_cipher().0,
             masked_model: mask_object().0,
             local_seed_dict: local_seed_dict().0,
         };
@@ -172,14 +202,43 @@ pub mod sum2 {
 
     use super::*;
 
+    /// Return a seed dictionary version and its serialized version
+    pub fn seed_dict_version() -> (u64, Vec<u8>) {
+        let version = 0x1122_3344_5566_7788_u64;
+        (version, version.to_be_bytes().to_vec())
+    }
+
     /// Return a sum2 message and its serialized version
     pub fn payload() -> (Sum2, Vec<u8>) {
+        let (sum_signature, sum_signature_bytes) = sum_task_signature();
+        let (seed_dict_version, seed_dict_version_bytes) = seed_dict_version();
+        let (model_mask, model_mask_bytes) = mask_object();
+        let bytes = [
+            sum_signature_bytes.as_slice(),
+            &[SUM2_PAYLOAD_VERSION],
+            seed_dict_version_bytes.as_slice(),
+            model_mask_bytes.as_slice(),
+        ]
+        .concat();
+
+        let sum2 = Sum2 {
+            sum_signature,
+            seed_dict_version: Some(seed_dict_version),
+            model_mask,
+        };
+        (sum2, bytes)
+    }
+
+    /// Return a sum2 message of the legacy layout, without a seed dictionary version, and its
+    /// serialized version
+    pub fn legacy_payload() -> (Sum2, Vec<u8>) {
         let (sum_signature, sum_signature_bytes) = sum_task_signature();
         let (model_mask, model_mask_bytes) = mask_object();
         let bytes = [sum_signature_bytes.as_slice(), model_mask_bytes.as_slice()].concat();
 
         let sum2 = Sum2 {
             sum_signature,
+            seed_dict_version: None,
             model_mask,
         };
         (sum2, bytes)
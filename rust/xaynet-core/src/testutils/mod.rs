@@ -1,2 +1,4 @@
+pub mod aggregation;
+pub mod identity;
 pub mod messages;
 pub mod multipart;
@@ -1,7 +1,7 @@
 use num::BigUint;
 
 use crate::{
-    crypto::{ByteObject, PublicSigningKey, Signature},
+    crypto::{ByteObject, PublicSigningKey, Sha256, Signature},
     mask::{
         BoundType,
         DataType,
@@ -12,6 +12,8 @@ use crate::{
         MaskUnit,
         MaskVect,
         ModelType,
+        SealedBox,
+        SeedCipher,
     },
     message::{Message, ToBytes, Update},
     testutils::messages,
@@ -28,14 +30,14 @@ pub fn local_seed_dict(len: usize) -> LocalSeedDict {
     }
 
     let nb_entries = (len - 4) / entry_len;
-    let mut dict = LocalSeedDict::new();
+    let mut dict = LocalSeedDict::default();
     for i in 0..nb_entries {
         let bytes = (i as u64).to_be_bytes();
         let pk_bytes = bytes.iter().cycle().take(32).copied().collect::<Vec<_>>();
         let seed_bytes = bytes.iter().cycle().take(80).copied().collect::<Vec<_>>();
         let pk = PublicSigningKey::from_slice(pk_bytes.as_slice()).unwrap();
         let mask_seed = EncryptedMaskSeed::from_slice(seed_bytes.as_slice()).unwrap();
-        dict.insert(pk, mask_seed);
+        dict.0.insert(pk, mask_seed);
     }
 
     // Check that our calculations are correct
@@ -106,11 +108,14 @@ pub fn task_signatures() -> (Signature, Signature) {
 /// ```no_rust
 /// (mask_len - 22) % 6 = 0
 /// (dict_len - 4) % 112 = 0
-/// S = dict_len + mask_len + 64*2
+/// S = dict_len + mask_len + 64*2 + 1 + 32 + 3
 /// ```
 pub fn update(dict_len: usize, mask_obj_len: usize) -> Update {
     // An update message is made of:
     // - 2 signatures of 64 bytes each
+    // - a version of 1 byte
+    // - a model checksum of 32 bytes
+    // - a seed cipher of 1 byte and a seed length of 2 bytes
     // - a mask object of variable length
     // - a seed dictionary of variable length
     //
@@ -118,17 +123,22 @@ pub fn update(dict_len: usize, mask_obj_len: usize) -> Update {
     // crate::messages::HEADER_LEN). So a message with
     // `dict_len` = 100 and `mask_obj_len` = 100 will be:
     //
-    //    100 + 100 + 64*2 + 136 = 464 bytes
+    //    100 + 100 + 64*2 + 1 + 32 + 3 + 136 = 500 bytes
     let (sum_signature, update_signature) = task_signatures();
 
     let payload = Update {
         sum_signature,
         update_signature,
+        model_checksum: Sha256::zeroed(),
+        seed_cipher: SealedBox::ID,
         masked_model: mask_object(mask_obj_len),
         local_seed_dict: local_seed_dict(dict_len),
     };
 
-    assert_eq!(payload.buffer_length(), mask_obj_len + dict_len + 64 * 2);
+    assert_eq!(
+        payload.buffer_length(),
+        mask_obj_len + dict_len + 64 * 2 + 1 + 32 + 3
+    );
     payload
 }
 
@@ -139,7 +149,7 @@ pub fn update(dict_len: usize, mask_obj_len: usize) -> Update {
 /// ```no_rust
 /// (mask_len - 22) % 6 = 0
 /// (dict_len - 4) % 112 = 0
-/// S = dict_len + mask_len + 64*2 + 136
+/// S = dict_len + mask_len + 64*2 + 1 + 32 + 3 + 136
 /// ```
 pub fn message(dict_len: usize, mask_obj_len: usize) -> Message {
     let (message, _) = messages::message(|| {
@@ -0,0 +1,7 @@
+//! Checks that the invariants of the seed dictionaries are enforced at compile time.
+
+#[test]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile_fail/*.rs");
+}
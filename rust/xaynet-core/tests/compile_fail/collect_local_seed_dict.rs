@@ -0,0 +1,7 @@
+use xaynet_core::{crypto::ByteObject, mask::EncryptedMaskSeed, LocalSeedDict, SumParticipantPublicKey};
+
+fn main() {
+    let _local_seed_dict = vec![(SumParticipantPublicKey::zeroed(), EncryptedMaskSeed::zeroed())]
+        .into_iter()
+        .collect::<LocalSeedDict>();
+}
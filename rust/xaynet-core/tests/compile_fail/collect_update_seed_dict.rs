@@ -0,0 +1,12 @@
+use xaynet_core::{
+    crypto::ByteObject,
+    mask::EncryptedMaskSeed,
+    UpdateParticipantPublicKey,
+    UpdateSeedDict,
+};
+
+fn main() {
+    let _update_seed_dict = vec![(UpdateParticipantPublicKey::zeroed(), EncryptedMaskSeed::zeroed())]
+        .into_iter()
+        .collect::<UpdateSeedDict>();
+}
@@ -0,0 +1,7 @@
+use std::collections::HashMap;
+
+use xaynet_core::UpdateSeedDict;
+
+fn main() {
+    let _update_seed_dict = UpdateSeedDict(HashMap::new());
+}
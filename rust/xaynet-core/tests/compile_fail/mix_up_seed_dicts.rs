@@ -0,0 +1,7 @@
+use xaynet_core::{LocalSeedDict, UpdateSeedDict};
+
+fn send_to_sum_participant(_update_seed_dict: UpdateSeedDict) {}
+
+fn main() {
+    send_to_sum_participant(LocalSeedDict::default());
+}
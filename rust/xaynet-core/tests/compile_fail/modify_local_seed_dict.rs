@@ -0,0 +1,6 @@
+use xaynet_core::{crypto::ByteObject, mask::EncryptedMaskSeed, LocalSeedDict, SumParticipantPublicKey};
+
+fn main() {
+    let mut local_seed_dict = LocalSeedDict::default();
+    local_seed_dict.insert(SumParticipantPublicKey::zeroed(), EncryptedMaskSeed::zeroed());
+}
@@ -0,0 +1,195 @@
+use std::os::raw::c_int;
+
+use xaynet_sdk::budget::ComputeTask;
+
+use super::{PARTICIPANT_TASK_NONE, PARTICIPANT_TASK_SUM, PARTICIPANT_TASK_UPDATE};
+use crate::{ErrorKind, Event, Task};
+
+/// A new round started
+pub const EVENT_KIND_NEW_ROUND: c_int = 1;
+/// The participant has been assigned a task for the current round
+pub const EVENT_KIND_TASK_ASSIGNED: c_int = 2;
+/// The global model of the previous round is available
+pub const EVENT_KIND_GLOBALMODEL_READY: c_int = 3;
+/// The participant sent its update message
+pub const EVENT_KIND_UPDATE_SENT: c_int = 4;
+/// The participant sent its sum2 message
+pub const EVENT_KIND_SUM2_SENT: c_int = 5;
+/// The previous round is over
+pub const EVENT_KIND_ROUND_COMPLETED: c_int = 6;
+/// The participant dropped out of the current round
+pub const EVENT_KIND_ERROR: c_int = 7;
+/// The participant doesn't take part in the current round, because the advertised masking
+/// configuration is not supported
+pub const EVENT_KIND_UNSUPPORTED_MASK_CONFIG: c_int = 8;
+/// The participant skips or aborts a task of the current round, because a computation exceeds
+/// its compute budget
+pub const EVENT_KIND_BUDGET_EXCEEDED: c_int = 9;
+/// The participant doesn't take part in the current round, because the crypto suite of the round
+/// is not supported
+pub const EVENT_KIND_UNSUPPORTED_CRYPTO_SUITE: c_int = 10;
+/// The event queue overflowed and the oldest events have been dropped
+pub const EVENT_KIND_EVENTS_DROPPED: c_int = 11;
+
+/// The coordinator rejected a message of the participant
+pub const EVENT_ERROR_MESSAGE_REJECTED: c_int = 1;
+/// A message of the participant couldn't be sent before the retry deadline passed
+pub const EVENT_ERROR_MESSAGE_EXPIRED: c_int = 2;
+/// The mask seeds of the update participants couldn't be decrypted
+pub const EVENT_ERROR_INVALID_SEEDS: c_int = 3;
+/// The local model of the participant changed while it was masked
+pub const EVENT_ERROR_MODEL_CORRUPTED: c_int = 4;
+/// The coordinator banned the participant, which doesn't take part in any further round
+pub const EVENT_ERROR_BANNED: c_int = 5;
+/// The generated ephemeral keys of the sum task repeat the signing keys or the ephemeral keys of
+/// the previous round, which indicates a broken random number generator
+pub const EVENT_ERROR_INSECURE_EPHEMERAL_KEYS: c_int = 6;
+/// The public key of the coordinator doesn't match the pinned key
+pub const EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY: c_int = 7;
+/// The coordinator evicted the participant from the bounded sum dictionary
+pub const EVENT_ERROR_EVICTED: c_int = 8;
+/// The parameters of the round don't match the commitment of the coordinator in the previous round
+pub const EVENT_ERROR_UNCOMMITTED_ROUND_PARAMETERS: c_int = 9;
+/// A message of the participant couldn't be sent before a new round started
+pub const EVENT_ERROR_MESSAGE_OUTDATED: c_int = 10;
+
+/// The masking of the local model in the update task
+pub const COMPUTE_TASK_MASKING: c_int = 1;
+/// The derivation and aggregation of the masks in the sum2 task
+pub const COMPUTE_TASK_SUM2: c_int = 2;
+
+#[repr(C)]
+#[derive(Default)]
+/// An event emitted by the participant, see [`xaynet_ffi_participant_next_event()`].
+///
+/// Only the fields that belong to the `kind` of the event are set, the other fields are
+/// zero.
+///
+/// [`xaynet_ffi_participant_next_event()`]: crate::ffi::xaynet_ffi_participant_next_event
+pub struct FfiEvent {
+    /// The kind of the event, one of the `EVENT_KIND_*` constants.
+    pub kind: c_int,
+    /// The round counted by the participant, for [`EVENT_KIND_NEW_ROUND`] and
+    /// [`EVENT_KIND_GLOBALMODEL_READY`] events.
+    pub round_id: u64,
+    /// The length of the global model, for [`EVENT_KIND_GLOBALMODEL_READY`] events.
+    pub length: u64,
+    /// The assigned task, for [`EVENT_KIND_TASK_ASSIGNED`] events. One of
+    /// [`PARTICIPANT_TASK_NONE`], [`PARTICIPANT_TASK_SUM`] and [`PARTICIPANT_TASK_UPDATE`].
+    pub task: c_int,
+    /// Whether the message of the participant made it to the coordinator, for
+    /// [`EVENT_KIND_ROUND_COMPLETED`] events: `1` if it did, `0` if it didn't and `-1` if
+    /// the participant didn't send any message.
+    pub included: c_int,
+    /// The reason of the failure, for [`EVENT_KIND_ERROR`] events. One of the
+    /// `EVENT_ERROR_*` constants.
+    pub error: c_int,
+    /// The masking configuration advertised by the coordinator, for
+    /// [`EVENT_KIND_UNSUPPORTED_MASK_CONFIG`] events. The group, data, bound and model types are
+    /// encoded in this order, see [`xaynet_ffi_settings_set_mask_config_bytes()`].
+    ///
+    /// [`xaynet_ffi_settings_set_mask_config_bytes()`]: crate::ffi::xaynet_ffi_settings_set_mask_config_bytes
+    pub mask_config: [u8; 4],
+    /// The computation which exceeded its budget, for [`EVENT_KIND_BUDGET_EXCEEDED`] events.
+    /// One of [`COMPUTE_TASK_MASKING`] and [`COMPUTE_TASK_SUM2`].
+    pub compute_task: c_int,
+    /// The estimated cost of the masking or the time already spent on the sum2 masks, in
+    /// milliseconds, for [`EVENT_KIND_BUDGET_EXCEEDED`] events.
+    pub cost_millis: u64,
+    /// The budget of the computation, in milliseconds, for [`EVENT_KIND_BUDGET_EXCEEDED`]
+    /// events.
+    pub budget_millis: u64,
+    /// The identifier of the crypto suite of the round, for
+    /// [`EVENT_KIND_UNSUPPORTED_CRYPTO_SUITE`] events.
+    pub crypto_suite: u8,
+    /// The number of events which have been dropped before the following events, for
+    /// [`EVENT_KIND_EVENTS_DROPPED`] events.
+    pub dropped: u64,
+}
+
+impl From<Event> for FfiEvent {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::NewRound { round_id } => FfiEvent {
+                kind: EVENT_KIND_NEW_ROUND,
+                round_id,
+                ..Default::default()
+            },
+            Event::TaskAssigned(task) => FfiEvent {
+                kind: EVENT_KIND_TASK_ASSIGNED,
+                task: match task {
+                    Task::None => PARTICIPANT_TASK_NONE,
+                    Task::Sum => PARTICIPANT_TASK_SUM,
+                    Task::Update => PARTICIPANT_TASK_UPDATE,
+                },
+                ..Default::default()
+            },
+            Event::GlobalModelReady { round_id, length } => FfiEvent {
+                kind: EVENT_KIND_GLOBALMODEL_READY,
+                round_id,
+                length: length as u64,
+                ..Default::default()
+            },
+            Event::UpdateSent => FfiEvent {
+                kind: EVENT_KIND_UPDATE_SENT,
+                ..Default::default()
+            },
+            Event::Sum2Sent => FfiEvent {
+                kind: EVENT_KIND_SUM2_SENT,
+                ..Default::default()
+            },
+            Event::RoundCompleted { included } => FfiEvent {
+                kind: EVENT_KIND_ROUND_COMPLETED,
+                included: match included {
+                    Some(true) => 1,
+                    Some(false) => 0,
+                    None => -1,
+                },
+                ..Default::default()
+            },
+            Event::Error(error) => FfiEvent {
+                kind: EVENT_KIND_ERROR,
+                error: match error {
+                    ErrorKind::MessageRejected => EVENT_ERROR_MESSAGE_REJECTED,
+                    ErrorKind::MessageExpired => EVENT_ERROR_MESSAGE_EXPIRED,
+                    ErrorKind::MessageOutdated => EVENT_ERROR_MESSAGE_OUTDATED,
+                    ErrorKind::InvalidSeeds => EVENT_ERROR_INVALID_SEEDS,
+                    ErrorKind::ModelCorrupted => EVENT_ERROR_MODEL_CORRUPTED,
+                    ErrorKind::Banned => EVENT_ERROR_BANNED,
+                    ErrorKind::InsecureEphemeralKeys => EVENT_ERROR_INSECURE_EPHEMERAL_KEYS,
+                    ErrorKind::UntrustedCoordinatorKey => EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY,
+                    ErrorKind::Evicted => EVENT_ERROR_EVICTED,
+                    ErrorKind::UncommittedRoundParameters => {
+                        EVENT_ERROR_UNCOMMITTED_ROUND_PARAMETERS
+                    }
+                },
+                ..Default::default()
+            },
+            Event::UnsupportedMaskConfig { advertised } => FfiEvent {
+                kind: EVENT_KIND_UNSUPPORTED_MASK_CONFIG,
+                mask_config: advertised.into(),
+                ..Default::default()
+            },
+            Event::BudgetExceeded { exceeded } => FfiEvent {
+                kind: EVENT_KIND_BUDGET_EXCEEDED,
+                compute_task: match exceeded.task {
+                    ComputeTask::Masking => COMPUTE_TASK_MASKING,
+                    ComputeTask::Sum2 => COMPUTE_TASK_SUM2,
+                },
+                cost_millis: exceeded.cost.as_millis() as u64,
+                budget_millis: exceeded.budget.as_millis() as u64,
+                ..Default::default()
+            },
+            Event::UnsupportedCryptoSuite { suite } => FfiEvent {
+                kind: EVENT_KIND_UNSUPPORTED_CRYPTO_SUITE,
+                crypto_suite: suite.id(),
+                ..Default::default()
+            },
+            Event::EventsDropped { count } => FfiEvent {
+                kind: EVENT_KIND_EVENTS_DROPPED,
+                dropped: count,
+                ..Default::default()
+            },
+        }
+    }
+}
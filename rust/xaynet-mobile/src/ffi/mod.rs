@@ -9,6 +9,9 @@ pub use settings::*;
 mod config;
 pub use config::*;
 
+mod event;
+pub use event::*;
+
 pub use ffi_support::{ByteBuffer, FfiStr};
 use std::os::raw::c_int;
 
@@ -107,3 +110,19 @@ pub const ERR_GLOBALMODEL_DATATYPE: c_int = 13;
 pub const ERR_GLOBALMODEL_LEN: c_int = 14;
 /// Failed to get the global model: invalid model
 pub const ERR_GLOBALMODEL_CONVERT: c_int = 15;
+/// No event is currently pending
+pub const EVENT_NONE: c_int = 16;
+/// Invalid masking configuration: the buffer is not 4 bytes long
+pub const ERR_MASKCONFIG_LEN: c_int = 17;
+/// Invalid masking configuration: invalid group type
+pub const ERR_MASKCONFIG_GROUPTYPE: c_int = 18;
+/// Invalid masking configuration: invalid data type
+pub const ERR_MASKCONFIG_DATATYPE: c_int = 19;
+/// Invalid masking configuration: invalid bound type
+pub const ERR_MASKCONFIG_BOUNDTYPE: c_int = 20;
+/// Invalid masking configuration: invalid model type
+pub const ERR_MASKCONFIG_MODELTYPE: c_int = 21;
+/// The cost of masking has not been calibrated yet
+pub const CALIBRATION_NONE: c_int = 22;
+/// Invalid coordinator public key: the buffer is not 32 bytes long
+pub const ERR_COORDINATOR_PK_LEN: c_int = 23;
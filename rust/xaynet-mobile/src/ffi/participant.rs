@@ -3,20 +3,25 @@ use std::{
     os::raw::{c_int, c_uchar, c_uint, c_void},
     ptr,
     slice,
+    time::Duration,
 };
 
 use ffi_support::{ByteBuffer, FfiStr};
 use xaynet_core::mask::{DataType, FromPrimitives, IntoPrimitives, Model};
 
 use super::{
+    FfiEvent,
     LocalModelConfig,
+    CALIBRATION_NONE,
     ERR_GLOBALMODEL_CONVERT,
     ERR_GLOBALMODEL_DATATYPE,
     ERR_GLOBALMODEL_IO,
     ERR_GLOBALMODEL_LEN,
+    ERR_MASKCONFIG_LEN,
     ERR_NULLPTR,
     ERR_SETMODEL_DATATYPE,
     ERR_SETMODEL_MODEL,
+    EVENT_NONE,
     GLOBALMODEL_NONE,
     OK,
 };
@@ -177,6 +182,101 @@ pub unsafe extern "C" fn xaynet_ffi_participant_tick(participant: *mut Participa
     flags
 }
 
+/// Pop the next event emitted by the participant and write it into `out_event`. The
+/// events are emitted while [`xaynet_ffi_participant_tick()`] drives the participant, so
+/// they should be drained after each tick.
+///
+/// At most 64 events are queued. If the caller doesn't keep up, the oldest events are
+/// dropped and an [`EVENT_KIND_EVENTS_DROPPED`] event with the number of dropped events is
+/// written before the remaining events.
+///
+/// # Return value
+///
+/// - [`OK`] if an event has been written into `out_event`
+/// - [`EVENT_NONE`] if no event is pending, or if the participant has been configured in
+///   notify-only mode (see [`xaynet_ffi_settings_set_notify_only()`])
+/// - [`ERR_NULLPTR`] if `participant` or `out_event` is NULL
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL *or*
+/// all of the following is true:
+///
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+/// [`xaynet_ffi_settings_set_notify_only()`]: crate::ffi::xaynet_ffi_settings_set_notify_only
+/// [`EVENT_KIND_EVENTS_DROPPED`]: crate::ffi::EVENT_KIND_EVENTS_DROPPED
+///
+/// # Example
+///
+/// ```c
+///  xaynet_ffi_participant_tick(participant);
+///  FfiEvent event;
+///  while (xaynet_ffi_participant_next_event(participant, &event) == OK) {
+///      if (event.kind == EVENT_KIND_GLOBALMODEL_READY) {
+///          // fetch the global model
+///      }
+///  }
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_participant_next_event(
+    participant: *mut Participant,
+    out_event: *mut FfiEvent,
+) -> c_int {
+    let participant = match unsafe { participant.as_mut() } {
+        Some(participant) => participant,
+        None => return ERR_NULLPTR,
+    };
+    let out_event = match unsafe { out_event.as_mut() } {
+        Some(out_event) => out_event,
+        None => return ERR_NULLPTR,
+    };
+
+    match participant.next_event() {
+        Some(event) => {
+            *out_event = event.into();
+            OK
+        }
+        None => EVENT_NONE,
+    }
+}
+
+/// Release the memory the participant holds beyond its state, e.g. before saving a
+/// long-running participant with [`xaynet_ffi_participant_save()`]. The latest global model
+/// is not cached anymore, hence the next global model is fetched in full. Events that have
+/// not been drained yet are kept.
+///
+/// # Return value
+///
+/// - [`OK`] on success
+/// - [`ERR_NULLPTR`] if `participant` is NULL
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointer is NULL *or*
+/// all of the following is true:
+///
+/// - The pointer must be properly [aligned].
+/// - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_participant_compact(participant: *mut Participant) -> c_int {
+    let participant = match unsafe { participant.as_mut() } {
+        Some(participant) => participant,
+        None => return ERR_NULLPTR,
+    };
+
+    participant.compact();
+    OK
+}
+
 /// Serialize the participant state and return a buffer that contains the serialized
 /// participant.
 ///
@@ -477,3 +577,145 @@ pub unsafe extern "C" fn xaynet_ffi_participant_local_model_config(
 
     Box::into_raw(Box::new(participant.local_model_config().into()))
 }
+
+/// Write the masking configuration advertised by the coordinator for the current round into
+/// `buffer`, as its 4 bytes serialization: the group type, the data type, the bound type
+/// and the model type, see [`xaynet_ffi_settings_set_mask_config_bytes()`].
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `participant` or `buffer` is `NULL`
+/// - [`ERR_MASKCONFIG_LEN`] if `len` is not 4
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL
+/// *or* all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+/// - `buffer` must point to `len` consecutive writable bytes.
+///
+/// [`xaynet_ffi_settings_set_mask_config_bytes()`]: crate::ffi::xaynet_ffi_settings_set_mask_config_bytes
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_participant_mask_config(
+    participant: *const Participant,
+    buffer: *mut u8,
+    len: usize,
+) -> c_int {
+    let participant = match unsafe { participant.as_ref() } {
+        Some(participant) => participant,
+        None => return ERR_NULLPTR,
+    };
+    if buffer.is_null() {
+        return ERR_NULLPTR;
+    }
+    if len != 4 {
+        return ERR_MASKCONFIG_LEN;
+    }
+
+    let buffer = unsafe { slice::from_raw_parts_mut(buffer, len) };
+    buffer.copy_from_slice(&<[u8; 4]>::from(participant.mask_config()));
+    OK
+}
+
+/// Write the calibrated cost of masking a single weight, in nanoseconds, into
+/// `out_nanos_per_weight`. The calibration runs once the masking is bounded by the compute
+/// budget, see [`xaynet_ffi_settings_set_compute_budget()`].
+///
+/// # Return value
+///
+/// - [`OK`] if the cost has been written into `out_nanos_per_weight`
+/// - [`CALIBRATION_NONE`] if the cost of masking has not been calibrated yet
+/// - [`ERR_NULLPTR`] if `participant` or `out_nanos_per_weight` is NULL
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL *or*
+/// all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`xaynet_ffi_settings_set_compute_budget()`]: crate::ffi::xaynet_ffi_settings_set_compute_budget
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_participant_calibration(
+    participant: *const Participant,
+    out_nanos_per_weight: *mut u64,
+) -> c_int {
+    let participant = match unsafe { participant.as_ref() } {
+        Some(participant) => participant,
+        None => return ERR_NULLPTR,
+    };
+    let out_nanos_per_weight = match unsafe { out_nanos_per_weight.as_mut() } {
+        Some(out_nanos_per_weight) => out_nanos_per_weight,
+        None => return ERR_NULLPTR,
+    };
+
+    match participant.calibration() {
+        Some(calibration) => {
+            *out_nanos_per_weight = calibration.masking_nanos_per_weight;
+            OK
+        }
+        None => CALIBRATION_NONE,
+    }
+}
+
+#[repr(C)]
+/// The actual costs of the computations of the participant, in the latest rounds it performed
+/// them, see [`xaynet_ffi_participant_last_costs()`].
+///
+/// The costs are in microseconds, or `-1` if the participant didn't perform the computation
+/// yet.
+pub struct FfiComputeCosts {
+    /// The time spent on masking the local model.
+    pub masking_micros: i64,
+    /// The time spent on deriving and aggregating the masks in the sum2 task.
+    pub sum2_micros: i64,
+}
+
+/// Write the actual costs of masking and of the sum2 task, in the latest rounds the
+/// participant performed them, into `out_costs`.
+///
+/// # Return value
+///
+/// - [`OK`] if the costs have been written into `out_costs`
+/// - [`ERR_NULLPTR`] if `participant` or `out_costs` is NULL
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL *or*
+/// all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_participant_last_costs(
+    participant: *const Participant,
+    out_costs: *mut FfiComputeCosts,
+) -> c_int {
+    let participant = match unsafe { participant.as_ref() } {
+        Some(participant) => participant,
+        None => return ERR_NULLPTR,
+    };
+    let out_costs = match unsafe { out_costs.as_mut() } {
+        Some(out_costs) => out_costs,
+        None => return ERR_NULLPTR,
+    };
+
+    let micros = |cost: Option<Duration>| cost.map_or(-1, |cost| cost.as_micros() as i64);
+    let costs = participant.last_costs();
+    *out_costs = FfiComputeCosts {
+        masking_micros: micros(costs.masking),
+        sum2_micros: micros(costs.sum2),
+    };
+    OK
+}
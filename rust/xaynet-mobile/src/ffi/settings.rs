@@ -1,13 +1,27 @@
-use std::os::raw::{c_double, c_int};
+use std::{
+    convert::TryFrom,
+    os::raw::{c_double, c_int},
+    slice,
+};
 
 use ffi_support::{ByteBuffer, FfiStr};
-use xaynet_core::crypto::{ByteObject, PublicSigningKey, SecretSigningKey, SigningKeyPair};
+use xaynet_core::{
+    crypto::{ByteObject, PublicSigningKey, SecretSigningKey, SigningKeyPair},
+    mask::{InvalidMaskConfigError, MaskConfig},
+    CoordinatorPublicKey,
+};
 use zeroize::Zeroize;
 
 use super::{
+    ERR_COORDINATOR_PK_LEN,
     ERR_CRYPTO_PUBLIC_KEY,
     ERR_CRYPTO_SECRET_KEY,
     ERR_INVALID_URL,
+    ERR_MASKCONFIG_BOUNDTYPE,
+    ERR_MASKCONFIG_DATATYPE,
+    ERR_MASKCONFIG_GROUPTYPE,
+    ERR_MASKCONFIG_LEN,
+    ERR_MASKCONFIG_MODELTYPE,
     ERR_NULLPTR,
     ERR_SETTINGS_KEYS,
     ERR_SETTINGS_SCALAR,
@@ -94,6 +108,218 @@ pub unsafe extern "C" fn xaynet_ffi_settings_set_scalar(
     }
 }
 
+/// Set the scalar setting to the exact fraction `numer / denom`.
+///
+/// Unlike [`xaynet_ffi_settings_set_scalar()`], the scalar doesn't suffer from the binary
+/// rounding of floats, e.g. `1 / 3` is exactly a third. A zero denominator is reported as an
+/// invalid scalar by [`xaynet_ffi_check_settings()`].
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `settings` is `NULL`
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointer is NULL *or*
+/// all of the following is true:
+/// - The pointer must be properly [aligned].
+/// - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_scalar_ratio(
+    settings: *mut Settings,
+    numer: u64,
+    denom: u64,
+) -> c_int {
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_scalar_ratio(numer, denom);
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
+/// Set whether the participant runs in notify-only mode. In notify-only mode, the
+/// participant doesn't queue any events for [`xaynet_ffi_participant_next_event()`] and
+/// the caller has to check the flags returned by [`xaynet_ffi_participant_tick()`]
+/// instead.
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `settings` is `NULL`
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointer is NULL *or*
+/// all of the following is true:
+/// - The pointer must be properly [aligned].
+/// - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+/// [`xaynet_ffi_participant_next_event()`]: crate::ffi::xaynet_ffi_participant_next_event
+/// [`xaynet_ffi_participant_tick()`]: crate::ffi::xaynet_ffi_participant_tick
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_notify_only(
+    settings: *mut Settings,
+    notify_only: bool,
+) -> c_int {
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_notify_only(notify_only);
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
+/// Set the masking configuration the participant expects from the coordinator, from its
+/// 4 bytes serialization: the group type, the data type, the bound type and the model
+/// type. The participant doesn't take part in rounds with a different masking
+/// configuration.
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `settings` or `bytes` is `NULL`
+/// - [`ERR_MASKCONFIG_LEN`] if `len` is not 4
+/// - [`ERR_MASKCONFIG_GROUPTYPE`] if the first byte is not a valid group type
+/// - [`ERR_MASKCONFIG_DATATYPE`] if the second byte is not a valid data type
+/// - [`ERR_MASKCONFIG_BOUNDTYPE`] if the third byte is not a valid bound type
+/// - [`ERR_MASKCONFIG_MODELTYPE`] if the fourth byte is not a valid model type
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL
+/// *or* all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+/// - `bytes` must point to `len` consecutive properly initialized bytes.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_mask_config_bytes(
+    settings: *mut Settings,
+    bytes: *const u8,
+    len: usize,
+) -> c_int {
+    if bytes.is_null() {
+        return ERR_NULLPTR;
+    }
+    let bytes = unsafe { slice::from_raw_parts(bytes, len) };
+    let bytes = match <[u8; 4]>::try_from(bytes) {
+        Ok(bytes) => bytes,
+        Err(_) => return ERR_MASKCONFIG_LEN,
+    };
+    let mask_config = match MaskConfig::try_from(bytes) {
+        Ok(mask_config) => mask_config,
+        Err(InvalidMaskConfigError::GroupType) => return ERR_MASKCONFIG_GROUPTYPE,
+        Err(InvalidMaskConfigError::DataType) => return ERR_MASKCONFIG_DATATYPE,
+        Err(InvalidMaskConfigError::BoundType) => return ERR_MASKCONFIG_BOUNDTYPE,
+        Err(InvalidMaskConfigError::ModelType) => return ERR_MASKCONFIG_MODELTYPE,
+    };
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_mask_config(mask_config);
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
+/// Pin the public key the coordinator is expected to publish in the round parameters, from its
+/// 32 bytes. The participant doesn't take part in rounds with another key, see
+/// [`EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY`].
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `settings` or `bytes` is `NULL`
+/// - [`ERR_COORDINATOR_PK_LEN`] if `len` is not 32
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL
+/// *or* all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+/// - `bytes` must point to `len` consecutive properly initialized bytes.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+/// [`EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY`]: crate::ffi::EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_coordinator_pk(
+    settings: *mut Settings,
+    bytes: *const u8,
+    len: usize,
+) -> c_int {
+    if bytes.is_null() {
+        return ERR_NULLPTR;
+    }
+    let bytes = unsafe { slice::from_raw_parts(bytes, len) };
+    let pk = match CoordinatorPublicKey::from_slice(bytes) {
+        Some(pk) => pk,
+        None => return ERR_COORDINATOR_PK_LEN,
+    };
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_coordinator_pk(pk);
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
+/// Set the bounds on the time the participant spends on masking its local model and on
+/// deriving the masks in the sum2 task, in milliseconds. A bound of `0` doesn't bound the
+/// computation.
+///
+/// The participant skips the rounds where masking is estimated to exceed its budget and
+/// aborts the sum2 task once it exceeded its budget, see [`EVENT_KIND_BUDGET_EXCEEDED`].
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `settings` is `NULL`
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointer is NULL *or*
+/// all of the following is true:
+/// - The pointer must be properly [aligned].
+/// - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+/// [`EVENT_KIND_BUDGET_EXCEEDED`]: crate::ffi::EVENT_KIND_BUDGET_EXCEEDED
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_compute_budget(
+    settings: *mut Settings,
+    max_masking_millis: u64,
+    max_sum2_millis: u64,
+) -> c_int {
+    let bound = |millis| if millis == 0 { None } else { Some(millis) };
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_compute_budget(bound(max_masking_millis), bound(max_sum2_millis));
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
 /// Set coordinator URL.
 ///
 /// # Return value
@@ -254,10 +480,13 @@ pub unsafe extern "C" fn xaynet_ffi_settings_set_keys(
 /// # Return value
 ///
 /// - [`OK`] on success
-/// - [`ERR_SETTINGS_URL`] if the URL has not been set
-/// - [`ERR_SETTINGS_KEYS`] if the signing keys have not been set
+/// - [`ERR_SETTINGS_URL`] if the URL has not been set or is invalid
+/// - [`ERR_SETTINGS_KEYS`] if the signing keys have not been set or don't match
 /// - [`ERR_SETTINGS_SCALAR`] if the scalar is out of bounds
 ///
+/// Only the first problem is reported, see [`xaynet_ffi_settings_validate()`] to get all of
+/// them.
+///
 /// # Safety
 ///
 /// When calling this method, you have to ensure that *either* the pointer is NULL *or*
@@ -275,10 +504,73 @@ pub unsafe extern "C" fn xaynet_ffi_check_settings(settings: *const Settings) ->
     match unsafe { settings.as_ref() } {
         Some(settings) => match settings.check() {
             Ok(()) => OK,
-            Err(SettingsError::MissingUrl) => ERR_SETTINGS_URL,
-            Err(SettingsError::MissingKeys) => ERR_SETTINGS_KEYS,
-            Err(SettingsError::OutOfScalarRange(_)) => ERR_SETTINGS_SCALAR,
+            Err(SettingsError::MissingUrl | SettingsError::InvalidUrl(_)) => ERR_SETTINGS_URL,
+            Err(SettingsError::MissingKeys | SettingsError::InvalidKeys) => ERR_SETTINGS_KEYS,
+            Err(SettingsError::OutOfScalarRange(_) | SettingsError::ZeroScalarDenominator) => {
+                ERR_SETTINGS_SCALAR
+            }
         },
         None => ERR_NULLPTR,
     }
 }
+
+/// Settings validation: the coordinator URL is not set
+pub const SETTINGS_URL_MISSING: c_int = 1;
+/// Settings validation: the coordinator URL is not a valid URL
+pub const SETTINGS_URL_INVALID: c_int = 1 << 1;
+/// Settings validation: the signing keys are not set
+pub const SETTINGS_KEYS_MISSING: c_int = 1 << 2;
+/// Settings validation: the public signing key doesn't match the secret signing key
+pub const SETTINGS_KEYS_INVALID: c_int = 1 << 3;
+/// Settings validation: the scalar is out of bounds
+pub const SETTINGS_SCALAR_INVALID: c_int = 1 << 4;
+
+/// Validate the given settings and report all the problems at once, unlike
+/// [`xaynet_ffi_check_settings()`] which only reports the first one.
+///
+/// # Return value
+///
+/// - [`OK`] if the settings are valid
+/// - -[`ERR_NULLPTR`] if `settings` is `NULL`, which is negative to set it apart from the bitmask
+/// - a bitmask of the problems otherwise, where each bit is one of:
+///   - [`SETTINGS_URL_MISSING`] if the URL has not been set
+///   - [`SETTINGS_URL_INVALID`] if the URL is invalid
+///   - [`SETTINGS_KEYS_MISSING`] if the signing keys have not been set
+///   - [`SETTINGS_KEYS_INVALID`] if the public key doesn't match the secret key
+///   - [`SETTINGS_SCALAR_INVALID`] if the scalar is out of bounds
+///
+/// The masking configuration and the model length are not part of the settings, since they
+/// are announced by the coordinator in each round.
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointer is NULL *or*
+/// all of the following is true:
+///
+/// - The pointer must be properly [aligned].
+/// - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+///   documentation.
+///
+/// [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_validate(settings: *const Settings) -> c_int {
+    let settings = match unsafe { settings.as_ref() } {
+        Some(settings) => settings,
+        None => return -ERR_NULLPTR,
+    };
+    match settings.validate() {
+        Ok(()) => OK,
+        Err(errors) => errors.iter().fold(0, |bits, error| {
+            bits | match error {
+                SettingsError::MissingUrl => SETTINGS_URL_MISSING,
+                SettingsError::InvalidUrl(_) => SETTINGS_URL_INVALID,
+                SettingsError::MissingKeys => SETTINGS_KEYS_MISSING,
+                SettingsError::InvalidKeys => SETTINGS_KEYS_INVALID,
+                SettingsError::OutOfScalarRange(_) | SettingsError::ZeroScalarDenominator => {
+                    SETTINGS_SCALAR_INVALID
+                }
+            }
+        }),
+    }
+}
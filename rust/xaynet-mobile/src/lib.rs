@@ -45,7 +45,7 @@ extern crate tracing;
 mod participant;
 mod settings;
 pub use self::{
-    participant::{Event, Events, InitError, Notifier, Participant, Task},
+    participant::{ErrorKind, Event, Events, InitError, Notifier, Participant, Task},
     settings::{Settings, SettingsError},
 };
 pub mod ffi;
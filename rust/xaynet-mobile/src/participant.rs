@@ -1,5 +1,5 @@
 //! Participant implementation
-use std::{convert::TryInto, sync::Arc};
+use std::{collections::VecDeque, convert::TryInto, sync::Arc};
 
 use futures::future::FutureExt;
 use thiserror::Error;
@@ -7,16 +7,22 @@ use tokio::{
     runtime::Runtime,
     sync::{mpsc, Mutex},
 };
-use xaynet_core::mask::Model;
+use xaynet_core::{
+    common::{GlobalModelMetadata, PhaseTimeline},
+    crypto::CryptoSuite,
+    mask::{MaskConfig, Model},
+};
 use xaynet_sdk::{
-    client::Client,
+    budget::{BudgetExceeded, Calibration, ComputeCosts, ComputeTask},
+    client::{CachedGlobalModel, Client},
+    Failure,
     LocalModelConfig,
     ModelStore,
     Notify,
     SerializableState,
+    StateFormatError,
     StateMachine,
     TransitionOutcome,
-    XaynetClient,
 };
 
 use crate::{
@@ -25,35 +31,179 @@ use crate::{
     ClientError,
 };
 
-/// Event emitted by the participant internal state machine as it advances through the
+/// Signal emitted by the participant internal state machine as it advances through the
 /// PET protocol
-pub enum Event {
-    /// Event emitted when the participant is selected for the update task
+enum Signal {
+    /// Signal emitted when the participant is selected for the update task
     Update,
-    /// Event emitted when the participant is selected for the sum task
+    /// Signal emitted when the participant is selected for the sum task
     Sum,
-    /// Event emitted when the participant is done with its task
+    /// Signal emitted when the participant is done with its task
     Idle,
-    /// Event emitted when a new round starts
+    /// Signal emitted when a new round starts
     NewRound,
-    /// Event emitted when the participant should load its model. This only happens if
+    /// Signal emitted when the participant should load its model. This only happens if
     /// the participant has been selected for the update task
     LoadModel,
+    /// Signal emitted when the participant sent its update message
+    UpdateSent,
+    /// Signal emitted when the participant sent its sum2 message
+    Sum2Sent,
+    /// Signal emitted when the participant dropped out of the current round
+    Failed(Failure),
+    /// Signal emitted when the masking configuration of the current round is not supported
+    UnsupportedMaskConfig(MaskConfig),
+    /// Signal emitted when a computation exceeds its compute budget
+    BudgetExceeded(BudgetExceeded),
+    /// Signal emitted when the crypto suite of the current round is not supported
+    UnsupportedCryptoSuite(CryptoSuite),
+}
+
+/// Event emitted by the participant as it advances through the PET protocol. The events
+/// are queued until the caller drains them with [`Participant::next_event()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A new round started.
+    ///
+    /// Participants don't learn the round identifier of the coordinator, so `round_id`
+    /// counts the rounds the participant observed since it was created or restored,
+    /// starting at 1.
+    NewRound { round_id: u64 },
+    /// The participant has been assigned a task for the current round. The
+    /// [`Task::None`] task is only emitted when the participant is done with its
+    /// previous task.
+    TaskAssigned(Task),
+    /// The global model of the previous round is available and can be fetched with
+    /// [`Participant::global_model()`]. `length` is the number of weights of the model.
+    GlobalModelReady { round_id: u64, length: usize },
+    /// The participant sent its update message to the coordinator
+    UpdateSent,
+    /// The participant sent its sum2 message to the coordinator
+    Sum2Sent,
+    /// The previous round is over. `included` tells whether the participant's message
+    /// made it to the coordinator, or is `None` if the participant didn't send any
+    /// message during that round.
+    RoundCompleted { included: Option<bool> },
+    /// The participant dropped out of the current round
+    Error(ErrorKind),
+    /// The participant doesn't take part in the current round, because the `advertised`
+    /// masking configuration of the coordinator is not supported, see
+    /// [`Settings::set_mask_config()`].
+    UnsupportedMaskConfig { advertised: MaskConfig },
+    /// The participant skips its update task or aborts its sum2 task for the current
+    /// round, because the computation `exceeded` its budget, see
+    /// [`Settings::set_compute_budget()`]. No message is sent for the aborted task.
+    BudgetExceeded { exceeded: BudgetExceeded },
+    /// The participant doesn't take part in the current round, because the crypto `suite` of
+    /// the round is not supported by this build of the library.
+    UnsupportedCryptoSuite { suite: CryptoSuite },
+    /// The event queue overflowed and the `count` oldest events have been dropped, see
+    /// [`Participant::next_event()`]. The dropped events were emitted before the events
+    /// which follow this one.
+    EventsDropped { count: u64 },
+}
+
+/// The reason why a participant dropped out of a round
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The coordinator rejected a message of the participant
+    MessageRejected,
+    /// A message of the participant couldn't be sent before the retry deadline passed
+    MessageExpired,
+    /// A message of the participant couldn't be sent before a new round started
+    MessageOutdated,
+    /// The mask seeds of the update participants couldn't be decrypted
+    InvalidSeeds,
+    /// The local model changed while it was masked
+    ModelCorrupted,
+    /// The coordinator banned the participant, which doesn't take part in any further round
+    Banned,
+    /// The generated ephemeral keys repeat the signing keys or the ephemeral keys of the previous
+    /// round, which indicates a broken random number generator
+    InsecureEphemeralKeys,
+    /// The public key of the coordinator doesn't match the pinned key, see
+    /// [`Settings::set_coordinator_pk()`]
+    UntrustedCoordinatorKey,
+    /// The coordinator evicted the participant from the bounded sum dictionary
+    Evicted,
+    /// The parameters of the round don't match the commitment of the coordinator in the previous
+    /// round
+    UncommittedRoundParameters,
+}
+
+impl From<Failure> for ErrorKind {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::MessageRejected => Self::MessageRejected,
+            Failure::MessageExpired => Self::MessageExpired,
+            Failure::MessageOutdated => Self::MessageOutdated,
+            Failure::InvalidSeeds => Self::InvalidSeeds,
+            Failure::ModelCorrupted => Self::ModelCorrupted,
+            Failure::Banned => Self::Banned,
+            Failure::InsecureEphemeralKeys => Self::InsecureEphemeralKeys,
+            Failure::UntrustedCoordinatorKey => Self::UntrustedCoordinatorKey,
+            Failure::Evicted => Self::Evicted,
+            Failure::UncommittedRoundParameters => Self::UncommittedRoundParameters,
+        }
+    }
 }
 
-/// Event sender that is passed to the participant internal state machine for emitting
+/// The maximal number of events the participant queues. Beyond that, the oldest events
+/// are dropped.
+const EVENT_QUEUE_CAPACITY: usize = 64;
+
+/// The events that the caller didn't drain yet.
+///
+/// The queue holds at most [`EVENT_QUEUE_CAPACITY`] events. If it is full, the oldest event
+/// is dropped and the dropped events are reported by an [`Event::EventsDropped`] marker
+/// ahead of the remaining events.
+#[derive(Debug, Default)]
+struct EventQueue {
+    /// The queued events, in the order of emission
+    events: VecDeque<Event>,
+    /// The number of events dropped since the last marker
+    dropped: u64,
+}
+
+impl EventQueue {
+    /// Queue the given event. If the queue is full, the oldest event is dropped.
+    fn push(&mut self, event: Event) {
+        if self.events.len() >= EVENT_QUEUE_CAPACITY {
+            warn!("event queue is full, dropping the oldest event");
+            self.events.pop_front();
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.events.push_back(event);
+    }
+
+    /// Pop the next event, which is a marker if events have been dropped meanwhile.
+    fn pop(&mut self) -> Option<Event> {
+        if self.dropped > 0 {
+            let count = std::mem::take(&mut self.dropped);
+            return Some(Event::EventsDropped { count });
+        }
+        self.events.pop_front()
+    }
+
+    /// Release the memory of the queue beyond its events.
+    fn shrink_to_fit(&mut self) {
+        self.events.shrink_to_fit();
+    }
+}
+
+/// Signal sender that is passed to the participant internal state machine for emitting
 /// notification
-pub struct Notifier(mpsc::Sender<Event>);
+pub struct Notifier(mpsc::Sender<Signal>);
 impl Notifier {
-    fn notify(&mut self, event: Event) {
-        if let Err(e) = self.0.try_send(event) {
+    fn notify(&mut self, signal: Signal) {
+        if let Err(e) = self.0.try_send(signal) {
             warn!("failed to notify participant: {}", e);
         }
     }
 }
 
-/// A receiver for events emitted by the participant internal state machine
-pub struct Events(mpsc::Receiver<Event>);
+/// A receiver for signals emitted by the participant internal state machine
+pub struct Events(mpsc::Receiver<Signal>);
 
 impl Events {
     /// Create a new event sender and receiver.
@@ -62,8 +212,8 @@ impl Events {
         (Self(rx), Notifier(tx))
     }
 
-    /// Pop the next event. If no event has been received, return `None`.
-    fn next(&mut self) -> Option<Event> {
+    /// Pop the next signal. If no signal has been received, return `None`.
+    fn next(&mut self) -> Option<Signal> {
         // Note `try_recv` (tokio 0.2.x) or `recv().now_or_never()` (tokio 1.x)
         // has an implementation bug where previously sent messages may not be
         // available immediately.
@@ -84,25 +234,43 @@ impl Events {
 }
 
 impl Notify for Notifier {
-    fn new_round(&mut self) {
-        self.notify(Event::NewRound)
+    fn new_round(&mut self, _round_token: &str) {
+        self.notify(Signal::NewRound)
     }
     fn sum(&mut self) {
-        self.notify(Event::Sum)
+        self.notify(Signal::Sum)
     }
     fn update(&mut self) {
-        self.notify(Event::Update)
+        self.notify(Signal::Update)
     }
     fn load_model(&mut self) {
-        self.notify(Event::LoadModel)
+        self.notify(Signal::LoadModel)
     }
     fn idle(&mut self) {
-        self.notify(Event::Idle)
+        self.notify(Signal::Idle)
+    }
+    fn update_sent(&mut self) {
+        self.notify(Signal::UpdateSent)
+    }
+    fn sum2_sent(&mut self) {
+        self.notify(Signal::Sum2Sent)
+    }
+    fn failed(&mut self, failure: Failure) {
+        self.notify(Signal::Failed(failure))
+    }
+    fn unsupported_mask_config(&mut self, advertised: MaskConfig, _supported: &[MaskConfig]) {
+        self.notify(Signal::UnsupportedMaskConfig(advertised))
+    }
+    fn budget_exceeded(&mut self, exceeded: BudgetExceeded) {
+        self.notify(Signal::BudgetExceeded(exceeded))
+    }
+    fn unsupported_crypto_suite(&mut self, suite: CryptoSuite) {
+        self.notify(Signal::UnsupportedCryptoSuite(suite))
     }
 }
 
 /// A store shared between by the participant and its internal state machine. When the
-/// state machine emits a [`Event::LoadModel`] event, the participant is expected to
+/// state machine emits a load model signal, the participant is expected to
 /// load its model into the store. See [`Participant::set_model()`].
 #[derive(Clone)]
 struct Store(Arc<Mutex<Option<Model>>>);
@@ -125,7 +293,7 @@ impl ModelStore for Store {
 }
 
 /// Represent the participant current task
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Task {
     /// The participant is taking part in the sum task
     Sum,
@@ -142,8 +310,17 @@ pub enum Task {
 pub struct Participant {
     /// Internal state machine
     state_machine: Option<StateMachine>,
-    /// Receiver for the events emitted by the state machine
+    /// Receiver for the signals emitted by the state machine
     events: Events,
+    /// Events that the caller didn't drain yet
+    queue: EventQueue,
+    /// Whether the participant only updates its state instead of queueing events
+    notify_only: bool,
+    /// The number of rounds the participant observed
+    round_id: u64,
+    /// Whether the message of the participant for the current round made it to the
+    /// coordinator, if it sent any
+    included: Option<bool>,
     /// Model store where the participant should load its model, when
     /// `self.should_set_model` is `true`.
     store: Store,
@@ -158,6 +335,9 @@ pub struct Participant {
     should_set_model: bool,
     /// Whether a new global model is available.
     new_global_model: bool,
+    /// The latest fetched global model, which only the delta to the next global model is
+    /// fetched for.
+    global_model_cache: Option<CachedGlobalModel>,
     /// The participant current task
     task: Task,
 }
@@ -167,7 +347,7 @@ pub struct Participant {
 #[derive(Error, Debug)]
 pub enum InitError {
     #[error("failed to deserialize the participant state {:?}", _0)]
-    Deserialization(#[from] Box<bincode::ErrorKind>),
+    Deserialization(#[from] StateFormatError),
     #[error("failed to initialize the participant runtime {:?}", _0)]
     Runtime(std::io::Error),
     #[error("failed to initialize HTTP client {:?}", _0)]
@@ -183,25 +363,29 @@ pub struct GetGlobalModelError(xaynet_sdk::client::ClientError);
 impl Participant {
     /// Create a new participant with the given settings
     pub fn new(settings: Settings) -> Result<Self, InitError> {
+        let notify_only = settings.notify_only();
         let (url, pet_settings) = settings.try_into()?;
         let client = new_client(url.as_str(), None, None)?;
         let (events, notifier) = Events::new();
         let store = Store::new();
         let state_machine =
             StateMachine::new(pet_settings, client.clone(), store.clone(), notifier);
-        Self::init(state_machine, client, events, store)
+        Self::init(state_machine, client, events, store, notify_only)
     }
 
     /// Restore a participant from it's serialized state. The coordinator client that
     /// the participant uses internally is not part of the participant state, so the
     /// `url` is used to instantiate a new one.
+    ///
+    /// The event queue is not part of the participant state either: a restored
+    /// participant queues events and counts the rounds from scratch.
     pub fn restore(state: &[u8], url: &str) -> Result<Self, InitError> {
-        let state: SerializableState = bincode::deserialize(state)?;
+        let state = SerializableState::from_bytes(state)?;
         let (events, notifier) = Events::new();
         let store = Store::new();
         let client = new_client(url, None, None)?;
         let state_machine = StateMachine::restore(state, client.clone(), store.clone(), notifier);
-        Self::init(state_machine, client, events, store)
+        Self::init(state_machine, client, events, store, false)
     }
 
     fn init(
@@ -209,17 +393,23 @@ impl Participant {
         client: Client<reqwest::Client>,
         events: Events,
         store: Store,
+        notify_only: bool,
     ) -> Result<Self, InitError> {
         let mut participant = Self {
             runtime: Self::runtime()?,
             state_machine: Some(state_machine),
             events,
+            queue: EventQueue::default(),
+            notify_only,
+            round_id: 0,
+            included: None,
             store,
             client,
             task: Task::None,
             made_progress: true,
             should_set_model: false,
             new_global_model: false,
+            global_model_cache: None,
         };
         participant.process_events();
         Ok(participant)
@@ -233,10 +423,23 @@ impl Participant {
     }
 
     /// Serialize the participant state and return the corresponding buffer.
+    ///
+    /// The state is serialized in a versioned format, see [`SerializableState::to_bytes()`].
     pub fn save(self) -> Vec<u8> {
         // UNWRAP_SAFE: the state machine is always set.
-        let state_machine = self.state_machine.unwrap().save();
-        bincode::serialize(&state_machine).unwrap()
+        self.state_machine.unwrap().save().to_bytes()
+    }
+
+    /// Release the memory the participant holds beyond its state, e.g. before saving a
+    /// long-running participant.
+    ///
+    /// The state of the participant only holds the data of the current round and is already
+    /// cleaned up whenever a new round starts. However, the participant caches the latest global
+    /// model, which is dropped here at the cost of fetching the next global model in full instead
+    /// of only its delta. Events that the caller didn't drain yet are kept.
+    pub fn compact(&mut self) {
+        self.global_model_cache = None;
+        self.queue.shrink_to_fit();
     }
 
     /// Drive the participant internal state machine.
@@ -249,6 +452,9 @@ impl Participant {
     ///   [`Participant::task()`]
     /// - whether the participant should load its model into the store by calling
     ///   [`Participant::should_set_model()`]
+    ///
+    /// Alternatively, the caller can drain the events emitted during the tick by calling
+    /// [`Participant::next_event()`].
     pub fn tick(&mut self) {
         // UNWRAP_SAFE: the state machine is always set.
         let state_machine = self.state_machine.take().unwrap();
@@ -271,27 +477,89 @@ impl Participant {
     fn process_events(&mut self) {
         loop {
             match self.events.next() {
-                Some(Event::Idle) => {
+                Some(Signal::Idle) => {
+                    if self.task != Task::None {
+                        self.push_event(Event::TaskAssigned(Task::None));
+                    }
                     self.task = Task::None;
                 }
-                Some(Event::Update) => {
+                Some(Signal::Update) => {
                     self.task = Task::Update;
+                    self.push_event(Event::TaskAssigned(Task::Update));
                 }
-                Some(Event::Sum) => {
+                Some(Signal::Sum) => {
                     self.task = Task::Sum;
+                    self.push_event(Event::TaskAssigned(Task::Sum));
                 }
-                Some(Event::NewRound) => {
+                Some(Signal::NewRound) => {
                     self.should_set_model = false;
                     self.new_global_model = true;
+                    if self.round_id > 0 {
+                        let included = self.included.take();
+                        self.push_event(Event::RoundCompleted { included });
+                    }
+                    self.round_id += 1;
+                    let round_id = self.round_id;
+                    let length = self.local_model_config().len;
+                    self.push_event(Event::NewRound { round_id });
+                    self.push_event(Event::GlobalModelReady { round_id, length });
                 }
-                Some(Event::LoadModel) => {
+                Some(Signal::LoadModel) => {
                     self.should_set_model = true;
                 }
+                Some(Signal::UpdateSent) => {
+                    self.included = Some(true);
+                    self.push_event(Event::UpdateSent);
+                }
+                Some(Signal::Sum2Sent) => {
+                    self.included = Some(true);
+                    self.push_event(Event::Sum2Sent);
+                }
+                Some(Signal::Failed(failure)) => {
+                    self.included = Some(false);
+                    self.push_event(Event::Error(failure.into()));
+                }
+                Some(Signal::UnsupportedMaskConfig(advertised)) => {
+                    self.push_event(Event::UnsupportedMaskConfig { advertised });
+                }
+                Some(Signal::BudgetExceeded(exceeded)) => {
+                    // the sum message of an aborted sum2 task doesn't make it into the
+                    // global model
+                    if exceeded.task == ComputeTask::Sum2 {
+                        self.included = Some(false);
+                    }
+                    self.push_event(Event::BudgetExceeded { exceeded });
+                }
+                Some(Signal::UnsupportedCryptoSuite(suite)) => {
+                    self.push_event(Event::UnsupportedCryptoSuite { suite });
+                }
                 None => break,
             }
         }
     }
 
+    /// Queue the given event, unless the participant runs in notify-only mode. If the
+    /// queue is full, the oldest event is dropped.
+    fn push_event(&mut self, event: Event) {
+        if !self.notify_only {
+            self.queue.push(event);
+        }
+    }
+
+    /// Pop the next event emitted by the participant, in the order of emission. If no
+    /// event is pending, or if the participant has been configured in notify-only mode
+    /// (see [`Settings::set_notify_only()`]), return `None`.
+    ///
+    /// The events are emitted while [`Participant::tick()`] drives the state machine,
+    /// so the caller should drain them after each tick.
+    ///
+    /// At most 64 events are queued. If the caller doesn't keep up, the oldest events are
+    /// dropped and an [`Event::EventsDropped`] event with the number of dropped events is
+    /// returned before the remaining events.
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.queue.pop()
+    }
+
     /// Check whether the participant internal state machine made progress while
     /// executing the PET protocol. If so, the participant state likely changed.
     pub fn made_progress(&self) -> bool {
@@ -333,21 +601,57 @@ impl Participant {
     }
 
     /// Retrieve the current global model, if available.
+    ///
+    /// The participant keeps the latest retrieved global model, hence only the delta to the next
+    /// global model is downloaded if the global model is retrieved in every round.
     pub fn global_model(&mut self) -> Result<Option<Model>, GetGlobalModelError> {
         let Self {
             ref mut runtime,
             ref mut client,
+            ref mut global_model_cache,
             ..
         } = self;
 
-        let global_model =
-            runtime.block_on(async { client.get_model().await.map_err(GetGlobalModelError) });
+        let global_model = runtime.block_on(async {
+            client
+                .cached_global_model(global_model_cache)
+                .await
+                .map_err(GetGlobalModelError)
+        });
         if global_model.is_ok() {
             self.new_global_model = false;
         }
         global_model
     }
 
+    /// Retrieve the metadata of the current global model, if available.
+    ///
+    /// The metadata declares the original data type of the weights of the global model.
+    pub fn global_model_metadata(
+        &mut self,
+    ) -> Result<Option<GlobalModelMetadata>, GetGlobalModelError> {
+        let Self {
+            ref mut runtime,
+            ref mut client,
+            ..
+        } = self;
+
+        runtime.block_on(async {
+            client
+                .global_model_metadata()
+                .await
+                .map_err(GetGlobalModelError)
+        })
+    }
+
+    /// Return the masking configuration advertised by the coordinator for the current
+    /// round.
+    pub fn mask_config(&self) -> MaskConfig {
+        // UNWRAP_SAFE: the state machine is always set.
+        let state_machine = self.state_machine.as_ref().unwrap();
+        state_machine.mask_config()
+    }
+
     /// Return the local model configuration of the model that is expected in the
     /// [`Participant::set_model`] method.
     pub fn local_model_config(&self) -> LocalModelConfig {
@@ -355,4 +659,96 @@ impl Participant {
         let state_machine = self.state_machine.as_ref().unwrap();
         state_machine.local_model_config()
     }
+
+    /// Return the timeline of the phases of the current round, if the coordinator publishes
+    /// it.
+    ///
+    /// Before training a local model for [`Participant::set_model`], the time left until the
+    /// update phase ends can be checked with [`PhaseTimeline::update_time_left()`].
+    pub fn phase_timeline(&self) -> Option<PhaseTimeline> {
+        // UNWRAP_SAFE: the state machine is always set.
+        let state_machine = self.state_machine.as_ref().unwrap();
+        state_machine.phase_timeline()
+    }
+
+    /// Return the calibrated cost of masking, if the participant ran the calibration
+    /// already. The calibration runs once the masking is bounded by the compute budget, see
+    /// [`Settings::set_compute_budget()`].
+    pub fn calibration(&self) -> Option<Calibration> {
+        // UNWRAP_SAFE: the state machine is always set.
+        let state_machine = self.state_machine.as_ref().unwrap();
+        state_machine.calibration()
+    }
+
+    /// Return the actual costs of masking and of the sum2 task, in the latest rounds the
+    /// participant performed them.
+    pub fn last_costs(&self) -> ComputeCosts {
+        // UNWRAP_SAFE: the state machine is always set.
+        let state_machine = self.state_machine.as_ref().unwrap();
+        state_machine.last_costs()
+    }
+
+    /// Compute the scalar which weights the local model by its number of training samples,
+    /// as in FedAvg, to be set via [`Settings::set_scalar()`].
+    ///
+    /// The scalar is the fraction `local_samples / total_samples_hint` of the training samples
+    /// of all participants of a round. The scalars of the participants sum up to `1` if the hint
+    /// is the true total, but the coordinator divides the aggregated model by the sum of the
+    /// scalars anyways, hence a rough hint suffices. A hint smaller than the local samples is
+    /// raised to them, such that the scalar is always within `[0, 1]`.
+    ///
+    /// The scalar is rounded to a float. Pass `local_samples` and `total_samples_hint` to
+    /// [`Settings::set_scalar_ratio()`] for the exact scalar instead.
+    pub fn scalar_for_samples(local_samples: u64, total_samples_hint: u64) -> f64 {
+        let total_samples = total_samples_hint.max(local_samples);
+        if total_samples == 0 {
+            return 0.;
+        }
+        local_samples as f64 / total_samples as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scalar_for_samples() {
+        let samples = [120_u64, 7, 3_000, 873, 1];
+        let total = samples.iter().sum::<u64>();
+        let scalars = samples
+            .iter()
+            .map(|&local| Participant::scalar_for_samples(local, total))
+            .collect::<Vec<_>>();
+        assert!(scalars.iter().all(|scalar| (0. ..=1.).contains(scalar)));
+        assert!((scalars.iter().sum::<f64>() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_event_queue_reports_dropped_events() {
+        let mut queue = EventQueue::default();
+        let events = (1..=EVENT_QUEUE_CAPACITY as u64 + 2)
+            .map(|round_id| Event::NewRound { round_id })
+            .collect::<Vec<_>>();
+        for &event in events.iter() {
+            queue.push(event);
+        }
+
+        assert_eq!(queue.pop(), Some(Event::EventsDropped { count: 2 }));
+        for &event in events[2..].iter() {
+            assert_eq!(queue.pop(), Some(event));
+        }
+        assert_eq!(queue.pop(), None);
+
+        queue.push(Event::UpdateSent);
+        assert_eq!(queue.pop(), Some(Event::UpdateSent));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_scalar_for_samples_bad_hint() {
+        assert_eq!(Participant::scalar_for_samples(10, 5), 1.);
+        assert_eq!(Participant::scalar_for_samples(0, 5), 0.);
+        assert_eq!(Participant::scalar_for_samples(0, 0), 0.);
+    }
 }
@@ -4,11 +4,13 @@
 
 use std::convert::TryInto;
 use thiserror::Error;
+use tracing::warn;
 use xaynet_core::{
     crypto::SigningKeyPair,
-    mask::{FromPrimitive, PrimitiveCastError, Scalar},
+    mask::{FromPrimitive, MaskConfig, PrimitiveCastError, Scalar},
+    CoordinatorPublicKey,
 };
-use xaynet_sdk::settings::{MaxMessageSize, PetSettings};
+use xaynet_sdk::settings::{ComputeBudget, MaxMessageSize, PetSettings, RetrySettings};
 
 /// A participant settings
 #[derive(Clone, Debug)]
@@ -18,9 +20,19 @@ pub struct Settings {
     /// The participant signing keys.
     keys: Option<SigningKeyPair>,
     /// The scalar used for masking.
-    scalar: Result<Scalar, PrimitiveCastError<f64>>,
+    scalar: Result<Scalar, SettingsError>,
     /// The maximum possible size of a message.
     max_message_size: MaxMessageSize,
+    /// Whether the ephemeral keys are derived instead of generated.
+    deterministic_ephm_keys: bool,
+    /// Whether the participant only updates its state instead of queueing events.
+    notify_only: bool,
+    /// The masking configuration expected from the coordinator, if any.
+    mask_config: Option<MaskConfig>,
+    /// The bounds on the computations of the participant.
+    compute_budget: ComputeBudget,
+    /// The public key the coordinator is expected to publish, if pinned.
+    coordinator_pk: Option<CoordinatorPublicKey>,
 }
 
 impl Default for Settings {
@@ -37,6 +49,11 @@ impl Settings {
             keys: None,
             scalar: Ok(Scalar::unit()),
             max_message_size: MaxMessageSize::default(),
+            deterministic_ephm_keys: false,
+            notify_only: false,
+            mask_config: None,
+            compute_budget: ComputeBudget::default(),
+            coordinator_pk: None,
         }
     }
 
@@ -46,8 +63,17 @@ impl Settings {
     }
 
     /// Set the scalar to use for masking
+    ///
+    /// The conversion from a float is lossy, e.g. `1.0 / 3.0` isn't exactly a third. Use
+    /// [`Settings::set_scalar_ratio()`] to set an exact scalar.
     pub fn set_scalar(&mut self, scalar: f64) {
-        self.scalar = Scalar::from_primitive(scalar)
+        self.scalar = Scalar::from_primitive(scalar).map_err(SettingsError::OutOfScalarRange);
+    }
+
+    /// Sets the scalar to use for masking to the exact fraction `numer / denom`.
+    pub fn set_scalar_ratio(&mut self, numer: u64, denom: u64) {
+        self.scalar =
+            Scalar::checked_new(numer, denom).ok_or(SettingsError::ZeroScalarDenominator);
     }
 
     /// Set the Xaynet coordinator address
@@ -60,52 +86,248 @@ impl Settings {
         self.max_message_size = size;
     }
 
-    /// Check whether the settings are complete and valid
+    /// Sets whether the ephemeral keys of a sum participant are derived from the signing keys and
+    /// the round seed instead of being generated randomly.
+    ///
+    /// Derived ephemeral keys survive a restart without being persisted, but they are as secret as
+    /// the signing keys only, see [`PetSettings::deterministic_ephm_keys`].
+    pub fn set_deterministic_ephm_keys(&mut self, deterministic: bool) {
+        self.deterministic_ephm_keys = deterministic;
+    }
+
+    /// Sets whether the participant runs in notify-only mode.
+    ///
+    /// In notify-only mode, the participant doesn't queue any [`Event`] and the caller has
+    /// to poll the participant state after each tick instead, as with earlier versions of
+    /// this crate.
+    ///
+    /// [`Event`]: crate::Event
+    pub fn set_notify_only(&mut self, notify_only: bool) {
+        self.notify_only = notify_only;
+    }
+
+    /// Sets the masking configuration the participant expects from the coordinator.
+    ///
+    /// The participant doesn't take part in rounds with a different masking configuration,
+    /// see [`PetSettings::expected_mask_config`].
+    pub fn set_mask_config(&mut self, mask_config: MaskConfig) {
+        self.mask_config = Some(mask_config);
+    }
+
+    /// Sets the bounds on the time the participant spends on masking its local model and on
+    /// deriving the masks in the sum2 task, in milliseconds. `None` doesn't bound the
+    /// computation.
+    ///
+    /// The participant skips the rounds where masking is estimated to exceed its budget and
+    /// aborts the sum2 task once it exceeded its budget, see [`Event::BudgetExceeded`].
+    ///
+    /// [`Event::BudgetExceeded`]: crate::Event::BudgetExceeded
+    pub fn set_compute_budget(
+        &mut self,
+        max_masking_millis: Option<u64>,
+        max_sum2_millis: Option<u64>,
+    ) {
+        self.compute_budget = ComputeBudget {
+            max_masking_millis,
+            max_sum2_millis,
+        };
+    }
+
+    /// Pins the public key the coordinator is expected to publish in the round parameters.
+    ///
+    /// The participant doesn't take part in rounds with another key, see
+    /// [`PetSettings::pinned_coordinator_pk`] and [`ErrorKind::UntrustedCoordinatorKey`].
+    ///
+    /// [`ErrorKind::UntrustedCoordinatorKey`]: crate::ErrorKind::UntrustedCoordinatorKey
+    pub fn set_coordinator_pk(&mut self, pk: CoordinatorPublicKey) {
+        self.coordinator_pk = Some(pk);
+    }
+
+    /// Whether the participant runs in notify-only mode.
+    pub(crate) fn notify_only(&self) -> bool {
+        self.notify_only
+    }
+
+    /// Check whether the settings are complete and valid.
+    ///
+    /// Only the first problem is reported, see [`Settings::validate()`] to get all of them.
     pub fn check(&self) -> Result<(), SettingsError> {
-        if self.url.is_none() {
-            Err(SettingsError::MissingUrl)
-        } else if self.keys.is_none() {
-            Err(SettingsError::MissingKeys)
-        } else if let Err(e) = &self.scalar {
-            Err(e.clone().into())
-        } else {
+        self.validate().map_err(|mut errors| errors.swap_remove(0))
+    }
+
+    /// Validate the settings and report all the problems at once.
+    ///
+    /// Settings which are valid but likely unintended, like a zero scalar which cancels the
+    /// local model out of the aggregation, are logged as warnings.
+    pub fn validate(&self) -> Result<(), Vec<SettingsError>> {
+        let mut errors = Vec::new();
+
+        match &self.url {
+            None => errors.push(SettingsError::MissingUrl),
+            Some(url) => {
+                if let Err(e) = reqwest::Url::parse(url) {
+                    errors.push(SettingsError::InvalidUrl(e.to_string()));
+                }
+            }
+        }
+
+        match &self.keys {
+            None => errors.push(SettingsError::MissingKeys),
+            Some(keys) => {
+                if keys.secret.public_key() != keys.public {
+                    errors.push(SettingsError::InvalidKeys);
+                }
+            }
+        }
+
+        match &self.scalar {
+            Err(e) => errors.push(e.clone()),
+            Ok(scalar) if *scalar == Scalar::from_integer(0_u8) => {
+                warn!("the scalar is zero, hence the local model doesn't affect the global model");
+            }
+            Ok(_) => {}
+        }
+
+        if errors.is_empty() {
             Ok(())
+        } else {
+            Err(errors)
         }
     }
 }
 
 /// Error returned when the settings are invalid
-#[derive(Debug, Error)]
+#[derive(Clone, Debug, Error)]
 pub enum SettingsError {
     #[error("the Xaynet coordinator URL must be specified")]
     MissingUrl,
+    #[error("the Xaynet coordinator URL is invalid: {0}")]
+    InvalidUrl(String),
     #[error("the participant signing key pair must be specified")]
     MissingKeys,
+    #[error("the participant signing public key doesn't match the secret key")]
+    InvalidKeys,
     #[error("float not within range of scalar: {0}")]
     OutOfScalarRange(#[from] PrimitiveCastError<f64>),
+    #[error("the denominator of the scalar must not be zero")]
+    ZeroScalarDenominator,
 }
 
 impl TryInto<(String, PetSettings)> for Settings {
     type Error = SettingsError;
 
     fn try_into(self) -> Result<(String, PetSettings), Self::Error> {
+        self.check()?;
         let Settings {
             keys,
             url,
             scalar,
             max_message_size,
+            deterministic_ephm_keys,
+            mask_config,
+            compute_budget,
+            coordinator_pk,
+            ..
         } = self;
 
         let url = url.ok_or(SettingsError::MissingUrl)?;
         let keys = keys.ok_or(SettingsError::MissingKeys)?;
-        let scalar = scalar.map_err(SettingsError::OutOfScalarRange)?;
+        let scalar = scalar?;
 
         let pet_settings = PetSettings {
             keys,
             scalar,
             max_message_size,
+            retry: RetrySettings::default(),
+            deterministic_ephm_keys,
+            expected_mask_config: mask_config,
+            supported_mask_configs: None,
+            compute_budget,
+            pinned_coordinator_pk: coordinator_pk,
         };
 
         Ok((url, pet_settings))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xaynet_core::crypto::ByteObject;
+
+    fn valid_settings() -> Settings {
+        let mut settings = Settings::new();
+        settings.set_url("http://localhost:8081".to_string());
+        settings.set_keys(SigningKeyPair::generate());
+        settings
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(valid_settings().validate().is_ok());
+
+        let errors = Settings::new().validate().unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [SettingsError::MissingUrl, SettingsError::MissingKeys]
+        ));
+    }
+
+    #[test]
+    fn test_validate_mismatching_keys() {
+        let mut settings = valid_settings();
+        settings.set_url("localhost".to_string());
+        settings.set_keys(SigningKeyPair {
+            public: SigningKeyPair::generate().public,
+            secret: SigningKeyPair::generate().secret,
+        });
+        settings.set_scalar(f64::NAN);
+
+        let errors = settings.validate().unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                SettingsError::InvalidUrl(_),
+                SettingsError::InvalidKeys,
+                SettingsError::OutOfScalarRange(_),
+            ]
+        ));
+        assert!(matches!(settings.check(), Err(SettingsError::InvalidUrl(_))));
+    }
+
+    #[test]
+    fn test_scalar_ratio() {
+        let mut settings = valid_settings();
+        settings.set_scalar_ratio(2, 6);
+        let (_, pet_settings): (String, PetSettings) = settings.try_into().unwrap();
+        assert_eq!(pet_settings.scalar, Scalar::new(1_u8, 3_u8));
+
+        let mut settings = valid_settings();
+        settings.set_scalar_ratio(1, 0);
+        assert!(matches!(
+            settings.check(),
+            Err(SettingsError::ZeroScalarDenominator)
+        ));
+    }
+
+    #[test]
+    fn test_compute_budget() {
+        let mut settings = valid_settings();
+        settings.set_compute_budget(Some(500), None);
+        let (_, pet_settings): (String, PetSettings) = settings.try_into().unwrap();
+        assert_eq!(pet_settings.compute_budget.max_masking_millis, Some(500));
+        assert_eq!(pet_settings.compute_budget.max_sum2_millis, None);
+    }
+
+    #[test]
+    fn test_coordinator_pk() {
+        let (_, pet_settings): (String, PetSettings) = valid_settings().try_into().unwrap();
+        assert!(pet_settings.pinned_coordinator_pk.is_none());
+
+        let mut settings = valid_settings();
+        let pk = CoordinatorPublicKey::from_slice_unchecked(&[1; 32]);
+        settings.set_coordinator_pk(pk);
+        let (_, pet_settings): (String, PetSettings) = settings.try_into().unwrap();
+        assert_eq!(pet_settings.pinned_coordinator_pk, Some(pk));
+    }
+}
@@ -46,6 +46,59 @@ static char *test_settings_set_url() {
   return 0;
 }
 
+static char *test_settings_set_mask_config_bytes() {
+  Settings *settings = xaynet_ffi_settings_new();
+
+  // integer group, f32 data, b0 bounds, m3 models
+  const uint8_t valid[4] = {0, 0, 0, 3};
+  int err = xaynet_ffi_settings_set_mask_config_bytes(settings, valid, 4);
+  mu_assert("failed to set mask config", !err);
+
+  err = xaynet_ffi_settings_set_mask_config_bytes(NULL, valid, 4);
+  mu_assert("NULL settings should fail", err == ERR_NULLPTR);
+  err = xaynet_ffi_settings_set_mask_config_bytes(settings, NULL, 4);
+  mu_assert("NULL bytes should fail", err == ERR_NULLPTR);
+  err = xaynet_ffi_settings_set_mask_config_bytes(settings, valid, 3);
+  mu_assert("short mask config should fail", err == ERR_MASKCONFIG_LEN);
+
+  const uint8_t invalid_group_type[4] = {3, 0, 0, 3};
+  err = xaynet_ffi_settings_set_mask_config_bytes(settings, invalid_group_type, 4);
+  mu_assert("invalid group type should fail", err == ERR_MASKCONFIG_GROUPTYPE);
+
+  const uint8_t invalid_data_type[4] = {0, 4, 0, 3};
+  err = xaynet_ffi_settings_set_mask_config_bytes(settings, invalid_data_type, 4);
+  mu_assert("invalid data type should fail", err == ERR_MASKCONFIG_DATATYPE);
+
+  const uint8_t invalid_bound_type[4] = {0, 0, 1, 3};
+  err = xaynet_ffi_settings_set_mask_config_bytes(settings, invalid_bound_type, 4);
+  mu_assert("invalid bound type should fail", err == ERR_MASKCONFIG_BOUNDTYPE);
+
+  const uint8_t invalid_model_type[4] = {0, 0, 0, 4};
+  err = xaynet_ffi_settings_set_mask_config_bytes(settings, invalid_model_type, 4);
+  mu_assert("invalid model type should fail", err == ERR_MASKCONFIG_MODELTYPE);
+
+  xaynet_ffi_settings_destroy(settings);
+  return 0;
+}
+
+static char *test_settings_set_coordinator_pk() {
+  Settings *settings = xaynet_ffi_settings_new();
+
+  const uint8_t pk[32] = {0};
+  int err = xaynet_ffi_settings_set_coordinator_pk(settings, pk, 32);
+  mu_assert("failed to set coordinator pk", !err);
+
+  err = xaynet_ffi_settings_set_coordinator_pk(NULL, pk, 32);
+  mu_assert("NULL settings should fail", err == ERR_NULLPTR);
+  err = xaynet_ffi_settings_set_coordinator_pk(settings, NULL, 32);
+  mu_assert("NULL bytes should fail", err == ERR_NULLPTR);
+  err = xaynet_ffi_settings_set_coordinator_pk(settings, pk, 31);
+  mu_assert("short coordinator pk should fail", err == ERR_COORDINATOR_PK_LEN);
+
+  xaynet_ffi_settings_destroy(settings);
+  return 0;
+}
+
 void with_keys(Settings *settings) {
   const KeyPair *keys = xaynet_ffi_generate_key_pair();
   int err = xaynet_ffi_settings_set_keys(settings, keys);
@@ -74,6 +127,69 @@ static char *test_settings() {
   return 0;
 }
 
+static char *test_settings_validate() {
+  int err = xaynet_ffi_settings_validate(NULL);
+  mu_assert("expected settings is null error", err == -ERR_NULLPTR);
+
+  Settings *settings = xaynet_ffi_settings_new();
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected missing url and keys bits",
+            err == (SETTINGS_URL_MISSING | SETTINGS_KEYS_MISSING));
+
+  xaynet_ffi_settings_set_scalar(settings, -1.0);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected missing url and keys and invalid scalar bits",
+            err == (SETTINGS_URL_MISSING | SETTINGS_KEYS_MISSING | SETTINGS_SCALAR_INVALID));
+
+  with_keys(settings);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected missing url and invalid scalar bits",
+            err == (SETTINGS_URL_MISSING | SETTINGS_SCALAR_INVALID));
+
+  xaynet_ffi_settings_set_url(settings, "not a url");
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected invalid url and scalar bits",
+            err == (SETTINGS_URL_INVALID | SETTINGS_SCALAR_INVALID));
+  err = xaynet_ffi_check_settings(settings);
+  mu_assert("expected the first error only", err == ERR_SETTINGS_URL);
+
+  xaynet_ffi_settings_set_scalar(settings, 0.5);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected invalid url bit", err == SETTINGS_URL_INVALID);
+
+  with_url(settings);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected valid settings", err == OK);
+  xaynet_ffi_settings_destroy(settings);
+
+  settings = xaynet_ffi_settings_new();
+  with_keys(settings);
+  with_url(settings);
+  xaynet_ffi_settings_set_scalar(settings, -1.0);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected invalid scalar bit", err == SETTINGS_SCALAR_INVALID);
+  xaynet_ffi_settings_destroy(settings);
+
+  settings = xaynet_ffi_settings_new();
+  with_keys(settings);
+  with_url(settings);
+  xaynet_ffi_settings_set_scalar_ratio(settings, 1, 0);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected invalid scalar bit", err == SETTINGS_SCALAR_INVALID);
+  xaynet_ffi_settings_set_scalar_ratio(settings, 1, 3);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected valid settings", err == OK);
+  xaynet_ffi_settings_destroy(settings);
+
+  settings = xaynet_ffi_settings_new();
+  with_url(settings);
+  err = xaynet_ffi_settings_validate(settings);
+  mu_assert("expected missing keys bit", err == SETTINGS_KEYS_MISSING);
+  xaynet_ffi_settings_destroy(settings);
+
+  return 0;
+}
+
 static char *test_global_model() {
   Settings *settings = xaynet_ffi_settings_new();
   with_keys(settings);
@@ -111,7 +227,9 @@ static char *test_participant_save_and_restore() {
   mu_assert("failed to create participant", participant != NULL);
   xaynet_ffi_settings_destroy(settings);
 
-  // save the participant
+  // compact and save the participant
+  int err = xaynet_ffi_participant_compact(participant);
+  mu_assert("failed to compact participant", err == OK);
   const ByteBuffer *save_buf = xaynet_ffi_participant_save(participant);
   mu_assert("failed to save participant", save_buf != NULL);
 
@@ -120,7 +238,7 @@ static char *test_participant_save_and_restore() {
   FILE *f = fopen(path, "w");
   fwrite(save_buf->data, 1, save_buf->len, f);
   fclose(f);
-  int err = xaynet_ffi_byte_buffer_destroy(save_buf);
+  err = xaynet_ffi_byte_buffer_destroy(save_buf);
   assert(!err);
 
   // read the serialized participant from the file
@@ -171,14 +289,79 @@ static char *test_participant_tick() {
   return 0;
 }
 
+static char *test_participant_next_event() {
+  Settings *settings = xaynet_ffi_settings_new();
+  with_keys(settings);
+  with_url(settings);
+
+  Participant *participant = xaynet_ffi_participant_new(settings);
+  mu_assert("failed to create participant", participant != NULL);
+
+  FfiEvent event;
+  int err = xaynet_ffi_participant_next_event(NULL, &event);
+  mu_assert("expected participant is null error", err == ERR_NULLPTR);
+  err = xaynet_ffi_participant_next_event(participant, NULL);
+  mu_assert("expected event is null error", err == ERR_NULLPTR);
+
+  // the coordinator is not reachable, hence no round starts
+  xaynet_ffi_participant_tick(participant);
+  err = xaynet_ffi_participant_next_event(participant, &event);
+  mu_assert("unexpected event", err == EVENT_NONE);
+
+  err = xaynet_ffi_settings_set_notify_only(NULL, true);
+  mu_assert("expected settings is null error", err == ERR_NULLPTR);
+  err = xaynet_ffi_settings_set_notify_only(settings, true);
+  mu_assert("failed to set notify only mode", err == OK);
+
+  // free memory
+  xaynet_ffi_settings_destroy(settings);
+  xaynet_ffi_participant_destroy(participant);
+
+  return 0;
+}
+
+static char *test_participant_mask_config() {
+  Settings *settings = xaynet_ffi_settings_new();
+  with_keys(settings);
+  with_url(settings);
+
+  Participant *participant = xaynet_ffi_participant_new(settings);
+  mu_assert("failed to create participant", participant != NULL);
+
+  uint8_t mask_config[4];
+  int err = xaynet_ffi_participant_mask_config(NULL, mask_config, 4);
+  mu_assert("expected participant is null error", err == ERR_NULLPTR);
+  err = xaynet_ffi_participant_mask_config(participant, NULL, 4);
+  mu_assert("expected buffer is null error", err == ERR_NULLPTR);
+  err = xaynet_ffi_participant_mask_config(participant, mask_config, 3);
+  mu_assert("expected mask config length error", err == ERR_MASKCONFIG_LEN);
+
+  // no round started yet, hence the placeholder configuration is advertised
+  err = xaynet_ffi_participant_mask_config(participant, mask_config, 4);
+  mu_assert("failed to get mask config", err == OK);
+  mu_assert("unexpected mask config",
+            mask_config[0] == 0 && mask_config[1] == 0 && mask_config[2] == 0 && mask_config[3] == 3);
+
+  // free memory
+  xaynet_ffi_settings_destroy(settings);
+  xaynet_ffi_participant_destroy(participant);
+
+  return 0;
+}
+
 static char *all_tests() {
   mu_run_test(test_settings_new);
   mu_run_test(test_settings_set_keys);
   mu_run_test(test_settings_set_url);
+  mu_run_test(test_settings_set_mask_config_bytes);
+  mu_run_test(test_settings_set_coordinator_pk);
   mu_run_test(test_settings);
+  mu_run_test(test_settings_validate);
   mu_run_test(test_global_model);
   mu_run_test(test_participant_save_and_restore);
   mu_run_test(test_participant_tick);
+  mu_run_test(test_participant_next_event);
+  mu_run_test(test_participant_mask_config);
   return 0;
 }
 
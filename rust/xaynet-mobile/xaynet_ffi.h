@@ -87,6 +87,46 @@
  */
 #define ERR_GLOBALMODEL_CONVERT 15
 
+/**
+ * No event is currently pending
+ */
+#define EVENT_NONE 16
+
+/**
+ * Invalid masking configuration: the buffer is not 4 bytes long
+ */
+#define ERR_MASKCONFIG_LEN 17
+
+/**
+ * Invalid masking configuration: invalid group type
+ */
+#define ERR_MASKCONFIG_GROUPTYPE 18
+
+/**
+ * Invalid masking configuration: invalid data type
+ */
+#define ERR_MASKCONFIG_DATATYPE 19
+
+/**
+ * Invalid masking configuration: invalid bound type
+ */
+#define ERR_MASKCONFIG_BOUNDTYPE 20
+
+/**
+ * Invalid masking configuration: invalid model type
+ */
+#define ERR_MASKCONFIG_MODELTYPE 21
+
+/**
+ * The cost of masking has not been calibrated yet
+ */
+#define CALIBRATION_NONE 22
+
+/**
+ * Invalid coordinator public key: the buffer is not 32 bytes long
+ */
+#define ERR_COORDINATOR_PK_LEN 23
+
 /**
  * The participant is not taking part in the sum or update task
  */
@@ -117,6 +157,150 @@
  */
 #define PARTICIPANT_NEW_GLOBALMODEL (1 << 5)
 
+/**
+ * Settings validation: the coordinator URL is not set
+ */
+#define SETTINGS_URL_MISSING 1
+
+/**
+ * Settings validation: the coordinator URL is not a valid URL
+ */
+#define SETTINGS_URL_INVALID (1 << 1)
+
+/**
+ * Settings validation: the signing keys are not set
+ */
+#define SETTINGS_KEYS_MISSING (1 << 2)
+
+/**
+ * Settings validation: the public signing key doesn't match the secret signing key
+ */
+#define SETTINGS_KEYS_INVALID (1 << 3)
+
+/**
+ * Settings validation: the scalar is out of bounds
+ */
+#define SETTINGS_SCALAR_INVALID (1 << 4)
+
+/**
+ * A new round started
+ */
+#define EVENT_KIND_NEW_ROUND 1
+
+/**
+ * The participant has been assigned a task for the current round
+ */
+#define EVENT_KIND_TASK_ASSIGNED 2
+
+/**
+ * The global model of the previous round is available
+ */
+#define EVENT_KIND_GLOBALMODEL_READY 3
+
+/**
+ * The participant sent its update message
+ */
+#define EVENT_KIND_UPDATE_SENT 4
+
+/**
+ * The participant sent its sum2 message
+ */
+#define EVENT_KIND_SUM2_SENT 5
+
+/**
+ * The previous round is over
+ */
+#define EVENT_KIND_ROUND_COMPLETED 6
+
+/**
+ * The participant dropped out of the current round
+ */
+#define EVENT_KIND_ERROR 7
+
+/**
+ * The participant doesn't take part in the current round, because the advertised masking
+ * configuration is not supported
+ */
+#define EVENT_KIND_UNSUPPORTED_MASK_CONFIG 8
+
+/**
+ * The participant skips or aborts a task of the current round, because a computation exceeds
+ * its compute budget
+ */
+#define EVENT_KIND_BUDGET_EXCEEDED 9
+
+/**
+ * The participant doesn't take part in the current round, because the crypto suite of the round
+ * is not supported
+ */
+#define EVENT_KIND_UNSUPPORTED_CRYPTO_SUITE 10
+
+/**
+ * The event queue overflowed and the oldest events have been dropped
+ */
+#define EVENT_KIND_EVENTS_DROPPED 11
+
+/**
+ * The coordinator rejected a message of the participant
+ */
+#define EVENT_ERROR_MESSAGE_REJECTED 1
+
+/**
+ * A message of the participant couldn't be sent before the retry deadline passed
+ */
+#define EVENT_ERROR_MESSAGE_EXPIRED 2
+
+/**
+ * The mask seeds of the update participants couldn't be decrypted
+ */
+#define EVENT_ERROR_INVALID_SEEDS 3
+
+/**
+ * The local model of the participant changed while it was masked
+ */
+#define EVENT_ERROR_MODEL_CORRUPTED 4
+
+/**
+ * The coordinator banned the participant, which doesn't take part in any further round
+ */
+#define EVENT_ERROR_BANNED 5
+
+/**
+ * The generated ephemeral keys of the sum task repeat the signing keys or the ephemeral keys of
+ * the previous round, which indicates a broken random number generator
+ */
+#define EVENT_ERROR_INSECURE_EPHEMERAL_KEYS 6
+
+/**
+ * The public key of the coordinator doesn't match the pinned key
+ */
+#define EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY 7
+
+/**
+ * The coordinator evicted the participant from the bounded sum dictionary
+ */
+#define EVENT_ERROR_EVICTED 8
+
+/**
+ * The parameters of the round don't match the commitment of the coordinator in the previous round
+ */
+#define EVENT_ERROR_UNCOMMITTED_ROUND_PARAMETERS 9
+
+/**
+ * A message of the participant couldn't be sent before a new round started
+ */
+#define EVENT_ERROR_MESSAGE_OUTDATED 10
+
+/**
+ * The masking of the local model in the update task
+ */
+#define COMPUTE_TASK_MASKING 1
+
+/**
+ * The derivation and aggregation of the masks in the sum2 task
+ */
+#define COMPUTE_TASK_SUM2 2
+
 /**
  * The original primitive data type of the numerical values to be masked.
  */
@@ -307,6 +491,97 @@ typedef struct LocalModelConfig {
   uint64_t len;
 } LocalModelConfig;
 
+/**
+ * An event emitted by the participant, see [`xaynet_ffi_participant_next_event()`].
+ *
+ * Only the fields that belong to the `kind` of the event are set, the other fields are
+ * zero.
+ *
+ * [`xaynet_ffi_participant_next_event()`]: crate::ffi::xaynet_ffi_participant_next_event
+ */
+typedef struct FfiEvent {
+  /**
+   * The kind of the event, one of the `EVENT_KIND_*` constants.
+   */
+  int kind;
+  /**
+   * The round counted by the participant, for [`EVENT_KIND_NEW_ROUND`] and
+   * [`EVENT_KIND_GLOBALMODEL_READY`] events.
+   */
+  uint64_t round_id;
+  /**
+   * The length of the global model, for [`EVENT_KIND_GLOBALMODEL_READY`] events.
+   */
+  uint64_t length;
+  /**
+   * The assigned task, for [`EVENT_KIND_TASK_ASSIGNED`] events. One of
+   * [`PARTICIPANT_TASK_NONE`], [`PARTICIPANT_TASK_SUM`] and [`PARTICIPANT_TASK_UPDATE`].
+   */
+  int task;
+  /**
+   * Whether the message of the participant made it to the coordinator, for
+   * [`EVENT_KIND_ROUND_COMPLETED`] events: `1` if it did, `0` if it didn't and `-1` if
+   * the participant didn't send any message.
+   */
+  int included;
+  /**
+   * The reason of the failure, for [`EVENT_KIND_ERROR`] events. One of the
+   * `EVENT_ERROR_*` constants.
+   */
+  int error;
+  /**
+   * The masking configuration advertised by the coordinator, for
+   * [`EVENT_KIND_UNSUPPORTED_MASK_CONFIG`] events. The group, data, bound and model types are
+   * encoded in this order, see [`xaynet_ffi_settings_set_mask_config_bytes()`].
+   *
+   * [`xaynet_ffi_settings_set_mask_config_bytes()`]: crate::ffi::xaynet_ffi_settings_set_mask_config_bytes
+   */
+  uint8_t mask_config[4];
+  /**
+   * The computation which exceeded its budget, for [`EVENT_KIND_BUDGET_EXCEEDED`] events.
+   * One of [`COMPUTE_TASK_MASKING`] and [`COMPUTE_TASK_SUM2`].
+   */
+  int compute_task;
+  /**
+   * The estimated cost of the masking or the time already spent on the sum2 masks, in
+   * milliseconds, for [`EVENT_KIND_BUDGET_EXCEEDED`] events.
+   */
+  uint64_t cost_millis;
+  /**
+   * The budget of the computation, in milliseconds, for [`EVENT_KIND_BUDGET_EXCEEDED`]
+   * events.
+   */
+  uint64_t budget_millis;
+  /**
+   * The identifier of the crypto suite of the round, for
+   * [`EVENT_KIND_UNSUPPORTED_CRYPTO_SUITE`] events.
+   */
+  uint8_t crypto_suite;
+  /**
+   * The number of events which have been dropped before the following events, for
+   * [`EVENT_KIND_EVENTS_DROPPED`] events.
+   */
+  uint64_t dropped;
+} FfiEvent;
+
+/**
+ * The actual costs of the computations of the participant, in the latest rounds it performed
+ * them, see [`xaynet_ffi_participant_last_costs()`].
+ *
+ * The costs are in microseconds, or `-1` if the participant didn't perform the computation
+ * yet.
+ */
+typedef struct FfiComputeCosts {
+  /**
+   * The time spent on masking the local model.
+   */
+  int64_t masking_micros;
+  /**
+   * The time spent on deriving and aggregating the masks in the sum2 task.
+   */
+  int64_t sum2_micros;
+} FfiComputeCosts;
+
 /**
  * Destroy the given `ByteBuffer` and free its memory. This function must only be
  * called on `ByteBuffer`s that have been created on the Rust side of the FFI. If you
@@ -451,6 +726,75 @@ struct Participant *xaynet_ffi_participant_new(const struct Settings *settings);
  */
 int xaynet_ffi_participant_tick(struct Participant *participant);
 
+/**
+ * Pop the next event emitted by the participant and write it into `out_event`. The
+ * events are emitted while [`xaynet_ffi_participant_tick()`] drives the participant, so
+ * they should be drained after each tick.
+ *
+ * At most 64 events are queued. If the caller doesn't keep up, the oldest events are
+ * dropped and an [`EVENT_KIND_EVENTS_DROPPED`] event with the number of dropped events is
+ * written before the remaining events.
+ *
+ * # Return value
+ *
+ * - [`OK`] if an event has been written into `out_event`
+ * - [`EVENT_NONE`] if no event is pending, or if the participant has been configured in
+ *   notify-only mode (see [`xaynet_ffi_settings_set_notify_only()`])
+ * - [`ERR_NULLPTR`] if `participant` or `out_event` is NULL
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL *or*
+ * all of the following is true:
+ *
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ * [`xaynet_ffi_settings_set_notify_only()`]: crate::ffi::xaynet_ffi_settings_set_notify_only
+ * [`EVENT_KIND_EVENTS_DROPPED`]: crate::ffi::EVENT_KIND_EVENTS_DROPPED
+ *
+ * # Example
+ *
+ * ```c
+ *  xaynet_ffi_participant_tick(participant);
+ *  FfiEvent event;
+ *  while (xaynet_ffi_participant_next_event(participant, &event) == OK) {
+ *      if (event.kind == EVENT_KIND_GLOBALMODEL_READY) {
+ *          // fetch the global model
+ *      }
+ *  }
+ * ```
+ */
+int xaynet_ffi_participant_next_event(struct Participant *participant, struct FfiEvent *out_event);
+
+/**
+ * Release the memory the participant holds beyond its state, e.g. before saving a
+ * long-running participant with [`xaynet_ffi_participant_save()`]. The latest global model
+ * is not cached anymore, hence the next global model is fetched in full. Events that have
+ * not been drained yet are kept.
+ *
+ * # Return value
+ *
+ * - [`OK`] on success
+ * - [`ERR_NULLPTR`] if `participant` is NULL
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointer is NULL *or*
+ * all of the following is true:
+ *
+ * - The pointer must be properly [aligned].
+ * - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_participant_compact(struct Participant *participant);
+
 /**
  * Serialize the participant state and return a buffer that contains the serialized
  * participant.
@@ -626,6 +970,83 @@ int xaynet_ffi_participant_global_model(struct Participant *participant,
  */
 struct LocalModelConfig *xaynet_ffi_participant_local_model_config(const struct Participant *participant);
 
+/**
+ * Write the masking configuration advertised by the coordinator for the current round into
+ * `buffer`, as its 4 bytes serialization: the group type, the data type, the bound type
+ * and the model type, see [`xaynet_ffi_settings_set_mask_config_bytes()`].
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `participant` or `buffer` is `NULL`
+ * - [`ERR_MASKCONFIG_LEN`] if `len` is not 4
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL
+ * *or* all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ * - `buffer` must point to `len` consecutive writable bytes.
+ *
+ * [`xaynet_ffi_settings_set_mask_config_bytes()`]: crate::ffi::xaynet_ffi_settings_set_mask_config_bytes
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_participant_mask_config(const struct Participant *participant,
+                                       uint8_t *buffer,
+                                       uintptr_t len);
+
+/**
+ * Write the calibrated cost of masking a single weight, in nanoseconds, into
+ * `out_nanos_per_weight`. The calibration runs once the masking is bounded by the compute
+ * budget, see [`xaynet_ffi_settings_set_compute_budget()`].
+ *
+ * # Return value
+ *
+ * - [`OK`] if the cost has been written into `out_nanos_per_weight`
+ * - [`CALIBRATION_NONE`] if the cost of masking has not been calibrated yet
+ * - [`ERR_NULLPTR`] if `participant` or `out_nanos_per_weight` is NULL
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL *or*
+ * all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`xaynet_ffi_settings_set_compute_budget()`]: crate::ffi::xaynet_ffi_settings_set_compute_budget
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_participant_calibration(const struct Participant *participant,
+                                       uint64_t *out_nanos_per_weight);
+
+/**
+ * Write the actual costs of masking and of the sum2 task, in the latest rounds the
+ * participant performed them, into `out_costs`.
+ *
+ * # Return value
+ *
+ * - [`OK`] if the costs have been written into `out_costs`
+ * - [`ERR_NULLPTR`] if `participant` or `out_costs` is NULL
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL *or*
+ * all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_participant_last_costs(const struct Participant *participant,
+                                      struct FfiComputeCosts *out_costs);
+
 /**
  * Destroy the settings created by [`xaynet_ffi_settings_new()`].
  *
@@ -683,6 +1104,146 @@ struct Settings *xaynet_ffi_settings_new(void);
  */
 int xaynet_ffi_settings_set_scalar(struct Settings *settings, double scalar);
 
+/**
+ * Set the scalar setting to the exact fraction `numer / denom`.
+ *
+ * Unlike [`xaynet_ffi_settings_set_scalar()`], the scalar doesn't suffer from the binary
+ * rounding of floats, e.g. `1 / 3` is exactly a third. A zero denominator is reported as an
+ * invalid scalar by [`xaynet_ffi_check_settings()`].
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `settings` is `NULL`
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointer is NULL *or*
+ * all of the following is true:
+ * - The pointer must be properly [aligned].
+ * - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_settings_set_scalar_ratio(struct Settings *settings, uint64_t numer, uint64_t denom);
+
+/**
+ * Set whether the participant runs in notify-only mode. In notify-only mode, the
+ * participant doesn't queue any events for [`xaynet_ffi_participant_next_event()`] and
+ * the caller has to check the flags returned by [`xaynet_ffi_participant_tick()`]
+ * instead.
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `settings` is `NULL`
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointer is NULL *or*
+ * all of the following is true:
+ * - The pointer must be properly [aligned].
+ * - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ * [`xaynet_ffi_participant_next_event()`]: crate::ffi::xaynet_ffi_participant_next_event
+ * [`xaynet_ffi_participant_tick()`]: crate::ffi::xaynet_ffi_participant_tick
+ */
+int xaynet_ffi_settings_set_notify_only(struct Settings *settings, bool notify_only);
+
+/**
+ * Set the masking configuration the participant expects from the coordinator, from its
+ * 4 bytes serialization: the group type, the data type, the bound type and the model
+ * type. The participant doesn't take part in rounds with a different masking
+ * configuration.
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `settings` or `bytes` is `NULL`
+ * - [`ERR_MASKCONFIG_LEN`] if `len` is not 4
+ * - [`ERR_MASKCONFIG_GROUPTYPE`] if the first byte is not a valid group type
+ * - [`ERR_MASKCONFIG_DATATYPE`] if the second byte is not a valid data type
+ * - [`ERR_MASKCONFIG_BOUNDTYPE`] if the third byte is not a valid bound type
+ * - [`ERR_MASKCONFIG_MODELTYPE`] if the fourth byte is not a valid model type
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL
+ * *or* all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ * - `bytes` must point to `len` consecutive properly initialized bytes.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_settings_set_mask_config_bytes(struct Settings *settings,
+                                              const uint8_t *bytes,
+                                              uintptr_t len);
+
+/**
+ * Pin the public key the coordinator is expected to publish in the round parameters, from its
+ * 32 bytes. The participant doesn't take part in rounds with another key, see
+ * [`EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY`].
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `settings` or `bytes` is `NULL`
+ * - [`ERR_COORDINATOR_PK_LEN`] if `len` is not 32
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL
+ * *or* all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ * - `bytes` must point to `len` consecutive properly initialized bytes.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ * [`EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY`]: crate::ffi::EVENT_ERROR_UNTRUSTED_COORDINATOR_KEY
+ */
+int xaynet_ffi_settings_set_coordinator_pk(struct Settings *settings,
+                                           const uint8_t *bytes,
+                                           uintptr_t len);
+
+/**
+ * Set the bounds on the time the participant spends on masking its local model and on
+ * deriving the masks in the sum2 task, in milliseconds. A bound of `0` doesn't bound the
+ * computation.
+ *
+ * The participant skips the rounds where masking is estimated to exceed its budget and
+ * aborts the sum2 task once it exceeded its budget, see [`EVENT_KIND_BUDGET_EXCEEDED`].
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `settings` is `NULL`
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointer is NULL *or*
+ * all of the following is true:
+ * - The pointer must be properly [aligned].
+ * - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ * [`EVENT_KIND_BUDGET_EXCEEDED`]: crate::ffi::EVENT_KIND_BUDGET_EXCEEDED
+ */
+int xaynet_ffi_settings_set_compute_budget(struct Settings *settings,
+                                           uint64_t max_masking_millis,
+                                           uint64_t max_sum2_millis);
+
 /**
  * Set coordinator URL.
  *
@@ -774,10 +1335,13 @@ int xaynet_ffi_settings_set_keys(struct Settings *settings, const struct KeyPair
  * # Return value
  *
  * - [`OK`] on success
- * - [`ERR_SETTINGS_URL`] if the URL has not been set
- * - [`ERR_SETTINGS_KEYS`] if the signing keys have not been set
+ * - [`ERR_SETTINGS_URL`] if the URL has not been set or is invalid
+ * - [`ERR_SETTINGS_KEYS`] if the signing keys have not been set or don't match
  * - [`ERR_SETTINGS_SCALAR`] if the scalar is out of bounds
  *
+ * Only the first problem is reported, see [`xaynet_ffi_settings_validate()`] to get all of
+ * them.
+ *
  * # Safety
  *
  * When calling this method, you have to ensure that *either* the pointer is NULL *or*
@@ -793,6 +1357,38 @@ int xaynet_ffi_settings_set_keys(struct Settings *settings, const struct KeyPair
  */
 int xaynet_ffi_check_settings(const struct Settings *settings);
 
+/**
+ * Validate the given settings and report all the problems at once, unlike
+ * [`xaynet_ffi_check_settings()`] which only reports the first one.
+ *
+ * # Return value
+ *
+ * - [`OK`] if the settings are valid
+ * - -[`ERR_NULLPTR`] if `settings` is `NULL`, which is negative to set it apart from the bitmask
+ * - a bitmask of the problems otherwise, where each bit is one of:
+ *   - [`SETTINGS_URL_MISSING`] if the URL has not been set
+ *   - [`SETTINGS_URL_INVALID`] if the URL is invalid
+ *   - [`SETTINGS_KEYS_MISSING`] if the signing keys have not been set
+ *   - [`SETTINGS_KEYS_INVALID`] if the public key doesn't match the secret key
+ *   - [`SETTINGS_SCALAR_INVALID`] if the scalar is out of bounds
+ *
+ * The masking configuration and the model length are not part of the settings, since they
+ * are announced by the coordinator in each round.
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointer is NULL *or*
+ * all of the following is true:
+ *
+ * - The pointer must be properly [aligned].
+ * - It must be "dereferencable" in the sense defined in the [`std::ptr`] module
+ *   documentation.
+ *
+ * [`std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_settings_validate(const struct Settings *settings);
+
 /**
  * Destroy the model configuration created by [`xaynet_ffi_participant_local_model_config()`].
  *
@@ -0,0 +1,107 @@
+//! Bounds on the computations of a participant.
+//!
+//! Masking a large model and deriving the masks of all the update participants in the sum2
+//! phase can take minutes of CPU time on low-end devices. A [`ComputeBudget`] bounds this work:
+//! the cost of masking is estimated from a [`Calibration`] before the participant takes part
+//! in the update task, and the derivation of the sum2 masks is aborted once it exceeded its
+//! budget.
+//!
+//! [`ComputeBudget`]: crate::settings::ComputeBudget
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use xaynet_core::mask::{FromPrimitives, MaskConfigPair, Masker, Model, Scalar};
+
+/// A computation of a participant which is bounded by the compute budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputeTask {
+    /// The masking of the local model in the update task.
+    Masking,
+    /// The derivation and aggregation of the masks in the sum2 task.
+    Sum2,
+}
+
+/// A computation which exceeded its compute budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    /// The computation which exceeded its budget.
+    pub task: ComputeTask,
+    /// The estimated cost of the masking or the time already spent on the sum2 masks.
+    pub cost: Duration,
+    /// The budget of the computation.
+    pub budget: Duration,
+}
+
+/// The cost of masking, measured by a micro-benchmark on the device of the participant.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Calibration {
+    /// The masking configuration the benchmark ran with.
+    pub mask_config: MaskConfigPair,
+    /// The cost of masking a single weight, in nanoseconds.
+    pub masking_nanos_per_weight: u64,
+}
+
+impl Calibration {
+    /// The number of weights of the model masked by the benchmark.
+    const BENCHMARK_MODEL_LENGTH: usize = 256;
+
+    /// Calibrates the cost of masking by masking a small model with the given configuration.
+    pub fn run(mask_config: MaskConfigPair) -> Self {
+        // UNWRAP_SAFE: zeros are finite
+        let model =
+            Model::from_primitives(vec![0_i32; Self::BENCHMARK_MODEL_LENGTH].into_iter()).unwrap();
+        let start = Instant::now();
+        let _ = Masker::new(mask_config).mask(Scalar::unit(), &model);
+        let nanos = start.elapsed().as_nanos() / Self::BENCHMARK_MODEL_LENGTH as u128;
+        Self {
+            mask_config,
+            // a weight is never free, such that any model exceeds a zero budget
+            masking_nanos_per_weight: (nanos as u64).max(1),
+        }
+    }
+
+    /// Estimates the cost of masking a model of the given length.
+    pub fn masking_cost(&self, model_length: usize) -> Duration {
+        let nanos = self
+            .masking_nanos_per_weight
+            .saturating_mul(model_length as u64);
+        Duration::from_nanos(nanos)
+    }
+}
+
+/// The actual costs of the computations of the participant, in the latest round it performed
+/// them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeCosts {
+    /// The time spent on masking the local model, if the participant masked a model yet.
+    pub masking: Option<Duration>,
+    /// The time spent on deriving and aggregating the sum2 masks, if the participant
+    /// completed a sum2 task yet.
+    pub sum2: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use xaynet_core::mask::{BoundType, DataType, GroupType, MaskConfig, ModelType};
+
+    use super::*;
+
+    #[test]
+    fn test_calibration() {
+        let mask_config = MaskConfigPair::from(MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        });
+        let calibration = Calibration::run(mask_config);
+        assert_eq!(calibration.mask_config, mask_config);
+        assert!(calibration.masking_nanos_per_weight > 0);
+        assert_eq!(calibration.masking_cost(0), Duration::from_nanos(0));
+        assert_eq!(
+            calibration.masking_cost(1000),
+            Duration::from_nanos(calibration.masking_nanos_per_weight * 1000),
+        );
+    }
+}
@@ -1,14 +1,30 @@
+#[cfg(feature = "reqwest-client")]
+use std::time::Duration;
+
 use async_trait::async_trait;
 use thiserror::Error;
+use tracing::{debug, warn};
 use url::Url;
 
 use crate::XaynetClient;
 use xaynet_core::{
-    common::RoundParameters,
-    crypto::{ByteObject, PublicSigningKey},
-    mask::Model,
+    common::{
+        GlobalModelMetadata,
+        RoundParameters,
+        RoundSubmissionStatus,
+        RoundSumDict,
+        RoundSummary,
+        ROUND_SUMMARY_VERSION,
+    },
+    crypto::ByteObject,
+    mask::{Model, ModelDelta},
+    message::FromBytes,
+    ParticipantPublicKey,
+    SeedDictSource,
     SumDict,
+    SumParticipantPublicKey,
     UpdateSeedDict,
+    VersionedSeeds,
 };
 
 /// Error returned upon failing to build a new [`Client`]
@@ -34,6 +50,18 @@ pub enum ClientError {
 
     #[error("No certificate found")]
     NoCertificate,
+
+    #[error("The message was rejected because it was derived from an outdated seed dictionary")]
+    StaleSeedDict,
+
+    #[error("The participant is banned by the coordinator")]
+    Banned,
+
+    #[error("The message was rejected by the coordinator (status {0})")]
+    Rejected(u16),
+
+    #[error(transparent)]
+    InvalidBaseUrl(#[from] InvalidBaseUrl),
 }
 
 #[cfg_attr(not(feature = "reqwest-client"), allow(dead_code))]
@@ -71,6 +99,42 @@ pub trait XaynetHttpClient {
 
     /// Perform an HTTP `POST` on the given URL, with the given body.
     async fn post(&mut self, url: &str, body: Vec<u8>) -> Result<(), ClientError>;
+
+    /// Perform a conditional HTTP `GET` on the given URL, with an `If-None-Match` header
+    /// carrying the `etag` if any.
+    ///
+    /// If the response is `NOT_MODIFIED`, the implementor must return
+    /// `Ok(ConditionalResponse::NotModified)`. Otherwise, the response body must be returned
+    /// like for [`get()`](XaynetHttpClient::get), together with the `ETag` of the response.
+    async fn get_if_none_match(
+        &mut self,
+        url: &str,
+        etag: Option<&str>,
+    ) -> Result<ConditionalResponse<Self::GetResponse>, ClientError>;
+}
+
+/// The response to a conditional `GET`, see [`XaynetHttpClient::get_if_none_match()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionalResponse<R> {
+    /// The resource didn't change since its entity tag was issued.
+    NotModified,
+    /// The resource changed. The body is `None` if the response is `NO_CONTENT`.
+    Modified {
+        /// The entity tag of the resource, if any.
+        etag: Option<String>,
+        /// The body of the response, if any.
+        body: Option<R>,
+    },
+}
+
+/// A global model kept by a participant in order to fetch only the delta to the next global
+/// model, see [`Client::cached_global_model()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedGlobalModel {
+    /// The id of the round in which the model was aggregated.
+    pub round_id: u64,
+    /// The global model.
+    pub model: Model,
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +144,8 @@ pub struct Client<C> {
     client: C,
     /// Coordinator URL
     base_url: Url,
+    /// The latest round summary together with its entity tag
+    round_summary: Option<(String, RoundSummary)>,
 }
 
 /// Error returned when trying to client a [`Client`] with an invalid
@@ -111,6 +177,7 @@ where
         Ok(Self {
             client: http_client,
             base_url,
+            round_summary: None,
         })
     }
 
@@ -134,16 +201,14 @@ where
     async fn post(&mut self, url: &Url, data: Vec<u8>) -> Result<(), ClientError> {
         self.client.post(url.as_str(), data).await
     }
-}
-
-#[async_trait]
-impl<C> XaynetClient for Client<C>
-where
-    C: XaynetHttpClient + Send,
-{
-    type Error = ClientError;
 
-    async fn get_round_params(&mut self) -> Result<RoundParameters, Self::Error> {
+    /// Fetch the round parameters of the current round from `GET /params`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the request fails or if the coordinator
+    /// answers without round parameters.
+    pub async fn round_params(&mut self) -> Result<RoundParameters, ClientError> {
         let url = self.url("params");
         let round_params: Option<RoundParameters> = self.get(&url).await?;
         round_params.ok_or_else(|| {
@@ -151,32 +216,441 @@ where
         })
     }
 
-    async fn get_sums(&mut self) -> Result<Option<SumDict>, Self::Error> {
+    /// Fetch the summary of the current round from
+    /// `GET /rounds/current/summary?version=<version>`, where the version is the
+    /// [`ROUND_SUMMARY_VERSION`].
+    ///
+    /// The summary is enough to check whether the participant is selected for a task with
+    /// [`should_wake()`](crate::eligibility::should_wake).
+    ///
+    /// The latest summary is cached together with its `ETag`. The request is conditional on the
+    /// cached `ETag`, such that the cached summary is returned if the coordinator answers with
+    /// `304 Not Modified`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the request fails or if the coordinator
+    /// answers without a round summary.
+    pub async fn round_summary(&mut self) -> Result<RoundSummary, ClientError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .unwrap()
+            .extend(&["rounds", "current", "summary"]);
+        url.query_pairs_mut()
+            .append_pair("version", &ROUND_SUMMARY_VERSION.to_string());
+        let etag = self.round_summary.as_ref().map(|(etag, _)| etag.as_str());
+        match self.client.get_if_none_match(url.as_str(), etag).await? {
+            ConditionalResponse::NotModified => match self.round_summary {
+                Some((_, ref summary)) => Ok(summary.clone()),
+                None => Err(ClientError::UnexpectedResponse(304)),
+            },
+            ConditionalResponse::Modified { etag, body } => {
+                let data = body.ok_or_else(|| {
+                    ClientError::Other("failed to fetch round summary: empty response".to_string())
+                })?;
+                let summary = bincode::deserialize::<RoundSummary>(data.as_ref())?;
+                self.round_summary = etag.map(|etag| (etag, summary.clone()));
+                Ok(summary)
+            }
+        }
+    }
+
+    /// Fetch the sum dictionary from `GET /sums`.
+    ///
+    /// `Ok(None)` is returned if the sum dictionary is not available yet.
+    pub async fn sum_dict(&mut self) -> Result<Option<SumDict>, ClientError> {
         let url = self.url("sums");
-        Ok(self.get(&url).await?)
+        self.get(&url).await
     }
 
-    async fn get_seeds(
+    /// Fetch the sum dictionary together with the seed of its round from `GET /sums?round=true`.
+    ///
+    /// The seed allows to check that the sum dictionary belongs to the round of the participant,
+    /// see [`RoundSumDict::is_for()`].
+    ///
+    /// `Ok(None)` is returned if the sum dictionary is not available yet.
+    pub async fn round_sum_dict(&mut self) -> Result<Option<RoundSumDict>, ClientError> {
+        let mut url = self.url("sums");
+        url.query_pairs_mut().append_pair("round", "true");
+        self.get(&url).await
+    }
+
+    /// Fetch the seeds dedicated to the sum participant `pk`.
+    ///
+    /// The seeds are fetched from the blob exported by the coordinator if available, see
+    /// [`seed_dict_source()`]. Otherwise, or if the blob can't be fetched, they are fetched
+    /// inline from `GET /seeds?pk=<pk>`, where the key is base64 encoded.
+    ///
+    /// `Ok(None)` is returned if the seed dictionary is not available yet.
+    ///
+    /// [`seed_dict_source()`]: Client::seed_dict_source
+    pub async fn seed_dict(
         &mut self,
-        pk: PublicSigningKey,
-    ) -> Result<Option<UpdateSeedDict>, Self::Error> {
+        pk: &SumParticipantPublicKey,
+    ) -> Result<Option<UpdateSeedDict>, ClientError> {
+        match self.seed_dict_source(pk).await {
+            Ok(Some(SeedDictSource::Location(location))) => {
+                match self.exported_seed_dict(&location).await {
+                    Ok(seeds) => return Ok(Some(seeds)),
+                    Err(e) => warn!(
+                        "failed to fetch the exported seed dictionary from {}: {}",
+                        location, e
+                    ),
+                }
+            }
+            Ok(Some(SeedDictSource::Inline(seeds))) => return Ok(Some(seeds)),
+            Ok(None) => return Ok(None),
+            // the coordinator may not support the seed dictionary export
+            Err(e) => debug!("failed to fetch the seed dictionary source: {}", e),
+        }
+
         let mut url = self.url("seeds");
         url.query_pairs_mut()
             .append_pair("pk", &base64::encode(pk.as_slice()));
         self.get(&url).await
     }
 
-    async fn get_model(&mut self) -> Result<Option<Model>, Self::Error> {
-        let url = self.url("model");
-        Ok(self.get(&url).await?)
+    /// Fetch the seeds dedicated to the sum participant `pk` together with the version of the
+    /// seed dictionary.
+    ///
+    /// The seeds are fetched from `GET /seeds?pk=<pk>&location=true&version=true`, where the key
+    /// is base64 encoded. If the coordinator points to an exported blob of the seeds which can't be
+    /// fetched, they are fetched inline from `GET /seeds?pk=<pk>&version=true` instead. The version
+    /// must be echoed in the sum2 message.
+    ///
+    /// `Ok(None)` is returned if the seed dictionary is not available yet.
+    pub async fn versioned_seed_dict(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+    ) -> Result<Option<VersionedSeeds>, ClientError> {
+        let mut url = self.url("seeds");
+        url.query_pairs_mut()
+            .append_pair("pk", &base64::encode(pk.as_slice()))
+            .append_pair("location", "true")
+            .append_pair("version", "true");
+        let VersionedSeeds { version, seeds } =
+            match self.get::<VersionedSeeds<SeedDictSource>>(&url).await? {
+                Some(versioned) => versioned,
+                None => return Ok(None),
+            };
+        let location = match seeds {
+            SeedDictSource::Inline(seeds) => return Ok(Some(VersionedSeeds { version, seeds })),
+            SeedDictSource::Location(location) => location,
+        };
+        match self.exported_seed_dict(&location).await {
+            Ok(seeds) => return Ok(Some(VersionedSeeds { version, seeds })),
+            Err(e) => warn!(
+                "failed to fetch the exported seed dictionary from {}: {}",
+                location, e
+            ),
+        }
+
+        let mut url = self.url("seeds");
+        url.query_pairs_mut()
+            .append_pair("pk", &base64::encode(pk.as_slice()))
+            .append_pair("version", "true");
+        Ok(
+            match self.get::<VersionedSeeds<SeedDictSource>>(&url).await? {
+                Some(VersionedSeeds {
+                    version,
+                    seeds: SeedDictSource::Inline(seeds),
+                }) => Some(VersionedSeeds { version, seeds }),
+                Some(VersionedSeeds {
+                    seeds: SeedDictSource::Location(_),
+                    ..
+                }) => {
+                    return Err(ClientError::Other(
+                        "failed to fetch the seed dictionary inline".to_string(),
+                    ))
+                }
+                None => None,
+            },
+        )
     }
 
-    async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Self::Error> {
+    /// Fetch the source of the seeds dedicated to the sum participant `pk` from
+    /// `GET /seeds?pk=<pk>&location=true`, where the key is base64 encoded.
+    ///
+    /// The source is the location of the blob of the seeds if the coordinator exported the seed
+    /// dictionary, otherwise it carries the seeds inline.
+    ///
+    /// `Ok(None)` is returned if the seed dictionary is not available yet.
+    pub async fn seed_dict_source(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+    ) -> Result<Option<SeedDictSource>, ClientError> {
+        let mut url = self.url("seeds");
+        url.query_pairs_mut()
+            .append_pair("pk", &base64::encode(pk.as_slice()))
+            .append_pair("location", "true");
+        self.get(&url).await
+    }
+
+    /// Fetch the seeds exported by the coordinator from the `location` of their blob.
+    ///
+    /// The blob holds the canonical binary encoding of the seeds.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the request fails or if the blob is empty or invalid.
+    pub async fn exported_seed_dict(
+        &mut self,
+        location: &str,
+    ) -> Result<UpdateSeedDict, ClientError> {
+        let data = self.client.get(location).await?.ok_or_else(|| {
+            ClientError::Other(
+                "failed to fetch exported seed dictionary: empty response".to_string(),
+            )
+        })?;
+        UpdateSeedDict::from_byte_slice(&data)
+            .map_err(|e| ClientError::Deserialize(format!("{}", e)))
+    }
+
+    /// Fetch the latest global model from `GET /model`, or the global model of the given `round`
+    /// from `GET /model?round=<round>`.
+    ///
+    /// The coordinator only keeps the latest global model. `Ok(None)` is returned if no global
+    /// model is available yet or if the latest global model doesn't belong to the `round`.
+    pub async fn global_model(&mut self, round: Option<u64>) -> Result<Option<Model>, ClientError> {
+        let mut url = self.url("model");
+        if let Some(round) = round {
+            url.query_pairs_mut()
+                .append_pair("round", &round.to_string());
+        }
+        self.get(&url).await
+    }
+
+    /// Fetch the delta from the global model of the `base_round_id` round to the latest global
+    /// model from `GET /model/delta?base=<base_round_id>`.
+    ///
+    /// `Ok(None)` is returned if no delta from the global model of the `base_round_id` round is
+    /// available, in which case the latest global model must be downloaded in full.
+    pub async fn global_model_delta(
+        &mut self,
+        base_round_id: u64,
+    ) -> Result<Option<ModelDelta>, ClientError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut().unwrap().extend(&["model", "delta"]);
+        url.query_pairs_mut()
+            .append_pair("base", &base_round_id.to_string());
+        self.get(&url).await
+    }
+
+    /// Fetch the latest global model and update the `cache` with it.
+    ///
+    /// If the `cache` holds the global model of the previous round, only the delta to the latest
+    /// global model is downloaded and applied. Otherwise, or if the delta can't be applied, the
+    /// latest global model is downloaded in full. The checksum of the delta guards against a
+    /// global model which changed in between the requests.
+    ///
+    /// `Ok(None)` is returned and the `cache` is cleared if no global model is available yet.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if a request fails or if the global model changes again while the
+    /// global model of its latest round is fetched.
+    pub async fn cached_global_model(
+        &mut self,
+        cache: &mut Option<CachedGlobalModel>,
+    ) -> Result<Option<Model>, ClientError> {
+        let mut round_id = self
+            .global_model_metadata()
+            .await?
+            .map(|metadata| metadata.round_id);
+
+        if let (Some(round_id), Some(cached)) = (round_id, cache.as_ref()) {
+            if cached.round_id == round_id {
+                return Ok(Some(cached.model.clone()));
+            }
+            if let Some(delta) = self.global_model_delta(cached.round_id).await? {
+                if delta.round_id == round_id {
+                    if let Ok(model) = delta.apply(&cached.model) {
+                        *cache = Some(CachedGlobalModel {
+                            round_id,
+                            model: model.clone(),
+                        });
+                        return Ok(Some(model));
+                    }
+                }
+            }
+        }
+
+        // requesting the model of the round guards against a global model which changed since its
+        // metadata was fetched
+        let mut model = self.global_model(round_id).await?;
+        if model.is_none() && round_id.is_some() {
+            // the global model changed, hence the model of its latest round is requested instead
+            round_id = self
+                .global_model_metadata()
+                .await?
+                .map(|metadata| metadata.round_id);
+            model = match round_id {
+                Some(round_id) => self.global_model(Some(round_id)).await?,
+                None => None,
+            };
+            if model.is_none() && round_id.is_some() {
+                *cache = None;
+                return Err(ClientError::Other(
+                    "failed to fetch global model: the global model changed in between the requests"
+                        .to_string(),
+                ));
+            }
+        }
+        *cache = match (round_id, model.as_ref()) {
+            (Some(round_id), Some(model)) => Some(CachedGlobalModel {
+                round_id,
+                model: model.clone(),
+            }),
+            _ => None,
+        };
+        Ok(model)
+    }
+
+    /// Fetch the metadata of the latest global model from `GET /model/metadata`.
+    ///
+    /// The metadata declares the original data type of the weights, which is needed to convert
+    /// the global model into primitive values, e.g. with
+    /// [`GlobalModelMetadata::to_primitives_bytes()`].
+    ///
+    /// `Ok(None)` is returned if no global model is available yet or if its metadata is unknown.
+    pub async fn global_model_metadata(
+        &mut self,
+    ) -> Result<Option<GlobalModelMetadata>, ClientError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .unwrap()
+            .extend(&["model", "metadata"]);
+        self.get(&url).await
+    }
+
+    /// Fetch the submission status of the participant `pk` in the current round from
+    /// `GET /rounds/current/submissions/<pk>`, where the key is URL-safe base64 encoded.
+    ///
+    /// The status tells which messages of the participant are recorded by the coordinator. It
+    /// only belongs to the round of the participant if its seed matches, see
+    /// [`RoundSubmissionStatus::is_for()`].
+    pub async fn submission_status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, ClientError> {
+        let pk = base64::encode_config(pk.as_slice(), base64::URL_SAFE);
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .unwrap()
+            .extend(&["rounds", "current", "submissions", &pk]);
+        self.get(&url).await
+    }
+
+    /// Send an encrypted and signed PET message to `POST /message`.
+    ///
+    /// The message is sent as is, as `application/octet-stream` body.
+    pub async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), ClientError> {
         let url = self.url("message");
         self.post(&url, msg).await
     }
 }
 
+#[async_trait]
+impl<C> XaynetClient for Client<C>
+where
+    C: XaynetHttpClient + Send,
+{
+    type Error = ClientError;
+
+    async fn get_round_params(&mut self) -> Result<RoundParameters, Self::Error> {
+        self.round_params().await
+    }
+
+    async fn get_sums(&mut self) -> Result<Option<RoundSumDict>, Self::Error> {
+        self.round_sum_dict().await
+    }
+
+    async fn get_seeds(
+        &mut self,
+        pk: SumParticipantPublicKey,
+    ) -> Result<Option<VersionedSeeds>, Self::Error> {
+        self.versioned_seed_dict(&pk).await
+    }
+
+    async fn get_model(&mut self) -> Result<Option<Model>, Self::Error> {
+        self.global_model(None).await
+    }
+
+    async fn get_submission_status(
+        &mut self,
+        pk: ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, Self::Error> {
+        self.submission_status(&pk).await
+    }
+
+    async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Self::Error> {
+        Client::send_message(self, msg).await
+    }
+}
+
+/// A [`Client`] backed by a [`reqwest::Client`].
+///
+/// The client accepts gzip and deflate compressed responses and decompresses them transparently.
+#[cfg(feature = "reqwest-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest-client")))]
+pub type CoordinatorClient = Client<reqwest::Client>;
+
+/// Settings of the HTTP(S) client used by a [`CoordinatorClient`].
+#[cfg(feature = "reqwest-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest-client")))]
+#[derive(Debug, Clone, Default)]
+pub struct HttpSettings {
+    /// Timeout of a whole request, from connecting until the end of the response body.
+    pub timeout: Option<Duration>,
+    /// Timeout of the connect phase of a request.
+    pub connect_timeout: Option<Duration>,
+    /// PEM encoded root certificate for TLS server authentication.
+    pub trust_anchor: Option<Vec<u8>>,
+    /// PEM encoded certificate and private key for TLS client authentication.
+    pub identity: Option<Vec<u8>>,
+}
+
+#[cfg(feature = "reqwest-client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest-client")))]
+impl Client<reqwest::Client> {
+    /// Create a new client for the coordinator at `base_url` with a default HTTP client.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `base_url` is not a valid URL or the HTTP
+    /// client cannot be initialized.
+    pub fn from_url(base_url: &str) -> Result<Self, ClientError> {
+        Self::from_settings(base_url, HttpSettings::default())
+    }
+
+    /// Create a new client for the coordinator at `base_url` with the given HTTP settings.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `base_url` is not a valid URL, the
+    /// certificates are invalid or the HTTP client cannot be initialized.
+    pub fn from_settings(base_url: &str, settings: HttpSettings) -> Result<Self, ClientError> {
+        let mut builder = reqwest::ClientBuilder::new().gzip(true).deflate(true);
+        if let Some(timeout) = settings.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = settings.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(pem) = settings.trust_anchor {
+            let root_cert =
+                reqwest::Certificate::from_pem(&pem).map_err(ClientError::http_error)?;
+            builder = builder.use_rustls_tls().add_root_certificate(root_cert);
+        }
+        if let Some(pem) = settings.identity {
+            let identity = reqwest::Identity::from_pem(&pem).map_err(ClientError::http_error)?;
+            builder = builder.use_rustls_tls().identity(identity);
+        }
+        let http_client = builder.build().map_err(ClientError::http_error)?;
+        Ok(Self::new(http_client, base_url)?)
+    }
+}
+
 #[cfg(feature = "reqwest-client")]
 #[cfg_attr(docsrs, doc(cfg(feature = "reqwest-client")))]
 #[async_trait]
@@ -200,14 +674,513 @@ impl XaynetHttpClient for reqwest::Client {
         }
     }
 
+    async fn get_if_none_match(
+        &mut self,
+        url: &str,
+        etag: Option<&str>,
+    ) -> Result<ConditionalResponse<Self::GetResponse>, ClientError> {
+        let mut request = reqwest::Client::get(self, url);
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let resp = request.send().await.map_err(ClientError::http_error)?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(ConditionalResponse::NotModified);
+        }
+        let resp = resp.error_for_status().map_err(ClientError::http_error)?;
+        let etag = resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(String::from);
+        let body = match resp.status() {
+            reqwest::StatusCode::OK => Some(resp.bytes().await.map_err(ClientError::http_error)?),
+            reqwest::StatusCode::NO_CONTENT => None,
+            status => return Err(ClientError::UnexpectedResponse(status.as_u16())),
+        };
+        Ok(ConditionalResponse::Modified { etag, body })
+    }
+
     async fn post(&mut self, url: &str, body: Vec<u8>) -> Result<(), ClientError> {
-        let _resp = reqwest::Client::post(self, url)
+        let resp = reqwest::Client::post(self, url)
             .body(body)
             .send()
             .await
-            .map_err(ClientError::http_error)?
-            .error_for_status()
             .map_err(ClientError::http_error)?;
-        Ok(())
+        match resp.status() {
+            reqwest::StatusCode::CONFLICT => Err(ClientError::StaleSeedDict),
+            reqwest::StatusCode::FORBIDDEN => Err(ClientError::Banned),
+            // the message won't get any smaller when it is resent
+            status @ reqwest::StatusCode::PAYLOAD_TOO_LARGE => {
+                Err(ClientError::Rejected(status.as_u16()))
+            }
+            // any other failure, e.g. `408 Request Timeout` or `429 Too Many Requests`, is
+            // considered transient and the message is resent
+            _ => {
+                let _resp = resp.error_for_status().map_err(ClientError::http_error)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "reqwest-client"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::Write,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use flate2::{write::GzEncoder, Compression};
+    use tokio::sync::mpsc;
+    use warp::{
+        http::{Response, StatusCode},
+        Filter,
+    };
+    use xaynet_core::{
+        mask::{EncryptedMaskSeed, FromPrimitives},
+        message::ToBytes,
+        SumParticipantEphemeralPublicKey,
+        UpdateParticipantPublicKey,
+    };
+
+    use super::*;
+    use crate::state_machine::tests::utils::{round_params, SelectFor};
+
+    fn ok(body: Vec<u8>) -> Response<Vec<u8>> {
+        Response::builder()
+            .header("Content-Type", "application/octet-stream")
+            .status(StatusCode::OK)
+            .body(body)
+            .unwrap()
+    }
+
+    fn status(code: StatusCode) -> Response<Vec<u8>> {
+        Response::builder().status(code).body(Vec::new()).unwrap()
+    }
+
+    /// Spawns a coordinator API serving canned responses and returns its address as well as
+    /// a receiver for the messages it receives.
+    fn serve(
+        sum_pk: SumParticipantPublicKey,
+        seeds: UpdateSeedDict,
+    ) -> (SocketAddr, mpsc::UnboundedReceiver<Vec<u8>>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let params = warp::path!("params")
+            .map(|| ok(bincode::serialize(&round_params(SelectFor::Sum)).unwrap()));
+        let summary = warp::path!("rounds" / "current" / "summary")
+            .map(|| ok(bincode::serialize(&round_summary()).unwrap()));
+        let sums = warp::path!("sums").map(|| status(StatusCode::NO_CONTENT));
+        let seeds = warp::path!("seeds")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(
+                move |query: HashMap<String, String>| match query.get("pk").map(base64::decode) {
+                    Some(Ok(pk)) if pk != sum_pk.as_slice() => status(StatusCode::BAD_REQUEST),
+                    Some(Ok(_)) if query.get("version").map(String::as_str) == Some("true") => {
+                        let versioned = VersionedSeeds {
+                            version: 4,
+                            seeds: SeedDictSource::Inline(seeds.clone()),
+                        };
+                        ok(bincode::serialize(&versioned).unwrap())
+                    }
+                    Some(Ok(_)) => ok(bincode::serialize(&seeds).unwrap()),
+                    _ => status(StatusCode::BAD_REQUEST),
+                },
+            );
+        let model = warp::path!("model").map(|| status(StatusCode::INTERNAL_SERVER_ERROR));
+        let model_delta = warp::path!("model" / "delta").map(|| status(StatusCode::NO_CONTENT));
+        let model_metadata = warp::path!("model" / "metadata")
+            .map(|| ok(bincode::serialize(&global_model_metadata()).unwrap()));
+        let message = warp::path!("message")
+            .and(warp::post())
+            .and(warp::body::bytes())
+            .map(move |body: bytes::Bytes| {
+                tx.send(body.to_vec()).unwrap();
+                warp::reply()
+            });
+        let routes = warp::get()
+            .and(
+                params
+                    .or(summary)
+                    .or(sums)
+                    .or(seeds)
+                    .or(model)
+                    .or(model_delta)
+                    .or(model_metadata),
+            )
+            .or(message);
+        let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (addr, rx)
+    }
+
+    fn round_summary() -> RoundSummary {
+        let params = round_params(SelectFor::Sum);
+        RoundSummary {
+            round_id: 1,
+            round_token: params.seed.token(),
+            seed: params.seed,
+            phase: "Sum".to_string(),
+            sum: params.sum,
+            update: params.update,
+            eligibility_salt: params.eligibility_salt,
+            crypto_suite: params.crypto_suite,
+        }
+    }
+
+    fn global_model_metadata() -> GlobalModelMetadata {
+        let params = round_params(SelectFor::Sum);
+        GlobalModelMetadata::new(1, params.mask_config, params.model_length, 2)
+    }
+
+    fn update_seed_dict() -> UpdateSeedDict {
+        UpdateSeedDict::from_storage(vec![(
+            UpdateParticipantPublicKey::fill_with(1),
+            EncryptedMaskSeed::fill_with(2),
+        )])
+    }
+
+    #[tokio::test]
+    async fn test_typed_requests() {
+        let sum_pk = SumParticipantPublicKey::fill_with(3);
+        let (addr, mut messages) = serve(sum_pk, update_seed_dict());
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert_eq!(
+            client.round_params().await.unwrap(),
+            round_params(SelectFor::Sum),
+        );
+        assert_eq!(client.round_summary().await.unwrap(), round_summary());
+        assert!(client.sum_dict().await.unwrap().is_none());
+        assert!(client.round_sum_dict().await.unwrap().is_none());
+        assert_eq!(
+            client.seed_dict(&sum_pk).await.unwrap(),
+            Some(update_seed_dict()),
+        );
+        assert!(matches!(
+            client.seed_dict(&SumParticipantPublicKey::zeroed()).await,
+            Err(ClientError::Http(_)),
+        ));
+        assert!(matches!(
+            client.global_model(None).await,
+            Err(ClientError::Http(_)),
+        ));
+        assert!(client.global_model_delta(0).await.unwrap().is_none());
+        assert_eq!(
+            client.global_model_metadata().await.unwrap(),
+            Some(global_model_metadata()),
+        );
+
+        client.send_message(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(messages.recv().await.unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_round_summary_not_modified() {
+        // serves the summary in full only if the entity tag doesn't match
+        let served = Arc::new(AtomicUsize::new(0));
+        let served_summaries = served.clone();
+        let summary = warp::path!("rounds" / "current" / "summary")
+            .and(warp::query::<HashMap<String, String>>())
+            .and(warp::header::optional::<String>("if-none-match"))
+            .map(
+                move |query: HashMap<String, String>, if_none_match: Option<String>| {
+                    assert_eq!(query["version"], ROUND_SUMMARY_VERSION.to_string());
+                    if if_none_match.as_deref() == Some("\"1-Sum-v2\"") {
+                        return status(StatusCode::NOT_MODIFIED);
+                    }
+                    served_summaries.fetch_add(1, Ordering::SeqCst);
+                    let mut response = ok(bincode::serialize(&round_summary()).unwrap());
+                    response
+                        .headers_mut()
+                        .insert("etag", "\"1-Sum-v2\"".parse().unwrap());
+                    response
+                },
+            );
+        let (addr, server) = warp::serve(summary).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert_eq!(client.round_summary().await.unwrap(), round_summary());
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+        // the cached summary is returned when it is not modified
+        assert_eq!(client.round_summary().await.unwrap(), round_summary());
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_global_model_of_round() {
+        // serves the global model of round 2 only
+        let model = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        let body = bincode::serialize(&model).unwrap();
+        let models = warp::path!("model")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(move |query: HashMap<String, String>| {
+                match query.get("round").map(String::as_str) {
+                    None | Some("2") => ok(body.clone()),
+                    Some(_) => status(StatusCode::NO_CONTENT),
+                }
+            });
+        let (addr, server) = warp::serve(models).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert_eq!(
+            client.global_model(None).await.unwrap(),
+            Some(model.clone())
+        );
+        assert_eq!(client.global_model(Some(2)).await.unwrap(), Some(model));
+        assert_eq!(client.global_model(Some(1)).await.unwrap(), None);
+    }
+
+    /// Spawns a coordinator API whose global model changes with every request of its metadata,
+    /// but which serves the global model of the `served_round` only.
+    fn serve_changing_global_model(served_round: u64, model: &Model) -> SocketAddr {
+        let round_id = Arc::new(AtomicUsize::new(0));
+        let model_metadata = warp::path!("model" / "metadata").map(move || {
+            let round_id = round_id.fetch_add(1, Ordering::SeqCst) as u64 + 1;
+            let params = round_params(SelectFor::Sum);
+            let metadata =
+                GlobalModelMetadata::new(round_id, params.mask_config, params.model_length, 2);
+            ok(bincode::serialize(&metadata).unwrap())
+        });
+        let body = bincode::serialize(model).unwrap();
+        let models = warp::path!("model")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(move |query: HashMap<String, String>| {
+                if query.get("round") == Some(&served_round.to_string()) {
+                    ok(body.clone())
+                } else {
+                    status(StatusCode::NO_CONTENT)
+                }
+            });
+        let (addr, server) =
+            warp::serve(model_metadata.or(models)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_cached_global_model_changed() {
+        // the global model of round 1 is replaced by the one of round 2 after its metadata was
+        // fetched
+        let model = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        let addr = serve_changing_global_model(2, &model);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        let mut cache = None;
+        assert_eq!(
+            client.cached_global_model(&mut cache).await.unwrap(),
+            Some(model.clone()),
+        );
+        assert_eq!(cache, Some(CachedGlobalModel { round_id: 2, model }));
+    }
+
+    #[tokio::test]
+    async fn test_cached_global_model_changed_twice() {
+        // the global models of round 1 and 2 are replaced after their metadata was fetched
+        let model = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        let addr = serve_changing_global_model(3, &model);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert!(matches!(
+            client.cached_global_model(&mut None).await,
+            Err(ClientError::Other(_)),
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_errors() {
+        // replies with the status code which is requested as round
+        let models = warp::path!("model")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|query: HashMap<String, String>| {
+                let code = query["round"].parse().unwrap();
+                status(StatusCode::from_u16(code).unwrap())
+            });
+        let (addr, server) = warp::serve(models).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        // the coordinator signals errors by status code only
+        assert!(matches!(
+            client.global_model(Some(400)).await,
+            Err(ClientError::Http(_)),
+        ));
+        assert!(matches!(
+            client.global_model(Some(503)).await,
+            Err(ClientError::Http(_)),
+        ));
+        assert!(matches!(
+            client.global_model(Some(202)).await,
+            Err(ClientError::UnexpectedResponse(202)),
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_message_rejections() {
+        // replies with the status code which is sent as message
+        let message = warp::path!("message")
+            .and(warp::post())
+            .and(warp::body::bytes())
+            .map(|body: bytes::Bytes| {
+                let code = std::str::from_utf8(&body).unwrap().parse().unwrap();
+                status(StatusCode::from_u16(code).unwrap())
+            });
+        let (addr, server) = warp::serve(message).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert!(client.send_message(b"200".to_vec()).await.is_ok());
+        assert!(matches!(
+            client.send_message(b"409".to_vec()).await,
+            Err(ClientError::StaleSeedDict),
+        ));
+        assert!(matches!(
+            client.send_message(b"403".to_vec()).await,
+            Err(ClientError::Banned),
+        ));
+        assert!(matches!(
+            client.send_message(b"413".to_vec()).await,
+            Err(ClientError::Rejected(413)),
+        ));
+        // transient failures are not rejections, so that the message is resent
+        for code in &["408", "429", "503"] {
+            assert!(matches!(
+                client.send_message(code.as_bytes().to_vec()).await,
+                Err(ClientError::Http(_)),
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_xaynet_client_uses_typed_requests() {
+        let sum_pk = SumParticipantPublicKey::fill_with(3);
+        let (addr, _messages) = serve(sum_pk, update_seed_dict());
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert_eq!(
+            client.get_round_params().await.unwrap(),
+            round_params(SelectFor::Sum),
+        );
+        assert_eq!(
+            client.get_seeds(sum_pk).await.unwrap(),
+            Some(VersionedSeeds {
+                version: 4,
+                seeds: update_seed_dict(),
+            }),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seed_dict_prefers_exported_blob() {
+        let seeds = update_seed_dict();
+        let mut blob = vec![0; seeds.buffer_length()];
+        seeds.to_bytes(&mut blob);
+        let blobs = warp::path!("blobs" / String).map(move |name: String| {
+            if name == "exported" {
+                ok(blob.clone())
+            } else {
+                status(StatusCode::NOT_FOUND)
+            }
+        });
+        let (blobs_addr, server) = warp::serve(blobs).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        // the inline seeds differ from the exported ones to tell the sources apart
+        let inline = UpdateSeedDict::default();
+        for (name, expected) in vec![("exported", &seeds), ("missing", &inline)] {
+            let location = format!("http://{}/blobs/{}", blobs_addr, name);
+            let inline_seeds = inline.clone();
+            let seeds = warp::path!("seeds")
+                .and(warp::query::<HashMap<String, String>>())
+                .map(move |query: HashMap<String, String>| {
+                    let source = if query.get("location").map(String::as_str) == Some("true") {
+                        SeedDictSource::Location(location.clone())
+                    } else {
+                        SeedDictSource::Inline(inline_seeds.clone())
+                    };
+                    match query.get("version").map(String::as_str) {
+                        Some("true") => ok(bincode::serialize(&VersionedSeeds {
+                            version: 4,
+                            seeds: source,
+                        })
+                        .unwrap()),
+                        _ => match source {
+                            SeedDictSource::Inline(seeds) => {
+                                ok(bincode::serialize(&seeds).unwrap())
+                            }
+                            source => ok(bincode::serialize(&source).unwrap()),
+                        },
+                    }
+                });
+            let (addr, server) = warp::serve(seeds).bind_ephemeral(([127, 0, 0, 1], 0));
+            tokio::spawn(server);
+            let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+            let sum_pk = SumParticipantPublicKey::fill_with(3);
+            assert_eq!(
+                client.seed_dict(&sum_pk).await.unwrap().as_ref(),
+                Some(expected)
+            );
+            assert_eq!(
+                client.versioned_seed_dict(&sum_pk).await.unwrap(),
+                Some(VersionedSeeds {
+                    version: 4,
+                    seeds: expected.clone(),
+                }),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decompresses_responses() {
+        let mut sum_dict = SumDict::new();
+        sum_dict.insert(
+            SumParticipantPublicKey::fill_with(1),
+            SumParticipantEphemeralPublicKey::fill_with(2),
+        );
+        let body = bincode::serialize(&sum_dict).unwrap();
+        // serves the sum dictionary only to clients which accept a compressed response
+        let sums = warp::path!("sums")
+            .and(warp::header::optional::<String>("accept-encoding"))
+            .map(move |accept_encoding: Option<String>| {
+                if !accept_encoding.unwrap_or_default().contains("gzip") {
+                    return status(StatusCode::NOT_ACCEPTABLE);
+                }
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&body).unwrap();
+                let mut response = ok(encoder.finish().unwrap());
+                response
+                    .headers_mut()
+                    .insert("content-encoding", "gzip".parse().unwrap());
+                response
+            });
+        let (addr, server) = warp::serve(sums).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let mut client = CoordinatorClient::from_url(&format!("http://{}", addr)).unwrap();
+
+        assert_eq!(client.sum_dict().await.unwrap(), Some(sum_dict));
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(matches!(
+            CoordinatorClient::from_url("not a url"),
+            Err(ClientError::InvalidBaseUrl(_)),
+        ));
+        let settings = HttpSettings {
+            identity: Some(b"not a certificate".to_vec()),
+            ..HttpSettings::default()
+        };
+        assert!(matches!(
+            CoordinatorClient::from_settings("http://localhost:8081", settings),
+            Err(ClientError::Http(_)),
+        ));
     }
 }
@@ -0,0 +1,83 @@
+//! Local checks of the task a participant is selected for.
+//!
+//! The selection of a participant is a pure function of its secret signing key and of the round
+//! seed and task fractions. These checks let an application find out whether a participant
+//! takes part in a round from a cached [`RoundSummary`], without running the state machine.
+
+use xaynet_core::{
+    common::{RoundSeed, RoundSummary},
+    crypto::{ByteObject, CryptoSuite, SecretSigningKey, Signature},
+};
+
+/// The task a participant is selected for in a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Task {
+    /// The participant is selected for the sum task.
+    Sum,
+    /// The participant is selected for the update task.
+    Update,
+    /// The participant is not selected for any task.
+    None,
+}
+
+/// Gets the bytes to be signed for the `task` in the round with the given crypto `suite` and
+/// `seed`, see [`CryptoSuite::task_signature_payload()`]. For the default suite this is the round
+/// seed concatenated with the task name `b"sum"` or `b"update"`.
+///
+/// This allows to compute the task signatures without access to the secret signing key, e.g.
+/// with a key kept in a hardware security module.
+pub fn task_signature_payload(suite: CryptoSuite, seed: &RoundSeed, task: &[u8]) -> Vec<u8> {
+    suite.task_signature_payload(seed.as_slice(), task)
+}
+
+/// Signs the [`task_signature_payload()`], which results in the signature that determines
+/// whether a participant is eligible for the task.
+pub(crate) fn task_signature(
+    sk: &SecretSigningKey,
+    suite: CryptoSuite,
+    seed: &RoundSeed,
+    task: &[u8],
+) -> Signature {
+    sk.sign_detached(&task_signature_payload(suite, seed, task))
+}
+
+/// Checks which task the participant with the secret key `sk` is selected for, given the crypto
+/// `suite`, the round `seed`, the `sum` and `update` fractions and the eligibility `salt` of the
+/// coordinator.
+///
+/// The sum task takes precedence over the update task. A participant is never selected in a round
+/// with a crypto suite which is not supported by this build.
+pub fn check_task(
+    sk: &SecretSigningKey,
+    suite: CryptoSuite,
+    seed: &RoundSeed,
+    sum: f64,
+    update: f64,
+    salt: &[u8],
+) -> Task {
+    if !suite.is_supported() {
+        Task::None
+    } else if task_signature(sk, suite, seed, b"sum").is_eligible_salted(sum, salt) {
+        Task::Sum
+    } else if task_signature(sk, suite, seed, b"update").is_eligible_salted(update, salt) {
+        Task::Update
+    } else {
+        Task::None
+    }
+}
+
+/// Checks which task the participant with the secret key `sk` is selected for in the round
+/// described by a cached `summary`.
+///
+/// An application can call this with the summary served at `GET /rounds/current/summary` to
+/// decide whether the participant must be woken up to take part in the round.
+pub fn should_wake(sk: &SecretSigningKey, summary: &RoundSummary) -> Task {
+    check_task(
+        sk,
+        summary.crypto_suite,
+        &summary.seed,
+        summary.sum,
+        summary.update,
+        &summary.eligibility_salt,
+    )
+}
@@ -152,7 +152,7 @@
 //! struct Notifier(mpsc::Sender<Event>);
 //!
 //! impl Notify for Notifier {
-//!     fn new_round(&mut self) {
+//!     fn new_round(&mut self, _round_token: &str) {
 //!         self.0.send(Event::NewRound).unwrap();
 //!     }
 //!     fn sum(&mut self) {
@@ -209,7 +209,9 @@
 //! # fn main() {} // don't actually run anything, because the client never terminates
 //! ```
 
+pub mod budget;
 pub mod client;
+pub mod eligibility;
 mod message_encoder;
 pub mod settings;
 mod state_machine;
@@ -217,5 +219,12 @@ mod traits;
 pub(crate) mod utils;
 
 pub(crate) use self::message_encoder::MessageEncoder;
-pub use self::traits::{ModelStore, Notify, XaynetClient};
-pub use state_machine::{LocalModelConfig, SerializableState, StateMachine, TransitionOutcome};
+pub use self::traits::{Failure, ModelStore, Notify, XaynetClient};
+pub use state_machine::{
+    LocalModelConfig,
+    SerializableState,
+    StateFormatError,
+    StateMachine,
+    TransitionOutcome,
+    STATE_FORMAT_VERSION,
+};
@@ -4,7 +4,7 @@ use thiserror::Error;
 use super::Chunker;
 use xaynet_core::{
     crypto::{PublicEncryptKey, SecretSigningKey, SigningKeyPair},
-    message::{Chunk, Message, Payload, Tag, ToBytes},
+    message::{Chunk, Flags, Message, MessageBuffer, Payload, Tag, ToBytes, MESSAGE_HEADER_LENGTH},
 };
 
 /// An encoder for multipart messages. It implements
@@ -27,6 +27,9 @@ pub struct MultipartEncoder {
     payload_size: usize,
     /// A random ID common to all the message chunks.
     message_id: u16,
+    /// Whether the masks of the message are bound to the round seed.
+    #[serde(default)]
+    round_bound_masks: bool,
 }
 
 /// Overhead induced by wrapping the data in [`Payload::Chunk`]
@@ -56,9 +59,11 @@ impl Iterator for MultipartEncoder {
             signature: None,
             participant_pk: self.keys.public,
             is_multipart: true,
+            round_bound_masks: self.round_bound_masks,
             tag: self.tag,
             payload: Payload::Chunk(chunk),
             coordinator_pk: self.coordinator_pk,
+            extensions: Vec::new(),
         };
         let data = serialize_message(&message, &self.keys.secret);
         Some(data)
@@ -99,13 +104,12 @@ pub enum InvalidEncodingInput {
 }
 
 impl MessageEncoder {
-    // NOTE: the only reason we need to consume the payload is because creating the Message
-    // consumes it.
     /// Create a new encoder for the given payload. The `participant`
     /// is used to sign the message(s). If the serialized payload is
     /// larger than `max_payload_size`, the message will we split in
     /// multiple chunks. If `max_payload_size` is `0`, the message
-    /// will not be split.
+    /// will not be split. The `round_bound_masks` flag is set in the
+    /// header of the message(s).
     ///
     /// # Errors
     ///
@@ -117,6 +121,7 @@ impl MessageEncoder {
         payload: Payload,
         coordinator_pk: PublicEncryptKey,
         max_payload_size: usize,
+        round_bound_masks: bool,
     ) -> Result<Self, InvalidEncodingInput> {
         // Reject payloads of type Payload::Chunk. It is the job of the encoder to produce those if
         // the payload is deemed to big to be sent in a single message
@@ -124,49 +129,96 @@ impl MessageEncoder {
             return Err(InvalidEncodingInput::Payload);
         }
 
+        let tag = Self::get_tag_from_payload(&payload);
+        let mut data = vec![0; payload.buffer_length()];
+        payload.to_bytes(&mut data);
+        Self::from_serialized_payload(
+            keys,
+            tag,
+            data,
+            coordinator_pk,
+            max_payload_size,
+            round_bound_masks,
+        )
+    }
+
+    /// Create a new encoder for a payload of the given `tag` which
+    /// is serialized already, e.g. an update payload written by an
+    /// [`UpdateWriter`] without building the local seed dictionary
+    /// first. The other arguments are the same as for
+    /// [`MessageEncoder::new()`].
+    ///
+    /// # Errors
+    ///
+    /// An [`InvalidEncodingInput`] error is returned when
+    /// `max_payload_size` is too small to hold a chunk.
+    ///
+    /// [`UpdateWriter`]: xaynet_core::message::UpdateWriter
+    pub fn from_serialized_payload(
+        keys: SigningKeyPair,
+        tag: Tag,
+        data: Vec<u8>,
+        coordinator_pk: PublicEncryptKey,
+        max_payload_size: usize,
+        round_bound_masks: bool,
+    ) -> Result<Self, InvalidEncodingInput> {
         if max_payload_size != 0 && max_payload_size <= MIN_PAYLOAD_SIZE {
             return Err(InvalidEncodingInput::PayloadSize);
         }
 
-        if max_payload_size != 0 && payload.buffer_length() > max_payload_size {
+        if max_payload_size != 0 && data.len() > max_payload_size {
             Ok(Self::new_multipart(
                 keys,
                 coordinator_pk,
-                payload,
+                tag,
+                data,
                 max_payload_size,
+                round_bound_masks,
             ))
         } else {
-            Ok(Self::new_simple(keys, coordinator_pk, payload))
+            Ok(Self::new_simple(
+                keys,
+                coordinator_pk,
+                tag,
+                &data,
+                round_bound_masks,
+            ))
         }
     }
 
     fn new_simple(
         keys: SigningKeyPair,
         coordinator_pk: PublicEncryptKey,
-        payload: Payload,
+        tag: Tag,
+        payload: &[u8],
+        round_bound_masks: bool,
     ) -> Self {
-        let message = Message {
-            // The signature is computed when serializing the message
-            signature: None,
-            participant_pk: keys.public,
-            is_multipart: false,
-            coordinator_pk,
-            tag: Self::get_tag_from_payload(&payload),
-            payload,
-        };
-        let data = serialize_message(&message, &keys.secret);
+        let length = MESSAGE_HEADER_LENGTH + payload.len();
+        let mut data = vec![0; length];
+        let mut writer = MessageBuffer::new_unchecked(&mut data);
+        writer.set_length(length);
+        writer.set_extensions_length(0);
+        keys.public.to_bytes(&mut writer.participant_pk_mut());
+        coordinator_pk.to_bytes(&mut writer.coordinator_pk_mut());
+        let mut flags = Flags::empty();
+        flags.set(Flags::ROUND_BOUND_MASKS, round_bound_masks);
+        writer.set_flags(flags);
+        writer.set_tag(tag.into());
+        writer.payload_mut().copy_from_slice(payload);
+        // The signature covers the whole message but the signature itself
+        let signature = keys.secret.sign_detached(writer.signed_data_mut());
+        signature.to_bytes(&mut writer.signature_mut());
         Self::Simple(Some(data))
     }
 
     fn new_multipart(
         keys: SigningKeyPair,
         coordinator_pk: PublicEncryptKey,
-        payload: Payload,
+        tag: Tag,
+        data: Vec<u8>,
         payload_size: usize,
+        round_bound_masks: bool,
     ) -> Self {
-        let tag = Self::get_tag_from_payload(&payload);
-        let mut data = vec![0; payload.buffer_length()];
-        payload.to_bytes(&mut data);
         Self::Multipart(MultipartEncoder {
             keys,
             data,
@@ -175,6 +227,7 @@ impl MessageEncoder {
             coordinator_pk,
             payload_size,
             message_id: rand::random::<u16>(),
+            round_bound_masks,
         })
     }
 
@@ -214,9 +267,11 @@ mod tests {
             signature: None,
             participant_pk: participant_keys().public,
             is_multipart: false,
+            round_bound_masks: false,
             tag: Tag::Update,
             payload,
             coordinator_pk: coordinator_keys().public,
+            extensions: Vec::new(),
         }
     }
 
@@ -224,8 +279,8 @@ mod tests {
         let dict_len = 80 + 32 + 4; // 116 => dict with a single entry
         let model_len = 6 + 18; // 24 => masked model with single weight
         let message = message(dict_len, model_len);
-        let payload_len = dict_len + model_len + 64 * 2; // 268
-        let message_len = payload_len + 136; // 404
+        let payload_len = dict_len + model_len + 64 * 2 + 1 + 32 + 3; // 304
+        let message_len = payload_len + 136; // 440
         assert_eq!(message.payload.buffer_length(), payload_len);
         assert_eq!(message.buffer_length(), message_len);
         message
@@ -239,13 +294,15 @@ mod tests {
             participant_keys(),
             msg.clone().payload,
             msg.coordinator_pk,
-            272,
+            305,
+            false,
         )
         .unwrap();
 
         let data = enc.next().unwrap();
         let parsed = Message::from_byte_slice(&data.as_slice()).unwrap();
         assert!(!parsed.is_multipart);
+        assert!(!parsed.round_bound_masks);
         assert_eq!(parsed.payload, msg.payload);
         assert!(enc.next().is_none());
     }
@@ -259,6 +316,7 @@ mod tests {
             msg.clone().payload,
             msg.coordinator_pk,
             200,
+            true,
         )
         .unwrap();
 
@@ -268,31 +326,54 @@ mod tests {
         //
         // 8 of these 200 payload bytes are for the Chunk payload
         // header. So this chunk actually only contains 192 bytes (out
-        // of 268) from the Update payload. So 76 bytes remain.
+        // of 304) from the Update payload. So 112 bytes remain.
         assert_eq!(data.len(), 200 + 136);
         let parsed = Message::from_byte_slice(&data.as_slice()).unwrap();
         assert!(parsed.is_multipart);
+        assert!(parsed.round_bound_masks);
         let chunk1 = extract_chunk(parsed);
         assert!(!chunk1.last);
         assert_eq!(chunk1.id, 0);
         assert_eq!(chunk1.data.len(), 192);
 
         let data = enc.next().unwrap();
-        // The payload should be 76 bytes + 8 bytes of CHUNK_OVERHEAD,
+        // The payload should be 112 bytes + 8 bytes of CHUNK_OVERHEAD,
         // plus 136 byte for the message header
-        assert_eq!(data.len(), 84 + 136);
+        assert_eq!(data.len(), 120 + 136);
         let parsed = Message::from_byte_slice(&data.as_slice()).unwrap();
         assert!(parsed.is_multipart);
         let chunk2 = extract_chunk(parsed);
         assert!(chunk2.last);
         assert_eq!(chunk2.id, 1);
-        assert_eq!(chunk2.data.len(), 76);
+        assert_eq!(chunk2.data.len(), 112);
 
         let payload_data: Vec<u8> = [chunk1.data, chunk2.data].concat();
         let update = Update::from_byte_slice(&payload_data).unwrap();
         assert_eq!(update, extract_update(msg));
     }
 
+    #[test]
+    fn serialized_payload() {
+        let msg = small_message();
+        let mut data = vec![0; msg.payload.buffer_length()];
+        msg.payload.to_bytes(&mut data);
+
+        let mut enc = MessageEncoder::from_serialized_payload(
+            participant_keys(),
+            Tag::Update,
+            data,
+            msg.coordinator_pk,
+            0,
+            false,
+        )
+        .unwrap();
+
+        // The message is the same as if it was serialized from its payload
+        let expected = serialize_message(&msg, &participant_keys().secret);
+        assert_eq!(enc.next().unwrap(), expected);
+        assert!(enc.next().is_none());
+    }
+
     fn extract_chunk(message: Message) -> Chunk {
         if let Payload::Chunk(c) = message.payload {
             c
@@ -1,15 +1,72 @@
 mod max_message_size;
 
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
 pub use max_message_size::{InvalidMaxMessageSize, MaxMessageSize, MIN_MESSAGE_SIZE};
-use xaynet_core::{crypto::SigningKeyPair, mask::Scalar};
+use xaynet_core::{
+    crypto::SigningKeyPair,
+    mask::{MaskConfig, Scalar},
+    CoordinatorPublicKey,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PetSettings {
     pub keys: SigningKeyPair,
     pub scalar: Scalar,
     pub max_message_size: MaxMessageSize,
+    #[serde(default)]
+    pub retry: RetrySettings,
+    /// Whether the ephemeral keys of a sum participant are derived from its signing keys and the
+    /// round seed instead of being generated randomly. Defaults to `false`.
+    ///
+    /// Derived ephemeral keys can be derived again after a restart, such that a sum participant
+    /// doesn't need to persist them between the sum and sum2 phases. However, anyone who learns
+    /// the signing keys can derive the ephemeral keys of all rounds, see
+    /// [`EncryptKeyPair::derive_from_signing_key()`].
+    ///
+    /// [`EncryptKeyPair::derive_from_signing_key()`]: xaynet_core::crypto::EncryptKeyPair::derive_from_signing_key
+    #[serde(default)]
+    pub deterministic_ephm_keys: bool,
+    /// The masking configuration the participant expects from the coordinator. Defaults to
+    /// `None`, i.e. any masking configuration is accepted.
+    ///
+    /// If set, the participant doesn't take part in rounds with a different masking
+    /// configuration.
+    #[serde(default)]
+    pub expected_mask_config: Option<MaskConfig>,
+    /// The masking configurations the participant supports. Defaults to `None`, i.e. any masking
+    /// configuration is supported.
+    ///
+    /// If set, the participant doesn't take part in rounds with an unsupported masking
+    /// configuration and notifies the application instead, see
+    /// [`Notify::unsupported_mask_config()`]. [`MaskConfig::catalogue()`] gets all catalogued
+    /// configurations for the data type of the local model.
+    ///
+    /// [`Notify::unsupported_mask_config()`]: crate::Notify::unsupported_mask_config
+    #[serde(default)]
+    pub supported_mask_configs: Option<Vec<MaskConfig>>,
+    /// The bounds on the computations of the participant. Defaults to an unbounded budget.
+    ///
+    /// The participant skips the update task of rounds where masking the local model is
+    /// estimated to exceed the budget, and aborts the sum2 task once deriving the masks
+    /// exceeded the budget, see [`Notify::budget_exceeded()`].
+    ///
+    /// [`Notify::budget_exceeded()`]: crate::Notify::budget_exceeded
+    #[serde(default)]
+    pub compute_budget: ComputeBudget,
+    /// The public key the coordinator is expected to publish in the round parameters. Defaults
+    /// to `None`, i.e. any key is accepted.
+    ///
+    /// If set, the participant doesn't take part in rounds with another key, which guards
+    /// against a man in the middle who serves round parameters with a substituted key, and
+    /// notifies the application instead, see [`Failure::UntrustedCoordinatorKey`]. The pin only
+    /// fits a coordinator which publishes the same key in every round.
+    ///
+    /// [`Failure::UntrustedCoordinatorKey`]: crate::Failure::UntrustedCoordinatorKey
+    #[serde(default)]
+    pub pinned_coordinator_pk: Option<CoordinatorPublicKey>,
 }
 
 impl PetSettings {
@@ -18,6 +75,77 @@ impl PetSettings {
             keys,
             scalar: Scalar::unit(),
             max_message_size: MaxMessageSize::default(),
+            retry: RetrySettings::default(),
+            deterministic_ephm_keys: false,
+            expected_mask_config: None,
+            supported_mask_configs: None,
+            compute_budget: ComputeBudget::default(),
+            pinned_coordinator_pk: None,
         }
     }
 }
+
+/// Settings for resending messages which couldn't be sent because of a transient failure, like
+/// a network error.
+///
+/// The delay between two attempts starts at `initial_backoff` and doubles after each failed
+/// attempt, up to `max_backoff`. A message that still couldn't be sent `deadline` after the
+/// first failure is dropped.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetrySettings {
+    /// The delay before the first retry.
+    pub initial_backoff: Duration,
+    /// The maximum delay between two retries.
+    pub max_backoff: Duration,
+    /// The total time after the first failure after which a message is dropped.
+    pub deadline: Duration,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            deadline: Duration::from_secs(600),
+        }
+    }
+}
+
+impl RetrySettings {
+    /// Gets the delay before the next attempt, after `attempts` failed attempts.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// Bounds on the time the participant spends on the costly computations of a round.
+///
+/// A bound of `None` doesn't bound the computation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// The maximum time for masking the local model, in milliseconds.
+    ///
+    /// The time is estimated before the participant takes part in the update task, see
+    /// [`Calibration`].
+    ///
+    /// [`Calibration`]: crate::budget::Calibration
+    pub max_masking_millis: Option<u64>,
+    /// The maximum time for deriving and aggregating the masks in the sum2 task, in
+    /// milliseconds.
+    pub max_sum2_millis: Option<u64>,
+}
+
+impl ComputeBudget {
+    /// Gets the maximum time for masking the local model, if bounded.
+    pub fn max_masking(&self) -> Option<Duration> {
+        self.max_masking_millis.map(Duration::from_millis)
+    }
+
+    /// Gets the maximum time for the sum2 masks, if bounded.
+    pub fn max_sum2(&self) -> Option<Duration> {
+        self.max_sum2_millis.map(Duration::from_millis)
+    }
+}
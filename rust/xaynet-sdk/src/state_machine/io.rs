@@ -3,14 +3,15 @@ use std::error::Error;
 use async_trait::async_trait;
 
 use xaynet_core::{
-    common::RoundParameters,
-    mask::Model,
-    SumDict,
+    common::{RoundParameters, RoundSubmissionStatus, RoundSumDict},
+    crypto::CryptoSuite,
+    mask::{MaskConfig, Model},
+    ParticipantPublicKey,
     SumParticipantPublicKey,
-    UpdateSeedDict,
+    VersionedSeeds,
 };
 
-use crate::{ModelStore, Notify, XaynetClient};
+use crate::{budget::BudgetExceeded, Failure, ModelStore, Notify, XaynetClient};
 
 /// Returned a dynamically dispatched [`IO`] object
 pub(crate) fn boxed_io<X, M, N>(
@@ -52,20 +53,26 @@ pub(crate) trait IO: Send + 'static {
 
     /// Fetch the round parameters from the coordinator
     async fn get_round_params(&mut self) -> Result<RoundParameters, Box<dyn Error>>;
-    /// Fetch the sum dictionary from the coordinator
-    async fn get_sums(&mut self) -> Result<Option<SumDict>, Box<dyn Error>>;
-    /// Fetch the seed dictionary for the given sum participant from the coordinator
+    /// Fetch the sum dictionary of the current round from the coordinator
+    async fn get_sums(&mut self) -> Result<Option<RoundSumDict>, Box<dyn Error>>;
+    /// Fetch the versioned seed dictionary for the given sum participant from the coordinator
     async fn get_seeds(
         &mut self,
         pk: SumParticipantPublicKey,
-    ) -> Result<Option<UpdateSeedDict>, Box<dyn Error>>;
+    ) -> Result<Option<VersionedSeeds>, Box<dyn Error>>;
     /// Fetch the latest global model from the coordinator
     async fn get_model(&mut self) -> Result<Option<Model>, Box<dyn Error>>;
+    /// Fetch the submission status of the given participant in the current round from the
+    /// coordinator
+    async fn get_submission_status(
+        &mut self,
+        pk: ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, Box<dyn Error>>;
     /// Send the given signed and encrypted PET message to the coordinator
     async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Box<dyn Error>>;
 
-    /// Notify the participant that a new round started
-    fn notify_new_round(&mut self);
+    /// Notify the participant that a new round with the given round token started
+    fn notify_new_round(&mut self, round_token: &str);
     /// Notify the participant that they have been selected for the sum task for the current
     /// round
     fn notify_sum(&mut self);
@@ -78,6 +85,23 @@ pub(crate) trait IO: Send + 'static {
     /// Notify the participant that is is expected to provide a model to the state
     /// machine by loading it into the store
     fn notify_load_model(&mut self);
+    /// Notify the participant that its update message has been sent
+    fn notify_update_sent(&mut self);
+    /// Notify the participant that its sum2 message has been sent
+    fn notify_sum2_sent(&mut self);
+    /// Notify the participant that it dropped out of the current round
+    fn notify_failed(&mut self, failure: Failure);
+    /// Notify the participant that the masking configuration of the current round is not
+    /// supported
+    fn notify_unsupported_mask_config(
+        &mut self,
+        advertised: MaskConfig,
+        supported: &[MaskConfig],
+    );
+    /// Notify the participant that a computation exceeds its compute budget
+    fn notify_budget_exceeded(&mut self, exceeded: BudgetExceeded);
+    /// Notify the participant that the crypto suite of the current round is not supported
+    fn notify_unsupported_crypto_suite(&mut self, suite: CryptoSuite);
 }
 
 /// Internal struct that implements the [`IO`] trait. It is not used as is in the state
@@ -123,7 +147,7 @@ where
             .map_err(|e| Box::new(e) as Box<dyn Error>)
     }
 
-    async fn get_sums(&mut self) -> Result<Option<SumDict>, Box<dyn Error>> {
+    async fn get_sums(&mut self) -> Result<Option<RoundSumDict>, Box<dyn Error>> {
         self.xaynet_client
             .get_sums()
             .await
@@ -133,7 +157,7 @@ where
     async fn get_seeds(
         &mut self,
         pk: SumParticipantPublicKey,
-    ) -> Result<Option<UpdateSeedDict>, Box<dyn Error>> {
+    ) -> Result<Option<VersionedSeeds>, Box<dyn Error>> {
         self.xaynet_client
             .get_seeds(pk)
             .await
@@ -147,6 +171,16 @@ where
             .map_err(|e| Box::new(e) as Box<dyn Error>)
     }
 
+    async fn get_submission_status(
+        &mut self,
+        pk: ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, Box<dyn Error>> {
+        self.xaynet_client
+            .get_submission_status(pk)
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+
     async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Box<dyn Error>> {
         self.xaynet_client
             .send_message(msg)
@@ -154,8 +188,8 @@ where
             .map_err(|e| Box::new(e) as Box<dyn Error>)
     }
 
-    fn notify_new_round(&mut self) {
-        self.notifier.new_round()
+    fn notify_new_round(&mut self, round_token: &str) {
+        self.notifier.new_round(round_token)
     }
 
     fn notify_sum(&mut self) {
@@ -173,6 +207,34 @@ where
     fn notify_load_model(&mut self) {
         self.notifier.load_model()
     }
+
+    fn notify_update_sent(&mut self) {
+        self.notifier.update_sent()
+    }
+
+    fn notify_sum2_sent(&mut self) {
+        self.notifier.sum2_sent()
+    }
+
+    fn notify_failed(&mut self, failure: Failure) {
+        self.notifier.failed(failure)
+    }
+
+    fn notify_unsupported_mask_config(
+        &mut self,
+        advertised: MaskConfig,
+        supported: &[MaskConfig],
+    ) {
+        self.notifier.unsupported_mask_config(advertised, supported)
+    }
+
+    fn notify_budget_exceeded(&mut self, exceeded: BudgetExceeded) {
+        self.notifier.budget_exceeded(exceeded)
+    }
+
+    fn notify_unsupported_crypto_suite(&mut self, suite: CryptoSuite) {
+        self.notifier.unsupported_crypto_suite(suite)
+    }
 }
 
 #[async_trait]
@@ -187,14 +249,14 @@ impl IO for Box<dyn IO<Model = Box<dyn AsRef<Model> + Send>>> {
         self.as_mut().get_round_params().await
     }
 
-    async fn get_sums(&mut self) -> Result<Option<SumDict>, Box<dyn Error>> {
+    async fn get_sums(&mut self) -> Result<Option<RoundSumDict>, Box<dyn Error>> {
         self.as_mut().get_sums().await
     }
 
     async fn get_seeds(
         &mut self,
         pk: SumParticipantPublicKey,
-    ) -> Result<Option<UpdateSeedDict>, Box<dyn Error>> {
+    ) -> Result<Option<VersionedSeeds>, Box<dyn Error>> {
         self.as_mut().get_seeds(pk).await
     }
 
@@ -202,12 +264,19 @@ impl IO for Box<dyn IO<Model = Box<dyn AsRef<Model> + Send>>> {
         self.as_mut().get_model().await
     }
 
+    async fn get_submission_status(
+        &mut self,
+        pk: ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, Box<dyn Error>> {
+        self.as_mut().get_submission_status(pk).await
+    }
+
     async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Box<dyn Error>> {
         self.as_mut().send_message(msg).await
     }
 
-    fn notify_new_round(&mut self) {
-        self.as_mut().notify_new_round()
+    fn notify_new_round(&mut self, round_token: &str) {
+        self.as_mut().notify_new_round(round_token)
     }
 
     fn notify_sum(&mut self) {
@@ -225,4 +294,32 @@ impl IO for Box<dyn IO<Model = Box<dyn AsRef<Model> + Send>>> {
     fn notify_load_model(&mut self) {
         self.as_mut().notify_load_model()
     }
+
+    fn notify_update_sent(&mut self) {
+        self.as_mut().notify_update_sent()
+    }
+
+    fn notify_sum2_sent(&mut self) {
+        self.as_mut().notify_sum2_sent()
+    }
+
+    fn notify_failed(&mut self, failure: Failure) {
+        self.as_mut().notify_failed(failure)
+    }
+
+    fn notify_unsupported_mask_config(
+        &mut self,
+        advertised: MaskConfig,
+        supported: &[MaskConfig],
+    ) {
+        self.as_mut().notify_unsupported_mask_config(advertised, supported)
+    }
+
+    fn notify_budget_exceeded(&mut self, exceeded: BudgetExceeded) {
+        self.as_mut().notify_budget_exceeded(exceeded)
+    }
+
+    fn notify_unsupported_crypto_suite(&mut self, suite: CryptoSuite) {
+        self.as_mut().notify_unsupported_crypto_suite(suite)
+    }
 }
@@ -14,11 +14,21 @@ use self::io::MockIO;
 use self::{
     io::{boxed_io, IO},
     phase::{IntoPhase, Phase, PhaseIo, Progress, SharedState, State, Step},
-    phases::{Awaiting, NewRound, SendingSum, SendingSum2, SendingUpdate, Sum, Sum2, Update},
+    phases::{
+        Awaiting,
+        Banned,
+        NewRound,
+        SendingSum,
+        SendingSum2,
+        SendingUpdate,
+        Sum,
+        Sum2,
+        Update,
+    },
 };
 
 pub use self::{
-    phase::{LocalModelConfig, SerializableState},
+    phase::{LocalModelConfig, SerializableState, StateFormatError, STATE_FORMAT_VERSION},
     state_machine::{StateMachine, TransitionOutcome},
 };
 
@@ -4,17 +4,31 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
 
-use super::{Awaiting, NewRound, SendingSum, SendingSum2, SendingUpdate, Sum, Sum2, Update, IO};
+use super::{
+    Awaiting,
+    Banned,
+    NewRound,
+    SendingSum,
+    SendingSum2,
+    SendingUpdate,
+    Sum,
+    Sum2,
+    Update,
+    IO,
+};
 use crate::{
-    settings::{MaxMessageSize, PetSettings},
+    budget::{Calibration, ComputeCosts},
+    settings::{ComputeBudget, MaxMessageSize, PetSettings, RetrySettings},
     state_machine::{StateMachine, TransitionOutcome},
+    Failure,
     MessageEncoder,
 };
 use xaynet_core::{
-    common::{RoundParameters, RoundSeed},
-    crypto::{ByteObject, PublicEncryptKey, SigningKeyPair},
+    common::{PhaseTimeline, RoundParameters, RoundSeed},
+    crypto::{ByteObject, CryptoSuite, PublicEncryptKey, SigningKeyPair},
     mask::{self, DataType, MaskConfig, Model, Scalar},
-    message::Payload,
+    message::{Payload, Tag},
+    CoordinatorPublicKey,
 };
 
 /// State of the state machine
@@ -70,6 +84,33 @@ pub struct SharedState {
     pub message_size: MaxMessageSize,
     /// Current round parameters
     pub round_params: RoundParameters,
+    /// Settings for resending messages after transient failures
+    #[serde(default)]
+    pub retry: RetrySettings,
+    /// Whether the ephemeral keys of a sum participant are derived instead of generated
+    #[serde(default)]
+    pub deterministic_ephm_keys: bool,
+    /// The masking configuration the participant expects, if any
+    #[serde(default)]
+    pub expected_mask_config: Option<MaskConfig>,
+    /// The masking configurations the participant supports, if restricted
+    #[serde(default)]
+    pub supported_mask_configs: Option<Vec<MaskConfig>>,
+    /// Bounds on the computations of the participant
+    #[serde(default)]
+    pub compute_budget: ComputeBudget,
+    /// The calibrated cost of masking, if the participant ran the benchmark already
+    #[serde(default)]
+    pub calibration: Option<Calibration>,
+    /// The actual costs of the computations in the latest rounds
+    #[serde(default)]
+    pub last_costs: ComputeCosts,
+    /// The ephemeral public key of the latest sum task and the seed of its round, if any
+    #[serde(default)]
+    pub last_ephm_pk: Option<(RoundSeed, PublicEncryptKey)>,
+    /// The public key the coordinator is expected to publish, if pinned
+    #[serde(default)]
+    pub pinned_coordinator_pk: Option<CoordinatorPublicKey>,
 }
 
 /// Get arbitrary round parameters. These round parameters are never used, we just
@@ -90,6 +131,12 @@ fn dummy_round_parameters() -> RoundParameters {
         }
         .into(),
         model_length: 0,
+        next_commitment: None,
+        round_bound_masks: false,
+        eligibility_salt: Vec::new(),
+        allow_sum_eligible_updates: false,
+        timeline: None,
+        crypto_suite: CryptoSuite::SHA256,
     }
 }
 
@@ -100,6 +147,29 @@ impl SharedState {
             scalar: settings.scalar,
             message_size: settings.max_message_size,
             round_params: dummy_round_parameters(),
+            retry: settings.retry,
+            deterministic_ephm_keys: settings.deterministic_ephm_keys,
+            expected_mask_config: settings.expected_mask_config,
+            supported_mask_configs: settings.supported_mask_configs,
+            compute_budget: settings.compute_budget,
+            calibration: None,
+            last_costs: ComputeCosts::default(),
+            last_ephm_pk: None,
+            pinned_coordinator_pk: settings.pinned_coordinator_pk,
+        }
+    }
+
+    /// Gets the masking configurations the participant supports, or `None` if it supports any.
+    ///
+    /// An expected masking configuration restricts the supported configurations to itself.
+    pub fn supported_mask_configs(&self) -> Option<Vec<MaskConfig>> {
+        match (&self.supported_mask_configs, self.expected_mask_config) {
+            (Some(supported), Some(expected)) => {
+                Some(supported.iter().copied().filter(|c| *c == expected).collect())
+            }
+            (Some(supported), None) => Some(supported.clone()),
+            (None, Some(expected)) => Some(vec![expected]),
+            (None, None) => None,
         }
     }
 }
@@ -113,6 +183,10 @@ pub trait Step {
     /// `TransitionOutcome::Complete`. If no progress can be made, the state machine is
     /// returned unchanged as `TransitionOutcome::Pending`.
     async fn step(mut self) -> TransitionOutcome;
+
+    /// Drops the pending work of the phase when a new round started, e.g. a message which is
+    /// waiting to be resent. Does nothing by default.
+    fn drop_outdated(&mut self) {}
 }
 
 #[macro_export]
@@ -161,8 +235,13 @@ where
         match self.check_round_freshness().await {
             RoundFreshness::Unknown => TransitionOutcome::Pending(self.into()),
             RoundFreshness::Outdated => {
-                info!("a new round started: updating the round parameters and resetting the state machine");
-                self.io.notify_new_round();
+                let round_token = self.state.shared.round_params.seed.token();
+                info!(
+                    "a new round {} started: updating the round parameters and resetting the state machine",
+                    round_token,
+                );
+                self.drop_outdated();
+                self.io.notify_new_round(&round_token);
                 TransitionOutcome::Complete(
                     Phase::<NewRound>::new(
                         State::new(self.state.shared, Box::new(NewRound)),
@@ -175,6 +254,19 @@ where
                 debug!("round is still fresh, continuing from where we left off");
                 <Self as Step>::step(self).await
             }
+            RoundFreshness::Uncommitted => {
+                error!(
+                    "a new round {} started, but its parameters don't match the commitment of the coordinator: going to sleep until next round",
+                    self.state.shared.round_params.seed.token(),
+                );
+                self.drop_outdated();
+                self.io.notify_failed(Failure::UncommittedRoundParameters);
+                TransitionOutcome::Complete(
+                    State::new(self.state.shared, Box::new(Awaiting))
+                        .into_phase(self.io)
+                        .into(),
+                )
+            }
         }
     }
 
@@ -192,8 +284,19 @@ where
                     RoundFreshness::Fresh
                 } else {
                     info!("fetched fresh round parameters");
+                    let is_committed = self
+                        .state
+                        .shared
+                        .round_params
+                        .next_commitment
+                        .map(|commitment| params.verify_commitment(&commitment))
+                        .unwrap_or(true);
                     self.state.shared.round_params = params;
-                    RoundFreshness::Outdated
+                    if is_committed {
+                        RoundFreshness::Outdated
+                    } else {
+                        RoundFreshness::Uncommitted
+                    }
                 }
             }
         }
@@ -232,6 +335,7 @@ impl<P> Phase<P> {
                 .message_size
                 .max_payload_size()
                 .unwrap_or(0),
+            self.state.shared.round_params.round_bound_masks,
         )
         // the encoder rejects Chunk payload, but in the state
         // machine, we never manually create such payloads so
@@ -239,6 +343,31 @@ impl<P> Phase<P> {
         .unwrap()
     }
 
+    /// Instantiate a message encoder for the given payload of type `tag`, which is serialized
+    /// already.
+    pub fn serialized_message_encoder(&self, tag: Tag, payload: Vec<u8>) -> MessageEncoder {
+        MessageEncoder::from_serialized_payload(
+            self.state.shared.keys.clone(),
+            tag,
+            payload,
+            self.state.shared.round_params.pk,
+            self.state
+                .shared
+                .message_size
+                .max_payload_size()
+                .unwrap_or(0),
+            self.state.shared.round_params.round_bound_masks,
+        )
+        // the max payload size is validated by the settings, so
+        // unwrapping is fine
+        .unwrap()
+    }
+
+    /// Return the masking configuration advertised by the coordinator for the current round.
+    pub fn mask_config(&self) -> MaskConfig {
+        self.state.shared.round_params.mask_config.vect
+    }
+
     /// Return the local model configuration of the model that is expected in the update phase.
     pub fn local_model_config(&self) -> LocalModelConfig {
         LocalModelConfig {
@@ -247,6 +376,21 @@ impl<P> Phase<P> {
         }
     }
 
+    /// Return the timeline of the phases of the current round, if the coordinator publishes it.
+    pub fn phase_timeline(&self) -> Option<PhaseTimeline> {
+        self.state.shared.round_params.timeline
+    }
+
+    /// Return the calibrated cost of masking, if the participant ran the calibration already.
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.state.shared.calibration
+    }
+
+    /// Return the actual costs of the computations in the latest rounds.
+    pub fn last_costs(&self) -> ComputeCosts {
+        self.state.shared.last_costs
+    }
+
     #[cfg(test)]
     pub(crate) fn with_io_mock<F>(&mut self, f: F)
     where
@@ -290,6 +434,9 @@ pub enum RoundFreshness {
     Unknown,
     /// The current round is still going
     Fresh,
+    /// A new round started, but its parameters don't match the
+    /// commitment of the coordinator in the previous round
+    Uncommitted,
 }
 
 /// A serializable representation of a phase state.
@@ -310,6 +457,7 @@ pub enum SerializableState {
     SendingSum(State<SendingSum>),
     SendingUpdate(State<SendingUpdate>),
     SendingSum2(State<SendingSum2>),
+    Banned(State<Banned>),
 }
 
 impl<P> From<Phase<P>> for SerializableState
@@ -320,3 +468,53 @@ where
         phase.state.into()
     }
 }
+
+/// The magic bytes which prefix a [`SerializableState`] of a versioned serialization format.
+const STATE_MAGIC: &[u8] = b"XNST";
+
+/// The version of the serialization format of [`SerializableState::to_bytes()`].
+///
+/// Version 1 is the bare bincode serialization of a [`SerializableState`], as saved before the
+/// format got versioned. Since version 2, the bincode serialization is prefixed by magic bytes and
+/// the version.
+pub const STATE_FORMAT_VERSION: u8 = 2;
+
+/// Error that can occur when deserializing a [`SerializableState`].
+#[derive(Error, Debug)]
+pub enum StateFormatError {
+    #[error("unsupported version {0} of the state format")]
+    UnsupportedVersion(u8),
+    #[error("invalid state: {0}")]
+    Invalid(#[from] bincode::Error),
+}
+
+impl SerializableState {
+    /// Serializes the state in the current [`STATE_FORMAT_VERSION`].
+    ///
+    /// The state only holds the data of the current round: the data of a task is dropped once
+    /// the participant is awaiting a new round. Hence, the size of the serialized state doesn't
+    /// grow with the number of rounds the participant took part in.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.push(STATE_FORMAT_VERSION);
+        // UNWRAP_SAFE: serializing into a vector only fails for unsupported types
+        bincode::serialize_into(&mut bytes, self).unwrap();
+        bytes
+    }
+
+    /// Deserializes a state which was serialized in the current or an older format version.
+    ///
+    /// # Errors
+    /// Fails if the format version is unsupported or if the state is invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StateFormatError> {
+        match bytes
+            .strip_prefix(STATE_MAGIC)
+            .and_then(|bytes| bytes.split_first())
+        {
+            Some((&STATE_FORMAT_VERSION, state)) => Ok(bincode::deserialize(state)?),
+            Some((&version, _)) => Err(StateFormatError::UnsupportedVersion(version)),
+            // version 1 states start with the bincode serialized variant index instead
+            None => Ok(bincode::deserialize(bytes)?),
+        }
+    }
+}
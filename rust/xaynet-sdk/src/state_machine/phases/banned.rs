@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::state_machine::{IntoPhase, Phase, PhaseIo, State, Step, TransitionOutcome};
+
+/// The state of a participant which has been banned by the coordinator.
+///
+/// The participant doesn't take part in any round anymore, hence the phase neither checks for
+/// new rounds nor transitions to another phase.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Banned;
+
+#[async_trait]
+impl Step for Phase<Banned> {
+    async fn step(mut self) -> TransitionOutcome {
+        info!("participant is banned");
+        TransitionOutcome::Pending(self.into())
+    }
+}
+
+impl IntoPhase<Banned> for State<Banned> {
+    fn into_phase(self, io: PhaseIo) -> Phase<Banned> {
+        Phase::<_>::new(self, io)
+    }
+}
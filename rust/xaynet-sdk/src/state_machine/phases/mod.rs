@@ -1,12 +1,16 @@
 mod awaiting;
+mod banned;
 mod new_round;
 mod sending;
 mod sum;
 mod sum2;
 mod update;
 
+#[cfg(test)]
+pub use self::update::UpdateValidationError;
 pub use self::{
     awaiting::Awaiting,
+    banned::Banned,
     new_round::NewRound,
     sending::{SendingSum, SendingSum2, SendingUpdate},
     sum::Sum,
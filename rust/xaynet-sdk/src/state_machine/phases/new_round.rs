@@ -1,18 +1,23 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tracing::info;
-use xaynet_core::crypto::{ByteObject, Signature};
-
-use crate::state_machine::{
-    Awaiting,
-    IntoPhase,
-    Phase,
-    PhaseIo,
-    State,
-    Step,
-    Sum,
-    TransitionOutcome,
-    Update,
+use tracing::{debug, error, info, warn};
+use xaynet_core::crypto::{ByteObject, EncryptKeyPair, Signature};
+
+use crate::{
+    budget::{BudgetExceeded, Calibration, ComputeTask},
+    eligibility::task_signature,
+    state_machine::{
+        Awaiting,
+        IntoPhase,
+        Phase,
+        PhaseIo,
+        State,
+        Step,
+        Sum,
+        TransitionOutcome,
+        Update,
+    },
+    Failure,
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -20,7 +25,7 @@ pub struct NewRound;
 
 impl IntoPhase<NewRound> for State<NewRound> {
     fn into_phase(self, mut io: PhaseIo) -> Phase<NewRound> {
-        io.notify_new_round();
+        io.notify_new_round(&self.shared.round_params.seed.token());
         Phase::<_>::new(self, io)
     }
 }
@@ -30,17 +35,61 @@ impl Step for Phase<NewRound> {
     async fn step(mut self) -> TransitionOutcome {
         info!("new_round task");
 
+        if let Some(pinned) = self.state.shared.pinned_coordinator_pk {
+            if pinned != self.state.shared.round_params.pk {
+                error!("coordinator public key doesn't match the pinned key, going to sleep until next round");
+                self.io.notify_failed(Failure::UntrustedCoordinatorKey);
+                let awaiting: Phase<Awaiting> = self.into();
+                return TransitionOutcome::Complete(awaiting.into());
+            }
+        }
+
+        let suite = self.state.shared.round_params.crypto_suite;
+        if !suite.is_supported() {
+            warn!(
+                "unsupported crypto suite {}, going to sleep until next round",
+                suite
+            );
+            self.io.notify_unsupported_crypto_suite(suite);
+            let awaiting: Phase<Awaiting> = self.into();
+            return TransitionOutcome::Complete(awaiting.into());
+        }
+
+        if let Some(supported) = self.state.shared.supported_mask_configs() {
+            let advertised = self.state.shared.round_params.mask_config.vect;
+            if !supported.contains(&advertised) {
+                warn!(
+                    "unsupported masking configuration {:?}, going to sleep until next round",
+                    advertised
+                );
+                self.io.notify_unsupported_mask_config(advertised, &supported);
+                let awaiting: Phase<Awaiting> = self.into();
+                return TransitionOutcome::Complete(awaiting.into());
+            }
+        }
+
         info!("checking eligibility for sum task");
+        let round_params = &self.state.shared.round_params;
         let sum_signature = self.sign(b"sum");
-        if sum_signature.is_eligible(self.state.shared.round_params.sum) {
+        if sum_signature.is_eligible_salted(round_params.sum, &round_params.eligibility_salt) {
             info!("eligible for sum task");
             return TransitionOutcome::Complete(self.into_sum(sum_signature).into());
         }
 
         info!("not eligible for sum task, checking eligibility for update task");
         let update_signature = self.sign(b"update");
-        if update_signature.is_eligible(self.state.shared.round_params.update) {
+        if update_signature.is_eligible_salted(round_params.update, &round_params.eligibility_salt)
+        {
             info!("eligible for update task");
+            if let Err(exceeded) = self.check_masking_budget() {
+                warn!(
+                    "masking is estimated to take {:?}, which exceeds the budget of {:?}, going to sleep until next round",
+                    exceeded.cost, exceeded.budget
+                );
+                self.io.notify_budget_exceeded(exceeded);
+                let awaiting: Phase<Awaiting> = self.into();
+                return TransitionOutcome::Complete(awaiting.into());
+            }
             return TransitionOutcome::Complete(
                 self.into_update(sum_signature, update_signature).into(),
             );
@@ -60,13 +109,60 @@ impl From<Phase<NewRound>> for Phase<Awaiting> {
 
 impl Phase<NewRound> {
     fn sign(&self, data: &[u8]) -> Signature {
-        let sk = &self.state.shared.keys.secret;
-        let seed = self.state.shared.round_params.seed.as_slice();
-        sk.sign_detached(&[seed, data].concat())
+        task_signature(
+            &self.state.shared.keys.secret,
+            self.state.shared.round_params.crypto_suite,
+            &self.state.shared.round_params.seed,
+            data,
+        )
+    }
+
+    /// Checks whether the estimated cost of masking the local model fits into the compute budget.
+    ///
+    /// The cost is estimated from a calibration of the masking configuration of the round, which
+    /// is cached in the shared state and only runs again if the configuration changes.
+    fn check_masking_budget(&mut self) -> Result<(), BudgetExceeded> {
+        let shared = &mut self.state.shared;
+        let budget = match shared.compute_budget.max_masking() {
+            Some(budget) => budget,
+            None => return Ok(()),
+        };
+        let mask_config = shared.round_params.mask_config;
+        let calibration = match shared.calibration {
+            Some(calibration) if calibration.mask_config == mask_config => calibration,
+            _ => {
+                debug!("calibrating the cost of masking");
+                let calibration = Calibration::run(mask_config);
+                shared.calibration = Some(calibration);
+                calibration
+            }
+        };
+        let cost = calibration.masking_cost(shared.round_params.model_length);
+        if cost > budget {
+            Err(BudgetExceeded {
+                task: ComputeTask::Masking,
+                cost,
+                budget,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Gets the ephemeral keys for the sum task, which are either derived from the signing keys
+    /// and the round seed or generated randomly.
+    fn ephm_keys(&self) -> EncryptKeyPair {
+        let shared = &self.state.shared;
+        if shared.deterministic_ephm_keys {
+            let seed = shared.round_params.seed.as_slice();
+            EncryptKeyPair::derive_from_signing_key(&shared.keys.secret, seed)
+        } else {
+            EncryptKeyPair::generate()
+        }
     }
 
     fn into_sum(self, sum_signature: Signature) -> Phase<Sum> {
-        let sum = Box::new(Sum::new(sum_signature));
+        let sum = Box::new(Sum::new(sum_signature, self.ephm_keys()));
         let state = State::new(self.state.shared, sum);
         state.into_phase(self.io)
     }
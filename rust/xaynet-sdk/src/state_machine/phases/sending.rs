@@ -1,12 +1,17 @@
+use std::{error::Error, time::SystemTime};
+
 use async_trait::async_trait;
 use paste::paste;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
+    client::ClientError,
+    settings::RetrySettings,
     state_machine::{
         phases::Sum2,
         Awaiting,
+        Banned,
         IntoPhase,
         Phase,
         PhaseIo,
@@ -16,12 +21,98 @@ use crate::{
         TransitionOutcome,
         IO,
     },
+    Failure,
     MessageEncoder,
 };
 
+/// Checks whether the coordinator rejected a message because it was derived from an outdated
+/// seed dictionary.
+fn is_stale_seed_dict(e: &(dyn Error + 'static)) -> bool {
+    matches!(
+        e.downcast_ref::<ClientError>(),
+        Some(ClientError::StaleSeedDict)
+    )
+}
+
+/// Checks whether the coordinator rejected a message because the participant is banned.
+fn is_banned(e: &(dyn Error + 'static)) -> bool {
+    matches!(e.downcast_ref::<ClientError>(), Some(ClientError::Banned))
+}
+
+/// Checks whether the coordinator rejected a message for good, such that resending it is
+/// pointless.
+fn is_permanent_rejection(e: &(dyn Error + 'static)) -> bool {
+    matches!(
+        e.downcast_ref::<ClientError>(),
+        Some(ClientError::Rejected(_))
+    )
+}
+
+/// Bookkeeping of the failed attempts to send a message chunk.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct Retries {
+    /// Number of failed attempts.
+    attempts: u32,
+    /// Time of the first failed attempt.
+    first_failure: SystemTime,
+    /// Earliest time of the next attempt.
+    next_attempt: SystemTime,
+}
+
+impl Retries {
+    /// Records the first failed attempt.
+    fn new(settings: &RetrySettings) -> Self {
+        let now = SystemTime::now();
+        Self {
+            attempts: 1,
+            first_failure: now,
+            next_attempt: now + settings.backoff(1),
+        }
+    }
+
+    /// Records another failed attempt.
+    fn record_failure(&mut self, settings: &RetrySettings) {
+        self.attempts = self.attempts.saturating_add(1);
+        self.next_attempt = SystemTime::now() + settings.backoff(self.attempts);
+    }
+
+    /// Checks whether the next attempt is due.
+    fn is_due(&self) -> bool {
+        SystemTime::now() >= self.next_attempt
+    }
+
+    /// Checks whether the deadline for sending the chunk has passed.
+    fn is_expired(&self, settings: &RetrySettings) -> bool {
+        matches!(self.first_failure.elapsed(), Ok(elapsed) if elapsed >= settings.deadline)
+    }
+}
+
 /// Implements the `SendingSum`, `SendingUpdate` and `SendingSum2` phases and transitions.
+///
+/// If a `retry` state is given, the phase goes back to it once when the coordinator rejects the
+/// message because of an outdated seed dictionary.
+///
+/// Chunks that couldn't be sent because of a transient failure are resent with an exponential
+/// backoff, as configured by the [`RetrySettings`]. The message is dropped, the failure is
+/// notified and the phase goes to the awaiting phase if the coordinator rejects it for good or if
+/// the retry deadline passes. If the participant is banned, the phase goes to the banned phase
+/// instead.
+///
+/// The round parameters are checked before every step, hence before every retry. If a new round
+/// started meanwhile, the failed chunk is dropped and the failure is notified.
+///
+/// Before a chunk is resent, the coordinator is asked whether it already recorded the message, as
+/// indicated by the `recorded` field of the submission status. This happens if only the response
+/// to the message got lost, e.g. because the coordinator restarted meanwhile. In that case, the
+/// message is considered sent.
+///
+/// If a `sent` notification is given, it is emitted once the whole message has been sent.
 macro_rules! impl_sending {
-    ($Phase: ty, $Next: ty, $phase: expr, $next: expr) => {
+    (
+        $Phase: ty, $Next: ty, $phase: expr, $next: expr, recorded: $recorded: ident
+        $(, sent: $notify_sent: ident)?
+        $(, retry: $Retry: ty)?
+    ) => {
         paste! {
             #[doc = "The state of the " $phase " sending phase."]
             #[derive(Serialize, Deserialize, Debug)]
@@ -32,8 +123,17 @@ macro_rules! impl_sending {
                 /// Chunk that couldn't be sent and should be tried again.
                 failed: Option<Vec<u8>>,
 
+                /// Failed attempts to send the `failed` chunk.
+                #[serde(default)]
+                retries: Option<Retries>,
+
                 /// State of the phase to transition to, after this one completes.
                 next: $Next,
+                $(
+                    /// State of the phase to go back to, if the message is rejected because
+                    /// of an outdated seed dictionary.
+                    retry: Option<$Retry>,
+                )?
             }
 
             impl [<Sending $Phase>] {
@@ -42,7 +142,9 @@ macro_rules! impl_sending {
                     Self {
                         message,
                         failed: None,
+                        retries: None,
                         next,
+                        $(retry: None::<$Retry>,)?
                     }
                 }
             }
@@ -58,11 +160,20 @@ macro_rules! impl_sending {
                 async fn step(mut self) -> TransitionOutcome {
                     info!("sending {} message", $phase);
                     self = try_progress!(self.send_next().await);
+                    $(self.io.$notify_sent();)?
 
                     info!("done sending {} message, going to {} phase", $phase, $next);
                     let phase: Phase<$Next> = self.into();
                     TransitionOutcome::Complete(phase.into())
                 }
+
+                fn drop_outdated(&mut self) {
+                    if self.state.private.failed.take().is_some() {
+                        warn!("a new round started, dropping {} message", $phase);
+                        self.state.private.retries = None;
+                        self.io.notify_failed(Failure::MessageOutdated);
+                    }
+                }
             }
 
             impl From<Phase<[<Sending $Phase>]>> for Phase<$Next> {
@@ -78,21 +189,98 @@ macro_rules! impl_sending {
                     info!("sending {} message (size = {})", $phase, data.len());
                     if let Err(e) = self.io.send_message(data.clone()).await {
                         error!("failed to send {} message: {:?}", $phase, e);
+                        $(
+                            if is_stale_seed_dict(e.as_ref()) {
+                                if let Some(retry) = self.state.private.retry.take() {
+                                    info!("{} message is outdated, going back to {} phase", $phase, $phase);
+                                    let phase: Phase<$Retry> =
+                                        State::new(self.state.shared, Box::new(retry))
+                                            .into_phase(self.io);
+                                    return Progress::Updated(phase.into());
+                                }
+                            }
+                        )?
+                        if is_banned(e.as_ref()) {
+                            warn!("participant is banned, dropping {} message", $phase);
+                            self.io.notify_failed(Failure::Banned);
+                            let phase: Phase<Banned> =
+                                State::new(self.state.shared, Box::new(Banned)).into_phase(self.io);
+                            return Progress::Updated(phase.into());
+                        }
+                        if is_permanent_rejection(e.as_ref()) {
+                            warn!("{} message was rejected, dropping it", $phase);
+                            return Progress::Updated(self.give_up(Failure::MessageRejected).into());
+                        }
+
+                        let settings = self.state.shared.retry;
+                        let retries = match self.state.private.retries {
+                            Some(mut retries) => {
+                                retries.record_failure(&settings);
+                                retries
+                            }
+                            None => Retries::new(&settings),
+                        };
+                        if retries.is_expired(&settings) {
+                            warn!(
+                                "failed to send {} message after {} attempts, dropping it",
+                                $phase,
+                                retries.attempts,
+                            );
+                            return Progress::Updated(self.give_up(Failure::MessageExpired).into());
+                        }
                         self.state.private.failed = Some(data);
+                        self.state.private.retries = Some(retries);
                         Progress::Stuck(self)
                     } else {
+                        self.state.private.retries = None;
                         Progress::Updated(self.into())
                     }
                 }
 
+                #[doc =
+                    "Checks whether the coordinator already recorded the " $phase " message of "
+                    "the participant in the current round."
+                ]
+                async fn is_recorded(&mut self) -> bool {
+                    let pk = self.state.shared.keys.public;
+                    match self.io.get_submission_status(pk).await {
+                        Ok(Some(status)) => {
+                            status.is_for(&self.state.shared.round_params) && status.status.$recorded
+                        }
+                        Ok(None) => false,
+                        Err(e) => {
+                            warn!("failed to fetch the submission status: {:?}", e);
+                            false
+                        }
+                    }
+                }
+
+                #[doc = "Drops the " $phase " message and goes to the awaiting phase."]
+                fn give_up(mut self, failure: Failure) -> Phase<Awaiting> {
+                    self.io.notify_failed(failure);
+                    State::new(self.state.shared, Box::new(Awaiting)).into_phase(self.io)
+                }
+
                 #[doc =
                     "Sends the next " $phase " message and reports back on the progress made.\n"
                     "\n"
-                    "Retries to send a previously failed message. Otherwise, tries to send the "
-                    "next message."
+                    "Retries to send a previously failed message, unless the coordinator already "
+                    "recorded it. Otherwise, tries to send the next message."
                 ]
                 async fn send_next(mut self) -> Progress<[<Sending $Phase>]> {
                     if let Some(data) = self.state.private.failed.take() {
+                        if let Some(retries) = self.state.private.retries {
+                            if !retries.is_due() {
+                                debug!("waiting before retrying to send {} message", $phase);
+                                self.state.private.failed = Some(data);
+                                return Progress::Stuck(self);
+                            }
+                        }
+                        if self.is_recorded().await {
+                            info!("{} message is already recorded, not resending it", $phase);
+                            self.state.private.retries = None;
+                            return Progress::Continue(self);
+                        }
                         debug!(
                             "retrying to send {} message that couldn't be sent previously",
                             $phase
@@ -116,6 +304,30 @@ macro_rules! impl_sending {
     }
 }
 
-impl_sending!(Sum, Sum2, "sum", "sum2");
-impl_sending!(Update, Awaiting, "update", "awaiting");
-impl_sending!(Sum2, Awaiting, "sum2", "awaiting");
+impl_sending!(Sum, Sum2, "sum", "sum2", recorded: sum);
+impl_sending!(
+    Update,
+    Awaiting,
+    "update",
+    "awaiting",
+    recorded: update,
+    sent: notify_update_sent
+);
+impl_sending!(
+    Sum2,
+    Awaiting,
+    "sum2",
+    "awaiting",
+    recorded: sum2,
+    sent: notify_sum2_sent,
+    retry: Sum2
+);
+
+impl SendingSum2 {
+    /// Sets the sum2 state to go back to, if the sum2 message is rejected because of an outdated
+    /// seed dictionary.
+    pub fn with_retry(mut self, retry: Sum2) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
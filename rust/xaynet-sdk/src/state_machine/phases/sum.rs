@@ -1,13 +1,14 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 use crate::{
     state_machine::{IntoPhase, Phase, PhaseIo, SendingSum, State, Step, Sum2, TransitionOutcome},
+    Failure,
     MessageEncoder,
 };
 use xaynet_core::{
-    crypto::{EncryptKeyPair, Signature},
+    crypto::{ByteObject, EncryptKeyPair, Signature},
     message::Sum as SumMessage,
 };
 
@@ -26,9 +27,9 @@ pub struct Sum {
 
 impl Sum {
     /// Creates a new sum state.
-    pub fn new(sum_signature: Signature) -> Self {
+    pub fn new(sum_signature: Signature, ephm_keys: EncryptKeyPair) -> Self {
         Sum {
-            ephm_keys: EncryptKeyPair::generate(),
+            ephm_keys,
             sum_signature,
         }
     }
@@ -45,6 +46,18 @@ impl IntoPhase<Sum> for State<Sum> {
 impl Step for Phase<Sum> {
     async fn step(mut self) -> TransitionOutcome {
         info!("sum task");
+        if !self.has_fresh_ephm_keys() {
+            error!("ephemeral keys are not fresh, refusing to send the sum message");
+            self.io.notify_failed(Failure::InsecureEphemeralKeys);
+            let awaiting: Phase<Awaiting> = self.into();
+            return TransitionOutcome::Complete(awaiting.into());
+        }
+        let shared = &mut self.state.shared;
+        shared.last_ephm_pk = Some((
+            shared.round_params.seed.clone(),
+            self.state.private.ephm_keys.public,
+        ));
+
         let sending: Phase<SendingSum> = self.into();
         TransitionOutcome::Complete(sending.into())
     }
@@ -70,6 +83,27 @@ impl From<Phase<Sum>> for Phase<Awaiting> {
 }
 
 impl Phase<Sum> {
+    /// Checks that the ephemeral keys differ from the signing keys and from the ephemeral keys
+    /// of the previous sum task.
+    ///
+    /// This is a cheap sanity check against a stuck random number generator. Deterministic
+    /// ephemeral keys are only compared against the ones of a previous round, since they are
+    /// intentionally the same within a round.
+    fn has_fresh_ephm_keys(&self) -> bool {
+        let shared = &self.state.shared;
+        let ephm_keys = &self.state.private.ephm_keys;
+        let signing_seed = &shared.keys.secret.as_slice()[..ephm_keys.secret.as_slice().len()];
+        if ephm_keys.public.as_slice() == shared.keys.public.as_slice()
+            || ephm_keys.secret.as_slice() == signing_seed
+        {
+            return false;
+        }
+        match &shared.last_ephm_pk {
+            Some((seed, pk)) => *pk != ephm_keys.public || *seed == shared.round_params.seed,
+            None => true,
+        }
+    }
+
     /// Creates and encodes the sum message from the sum state.
     pub fn compose_message(&self) -> MessageEncoder {
         let sum = SumMessage {
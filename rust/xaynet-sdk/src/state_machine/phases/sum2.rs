@@ -1,14 +1,17 @@
+use std::time::Instant;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 use xaynet_core::{
-    crypto::{EncryptKeyPair, Signature},
+    crypto::{ByteObject, EncryptKeyPair, Signature},
     mask::{Aggregation, MaskObject, MaskSeed},
     message::Sum2 as Sum2Message,
     UpdateSeedDict,
 };
 
 use crate::{
+    budget::{BudgetExceeded, ComputeTask},
     state_machine::{
         IntoPhase,
         Phase,
@@ -20,6 +23,7 @@ use crate::{
         TransitionOutcome,
         IO,
     },
+    Failure,
     MessageEncoder,
 };
 
@@ -37,6 +41,12 @@ pub struct Sum2 {
     /// Dictionary containing the encrypted mask seed of every update
     /// participants.
     pub seed_dict: Option<UpdateSeedDict>,
+    /// The version of the seed dictionary, as served by the
+    /// coordinator. It is echoed in the sum2 message.
+    pub seed_dict_version: Option<u64>,
+    /// Whether the seed dictionary has already been fetched again,
+    /// after the coordinator rejected an outdated mask.
+    pub seed_dict_refetched: bool,
     /// The decrypted mask seeds
     pub seeds: Option<Vec<MaskSeed>>,
     /// The global mask, obtained by aggregating the masks derived
@@ -51,11 +61,26 @@ impl Sum2 {
             ephm_keys,
             sum_signature,
             seed_dict: None,
+            seed_dict_version: None,
+            seed_dict_refetched: false,
             seeds: None,
             mask: None,
         }
     }
 
+    /// Creates a new sum2 state that fetches the seed dict again.
+    ///
+    /// Returns `None` if the seed dict has already been refetched
+    /// once.
+    fn refetch(&self) -> Option<Self> {
+        if self.seed_dict_refetched {
+            return None;
+        }
+        let mut sum2 = Self::new(self.ephm_keys.clone(), self.sum_signature);
+        sum2.seed_dict_refetched = true;
+        Some(sum2)
+    }
+
     /// Checks if the seed dict has already been fetched.
     fn has_fetched_seed_dict(&self) -> bool {
         self.seed_dict.is_some() || self.has_decrypted_seeds()
@@ -96,7 +121,11 @@ impl From<Phase<Sum2>> for Phase<SendingSum2> {
         let message = sum2.compose_message();
 
         debug!("going to sending phase");
-        let sending = Box::new(SendingSum2::new(message, Awaiting));
+        let mut sending = SendingSum2::new(message, Awaiting);
+        if let Some(retry) = sum2.state.private.refetch() {
+            sending = sending.with_retry(retry);
+        }
+        let sending = Box::new(sending);
         let state = State::new(sum2.state.shared, sending);
         state.into_phase(sum2.io)
     }
@@ -115,22 +144,49 @@ impl Phase<Sum2> {
             return Progress::Continue(self);
         }
         debug!("polling for update seeds");
-        match self.io.get_seeds(self.state.shared.keys.public).await {
+        let seeds = match self.io.get_seeds(self.state.shared.keys.public).await {
             Err(e) => {
                 warn!("failed to fetch seeds: {}", e);
-                Progress::Stuck(self)
+                return Progress::Stuck(self);
+            }
+            Ok(seeds) => seeds,
+        };
+        match seeds {
+            None if self.is_evicted().await => {
+                warn!("participant has been evicted from the sum dictionary, going back to waiting phase");
+                self.io.notify_failed(Failure::Evicted);
+                let awaiting: Phase<Awaiting> = self.into();
+                Progress::Updated(awaiting.into())
             }
-            Ok(None) => {
+            None => {
                 debug!("seeds not available yet");
                 Progress::Stuck(self)
             }
-            Ok(Some(seeds)) => {
-                self.state.private.seed_dict = Some(seeds);
+            Some(seeds) => {
+                self.state.private.seed_dict_version = Some(seeds.version);
+                self.state.private.seed_dict = Some(seeds.seeds);
                 Progress::Updated(self.into())
             }
         }
     }
 
+    /// Checks whether the coordinator evicted the participant from the
+    /// sum dictionary of the current round, in which case the seeds
+    /// never become available.
+    async fn is_evicted(&mut self) -> bool {
+        let pk = self.state.shared.keys.public;
+        match self.io.get_submission_status(pk).await {
+            Ok(Some(status)) => {
+                status.is_for(&self.state.shared.round_params) && status.status.evicted
+            }
+            Ok(None) => false,
+            Err(e) => {
+                warn!("failed to fetch the submission status: {:?}", e);
+                false
+            }
+        }
+    }
+
     /// Decrypt the mask seeds that the update participants generated.
     pub(crate) fn decrypt_seeds(mut self) -> Progress<Sum2> {
         if self.state.private.has_decrypted_seeds() {
@@ -157,6 +213,7 @@ impl Phase<Sum2> {
             }
             Err(_) => {
                 warn!("failed to decrypt mask seeds, going back to waiting phase");
+                self.io.notify_failed(Failure::InvalidSeeds);
                 self.io.notify_idle();
                 let awaiting: Phase<Awaiting> = self.into();
                 Progress::Updated(awaiting.into())
@@ -175,19 +232,43 @@ impl Phase<Sum2> {
         info!("aggregating masks");
         let config = self.state.shared.round_params.mask_config;
         let mask_len = self.state.shared.round_params.model_length;
+        let round_bound_masks = self.state.shared.round_params.round_bound_masks;
+        let round_seed = self.state.shared.round_params.seed.clone();
         let mut mask_agg = Aggregation::new(config, mask_len as usize);
+        let round_seed = if round_bound_masks {
+            Some(round_seed.as_slice())
+        } else {
+            None
+        };
+        let budget = self.state.shared.compute_budget.max_sum2();
+        let start = Instant::now();
         // UNWRAP_SAFE: the seeds are set in `decrypt_seeds()` which is called before this method
         for seed in self.state.private.seeds.take().unwrap().into_iter() {
-            let mask = seed.derive_mask(mask_len as usize, config);
-            if let Err(e) = mask_agg.validate_aggregation(&mask) {
+            if let Err(e) = mask_agg.aggregate_seed(&seed, round_seed) {
                 error!("sum2 phase failed: cannot aggregate masks: {}", e);
                 error!("going to awaiting phase");
                 let awaiting: Phase<Awaiting> = self.into();
                 return Progress::Updated(awaiting.into());
-            } else {
-                mask_agg.aggregate(mask);
+            }
+            // the budget is checked between the seeds, hence it's exceeded by at most the cost
+            // of deriving a single mask
+            let cost = start.elapsed();
+            if let Some(budget) = budget.filter(|budget| cost > *budget) {
+                warn!(
+                    "sum2 phase aborted: deriving the masks took {:?}, which exceeds the budget of {:?}",
+                    cost, budget
+                );
+                info!("going to awaiting phase");
+                self.io.notify_budget_exceeded(BudgetExceeded {
+                    task: ComputeTask::Sum2,
+                    cost,
+                    budget,
+                });
+                let awaiting: Phase<Awaiting> = self.into();
+                return Progress::Updated(awaiting.into());
             }
         }
+        self.state.shared.last_costs.sum2 = Some(start.elapsed());
         self.state.private.mask = Some(mask_agg.into());
         Progress::Updated(self.into())
     }
@@ -196,6 +277,7 @@ impl Phase<Sum2> {
     pub fn compose_message(&mut self) -> MessageEncoder {
         let sum2 = Sum2Message {
             sum_signature: self.state.private.sum_signature,
+            seed_dict_version: self.state.private.seed_dict_version,
             // UNWRAP_SAFE: the mask set in `aggregate_masks()` which is called before this method
             model_mask: self.state.private.mask.take().unwrap(),
         };
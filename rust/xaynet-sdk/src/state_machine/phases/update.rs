@@ -1,14 +1,16 @@
-use std::ops::Deref;
+use std::{ops::Deref, time::Instant};
 
 use async_trait::async_trait;
 use derive_more::From;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use tracing::{debug, info, warn};
 
 use xaynet_core::{
-    crypto::Signature,
-    mask::{MaskObject, MaskSeed, Masker, Model},
-    message::Update as UpdateMessage,
+    common::{RoundParameters, RoundSumDict},
+    crypto::{ByteObject, Sha256, Signature},
+    mask::{MaskObject, MaskSeed, Masker, Model, SealedBox, SeedCipher},
+    message::{Tag, Update as UpdateMessage, UpdateWriter},
     LocalSeedDict,
     ParticipantTaskSignature,
     SumDict,
@@ -27,6 +29,7 @@ use crate::{
         TransitionOutcome,
         IO,
     },
+    Failure,
     MessageEncoder,
 };
 
@@ -78,15 +81,39 @@ impl<'de> serde::de::Deserialize<'de> for LocalModel {
     }
 }
 
+/// Errors which make the coordinator reject an update message.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum UpdateValidationError {
+    #[error("the update message is not complete yet")]
+    Incomplete,
+    #[error("the task signatures don't verify against the round seed")]
+    InvalidSignature,
+    #[error("the participant is eligible for the sum task")]
+    SumEligible,
+    #[error("the participant is not eligible for the update task")]
+    NotUpdateEligible,
+    #[error("the sum dictionary belongs to another round")]
+    StaleSumDict,
+    #[error("the local seed dictionary doesn't cover exactly the sum participants")]
+    SeedDictMismatch,
+    #[error("the masked model doesn't match the masking configuration and model length")]
+    InvalidMaskedModel,
+}
+
 /// The state of the update phase.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Update {
     pub sum_signature: ParticipantTaskSignature,
     pub update_signature: ParticipantTaskSignature,
-    pub sum_dict: Option<SumDict>,
+    pub sum_dict: Option<RoundSumDict>,
+    /// The local seed dictionary, which is only built by earlier versions. The update payload
+    /// is serialized from the sum dictionary without building it otherwise.
     pub seed_dict: Option<LocalSeedDict>,
     pub model: Option<LocalModel>,
     pub mask: Option<(MaskSeed, MaskObject)>,
+    /// The checksum of the local model before it was masked.
+    #[serde(default)]
+    pub model_checksum: Option<Sha256>,
 }
 
 impl Update {
@@ -99,6 +126,7 @@ impl Update {
             seed_dict: None,
             model: None,
             mask: None,
+            model_checksum: None,
         }
     }
 
@@ -135,9 +163,14 @@ impl Step for Phase<Update> {
         self = try_progress!(self.fetch_sum_dict().await);
         self = try_progress!(self.load_model().await);
         self = try_progress!(self.mask_model());
-        self = try_progress!(self.build_seed_dict());
-        let sending: Phase<SendingUpdate> = self.into();
-        TransitionOutcome::Complete(sending.into())
+        if self.state.private.has_built_seed_dict() {
+            let sending: Phase<SendingUpdate> = self.into();
+            return TransitionOutcome::Complete(sending.into());
+        }
+        match self.compose_message_streaming() {
+            Ok(sending) => TransitionOutcome::Complete(sending.into()),
+            Err(awaiting) => TransitionOutcome::Complete(awaiting.into()),
+        }
     }
 }
 
@@ -206,51 +239,176 @@ impl Phase<Update> {
     }
 
     /// Generate a mask seed and mask a local model.
+    ///
+    /// The checksum of the local model is computed before and after masking it. If the model
+    /// changed in the meantime, the masked model doesn't match the checksum and the participant
+    /// drops out of the round instead of sending a corrupted update.
     pub(crate) fn mask_model(mut self) -> Progress<Update> {
         if self.state.private.has_masked_model() {
             debug!("already computed the masked model, continuing");
             return Progress::Continue(self);
         }
         info!("computing masked model");
-        let config = self.state.shared.round_params.mask_config;
-        let masker = Masker::new(config);
+        let round_params = &self.state.shared.round_params;
+        let mut masker = Masker::new(round_params.mask_config);
+        if round_params.round_bound_masks {
+            masker = masker.for_round(round_params.seed.as_slice());
+        }
         // UNWRAP_SAFE: the model is set, per the `has_masked_model()` check above
         let model = self.state.private.model.take().unwrap();
         let scalar = self.state.shared.scalar.clone();
-        self.state.private.mask = Some(masker.mask(scalar, model.as_ref()));
+        let checksum = model.as_ref().checksum();
+        let start = Instant::now();
+        let mask = masker.mask(scalar, model.as_ref());
+        self.state.shared.last_costs.masking = Some(start.elapsed());
+        if model.as_ref().checksum() != checksum {
+            warn!("the local model changed while it was masked");
+            info!("going to awaiting phase");
+            self.io.notify_failed(Failure::ModelCorrupted);
+            let awaiting: Phase<Awaiting> = self.into();
+            return Progress::Updated(awaiting.into());
+        }
+        self.state.private.mask = Some(mask);
+        self.state.private.model_checksum = Some(checksum);
         Progress::Updated(self.into())
     }
 
-    // Create a local seed dictionary from a sum dictionary.
-    pub(crate) fn build_seed_dict(mut self) -> Progress<Update> {
-        if self.state.private.has_built_seed_dict() {
-            debug!("already built the seed dictionary, continuing");
-            return Progress::Continue(self);
+    /// Validates the update of this participant against the sum dictionary and composes the
+    /// update message, where the mask seed is encrypted for each sum participant right into the
+    /// serialized payload instead of building the local seed dictionary first.
+    ///
+    /// The mask seed is encrypted with the keys of the sum dictionary, hence a sum dictionary of
+    /// another round would render the local seed dictionary useless. In that case, or if the
+    /// coordinator would reject the update message otherwise, the participant drops out of the
+    /// round.
+    pub(crate) fn compose_message_streaming(
+        mut self,
+    ) -> Result<Phase<SendingUpdate>, Phase<Awaiting>> {
+        // UNWRAP_SAFE: the dict is set in `fetch_sum_dict()` which is called before this method
+        let round_sum_dict = self.state.private.sum_dict.take().unwrap();
+        if !round_sum_dict.is_for(&self.state.shared.round_params) {
+            warn!(
+                "the coordinator would reject the update message: {}",
+                UpdateValidationError::StaleSumDict
+            );
+            info!("going to awaiting phase");
+            return Err(self.into());
         }
-        // UNWRAP_SAFE: the mask is set in `mask_model()` which is called before this method
-        let mask_seed = &self.state.private.mask.as_ref().unwrap().0;
-        info!("building local seed dictionary");
-        let seeds = self
-            .state
-            .private
-            .sum_dict
-            .take()
-            .unwrap()
-            .into_iter()
-            .map(|(pk, ephm_pk)| (pk, mask_seed.encrypt(&ephm_pk)))
-            .collect();
-        self.state.private.seed_dict = Some(seeds);
-        Progress::Updated(self.into())
+        let sum_dict = round_sum_dict.sum_dict;
+
+        let round_params = &self.state.shared.round_params;
+        if let Err(e) = self.self_validate_update(round_params, &sum_dict) {
+            warn!("the coordinator would reject the update message: {}", e);
+            info!("going to awaiting phase");
+            return Err(self.into());
+        }
+
+        debug!("composing update message");
+        let update = &mut self.state.private;
+        // UNWRAP_SAFE: the mask and the checksum are set in `mask_model()` which is called before
+        // this method
+        let (mask_seed, masked_model) = update.mask.take().unwrap();
+        let writer = UpdateWriter::new(
+            update.sum_signature,
+            update.update_signature,
+            update.model_checksum.take().unwrap(),
+            &masked_model,
+            &mask_seed,
+            sum_dict.len(),
+        );
+        let mut payload = vec![0; writer.buffer_length()];
+        info!(
+            "encrypting mask seed for {} sum participants",
+            sum_dict.len()
+        );
+        // UNWRAP_SAFE: the writer expects exactly the entries of the sum dictionary
+        writer.write(sum_dict, &mut payload).unwrap();
+        let message = self.serialized_message_encoder(Tag::Update, payload);
+
+        debug!("going to sending phase");
+        let sending = Box::new(SendingUpdate::new(message, Awaiting));
+        Ok(State::new(self.state.shared, sending).into_phase(self.io))
+    }
+
+    /// Checks whether the coordinator will accept the update message of this participant for
+    /// the round with the given parameters and sum dictionary.
+    ///
+    /// This mirrors the validation of the coordinator: the task signatures must verify, the
+    /// participant must be eligible for the update task but not for the sum task, unless the
+    /// round parameters allow sum-eligible participants to update, a local seed dictionary which
+    /// has been built already must contain exactly the participants of the `sum_dict` and the
+    /// masked model must match the masking configuration and model length.
+    ///
+    /// # Errors
+    /// Fails if the coordinator would reject the update message or if the message is not
+    /// complete yet, i.e. if the model has not been masked.
+    pub(crate) fn self_validate_update(
+        &self,
+        round_params: &RoundParameters,
+        sum_dict: &SumDict,
+    ) -> Result<(), UpdateValidationError> {
+        let update = &self.state.private;
+        let masked_model = match &update.mask {
+            Some((_, masked_model)) => masked_model,
+            None => return Err(UpdateValidationError::Incomplete),
+        };
+
+        let pk = &self.state.shared.keys.public;
+        let seed = round_params.seed.as_slice();
+        let suite = round_params.crypto_suite;
+        if !pk.verify_detached(
+            &update.sum_signature,
+            &suite.task_signature_payload(seed, b"sum"),
+        ) || !pk.verify_detached(
+            &update.update_signature,
+            &suite.task_signature_payload(seed, b"update"),
+        ) {
+            return Err(UpdateValidationError::InvalidSignature);
+        }
+        let salt = &round_params.eligibility_salt;
+        if !round_params.allow_sum_eligible_updates
+            && update
+                .sum_signature
+                .is_eligible_salted(round_params.sum, salt)
+        {
+            return Err(UpdateValidationError::SumEligible);
+        }
+        if !update
+            .update_signature
+            .is_eligible_salted(round_params.update, salt)
+        {
+            return Err(UpdateValidationError::NotUpdateEligible);
+        }
+
+        if let Some(seed_dict) = &update.seed_dict {
+            if !seed_dict.matches(sum_dict) {
+                return Err(UpdateValidationError::SeedDictMismatch);
+            }
+        }
+
+        if masked_model.vect.config != round_params.mask_config.vect
+            || masked_model.unit.config != round_params.mask_config.unit
+            || masked_model.vect.data.len() != round_params.model_length
+            || !masked_model.is_valid()
+        {
+            return Err(UpdateValidationError::InvalidMaskedModel);
+        }
+        Ok(())
     }
 
-    /// Creates and encodes the update message from the update state.
+    /// Creates and encodes the update message from the update state, once the local seed
+    /// dictionary has been built.
     pub fn compose_message(&mut self) -> MessageEncoder {
         let update = UpdateMessage {
             sum_signature: self.state.private.sum_signature,
             update_signature: self.state.private.update_signature,
+            // UNWRAP_SAFE: the checksum is set in `mask_model()` which is called before this method
+            model_checksum: self.state.private.model_checksum.take().unwrap(),
+            // the seeds are encrypted with the default cipher by `LocalSeedDict::new()`
+            seed_cipher: SealedBox::ID,
             // UNWRAP_SAFE: the mask is set in `mask_model()` which is called before this method
             masked_model: self.state.private.mask.take().unwrap().1,
-            // UNWRAP_SAFE: the dict is set in `build_seed_dict()` which is called before this method
+            // UNWRAP_SAFE: the dict is set, per the `has_built_seed_dict()` check in `step()`
             local_seed_dict: self.state.private.seed_dict.take().unwrap(),
         };
         self.message_encoder(update.into())
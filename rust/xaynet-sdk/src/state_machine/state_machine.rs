@@ -3,6 +3,7 @@ use derive_more::From;
 use super::{
     boxed_io,
     Awaiting,
+    Banned,
     IntoPhase,
     LocalModelConfig,
     NewRound,
@@ -13,11 +14,19 @@ use super::{
     SerializableState,
     SharedState,
     State,
+    Step,
     Sum,
     Sum2,
     Update,
 };
-use crate::{settings::PetSettings, ModelStore, Notify, XaynetClient};
+use crate::{
+    budget::{Calibration, ComputeCosts},
+    settings::PetSettings,
+    ModelStore,
+    Notify,
+    XaynetClient,
+};
+use xaynet_core::{common::PhaseTimeline, mask::MaskConfig};
 
 /// Outcome of a state machine transition attempt.
 #[derive(Debug)]
@@ -48,6 +57,8 @@ pub enum StateMachine {
     SendingUpdate(Phase<SendingUpdate>),
     /// PET state machine in the "sending sum2 message" phase
     SendingSum2(Phase<SendingSum2>),
+    /// PET state machine in the "banned" phase
+    Banned(Phase<Banned>),
 }
 
 impl StateMachine {
@@ -62,6 +73,8 @@ impl StateMachine {
             StateMachine::SendingSum(phase) => phase.step().await,
             StateMachine::SendingUpdate(phase) => phase.step().await,
             StateMachine::SendingSum2(phase) => phase.step().await,
+            // a banned participant doesn't check for new rounds
+            StateMachine::Banned(phase) => <Phase<Banned> as Step>::step(phase).await,
         }
     }
 
@@ -77,6 +90,22 @@ impl StateMachine {
             StateMachine::SendingSum(phase) => phase.state.into(),
             StateMachine::SendingUpdate(phase) => phase.state.into(),
             StateMachine::SendingSum2(phase) => phase.state.into(),
+            StateMachine::Banned(phase) => phase.state.into(),
+        }
+    }
+
+    /// Return the masking configuration advertised by the coordinator for the current round.
+    pub fn mask_config(&self) -> MaskConfig {
+        match self {
+            StateMachine::NewRound(ref phase) => phase.mask_config(),
+            StateMachine::Awaiting(ref phase) => phase.mask_config(),
+            StateMachine::Sum(ref phase) => phase.mask_config(),
+            StateMachine::Update(ref phase) => phase.mask_config(),
+            StateMachine::Sum2(ref phase) => phase.mask_config(),
+            StateMachine::SendingSum(ref phase) => phase.mask_config(),
+            StateMachine::SendingUpdate(ref phase) => phase.mask_config(),
+            StateMachine::SendingSum2(ref phase) => phase.mask_config(),
+            StateMachine::Banned(ref phase) => phase.mask_config(),
         }
     }
 
@@ -91,6 +120,61 @@ impl StateMachine {
             StateMachine::SendingSum(ref phase) => phase.local_model_config(),
             StateMachine::SendingUpdate(ref phase) => phase.local_model_config(),
             StateMachine::SendingSum2(ref phase) => phase.local_model_config(),
+            StateMachine::Banned(ref phase) => phase.local_model_config(),
+        }
+    }
+
+    /// Return the timeline of the phases of the current round, if the coordinator publishes it.
+    ///
+    /// A participant which is selected for the update task can check with
+    /// [`PhaseTimeline::update_time_left()`] whether it has enough time to train its local model.
+    pub fn phase_timeline(&self) -> Option<PhaseTimeline> {
+        match self {
+            StateMachine::NewRound(ref phase) => phase.phase_timeline(),
+            StateMachine::Awaiting(ref phase) => phase.phase_timeline(),
+            StateMachine::Sum(ref phase) => phase.phase_timeline(),
+            StateMachine::Update(ref phase) => phase.phase_timeline(),
+            StateMachine::Sum2(ref phase) => phase.phase_timeline(),
+            StateMachine::SendingSum(ref phase) => phase.phase_timeline(),
+            StateMachine::SendingUpdate(ref phase) => phase.phase_timeline(),
+            StateMachine::SendingSum2(ref phase) => phase.phase_timeline(),
+            StateMachine::Banned(ref phase) => phase.phase_timeline(),
+        }
+    }
+
+    /// Return the calibrated cost of masking, if the participant ran the calibration already.
+    ///
+    /// The calibration only runs if the masking is bounded by the compute budget, see
+    /// [`ComputeBudget`].
+    ///
+    /// [`ComputeBudget`]: crate::settings::ComputeBudget
+    pub fn calibration(&self) -> Option<Calibration> {
+        match self {
+            StateMachine::NewRound(ref phase) => phase.calibration(),
+            StateMachine::Awaiting(ref phase) => phase.calibration(),
+            StateMachine::Sum(ref phase) => phase.calibration(),
+            StateMachine::Update(ref phase) => phase.calibration(),
+            StateMachine::Sum2(ref phase) => phase.calibration(),
+            StateMachine::SendingSum(ref phase) => phase.calibration(),
+            StateMachine::SendingUpdate(ref phase) => phase.calibration(),
+            StateMachine::SendingSum2(ref phase) => phase.calibration(),
+            StateMachine::Banned(ref phase) => phase.calibration(),
+        }
+    }
+
+    /// Return the actual costs of the computations in the latest rounds the participant
+    /// performed them.
+    pub fn last_costs(&self) -> ComputeCosts {
+        match self {
+            StateMachine::NewRound(ref phase) => phase.last_costs(),
+            StateMachine::Awaiting(ref phase) => phase.last_costs(),
+            StateMachine::Sum(ref phase) => phase.last_costs(),
+            StateMachine::Update(ref phase) => phase.last_costs(),
+            StateMachine::Sum2(ref phase) => phase.last_costs(),
+            StateMachine::SendingSum(ref phase) => phase.last_costs(),
+            StateMachine::SendingUpdate(ref phase) => phase.last_costs(),
+            StateMachine::SendingSum2(ref phase) => phase.last_costs(),
+            StateMachine::Banned(ref phase) => phase.last_costs(),
         }
     }
 }
@@ -143,6 +227,7 @@ impl StateMachine {
             SerializableState::SendingSum(state) => state.into_phase(io).into(),
             SerializableState::SendingUpdate(state) => state.into_phase(io).into(),
             SerializableState::SendingSum2(state) => state.into_phase(io).into(),
+            SerializableState::Banned(state) => state.into_phase(io).into(),
         }
     }
 }
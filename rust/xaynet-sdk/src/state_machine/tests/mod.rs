@@ -1,2 +1,3 @@
 mod phases;
+mod round;
 pub mod utils;
@@ -1,4 +1,5 @@
 mod new_round;
+mod sending;
 mod sum;
 mod sum2;
 mod update;
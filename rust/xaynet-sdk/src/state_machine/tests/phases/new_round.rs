@@ -1,4 +1,13 @@
+use mockall::predicate::eq;
+use xaynet_core::{
+    common::{RoundSeed, RoundSummary},
+    crypto::{ByteObject, CryptoSuite, EncryptKeyPair, SigningKeyPair},
+    mask::{BoundType, DataType, MaskConfig},
+};
+
 use crate::{
+    budget::{Calibration, ComputeTask},
+    eligibility::{should_wake, Task},
     state_machine::{
         tests::utils::{shared_state, SelectFor},
         IntoPhase,
@@ -6,8 +15,12 @@ use crate::{
         NewRound,
         Phase,
         State,
+        StateMachine,
+        Step,
+        TransitionOutcome,
     },
     unwrap_step,
+    Failure,
 };
 
 #[tokio::test]
@@ -27,6 +40,236 @@ async fn test_selected_for_update() {
     unwrap_step!(phase, complete, update);
 }
 
+/// Runs a new round phase for a participant selected for the sum task and gets its ephemeral keys.
+async fn sum_ephm_keys(seed: RoundSeed, deterministic: bool) -> EncryptKeyPair {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.round_params.seed = seed;
+    shared.deterministic_ephm_keys = deterministic;
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_sum().return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    let sum = unwrap_step!(phase, complete, sum);
+    sum.state.private.ephm_keys.clone()
+}
+
+#[tokio::test]
+async fn test_deterministic_ephm_keys() {
+    let seed = RoundSeed::generate();
+    let keys = sum_ephm_keys(seed.clone(), true).await;
+    // a restarted participant derives the same keys again
+    assert_eq!(sum_ephm_keys(seed, true).await, keys);
+    assert_ne!(sum_ephm_keys(RoundSeed::generate(), true).await, keys);
+
+    let seed = RoundSeed::generate();
+    assert_ne!(
+        sum_ephm_keys(seed.clone(), false).await,
+        sum_ephm_keys(seed, false).await,
+    );
+}
+
+#[tokio::test]
+async fn test_unexpected_mask_config() {
+    let mut shared = shared_state(SelectFor::Sum);
+    let advertised = shared.round_params.mask_config.vect;
+    let mut expected = advertised;
+    expected.bound_type = BoundType::Bmax;
+    shared.expected_mask_config = Some(expected);
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_unsupported_mask_config()
+        .withf(move |config, supported| *config == advertised && supported == [expected])
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_supported_mask_config() {
+    let mut shared = shared_state(SelectFor::Sum);
+    let advertised = shared.round_params.mask_config.vect;
+    shared.supported_mask_configs = Some(MaskConfig::catalogue(advertised.data_type));
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_sum().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, sum);
+}
+
+#[tokio::test]
+async fn test_unsupported_mask_config() {
+    let mut shared = shared_state(SelectFor::Sum);
+    let advertised = shared.round_params.mask_config.vect;
+    let supported = MaskConfig::catalogue(advertised.data_type)
+        .into_iter()
+        .filter(|config| config.bound_type != advertised.bound_type)
+        .collect::<Vec<_>>();
+    shared.supported_mask_configs = Some(supported.clone());
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_unsupported_mask_config()
+        .withf(move |config, configs| *config == advertised && configs == supported.as_slice())
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_mask_config_data_type_mismatch() {
+    let mut shared = shared_state(SelectFor::Sum);
+    let advertised = shared.round_params.mask_config.vect;
+    assert_ne!(advertised.data_type, DataType::I64);
+    // the client only supports the configurations for its own data type
+    shared.supported_mask_configs = Some(MaskConfig::catalogue(DataType::I64));
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_unsupported_mask_config()
+        .withf(move |config, _| *config == advertised)
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_unsupported_crypto_suite() {
+    let mut shared = shared_state(SelectFor::Sum);
+    let suite = CryptoSuite::new(u8::MAX);
+    shared.round_params.crypto_suite = suite;
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_unsupported_crypto_suite()
+        .withf(move |unsupported| *unsupported == suite)
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    io.expect_notify_sum().never();
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_pinned_coordinator_pk() {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.pinned_coordinator_pk = Some(shared.round_params.pk);
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_sum().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, sum);
+}
+
+#[tokio::test]
+async fn test_not_pinned_coordinator_pk() {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.pinned_coordinator_pk = Some(EncryptKeyPair::generate().public);
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_failed()
+        .with(eq(Failure::UntrustedCoordinatorKey))
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    io.expect_notify_sum().never();
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, awaiting);
+}
+
+/// A client which only supports the default suite refuses to take part in a `BLAKE3` round.
+#[cfg(not(feature = "blake3"))]
+#[tokio::test]
+async fn test_blake3_round_without_blake3_support() {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.round_params.crypto_suite = CryptoSuite::BLAKE3;
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_unsupported_crypto_suite()
+        .withf(|suite| *suite == CryptoSuite::BLAKE3)
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    unwrap_step!(phase, complete, awaiting);
+}
+
+#[cfg(feature = "blake3")]
+#[tokio::test]
+async fn test_blake3_round() {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.round_params.crypto_suite = CryptoSuite::BLAKE3;
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_sum().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    let sum = unwrap_step!(phase, complete, sum);
+
+    // the sum signature is domain separated by the suite
+    let shared = &sum.state.shared;
+    let seed = shared.round_params.seed.as_slice();
+    let signature = &sum.state.private.sum_signature;
+    assert!(shared.keys.public.verify_detached(
+        signature,
+        &CryptoSuite::BLAKE3.task_signature_payload(seed, b"sum"),
+    ));
+    assert!(!shared.keys.public.verify_detached(
+        signature,
+        &CryptoSuite::SHA256.task_signature_payload(seed, b"sum"),
+    ));
+}
+
+#[tokio::test]
+async fn test_masking_budget_exceeded() {
+    let mut shared = shared_state(SelectFor::Update);
+    shared.round_params.model_length = 1000;
+    // an artificially tiny budget which any model exceeds
+    shared.compute_budget.max_masking_millis = Some(0);
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_budget_exceeded()
+        .withf(|exceeded| exceeded.task == ComputeTask::Masking && exceeded.cost > exceeded.budget)
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    io.expect_notify_update().never();
+    io.expect_notify_load_model().never();
+    io.expect_send_message().never();
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    let awaiting = unwrap_step!(phase, complete, awaiting);
+
+    // the calibration is cached for the next rounds
+    let shared = &awaiting.state.shared;
+    let calibration = shared.calibration.unwrap();
+    assert_eq!(calibration.mask_config, shared.round_params.mask_config);
+}
+
+#[tokio::test]
+async fn test_masking_budget_recalibrated() {
+    let mut shared = shared_state(SelectFor::Update);
+    shared.round_params.model_length = 1000;
+    shared.compute_budget.max_masking_millis = Some(60_000);
+    // a calibration for another masking configuration is not reused
+    let mut stale = Calibration::run(shared.round_params.mask_config);
+    stale.mask_config.vect.bound_type = BoundType::Bmax;
+    stale.masking_nanos_per_weight = u64::MAX;
+    shared.calibration = Some(stale);
+    let mut io = MockIO::new();
+    io.expect_notify_new_round().return_const(());
+    io.expect_notify_update().times(1).return_const(());
+    io.expect_notify_load_model().times(1).return_const(());
+    let phase: Phase<NewRound> = State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+    let update = unwrap_step!(phase, complete, update);
+
+    let shared = &update.state.shared;
+    let calibration = shared.calibration.unwrap();
+    assert_eq!(calibration.mask_config, shared.round_params.mask_config);
+    assert!(calibration.masking_nanos_per_weight < u64::MAX);
+}
+
 #[tokio::test]
 async fn test_not_selected() {
     let mut io = MockIO::new();
@@ -35,6 +278,52 @@ async fn test_not_selected() {
     unwrap_step!(phase, complete, awaiting);
 }
 
+#[tokio::test]
+async fn test_should_wake_agrees_with_new_round() {
+    for _ in 0..100 {
+        let mut shared = shared_state(SelectFor::None);
+        shared.keys = SigningKeyPair::generate();
+        shared.round_params.seed = RoundSeed::generate();
+        shared.round_params.sum = 0.3;
+        shared.round_params.update = 0.5;
+        shared.round_params.eligibility_salt = b"deployment".to_vec();
+        let summary = RoundSummary {
+            round_id: 1,
+            seed: shared.round_params.seed.clone(),
+            round_token: shared.round_params.seed.token(),
+            phase: "Sum".to_string(),
+            sum: shared.round_params.sum,
+            update: shared.round_params.update,
+            eligibility_salt: shared.round_params.eligibility_salt.clone(),
+            crypto_suite: shared.round_params.crypto_suite,
+        };
+        let expected = should_wake(&shared.keys.secret, &summary);
+
+        // a participant is never woken up for a round with an unsupported crypto suite
+        let unsupported = RoundSummary {
+            crypto_suite: CryptoSuite::new(u8::MAX),
+            ..summary
+        };
+        assert_eq!(should_wake(&shared.keys.secret, &unsupported), Task::None);
+
+        let mut io = MockIO::new();
+        io.expect_notify_new_round().return_const(());
+        io.expect_notify_sum().return_const(());
+        io.expect_notify_update().return_const(());
+        io.expect_notify_load_model().return_const(());
+        io.expect_notify_idle().return_const(());
+        let phase: Phase<NewRound> =
+            State::new(shared, Box::new(NewRound)).into_phase(Box::new(io));
+        let task = match Step::step(phase).await {
+            TransitionOutcome::Complete(StateMachine::Sum(_)) => Task::Sum,
+            TransitionOutcome::Complete(StateMachine::Update(_)) => Task::Update,
+            TransitionOutcome::Complete(StateMachine::Awaiting(_)) => Task::None,
+            outcome => panic!("unexpected transition: {:?}", outcome),
+        };
+        assert_eq!(task, expected);
+    }
+}
+
 /// Instantiate a new round phase.
 ///
 /// - `task` is the task we want the simulated participant to be selected for. If you want a
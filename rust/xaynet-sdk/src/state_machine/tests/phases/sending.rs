@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use mockall::{predicate::eq, Sequence};
+use xaynet_core::{
+    common::{RoundSeed, RoundSubmissionStatus, SubmissionStatus},
+    crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed},
+    ParticipantPublicKey,
+};
+
+use crate::{
+    client::ClientError,
+    settings::RetrySettings,
+    state_machine::{
+        tests::utils::{round_params, shared_state, SelectFor},
+        IntoPhase,
+        MockIO,
+        Phase,
+        SendingSum,
+        State,
+        StateMachine,
+        Sum,
+        TransitionOutcome,
+    },
+    unwrap_as,
+    unwrap_step,
+    Failure,
+};
+
+/// Instantiate a sum sending phase with the given retry settings.
+async fn make_phase(retry: RetrySettings) -> Phase<SendingSum> {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.retry = retry;
+    let ephm_keys = EncryptKeyPair::derive_from_seed(&EncryptKeySeed::zeroed());
+    let sk = &shared.keys.secret;
+    let seed = shared.round_params.seed.as_slice();
+    let sum_signature = sk.sign_detached(&[seed, b"sum"].concat());
+    let sum = Box::new(Sum {
+        ephm_keys,
+        sum_signature,
+    });
+
+    let mut mock = MockIO::new();
+    mock.expect_notify_sum().times(1).return_const(());
+    let mut phase: Phase<Sum> = State::new(shared, sum).into_phase(Box::new(mock));
+    phase.check_io_mock();
+    unwrap_step!(phase, complete, sending_sum)
+}
+
+fn no_backoff() -> RetrySettings {
+    RetrySettings {
+        initial_backoff: Duration::from_secs(0),
+        max_backoff: Duration::from_secs(0),
+        deadline: Duration::from_secs(3600),
+    }
+}
+
+fn transient_error() -> Box<ClientError> {
+    Box::new(ClientError::Http("connection reset".to_string()))
+}
+
+#[tokio::test]
+async fn test_retry_after_transient_failures() {
+    let mut phase = make_phase(no_backoff()).await;
+    let mut seq = Sequence::new();
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(|_| Err(transient_error()));
+        mock.expect_send_message()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        // the coordinator didn't record the message, so it is resent
+        mock.expect_get_submission_status()
+            .times(2)
+            .returning(|_| Ok(None));
+    });
+
+    let phase = unwrap_step!(phase, pending, sending_sum);
+    let phase = unwrap_step!(phase, pending, sending_sum);
+    let mut phase = unwrap_step!(phase, complete, sending_sum);
+    phase.check_io_mock();
+}
+
+/// Make the coordinator report the given submission status of the participant `pk`.
+fn expect_submission_status(
+    mock: &mut MockIO,
+    pk: ParticipantPublicKey,
+    seed: RoundSeed,
+    sum: bool,
+) {
+    mock.expect_get_submission_status()
+        .with(eq(pk))
+        .times(1)
+        .returning(move |_| {
+            Ok(Some(RoundSubmissionStatus {
+                seed: seed.clone(),
+                status: SubmissionStatus {
+                    sum,
+                    ..SubmissionStatus::default()
+                },
+            }))
+        });
+}
+
+#[tokio::test]
+async fn test_recorded_message_is_not_resent() {
+    let mut phase = make_phase(no_backoff()).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(transient_error()));
+    });
+    let mut phase = unwrap_step!(phase, pending, sending_sum);
+    phase.check_io_mock();
+
+    // The message reached the coordinator but the response got lost:
+    // the message is considered sent
+    let pk = phase.state.shared.keys.public;
+    let seed = phase.state.shared.round_params.seed.clone();
+    phase.with_io_mock(|mock| expect_submission_status(mock, pk, seed, true));
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+}
+
+#[tokio::test]
+async fn test_unrecorded_message_is_resent() {
+    let round_seed = round_params(SelectFor::Sum).seed;
+    // The message isn't recorded, or only in another round
+    for (seed, sum) in [(round_seed, false), (RoundSeed::generate(), true)] {
+        let mut phase = make_phase(no_backoff()).await;
+        phase.with_io_mock(|mock| {
+            mock.expect_send_message()
+                .times(1)
+                .returning(|_| Err(transient_error()));
+        });
+        let mut phase = unwrap_step!(phase, pending, sending_sum);
+        phase.check_io_mock();
+
+        let pk = phase.state.shared.keys.public;
+        phase.with_io_mock(|mock| {
+            expect_submission_status(mock, pk, seed, sum);
+            mock.expect_send_message().times(1).returning(|_| Ok(()));
+        });
+        let mut phase = unwrap_step!(phase, complete, sending_sum);
+        phase.check_io_mock();
+    }
+}
+
+#[tokio::test]
+async fn test_retry_waits_for_backoff() {
+    let retry = RetrySettings {
+        initial_backoff: Duration::from_secs(3600),
+        max_backoff: Duration::from_secs(3600),
+        ..no_backoff()
+    };
+    let mut phase = make_phase(retry).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(transient_error()));
+    });
+    let phase = unwrap_step!(phase, pending, sending_sum);
+
+    // The backoff didn't elapse yet, so the message is not sent again
+    let mut phase = unwrap_step!(phase, pending, sending_sum);
+    phase.check_io_mock();
+}
+
+#[tokio::test]
+async fn test_retry_deadline() {
+    let retry = RetrySettings {
+        deadline: Duration::from_secs(0),
+        ..no_backoff()
+    };
+    let mut phase = make_phase(retry).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(transient_error()));
+        mock.expect_notify_failed()
+            .with(eq(Failure::MessageExpired))
+            .times(1)
+            .return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let _phase = unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_permanent_rejection_is_not_retried() {
+    let mut phase = make_phase(no_backoff()).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(Box::new(ClientError::Rejected(413))));
+        mock.expect_notify_failed()
+            .with(eq(Failure::MessageRejected))
+            .times(1)
+            .return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let _phase = unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_banned_participant_stops_participating() {
+    let mut phase = make_phase(no_backoff()).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(Box::new(ClientError::Banned)));
+        mock.expect_notify_failed()
+            .with(eq(Failure::Banned))
+            .times(1)
+            .return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, banned);
+    phase.check_io_mock();
+
+    // The banned participant neither checks for new rounds nor
+    // leaves the banned phase
+    let state_machine = StateMachine::from(phase);
+    let state_machine = unwrap_as!(state_machine.transition().await, TransitionOutcome::Pending);
+    let _phase = unwrap_as!(state_machine, StateMachine::Banned);
+}
+
+#[tokio::test]
+async fn test_new_round_drops_pending_message() {
+    let mut phase = make_phase(no_backoff()).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(transient_error()));
+    });
+    let mut phase = unwrap_step!(phase, pending, sending_sum);
+    phase.check_io_mock();
+
+    // A new round started before the message could be resent: the
+    // state machine drops it, notifies the failure and starts over
+    phase.with_io_mock(|mock| {
+        let mut new_round_params = round_params(SelectFor::Sum);
+        new_round_params.seed = RoundSeed::generate();
+        mock.expect_get_round_params()
+            .times(1)
+            .returning(move || Ok(new_round_params.clone()));
+        mock.expect_notify_failed()
+            .with(eq(Failure::MessageOutdated))
+            .times(1)
+            .return_const(());
+        mock.expect_notify_new_round().times(1).return_const(());
+    });
+    let outcome = phase.step().await;
+    let _phase = unwrap_as!(
+        unwrap_as!(outcome, TransitionOutcome::Complete),
+        StateMachine::NewRound
+    );
+}
@@ -1,5 +1,9 @@
+use mockall::predicate::eq;
 use thiserror::Error;
-use xaynet_core::crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed};
+use xaynet_core::{
+    common::RoundSeed,
+    crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed, SecretEncryptKey},
+};
 
 use crate::{
     state_machine::{
@@ -12,11 +16,16 @@ use crate::{
         Sum,
     },
     unwrap_step,
+    Failure,
 };
 
 /// Instantiate a sum phase.
 fn make_phase(io: MockIO) -> Phase<Sum> {
-    let shared = shared_state(SelectFor::Sum);
+    make_phase_with_shared(io, shared_state(SelectFor::Sum))
+}
+
+/// Instantiate a sum phase with the given shared state.
+fn make_phase_with_shared(io: MockIO, shared: Box<SharedState>) -> Phase<Sum> {
     let sum = make_sum(&shared);
 
     // Check IntoPhase<Sum> implementation
@@ -45,9 +54,58 @@ fn make_sum(shared: &SharedState) -> Box<Sum> {
 async fn test_phase() {
     let io = MockIO::new();
     let phase = make_phase(io);
+    let ephm_pk = phase.state.private.ephm_keys.public;
+    let seed = phase.state.shared.round_params.seed.clone();
+    let phase = unwrap_step!(phase, complete, sending_sum);
+    assert_eq!(phase.state.shared.last_ephm_pk, Some((seed, ephm_pk)));
+}
+
+/// Expect that the participant refuses to send the sum message.
+fn expect_insecure_ephm_keys() -> MockIO {
+    let mut io = MockIO::new();
+    io.expect_notify_failed()
+        .with(eq(Failure::InsecureEphemeralKeys))
+        .times(1)
+        .return_const(());
+    io.expect_notify_idle().times(1).return_const(());
+    io
+}
+
+#[tokio::test]
+async fn test_ephm_keys_of_previous_round() {
+    let mut shared = shared_state(SelectFor::Sum);
+    let ephm_pk = make_sum(&shared).ephm_keys.public;
+    let previous_seed = RoundSeed::from_slice_unchecked(&[1; RoundSeed::LENGTH]);
+    shared.last_ephm_pk = Some((previous_seed, ephm_pk));
+
+    let phase = make_phase_with_shared(expect_insecure_ephm_keys(), shared);
+    let _phase = unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_ephm_keys_of_same_round() {
+    // deterministic ephemeral keys are the same if the participant runs the sum task of a round
+    // again, e.g. after it was restored
+    let mut shared = shared_state(SelectFor::Sum);
+    let ephm_pk = make_sum(&shared).ephm_keys.public;
+    shared.last_ephm_pk = Some((shared.round_params.seed.clone(), ephm_pk));
+
+    let phase = make_phase_with_shared(MockIO::new(), shared);
     let _phase = unwrap_step!(phase, complete, sending_sum);
 }
 
+#[tokio::test]
+async fn test_ephm_keys_of_signing_keys() {
+    let mut phase = make_phase(expect_insecure_ephm_keys());
+    let signing_seed = &phase.state.shared.keys.secret.as_slice()[..SecretEncryptKey::LENGTH];
+    let secret = SecretEncryptKey::from_slice_unchecked(signing_seed);
+    phase.state.private.ephm_keys = EncryptKeyPair {
+        public: secret.public_key(),
+        secret,
+    };
+    let _phase = unwrap_step!(phase, complete, awaiting);
+}
+
 #[derive(Error, Debug)]
 #[error("error")]
 struct DummyErr;
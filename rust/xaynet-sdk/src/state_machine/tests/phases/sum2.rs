@@ -1,11 +1,24 @@
-use mockall::Sequence;
+use mockall::{predicate::eq, Sequence};
 use xaynet_core::{
+    common::{RoundSeed, RoundSubmissionStatus, SubmissionStatus},
     crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed, PublicEncryptKey},
-    mask::{FromPrimitives, MaskConfigPair, MaskObject, MaskSeed, Masker, Model, Scalar},
+    mask::{
+        Aggregation,
+        FromPrimitives,
+        MaskConfigPair,
+        MaskObject,
+        MaskSeed,
+        Masker,
+        Model,
+        Scalar,
+    },
     UpdateSeedDict,
+    VersionedSeeds,
 };
 
 use crate::{
+    budget::ComputeTask,
+    client::ClientError,
     state_machine::{
         tests::utils::{shared_state, SelectFor, SigningKeyGenerator},
         IntoPhase,
@@ -18,6 +31,7 @@ use crate::{
     },
     unwrap_progress_continue,
     unwrap_step,
+    Failure,
 };
 
 /// Instantiate a sum phase.
@@ -42,6 +56,8 @@ fn make_sum2(shared: &SharedState) -> Box<Sum2> {
         ephm_keys,
         sum_signature: signature,
         seed_dict: None,
+        seed_dict_version: None,
+        seed_dict_refetched: false,
         seeds: None,
         mask: None,
     })
@@ -50,12 +66,7 @@ fn make_sum2(shared: &SharedState) -> Box<Sum2> {
 fn make_seed_dict(mask_config: MaskConfigPair, ephm_pk: PublicEncryptKey) -> UpdateSeedDict {
     let (seed, _mask) = make_masked_model(mask_config);
     let mut key_gen = SigningKeyGenerator::new();
-    let mut dict = UpdateSeedDict::new();
-    for _ in 0..4 {
-        let pk = key_gen.next().public;
-        dict.insert(pk, seed.encrypt(&ephm_pk));
-    }
-    dict
+    UpdateSeedDict::from_storage((0..4).map(|_| (key_gen.next().public, seed.encrypt(&ephm_pk))))
 }
 
 fn make_model() -> Model {
@@ -80,11 +91,20 @@ async fn step1_fetch_seed_dict(mut phase: Phase<Sum2>) -> Phase<Sum2> {
             .times(1)
             .in_sequence(&mut seq)
             .returning(|_| Ok(None));
+        // The participant hasn't been evicted meanwhile
+        mock.expect_get_submission_status()
+            .times(1)
+            .returning(|_| Ok(None));
         // The second time, return it
         mock.expect_get_seeds()
             .times(1)
             .in_sequence(&mut seq)
-            .returning(move |_| Ok(Some(make_seed_dict(mask_config, ephm_pk))));
+            .returning(move |_| {
+                Ok(Some(VersionedSeeds {
+                    version: 7,
+                    seeds: make_seed_dict(mask_config, ephm_pk),
+                }))
+            });
     });
 
     // First time: no progress should be made, since we didn't
@@ -94,6 +114,9 @@ async fn step1_fetch_seed_dict(mut phase: Phase<Sum2>) -> Phase<Sum2> {
     // Second time: now the state machine should have made progress
     let phase = unwrap_step!(phase, complete, sum2);
 
+    // The version is the one served by the coordinator
+    assert_eq!(phase.state.private.seed_dict_version, Some(7));
+
     // Calling `fetch_seed_dict` again should return Progress::Continue
     let mut phase = unwrap_progress_continue!(phase, fetch_seed_dict, async);
     phase.check_io_mock();
@@ -113,6 +136,7 @@ async fn step3_aggregate_masks(phase: Phase<Sum2>) -> Phase<Sum2> {
     assert!(phase.state.private.mask.is_some());
     // Make sure this steps consumes the seeds.
     assert!(phase.state.private.seeds.is_none());
+    assert!(phase.state.shared.last_costs.sum2.is_some());
     phase
 }
 
@@ -129,3 +153,194 @@ async fn test_phase() {
     let phase = step3_aggregate_masks(phase).await;
     let _phase = step4_into_sending_phase(phase).await;
 }
+
+#[tokio::test]
+async fn test_evicted() {
+    let mut phase = make_phase();
+    let pk = phase.state.shared.keys.public;
+    let seed = phase.state.shared.round_params.seed.clone();
+    phase.with_io_mock(move |mock| {
+        // The seeds never become available for an evicted sum participant
+        mock.expect_get_seeds().times(1).returning(|_| Ok(None));
+        mock.expect_get_submission_status()
+            .with(eq(pk))
+            .times(1)
+            .returning(move |_| {
+                Ok(Some(RoundSubmissionStatus {
+                    seed: seed.clone(),
+                    status: SubmissionStatus {
+                        evicted: true,
+                        ..SubmissionStatus::default()
+                    },
+                }))
+            });
+        mock.expect_notify_failed()
+            .with(eq(Failure::Evicted))
+            .times(1)
+            .return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    phase.check_io_mock();
+}
+
+#[tokio::test]
+async fn test_evicted_in_previous_round() {
+    let mut phase = make_phase();
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_seeds().times(1).returning(|_| Ok(None));
+        // The submission status belongs to another round
+        mock.expect_get_submission_status().times(1).returning(|_| {
+            Ok(Some(RoundSubmissionStatus {
+                seed: RoundSeed::generate(),
+                status: SubmissionStatus {
+                    evicted: true,
+                    ..SubmissionStatus::default()
+                },
+            }))
+        });
+        mock.expect_notify_failed().never();
+    });
+    let mut phase = unwrap_step!(phase, pending, sum2);
+    phase.check_io_mock();
+}
+
+/// Fetch the seed dict at once and go through the sum2 phase until the sending phase.
+async fn into_sending_phase(mut phase: Phase<Sum2>) -> Phase<SendingSum2> {
+    let mask_config = phase.state.shared.round_params.mask_config;
+    let ephm_pk = phase.state.private.ephm_keys.public;
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_seeds().times(1).returning(move |_| {
+            Ok(Some(VersionedSeeds {
+                version: 7,
+                seeds: make_seed_dict(mask_config, ephm_pk),
+            }))
+        });
+    });
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    let phase = step2_decrypt_seeds(phase).await;
+    let phase = step3_aggregate_masks(phase).await;
+    step4_into_sending_phase(phase).await
+}
+
+/// Pretend that the coordinator rejects the sum2 message because of an outdated seed dict.
+fn reject_stale_seed_dict(phase: &mut Phase<SendingSum2>) {
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message()
+            .times(1)
+            .returning(|_| Err(Box::new(ClientError::StaleSeedDict)));
+    });
+}
+
+#[tokio::test]
+async fn test_stale_seed_dict_refetch() {
+    let phase = make_phase();
+    let mut phase = into_sending_phase(phase).await;
+
+    // The rejected message makes the state machine go back to the
+    // sum2 phase to fetch the seed dict again
+    reject_stale_seed_dict(&mut phase);
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    assert!(phase.state.private.seed_dict_refetched);
+    assert!(phase.state.private.seed_dict_version.is_none());
+    assert!(phase.state.private.mask.is_none());
+
+    // This time the message is accepted
+    let mut phase = into_sending_phase(phase).await;
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message().times(1).returning(|_| Ok(()));
+    });
+    let mut phase = unwrap_step!(phase, complete, sending_sum2);
+    phase.check_io_mock();
+    phase.with_io_mock(|mock| {
+        mock.expect_notify_sum2_sent().times(1).return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let _phase = unwrap_step!(phase, complete, awaiting);
+}
+
+#[tokio::test]
+async fn test_stale_seed_dict_refetch_once() {
+    let phase = make_phase();
+    let mut phase = into_sending_phase(phase).await;
+    reject_stale_seed_dict(&mut phase);
+    let phase = unwrap_step!(phase, complete, sum2);
+    let mut phase = into_sending_phase(phase).await;
+
+    // The seed dict has already been refetched: the state machine
+    // does not go back to the sum2 phase again
+    reject_stale_seed_dict(&mut phase);
+    let mut phase = unwrap_step!(phase, pending, sending_sum2);
+    phase.check_io_mock();
+}
+
+#[tokio::test]
+async fn test_round_bound_masks() {
+    let mut phase = make_phase();
+    let round_params = &mut phase.state.shared.round_params;
+    round_params.round_bound_masks = true;
+    round_params.seed = RoundSeed::generate();
+    round_params.model_length = 4;
+    let round_params = round_params.clone();
+
+    let seed = MaskSeed::generate();
+    let encrypted_seed = seed.encrypt(&phase.state.private.ephm_keys.public);
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_seeds().times(1).returning(move |_| {
+            let dict = vec![(
+                SigningKeyGenerator::new().next().public,
+                encrypted_seed.clone(),
+            )];
+            Ok(Some(VersionedSeeds {
+                version: 1,
+                seeds: UpdateSeedDict::from_storage(dict),
+            }))
+        });
+    });
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    let phase = step2_decrypt_seeds(phase).await;
+    let phase = step3_aggregate_masks(phase).await;
+
+    // the mask is derived from the seed and the round seed
+    let mask =
+        seed.derive_mask_for_round(4, round_params.mask_config, round_params.seed.as_slice());
+    let mut expected = Aggregation::new(round_params.mask_config, 4);
+    expected.aggregate(mask);
+    assert_eq!(phase.state.private.mask, Some(expected.into()));
+}
+
+#[tokio::test]
+async fn test_sum2_budget_exceeded() {
+    let mut phase = make_phase();
+    phase.state.shared.round_params.model_length = 4;
+    // an artificially tiny budget which is exceeded by the first mask
+    phase.state.shared.compute_budget.max_sum2_millis = Some(0);
+    let mask_config = phase.state.shared.round_params.mask_config;
+    let ephm_pk = phase.state.private.ephm_keys.public;
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_seeds().times(1).returning(move |_| {
+            Ok(Some(VersionedSeeds {
+                version: 7,
+                seeds: make_seed_dict(mask_config, ephm_pk),
+            }))
+        });
+    });
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    let mut phase = step2_decrypt_seeds(phase).await;
+
+    phase.with_io_mock(|mock| {
+        mock.expect_notify_budget_exceeded()
+            .withf(|exceeded| exceeded.task == ComputeTask::Sum2 && exceeded.cost > exceeded.budget)
+            .times(1)
+            .return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+        mock.expect_send_message().never();
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    assert!(phase.state.shared.last_costs.sum2.is_none());
+    phase.check_io_mock();
+}
@@ -1,13 +1,19 @@
-use mockall::Sequence;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mockall::{predicate::eq, Sequence};
 use xaynet_core::{
-    crypto::ByteObject,
+    common::{RoundSeed, RoundSumDict},
+    crypto::{ByteObject, CryptoSuite, EncryptKeySeed},
     mask::{FromPrimitives, Model},
+    message::{Message, Payload},
+    LocalSeedDict,
     SumDict,
 };
 
 use crate::{
     save_and_restore,
     state_machine::{
+        phases::UpdateValidationError,
         tests::utils::{shared_state, EncryptKeyGenerator, SelectFor, SigningKeyGenerator},
         IntoPhase,
         MockIO,
@@ -19,11 +25,18 @@ use crate::{
     },
     unwrap_progress_continue,
     unwrap_step,
+    Failure,
 };
 
-/// Instantiate a sum phase.
+/// Instantiate an update phase.
 fn make_phase() -> Phase<Update> {
-    let shared = shared_state(SelectFor::Update);
+    make_phase_for(SelectFor::Update)
+}
+
+/// Instantiate an update phase with round parameters that select for the given task.
+fn make_phase_for(task: SelectFor) -> Phase<Update> {
+    let mut shared = shared_state(task);
+    shared.round_params.model_length = make_model().len();
     let update = make_update(&shared);
 
     // Check IntoPhase<Update> implementation
@@ -39,8 +52,9 @@ fn make_phase() -> Phase<Update> {
 fn make_update(shared: &SharedState) -> Box<Update> {
     let sk = &shared.keys.secret;
     let seed = shared.round_params.seed.as_slice();
-    let sum_signature = sk.sign_detached(&[seed, b"sum"].concat());
-    let update_signature = sk.sign_detached(&[seed, b"update"].concat());
+    let suite = shared.round_params.crypto_suite;
+    let sum_signature = sk.sign_detached(&suite.task_signature_payload(seed, b"sum"));
+    let update_signature = sk.sign_detached(&suite.task_signature_payload(seed, b"update"));
     Box::new(Update {
         sum_signature,
         update_signature,
@@ -48,6 +62,7 @@ fn make_update(shared: &SharedState) -> Box<Update> {
         seed_dict: None,
         model: None,
         mask: None,
+        model_checksum: None,
     })
 }
 
@@ -56,6 +71,23 @@ fn make_model() -> Model {
     Model::from_primitives(weights.into_iter()).unwrap()
 }
 
+/// A local model which is overwritten by the application after it has been read once.
+struct MutatingModel {
+    reads: AtomicUsize,
+    before: Model,
+    after: Model,
+}
+
+impl AsRef<Model> for MutatingModel {
+    fn as_ref(&self) -> &Model {
+        if self.reads.fetch_add(1, Ordering::SeqCst) == 0 {
+            &self.before
+        } else {
+            &self.after
+        }
+    }
+}
+
 fn make_sum_dict() -> SumDict {
     let mut dict = SumDict::new();
 
@@ -68,8 +100,16 @@ fn make_sum_dict() -> SumDict {
     dict
 }
 
+fn make_round_sum_dict(seed: &RoundSeed) -> RoundSumDict {
+    RoundSumDict {
+        seed: seed.clone(),
+        sum_dict: make_sum_dict(),
+    }
+}
+
 async fn step1_fetch_sum_dict(mut phase: Phase<Update>) -> Phase<Update> {
-    phase.with_io_mock(|mock| {
+    let seed = phase.state.shared.round_params.seed.clone();
+    phase.with_io_mock(move |mock| {
         let mut seq = Sequence::new();
         // The first time the state machine fetches the sum dict,
         // pretend it's not published yet
@@ -81,7 +121,7 @@ async fn step1_fetch_sum_dict(mut phase: Phase<Update>) -> Phase<Update> {
         mock.expect_get_sums()
             .times(1)
             .in_sequence(&mut seq)
-            .returning(|| Ok(Some(make_sum_dict())));
+            .returning(move || Ok(Some(make_round_sum_dict(&seed))));
     });
 
     // First time: no progress should be made, since we didn't
@@ -128,21 +168,41 @@ async fn step2_load_model(mut phase: Phase<Update>) -> Phase<Update> {
 
 async fn step3_mask_model(phase: Phase<Update>) -> Phase<Update> {
     let phase = unwrap_step!(phase, complete, update);
+    assert!(phase.state.shared.last_costs.masking.is_some());
     let mut phase = unwrap_progress_continue!(phase, mask_model);
     phase.check_io_mock();
     phase
 }
 
-async fn step4_build_seed_dict(phase: Phase<Update>) -> Phase<Update> {
-    let phase = unwrap_step!(phase, complete, update);
-    let mut phase = unwrap_progress_continue!(phase, build_seed_dict);
-    phase.check_io_mock();
+async fn step4_into_sending_phase(phase: Phase<Update>) -> Phase<SendingUpdate> {
+    let phase = unwrap_step!(phase, complete, sending_update);
     phase
 }
 
-async fn step5_into_sending_phase(phase: Phase<Update>) -> Phase<SendingUpdate> {
-    let phase = unwrap_step!(phase, complete, sending_update);
-    phase
+/// Sends the update message and checks that its local seed dictionary covers the sum dictionary.
+async fn step5_send_update(mut phase: Phase<SendingUpdate>) {
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message().times(1).returning(|data| {
+            let (pk, sk) = EncryptKeySeed::zeroed().derive_encrypt_key_pair();
+            let data = sk.decrypt(&data, &pk).unwrap();
+            let message = Message::from_byte_slice(&data).unwrap();
+            if let Payload::Update(update) = message.payload {
+                assert!(update.local_seed_dict.matches(&make_sum_dict()));
+            } else {
+                panic!("not an update message");
+            }
+            Ok(())
+        });
+    });
+    let mut phase = unwrap_step!(phase, complete, sending_update);
+    phase.check_io_mock();
+
+    phase.with_io_mock(|mock| {
+        mock.expect_notify_update_sent().times(1).return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    phase.check_io_mock();
 }
 
 #[tokio::test]
@@ -151,8 +211,130 @@ async fn test_update_phase() {
     let phase = step1_fetch_sum_dict(phase).await;
     let phase = step2_load_model(phase).await;
     let phase = step3_mask_model(phase).await;
-    let phase = step4_build_seed_dict(phase).await;
-    let _phase = step5_into_sending_phase(phase).await;
+    let phase = step4_into_sending_phase(phase).await;
+    step5_send_update(phase).await;
+}
+
+#[tokio::test]
+async fn test_built_seed_dict() {
+    let phase = make_phase();
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let mut phase = step3_mask_model(phase).await;
+
+    // the local seed dictionary of a state saved by an earlier version is sent as is
+    let mask_seed = &phase.state.private.mask.as_ref().unwrap().0;
+    let seed_dict = LocalSeedDict::new(&make_sum_dict(), mask_seed);
+    phase.state.private.sum_dict = None;
+    phase.state.private.seed_dict = Some(seed_dict);
+    let phase = step4_into_sending_phase(phase).await;
+    step5_send_update(phase).await;
+}
+
+#[tokio::test]
+async fn test_stale_sum_dict() {
+    let mut phase = make_phase();
+    phase.with_io_mock(|mock| {
+        mock.expect_get_sums()
+            .times(1)
+            .returning(|| Ok(Some(make_round_sum_dict(&RoundSeed::generate()))));
+    });
+    let phase = unwrap_step!(phase, complete, update);
+    let phase = step2_load_model(phase).await;
+    let mut phase = step3_mask_model(phase).await;
+
+    // the sum dictionary belongs to another round, hence the participant doesn't build the
+    // local seed dictionary and drops out
+    phase.with_io_mock(|mock| {
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    phase.check_io_mock();
+}
+
+#[tokio::test]
+async fn test_self_validate_update() {
+    let phase = make_phase();
+    assert_eq!(
+        phase.self_validate_update(&phase.state.shared.round_params, &make_sum_dict()),
+        Err(UpdateValidationError::Incomplete),
+    );
+
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let mut phase = step3_mask_model(phase).await;
+    let round_params = phase.state.shared.round_params.clone();
+    assert!(phase
+        .self_validate_update(&round_params, &make_sum_dict())
+        .is_ok());
+
+    // a built seed dictionary must cover exactly the sum participants
+    let mask_seed = &phase.state.private.mask.as_ref().unwrap().0;
+    let seed_dict = LocalSeedDict::new(&make_sum_dict(), mask_seed);
+    phase.state.private.seed_dict = Some(seed_dict);
+    let mut sum_dict = make_sum_dict();
+    let mut signing_keys = SigningKeyGenerator::new();
+    signing_keys.next();
+    sum_dict.remove(&signing_keys.next().public);
+    assert_eq!(
+        phase.self_validate_update(&round_params, &sum_dict),
+        Err(UpdateValidationError::SeedDictMismatch),
+    );
+
+    // the task signatures must match the crypto suite
+    let mut round_params = round_params;
+    round_params.crypto_suite = CryptoSuite::BLAKE3;
+    assert_eq!(
+        phase.self_validate_update(&round_params, &make_sum_dict()),
+        Err(UpdateValidationError::InvalidSignature),
+    );
+    round_params.crypto_suite = CryptoSuite::SHA256;
+
+    // the masked model must match the model length
+    round_params.model_length += 1;
+    assert_eq!(
+        phase.self_validate_update(&round_params, &make_sum_dict()),
+        Err(UpdateValidationError::InvalidMaskedModel),
+    );
+}
+
+#[tokio::test]
+async fn test_not_update_eligible() {
+    let phase = make_phase_for(SelectFor::None);
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let mut phase = step3_mask_model(phase).await;
+    let round_params = phase.state.shared.round_params.clone();
+
+    // the coordinator would reject the update, hence the participant doesn't send it
+    phase.with_io_mock(|mock| {
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    phase.check_io_mock();
+
+    let phase = make_phase_for(SelectFor::Sum);
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let phase = step3_mask_model(phase).await;
+    assert_eq!(
+        phase.self_validate_update(&round_params, &SumDict::new()),
+        Err(UpdateValidationError::NotUpdateEligible),
+    );
+    let sum_round_params = phase.state.shared.round_params.clone();
+    assert_eq!(
+        phase.self_validate_update(&sum_round_params, &SumDict::new()),
+        Err(UpdateValidationError::SumEligible),
+    );
+
+    // unless the coordinator allows sum-eligible participants to update
+    let mut dual_round_params = sum_round_params;
+    dual_round_params.update = 1.0;
+    dual_round_params.allow_sum_eligible_updates = true;
+    assert_eq!(
+        phase.self_validate_update(&dual_round_params, &SumDict::new()),
+        Ok(()),
+    );
 }
 
 #[tokio::test]
@@ -185,9 +367,49 @@ async fn test_save_and_restore() {
     });
     let phase = save_and_restore!(phase, Update);
 
-    let mut phase = step4_build_seed_dict(phase).await;
+    let phase = step4_into_sending_phase(phase).await;
+    let phase = save_and_restore!(phase, SendingUpdate);
+    step5_send_update(phase).await;
+}
+
+#[tokio::test]
+async fn test_model_changed_while_masking() {
+    let phase = make_phase();
+    let mut phase = step1_fetch_sum_dict(phase).await;
+
     phase.with_io_mock(|mock| {
-        mock.expect_notify_update().times(1).return_const(());
+        mock.expect_load_model().times(1).returning(|| {
+            let weights: Vec<f32> = vec![1.1, 2.2, 3.3, 5.5];
+            Ok(Some(Box::new(MutatingModel {
+                reads: AtomicUsize::new(0),
+                before: make_model(),
+                after: Model::from_primitives(weights.into_iter()).unwrap(),
+            })))
+        });
     });
-    let _phase = save_and_restore!(phase, Update);
+    let mut phase = unwrap_step!(phase, complete, update);
+    phase.check_io_mock();
+
+    // the model differs from its checksum after masking, hence the participant drops out
+    phase.with_io_mock(|mock| {
+        mock.expect_notify_failed()
+            .with(eq(Failure::ModelCorrupted))
+            .times(1)
+            .return_const(());
+        mock.expect_notify_idle().times(1).return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    phase.check_io_mock();
+}
+
+#[tokio::test]
+async fn test_model_checksum() {
+    let phase = make_phase();
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let phase = step3_mask_model(phase).await;
+    assert_eq!(
+        phase.state.private.model_checksum,
+        Some(make_model().checksum()),
+    );
 }
@@ -0,0 +1,352 @@
+use std::{
+    error::Error,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use mockall::{predicate::eq, Sequence};
+use xaynet_core::{
+    common::{PhaseTimeline, RoundParameters, RoundSeed, RoundSumDict},
+    crypto::{ByteObject, Sha256},
+    mask::{FromPrimitives, Model},
+    SumDict,
+};
+
+use crate::{
+    client::ClientError,
+    state_machine::{
+        tests::utils::{
+            round_params,
+            shared_state,
+            EncryptKeyGenerator,
+            SelectFor,
+            SigningKeyGenerator,
+        },
+        Awaiting,
+        IntoPhase,
+        MockIO,
+        Phase,
+        SerializableState,
+        State,
+        StateFormatError,
+        StateMachine,
+        TransitionOutcome,
+        STATE_FORMAT_VERSION,
+    },
+    unwrap_as,
+    Failure,
+};
+
+fn make_model() -> Model {
+    let weights: Vec<f32> = vec![1.1, 2.2, 3.3, 4.4];
+    Model::from_primitives(weights.into_iter()).unwrap()
+}
+
+fn make_sum_dict() -> SumDict {
+    let mut dict = SumDict::new();
+    dict.insert(
+        SigningKeyGenerator::new().next().public,
+        EncryptKeyGenerator::new().next().public,
+    );
+    dict
+}
+
+/// Instantiate an awaiting phase and set up the mock to publish a new round which selects the
+/// participant for the update task. The `notify` closure sets the expected notifications.
+fn make_state_machine<F>(send_result: fn() -> Result<(), ClientError>, notify: F) -> StateMachine
+where
+    F: FnOnce(&mut MockIO, &mut Sequence),
+{
+    let mut shared = shared_state(SelectFor::None);
+    shared.round_params.model_length = make_model().len();
+
+    let mut mock = MockIO::new();
+    mock.expect_notify_idle().times(1).return_const(());
+    let mut phase: Phase<Awaiting> =
+        State::new(shared, Box::new(Awaiting)).into_phase(Box::new(mock));
+    phase.check_io_mock();
+
+    let mut new_round_params = round_params(SelectFor::Update);
+    new_round_params.seed = RoundSeed::generate();
+    new_round_params.model_length = make_model().len();
+    let seed = new_round_params.seed.clone();
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_round_params()
+            .returning(move || Ok(new_round_params.clone()));
+        mock.expect_get_sums().returning(move || {
+            Ok(Some(RoundSumDict {
+                seed: seed.clone(),
+                sum_dict: make_sum_dict(),
+            }))
+        });
+        mock.expect_load_model()
+            .returning(|| Ok(Some(Box::new(make_model()))));
+        mock.expect_send_message()
+            .returning(move |_| send_result().map_err(|e| Box::new(e) as Box<dyn Error>));
+
+        let mut seq = Sequence::new();
+        notify(mock, &mut seq);
+    });
+    phase.into()
+}
+
+/// Drive the state machine until it can't make progress anymore.
+async fn run_until_stuck(mut state_machine: StateMachine) -> StateMachine {
+    for _ in 0..100 {
+        state_machine = match state_machine.transition().await {
+            TransitionOutcome::Complete(state_machine) => state_machine,
+            TransitionOutcome::Pending(state_machine) => return state_machine,
+        };
+    }
+    panic!("the state machine doesn't get stuck");
+}
+
+#[tokio::test]
+async fn test_update_round_events() {
+    let state_machine = make_state_machine(
+        || Ok(()),
+        |mock, seq| {
+            mock.expect_notify_new_round()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_update()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_load_model()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_update_sent()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_idle()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+        },
+    );
+
+    // dropping the state machine runs the checks of the expected notifications
+    let state_machine = run_until_stuck(state_machine).await;
+    let _phase = unwrap_as!(state_machine, StateMachine::Awaiting);
+}
+
+#[tokio::test]
+async fn test_rejected_update_round_events() {
+    let state_machine = make_state_machine(
+        || Err(ClientError::Rejected(413)),
+        |mock, seq| {
+            mock.expect_notify_new_round()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_update()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_load_model()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_failed()
+                .with(eq(Failure::MessageRejected))
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+            mock.expect_notify_idle()
+                .times(1)
+                .in_sequence(seq)
+                .return_const(());
+        },
+    );
+
+    let state_machine = run_until_stuck(state_machine).await;
+    let _phase = unwrap_as!(state_machine, StateMachine::Awaiting);
+}
+
+/// Instantiate an awaiting phase with the given commitment to the next round and set up the mock
+/// to publish a new round which selects the participant for the sum task. The `notify` closure
+/// sets the expected notifications.
+fn make_committed_state_machine<F>(
+    commitment: fn(&RoundParameters) -> Sha256,
+    notify: F,
+) -> StateMachine
+where
+    F: FnOnce(&mut MockIO),
+{
+    let mut new_round_params = round_params(SelectFor::Sum);
+    new_round_params.seed = RoundSeed::generate();
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.round_params.next_commitment = Some(commitment(&new_round_params));
+
+    let mut mock = MockIO::new();
+    mock.expect_notify_idle().times(1).return_const(());
+    let mut phase: Phase<Awaiting> =
+        State::new(shared, Box::new(Awaiting)).into_phase(Box::new(mock));
+    phase.check_io_mock();
+
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_round_params()
+            .returning(move || Ok(new_round_params.clone()));
+        notify(mock);
+    });
+    phase.into()
+}
+
+#[tokio::test]
+async fn test_committed_round() {
+    let state_machine = make_committed_state_machine(RoundParameters::commitment, |mock| {
+        mock.expect_notify_new_round().return_const(());
+        mock.expect_notify_sum().return_const(());
+        mock.expect_notify_failed().never();
+    });
+
+    let state_machine = unwrap_as!(
+        state_machine.transition().await,
+        TransitionOutcome::Complete
+    );
+    let state_machine = unwrap_as!(state_machine, StateMachine::NewRound);
+    let state_machine = unwrap_as!(
+        StateMachine::from(state_machine).transition().await,
+        TransitionOutcome::Complete
+    );
+    let _phase = unwrap_as!(state_machine, StateMachine::Sum);
+}
+
+#[tokio::test]
+async fn test_uncommitted_round() {
+    let state_machine = make_committed_state_machine(
+        |_| Sha256::zeroed(),
+        |mock| {
+            mock.expect_notify_failed()
+                .with(eq(Failure::UncommittedRoundParameters))
+                .times(1)
+                .return_const(());
+            mock.expect_notify_idle().times(1).return_const(());
+            mock.expect_notify_new_round().never();
+            mock.expect_notify_sum().never();
+        },
+    );
+
+    // the round is rejected and the participant sleeps until the next round
+    let state_machine = unwrap_as!(
+        state_machine.transition().await,
+        TransitionOutcome::Complete
+    );
+    let state_machine = unwrap_as!(state_machine, StateMachine::Awaiting);
+    let state_machine = unwrap_as!(
+        StateMachine::from(state_machine).transition().await,
+        TransitionOutcome::Pending
+    );
+    let _phase = unwrap_as!(state_machine, StateMachine::Awaiting);
+}
+
+/// Set up a mock which publishes a new round that selects the participant for the update task.
+fn make_round_mock() -> MockIO {
+    let mut round_params = round_params(SelectFor::Update);
+    round_params.seed = RoundSeed::generate();
+    round_params.model_length = make_model().len();
+    let seed = round_params.seed.clone();
+
+    let mut mock = MockIO::new();
+    mock.expect_get_round_params()
+        .returning(move || Ok(round_params.clone()));
+    mock.expect_get_sums().returning(move || {
+        Ok(Some(RoundSumDict {
+            seed: seed.clone(),
+            sum_dict: make_sum_dict(),
+        }))
+    });
+    mock.expect_load_model()
+        .returning(|| Ok(Some(Box::new(make_model()))));
+    mock.expect_send_message().returning(|_| Ok(()));
+    mock.expect_notify_new_round().return_const(());
+    mock.expect_notify_update().return_const(());
+    mock.expect_notify_load_model().return_const(());
+    mock.expect_notify_update_sent().return_const(());
+    mock.expect_notify_idle().return_const(());
+    mock
+}
+
+#[tokio::test]
+async fn test_state_size_plateaus() {
+    let mut shared = shared_state(SelectFor::None);
+    shared.round_params.model_length = make_model().len();
+    let mut bytes = SerializableState::from(State::new(shared, Box::new(Awaiting))).to_bytes();
+
+    // the state of a participant which took part in many rounds doesn't grow
+    let mut sizes = Vec::new();
+    for _ in 0..100 {
+        let state = unwrap_as!(
+            SerializableState::from_bytes(&bytes).unwrap(),
+            SerializableState::Awaiting
+        );
+        let phase: Phase<Awaiting> = state.into_phase(Box::new(make_round_mock()));
+        let state_machine = run_until_stuck(phase.into()).await;
+        bytes = state_machine.save().to_bytes();
+        sizes.push(bytes.len());
+    }
+    assert!(sizes.iter().all(|size| *size == sizes[0]));
+}
+
+#[tokio::test]
+async fn test_phase_timeline() {
+    let mut mock = MockIO::new();
+    mock.expect_notify_idle().times(1).return_const(());
+    let mut phase: Phase<Awaiting> =
+        State::new(shared_state(SelectFor::None), Box::new(Awaiting)).into_phase(Box::new(mock));
+    phase.check_io_mock();
+    assert_eq!(phase.phase_timeline(), None);
+
+    // the timeline is surfaced once the parameters of the new round have been fetched
+    let round_start = UNIX_EPOCH + Duration::from_secs(1_000);
+    let timeline = PhaseTimeline::new(
+        round_start,
+        Duration::from_secs(10),
+        Duration::from_secs(20),
+        Duration::from_secs(30),
+    );
+    let mut new_round_params = round_params(SelectFor::None);
+    new_round_params.seed = RoundSeed::generate();
+    new_round_params.timeline = Some(timeline);
+    phase.with_io_mock(move |mock| {
+        mock.expect_get_round_params()
+            .returning(move || Ok(new_round_params.clone()));
+        mock.expect_notify_new_round().return_const(());
+        mock.expect_notify_idle().return_const(());
+    });
+
+    let state_machine = run_until_stuck(phase.into()).await;
+    let fetched = state_machine.phase_timeline().unwrap();
+    assert_eq!(fetched, timeline);
+    assert_eq!(
+        fetched.update_time_left(round_start + Duration::from_secs(25)),
+        Duration::from_secs(5)
+    );
+}
+
+#[test]
+fn test_restore_unversioned_state() {
+    let state = SerializableState::from(State::new(
+        shared_state(SelectFor::Update),
+        Box::new(Awaiting),
+    ));
+    let bytes = state.to_bytes();
+    assert_eq!(&bytes[..5], b"XNST\x02");
+
+    // states saved before the format got versioned are bare bincode serializations
+    let v1_bytes = bincode::serialize(&state).unwrap();
+    assert_eq!(&bytes[5..], v1_bytes.as_slice());
+    let restored = SerializableState::from_bytes(&v1_bytes).unwrap();
+    let restored = unwrap_as!(restored, SerializableState::Awaiting);
+    assert_eq!(restored.shared.round_params, round_params(SelectFor::Update));
+
+    let mut future_bytes = bytes;
+    future_bytes[4] = STATE_FORMAT_VERSION + 1;
+    assert!(matches!(
+        SerializableState::from_bytes(&future_bytes),
+        Err(StateFormatError::UnsupportedVersion(version)) if version == STATE_FORMAT_VERSION + 1
+    ));
+}
@@ -1,10 +1,21 @@
 use xaynet_core::{
     common::{RoundParameters, RoundSeed},
-    crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed, SigningKeyPair, SigningKeySeed},
+    crypto::{
+        ByteObject,
+        CryptoSuite,
+        EncryptKeyPair,
+        EncryptKeySeed,
+        SigningKeyPair,
+        SigningKeySeed,
+    },
     mask::{self, MaskConfig, Scalar},
 };
 
-use crate::{settings::MaxMessageSize, state_machine::SharedState};
+use crate::{
+    budget::ComputeCosts,
+    settings::{ComputeBudget, MaxMessageSize, RetrySettings},
+    state_machine::SharedState,
+};
 
 #[macro_export]
 macro_rules! unwrap_as {
@@ -81,6 +92,13 @@ macro_rules! unwrap_step {
             $crate::state_machine::StateMachine::SendingSum2
         )
     };
+    ($phase:expr, $transition_outcome:path, banned) => {
+        unwrap_step!(
+            $phase,
+            $transition_outcome,
+            $crate::state_machine::StateMachine::Banned
+        )
+    };
     ($phase:expr, $transition_outcome:path, $state_machine:path) => {{
         let x = $crate::unwrap_as!(
             $crate::state_machine::Step::step($phase).await,
@@ -148,6 +166,12 @@ pub fn round_params(task: SelectFor) -> RoundParameters {
         seed: RoundSeed::zeroed(),
         mask_config: mask_config().into(),
         model_length: 0,
+        next_commitment: None,
+        round_bound_masks: false,
+        eligibility_salt: Vec::new(),
+        allow_sum_eligible_updates: false,
+        timeline: None,
+        crypto_suite: CryptoSuite::SHA256,
     }
 }
 
@@ -157,6 +181,15 @@ pub fn shared_state(task: SelectFor) -> Box<SharedState> {
         scalar: Scalar::unit(),
         message_size: MaxMessageSize::unlimited(),
         round_params: round_params(task),
+        retry: RetrySettings::default(),
+        deterministic_ephm_keys: false,
+        expected_mask_config: None,
+        supported_mask_configs: None,
+        compute_budget: ComputeBudget::default(),
+        calibration: None,
+        last_costs: ComputeCosts::default(),
+        last_ephm_pk: None,
+        pinned_coordinator_pk: None,
     })
 }
 
@@ -1,21 +1,27 @@
 use async_trait::async_trait;
 
 use xaynet_core::{
-    common::RoundParameters,
-    mask::Model,
-    SumDict,
+    common::{RoundParameters, RoundSubmissionStatus, RoundSumDict},
+    crypto::CryptoSuite,
+    mask::{MaskConfig, Model},
+    ParticipantPublicKey,
     SumParticipantPublicKey,
-    UpdateSeedDict,
+    VersionedSeeds,
 };
 
+use crate::budget::BudgetExceeded;
+
 /// A trait used by the [`StateMachine`] to emit notifications upon
 /// certain events.
 ///
 /// [`StateMachine`]: crate::StateMachine
 pub trait Notify {
     /// Emit a notification when a new round of federated learning
-    /// starts
-    fn new_round(&mut self) {}
+    /// starts. The round is identified by the given round token (see
+    /// [`RoundSeed::token()`]).
+    ///
+    /// [`RoundSeed::token()`]: xaynet_core::common::RoundSeed::token
+    fn new_round(&mut self, _round_token: &str) {}
     /// Emit a notification when the participant has been selected for
     /// the sum task
     fn sum(&mut self) {}
@@ -28,6 +34,64 @@ pub trait Notify {
     /// Emit a notification when the participant should populate the
     /// model store (see [`ModelStore`]).
     fn load_model(&mut self) {}
+    /// Emit a notification when the participant sent its update
+    /// message to the coordinator
+    fn update_sent(&mut self) {}
+    /// Emit a notification when the participant sent its sum2
+    /// message to the coordinator
+    fn sum2_sent(&mut self) {}
+    /// Emit a notification when the participant dropped out of the
+    /// current round because of the given failure
+    fn failed(&mut self, _failure: Failure) {}
+    /// Emit a notification when the participant doesn't take part in
+    /// the current round, because the `advertised` masking
+    /// configuration of the coordinator is not among the `supported`
+    /// ones
+    fn unsupported_mask_config(&mut self, _advertised: MaskConfig, _supported: &[MaskConfig]) {}
+    /// Emit a notification when the participant skips or aborts a
+    /// task of the current round, because a computation exceeds its
+    /// compute budget
+    fn budget_exceeded(&mut self, _exceeded: BudgetExceeded) {}
+    /// Emit a notification when the participant doesn't take part in
+    /// the current round, because the crypto `suite` of the round is
+    /// not supported
+    fn unsupported_crypto_suite(&mut self, _suite: CryptoSuite) {}
+}
+
+/// A failure which makes the participant drop out of the current
+/// round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The coordinator rejected a message of the participant
+    MessageRejected,
+    /// A message of the participant couldn't be sent before the
+    /// retry deadline passed
+    MessageExpired,
+    /// A message of the participant couldn't be sent before a new
+    /// round started
+    MessageOutdated,
+    /// The mask seeds of the update participants couldn't be
+    /// decrypted
+    InvalidSeeds,
+    /// The local model changed while it was masked
+    ModelCorrupted,
+    /// The coordinator banned the participant, which doesn't take
+    /// part in any further round
+    Banned,
+    /// The generated ephemeral keys of the sum task repeat the
+    /// signing keys or the ephemeral keys of the previous round,
+    /// which indicates a broken random number generator
+    InsecureEphemeralKeys,
+    /// The public key of the coordinator in the round parameters
+    /// doesn't match the pinned key
+    UntrustedCoordinatorKey,
+    /// The coordinator evicted the participant from the bounded sum
+    /// dictionary, hence it can't complete its sum task
+    Evicted,
+    /// The parameters of the new round don't match the commitment of
+    /// the coordinator in the previous round, hence the participant
+    /// doesn't take part in the round
+    UncommittedRoundParameters,
 }
 
 /// A trait used by the [`StateMachine`] to load the model trained by
@@ -55,19 +119,27 @@ pub trait XaynetClient {
     /// Retrieve the current round parameters
     async fn get_round_params(&mut self) -> Result<RoundParameters, Self::Error>;
 
-    /// Retrieve the current sum dictionary, if available.
-    async fn get_sums(&mut self) -> Result<Option<SumDict>, Self::Error>;
+    /// Retrieve the current sum dictionary together with the seed of
+    /// its round, if available.
+    async fn get_sums(&mut self) -> Result<Option<RoundSumDict>, Self::Error>;
 
     /// Retrieve the current seed dictionary for the given sum
-    /// participant, if available.
+    /// participant together with its version, if available.
     async fn get_seeds(
         &mut self,
         pk: SumParticipantPublicKey,
-    ) -> Result<Option<UpdateSeedDict>, Self::Error>;
+    ) -> Result<Option<VersionedSeeds>, Self::Error>;
 
     /// Retrieve the current global model, if available.
     async fn get_model(&mut self) -> Result<Option<Model>, Self::Error>;
 
+    /// Retrieve which messages of the given participant are recorded
+    /// in the current round, together with the seed of the round.
+    async fn get_submission_status(
+        &mut self,
+        pk: ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, Self::Error>;
+
     /// Send an encrypted and signed PET message to the coordinator.
     async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Self::Error>;
 }
@@ -1,22 +1,64 @@
-use std::{path::PathBuf, process};
+use std::{future::Future, path::PathBuf, process, time::Duration};
 
+use futures::{future, pin_mut};
 use structopt::StructOpt;
-use tokio::signal;
-use tracing::warn;
+use tokio::{
+    signal,
+    sync::{oneshot, watch},
+};
+use tracing::{info, warn};
 use tracing_subscriber::*;
 
 #[cfg(feature = "metrics")]
 use xaynet_server::{metrics, settings::InfluxSettings};
 
 use xaynet_server::{
-    rest::{serve, RestError},
-    services,
-    settings::{LoggingSettings, RedisSettings, Settings},
-    state_machine::initializer::StateMachineInitializer,
-    storage::{coordinator_storage::redis, Storage, Store},
+    plan::{max_message_size, RoundPlan},
+    rest::{serve, serve_standby, RestError},
+    services::{
+        self,
+        health::{HealthChecker, StorageHealthMonitor, STORAGE_HEALTH_INTERVAL},
+        messages::{MessageThreadPool, PetMessageHandler},
+        seed_export::SeedDictExporter,
+        submissions::SubmissionChecker,
+        webhooks::WebhookNotifier,
+    },
+    settings::{
+        ApiSettings,
+        CircuitBreakerSettings,
+        DenylistSettings,
+        LoggingSettings,
+        MaskSettings,
+        ModelSettings,
+        PetSettings,
+        RedisSettings,
+        SeedExportSettings,
+        Settings,
+        WebhookSettings,
+    },
+    state_machine::{
+        debug::{dump_dictionaries, dump_stored_state, StateDumper},
+        denylist::{Denylist, DenylistManager},
+        initializer::StateMachineInitializer,
+        pause::Pause,
+    },
+    storage::{
+        coordinator_storage::redis,
+        leader::{leadership, FencingToken, Role},
+        CircuitBreaker,
+        FsBlobSink,
+        LeaderElection,
+        ModelStorage,
+        Storage,
+        Store,
+    },
 };
 #[cfg(feature = "model-persistence")]
-use xaynet_server::{settings::S3Settings, storage::model_storage::s3};
+use xaynet_server::{
+    settings::{RestoreSettings, S3Settings},
+    state_machine::initializer::StandbySnapshot,
+    storage::model_storage::s3,
+};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "Coordinator")]
@@ -24,13 +66,39 @@ struct Opt {
     /// Path of the configuration file
     #[structopt(short, parse(from_os_str))]
     config_path: PathBuf,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print the coordinator state stored in Redis as JSON, without any secret keys
+    DumpState,
+    /// Print a summary of the dictionaries of the current round stored in Redis as JSON
+    DumpDictionaries,
+    /// Print the expected resource usage of a round
+    Plan {
+        /// The expected number of participants, otherwise the maximal counts are planned for
+        #[structopt(long)]
+        participants: Option<u64>,
+        /// The length of the model, overriding the configured length
+        #[structopt(long)]
+        model_length: Option<usize>,
+        /// Print the estimates as JSON
+        #[structopt(long)]
+        json: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() {
-    let opt = Opt::from_args();
+    let Opt {
+        config_path,
+        command,
+    } = Opt::from_args();
 
-    let settings = Settings::new(opt.config_path).unwrap_or_else(|err| {
+    let settings = Settings::new(config_path).unwrap_or_else(|err| {
         eprintln!("{}", err);
         process::exit(1);
     });
@@ -41,9 +109,41 @@ async fn main() {
         log: log_settings,
         model: model_settings,
         redis: redis_settings,
+        denylist: denylist_settings,
+        circuit_breaker: circuit_breaker_settings,
+        leader: leader_settings,
+        seed_export: seed_export_settings,
+        webhooks: webhook_settings,
         ..
     } = settings;
 
+    match command {
+        Some(Command::DumpState) => {
+            dump_state(redis_settings).await;
+            return;
+        }
+        Some(Command::DumpDictionaries) => {
+            print_dictionaries(redis_settings).await;
+            return;
+        }
+        Some(Command::Plan {
+            participants,
+            model_length,
+            json,
+        }) => {
+            let model_length = model_length.unwrap_or(model_settings.length);
+            plan(
+                &pet_settings,
+                mask_settings,
+                model_length,
+                participants,
+                json,
+            );
+            return;
+        }
+        None => {}
+    }
+
     init_tracing(log_settings);
 
     // This should already called internally when instantiating the
@@ -51,42 +151,260 @@ async fn main() {
     // is correctly initialized
     sodiumoxide::init().unwrap();
 
+    if pet_settings.sum.distinct_identities && api_settings.client_identity_header.is_none() {
+        warn!(
+            "distinct sum identities are required without a client identity header: all sum messages will be rejected"
+        );
+    }
+
+    let message_size_limit = api_settings
+        .max_message_size
+        .unwrap_or_else(|| max_message_size(&pet_settings, mask_settings, model_settings.length));
+
     #[cfg(feature = "metrics")]
     init_metrics(settings.metrics.influxdb);
 
-    let store = init_store(
-        redis_settings,
+    let coordinator_store =
+        init_coordinator_store(&redis_settings, circuit_breaker_settings, None).await;
+    let model_store = init_model_store(
         #[cfg(feature = "model-persistence")]
         settings.s3,
     )
     .await;
+    let shutdown = async {
+        let _ = signal::ctrl_c().await;
+    };
+
+    let leader_settings = match leader_settings {
+        Some(leader_settings) => leader_settings,
+        None => {
+            return run_coordinator(
+                Store::new(coordinator_store, model_store),
+                pet_settings,
+                mask_settings,
+                model_settings,
+                #[cfg(feature = "model-persistence")]
+                settings.restore,
+                denylist_settings,
+                api_settings,
+                seed_export_settings,
+                webhook_settings,
+                message_size_limit,
+                #[cfg(feature = "model-persistence")]
+                StandbySnapshot::default(),
+                false,
+                future::pending(),
+                shutdown,
+            )
+            .await;
+        }
+    };
+
+    let lock = redis::Client::new(redis_settings.url.clone())
+        .await
+        .expect("failed to establish a connection to Redis");
+    #[cfg(feature = "model-persistence")]
+    let renew_interval = Duration::from_millis(leader_settings.renew_interval);
+    let (election, mut role_rx) = LeaderElection::new(lock, leader_settings);
+    tokio::spawn(election.run());
 
-    let (state_machine, requests_tx, event_subscriber) = StateMachineInitializer::new(
+    // the standby keeps the latest global model restored until it becomes the leader
+    #[cfg(feature = "model-persistence")]
+    let mut snapshot = StandbySnapshot::default();
+    let restore = {
+        #[cfg(not(feature = "model-persistence"))]
+        {
+            future::pending()
+        }
+
+        #[cfg(feature = "model-persistence")]
+        {
+            snapshot.restore_continuously(
+                Store::new(coordinator_store, model_store.clone()),
+                renew_interval,
+            )
+        }
+    };
+    let token = stand_by(api_settings.clone(), &mut role_rx, restore).await;
+    #[cfg(feature = "model-persistence")]
+    info!("take over in round {:?}", snapshot.round_id);
+
+    // the coordinator store of the leader is fenced, such that it can't overwrite the state of
+    // another leader after it has been deposed
+    let coordinator_store =
+        init_coordinator_store(&redis_settings, circuit_breaker_settings, Some(token)).await;
+    run_coordinator(
+        Store::new(coordinator_store, model_store),
         pet_settings,
         mask_settings,
         model_settings,
         #[cfg(feature = "model-persistence")]
         settings.restore,
+        denylist_settings,
+        api_settings,
+        seed_export_settings,
+        webhook_settings,
+        message_size_limit,
+        #[cfg(feature = "model-persistence")]
+        snapshot,
+        true,
+        deposition(role_rx),
+        shutdown,
+    )
+    .await;
+}
+
+/// Serves the REST API of a standby replica and runs the `restore` of its snapshot until the
+/// replica becomes the leader.
+async fn stand_by<R>(
+    api_settings: ApiSettings,
+    role_rx: &mut watch::Receiver<Role>,
+    restore: R,
+) -> FencingToken
+where
+    R: Future<Output = ()>,
+{
+    info!("standing by until this replica becomes the leader");
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = serve_standby(api_settings, role_rx.clone(), async {
+        let _ = shutdown_rx.await;
+    });
+    pin_mut!(server);
+
+    let token = tokio::select! {
+        token = leadership(role_rx) => token.expect("the leader election stopped"),
+        _ = restore => unreachable!("the restore of the standby snapshot never completes"),
+        result = &mut server => {
+            if let Err(RestError::InvalidTlsConfig) = result {
+                warn!("shutting down: invalid TLS settings for REST server");
+            }
+            process::exit(1);
+        }
+    };
+    let _ = shutdown_tx.send(());
+    let _ = server.await;
+    token
+}
+
+/// Waits until the replica is deposed as the leader.
+async fn deposition(mut role_rx: watch::Receiver<Role>) {
+    loop {
+        if !matches!(*role_rx.borrow(), Role::Leader(_)) {
+            return;
+        }
+        if role_rx.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Runs the state machine and the REST API until the service terminates, the replica is
+/// `deposed` as the leader or the `shutdown` signal.
+///
+/// If `takeover` is set, the replica has become the leader and continues the round of the
+/// previous leader instead of initializing the state machine from scratch.
+#[allow(clippy::too_many_arguments)]
+async fn run_coordinator<T, D, S>(
+    mut store: T,
+    pet_settings: PetSettings,
+    mask_settings: MaskSettings,
+    model_settings: ModelSettings,
+    #[cfg(feature = "model-persistence")] restore_settings: RestoreSettings,
+    denylist_settings: DenylistSettings,
+    api_settings: ApiSettings,
+    seed_export_settings: Option<SeedExportSettings>,
+    webhook_settings: Option<WebhookSettings>,
+    message_size_limit: u64,
+    #[cfg(feature = "model-persistence")] standby_snapshot: StandbySnapshot,
+    takeover: bool,
+    deposed: D,
+    shutdown: S,
+) where
+    T: Storage,
+    D: Future<Output = ()>,
+    S: Future<Output = ()> + Send + 'static,
+{
+    let dump_store = store.clone();
+    let submission_store = store.clone();
+    let (storage_monitor, storage_health) =
+        StorageHealthMonitor::new(store.clone(), STORAGE_HEALTH_INTERVAL);
+    let denylist = Denylist::restore(&denylist_settings, &mut store)
+        .await
+        .expect("failed to restore the denylist");
+    let denylist_manager = DenylistManager::new(store.clone(), denylist.clone());
+    let pause = Pause::default();
+
+    let state_machine_initializer = StateMachineInitializer::new(
+        pet_settings,
+        mask_settings,
+        model_settings,
+        #[cfg(feature = "model-persistence")]
+        restore_settings,
         store,
     )
-    .init()
-    .await
+    .with_denylist(denylist)
+    .with_pause(pause.clone());
+    #[cfg(feature = "model-persistence")]
+    let state_machine_initializer =
+        state_machine_initializer.with_standby_snapshot(standby_snapshot);
+    let (state_machine, requests_tx, event_subscriber) = if takeover {
+        state_machine_initializer.take_over().await
+    } else {
+        state_machine_initializer.init().await
+    }
     .expect("failed to initialize state machine");
 
-    let fetcher = services::fetchers::fetcher(&event_subscriber);
-    let message_handler =
-        services::messages::PetMessageHandler::new(&event_subscriber, requests_tx);
+    let fetcher = services::fetchers::fetcher(&event_subscriber, api_settings.compression_level);
+    let seed_dict_locations = seed_export_settings.map(|settings| {
+        let (exporter, locations) =
+            SeedDictExporter::new(FsBlobSink::from(settings), &event_subscriber);
+        tokio::spawn(exporter.run());
+        locations
+    });
+    if let Some(settings) = webhook_settings {
+        tokio::spawn(WebhookNotifier::new(settings, &event_subscriber).run());
+    }
+    let thread_pool = MessageThreadPool::new(api_settings.message_threads)
+        .expect("failed to create the message processing thread pool");
+    let mut message_handler =
+        PetMessageHandler::with_thread_pool(&event_subscriber, requests_tx, thread_pool)
+            .with_max_message_size(message_size_limit as usize);
+    if let Some(batch_size) = api_settings.signature_batch_size {
+        message_handler = message_handler.with_signature_batching(
+            batch_size,
+            Duration::from_millis(api_settings.signature_batch_delay),
+        );
+    }
+    let submission_checker = SubmissionChecker::new(submission_store, &event_subscriber);
+    let state_dumper = StateDumper::new(dump_store, &event_subscriber);
+    tokio::spawn(storage_monitor.run());
+    let health_checker =
+        HealthChecker::new(message_handler.clone(), storage_health, &event_subscriber)
+            .with_pause(pause.clone());
 
     tokio::select! {
         biased;
 
-        _ =  signal::ctrl_c() => {}
         _ = state_machine.run() => {
             warn!("shutting down: Service terminated");
         }
-        result = serve(api_settings, fetcher, message_handler) => {
+        _ = deposed => {
+            warn!("shutting down: deposed as the leader");
+        }
+        result = serve(
+            api_settings,
+            fetcher,
+            seed_dict_locations,
+            message_handler,
+            submission_checker,
+            state_dumper,
+            denylist_manager,
+            pause,
+            health_checker,
+            shutdown,
+        ) => {
             match result {
-                Ok(()) => warn!("shutting down: REST server terminated"),
+                Ok(()) => warn!("shutting down: REST server drained or terminated"),
                 Err(RestError::InvalidTlsConfig) => {
                     warn!("shutting down: invalid TLS settings for REST server");
                 },
@@ -102,6 +420,56 @@ fn init_tracing(settings: LoggingSettings) {
         .init();
 }
 
+/// Prints the coordinator state stored in Redis.
+async fn dump_state(redis_settings: RedisSettings) {
+    let mut coordinator_store = redis::Client::new(redis_settings.url)
+        .await
+        .expect("failed to establish a connection to Redis");
+
+    match dump_stored_state(&mut coordinator_store, None).await {
+        Ok(Some(dump)) => println!("{}", serde_json::to_string_pretty(&dump).unwrap()),
+        Ok(None) => {
+            eprintln!("no coordinator state found");
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("failed to read the coordinator state: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints a summary of the dictionaries stored in Redis.
+async fn print_dictionaries(redis_settings: RedisSettings) {
+    let mut coordinator_store = redis::Client::new(redis_settings.url)
+        .await
+        .expect("failed to establish a connection to Redis");
+
+    match dump_dictionaries(&mut coordinator_store).await {
+        Ok(dump) => println!("{}", serde_json::to_string_pretty(&dump).unwrap()),
+        Err(err) => {
+            eprintln!("failed to read the dictionaries: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints the expected resource usage of a round.
+fn plan(
+    pet_settings: &PetSettings,
+    mask_settings: MaskSettings,
+    model_length: usize,
+    participants: Option<u64>,
+    json: bool,
+) {
+    let plan = RoundPlan::new(pet_settings, mask_settings, model_length, participants);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+    } else {
+        print!("{}", plan);
+    }
+}
+
 #[cfg(feature = "metrics")]
 fn init_metrics(settings: InfluxSettings) {
     let recorder = metrics::Recorder::new(settings);
@@ -110,15 +478,30 @@ fn init_metrics(settings: InfluxSettings) {
     };
 }
 
-async fn init_store(
-    redis_settings: RedisSettings,
-    #[cfg(feature = "model-persistence")] s3_settings: S3Settings,
-) -> impl Storage {
-    let coordinator_store = redis::Client::new(redis_settings.url)
+async fn init_coordinator_store(
+    redis_settings: &RedisSettings,
+    circuit_breaker_settings: CircuitBreakerSettings,
+    fencing: Option<FencingToken>,
+) -> CircuitBreaker<redis::Client> {
+    let mut coordinator_store = redis::Client::new(redis_settings.url.clone())
         .await
         .expect("failed to establish a connection to Redis");
+    if let Some(token) = fencing {
+        coordinator_store = coordinator_store.with_fencing_token(token);
+    }
+    if let Some(size) = redis_settings.seed_dict_batch_size {
+        coordinator_store = coordinator_store.with_seed_dict_batching(
+            size,
+            Duration::from_millis(redis_settings.seed_dict_flush_interval),
+        );
+    }
+    CircuitBreaker::new(coordinator_store, circuit_breaker_settings)
+}
 
-    let model_store = {
+async fn init_model_store(
+    #[cfg(feature = "model-persistence")] s3_settings: S3Settings,
+) -> impl ModelStorage {
+    {
         #[cfg(not(feature = "model-persistence"))]
         {
             xaynet_server::storage::model_storage::noop::NoOp
@@ -132,7 +515,5 @@ async fn init_store(
                 .expect("failed to create bucket for global models");
             s3
         }
-    };
-
-    Store::new(coordinator_store, model_store)
+    }
 }
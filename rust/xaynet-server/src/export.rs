@@ -0,0 +1,182 @@
+//! Export of round results to columnar formats.
+//!
+//! The global model of a round is written together with its [`GlobalModelMetadata`] as a table
+//! with one row per weight, either as CSV or as Parquet. The tables can be loaded directly into
+//! data frame libraries like pandas or polars for an offline analysis of the rounds.
+//!
+//! Both formats share the same columns:
+//!
+//! | column      | type  | description                                      |
+//! | ----------- | ----- | ------------------------------------------------ |
+//! | `round_id`  | `u64` | The id of the round of the global model.         |
+//! | `nb_models` | `u64` | The number of aggregated local models.           |
+//! | `index`     | `u64` | The index of the weight in the global model.     |
+//! | `weight`    | `f64` | The unmasked weight, converted to a float.       |
+
+use std::{fs::File, io, path::Path, sync::Arc};
+
+use displaydoc::Display;
+use parquet::{
+    data_type::{DoubleType, Int64Type},
+    errors::ParquetError,
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+use serde::Serialize;
+use thiserror::Error;
+
+use xaynet_core::{
+    common::GlobalModelMetadata,
+    mask::{IntoPrimitives, Model, ModelCastError},
+};
+
+/// The Parquet schema of an exported round.
+const PARQUET_SCHEMA: &str = "
+    message round {
+        REQUIRED INT64 round_id;
+        REQUIRED INT64 nb_models;
+        REQUIRED INT64 index;
+        REQUIRED DOUBLE weight;
+    }
+";
+
+/// Errors which can occur while exporting a round.
+#[derive(Debug, Display, Error)]
+pub enum ExportError {
+    /// Failed to convert the weights of the global model: {0}.
+    Conversion(#[from] ModelCastError),
+    /// Failed to access the export file: {0}.
+    Io(#[from] io::Error),
+    /// Failed to write the CSV file: {0}.
+    Csv(#[from] csv::Error),
+    /// Failed to write the Parquet file: {0}.
+    Parquet(#[from] ParquetError),
+}
+
+/// A row of an exported round.
+#[derive(Debug, Serialize)]
+struct Row {
+    round_id: u64,
+    nb_models: u64,
+    index: u64,
+    weight: f64,
+}
+
+/// Gets the rows of the global model of a round.
+///
+/// # Errors
+/// Fails if a weight can't be converted into a float.
+fn rows(model: &Model, metadata: &GlobalModelMetadata) -> Result<Vec<Row>, ExportError> {
+    IntoPrimitives::<f64>::to_primitives(model)
+        .enumerate()
+        .map(|(index, weight)| {
+            Ok(Row {
+                round_id: metadata.round_id,
+                nb_models: metadata.nb_models as u64,
+                index: index as u64,
+                weight: weight?,
+            })
+        })
+        .collect()
+}
+
+/// Writes the global model of a round and its metadata as CSV to the given `path`.
+///
+/// The file starts with a header row of the column names, an existing file is overwritten.
+///
+/// # Errors
+/// Fails if a weight can't be converted into a float or if the file can't be written.
+pub fn write_round_csv(
+    path: impl AsRef<Path>,
+    model: &Model,
+    metadata: &GlobalModelMetadata,
+) -> Result<(), ExportError> {
+    let rows = rows(model, metadata)?;
+    let mut writer = csv::Writer::from_path(path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the global model of a round and its metadata as Parquet to the given `path`.
+///
+/// The table is written as a single row group, an existing file is overwritten.
+///
+/// # Errors
+/// Fails if a weight can't be converted into a float or if the file can't be written.
+pub fn write_round_parquet(
+    path: impl AsRef<Path>,
+    model: &Model,
+    metadata: &GlobalModelMetadata,
+) -> Result<(), ExportError> {
+    let rows = rows(model, metadata)?;
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, properties)?;
+
+    let mut int_columns = vec![
+        rows.iter()
+            .map(|row| row.round_id as i64)
+            .collect::<Vec<_>>(),
+        rows.iter().map(|row| row.nb_models as i64).collect(),
+        rows.iter().map(|row| row.index as i64).collect(),
+    ]
+    .into_iter();
+    let weights = rows.iter().map(|row| row.weight).collect::<Vec<_>>();
+
+    let mut row_group = writer.next_row_group()?;
+    while let Some(mut column) = row_group.next_column()? {
+        match int_columns.next() {
+            Some(values) => column
+                .typed::<Int64Type>()
+                .write_batch(&values, None, None)?,
+            None => column
+                .typed::<DoubleType>()
+                .write_batch(&weights, None, None)?,
+        };
+        column.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, fs};
+
+    use xaynet_core::mask::{FromPrimitives, MaskConfig};
+
+    use crate::state_machine::tests::utils::mask_settings;
+
+    #[test]
+    fn test_write_round_csv() {
+        let weights = vec![0.5_f64, -1.25, 2., 0.];
+        let model = Model::from_primitives(weights.clone().into_iter()).unwrap();
+        let mask_config = MaskConfig::from(mask_settings());
+        let metadata = GlobalModelMetadata::new(7, mask_config.into(), model.len(), 3);
+
+        let path = env::temp_dir().join(format!("xaynet_export_{}.csv", std::process::id()));
+        write_round_csv(&path, &model, &metadata).unwrap();
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["round_id", "nb_models", "index", "weight"]
+        );
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(records.len(), weights.len());
+        for (index, (record, weight)) in records.iter().zip(weights).enumerate() {
+            assert_eq!(&record[0], "7");
+            assert_eq!(&record[1], "3");
+            assert_eq!(record[2].parse::<usize>().unwrap(), index);
+            assert_eq!(record[3].parse::<f64>().unwrap(), weight);
+        }
+    }
+}
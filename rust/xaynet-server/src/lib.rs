@@ -17,7 +17,11 @@
 
 pub mod examples;
 
+#[cfg(feature = "export")]
+#[cfg_attr(docsrs, doc(cfg(feature = "export")))]
+pub mod export;
 pub mod metrics;
+pub mod plan;
 pub mod rest;
 pub mod services;
 pub mod settings;
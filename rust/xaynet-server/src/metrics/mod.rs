@@ -8,6 +8,14 @@ pub use self::recorders::influxdb::{Measurement, Recorder, Tags};
 
 static RECORDER: OnceCell<Recorder> = OnceCell::new();
 
+#[cfg(test)]
+thread_local! {
+    /// A recorder which overrides the global recorder for the tests running on this thread.
+    // const initializers for thread locals require a more recent rustc than the MSRV
+    #[allow(clippy::missing_const_for_thread_local)]
+    static LOCAL_RECORDER: std::cell::Cell<Option<&'static Recorder>> = std::cell::Cell::new(None);
+}
+
 /// A wrapper around a static global metrics/events recorder.
 pub struct GlobalRecorder;
 
@@ -17,6 +25,12 @@ impl GlobalRecorder {
     /// Returns `None` if no recorder is set or is currently being initialized.
     /// This method never blocks.
     pub fn global() -> Option<&'static Recorder> {
+        #[cfg(test)]
+        {
+            if let Some(recorder) = LOCAL_RECORDER.with(|local| local.get()) {
+                return Some(recorder);
+            }
+        }
         RECORDER.get()
     }
 
@@ -26,6 +40,17 @@ impl GlobalRecorder {
     pub fn install(recorder: Recorder) -> Result<(), Recorder> {
         RECORDER.set(recorder)
     }
+
+    /// Installs a recorder for the current thread only, which takes precedence over the global
+    /// recorder.
+    ///
+    /// Tests run in parallel, hence a recorder installed by one test must not leak into the others.
+    #[cfg(test)]
+    pub(crate) fn install_local(recorder: Recorder) -> &'static Recorder {
+        let recorder: &'static Recorder = Box::leak(Box::new(recorder));
+        LOCAL_RECORDER.with(|local| local.set(Some(recorder)));
+        recorder
+    }
 }
 
 /// Records an event.
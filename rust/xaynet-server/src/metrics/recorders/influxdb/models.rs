@@ -11,8 +11,21 @@ pub enum Measurement {
     MasksTotalNumber,
     RoundTotalNumber,
     MessageAccepted,
+    MessageBanned,
     MessageDiscarded,
     MessageRejected,
+    PhaseDuration,
+    PhaseSoftDeadlineExceeded,
+    StorageRetry,
+    StorageCircuitOpened,
+    StorageCircuitOpenDuration,
+    SeedDictExportDuration,
+    SeedDictExportSize,
+    DuplicateSeedRejected,
+    EphemeralKeyCollisionRejected,
+    WebhookDelivered,
+    WebhookGaveUp,
+    MessageQueueDepth,
 }
 
 impl From<Measurement> for &'static str {
@@ -24,8 +37,21 @@ impl From<Measurement> for &'static str {
             Measurement::MasksTotalNumber => "masks_total_number",
             Measurement::RoundTotalNumber => "round_total_number",
             Measurement::MessageAccepted => "message_accepted",
+            Measurement::MessageBanned => "message_banned",
             Measurement::MessageDiscarded => "message_discarded",
             Measurement::MessageRejected => "message_rejected",
+            Measurement::PhaseDuration => "phase_duration",
+            Measurement::PhaseSoftDeadlineExceeded => "phase_soft_deadline_exceeded",
+            Measurement::StorageRetry => "storage_retry",
+            Measurement::StorageCircuitOpened => "storage_circuit_opened",
+            Measurement::StorageCircuitOpenDuration => "storage_circuit_open_duration",
+            Measurement::SeedDictExportDuration => "seed_dict_export_duration",
+            Measurement::SeedDictExportSize => "seed_dict_export_size",
+            Measurement::DuplicateSeedRejected => "duplicate_seed_rejected",
+            Measurement::EphemeralKeyCollisionRejected => "ephemeral_key_collision_rejected",
+            Measurement::WebhookDelivered => "webhook_delivered",
+            Measurement::WebhookGaveUp => "webhook_gave_up",
+            Measurement::MessageQueueDepth => "message_queue_depth",
         }
     }
 }
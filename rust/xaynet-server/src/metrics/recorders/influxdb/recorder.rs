@@ -1,9 +1,6 @@
 use std::borrow::Borrow;
 
-use futures::future::poll_fn;
 use influxdb::Type;
-use tower::Service;
-use tracing::{error, warn};
 
 use super::{Dispatcher, Event, InfluxDbService, Measurement, Metric, Request, Tags};
 use crate::settings::InfluxSettings;
@@ -16,6 +13,12 @@ pub struct Recorder {
 
 impl Recorder {
     /// Creates a new InfluxDB recorder.
+    ///
+    /// Recording never blocks: the metrics / events are buffered and dispatched in the background.
+    /// If the InfluxDB instance is unavailable, the oldest buffered metrics / events are dropped.
+    ///
+    /// # Panics
+    /// Panics if called outside of a tokio runtime.
     pub fn new(settings: InfluxSettings) -> Self {
         let dispatcher = Dispatcher::new(settings.url, settings.db);
         Self {
@@ -23,6 +26,12 @@ impl Recorder {
         }
     }
 
+    /// Gets the number of metrics / events which have been dropped so far, either because the
+    /// buffer overflowed or because they failed to be dispatched.
+    pub fn metrics_dropped(&self) -> u64 {
+        self.service.metrics_dropped()
+    }
+
     /// Records a new metric and dispatches it to an InfluxDB instance.
     pub fn metric<V, T, I>(&self, measurement: Measurement, value: V, tags: T)
     where
@@ -51,15 +60,57 @@ impl Recorder {
     }
 
     fn call(&self, req: Request) {
-        let mut handle = self.service.0.clone();
-        tokio::spawn(async move {
-            if let Err(err) = poll_fn(|cx| handle.poll_ready(cx)).await {
-                error!("influx service temporarily unavailable: {}", err)
+        self.service.enqueue(req);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use futures::future::{self, BoxFuture};
+    use std::task::{Context, Poll};
+    use tokio::time::{timeout, Duration};
+    use tower::Service;
+
+    use super::*;
+
+    /// A sink which fails to dispatch any request, like an unreachable InfluxDB instance.
+    pub struct FailingSink;
+
+    impl Service<Request> for FailingSink {
+        type Response = ();
+        type Error = anyhow::Error;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Request) -> Self::Future {
+            Box::pin(future::err(anyhow::anyhow!("connection refused")))
+        }
+    }
+
+    impl Recorder {
+        /// Creates a recorder whose metrics / events are never dispatched successfully.
+        pub fn failing() -> Self {
+            Self {
+                service: InfluxDbService::new(FailingSink),
             }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_sink_drops_metrics() {
+        let recorder = Recorder::failing();
+        recorder.metric::<_, _, Tags>(Measurement::Phase, 1, None);
+        recorder.event::<_, _, &str, _, &[_], &str>("event", None, None);
 
-            if let Err(err) = handle.call(req).await {
-                warn!("influx service error: {}", err)
+        let dropped = async {
+            while recorder.metrics_dropped() < 2 {
+                tokio::task::yield_now().await;
             }
-        });
+        };
+        timeout(Duration::from_secs(1), dropped).await.unwrap();
+        assert_eq!(recorder.metrics_dropped(), 2);
     }
 }
@@ -1,17 +1,165 @@
-use super::{Dispatcher, Request};
-use tower::{buffer::Buffer, limit::ConcurrencyLimit, load_shed::LoadShed, ServiceBuilder};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+};
 
-pub(in crate::metrics) struct InfluxDbService(
-    pub LoadShed<Buffer<ConcurrencyLimit<Dispatcher>, Request>>,
-);
+use futures::future::poll_fn;
+use tokio::sync::Notify;
+use tower::{limit::ConcurrencyLimit, Service};
+use tracing::{debug, error, warn};
+
+use super::Request;
+
+/// The maximal number of requests which are buffered before the oldest ones are dropped.
+const BUFFER_CAPACITY: usize = 4048;
+/// The maximal number of requests which are dispatched concurrently.
+const CONCURRENCY_LIMIT: usize = 50;
+
+/// A fire-and-forget service that dispatches requests in the background.
+///
+/// Requests are kept in a bounded buffer. If the buffer is full, the oldest request is dropped to
+/// make room for the new one, hence enqueuing a request never blocks, even if the InfluxDB
+/// instance is unreachable. Requests which are dropped or fail to be dispatched are counted.
+pub(in crate::metrics) struct InfluxDbService {
+    buffer: Arc<RequestBuffer>,
+}
 
 impl InfluxDbService {
-    pub fn new(dispatcher: Dispatcher) -> Self {
-        let service = ServiceBuilder::new()
-            .load_shed()
-            .buffer(4048)
-            .concurrency_limit(50)
-            .service(dispatcher);
-        Self(service)
+    /// Creates a new service which dispatches the requests to the given sink.
+    ///
+    /// # Panics
+    /// Panics if called outside of a tokio runtime.
+    pub fn new<S>(sink: S) -> Self
+    where
+        S: Service<Request, Response = (), Error = anyhow::Error> + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        Self::with_capacity(sink, BUFFER_CAPACITY)
+    }
+
+    /// Creates a new service with a buffer for at most `capacity` requests.
+    ///
+    /// # Panics
+    /// Panics if called outside of a tokio runtime.
+    pub fn with_capacity<S>(sink: S, capacity: usize) -> Self
+    where
+        S: Service<Request, Response = (), Error = anyhow::Error> + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        let buffer = Arc::new(RequestBuffer::new(capacity));
+        let sink = ConcurrencyLimit::new(sink, CONCURRENCY_LIMIT);
+        tokio::spawn(dispatch(sink, buffer.clone()));
+        Self { buffer }
+    }
+
+    /// Enqueues a request for dispatching.
+    pub fn enqueue(&self, req: Request) {
+        self.buffer.push(req);
+    }
+
+    /// Gets the number of requests which have been dropped so far.
+    pub fn metrics_dropped(&self) -> u64 {
+        self.buffer.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// A bounded buffer of requests which drops the oldest request on overflow.
+struct RequestBuffer {
+    requests: Mutex<VecDeque<Request>>,
+    capacity: usize,
+    notify: Notify,
+    dropped: AtomicU64,
+}
+
+impl RequestBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            requests: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            notify: Notify::new(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn push(&self, req: Request) {
+        {
+            // safe unwrap: the lock is never held across a panic
+            let mut requests = self.requests.lock().unwrap();
+            if requests.len() >= self.capacity && requests.pop_front().is_some() {
+                debug!("metrics buffer is full, dropping the oldest metric");
+                self.record_dropped();
+            }
+            requests.push_back(req);
+        }
+        self.notify.notify_one();
+    }
+
+    fn pop(&self) -> Option<Request> {
+        // safe unwrap: the lock is never held across a panic
+        self.requests.lock().unwrap().pop_front()
+    }
+
+    fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Dispatches the buffered requests to the sink until the runtime shuts down.
+async fn dispatch<S>(mut sink: S, buffer: Arc<RequestBuffer>)
+where
+    S: Service<Request, Response = (), Error = anyhow::Error>,
+    S::Future: Send + 'static,
+{
+    loop {
+        let req = match buffer.pop() {
+            Some(req) => req,
+            None => {
+                buffer.notify.notified().await;
+                continue;
+            }
+        };
+
+        if let Err(err) = poll_fn(|cx| sink.poll_ready(cx)).await {
+            error!("influx service temporarily unavailable: {}", err);
+            buffer.record_dropped();
+            continue;
+        }
+
+        let fut = sink.call(req);
+        let buffer = buffer.clone();
+        tokio::spawn(async move {
+            if let Err(err) = fut.await {
+                warn!("influx service error: {}", err);
+                buffer.record_dropped();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{
+        recorders::influxdb::models::{Event, Metric},
+        Measurement,
+    };
+
+    #[test]
+    fn test_buffer_drops_oldest_on_overflow() {
+        let buffer = RequestBuffer::new(2);
+        buffer.push(Metric::new(Measurement::Phase, 1).into());
+        buffer.push(Event::new("event").into());
+        assert_eq!(buffer.dropped.load(Ordering::Relaxed), 0);
+
+        buffer.push(Metric::new(Measurement::Phase, 2).into());
+        assert_eq!(buffer.dropped.load(Ordering::Relaxed), 1);
+
+        assert!(matches!(buffer.pop(), Some(Request::Event(_))));
+        assert!(matches!(buffer.pop(), Some(Request::Metric(_))));
+        assert!(buffer.pop().is_none());
     }
 }
@@ -0,0 +1,357 @@
+//! Estimates of the resource usage of a round.
+//!
+//! A [`RoundPlan`] is derived from the settings of the coordinator and an expected number of
+//! participants. The sizes are computed with the same functions which lay out the PET messages and
+//! the dictionaries, hence the estimates follow any change of the formats.
+//!
+//! The estimates cover the PET messages and the dictionaries of a round. They leave out the
+//! download of the global model, the compression of the responses, the chunking of large messages
+//! and the constant overhead of the REST API and of Redis.
+
+use std::{fmt, iter};
+
+use serde::Serialize;
+
+use crate::settings::{CoordinatorMode, MaskSettings, PetMode, PetSettings};
+use xaynet_core::{
+    crypto::{ByteObject, Sha256, SEALBYTES},
+    mask::{Aggregation, EncryptedMaskSeed, MaskConfig, MaskConfigPair, MaskObject},
+    message::{seed_dict_length, Sum, Sum2, Update, MESSAGE_HEADER_LENGTH},
+    SumDict,
+    SumParticipantEphemeralPublicKey,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+    UpdateSeedDict,
+};
+
+/// The expected resource usage of a round.
+///
+/// All sizes are in bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RoundPlan {
+    /// The expected number of participants, if known.
+    ///
+    /// Without an expected number of participants, the maximal counts of the phases are planned
+    /// for.
+    pub participants: Option<u64>,
+    /// The length of the model.
+    pub model_length: usize,
+    /// The bytes per number of the masking configuration.
+    pub bytes_per_number: usize,
+    /// The expected number of sum messages.
+    pub sum_participants: u64,
+    /// The expected number of update messages.
+    pub update_participants: u64,
+    /// The expected number of sum2 messages.
+    pub sum2_participants: u64,
+    /// The expected number of entries of the sum dictionary.
+    pub sum_dict_entries: u64,
+    /// The size of a serialized masked model or mask.
+    pub masked_model_size: u64,
+    /// The size of an encrypted sum message.
+    pub sum_message_size: u64,
+    /// The size of an encrypted update message.
+    pub update_message_size: u64,
+    /// The size of an encrypted sum2 message.
+    pub sum2_message_size: u64,
+    /// The size of the serialized local seed dictionary of an update message.
+    pub local_seed_dict_size: u64,
+    /// The size of the serialized seed dictionary, i.e. of the update seed dictionaries of all
+    /// sum participants.
+    pub seed_dict_size: u64,
+    /// The bytes received by the coordinator in PET messages.
+    pub ingress: u64,
+    /// The bytes sent by the coordinator in sum and seed dictionaries.
+    pub egress: u64,
+    /// The peak memory of the aggregation buffer, including the materialized aggregated model.
+    pub aggregation_memory: u64,
+    /// The memory of the dictionaries of the round in Redis.
+    pub redis_memory: u64,
+}
+
+impl RoundPlan {
+    /// Plans a round of the given settings and model length.
+    ///
+    /// The expected counts of the phases are derived from the selection fractions of the
+    /// `participants`, capped by the maximal counts of the phases. Without an expected number of
+    /// participants, the maximal counts are planned for.
+    pub fn new(
+        pet: &PetSettings,
+        mask: MaskSettings,
+        model_length: usize,
+        participants: Option<u64>,
+    ) -> Self {
+        let trusted = pet.mode == PetMode::Trusted;
+        let full = pet.coordinator_mode == CoordinatorMode::Full;
+
+        let (sum_participants, update_participants) = match participants {
+            Some(participants) => {
+                // the trusted aggregator replaces the sum participants, hence nobody is selected
+                let sum_prob = if trusted { 0. } else { pet.sum.prob };
+                let sum = expected_count(participants, sum_prob, pet.sum.count.max);
+                let update = expected_count(
+                    participants,
+                    (1. - sum_prob) * pet.update.prob,
+                    pet.update.count.max,
+                );
+                (sum, update)
+            }
+            None => (pet.sum.count.max, pet.update.count.max),
+        };
+        let (sum_participants, sum_dict_entries) = if trusted {
+            (0, 1)
+        } else {
+            let capacity = pet.sum.dict_capacity.unwrap_or(u64::MAX);
+            (sum_participants, sum_participants.min(capacity))
+        };
+        let sum2_participants = if trusted || !full {
+            0
+        } else {
+            sum_dict_entries.min(pet.sum2.count.max)
+        };
+
+        let mask_config = MaskConfig::from(mask);
+        let config = MaskConfigPair::from(mask_config);
+        let masked_model_size = MaskObject::serialized_length(config, model_length) as u64;
+        let sum_message_size = encrypted_message_length(Sum::serialized_length());
+        let update_message_size = encrypted_message_length(Update::serialized_length(
+            config,
+            model_length,
+            sum_dict_entries as usize,
+        ));
+        let sum2_message_size =
+            encrypted_message_length(Sum2::serialized_length(config, model_length));
+        let local_seed_dict_size = seed_dict_length(sum_dict_entries as usize) as u64;
+        let seed_dict_size =
+            sum_dict_entries * seed_dict_length(update_participants as usize) as u64;
+
+        let ingress = sum_participants * sum_message_size
+            + update_participants * update_message_size
+            + sum2_participants * sum2_message_size;
+        // every update participant fetches the sum dictionary and every sum participant of the
+        // sum2 phase fetches its update seed dictionary
+        let egress = update_participants * encoded_sum_dict_length(sum_dict_entries)
+            + sum2_participants * encoded_update_seed_dict_length(update_participants);
+
+        let aggregation_memory = if full {
+            Aggregation::predicted_memory_usage(config, model_length) as u64
+        } else {
+            0
+        };
+
+        let sum_dict_memory = sum_dict_entries
+            * (2 * SumParticipantPublicKey::LENGTH + SumParticipantEphemeralPublicKey::LENGTH)
+                as u64;
+        let seed_dict_memory =
+            seed_dict_size + update_participants * UpdateParticipantPublicKey::LENGTH as u64;
+        // the masks of honest sum participants are identical
+        let mask_dict_memory = if sum2_participants > 0 {
+            sum2_participants * SumParticipantPublicKey::LENGTH as u64 + masked_model_size
+        } else {
+            0
+        };
+        let checksums_memory =
+            update_participants * (UpdateParticipantPublicKey::LENGTH + Sha256::LENGTH) as u64;
+        let collected_memory = if full {
+            0
+        } else {
+            update_participants * (UpdateParticipantPublicKey::LENGTH as u64 + masked_model_size)
+                + seed_dict_size
+        };
+        let redis_memory = sum_dict_memory
+            + seed_dict_memory
+            + mask_dict_memory
+            + checksums_memory
+            + collected_memory;
+
+        Self {
+            participants,
+            model_length,
+            bytes_per_number: mask_config.bytes_per_number(),
+            sum_participants,
+            update_participants,
+            sum2_participants,
+            sum_dict_entries,
+            masked_model_size,
+            sum_message_size,
+            update_message_size,
+            sum2_message_size,
+            local_seed_dict_size,
+            seed_dict_size,
+            ingress,
+            egress,
+            aggregation_memory,
+            redis_memory,
+        }
+    }
+}
+
+/// The headroom in bytes on top of the largest PET message for the default message size limit.
+pub const MESSAGE_SIZE_HEADROOM: u64 = 4096;
+
+/// Gets the default limit of the size of an encrypted PET message of a round of the given settings
+/// and model length.
+///
+/// The limit is the size of the largest encrypted PET message of a round with the maximal counts
+/// of the phases plus the [`MESSAGE_SIZE_HEADROOM`]. The size of an update message grows with the
+/// number of sum participants, hence the limit is only as tight as the maximal sum count or the
+/// sum dictionary capacity.
+pub fn max_message_size(pet: &PetSettings, mask: MaskSettings, model_length: usize) -> u64 {
+    let plan = RoundPlan::new(pet, mask, model_length, None);
+    plan.sum_message_size
+        .max(plan.update_message_size)
+        .max(plan.sum2_message_size)
+        + MESSAGE_SIZE_HEADROOM
+}
+
+/// Gets the expected number of participants selected with the given fraction, capped by `max`.
+fn expected_count(participants: u64, fraction: f64, max: u64) -> u64 {
+    ((participants as f64 * fraction).round() as u64).min(max)
+}
+
+/// Gets the length of an encrypted PET message with a payload of the given length.
+fn encrypted_message_length(payload_length: usize) -> u64 {
+    (SEALBYTES + MESSAGE_HEADER_LENGTH + payload_length) as u64
+}
+
+/// Gets the length of the bincode encoding of a dictionary with the given number of entries, as
+/// served by the REST API, where `single` is a dictionary with a single entry.
+fn encoded_dict_length<D: Default + Serialize>(single: D, entries: u64) -> u64 {
+    let empty = bincode::serialized_size(&D::default()).unwrap();
+    let entry = bincode::serialized_size(&single).unwrap() - empty;
+    empty + entries * entry
+}
+
+/// Gets the length of the encoded sum dictionary with the given number of entries.
+fn encoded_sum_dict_length(entries: u64) -> u64 {
+    let single = iter::once((
+        SumParticipantPublicKey::zeroed(),
+        SumParticipantEphemeralPublicKey::zeroed(),
+    ))
+    .collect::<SumDict>();
+    encoded_dict_length(single, entries)
+}
+
+/// Gets the length of an encoded update seed dictionary with the given number of entries.
+fn encoded_update_seed_dict_length(entries: u64) -> u64 {
+    let single = UpdateSeedDict::from_storage(iter::once((
+        UpdateParticipantPublicKey::zeroed(),
+        EncryptedMaskSeed::zeroed(),
+    )));
+    encoded_dict_length(single, entries)
+}
+
+/// Writes a row of the table of a [`RoundPlan`].
+fn write_row(f: &mut fmt::Formatter<'_>, name: &str, value: impl fmt::Display) -> fmt::Result {
+    writeln!(f, "{:<26}{:>16}", name, value)
+}
+
+impl fmt::Display for RoundPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.participants {
+            Some(participants) => write_row(f, "participants", participants)?,
+            None => write_row(f, "participants", "maximal counts")?,
+        }
+        write_row(f, "model length", self.model_length)?;
+        write_row(f, "bytes per number", self.bytes_per_number)?;
+        write_row(f, "sum participants", self.sum_participants)?;
+        write_row(f, "update participants", self.update_participants)?;
+        write_row(f, "sum2 participants", self.sum2_participants)?;
+        write_row(f, "sum dict entries", self.sum_dict_entries)?;
+        write_row(f, "masked model size (B)", self.masked_model_size)?;
+        write_row(f, "sum message size (B)", self.sum_message_size)?;
+        write_row(f, "update message size (B)", self.update_message_size)?;
+        write_row(f, "sum2 message size (B)", self.sum2_message_size)?;
+        write_row(f, "local seed dict size (B)", self.local_seed_dict_size)?;
+        write_row(f, "seed dict size (B)", self.seed_dict_size)?;
+        write_row(f, "ingress (B)", self.ingress)?;
+        write_row(f, "egress (B)", self.egress)?;
+        write_row(f, "aggregation memory (B)", self.aggregation_memory)?;
+        write_row(f, "redis memory (B)", self.redis_memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_pet_round() {
+        let plan = RoundPlan::new(
+            &PetSettings::default(),
+            MaskSettings::default(),
+            100,
+            Some(10_000),
+        );
+        assert_eq!(
+            plan,
+            RoundPlan {
+                participants: Some(10_000),
+                model_length: 100,
+                bytes_per_number: 6,
+                sum_participants: 100,
+                update_participants: 990,
+                sum2_participants: 100,
+                sum_dict_entries: 100,
+                masked_model_size: 618,
+                sum_message_size: 280,
+                update_message_size: 12_170,
+                sum2_message_size: 875,
+                local_seed_dict_size: 11_204,
+                seed_dict_size: 11_088_400,
+                ingress: 12_163_800,
+                egress: 20_600_720,
+                aggregation_memory: 3_838,
+                redis_memory: 11_196_858,
+            }
+        );
+    }
+
+    #[test]
+    fn test_plan_trusted_round() {
+        let pet = PetSettings {
+            mode: PetMode::Trusted,
+            ..PetSettings::default()
+        };
+        let plan = RoundPlan::new(&pet, MaskSettings::default(), 1_000, None);
+        assert_eq!(
+            plan,
+            RoundPlan {
+                participants: None,
+                model_length: 1_000,
+                bytes_per_number: 6,
+                sum_participants: 0,
+                update_participants: 10_000,
+                sum2_participants: 0,
+                sum_dict_entries: 1,
+                masked_model_size: 6_018,
+                sum_message_size: 280,
+                update_message_size: 6_482,
+                sum2_message_size: 6_275,
+                local_seed_dict_size: 116,
+                seed_dict_size: 1_120_004,
+                ingress: 64_820_000,
+                egress: 880_000,
+                aggregation_memory: 38_038,
+                redis_memory: 2_080_100,
+            }
+        );
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let plan = RoundPlan::new(&PetSettings::default(), MaskSettings::default(), 100, None);
+        assert_eq!(
+            max_message_size(&PetSettings::default(), MaskSettings::default(), 100),
+            plan.update_message_size + MESSAGE_SIZE_HEADROOM,
+        );
+    }
+
+    #[test]
+    fn test_plan_display() {
+        let plan = RoundPlan::new(&PetSettings::default(), MaskSettings::default(), 100, None);
+        let table = plan.to_string();
+        assert_eq!(table.lines().count(), 17);
+        assert!(table.starts_with("participants                maximal counts\n"));
+    }
+}
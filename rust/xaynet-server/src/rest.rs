@@ -1,15 +1,27 @@
 //! A HTTP API for the PET protocol interactions.
 
-use std::convert::Infallible;
 #[cfg(feature = "tls")]
 use std::path::PathBuf;
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use bytes::Bytes;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{pin_mut, FutureExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::{error, warn};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
 use warp::{
-    http::{Response, StatusCode},
+    http::{header, response::Builder as ResponseBuilder, HeaderMap, Response, StatusCode},
     reply::Reply,
     Filter,
 };
@@ -17,14 +29,68 @@ use warp::{
 use warp::{Server, TlsServer};
 
 use crate::{
-    services::{fetchers::Fetcher, messages::PetMessageHandler},
+    services::{
+        fetchers::{ContentEncoding, EncodedBody, Fetcher},
+        health::{HealthChecker, NotReady},
+        messages::{PetMessageHandler, ServiceError},
+        seed_export::SeedDictLocations,
+        submissions::SubmissionChecker,
+    },
     settings::ApiSettings,
+    state_machine::{
+        debug::StateDumper,
+        denylist::{DenylistError, DenylistManager},
+        pause::Pause,
+        requests::RequestError,
+    },
+    storage::{
+        circuit_breaker::is_retryable,
+        leader::Role,
+        CoordinatorStorage,
+    },
+};
+use xaynet_core::{
+    common::{RoundSeed, RoundSubmissionStatus, RoundSumDict, RoundSummary, ROUND_SUMMARY_VERSION},
+    crypto::ByteObject,
+    ParticipantPublicKey,
+    SeedDictSource,
+    VersionedSeeds,
 };
-use xaynet_core::{crypto::ByteObject, ParticipantPublicKey};
+
+#[derive(Deserialize, Serialize)]
+struct SumDictQuery {
+    /// Whether the sums are served as a [`RoundSumDict`], which ties them to their round.
+    #[serde(default)]
+    round: bool,
+}
 
 #[derive(Deserialize, Serialize)]
 struct SeedDictQuery {
     pk: String,
+    /// Whether the seeds are served as a [`SeedDictSource`], which may point to an exported blob.
+    #[serde(default)]
+    location: bool,
+    /// Whether the seeds are served as [`VersionedSeeds`], which carry the version of the seed
+    /// dictionary.
+    #[serde(default)]
+    version: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RoundSummaryQuery {
+    /// The version of the layout of the summary, see [`ROUND_SUMMARY_VERSION`].
+    version: Option<u16>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ModelQuery {
+    /// The round of the requested global model, if any.
+    round: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ModelDeltaQuery {
+    base: u64,
 }
 
 /// Starts a HTTP server at the given address, listening to GET requests for
@@ -33,139 +99,644 @@ struct SeedDictQuery {
 /// * `api_settings`: address of the server and optional certificate and key for TLS server
 ///   authentication as well as trusted anchors for TLS client authentication.
 /// * `fetcher`: fetcher for responding to data requests.
-/// * `pet_message_handler`: handler for responding to PET messages.
+/// * `seed_dict_locations`: locations of the exported seed dictionary, if it is exported.
+/// * `pet_message_handler`: handler for responding to PET messages and to requests of the
+///   rate-limited `POST /messages/validate` endpoint, which validates PET messages without
+///   processing them.
+/// * `submission_checker`: checker for responding to requests of the
+///   `GET /rounds/current/submissions/<pk>` endpoint, which tells which messages of the
+///   participant with the URL-safe base64 encoded public key are recorded in the current round.
+/// * `state_dumper`: dumper for responding to requests of the token-protected `GET /admin/state`
+///   and `GET /admin/dictionaries` debugging endpoints.
+/// * `denylist_manager`: manager for responding to requests of the token-protected
+///   `PUT /admin/denylist/<pk>` and `DELETE /admin/denylist/<pk>` endpoints, which ban and unban
+///   the participant with the URL-safe base64 encoded public key.
+/// * `pause`: switch for responding to requests of the token-protected `PUT /admin/pause` and
+///   `DELETE /admin/pause` endpoints, which pause and resume the acceptance of PET messages.
+/// * `health_checker`: checker for responding to requests of the unauthenticated `GET /healthz`
+///   liveness and `GET /readyz` readiness probes. The probes are neither rate-limited nor logged.
+/// * `shutdown`: signal for shutting down the server. Once it completes, the server stops
+///   accepting new connections and waits for the in-flight requests to finish for the shutdown
+///   grace period of the `api_settings`.
+///
+/// Requests which aren't answered within the request timeouts of the `api_settings` are replied
+/// to with `503 Service Unavailable`. The bodies of PET messages are limited to the message size
+/// limit of the `pet_message_handler`, larger requests are replied to with
+/// `413 Payload Too Large`. The other endpoints never read the request body.
 ///
 /// # Errors
 /// Fails if the TLS settings are invalid.
-pub async fn serve<F>(
+#[allow(clippy::too_many_arguments)]
+pub async fn serve<F, C, S>(
     api_settings: ApiSettings,
     fetcher: F,
+    seed_dict_locations: Option<SeedDictLocations>,
     pet_message_handler: PetMessageHandler,
+    submission_checker: SubmissionChecker<C>,
+    state_dumper: StateDumper<C>,
+    denylist_manager: DenylistManager<C>,
+    pause: Pause,
+    health_checker: HealthChecker,
+    shutdown: S,
 ) -> Result<(), RestError>
 where
     F: Fetcher + Sync + Send + 'static + Clone,
+    C: CoordinatorStorage,
+    S: Future<Output = ()> + Send + 'static,
 {
+    let request_timeout = Duration::from_secs(api_settings.request_timeout);
+    let model_request_timeout = Duration::from_secs(api_settings.model_request_timeout);
+    let max_message_size = pet_message_handler.max_message_size();
+
     let message = warp::path!("message")
         .and(warp::post())
-        .and(warp::body::bytes())
+        .and(with_body_limit(max_message_size))
+        .and(with_client_identity(api_settings.client_identity_header.clone()))
+        .and(with_message_handler(pet_message_handler.clone()))
+        .and_then(move |body, identity, handler| {
+            with_timeout(request_timeout, handle_message(body, identity, handler))
+        });
+
+    let validate_message = warp::path!("messages" / "validate")
+        .and(warp::post())
+        .and(with_rate_limit(api_settings.validate_rate_limit))
+        .and(with_body_limit(max_message_size))
         .and(with_message_handler(pet_message_handler.clone()))
-        .and_then(handle_message);
+        .and_then(move |body, handler| {
+            with_timeout(request_timeout, handle_validate_message(body, handler))
+        });
 
     let sum_dict = warp::path!("sums")
         .and(warp::get())
+        .and(warp::query::<SumDictQuery>().map(|query: SumDictQuery| query.round))
+        .and(with_content_encoding())
         .and(with_fetcher(fetcher.clone()))
-        .and_then(handle_sums);
+        .and_then(move |round, encoding, fetcher| {
+            with_timeout(request_timeout, handle_sums(round, encoding, fetcher))
+        });
 
     let seed_dict = warp::path!("seeds")
         .and(warp::get())
         .and(warp::query::<SeedDictQuery>())
         .and_then(part_pk)
+        .and(warp::query::<SeedDictQuery>().map(|query: SeedDictQuery| query.location))
+        .and(warp::query::<SeedDictQuery>().map(|query: SeedDictQuery| query.version))
+        .and(with_content_encoding())
         .and(with_fetcher(fetcher.clone()))
-        .and_then(handle_seeds);
+        .and(warp::any().map(move || seed_dict_locations.clone()))
+        .and_then(move |pk, location, version, encoding, fetcher, locations| {
+            with_timeout(
+                request_timeout,
+                handle_seeds(pk, location, version, encoding, fetcher, locations),
+            )
+        });
 
     let round_params = warp::path!("params")
         .and(warp::get())
         .and(with_fetcher(fetcher.clone()))
-        .and_then(handle_params);
+        .and_then(move |fetcher| with_timeout(request_timeout, handle_params(fetcher)));
+
+    let round_summary = warp::path!("rounds" / "current" / "summary")
+        .and(warp::get())
+        .and(warp::query::<RoundSummaryQuery>().map(|query: RoundSummaryQuery| query.version))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(with_fetcher(fetcher.clone()))
+        .and_then(move |version, if_none_match, fetcher| {
+            with_timeout(
+                request_timeout,
+                handle_round_summary(version, if_none_match, fetcher),
+            )
+        });
+
+    let submissions = warp::path!("rounds" / "current" / "submissions" / String)
+        .and(warp::get())
+        .and_then(path_pk)
+        .and(with_submission_checker(submission_checker))
+        .and_then(move |pk, submission_checker| {
+            with_timeout(request_timeout, handle_submissions(pk, submission_checker))
+        });
 
     let model = warp::path!("model")
+        .and(warp::get())
+        .and(warp::query::<ModelQuery>())
+        .and(with_content_encoding())
+        .and(with_fetcher(fetcher.clone()))
+        .and_then(move |query: ModelQuery, encoding, fetcher| {
+            with_timeout(
+                model_request_timeout,
+                handle_model(query.round, encoding, fetcher),
+            )
+        });
+
+    let model_delta = warp::path!("model" / "delta")
+        .and(warp::get())
+        .and(warp::query::<ModelDeltaQuery>())
+        .and(with_content_encoding())
+        .and(with_fetcher(fetcher.clone()))
+        .and_then(move |query: ModelDeltaQuery, encoding, fetcher| {
+            with_timeout(
+                model_request_timeout,
+                handle_model_delta(query.base, encoding, fetcher),
+            )
+        });
+
+    let model_metadata = warp::path!("model" / "metadata")
         .and(warp::get())
         .and(with_fetcher(fetcher.clone()))
-        .and_then(handle_model);
+        .and_then(move |fetcher| with_timeout(request_timeout, handle_model_metadata(fetcher)));
+
+    let admin_state = warp::path!("admin" / "state")
+        .and(warp::get())
+        .and(with_admin_token(api_settings.admin_token.clone()))
+        .and(with_state_dumper(state_dumper.clone()))
+        .and_then(move |state_dumper| {
+            with_timeout(request_timeout, handle_admin_state(state_dumper))
+        });
+
+    let admin_dictionaries = warp::path!("admin" / "dictionaries")
+        .and(warp::get())
+        .and(with_admin_token(api_settings.admin_token.clone()))
+        .and(with_state_dumper(state_dumper))
+        .and_then(move |state_dumper| {
+            with_timeout(request_timeout, handle_admin_dictionaries(state_dumper))
+        });
+
+    let admin_ban = warp::path!("admin" / "denylist" / String)
+        .and(warp::put())
+        .and(with_admin_token(api_settings.admin_token.clone()))
+        .and_then(path_pk)
+        .and(with_denylist_manager(denylist_manager.clone()))
+        .and_then(move |pk, denylist_manager| {
+            with_timeout(request_timeout, handle_admin_ban(pk, denylist_manager))
+        });
+
+    let admin_unban = warp::path!("admin" / "denylist" / String)
+        .and(warp::delete())
+        .and(with_admin_token(api_settings.admin_token.clone()))
+        .and_then(path_pk)
+        .and(with_denylist_manager(denylist_manager))
+        .and_then(move |pk, denylist_manager| {
+            with_timeout(request_timeout, handle_admin_unban(pk, denylist_manager))
+        });
+
+    let admin_pause = warp::path!("admin" / "pause")
+        .and(warp::put())
+        .and(with_admin_token(api_settings.admin_token.clone()))
+        .and(with_pause(pause.clone()))
+        .map(handle_admin_pause);
+
+    let admin_resume = warp::path!("admin" / "pause")
+        .and(warp::delete())
+        .and(with_admin_token(api_settings.admin_token.clone()))
+        .and(with_pause(pause))
+        .map(handle_admin_resume);
+
+    // the server is not ready anymore once it drains the in-flight requests
+    let draining = Arc::new(AtomicBool::new(false));
+    let shutdown = {
+        let draining = draining.clone();
+        shutdown.map(move |_| draining.store(true, Ordering::SeqCst))
+    };
+
+    let liveness = warp::path!("healthz")
+        .and(warp::get())
+        .and(with_health_checker(health_checker.clone()))
+        .and_then(handle_liveness);
+
+    let readiness = warp::path!("readyz")
+        .and(warp::get())
+        .and(with_health_checker(health_checker))
+        .map(move |health_checker| handle_readiness(health_checker, &draining));
 
     let routes = message
+        .or(validate_message)
         .or(round_params)
+        .or(round_summary)
+        .or(submissions)
         .or(sum_dict)
         .or(seed_dict)
         .or(model)
+        .or(model_delta)
+        .or(model_metadata)
+        .or(admin_state)
+        .or(admin_dictionaries)
+        .or(admin_ban)
+        .or(admin_unban)
+        .or(admin_pause)
+        .or(admin_resume)
         .recover(handle_reject)
         .with(warp::log("http"));
+    let routes = liveness.or(readiness).or(routes);
+
+    let shutdown = shutdown.boxed().shared();
+    #[cfg(not(feature = "tls"))]
+    return run_http(routes, api_settings, shutdown)
+        .await
+        .map_err(RestError::from);
+    #[cfg(feature = "tls")]
+    return run_https(routes, api_settings, shutdown).await;
+}
+
+/// The header which advertises the address of the current leader to the clients of a standby
+/// coordinator replica.
+pub const LEADER_HEADER: &str = "x-xaynet-leader";
+
+/// Starts an HTTP server for a standby coordinator replica at the given address.
+///
+/// Every request is answered with `503 Service Unavailable` and a `Retry-After` header. The
+/// address of the current leader is advertised in the [`LEADER_HEADER`] if it is known.
+///
+/// # Errors
+/// Fails if the TLS settings are invalid.
+pub async fn serve_standby<S>(
+    api_settings: ApiSettings,
+    role_rx: watch::Receiver<Role>,
+    shutdown: S,
+) -> Result<(), RestError>
+where
+    S: Future<Output = ()> + Send + 'static,
+{
+    let routes = warp::any()
+        .map(move || handle_standby(&role_rx.borrow()))
+        .with(warp::log("http"));
 
+    let shutdown = shutdown.boxed().shared();
     #[cfg(not(feature = "tls"))]
-    return run_http(routes, api_settings)
+    return run_http(routes, api_settings, shutdown)
         .await
         .map_err(RestError::from);
     #[cfg(feature = "tls")]
-    return run_https(routes, api_settings).await;
+    return run_https(routes, api_settings, shutdown).await;
+}
+
+/// Responds to a request to a standby coordinator replica.
+fn handle_standby(role: &Role) -> warp::reply::Response {
+    let mut response = Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(header::RETRY_AFTER, 1);
+    if let Role::Standby(Some(leader)) = role {
+        response = response.header(LEADER_HEADER, leader.as_str());
+    }
+    response.body(Vec::new()).unwrap().into_response()
+}
+
+/// Responds to a request with the reply of the `handler` or with `503 Service Unavailable` if the
+/// handler doesn't reply within the `timeout`.
+///
+/// The handler is dropped on timeout, which also cancels a pending wait for the response of the
+/// state machine. The state machine still processes the request, but its response is discarded.
+async fn with_timeout<H, R>(
+    timeout: Duration,
+    handler: H,
+) -> Result<warp::reply::Response, Infallible>
+where
+    H: Future<Output = Result<R, Infallible>>,
+    R: Reply,
+{
+    match tokio::time::timeout(timeout, handler).await {
+        Ok(reply) => reply.map(Reply::into_response),
+        Err(_) => {
+            warn!("failed to handle request: timed out after {:?}", timeout);
+            // ask the client to retry not before the timeout, but at least after a second
+            let retry_after = timeout.as_secs().max(1);
+            Ok(Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(header::RETRY_AFTER, retry_after)
+                .body(Vec::new())
+                .unwrap()
+                .into_response())
+        }
+    }
 }
 
 /// Handles and responds to a PET message.
+///
+/// Replies with `409 Conflict` if a sum2 message was rejected because of an outdated seed
+/// dictionary, so that the participant can refetch it, with `403 Forbidden` if the participant
+/// is banned, with `413 Payload Too Large` if the message exceeds the size limit and with
+/// `503 Service Unavailable` if the storage is temporarily unavailable, so that the participant
+/// can resend the message. Any other outcome is acknowledged with `200 OK`.
 async fn handle_message(
+    body: Bytes,
+    identity: Option<String>,
+    mut handler: PetMessageHandler,
+) -> Result<warp::reply::Response, Infallible> {
+    let status = match handler
+        .handle_message_with_identity(body.to_vec(), identity)
+        .await
+    {
+        Ok(()) => StatusCode::OK,
+        Err(ServiceError::StateMachine(RequestError::CoordinatorStorage(e)))
+            if is_retryable(&e) =>
+        {
+            warn!("failed to handle message: storage unavailable: {}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(header::RETRY_AFTER, 1)
+                .body(Vec::new())
+                .unwrap()
+                .into_response());
+        }
+        Err(ServiceError::StateMachine(RequestError::Paused)) => {
+            // the participant should try again once the acceptance of messages is resumed
+            debug!("failed to handle message: the acceptance of messages is paused");
+            return Ok(Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(header::RETRY_AFTER, 1)
+                .body(Vec::new())
+                .unwrap()
+                .into_response());
+        }
+        Err(ServiceError::StateMachine(e @ RequestError::StaleSeedDict(..))) => {
+            // the participant must be told to refetch the seed dictionary
+            warn!("failed to handle message: {:?}", e);
+            StatusCode::CONFLICT
+        }
+        Err(ServiceError::StateMachine(e)) if e.is_already_recorded() => {
+            // the participant resubmitted a message which has been accepted before
+            debug!("message already recorded: {}", e);
+            StatusCode::OK
+        }
+        Err(ServiceError::StateMachine(RequestError::Banned)) => {
+            // the participant must be told to stop participating
+            warn!("failed to handle message: participant is banned");
+            StatusCode::FORBIDDEN
+        }
+        Err(e @ ServiceError::MessageTooLarge(..)) => {
+            warn!("failed to handle message: {}", e);
+            StatusCode::PAYLOAD_TOO_LARGE
+        }
+        Err(e) => {
+            warn!("failed to handle message: {:?}", e);
+            StatusCode::OK
+        }
+    };
+    Ok(warp::reply::with_status(warp::reply(), status).into_response())
+}
+
+/// Handles and responds to a request for the validation of a PET message.
+///
+/// Replies with the JSON encoded verdict, which lists the outcome of every check of the message.
+/// The message is never processed.
+async fn handle_validate_message(
     body: Bytes,
     mut handler: PetMessageHandler,
 ) -> Result<impl warp::Reply, Infallible> {
-    let _ = handler.handle_message(body.to_vec()).await.map_err(|e| {
-        warn!("failed to handle message: {:?}", e);
-    });
-    Ok(warp::reply())
+    Ok(match handler.validate_message(body.to_vec()).await {
+        Ok(verdict) => Response::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .status(StatusCode::OK)
+            .body(serde_json::to_vec_pretty(&verdict).unwrap())
+            .unwrap(),
+        Err(e @ ServiceError::MessageTooLarge(..)) => {
+            warn!("failed to handle message validation request: {}", e);
+            Response::builder()
+                .status(StatusCode::PAYLOAD_TOO_LARGE)
+                .body(Vec::new())
+                .unwrap()
+        }
+        Err(e) => {
+            warn!("failed to handle message validation request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+    })
 }
 
 /// Handles and responds to a request for the sum dictionary.
-async fn handle_sums<F: Fetcher>(mut fetcher: F) -> Result<impl warp::Reply, Infallible> {
+///
+/// If the `round` is requested, the sums are served as a [`RoundSumDict`], which carries the seed
+/// of the round the sums belong to.
+async fn handle_sums<F: Fetcher>(
+    round: bool,
+    encoding: ContentEncoding,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
     Ok(match fetcher.sum_dict().await {
         Err(e) => {
             warn!("failed to handle sum dict request: {:?}", e);
             Response::builder()
                 .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Vec::new())
+                .body(Bytes::new())
                 .unwrap()
         }
         Ok(None) => Response::builder()
             .status(StatusCode::NO_CONTENT)
-            .body(Vec::new())
+            .body(Bytes::new())
             .unwrap(),
-        Ok(Some(dict)) => {
-            let bytes = bincode::serialize(dict.as_ref()).unwrap();
+        Ok(Some((dict, _, seed))) if round => {
+            let round_sum_dict = RoundSumDict {
+                seed,
+                sum_dict: dict.as_ref().clone(),
+            };
             Response::builder()
-                .header("Content-Type", "application/octet-stream")
+                .header(header::CONTENT_TYPE, "application/octet-stream")
                 .status(StatusCode::OK)
-                .body(bytes)
+                .body(Bytes::from(bincode::serialize(&round_sum_dict).unwrap()))
                 .unwrap()
         }
+        Ok(Some((_, body, _))) => encoded_response(Response::builder(), &body, encoding),
     })
 }
 
 /// Handles and responds to a request for the seed dictionary.
+///
+/// If the `location` is requested, the seeds are served as a [`SeedDictSource`], which points to
+/// the exported blob of the seeds if available and carries the seeds inline otherwise.
+///
+/// If the `version` is requested, the [`SeedDictSource`] is wrapped in [`VersionedSeeds`], which
+/// carry the version of the seed dictionary. Sum participants must echo this version in their
+/// sum2 message. The seeds only point to the exported blob if the `location` is requested, too.
 async fn handle_seeds<F: Fetcher>(
     pk: ParticipantPublicKey,
+    location: bool,
+    version: bool,
+    encoding: ContentEncoding,
     mut fetcher: F,
+    locations: Option<SeedDictLocations>,
 ) -> Result<impl warp::Reply, Infallible> {
     Ok(match fetcher.seed_dict().await {
         Err(e) => {
             warn!("failed to handle seed dict request: {:?}", e);
             Response::builder()
                 .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Vec::new())
+                .body(Bytes::new())
                 .unwrap()
         }
-        Ok(Some(dict)) if dict.get(&pk).is_some() => {
-            let bytes = bincode::serialize(dict.as_ref().get(&pk).unwrap()).unwrap();
+        Ok(Some((dict, _))) if (location || version) && dict.seeds.get(&pk).is_some() => {
+            let source = match locations
+                .filter(|_| location)
+                .and_then(|locations| locations.get(&dict, &pk))
+            {
+                Some(location) => SeedDictSource::Location(location),
+                None => SeedDictSource::Inline(dict.seeds.get(&pk).unwrap().clone()),
+            };
+            let body = if version {
+                bincode::serialize(&VersionedSeeds {
+                    version: dict.version,
+                    seeds: source,
+                })
+            } else {
+                bincode::serialize(&source)
+            };
             Response::builder()
-                .header("Content-Type", "application/octet-stream")
+                .header(header::CONTENT_TYPE, "application/octet-stream")
                 .status(StatusCode::OK)
-                .body(bytes)
+                .body(Bytes::from(body.unwrap()))
                 .unwrap()
         }
+        Ok(Some((dict, entries))) if dict.seeds.get(&pk).is_some() => {
+            let body = entries.get_or_insert(&pk, dict.seeds.get(&pk).unwrap());
+            encoded_response(Response::builder(), &body, encoding)
+        }
         _ => Response::builder()
             .status(StatusCode::NO_CONTENT)
-            .body(Vec::new())
+            .body(Bytes::new())
             .unwrap(),
     })
 }
 
+/// The header of a global model response which carries the JSON encoded metadata of the model.
+const MODEL_METADATA_HEADER: &str = "x-xaynet-model-metadata";
+
 /// Handles and responds to a request for the global model.
-async fn handle_model<F: Fetcher>(mut fetcher: F) -> Result<impl warp::Reply, Infallible> {
+///
+/// The response carries the JSON encoded metadata of the model in the
+/// [`MODEL_METADATA_HEADER`], if the metadata is known.
+///
+/// Only the latest global model is kept. If a `round` is requested, replies with `204 No
+/// Content` unless the latest global model is known to belong to that round.
+async fn handle_model<F: Fetcher>(
+    round: Option<u64>,
+    encoding: ContentEncoding,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(match fetcher.model().await {
+        Ok(Some((_, metadata, body)))
+            if round.is_none() || metadata.as_ref().map(|metadata| metadata.round_id) == round =>
+        {
+            let mut response = Response::builder();
+            if let Some(metadata) = metadata {
+                response = response.header(
+                    MODEL_METADATA_HEADER,
+                    serde_json::to_string(&metadata).unwrap(),
+                );
+            }
+            encoded_response(response, &body, encoding)
+        }
+        Ok(_) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Bytes::new())
+            .unwrap(),
+        Err(e) => {
+            warn!("failed to handle model request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Bytes::new())
+                .unwrap()
+        }
+    })
+}
+
+/// Handles and responds to a request for the delta from the global model of the `base` round to
+/// the latest global model.
+///
+/// Replies with `204 No Content` if no delta is available or if the delta doesn't start from the
+/// `base` round, in which case the client has to download the full global model.
+async fn handle_model_delta<F: Fetcher>(
+    base: u64,
+    encoding: ContentEncoding,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(match fetcher.model_delta().await {
+        Ok(Some((delta, body))) if delta.base_round_id == base => {
+            encoded_response(Response::builder(), &body, encoding)
+        }
+        Ok(_) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Bytes::new())
+            .unwrap(),
+        Err(e) => {
+            warn!("failed to handle model delta request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Bytes::new())
+                .unwrap()
+        }
+    })
+}
+
+/// Completes a response with the `body` in the negotiated content `encoding`.
+///
+/// The `Content-Encoding` header is omitted for the identity encoding.
+fn encoded_response(
+    response: ResponseBuilder,
+    body: &EncodedBody,
+    encoding: ContentEncoding,
+) -> Response<Bytes> {
+    let mut response = response
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::VARY, "accept-encoding");
+    if let Some(content_encoding) = encoding.header_value() {
+        response = response.header(header::CONTENT_ENCODING, content_encoding);
+    }
+    response
+        .status(StatusCode::OK)
+        .body(body.encoded(encoding))
+        .unwrap()
+}
+
+/// Handles and responds to a liveness probe.
+///
+/// Replies with `503 Service Unavailable` if the message processing is stalled.
+async fn handle_liveness(health_checker: HealthChecker) -> Result<impl warp::Reply, Infallible> {
+    Ok(if health_checker.check_liveness().await {
+        warp::reply::with_status("ok", StatusCode::OK)
+    } else {
+        warp::reply::with_status("stalled", StatusCode::SERVICE_UNAVAILABLE)
+    })
+}
+
+/// Handles and responds to a readiness probe.
+///
+/// Replies with `503 Service Unavailable` if the storage is not reachable, the state machine shut
+/// down, the acceptance of messages is paused or the server is `draining` the in-flight requests.
+fn handle_readiness(health_checker: HealthChecker, draining: &AtomicBool) -> impl warp::Reply {
+    if draining.load(Ordering::SeqCst) {
+        return warp::reply::with_status("draining", StatusCode::SERVICE_UNAVAILABLE);
+    }
+    match health_checker.check_readiness() {
+        Ok(()) => warp::reply::with_status("ok", StatusCode::OK),
+        Err(NotReady::Storage) => {
+            warp::reply::with_status("storage unavailable", StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(NotReady::Shutdown) => {
+            warp::reply::with_status("shut down", StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(NotReady::Paused) => {
+            warp::reply::with_status("paused", StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+/// Handles and responds to a request for the metadata of the global model.
+///
+/// Replies with `204 No Content` if no global model is available or if its metadata is unknown.
+async fn handle_model_metadata<F: Fetcher>(mut fetcher: F) -> Result<impl warp::Reply, Infallible> {
     Ok(match fetcher.model().await {
-        Ok(Some(model)) => Response::builder()
+        Ok(Some((_, Some(metadata), _))) => Response::builder()
+            .header("Content-Type", "application/octet-stream")
             .status(StatusCode::OK)
-            .body(bincode::serialize(model.as_ref()).unwrap())
+            .body(bincode::serialize(&metadata).unwrap())
             .unwrap(),
-        Ok(None) => Response::builder()
+        Ok(_) => Response::builder()
             .status(StatusCode::NO_CONTENT)
             .body(Vec::new())
             .unwrap(),
         Err(e) => {
-            warn!("failed to handle model request: {:?}", e);
+            warn!("failed to handle model metadata request: {:?}", e);
             Response::builder()
                 .status(StatusCode::INTERNAL_SERVER_ERROR)
                 .body(Vec::new())
@@ -191,6 +762,237 @@ async fn handle_params<F: Fetcher>(mut fetcher: F) -> Result<impl warp::Reply, I
     })
 }
 
+/// Handles and responds to a request for the round summary.
+///
+/// The summary is served in the layout of the requested `version`, which defaults to version 1
+/// for participants which don't request a version. Unsupported versions are answered with `400
+/// Bad Request`.
+///
+/// The summary only changes when a new round or phase starts. Therefore, the response carries an
+/// `ETag` and a request with a matching `If-None-Match` header is answered with `304 Not
+/// Modified` and an empty body.
+async fn handle_round_summary<F: Fetcher>(
+    version: Option<u16>,
+    if_none_match: Option<String>,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    let version = version.unwrap_or(1);
+    if version == 0 || version > ROUND_SUMMARY_VERSION {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Vec::new())
+            .unwrap());
+    }
+    Ok(match fetcher.round_summary().await {
+        Ok(summary) => {
+            let etag = round_summary_etag(&summary, version);
+            let response = Response::builder()
+                .header(header::ETAG, &etag)
+                .header(header::CACHE_CONTROL, "no-cache");
+            if matches!(if_none_match, Some(tags) if etag_matches(&tags, &etag)) {
+                response
+                    .status(StatusCode::NOT_MODIFIED)
+                    .body(Vec::new())
+                    .unwrap()
+            } else {
+                response
+                    .header("Content-Type", "application/octet-stream")
+                    .status(StatusCode::OK)
+                    .body(if version == 1 {
+                        bincode::serialize(&RoundSummaryV1::from(&summary)).unwrap()
+                    } else {
+                        bincode::serialize(&summary).unwrap()
+                    })
+                    .unwrap()
+            }
+        }
+        Err(e) => {
+            warn!("failed to handle round summary request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+    })
+}
+
+/// Handles and responds to a request for the submission status of a participant.
+///
+/// Replies with the [`RoundSubmissionStatus`], whose round seed tells whether the status belongs
+/// to the round of the participant.
+async fn handle_submissions<C: CoordinatorStorage>(
+    pk: ParticipantPublicKey,
+    mut submission_checker: SubmissionChecker<C>,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(match submission_checker.status(&pk).await {
+        Ok(status) => Response::builder()
+            .header("Content-Type", "application/octet-stream")
+            .status(StatusCode::OK)
+            .body(bincode::serialize::<RoundSubmissionStatus>(&status).unwrap())
+            .unwrap(),
+        Err(e) => {
+            warn!("failed to handle submission status request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+    })
+}
+
+/// The layout of version 1 of the round summary, which lacks the [`RoundSummary::crypto_suite`].
+#[derive(Serialize)]
+struct RoundSummaryV1<'a> {
+    round_id: u64,
+    seed: &'a RoundSeed,
+    round_token: &'a str,
+    phase: &'a str,
+    sum: f64,
+    update: f64,
+    eligibility_salt: &'a [u8],
+}
+
+impl<'a> From<&'a RoundSummary> for RoundSummaryV1<'a> {
+    fn from(summary: &'a RoundSummary) -> Self {
+        Self {
+            round_id: summary.round_id,
+            seed: &summary.seed,
+            round_token: &summary.round_token,
+            phase: &summary.phase,
+            sum: summary.sum,
+            update: summary.update,
+            eligibility_salt: &summary.eligibility_salt,
+        }
+    }
+}
+
+/// Gets the entity tag of a round summary in the layout of the `version`, which changes with the
+/// round and the phase.
+fn round_summary_etag(summary: &RoundSummary, version: u16) -> String {
+    format!("\"{}-{}-v{}\"", summary.round_id, summary.phase, version)
+}
+
+/// Checks whether the entity tags of an `If-None-Match` header match the `etag`.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Handles and responds to a request for a dump of the coordinator state.
+async fn handle_admin_state<C: CoordinatorStorage>(
+    mut state_dumper: StateDumper<C>,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(match state_dumper.dump().await {
+        Err(e) => {
+            warn!("failed to handle coordinator state request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+        Ok(None) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Vec::new())
+            .unwrap(),
+        Ok(Some(dump)) => Response::builder()
+            .header("Content-Type", "application/json")
+            .status(StatusCode::OK)
+            .body(serde_json::to_vec_pretty(&dump).unwrap())
+            .unwrap(),
+    })
+}
+
+/// Handles and responds to a request for a summary of the dictionaries of the current round.
+async fn handle_admin_dictionaries<C: CoordinatorStorage>(
+    mut state_dumper: StateDumper<C>,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(match state_dumper.dump_dictionaries().await {
+        Err(e) => {
+            warn!("failed to handle dictionaries request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+        Ok(dump) => Response::builder()
+            .header("Content-Type", "application/json")
+            .status(StatusCode::OK)
+            .body(serde_json::to_vec_pretty(&dump).unwrap())
+            .unwrap(),
+    })
+}
+
+/// Handles and responds to a request to ban a participant.
+///
+/// Replies with `201 Created` if the participant has been newly banned, with `200 OK` if the
+/// participant is already banned and with `409 Conflict` if the denylist is full.
+async fn handle_admin_ban<C: CoordinatorStorage>(
+    pk: ParticipantPublicKey,
+    mut denylist_manager: DenylistManager<C>,
+) -> Result<impl warp::Reply, Infallible> {
+    let status = match denylist_manager.ban(pk).await {
+        Ok(true) => StatusCode::CREATED,
+        Ok(false) => StatusCode::OK,
+        Err(DenylistError::Full(capacity)) => {
+            warn!(
+                "failed to ban participant: denylist capacity {} reached",
+                capacity
+            );
+            StatusCode::CONFLICT
+        }
+        Err(e) => {
+            warn!("failed to ban participant: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    Ok(warp::reply::with_status(warp::reply(), status))
+}
+
+/// Handles and responds to a request to lift the ban of a participant.
+///
+/// Replies with `200 OK` if the participant has been banned and with `404 Not Found` otherwise.
+async fn handle_admin_unban<C: CoordinatorStorage>(
+    pk: ParticipantPublicKey,
+    mut denylist_manager: DenylistManager<C>,
+) -> Result<impl warp::Reply, Infallible> {
+    let status = match denylist_manager.unban(&pk).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            warn!("failed to unban participant: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    Ok(warp::reply::with_status(warp::reply(), status))
+}
+
+/// Handles and responds to a request to pause the acceptance of PET messages.
+///
+/// Replies with `201 Created` if the acceptance has been newly paused and with `200 OK` if it is
+/// already paused.
+fn handle_admin_pause(pause: Pause) -> impl warp::Reply {
+    let status = if pause.pause() {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    warp::reply::with_status(warp::reply(), status)
+}
+
+/// Handles and responds to a request to resume the acceptance of PET messages.
+///
+/// Replies with `200 OK` if the acceptance has been paused and with `404 Not Found` otherwise.
+fn handle_admin_resume(pause: Pause) -> impl warp::Reply {
+    let status = if pause.resume() {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    };
+    warp::reply::with_status(warp::reply(), status)
+}
+
 /// Converts a PET message handler into a `warp` filter.
 fn with_message_handler(
     handler: PetMessageHandler,
@@ -198,6 +1000,22 @@ fn with_message_handler(
     warp::any().map(move || handler.clone())
 }
 
+/// Extracts the client certificate identity from the `header_name` header, if any.
+///
+/// The header is ignored if no `header_name` is configured or if it is empty.
+fn with_client_identity(
+    header_name: Option<String>,
+) -> impl Filter<Extract = (Option<String>,), Error = Infallible> + Clone {
+    warp::header::headers_cloned().map(move |headers: HeaderMap| {
+        header_name
+            .as_deref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .filter(|identity| !identity.is_empty())
+            .map(String::from)
+    })
+}
+
 /// Converts a data fetcher into a `warp` filter.
 fn with_fetcher<F: Fetcher + Sync + Send + 'static + Clone>(
     fetcher: F,
@@ -205,6 +1023,173 @@ fn with_fetcher<F: Fetcher + Sync + Send + 'static + Clone>(
     warp::any().map(move || fetcher.clone())
 }
 
+/// Negotiates the content encoding of a response from the `Accept-Encoding` header.
+fn with_content_encoding(
+) -> impl Filter<Extract = (ContentEncoding,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("accept-encoding").map(|accept_encoding: Option<String>| {
+        ContentEncoding::negotiate(accept_encoding.as_deref())
+    })
+}
+
+/// Converts a health checker into a `warp` filter.
+fn with_health_checker(
+    health_checker: HealthChecker,
+) -> impl Filter<Extract = (HealthChecker,), Error = Infallible> + Clone {
+    warp::any().map(move || health_checker.clone())
+}
+
+/// Converts a submission checker into a `warp` filter.
+fn with_submission_checker<C: CoordinatorStorage>(
+    submission_checker: SubmissionChecker<C>,
+) -> impl Filter<Extract = (SubmissionChecker<C>,), Error = Infallible> + Clone {
+    warp::any().map(move || submission_checker.clone())
+}
+
+/// Converts a state dumper into a `warp` filter.
+fn with_state_dumper<C: CoordinatorStorage>(
+    state_dumper: StateDumper<C>,
+) -> impl Filter<Extract = (StateDumper<C>,), Error = Infallible> + Clone {
+    warp::any().map(move || state_dumper.clone())
+}
+
+/// Converts a denylist manager into a `warp` filter.
+fn with_denylist_manager<C: CoordinatorStorage>(
+    denylist_manager: DenylistManager<C>,
+) -> impl Filter<Extract = (DenylistManager<C>,), Error = Infallible> + Clone {
+    warp::any().map(move || denylist_manager.clone())
+}
+
+/// Converts a pause switch into a `warp` filter.
+fn with_pause(pause: Pause) -> impl Filter<Extract = (Pause,), Error = Infallible> + Clone {
+    warp::any().map(move || pause.clone())
+}
+
+/// Checks the bearer token of a request against the admin token.
+///
+/// Requests are rejected as not found if no admin token is configured.
+fn with_admin_token(
+    admin_token: Option<String>,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            let admin_token = admin_token.clone();
+            async move {
+                let admin_token = admin_token.ok_or_else(warp::reject::not_found)?;
+                let token = authorization
+                    .as_deref()
+                    .and_then(|authorization| authorization.strip_prefix("Bearer "))
+                    .ok_or_else(|| warp::reject::custom(Unauthorized))?;
+                // compare in constant time to not leak the token via timing
+                if sodiumoxide::utils::memcmp(token.as_bytes(), admin_token.as_bytes()) {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// A limiter for the number of requests per second.
+#[derive(Clone)]
+struct RateLimiter {
+    /// The maximal number of requests per second.
+    limit: u32,
+    /// The start of the current one second window and the number of requests within it.
+    window: Arc<Mutex<(Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter for the given number of requests per second.
+    fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            window: Arc::new(Mutex::new((Instant::now(), 0))),
+        }
+    }
+
+    /// Counts a request and checks whether it is within the limit.
+    fn try_acquire(&self) -> bool {
+        let mut window = self.window.lock().unwrap();
+        let now = Instant::now();
+        if now.duration_since(window.0) >= Duration::from_secs(1) {
+            *window = (now, 0);
+        }
+        if window.1 < self.limit {
+            window.1 += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Limits the requests to the given number per second.
+///
+/// Requests are rejected as not found if the limit is `0`.
+fn with_rate_limit(limit: u32) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let limiter = RateLimiter::new(limit);
+    warp::any()
+        .and_then(move || {
+            let limiter = limiter.clone();
+            async move {
+                if limiter.limit == 0 {
+                    Err(warp::reject::not_found())
+                } else if limiter.try_acquire() {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(RateLimited))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// Reads the request body, which must not exceed `limit` bytes if a limit is given.
+///
+/// Requests are rejected as payload too large if the `Content-Length` header exceeds the limit,
+/// without reading the body at all, or as soon as the streamed body exceeds the limit.
+fn with_body_limit(
+    limit: Option<usize>,
+) -> impl Filter<Extract = (Bytes,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and(warp::body::stream())
+        .and_then(move |content_length, body| read_body(body, content_length, limit))
+}
+
+/// Reads a streamed body chunk by chunk until it ends or exceeds the `limit`.
+///
+/// At most the limit and a single chunk are read from the stream.
+async fn read_body<S, B, E>(
+    body: S,
+    content_length: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Bytes, warp::Rejection>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Buf,
+    E: Display,
+{
+    let limit = limit.unwrap_or(usize::MAX);
+    if matches!(content_length, Some(length) if length > limit as u64) {
+        return Err(warp::reject::custom(PayloadTooLarge));
+    }
+
+    pin_mut!(body);
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| {
+            warn!("failed to read request body: {}", e);
+            warp::reject::custom(InvalidBody)
+        })?;
+        if chunk.remaining() > limit - bytes.len() {
+            return Err(warp::reject::custom(PayloadTooLarge));
+        }
+        bytes.put(chunk);
+    }
+    Ok(bytes.freeze())
+}
+
 /// Extracts a participant public key from the url query string
 async fn part_pk(query: SeedDictQuery) -> Result<ParticipantPublicKey, warp::Rejection> {
     match base64::decode(query.pk.as_bytes()) {
@@ -219,17 +1204,53 @@ async fn part_pk(query: SeedDictQuery) -> Result<ParticipantPublicKey, warp::Rej
     }
 }
 
+/// Extracts a participant public key from the URL-safe base64 encoded path segment
+async fn path_pk(pk: String) -> Result<ParticipantPublicKey, warp::Rejection> {
+    base64::decode_config(pk.as_bytes(), base64::URL_SAFE)
+        .ok()
+        .and_then(|bytes| ParticipantPublicKey::from_slice(&bytes[..]))
+        .ok_or_else(|| warp::reject::custom(InvalidPublicKey))
+}
+
 #[derive(Debug)]
 struct InvalidPublicKey;
 
 impl warp::reject::Reject for InvalidPublicKey {}
 
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Debug)]
+struct RateLimited;
+
+impl warp::reject::Reject for RateLimited {}
+
+#[derive(Debug)]
+struct PayloadTooLarge;
+
+impl warp::reject::Reject for PayloadTooLarge {}
+
+#[derive(Debug)]
+struct InvalidBody;
+
+impl warp::reject::Reject for InvalidBody {}
+
 /// Handles `warp` rejections of bad requests.
 async fn handle_reject(err: warp::Rejection) -> Result<impl warp::Reply, Infallible> {
     let code = if err.is_not_found() {
         StatusCode::NOT_FOUND
     } else if let Some(InvalidPublicKey) = err.find() {
         StatusCode::BAD_REQUEST
+    } else if let Some(Unauthorized) = err.find() {
+        StatusCode::UNAUTHORIZED
+    } else if let Some(RateLimited) = err.find() {
+        StatusCode::TOO_MANY_REQUESTS
+    } else if let Some(PayloadTooLarge) = err.find() {
+        StatusCode::PAYLOAD_TOO_LARGE
+    } else if let Some(InvalidBody) = err.find() {
+        StatusCode::BAD_REQUEST
     } else {
         error!("unhandled rejection: {:?}", err);
         StatusCode::INTERNAL_SERVER_ERROR
@@ -282,34 +1303,837 @@ where
     Ok(server)
 }
 
+/// Runs a server until it terminates or until it is drained after the `shutdown` signal.
+///
+/// The server must stop accepting new connections once the `shutdown` signal completes. The
+/// in-flight requests are given the `grace_period` to finish before they are aborted.
+async fn drain<F, S>(server: F, shutdown: S, grace_period: Duration)
+where
+    F: Future<Output = ()>,
+    S: Future<Output = ()>,
+{
+    tokio::pin!(server);
+    tokio::select! {
+        _ = &mut server => return,
+        _ = shutdown => info!("draining in-flight requests of the REST server"),
+    }
+    if tokio::time::timeout(grace_period, server).await.is_err() {
+        warn!("aborting in-flight requests: the shutdown grace period elapsed");
+    }
+}
+
 #[cfg(not(feature = "tls"))]
-/// Runs a server with the provided filter routes.
-async fn run_http<F>(filter: F, api_settings: ApiSettings) -> Result<(), Infallible>
+/// Runs a server with the provided filter routes until it is drained after the `shutdown` signal.
+async fn run_http<F, S>(filter: F, api_settings: ApiSettings, shutdown: S) -> Result<(), Infallible>
 where
     F: Filter + Clone + Send + Sync + 'static,
     F::Extract: Reply,
+    S: Future<Output = ()> + Clone + Send + 'static,
 {
-    warp::serve(filter).run(api_settings.bind_address).await;
+    let (_, server) = warp::serve(filter)
+        .bind_with_graceful_shutdown(api_settings.bind_address, shutdown.clone());
+    let grace_period = Duration::from_secs(api_settings.shutdown_grace_period);
+    drain(server, shutdown, grace_period).await;
     Ok(())
 }
 
 #[cfg(feature = "tls")]
-/// Runs a TLS server with the provided filter routes.
+/// Runs a TLS server with the provided filter routes until it is drained after the `shutdown`
+/// signal.
 ///
 /// # Errors
 /// Fails if the TLS settings are invalid.
-async fn run_https<F>(filter: F, api_settings: ApiSettings) -> Result<(), RestError>
+async fn run_https<F, S>(filter: F, api_settings: ApiSettings, shutdown: S) -> Result<(), RestError>
 where
     F: Filter + Clone + Send + Sync + 'static,
     F::Extract: Reply,
+    S: Future<Output = ()> + Clone + Send + 'static,
 {
-    configure_tls(
+    let (_, server) = configure_tls(
         warp::serve(filter),
         api_settings.tls_certificate,
         api_settings.tls_key,
         api_settings.tls_client_auth,
     )?
-    .run(api_settings.bind_address)
-    .await;
+    .bind_with_graceful_shutdown(api_settings.bind_address, shutdown.clone());
+    let grace_period = Duration::from_secs(api_settings.shutdown_grace_period);
+    drain(server, shutdown, grace_period).await;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use flate2::read::GzDecoder;
+    use futures::{future, stream};
+    use tracing::Span;
+
+    use std::{
+        io::Read,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+    use crate::{
+        plan::max_message_size,
+        services::{
+            fetchers::fetcher,
+            health::{StorageHealthMonitor, STORAGE_HEALTH_INTERVAL},
+            seed_export::SeedDictExporter,
+            tests::utils::{encrypt_message, mask_config, new_event_channels, new_update_message},
+        },
+        state_machine::{
+            events::{DictionaryUpdate, ModelUpdate, VersionedSeedDict},
+            phases::PhaseName,
+            requests::{RequestReceiver, StateMachineRequest, SumRequest},
+            tests::utils::{mask_settings, pet_settings},
+        },
+        storage::{
+            tests::{MockCoordinatorStore, MockModelStore},
+            FsBlobSink,
+            Store,
+        },
+    };
+    use xaynet_core::{
+        common::{GlobalModelMetadata, SubmissionStatus},
+        crypto::{EncryptKeyPair, SigningKeyPair},
+        mask::{
+            DataType,
+            EncryptedMaskSeed,
+            Endianness,
+            FromPrimitives,
+            MaskConfig,
+            MaskObject,
+            Model,
+        },
+        message::{FromBytes, Payload},
+        LocalSeedDict,
+        SeedDict,
+        SumDict,
+        UpdateSeedDict,
+    };
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"3-Sum\"";
+        assert!(etag_matches("\"3-Sum\"", etag));
+        assert!(etag_matches("W/\"3-Sum\"", etag));
+        assert!(etag_matches("\"2-Unmask\", \"3-Sum\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("\"3-Update\"", etag));
+        assert!(!etag_matches("", etag));
+    }
+
+    #[tokio::test]
+    async fn test_client_identity() {
+        let header_name = Some("x-client-cert-fingerprint".to_string());
+
+        let identity = warp::test::request()
+            .header("x-client-cert-fingerprint", "device-a")
+            .filter(&with_client_identity(header_name.clone()))
+            .await
+            .unwrap();
+        assert_eq!(identity.as_deref(), Some("device-a"));
+
+        let identity = warp::test::request()
+            .header("x-client-cert-fingerprint", "")
+            .filter(&with_client_identity(header_name.clone()))
+            .await
+            .unwrap();
+        assert!(identity.is_none());
+
+        let identity = warp::test::request()
+            .filter(&with_client_identity(header_name))
+            .await
+            .unwrap();
+        assert!(identity.is_none());
+
+        // the header is ignored unless configured
+        let identity = warp::test::request()
+            .header("x-client-cert-fingerprint", "device-a")
+            .filter(&with_client_identity(None))
+            .await
+            .unwrap();
+        assert!(identity.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_round_summary_not_modified() {
+        let (mut publisher, subscriber) = new_event_channels();
+        let fetcher = fetcher(&subscriber, 6);
+
+        let response = handle_round_summary(Some(ROUND_SUMMARY_VERSION), None, fetcher.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = handle_round_summary(
+            Some(ROUND_SUMMARY_VERSION),
+            Some(etag.clone()),
+            fetcher.clone(),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // the summary changes with the phase
+        publisher.broadcast_phase(PhaseName::Sum);
+        let response =
+            handle_round_summary(Some(ROUND_SUMMARY_VERSION), Some(etag.clone()), fetcher)
+                .await
+                .unwrap()
+                .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[header::ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_round_summary_versions() {
+        let (_publisher, subscriber) = new_event_channels();
+        let fetcher = fetcher(&subscriber, 6);
+        let round_summary = |version: Option<u16>| {
+            let fetcher = fetcher.clone();
+            async move {
+                let response = handle_round_summary(version, None, fetcher)
+                    .await
+                    .unwrap()
+                    .into_response();
+                let status = response.status();
+                let etag = response.headers().get(header::ETAG).cloned();
+                let body = warp::hyper::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap();
+                (status, etag, body)
+            }
+        };
+
+        let (status, etag_v2, body_v2) = round_summary(Some(ROUND_SUMMARY_VERSION)).await;
+        assert_eq!(status, StatusCode::OK);
+        let summary = bincode::deserialize::<RoundSummary>(&body_v2).unwrap();
+
+        // version 1 is served by default and lacks the trailing crypto suite
+        let (status, etag_v1, body_v1) = round_summary(None).await;
+        assert_eq!(status, StatusCode::OK);
+        let crypto_suite_length = bincode::serialized_size(&summary.crypto_suite).unwrap();
+        assert_eq!(
+            &body_v1[..],
+            &body_v2[..body_v2.len() - crypto_suite_length as usize]
+        );
+        assert_eq!(round_summary(Some(1)).await.2, body_v1);
+        // the layouts are cached separately
+        assert_ne!(etag_v1, etag_v2);
+
+        for version in &[0, ROUND_SUMMARY_VERSION + 1] {
+            let (status, _, _) = round_summary(Some(*version)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submissions() {
+        let (_publisher, subscriber) = new_event_channels();
+        let pk = SigningKeyPair::generate().public;
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_submission_status().return_once(|_| {
+            Ok(SubmissionStatus {
+                sum: false,
+                update: true,
+                sum2: false,
+                evicted: false,
+            })
+        });
+        let submission_checker = SubmissionChecker::new(cs, &subscriber);
+
+        let response = handle_submissions(pk, submission_checker)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let status: RoundSubmissionStatus = bincode::deserialize(&body).unwrap();
+        assert!(status.is_for(&subscriber.params_listener().get_latest().event));
+        assert!(status.status.update);
+        assert!(!status.status.sum && !status.status.sum2);
+    }
+
+    #[tokio::test]
+    async fn test_model_metadata() {
+        let (mut publisher, subscriber) = new_event_channels();
+        let fetcher = fetcher(&subscriber, 6);
+
+        let response = handle_model_metadata(fetcher.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let weights = [i64::MIN, -1, 0, i64::MAX];
+        let model = Model::from_primitives(weights.iter().cloned()).unwrap();
+        let mask_config = MaskConfig {
+            data_type: DataType::I64,
+            ..mask_config()
+        };
+        let metadata = GlobalModelMetadata::new(1, mask_config.into(), weights.len(), 1);
+        publisher.broadcast_model(ModelUpdate::New(Arc::new(model), Some(metadata.clone())));
+
+        // the metadata is sent alongside the model
+        let response = handle_model(None, ContentEncoding::Identity, fetcher.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let header = response.headers()[MODEL_METADATA_HEADER].to_str().unwrap();
+        let sent_metadata: GlobalModelMetadata = serde_json::from_str(header).unwrap();
+        assert_eq!(sent_metadata, metadata);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let sent_model: Model = bincode::deserialize(&body).unwrap();
+        let bytes = sent_metadata
+            .to_primitives_bytes(&sent_model, Endianness::Little)
+            .unwrap();
+        assert_eq!(
+            bytes,
+            weights
+                .iter()
+                .flat_map(|weight| weight.to_le_bytes())
+                .collect::<Vec<u8>>(),
+        );
+
+        // and on its own
+        let response = handle_model_metadata(fetcher)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let sent_metadata: GlobalModelMetadata = bincode::deserialize(&body).unwrap();
+        assert_eq!(sent_metadata, metadata);
+    }
+
+    #[tokio::test]
+    async fn test_model_of_round() {
+        let (mut publisher, subscriber) = new_event_channels();
+        let fetcher = fetcher(&subscriber, 6);
+        let model_of_round = |round| {
+            let fetcher = fetcher.clone();
+            async move {
+                handle_model(round, ContentEncoding::Identity, fetcher)
+                    .await
+                    .unwrap()
+                    .into_response()
+                    .status()
+            }
+        };
+
+        // the round of a model without metadata is unknown
+        let model = Model::from_primitives(vec![0_i32, 1, 2].into_iter()).unwrap();
+        publisher.broadcast_model(ModelUpdate::New(Arc::new(model.clone()), None));
+        assert_eq!(model_of_round(None).await, StatusCode::OK);
+        assert_eq!(model_of_round(Some(2)).await, StatusCode::NO_CONTENT);
+
+        let metadata = GlobalModelMetadata::new(2, mask_config().into(), 3, 1);
+        publisher.broadcast_model(ModelUpdate::New(Arc::new(model), Some(metadata)));
+        assert_eq!(model_of_round(Some(2)).await, StatusCode::OK);
+        // only the latest global model is served
+        assert_eq!(model_of_round(Some(1)).await, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_content_encoding_negotiation() {
+        let filter = with_content_encoding();
+        let encoding = warp::test::request()
+            .header("accept-encoding", "gzip, deflate")
+            .filter(&filter)
+            .await
+            .unwrap();
+        assert_eq!(encoding, ContentEncoding::Gzip);
+
+        let encoding = warp::test::request().filter(&filter).await.unwrap();
+        assert_eq!(encoding, ContentEncoding::Identity);
+    }
+
+    #[tokio::test]
+    async fn test_sums_content_encoding() {
+        let (mut publisher, subscriber) = new_event_channels();
+        let fetcher = fetcher(&subscriber, 6);
+
+        let mut sum_dict = SumDict::new();
+        for i in 0..100 {
+            sum_dict.insert(
+                SigningKeyPair::generate().public,
+                EncryptKeyPair::generate().public,
+            );
+            assert_eq!(sum_dict.len(), i + 1);
+        }
+        let identity = bincode::serialize(&sum_dict).unwrap();
+        publisher.broadcast_sum_dict(DictionaryUpdate::New(Arc::new(sum_dict.clone())));
+
+        // the compressed dictionary is served to clients which accept it
+        let mut bodies = Vec::new();
+        for _ in 0..2 {
+            let response = handle_sums(false, ContentEncoding::Gzip, fetcher.clone())
+                .await
+                .unwrap()
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+            bodies.push(
+                warp::hyper::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_eq!(bodies[0], bodies[1]);
+        let mut decoded = Vec::new();
+        GzDecoder::new(bodies[0].as_ref())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, identity);
+
+        // clients without an accepted encoding get the uncompressed dictionary
+        let response = handle_sums(false, ContentEncoding::Identity, fetcher.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(body, identity);
+
+        // the sums are tied to their round on request
+        let response = handle_sums(true, ContentEncoding::Gzip, fetcher)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let round_sum_dict = bincode::deserialize::<RoundSumDict>(&body).unwrap();
+        assert_eq!(round_sum_dict.sum_dict, sum_dict);
+        assert!(round_sum_dict.is_for(&subscriber.params_listener().get_latest().event));
+    }
+
+    #[test]
+    fn test_standby_advertises_leader() {
+        let response = handle_standby(&Role::Standby(Some("http://leader:8081".into())));
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+        assert_eq!(response.headers()[LEADER_HEADER], "http://leader:8081");
+
+        let response = handle_standby(&Role::Standby(None));
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().get(LEADER_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_seeds_location() {
+        let (mut publisher, subscriber) = new_event_channels();
+        let fetcher = fetcher(&subscriber, 6);
+        let path = std::env::temp_dir().join(format!("xaynet_rest_seeds_{}", std::process::id()));
+        let sink = FsBlobSink::new(&path, "https://seeds.example.com");
+        let (exporter, locations) = SeedDictExporter::new(sink, &subscriber);
+        let exporter = tokio::spawn(exporter.run());
+
+        let sum_pk = SigningKeyPair::generate().public;
+        let seeds = UpdateSeedDict::from_storage(vec![(
+            SigningKeyPair::generate().public,
+            EncryptedMaskSeed::zeroed(),
+        )]);
+        let seed_dict = Arc::new(VersionedSeedDict {
+            version: 7,
+            seeds: vec![(sum_pk, seeds.clone())]
+                .into_iter()
+                .collect::<SeedDict>(),
+        });
+        publisher.broadcast_seed_dict(DictionaryUpdate::New(seed_dict.clone()));
+
+        let seeds_source = |locations: Option<SeedDictLocations>| {
+            let fetcher = fetcher.clone();
+            async move {
+                let encoding = ContentEncoding::Identity;
+                let response = handle_seeds(sum_pk, true, false, encoding, fetcher, locations)
+                    .await
+                    .unwrap()
+                    .into_response();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = warp::hyper::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap();
+                bincode::deserialize::<SeedDictSource>(&body).unwrap()
+            }
+        };
+
+        // the seeds are served inline unless they are exported
+        assert_eq!(seeds_source(None).await, SeedDictSource::Inline(seeds.clone()));
+        while locations.get(&seed_dict, &sum_pk).is_none() {
+            tokio::task::yield_now().await;
+        }
+        let location = match seeds_source(Some(locations.clone())).await {
+            SeedDictSource::Location(location) => location,
+            source => panic!("unexpected seeds source: {:?}", source),
+        };
+        let name = location
+            .strip_prefix("https://seeds.example.com/")
+            .unwrap()
+            .to_string();
+        let exported = std::fs::read(path.join(name)).unwrap();
+        assert_eq!(UpdateSeedDict::from_byte_slice(&exported).unwrap(), seeds);
+
+        // the plain seeds are still served to clients which don't ask for the location
+        let response = handle_seeds(
+            sum_pk,
+            false,
+            false,
+            ContentEncoding::Identity,
+            fetcher.clone(),
+            None,
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(
+            bincode::deserialize::<UpdateSeedDict>(&body).unwrap(),
+            seeds
+        );
+
+        // the versioned seeds carry the version of the seed dict and only point to the exported
+        // blob if the location is requested, too
+        let versioned_seeds = |location: bool| {
+            let fetcher = fetcher.clone();
+            let locations = locations.clone();
+            async move {
+                let encoding = ContentEncoding::Identity;
+                let response =
+                    handle_seeds(sum_pk, location, true, encoding, fetcher, Some(locations))
+                        .await
+                        .unwrap()
+                        .into_response();
+                let body = warp::hyper::body::to_bytes(response.into_body())
+                    .await
+                    .unwrap();
+                bincode::deserialize::<VersionedSeeds<SeedDictSource>>(&body).unwrap()
+            }
+        };
+        assert_eq!(
+            versioned_seeds(false).await,
+            VersionedSeeds {
+                version: 7,
+                seeds: SeedDictSource::Inline(seeds.clone()),
+            }
+        );
+        assert_eq!(
+            versioned_seeds(true).await,
+            VersionedSeeds {
+                version: 7,
+                seeds: SeedDictSource::Location(location),
+            }
+        );
+
+        drop(publisher);
+        exporter.await.unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stalled_request_times_out() {
+        let stalled = future::pending::<Result<StatusCode, Infallible>>();
+        let response = with_timeout(Duration::from_millis(10), stalled)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        let response = with_timeout(Duration::from_secs(30), future::ok(StatusCode::OK))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_response_after_timeout_is_discarded() {
+        let (mut request_rx, request_tx) = RequestReceiver::new();
+        let request = StateMachineRequest::Sum(SumRequest {
+            participant_pk: SigningKeyPair::generate().public,
+            ephm_pk: EncryptKeyPair::generate().public,
+            identity: None,
+        });
+        let handler = async move {
+            let response = request_tx.request(request, Span::none()).await;
+            Ok::<_, Infallible>(match response {
+                Ok(()) => StatusCode::OK,
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            })
+        };
+        let response = with_timeout(Duration::from_millis(10), handler)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // the state machine answers the request only after the timeout
+        let (_, _, resp_tx) = request_rx.next().await.unwrap();
+        assert!(resp_tx.is_closed());
+        assert!(resp_tx.send(Ok(())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_message() {
+        let (_publisher, subscriber) = new_event_channels();
+        let (mut request_rx, request_tx) = RequestReceiver::new();
+        let handler = PetMessageHandler::new(&subscriber, request_tx);
+
+        let response = handle_validate_message(Bytes::from(vec![0, 1, 2, 3]), handler)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let verdict: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(verdict["valid"], false);
+        assert_eq!(verdict["checks"][0]["check"], "decryption");
+        assert_eq!(verdict["checks"][0]["status"], "failed");
+        assert_eq!(verdict["checks"][1]["status"], "skipped");
+
+        // the message never reaches the state machine
+        assert!(request_rx.next().now_or_never().flatten().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probes() {
+        let (_publisher, subscriber) = new_event_channels();
+        let (_request_rx, request_tx) = RequestReceiver::new();
+        let handler = PetMessageHandler::new(&subscriber, request_tx);
+        let store = Store::new(MockCoordinatorStore::new(), MockModelStore::new());
+        let (_monitor, storage) = StorageHealthMonitor::new(store, STORAGE_HEALTH_INTERVAL);
+        let health_checker = HealthChecker::new(handler, storage, &subscriber);
+
+        let response = handle_liveness(health_checker.clone())
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // the storage hasn't been checked yet
+        let draining = AtomicBool::new(false);
+        let response = handle_readiness(health_checker.clone(), &draining).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(body, "storage unavailable");
+
+        draining.store(true, Ordering::SeqCst);
+        let response = handle_readiness(health_checker, &draining).into_response();
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(body, "draining");
+    }
+
+    #[test]
+    fn test_admin_pause() {
+        let pause = Pause::default();
+        let response = handle_admin_pause(pause.clone()).into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(pause.is_paused());
+        let response = handle_admin_pause(pause.clone()).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = handle_admin_resume(pause.clone()).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!pause.is_paused());
+        let response = handle_admin_resume(pause).into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let filter = with_rate_limit(2);
+        for _ in 0..2 {
+            assert!(warp::test::request().filter(&filter).await.is_ok());
+        }
+        let rejection = warp::test::request().filter(&filter).await.unwrap_err();
+        let response = handle_reject(rejection).await.unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // a limit of zero disables the endpoint
+        let filter = with_rate_limit(0);
+        let rejection = warp::test::request().filter(&filter).await.unwrap_err();
+        assert!(rejection.is_not_found());
+    }
+
+    #[tokio::test]
+    async fn test_body_limit() {
+        let filter = with_body_limit(Some(100));
+        let body = warp::test::request()
+            .method("POST")
+            .body(vec![1; 100])
+            .filter(&filter)
+            .await
+            .unwrap();
+        assert_eq!(body, vec![1; 100]);
+
+        let rejection = warp::test::request()
+            .method("POST")
+            .body(vec![1; 101])
+            .filter(&filter)
+            .await
+            .unwrap_err();
+        let response = handle_reject(rejection).await.unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_stops_reading_oversized_stream() {
+        // an endless body without a content length, which counts the bytes read from it
+        let read = Arc::new(AtomicUsize::new(0));
+        let counter = read.clone();
+        let body = stream::repeat_with(move || {
+            counter.fetch_add(16, Ordering::SeqCst);
+            Ok::<_, Infallible>(Bytes::from(vec![1; 16]))
+        });
+
+        let rejection = read_body(body, None, Some(100)).await.unwrap_err();
+        assert!(rejection.find::<PayloadTooLarge>().is_some());
+        // the limit is exceeded by the last read chunk
+        assert!(read.load(Ordering::SeqCst) <= 100 + 16);
+    }
+
+    #[tokio::test]
+    async fn test_max_size_message_passes_body_limit() {
+        let mut pet_settings = pet_settings();
+        pet_settings.sum.count.max = 3;
+        let limit = max_message_size(&pet_settings, mask_settings(), 10) as usize;
+
+        // an update message of the maximal size
+        let (_publisher, subscriber) = new_event_channels();
+        let round_params = subscriber.params_listener().get_latest().event;
+        let (mut message, keys) = new_update_message(&round_params);
+        if let Payload::Update(update) = &mut message.payload {
+            let config = MaskConfig::from(mask_settings()).into();
+            update.masked_model = MaskObject::empty(config, 10);
+            update.local_seed_dict = LocalSeedDict::from_decoded((0..3).map(|_| {
+                (
+                    SigningKeyPair::generate().public,
+                    EncryptedMaskSeed::zeroed(),
+                )
+            }));
+        }
+        let message = encrypt_message(&message, &round_params, &keys);
+        assert!(message.len() <= limit);
+
+        let body = warp::test::request()
+            .method("POST")
+            .body(message.clone())
+            .filter(&with_body_limit(Some(limit)))
+            .await
+            .unwrap();
+        assert_eq!(body, message);
+
+        // the message handler checks the limit independently of the transport
+        let (_, request_tx) = RequestReceiver::new();
+        let handler = PetMessageHandler::new(&subscriber, request_tx)
+            .with_max_message_size(message.len() - 1);
+        let response = handle_message(Bytes::from(message), None, handler)
+            .await
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[cfg(feature = "tls")]
+    mod tls {
+        use std::fs;
+
+        use super::super::*;
+
+        /// Gets the path to a file generated by `tests/tls/generate.sh`.
+        fn tls_file(name: &str) -> PathBuf {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/tls")
+                .join(name)
+        }
+
+        /// Spawns a TLS server which requires client certificates signed by the test CA.
+        fn spawn_server() -> u16 {
+            let route = warp::any().map(|| "ok");
+            let (addr, server) = configure_tls(
+                warp::serve(route),
+                Some(tls_file("server.pem")),
+                Some(tls_file("server.key")),
+                Some(tls_file("ca.pem")),
+            )
+            .unwrap()
+            .bind_ephemeral(([127, 0, 0, 1], 0));
+            tokio::spawn(server);
+            addr.port()
+        }
+
+        /// Creates a client which trusts the test CA and authenticates with the given `identity`.
+        fn client(identity: Option<&str>) -> reqwest::Client {
+            let ca = fs::read(tls_file("ca.pem")).unwrap();
+            let builder = reqwest::Client::builder()
+                .use_rustls_tls()
+                .add_root_certificate(reqwest::Certificate::from_pem(&ca).unwrap());
+            let builder = match identity {
+                Some(identity) => {
+                    let mut pem = fs::read(tls_file(&format!("{}.key", identity))).unwrap();
+                    pem.extend(fs::read(tls_file(&format!("{}.pem", identity))).unwrap());
+                    builder.identity(reqwest::Identity::from_pem(&pem).unwrap())
+                }
+                None => builder,
+            };
+            builder.build().unwrap()
+        }
+
+        async fn get(client: reqwest::Client, port: u16) -> Result<String, reqwest::Error> {
+            client
+                .get(format!("https://localhost:{}/", port))
+                .send()
+                .await?
+                .text()
+                .await
+        }
+
+        #[tokio::test]
+        async fn test_tls_client_ca_accepts_trusted_client() {
+            let port = spawn_server();
+            let body = get(client(Some("client")), port).await.unwrap();
+            assert_eq!(body, "ok");
+        }
+
+        #[tokio::test]
+        async fn test_tls_client_ca_rejects_untrusted_client() {
+            let port = spawn_server();
+            assert!(get(client(Some("untrusted_client")), port).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_tls_client_ca_rejects_anonymous_client() {
+            let port = spawn_server();
+            assert!(get(client(None), port).await.is_err());
+        }
+    }
+}
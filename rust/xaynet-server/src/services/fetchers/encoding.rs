@@ -0,0 +1,250 @@
+//! Content encodings of the served data.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use flate2::{
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+/// The content encodings which the fetched data can be served in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContentEncoding {
+    /// No compression.
+    Identity,
+    /// Gzip compression.
+    Gzip,
+    /// Zlib compression, which is called `deflate` in HTTP.
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Negotiates the content encoding from the value of an `Accept-Encoding` header.
+    ///
+    /// Gzip is preferred over deflate. Encodings with a quality value of zero are not acceptable.
+    /// Falls back to the identity encoding if no compression is acceptable.
+    pub fn negotiate(accept_encoding: Option<&str>) -> Self {
+        let codings = accept_encoding
+            .into_iter()
+            .flat_map(|header| header.split(','))
+            .filter_map(|coding| {
+                let mut params = coding.split(';').map(str::trim);
+                let coding = params.next()?;
+                let quality = params
+                    .find_map(|param| param.strip_prefix("q="))
+                    .map_or(Some(1.0), |quality| quality.parse::<f32>().ok())?;
+                Some((coding, quality))
+            })
+            .collect::<Vec<_>>();
+        // an explicitly named encoding takes precedence over the wildcard
+        let accepted = |name: &str| {
+            let coding = codings
+                .iter()
+                .find(|(coding, _)| coding.eq_ignore_ascii_case(name))
+                .or_else(|| codings.iter().find(|(coding, _)| *coding == "*"));
+            matches!(coding, Some((_, quality)) if *quality > 0.0)
+        };
+
+        if accepted("gzip") {
+            Self::Gzip
+        } else if accepted("deflate") {
+            Self::Deflate
+        } else {
+            Self::Identity
+        }
+    }
+
+    /// Gets the value of the `Content-Encoding` header for this encoding.
+    ///
+    /// Returns `None` for the identity encoding, which is signaled by omitting the header.
+    pub fn header_value(&self) -> Option<&'static str> {
+        match self {
+            Self::Identity => None,
+            Self::Gzip => Some("gzip"),
+            Self::Deflate => Some("deflate"),
+        }
+    }
+}
+
+/// The serialized representation of an immutable payload.
+///
+/// The compressed representations are computed once on demand and cached alongside the
+/// uncompressed one. The payloads are immutable during a phase, hence they are compressed at most
+/// once per phase instead of once per request.
+#[derive(Debug)]
+pub struct EncodedBody {
+    identity: Bytes,
+    gzip: OnceCell<Bytes>,
+    deflate: OnceCell<Bytes>,
+    level: Compression,
+}
+
+impl EncodedBody {
+    /// Creates a new body from the bincode serialization of the `value`.
+    ///
+    /// The `level` of the compression ranges from `0` (none) to `9` (best).
+    pub fn new<T: Serialize + ?Sized>(value: &T, level: u32) -> Self {
+        Self {
+            // safe unwrap: the served data types are always serializable
+            identity: bincode::serialize(value).unwrap().into(),
+            gzip: OnceCell::new(),
+            deflate: OnceCell::new(),
+            level: Compression::new(level),
+        }
+    }
+
+    /// Gets the representation of the body in the given `encoding`.
+    ///
+    /// The returned bytes share the cached memory, hence this is cheap once the body has been
+    /// compressed.
+    pub fn encoded(&self, encoding: ContentEncoding) -> Bytes {
+        // safe unwraps: writing to a vector never fails
+        match encoding {
+            ContentEncoding::Identity => self.identity.clone(),
+            ContentEncoding::Gzip => self
+                .gzip
+                .get_or_init(|| {
+                    let mut encoder = GzEncoder::new(Vec::new(), self.level);
+                    encoder.write_all(&self.identity).unwrap();
+                    encoder.finish().unwrap().into()
+                })
+                .clone(),
+            ContentEncoding::Deflate => self
+                .deflate
+                .get_or_init(|| {
+                    let mut encoder = ZlibEncoder::new(Vec::new(), self.level);
+                    encoder.write_all(&self.identity).unwrap();
+                    encoder.finish().unwrap().into()
+                })
+                .clone(),
+        }
+    }
+}
+
+/// A cache of the serialized representations of the entries of an immutable map.
+///
+/// The entries are serialized and cached on demand, since most of them might never be requested.
+#[derive(Debug)]
+pub struct EncodedEntries<K> {
+    bodies: Mutex<HashMap<K, Arc<EncodedBody>>>,
+    level: u32,
+}
+
+impl<K> EncodedEntries<K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Creates a new empty cache with the given compression `level`.
+    pub fn new(level: u32) -> Self {
+        Self {
+            bodies: Mutex::new(HashMap::new()),
+            level,
+        }
+    }
+
+    /// Gets the serialized representation of the entry for the `key`, serializing `value` if the
+    /// entry hasn't been cached yet.
+    pub fn get_or_insert<V: Serialize>(&self, key: &K, value: &V) -> Arc<EncodedBody> {
+        // safe unwrap: the lock is never held across a panic
+        let mut bodies = self.bodies.lock().unwrap();
+        bodies
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(EncodedBody::new(value, self.level)))
+            .clone()
+    }
+}
+
+/// A cache of the encoded representation of the latest payload of a fetcher.
+///
+/// A new payload is broadcasted at most once per phase, so the encoded representation is only
+/// recomputed if the payload differs from the cached one.
+pub(in crate::services) struct EncodingCache<T, E> {
+    latest: Option<(Arc<T>, Arc<E>)>,
+}
+
+impl<T, E> EncodingCache<T, E> {
+    /// Creates a new empty cache.
+    pub fn new() -> Self {
+        Self { latest: None }
+    }
+
+    /// Gets the encoded representation of the `payload`, encoding it with `encode` if it isn't
+    /// the cached payload.
+    pub fn get_or_encode(&mut self, payload: &Arc<T>, encode: impl FnOnce(&T) -> E) -> Arc<E> {
+        match self.latest {
+            Some((ref cached, ref encoded)) if Arc::ptr_eq(cached, payload) => encoded.clone(),
+            _ => {
+                let encoded = Arc::new(encode(payload));
+                self.latest = Some((payload.clone(), encoded.clone()));
+                encoded
+            }
+        }
+    }
+
+    /// Clears the cache.
+    pub fn clear(&mut self) {
+        self.latest = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        use ContentEncoding::*;
+
+        assert_eq!(ContentEncoding::negotiate(None), Identity);
+        assert_eq!(ContentEncoding::negotiate(Some("")), Identity);
+        assert_eq!(ContentEncoding::negotiate(Some("br")), Identity);
+        assert_eq!(ContentEncoding::negotiate(Some("gzip, deflate")), Gzip);
+        assert_eq!(ContentEncoding::negotiate(Some("deflate, GZIP")), Gzip);
+        assert_eq!(ContentEncoding::negotiate(Some("deflate")), Deflate);
+        assert_eq!(
+            ContentEncoding::negotiate(Some("gzip;q=0, deflate")),
+            Deflate
+        );
+        assert_eq!(ContentEncoding::negotiate(Some("gzip; q=0.5")), Gzip);
+        assert_eq!(ContentEncoding::negotiate(Some("*")), Gzip);
+        assert_eq!(ContentEncoding::negotiate(Some("gzip;q=0, *")), Deflate);
+        assert_eq!(ContentEncoding::negotiate(Some("gzip;q=0")), Identity);
+    }
+
+    #[test]
+    fn test_encoded_body() {
+        let value = vec![0xaa_u8; 1024];
+        let body = EncodedBody::new(&value, 6);
+        let identity = body.encoded(ContentEncoding::Identity);
+        assert_eq!(identity, bincode::serialize(&value).unwrap());
+
+        let gzip = body.encoded(ContentEncoding::Gzip);
+        assert!(gzip.len() < identity.len());
+        let mut decoded = Vec::new();
+        GzDecoder::new(gzip.as_ref())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, identity);
+        // the compressed representation is cached
+        assert_eq!(body.encoded(ContentEncoding::Gzip).as_ptr(), gzip.as_ptr());
+
+        let deflate = body.encoded(ContentEncoding::Deflate);
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(deflate.as_ref())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, identity);
+    }
+}
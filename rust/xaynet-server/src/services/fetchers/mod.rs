@@ -3,8 +3,11 @@
 //! There are multiple such services and the [`Fetcher`] trait
 //! provides a single unifying interface for all of these.
 
+mod encoding;
 mod model;
+mod model_delta;
 mod round_parameters;
+mod round_summary;
 mod seed_dict;
 mod sum_dict;
 
@@ -15,8 +18,11 @@ use futures::future::poll_fn;
 use tower::{layer::Layer, Service, ServiceBuilder};
 
 pub use self::{
+    encoding::{ContentEncoding, EncodedBody, EncodedEntries},
     model::{ModelRequest, ModelResponse, ModelService},
+    model_delta::{ModelDeltaRequest, ModelDeltaResponse, ModelDeltaService},
     round_parameters::{RoundParamsRequest, RoundParamsResponse, RoundParamsService},
+    round_summary::{RoundSummaryRequest, RoundSummaryResponse, RoundSummaryService},
     seed_dict::{SeedDictRequest, SeedDictResponse, SeedDictService},
     sum_dict::{SumDictRequest, SumDictResponse, SumDictService},
 };
@@ -28,9 +34,15 @@ pub trait Fetcher {
     /// Fetch the parameters for the current round
     async fn round_params(&mut self) -> Result<RoundParamsResponse, FetchError>;
 
+    /// Fetch a summary of the current round
+    async fn round_summary(&mut self) -> Result<RoundSummaryResponse, FetchError>;
+
     /// Fetch the latest global model.
     async fn model(&mut self) -> Result<ModelResponse, FetchError>;
 
+    /// Fetch the delta from the previous to the latest global model.
+    async fn model_delta(&mut self) -> Result<ModelDeltaResponse, FetchError>;
+
     /// Fetch the global seed dictionary. Each sum2 participant needs a
     /// different portion of that dictionary.
     async fn seed_dict(&mut self) -> Result<SeedDictResponse, FetchError>;
@@ -51,8 +63,8 @@ fn into_fetch_error<E: Into<Box<dyn std::error::Error + 'static + Sync + Send>>>
 }
 
 #[async_trait]
-impl<RoundParams, SumDict, SeedDict, Model> Fetcher
-    for Fetchers<RoundParams, SumDict, SeedDict, Model>
+impl<RoundParams, RoundSummary, SumDict, SeedDict, Model, ModelDelta> Fetcher
+    for Fetchers<RoundParams, RoundSummary, SumDict, SeedDict, Model, ModelDelta>
 where
     Self: Send + Sync + 'static,
 
@@ -61,11 +73,21 @@ where
     <RoundParams as Service<RoundParamsRequest>>::Error:
         Into<Box<dyn std::error::Error + 'static + Sync + Send>>,
 
+    RoundSummary: Service<RoundSummaryRequest, Response = RoundSummaryResponse> + Send + 'static,
+    <RoundSummary as Service<RoundSummaryRequest>>::Future: Send + Sync + 'static,
+    <RoundSummary as Service<RoundSummaryRequest>>::Error:
+        Into<Box<dyn std::error::Error + 'static + Sync + Send>>,
+
     Model: Service<ModelRequest, Response = ModelResponse> + Send + 'static,
     <Model as Service<ModelRequest>>::Future: Send + Sync + 'static,
     <Model as Service<ModelRequest>>::Error:
         Into<Box<dyn std::error::Error + 'static + Sync + Send>>,
 
+    ModelDelta: Service<ModelDeltaRequest, Response = ModelDeltaResponse> + Send + 'static,
+    <ModelDelta as Service<ModelDeltaRequest>>::Future: Send + Sync + 'static,
+    <ModelDelta as Service<ModelDeltaRequest>>::Error:
+        Into<Box<dyn std::error::Error + 'static + Sync + Send>>,
+
     SeedDict: Service<SeedDictRequest, Response = SeedDictResponse> + Send + 'static,
     <SeedDict as Service<SeedDictRequest>>::Future: Send + Sync + 'static,
     <SeedDict as Service<SeedDictRequest>>::Error:
@@ -90,6 +112,20 @@ where
         .map_err(into_fetch_error)?)
     }
 
+    async fn round_summary(&mut self) -> Result<RoundSummaryResponse, FetchError> {
+        poll_fn(|cx| {
+            <RoundSummary as Service<RoundSummaryRequest>>::poll_ready(&mut self.round_summary, cx)
+        })
+        .await
+        .map_err(into_fetch_error)?;
+        Ok(<RoundSummary as Service<RoundSummaryRequest>>::call(
+            &mut self.round_summary,
+            RoundSummaryRequest,
+        )
+        .await
+        .map_err(into_fetch_error)?)
+    }
+
     async fn model(&mut self) -> Result<ModelResponse, FetchError> {
         poll_fn(|cx| <Model as Service<ModelRequest>>::poll_ready(&mut self.model, cx))
             .await
@@ -101,6 +137,20 @@ where
         )
     }
 
+    async fn model_delta(&mut self) -> Result<ModelDeltaResponse, FetchError> {
+        poll_fn(|cx| {
+            <ModelDelta as Service<ModelDeltaRequest>>::poll_ready(&mut self.model_delta, cx)
+        })
+        .await
+        .map_err(into_fetch_error)?;
+        Ok(<ModelDelta as Service<ModelDeltaRequest>>::call(
+            &mut self.model_delta,
+            ModelDeltaRequest,
+        )
+        .await
+        .map_err(into_fetch_error)?)
+    }
+
     async fn seed_dict(&mut self) -> Result<SeedDictResponse, FetchError> {
         poll_fn(|cx| <SeedDict as Service<SeedDictRequest>>::poll_ready(&mut self.seed_dict, cx))
             .await
@@ -154,54 +204,87 @@ impl<S> Layer<S> for FetcherLayer {
 }
 
 #[derive(Debug, Clone)]
-pub struct Fetchers<RoundParams, SumDict, SeedDict, Model> {
+pub struct Fetchers<RoundParams, RoundSummary, SumDict, SeedDict, Model, ModelDelta> {
     round_params: RoundParams,
+    round_summary: RoundSummary,
     sum_dict: SumDict,
     seed_dict: SeedDict,
     model: Model,
+    model_delta: ModelDelta,
 }
 
-impl<RoundParams, SumDict, SeedDict, Model> Fetchers<RoundParams, SumDict, SeedDict, Model> {
+impl<RoundParams, RoundSummary, SumDict, SeedDict, Model, ModelDelta>
+    Fetchers<RoundParams, RoundSummary, SumDict, SeedDict, Model, ModelDelta>
+{
     pub fn new(
         round_params: RoundParams,
+        round_summary: RoundSummary,
         sum_dict: SumDict,
         seed_dict: SeedDict,
         model: Model,
+        model_delta: ModelDelta,
     ) -> Self {
         Self {
             round_params,
+            round_summary,
             sum_dict,
             seed_dict,
             model,
+            model_delta,
         }
     }
 }
 
 /// Construct a [`Fetcher`] service
-pub fn fetcher(event_subscriber: &EventSubscriber) -> impl Fetcher + Sync + Send + Clone + 'static {
+///
+/// The sum dictionary, the seed dictionary, the global model and its delta are compressed with the
+/// given `compression_level`, which ranges from `0` (none) to `9` (best), if requested.
+pub fn fetcher(
+    event_subscriber: &EventSubscriber,
+    compression_level: u32,
+) -> impl Fetcher + Sync + Send + Clone + 'static {
     let round_params = ServiceBuilder::new()
         .buffer(100)
         .concurrency_limit(100)
         .layer(FetcherLayer)
         .service(RoundParamsService::new(event_subscriber));
 
+    let round_summary = ServiceBuilder::new()
+        .buffer(100)
+        .concurrency_limit(100)
+        .layer(FetcherLayer)
+        .service(RoundSummaryService::new(event_subscriber));
+
     let model = ServiceBuilder::new()
         .buffer(100)
         .concurrency_limit(100)
         .layer(FetcherLayer)
-        .service(ModelService::new(event_subscriber));
+        .service(ModelService::new(event_subscriber, compression_level));
+
+    let model_delta = ServiceBuilder::new()
+        .buffer(100)
+        .concurrency_limit(100)
+        .layer(FetcherLayer)
+        .service(ModelDeltaService::new(event_subscriber, compression_level));
 
     let sum_dict = ServiceBuilder::new()
         .buffer(100)
         .concurrency_limit(100)
         .layer(FetcherLayer)
-        .service(SumDictService::new(event_subscriber));
+        .service(SumDictService::new(event_subscriber, compression_level));
 
     let seed_dict = ServiceBuilder::new()
         .buffer(100)
         .concurrency_limit(100)
         .layer(FetcherLayer)
-        .service(SeedDictService::new(event_subscriber));
-
-    Fetchers::new(round_params, sum_dict, seed_dict, model)
+        .service(SeedDictService::new(event_subscriber, compression_level));
+
+    Fetchers::new(
+        round_params,
+        round_summary,
+        sum_dict,
+        seed_dict,
+        model,
+        model_delta,
+    )
 }
@@ -8,8 +8,11 @@ use tower::Service;
 use tracing::error_span;
 use tracing_futures::{Instrument, Instrumented};
 
-use crate::state_machine::events::{EventListener, EventSubscriber, ModelUpdate};
-use xaynet_core::mask::Model;
+use crate::{
+    services::fetchers::encoding::{EncodedBody, EncodingCache},
+    state_machine::events::{EventListener, EventSubscriber, ModelUpdate},
+};
+use xaynet_core::{common::GlobalModelMetadata, mask::Model};
 
 /// [`ModelService`]'s request type
 #[derive(Default, Clone, Eq, PartialEq, Debug)]
@@ -17,15 +20,25 @@ pub struct ModelRequest;
 
 /// [`ModelService`]'s response type.
 ///
-/// The response is `None` when no model is currently available.
-pub type ModelResponse = Option<Arc<Model>>;
+/// The response is `None` when no model is currently available. Otherwise, it contains the model,
+/// its metadata, if known, and its encoded representation, which is shared by all requests until
+/// the next model is available.
+pub type ModelResponse = Option<(Arc<Model>, Option<GlobalModelMetadata>, Arc<EncodedBody>)>;
 
 /// A service that serves the latest available global model
-pub struct ModelService(EventListener<ModelUpdate>);
+pub struct ModelService {
+    listener: EventListener<ModelUpdate>,
+    compression_level: u32,
+    cache: EncodingCache<Model, EncodedBody>,
+}
 
 impl ModelService {
-    pub fn new(events: &EventSubscriber) -> Self {
-        Self(events.model_listener())
+    pub fn new(events: &EventSubscriber, compression_level: u32) -> Self {
+        Self {
+            listener: events.model_listener(),
+            compression_level,
+            cache: EncodingCache::new(),
+        }
     }
 }
 
@@ -39,9 +52,18 @@ impl Service<ModelRequest> for ModelService {
     }
 
     fn call(&mut self, _req: ModelRequest) -> Self::Future {
-        future::ready(match self.0.get_latest().event {
-            ModelUpdate::Invalidate => Ok(None),
-            ModelUpdate::New(model) => Ok(Some(model)),
+        let compression_level = self.compression_level;
+        future::ready(match self.listener.get_latest().event {
+            ModelUpdate::Invalidate => {
+                self.cache.clear();
+                Ok(None)
+            }
+            ModelUpdate::New(model, metadata) => {
+                let body = self
+                    .cache
+                    .get_or_encode(&model, |model| EncodedBody::new(model, compression_level));
+                Ok(Some((model, metadata, body)))
+            }
         })
         .instrument(error_span!("model_fetch_request"))
     }
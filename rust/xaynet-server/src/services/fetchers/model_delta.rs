@@ -0,0 +1,70 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::future::{self, Ready};
+use tower::Service;
+use tracing::error_span;
+use tracing_futures::{Instrument, Instrumented};
+
+use crate::{
+    services::fetchers::encoding::{EncodedBody, EncodingCache},
+    state_machine::events::{EventListener, EventSubscriber},
+};
+use xaynet_core::mask::ModelDelta;
+
+/// A service that serves the delta from the previous to the latest global model.
+pub struct ModelDeltaService {
+    listener: EventListener<Option<Arc<ModelDelta>>>,
+    compression_level: u32,
+    cache: EncodingCache<ModelDelta, EncodedBody>,
+}
+
+/// [`ModelDeltaService`]'s request type
+#[derive(Default, Clone, Eq, PartialEq, Debug)]
+pub struct ModelDeltaRequest;
+
+/// [`ModelDeltaService`]'s response type.
+///
+/// The response is `None` when no delta to the latest global model is currently available.
+/// Otherwise, it contains the delta and its encoded representation, which is shared by all
+/// requests until the next delta is available.
+pub type ModelDeltaResponse = Option<(Arc<ModelDelta>, Arc<EncodedBody>)>;
+
+impl ModelDeltaService {
+    pub fn new(events: &EventSubscriber, compression_level: u32) -> Self {
+        Self {
+            listener: events.model_delta_listener(),
+            compression_level,
+            cache: EncodingCache::new(),
+        }
+    }
+}
+
+impl Service<ModelDeltaRequest> for ModelDeltaService {
+    type Response = ModelDeltaResponse;
+    type Error = std::convert::Infallible;
+    type Future = Instrumented<Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: ModelDeltaRequest) -> Self::Future {
+        let compression_level = self.compression_level;
+        future::ready(match self.listener.get_latest().event {
+            None => {
+                self.cache.clear();
+                Ok(None)
+            }
+            Some(delta) => {
+                let body = self
+                    .cache
+                    .get_or_encode(&delta, |delta| EncodedBody::new(delta, compression_level));
+                Ok(Some((delta, body)))
+            }
+        })
+        .instrument(error_span!("model_delta_fetch_request"))
+    }
+}
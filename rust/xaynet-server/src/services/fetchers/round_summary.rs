@@ -0,0 +1,60 @@
+use std::task::{Context, Poll};
+
+use futures::future::{self, Ready};
+use tower::Service;
+use tracing::error_span;
+use tracing_futures::{Instrument, Instrumented};
+
+use crate::state_machine::{
+    events::{EventListener, EventSubscriber},
+    phases::PhaseName,
+};
+use xaynet_core::common::{RoundParameters, RoundSummary};
+
+/// [`RoundSummaryService`]'s request type
+#[derive(Default, Clone, Eq, PartialEq, Debug)]
+pub struct RoundSummaryRequest;
+
+/// [`RoundSummaryService`]'s response type
+pub type RoundSummaryResponse = RoundSummary;
+
+/// A service that serves a summary of the current round.
+pub struct RoundSummaryService {
+    params: EventListener<RoundParameters>,
+    phase: EventListener<PhaseName>,
+}
+
+impl RoundSummaryService {
+    pub fn new(events: &EventSubscriber) -> Self {
+        Self {
+            params: events.params_listener(),
+            phase: events.phase_listener(),
+        }
+    }
+}
+
+impl Service<RoundSummaryRequest> for RoundSummaryService {
+    type Response = RoundSummary;
+    type Error = std::convert::Infallible;
+    type Future = Instrumented<Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: RoundSummaryRequest) -> Self::Future {
+        let params = self.params.get_latest();
+        let phase = self.phase.get_latest().event;
+        let summary = RoundSummary {
+            round_id: params.round_id,
+            round_token: params.event.seed.token(),
+            seed: params.event.seed,
+            phase: phase.to_string(),
+            sum: params.event.sum,
+            update: params.event.update,
+            eligibility_salt: params.event.eligibility_salt,
+            crypto_suite: params.event.crypto_suite,
+        };
+        future::ready(Ok(summary)).instrument(error_span!("round_summary_fetch_request"))
+    }
+}
@@ -8,15 +8,26 @@ use tower::Service;
 use tracing::error_span;
 use tracing_futures::{Instrument, Instrumented};
 
-use crate::state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber};
-use xaynet_core::SeedDict;
+use crate::{
+    services::fetchers::encoding::{EncodedEntries, EncodingCache},
+    state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber, VersionedSeedDict},
+};
+use xaynet_core::SumParticipantPublicKey;
 
 /// A service that serves the seed dictionary for the current round.
-pub struct SeedDictService(EventListener<DictionaryUpdate<SeedDict>>);
+pub struct SeedDictService {
+    listener: EventListener<DictionaryUpdate<VersionedSeedDict>>,
+    compression_level: u32,
+    cache: EncodingCache<VersionedSeedDict, EncodedEntries<SumParticipantPublicKey>>,
+}
 
 impl SeedDictService {
-    pub fn new(events: &EventSubscriber) -> Self {
-        Self(events.seed_dict_listener())
+    pub fn new(events: &EventSubscriber, compression_level: u32) -> Self {
+        Self {
+            listener: events.seed_dict_listener(),
+            compression_level,
+            cache: EncodingCache::new(),
+        }
     }
 }
 
@@ -27,8 +38,13 @@ pub struct SeedDictRequest;
 /// [`SeedDictService`]'s response type.
 ///
 /// The response is `None` when no seed dictionary is currently
-/// available
-pub type SeedDictResponse = Option<Arc<SeedDict>>;
+/// available. Otherwise, it contains the versioned dictionary and the encoded
+/// representations of its entries, which are shared by all requests
+/// of the phase.
+pub type SeedDictResponse = Option<(
+    Arc<VersionedSeedDict>,
+    Arc<EncodedEntries<SumParticipantPublicKey>>,
+)>;
 
 impl Service<SeedDictRequest> for SeedDictService {
     type Response = SeedDictResponse;
@@ -40,9 +56,18 @@ impl Service<SeedDictRequest> for SeedDictService {
     }
 
     fn call(&mut self, _req: SeedDictRequest) -> Self::Future {
-        future::ready(match self.0.get_latest().event {
-            DictionaryUpdate::Invalidate => Ok(None),
-            DictionaryUpdate::New(dict) => Ok(Some(dict)),
+        let compression_level = self.compression_level;
+        future::ready(match self.listener.get_latest().event {
+            DictionaryUpdate::Invalidate => {
+                self.cache.clear();
+                Ok(None)
+            }
+            DictionaryUpdate::New(dict) => {
+                let entries = self
+                    .cache
+                    .get_or_encode(&dict, |_| EncodedEntries::new(compression_level));
+                Ok(Some((dict, entries)))
+            }
         })
         .instrument(error_span!("seed_dict_fetch_request"))
     }
@@ -8,11 +8,22 @@ use tower::Service;
 use tracing::error_span;
 use tracing_futures::{Instrument, Instrumented};
 
-use crate::state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber};
-use xaynet_core::SumDict;
+use crate::{
+    services::fetchers::encoding::{EncodedBody, EncodingCache},
+    state_machine::events::{DictionaryUpdate, Event, EventListener, EventSubscriber},
+};
+use xaynet_core::{
+    common::{RoundParameters, RoundSeed},
+    SumDict,
+};
 
 /// A service that returns the sum dictionary for the current round.
-pub struct SumDictService(EventListener<DictionaryUpdate<SumDict>>);
+pub struct SumDictService {
+    listener: EventListener<DictionaryUpdate<SumDict>>,
+    params_listener: EventListener<RoundParameters>,
+    compression_level: u32,
+    cache: EncodingCache<SumDict, EncodedBody>,
+}
 
 /// [`SumDictService`]'s request type
 #[derive(Default, Clone, Eq, PartialEq, Debug)]
@@ -21,12 +32,19 @@ pub struct SumDictRequest;
 /// [`SumDictService`]'s response type.
 ///
 /// The response is `None` when no sum dictionary is currently
-/// available
-pub type SumDictResponse = Option<Arc<SumDict>>;
+/// available. Otherwise, it contains the dictionary, its encoded
+/// representation, which is shared by all requests of the phase, and
+/// the seed of the round the dictionary belongs to.
+pub type SumDictResponse = Option<(Arc<SumDict>, Arc<EncodedBody>, RoundSeed)>;
 
 impl SumDictService {
-    pub fn new(events: &EventSubscriber) -> Self {
-        Self(events.sum_dict_listener())
+    pub fn new(events: &EventSubscriber, compression_level: u32) -> Self {
+        Self {
+            listener: events.sum_dict_listener(),
+            params_listener: events.params_listener(),
+            compression_level,
+            cache: EncodingCache::new(),
+        }
     }
 }
 
@@ -40,9 +58,22 @@ impl Service<SumDictRequest> for SumDictService {
     }
 
     fn call(&mut self, _req: SumDictRequest) -> Self::Future {
-        future::ready(match self.0.get_latest().event {
-            DictionaryUpdate::Invalidate => Ok(None),
-            DictionaryUpdate::New(dict) => Ok(Some(dict)),
+        let compression_level = self.compression_level;
+        let Event { round_id, event } = self.listener.get_latest();
+        let params = self.params_listener.get_latest();
+        future::ready(match event {
+            DictionaryUpdate::Invalidate => {
+                self.cache.clear();
+                Ok(None)
+            }
+            // a dictionary is only served together with the seed of its own round
+            DictionaryUpdate::New(_) if params.round_id != round_id => Ok(None),
+            DictionaryUpdate::New(dict) => {
+                let body = self
+                    .cache
+                    .get_or_encode(&dict, |dict| EncodedBody::new(dict, compression_level));
+                Ok(Some((dict, body, params.event.seed)))
+            }
         })
         .instrument(error_span!("sum_dict_fetch_request"))
     }
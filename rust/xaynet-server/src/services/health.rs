@@ -0,0 +1,283 @@
+//! This module provides the health checks behind the liveness and readiness probes.
+//!
+//! The probes are meant for orchestrators like Kubernetes and must be cheap, hence the health of
+//! the storage is checked by a [`StorageHealthMonitor`] in the background and the probes only read
+//! the cached result.
+
+use std::time::Duration;
+
+use tokio::{
+    sync::watch,
+    time::{sleep, timeout},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    services::messages::PetMessageHandler,
+    state_machine::{
+        events::{EventListener, EventSubscriber},
+        pause::Pause,
+        phases::PhaseName,
+    },
+    storage::Storage,
+};
+
+/// The interval in which the [`StorageHealthMonitor`] checks the storage.
+pub const STORAGE_HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The time after which an unanswered liveness check fails.
+pub const LIVENESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A handle to the cached health of the storage, see [`StorageHealthMonitor`].
+#[derive(Clone, Debug)]
+pub struct StorageHealth(watch::Receiver<bool>);
+
+impl StorageHealth {
+    /// Whether the storage was ready at the last check.
+    ///
+    /// The storage is considered not ready until it has been checked once.
+    pub fn is_ready(&self) -> bool {
+        *self.0.borrow()
+    }
+}
+
+/// Checks the health of the storage regularly.
+pub struct StorageHealthMonitor<T> {
+    store: T,
+    interval: Duration,
+    health_tx: watch::Sender<bool>,
+}
+
+impl<T> StorageHealthMonitor<T>
+where
+    T: Storage,
+{
+    /// Creates a monitor which checks the `store` every `interval` and the handle to the cached
+    /// result.
+    pub fn new(store: T, interval: Duration) -> (Self, StorageHealth) {
+        let (health_tx, health_rx) = watch::channel(false);
+        let monitor = Self {
+            store,
+            interval,
+            health_tx,
+        };
+        (monitor, StorageHealth(health_rx))
+    }
+
+    /// Checks the storage until all handles to the cached result are dropped.
+    pub async fn run(mut self) {
+        while !self.health_tx.is_closed() {
+            self.check().await;
+            sleep(self.interval).await;
+        }
+    }
+
+    /// Checks the storage once and caches the result.
+    async fn check(&mut self) {
+        let ready = match <T as Storage>::is_ready(&mut self.store).await {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("storage not ready: {}", err);
+                false
+            }
+        };
+        if *self.health_tx.borrow() != ready {
+            debug!("storage readiness changed to {}", ready);
+        }
+        let _ = self.health_tx.send(ready);
+    }
+}
+
+/// The reasons why the coordinator is not ready.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotReady {
+    /// The storage was not reachable at the last check.
+    Storage,
+    /// The state machine shut down.
+    Shutdown,
+    /// The acceptance of messages is paused.
+    Paused,
+}
+
+/// Performs the health checks of the coordinator.
+#[derive(Clone)]
+pub struct HealthChecker {
+    message_handler: PetMessageHandler,
+    storage: StorageHealth,
+    phase: EventListener<PhaseName>,
+    pause: Pause,
+}
+
+impl HealthChecker {
+    /// Creates a health checker for the initialized state machine which broadcasts the `events`.
+    pub fn new(
+        message_handler: PetMessageHandler,
+        storage: StorageHealth,
+        events: &EventSubscriber,
+    ) -> Self {
+        Self {
+            message_handler,
+            storage,
+            phase: events.phase_listener(),
+            pause: Pause::default(),
+        }
+    }
+
+    /// Sets the switch to pause the acceptance of messages, which is reported by the readiness
+    /// check.
+    pub fn with_pause(mut self, pause: Pause) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Checks whether the coordinator is alive.
+    ///
+    /// A no-op task is round-tripped through the message processing within the
+    /// [`LIVENESS_TIMEOUT`], which fails if the processing is stalled. The state of the round
+    /// doesn't matter, e.g. the coordinator is alive in between rounds and during a storage
+    /// outage.
+    pub async fn check_liveness(&self) -> bool {
+        self.check_liveness_within(LIVENESS_TIMEOUT).await
+    }
+
+    async fn check_liveness_within(&self, limit: Duration) -> bool {
+        match timeout(limit, self.message_handler.ping()).await {
+            Ok(Ok(())) => true,
+            Ok(Err(err)) => {
+                warn!("liveness check failed: {}", err);
+                false
+            }
+            Err(_) => {
+                warn!("liveness check timed out after {:?}", limit);
+                false
+            }
+        }
+    }
+
+    /// Checks whether the coordinator is ready to serve the participants.
+    ///
+    /// The health checker only exists once the state machine is initialized. The health of the
+    /// storage is cached, hence this is cheap. A paused coordinator keeps its round, but it isn't
+    /// ready to accept messages.
+    pub fn check_readiness(&self) -> Result<(), NotReady> {
+        if self.phase.get_latest().event == PhaseName::Shutdown {
+            Err(NotReady::Shutdown)
+        } else if !self.storage.is_ready() {
+            Err(NotReady::Storage)
+        } else if self.pause.is_paused() {
+            Err(NotReady::Paused)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{mpsc, Arc, Mutex};
+
+    use anyhow::anyhow;
+
+    use crate::{
+        services::tests::utils::new_event_channels,
+        state_machine::requests::RequestReceiver,
+        storage::{
+            tests::{MockCoordinatorStore, MockModelStore},
+            Store,
+        },
+    };
+
+    fn health_checker(storage: StorageHealth) -> HealthChecker {
+        let (_publisher, subscriber) = new_event_channels();
+        let (_request_rx, request_tx) = RequestReceiver::new();
+        let handler = PetMessageHandler::new(&subscriber, request_tx);
+        HealthChecker::new(handler, storage, &subscriber)
+    }
+
+    #[tokio::test]
+    async fn test_stalled_message_processing() {
+        let (_health_tx, health_rx) = watch::channel(true);
+        let checker = health_checker(StorageHealth(health_rx));
+        assert!(checker.check_liveness_within(Duration::from_secs(1)).await);
+
+        // block every thread of the message processing
+        let thread_pool = checker.message_handler.thread_pool();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let release_rx = Arc::new(Mutex::new(release_rx));
+        let blocked = (0..thread_pool.current_num_threads())
+            .map(|_| {
+                let release_rx = release_rx.clone();
+                thread_pool.spawn(move || {
+                    let _ = release_rx.lock().unwrap().recv();
+                })
+            })
+            .collect::<Vec<_>>();
+        assert!(!checker.check_liveness_within(Duration::from_millis(100)).await);
+        assert!(checker.check_readiness().is_ok());
+
+        drop(release_tx);
+        for task in blocked {
+            task.await.unwrap();
+        }
+        assert!(checker.check_liveness_within(Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test]
+    async fn test_storage_outage() {
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_is_ready()
+            .returning(|| Err(anyhow!("connection refused")));
+        let mut ms = MockModelStore::new();
+        ms.expect_is_ready().returning(|| Ok(()));
+        let (mut monitor, storage) =
+            StorageHealthMonitor::new(Store::new(cs, ms), STORAGE_HEALTH_INTERVAL);
+        let checker = health_checker(storage.clone());
+
+        // the storage is not ready until it has been checked
+        assert_eq!(checker.check_readiness(), Err(NotReady::Storage));
+        monitor.check().await;
+        assert!(!storage.is_ready());
+        assert_eq!(checker.check_readiness(), Err(NotReady::Storage));
+        assert!(checker.check_liveness().await);
+    }
+
+    #[tokio::test]
+    async fn test_paused() {
+        let (_health_tx, health_rx) = watch::channel(true);
+        let pause = Pause::default();
+        let checker = health_checker(StorageHealth(health_rx)).with_pause(pause.clone());
+        assert!(checker.check_readiness().is_ok());
+
+        pause.pause();
+        assert_eq!(checker.check_readiness(), Err(NotReady::Paused));
+        assert!(checker.check_liveness().await);
+
+        pause.resume();
+        assert!(checker.check_readiness().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_storage_recovery() {
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_is_ready().returning(|| Ok(()));
+        let mut ms = MockModelStore::new();
+        ms.expect_is_ready().returning(|| Ok(()));
+        let (monitor, storage) =
+            StorageHealthMonitor::new(Store::new(cs, ms), Duration::from_millis(10));
+        let checker = health_checker(storage);
+        let monitor = tokio::spawn(monitor.run());
+
+        let mut health_rx = checker.storage.0.clone();
+        while !*health_rx.borrow() {
+            health_rx.changed().await.unwrap();
+        }
+        assert!(checker.check_readiness().is_ok());
+
+        // the monitor stops once nobody is interested in the health of the storage anymore
+        drop(health_rx);
+        drop(checker);
+        monitor.await.unwrap();
+    }
+}
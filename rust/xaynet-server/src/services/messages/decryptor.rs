@@ -1,8 +1,6 @@
-use std::{pin::Pin, sync::Arc, task::Poll};
+use std::{pin::Pin, task::Poll};
 
 use futures::{future::Future, task::Context};
-use rayon::ThreadPool;
-use tokio::sync::oneshot;
 use tower::{
     limit::concurrency::{future::ResponseFuture, ConcurrencyLimit},
     Service,
@@ -10,7 +8,7 @@ use tower::{
 use tracing::{debug, info, trace};
 
 use crate::{
-    services::messages::{BoxedServiceFuture, ServiceError},
+    services::messages::{BoxedServiceFuture, MessageThreadPool, ServiceError},
     state_machine::events::{EventListener, EventSubscriber},
 };
 use xaynet_core::crypto::EncryptKeyPair;
@@ -28,7 +26,7 @@ struct RawDecryptor {
     keys_events: EventListener<EncryptKeyPair>,
 
     /// Thread-pool the CPU-intensive tasks are offloaded to.
-    thread_pool: Arc<ThreadPool>,
+    thread_pool: MessageThreadPool,
 }
 
 impl<T> Service<T> for RawDecryptor
@@ -48,32 +46,27 @@ where
     fn call(&mut self, data: T) -> Self::Future {
         debug!("retrieving the current keys");
         let keys = self.keys_events.get_latest().event;
-        let (tx, rx) = oneshot::channel::<Result<Self::Response, Self::Error>>();
-
-        trace!("spawning decryption task on threadpool");
-        self.thread_pool.spawn(move || {
-            info!("decrypting message");
-            let res = keys
-                .secret
-                .decrypt(data.as_ref(), &keys.public)
-                .map_err(|_| ServiceError::Decrypt);
-            let _ = tx.send(res);
-        });
-        Box::pin(async move {
-            rx.await.unwrap_or_else(|_| {
-                Err(ServiceError::InternalError(
-                    "failed to receive response from thread-pool".to_string(),
-                ))
-            })
-        })
+        trace!("spawning decryption task on thread-pool");
+        let decrypted = self
+            .thread_pool
+            .spawn(move || decrypt(&keys, data.as_ref()));
+        Box::pin(async move { decrypted.await? })
     }
 }
 
+/// Decrypts a message with the given coordinator keys.
+pub(super) fn decrypt(keys: &EncryptKeyPair, data: &[u8]) -> Result<Vec<u8>, ServiceError> {
+    info!("decrypting message");
+    keys.secret
+        .decrypt(data, &keys.public)
+        .map_err(|_| ServiceError::Decrypt)
+}
+
 #[derive(Clone)]
 pub struct Decryptor(ConcurrencyLimit<RawDecryptor>);
 
 impl Decryptor {
-    pub fn new(state_machine_events: &EventSubscriber, thread_pool: Arc<ThreadPool>) -> Self {
+    pub fn new(state_machine_events: &EventSubscriber, thread_pool: MessageThreadPool) -> Self {
         let limit = thread_pool.current_num_threads();
         let keys_events = state_machine_events.keys_listener();
         let service = RawDecryptor {
@@ -103,7 +96,6 @@ where
 
 #[cfg(test)]
 mod tests {
-    use rayon::ThreadPoolBuilder;
     use tokio_test::assert_ready;
     use tower_test::mock::Spawn;
 
@@ -116,7 +108,7 @@ mod tests {
 
     fn spawn_svc() -> (EventPublisher, EventSubscriber, Spawn<Decryptor>) {
         let (publisher, subscriber) = utils::new_event_channels();
-        let thread_pool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
+        let thread_pool = MessageThreadPool::new(None).unwrap();
         let task = Spawn::new(Decryptor::new(&subscriber, thread_pool));
         (publisher, subscriber, task)
     }
@@ -2,11 +2,13 @@ use displaydoc::Display;
 use thiserror::Error;
 
 use crate::state_machine::requests::RequestError;
-use xaynet_core::message::DecodeError;
+use xaynet_core::{mask::AggregationError, message::DecodeError};
 
 /// Errors for the message parsing service.
 #[derive(Debug, Display, Error)]
 pub enum ServiceError {
+    /// The message of {0} bytes exceeds the size limit of {1} bytes.
+    MessageTooLarge(usize, usize),
     /// Failed to decrypt the message with the coordinator secret key.
     Decrypt,
     /// Failed to parse the message: {0}.
@@ -25,6 +27,12 @@ pub enum ServiceError {
     NotSumEligible,
     /// Participant is not eligible for update task.
     NotUpdateEligible,
+    /// The masks of the message are not bound to the round as negotiated.
+    MaskBindingMismatch,
+    /// The masks of the message don't match the round parameters: {0}.
+    InvalidMasks(AggregationError),
+    /// Multipart messages can't be validated.
+    UnsupportedMultipart,
     /// Internal error: {0}.
     InternalError(String),
 }
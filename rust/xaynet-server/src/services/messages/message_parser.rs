@@ -1,13 +1,11 @@
 use std::{convert::TryInto, sync::Arc, task::Poll};
 
 use futures::{future, task::Context};
-use rayon::ThreadPool;
-use tokio::sync::oneshot;
 use tower::{layer::Layer, limit::concurrency::ConcurrencyLimit, Service, ServiceBuilder};
 use tracing::{debug, info, trace, warn};
 
 use crate::{
-    services::messages::{BoxedServiceFuture, ServiceError},
+    services::messages::{BoxedServiceFuture, MessageThreadPool, ServiceError},
     state_machine::{
         events::{EventListener, EventSubscriber},
         phases::PhaseName,
@@ -110,21 +108,31 @@ where
     fn call(&mut self, req: RawMessage<T>) -> Self::Future {
         debug!("retrieving the current phase");
         let phase = self.phase.get_latest().event;
-        match req.buffer.tag().try_into() {
-            Ok(tag) => match (phase, tag) {
-                (PhaseName::Sum, Tag::Sum)
-                | (PhaseName::Update, Tag::Update)
-                | (PhaseName::Sum2, Tag::Sum2) => {
-                    let fut = self.next_svc.call(req);
-                    Box::pin(async move { fut.await })
-                }
-                _ => Box::pin(future::ready(Err(ServiceError::UnexpectedMessage))),
-            },
-            Err(e) => Box::pin(future::ready(Err(ServiceError::Parsing(e)))),
+        match decode_tag(&req.buffer).and_then(|tag| check_phase(phase, tag)) {
+            Ok(()) => {
+                let fut = self.next_svc.call(req);
+                Box::pin(async move { fut.await })
+            }
+            Err(e) => Box::pin(future::ready(Err(e))),
         }
     }
 }
 
+/// Decodes the tag of a message.
+pub(super) fn decode_tag<T: AsRef<[u8]>>(buffer: &MessageBuffer<T>) -> Result<Tag, ServiceError> {
+    buffer.tag().try_into().map_err(ServiceError::Parsing)
+}
+
+/// Checks whether a message with the given tag is expected in the current phase.
+pub(super) fn check_phase(phase: PhaseName, tag: Tag) -> Result<(), ServiceError> {
+    match (phase, tag) {
+        (PhaseName::Sum, Tag::Sum)
+        | (PhaseName::Update, Tag::Update)
+        | (PhaseName::Sum2, Tag::Sum2) => Ok(()),
+        _ => Err(ServiceError::UnexpectedMessage),
+    }
+}
+
 struct PhaseFilterLayer {
     phase: EventListener<PhaseName>,
 }
@@ -148,7 +156,7 @@ impl<S> Layer<S> for PhaseFilterLayer {
 #[derive(Debug, Clone)]
 struct SignatureVerifier<S> {
     /// Thread-pool the CPU-intensive tasks are offloaded to.
-    thread_pool: Arc<ThreadPool>,
+    thread_pool: MessageThreadPool,
     /// The service to be called after the [`SignatureVerifier`]
     next_svc: S,
 }
@@ -172,39 +180,37 @@ where
     }
 
     fn call(&mut self, req: RawMessage<T>) -> Self::Future {
-        let (tx, rx) = oneshot::channel::<Result<(), ServiceError>>();
-
         let req_clone = req.clone();
         trace!("spawning signature verification task on thread-pool");
-        self.thread_pool.spawn(move || {
-            let res = match req.buffer.as_ref().as_ref().check_signature() {
-                Ok(()) => {
-                    info!("found a valid message signature");
-                    Ok(())
-                }
-                Err(e) => {
-                    warn!("invalid message signature: {:?}", e);
-                    Err(ServiceError::InvalidMessageSignature)
-                }
-            };
-            let _ = tx.send(res);
-        });
+        let verified = self.thread_pool.spawn(move || check_signature(&req.buffer));
 
         let mut next_svc = self.next_svc.clone();
         let fut = async move {
-            rx.await.map_err(|_| {
-                ServiceError::InternalError(
-                    "failed to receive response from thread-pool".to_string(),
-                )
-            })??;
+            verified.await??;
             next_svc.call(req_clone).await
         };
         Box::pin(fut)
     }
 }
 
+/// Verifies the signature of a message.
+pub(super) fn check_signature<T: AsRef<[u8]>>(
+    buffer: &MessageBuffer<T>,
+) -> Result<(), ServiceError> {
+    match buffer.as_ref().check_signature() {
+        Ok(()) => {
+            info!("found a valid message signature");
+            Ok(())
+        }
+        Err(e) => {
+            warn!("invalid message signature: {:?}", e);
+            Err(ServiceError::InvalidMessageSignature)
+        }
+    }
+}
+
 struct SignatureVerifierLayer {
-    thread_pool: Arc<ThreadPool>,
+    thread_pool: MessageThreadPool,
 }
 
 impl<S> Layer<S> for SignatureVerifierLayer {
@@ -252,26 +258,34 @@ where
     fn call(&mut self, req: RawMessage<T>) -> Self::Future {
         debug!("retrieving the current keys");
         let coord_pk = self.keys.get_latest().event.public;
-        match PublicEncryptKey::from_byte_slice(&req.buffer.as_ref().as_ref().coordinator_pk()) {
-            Ok(pk) => {
-                if pk != coord_pk {
-                    warn!("found an invalid coordinator public key");
-                    Box::pin(future::ready(Err(
-                        ServiceError::InvalidCoordinatorPublicKey,
-                    )))
-                } else {
-                    info!("found a valid coordinator public key");
-                    let fut = self.next_svc.call(req);
-                    Box::pin(async move { fut.await })
-                }
+        match check_coordinator_pk(&coord_pk, &req.buffer) {
+            Ok(()) => {
+                let fut = self.next_svc.call(req);
+                Box::pin(async move { fut.await })
             }
-            Err(_) => Box::pin(future::ready(Err(
-                ServiceError::InvalidCoordinatorPublicKey,
-            ))),
+            Err(e) => Box::pin(future::ready(Err(e))),
         }
     }
 }
 
+/// Checks whether a message is addressed to the coordinator with the given public key.
+pub(super) fn check_coordinator_pk<T: AsRef<[u8]>>(
+    coord_pk: &PublicEncryptKey,
+    buffer: &MessageBuffer<T>,
+) -> Result<(), ServiceError> {
+    match PublicEncryptKey::from_byte_slice(&buffer.as_ref().coordinator_pk()) {
+        Ok(pk) if &pk == coord_pk => {
+            info!("found a valid coordinator public key");
+            Ok(())
+        }
+        Ok(_) => {
+            warn!("found an invalid coordinator public key");
+            Err(ServiceError::InvalidCoordinatorPublicKey)
+        }
+        Err(_) => Err(ServiceError::InvalidCoordinatorPublicKey),
+    }
+}
+
 struct CoordinatorPublicKeyValidatorLayer {
     keys: EventListener<EncryptKeyPair>,
 }
@@ -287,27 +301,41 @@ impl<S> Layer<S> for CoordinatorPublicKeyValidatorLayer {
     }
 }
 
+/// A service for decoding the payload of PET messages.
+///
+/// Since this is a CPU-intensive task for large messages, this
+/// service offloads the processing to a `rayon` thread-pool to avoid
+/// overloading the tokio thread-pool with blocking tasks.
 #[derive(Debug, Clone)]
-struct Parser;
+struct Parser {
+    /// Thread-pool the CPU-intensive tasks are offloaded to.
+    thread_pool: MessageThreadPool,
+}
 
 impl<T> Service<RawMessage<T>> for Parser
 where
-    T: AsRef<[u8]> + Send + 'static,
+    T: AsRef<[u8]> + Sync + Send + 'static,
 {
     type Response = Message;
     type Error = ServiceError;
-    type Future = future::Ready<Result<Self::Response, Self::Error>>;
+    type Future = BoxedServiceFuture<Self::Response, Self::Error>;
 
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, req: RawMessage<T>) -> Self::Future {
-        let bytes = req.buffer.inner();
-        future::ready(Message::from_byte_slice(&bytes).map_err(ServiceError::Parsing))
+        trace!("spawning parsing task on thread-pool");
+        let parsed = self.thread_pool.spawn(move || parse(&req.buffer));
+        Box::pin(async move { parsed.await? })
     }
 }
 
+/// Parses a message.
+pub(super) fn parse<T: AsRef<[u8]>>(buffer: &MessageBuffer<T>) -> Result<Message, ServiceError> {
+    Message::from_byte_slice(&buffer.inner()).map_err(ServiceError::Parsing)
+}
+
 type InnerService = BufferWrapper<
     PhaseFilter<ConcurrencyLimit<SignatureVerifier<CoordinatorPublicKeyValidator<Parser>>>>,
 >;
@@ -334,24 +362,25 @@ where
 }
 
 impl MessageParser {
-    pub fn new(events: &EventSubscriber, thread_pool: Arc<ThreadPool>) -> Self {
+    pub fn new(events: &EventSubscriber, thread_pool: MessageThreadPool) -> Self {
         let inner = ServiceBuilder::new()
             .layer(BufferWrapperLayer)
             .layer(PhaseFilterLayer {
                 phase: events.phase_listener(),
             })
-            .layer(SignatureVerifierLayer { thread_pool })
+            .layer(SignatureVerifierLayer {
+                thread_pool: thread_pool.clone(),
+            })
             .layer(CoordinatorPublicKeyValidatorLayer {
                 keys: events.keys_listener(),
             })
-            .service(Parser);
+            .service(Parser { thread_pool });
         Self(inner)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use rayon::ThreadPoolBuilder;
     use tokio_test::assert_ready;
     use tower_test::mock::Spawn;
 
@@ -363,7 +392,7 @@ mod tests {
 
     fn spawn_svc() -> (EventPublisher, EventSubscriber, Spawn<MessageParser>) {
         let (publisher, subscriber) = utils::new_event_channels();
-        let thread_pool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
+        let thread_pool = MessageThreadPool::new(None).unwrap();
         let task = Spawn::new(MessageParser::new(&subscriber, thread_pool));
         (publisher, subscriber, task)
     }
@@ -7,38 +7,63 @@ mod decryptor;
 mod error;
 mod message_parser;
 mod multipart;
+mod sequencer;
+mod signature_batcher;
 mod state_machine;
 mod task_validator;
+mod thread_pool;
+mod validator;
 
-use std::sync::Arc;
+use std::time::Duration;
 
 use futures::future::poll_fn;
-use rayon::ThreadPoolBuilder;
 use tower::Service;
 use xaynet_core::message::Message;
 
-pub use self::error::ServiceError;
 use self::{
     decryptor::Decryptor,
     message_parser::MessageParser,
     multipart::MultipartHandler,
+    sequencer::{Sequencer, Ticket},
     state_machine::StateMachine,
     task_validator::TaskValidator,
+    validator::MessageValidator,
+};
+pub use self::{
+    error::ServiceError,
+    signature_batcher::{verify_signatures, SignatureBatcher, SignatureCheck},
+    thread_pool::MessageThreadPool,
+    validator::{Check, CheckOutcome, CheckStatus, Verdict},
+};
+use crate::state_machine::{
+    events::EventSubscriber,
+    requests::{RequestSender, StateMachineRequest},
 };
-use crate::state_machine::{events::EventSubscriber, requests::RequestSender};
 
 impl PetMessageHandler {
     pub fn new(event_subscriber: &EventSubscriber, requests_tx: RequestSender) -> Self {
-        // TODO: make this configurable. Users should be able to
-        // choose how many threads they want etc.
-        //
         // TODO: don't unwrap
-        let thread_pool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
+        let thread_pool = MessageThreadPool::new(None).unwrap();
+        Self::with_thread_pool(event_subscriber, requests_tx, thread_pool)
+    }
+
+    /// Creates a message handler which offloads the decryption, the signature verifications and
+    /// the decoding of the messages to the given thread pool.
+    ///
+    /// Only these CPU-intensive tasks run on the thread pool. The messages are still handed to the
+    /// state machine one by one and in the order in which they arrived, hence the duplicate checks
+    /// of the messages of a participant can't race each other.
+    pub fn with_thread_pool(
+        event_subscriber: &EventSubscriber,
+        requests_tx: RequestSender,
+        thread_pool: MessageThreadPool,
+    ) -> Self {
         let decryptor = Decryptor::new(event_subscriber, thread_pool.clone());
         let multipart_handler = MultipartHandler::new();
-        let message_parser = MessageParser::new(event_subscriber, thread_pool);
-        let task_validator = TaskValidator::new(event_subscriber);
+        let message_parser = MessageParser::new(event_subscriber, thread_pool.clone());
+        let task_validator = TaskValidator::new(event_subscriber, thread_pool.clone());
         let state_machine = StateMachine::new(requests_tx);
+        let message_validator = MessageValidator::new(event_subscriber, thread_pool.clone());
 
         Self {
             decryptor,
@@ -46,8 +71,63 @@ impl PetMessageHandler {
             message_parser,
             task_validator,
             state_machine,
+            message_validator,
+            thread_pool,
+            sequencer: Sequencer::default(),
+            max_message_size: None,
         }
     }
+
+    /// Limits the size of the encrypted messages to `max_message_size` bytes.
+    ///
+    /// Larger messages are rejected before they are decrypted. The REST API enforces the same
+    /// limit while reading the request body already, this covers other transports.
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Verifies the task signatures of concurrent messages in batches.
+    ///
+    /// A batch is verified once it holds `batch_size` signatures or once `max_delay` passed since
+    /// its first signature arrived. The batches are verified on the thread pool of the message
+    /// handler. This must be called within a tokio runtime.
+    pub fn with_signature_batching(mut self, batch_size: usize, max_delay: Duration) -> Self {
+        let batcher = SignatureBatcher::new(self.thread_pool.clone(), batch_size, max_delay);
+        self.task_validator = self.task_validator.with_signature_batcher(batcher);
+        self
+    }
+
+    /// Gets the size limit of the encrypted messages, if any.
+    pub fn max_message_size(&self) -> Option<usize> {
+        self.max_message_size
+    }
+
+    /// Round-trips a no-op task through the thread pool which decrypts, parses and validates the
+    /// messages.
+    ///
+    /// The task only completes if the thread pool isn't stalled, which makes this a cheap
+    /// liveness check of the message processing.
+    pub async fn ping(&self) -> Result<(), ServiceError> {
+        self.thread_pool.spawn(|| ()).await
+    }
+
+    /// Gets the thread pool of the message processing.
+    #[cfg(test)]
+    pub(crate) fn thread_pool(&self) -> &MessageThreadPool {
+        &self.thread_pool
+    }
+
+    /// Checks the size of an encrypted message against the size limit.
+    fn check_message_size(&self, enc_data: &[u8]) -> Result<(), ServiceError> {
+        match self.max_message_size {
+            Some(limit) if enc_data.len() > limit => {
+                Err(ServiceError::MessageTooLarge(enc_data.len(), limit))
+            }
+            _ => Ok(()),
+        }
+    }
+
     async fn decrypt(&mut self, enc_data: Vec<u8>) -> Result<Vec<u8>, ServiceError> {
         poll_fn(|cx| <Decryptor as Service<Vec<u8>>>::poll_ready(&mut self.decryptor, cx)).await?;
         self.decryptor.call(enc_data).await
@@ -72,22 +152,64 @@ impl PetMessageHandler {
         self.task_validator.call(message).await
     }
 
-    async fn process(&mut self, message: Message) -> Result<(), ServiceError> {
+    async fn process(
+        &mut self,
+        message: Message,
+        identity: Option<String>,
+        ticket: Ticket,
+    ) -> Result<(), ServiceError> {
+        let req = StateMachineRequest::from(message).with_identity(identity);
         poll_fn(|cx| self.state_machine.poll_ready(cx)).await?;
-        self.state_machine.call(message).await
+        ticket.turn().await;
+        // the request is enqueued once the service is called, hence the next message can take its
+        // turn while this one awaits its response
+        let response = self.state_machine.call(req);
+        drop(ticket);
+        response.await
     }
 
     pub async fn handle_message(&mut self, enc_data: Vec<u8>) -> Result<(), ServiceError> {
+        self.handle_message_with_identity(enc_data, None).await
+    }
+
+    /// Handles a PET message of a participant with a known client certificate identity.
+    ///
+    /// The identity is only taken into account for sum messages, see the
+    /// `pet.sum.distinct_identities` setting. Otherwise this is the same as [`handle_message()`].
+    ///
+    /// [`handle_message()`]: PetMessageHandler::handle_message
+    pub async fn handle_message_with_identity(
+        &mut self,
+        enc_data: Vec<u8>,
+        identity: Option<String>,
+    ) -> Result<(), ServiceError> {
+        let ticket = self.sequencer.ticket();
+        self.check_message_size(&enc_data)?;
         let raw_message = self.decrypt(enc_data).await?;
         let message = self.parse(raw_message).await?;
         match self.handle_multipart(message).await? {
             Some(message) => {
                 let message = self.validate_task(message).await?;
-                self.process(message).await
+                self.process(message, identity, ticket).await
             }
             None => Ok(()),
         }
     }
+
+    /// Validates a PET message without processing it.
+    ///
+    /// The message runs through the same checks as in [`handle_message()`], but it is never
+    /// handed to the state machine. The verdict lists the outcome of every check.
+    ///
+    /// [`handle_message()`]: PetMessageHandler::handle_message
+    pub async fn validate_message(&mut self, enc_data: Vec<u8>) -> Result<Verdict, ServiceError> {
+        self.check_message_size(&enc_data)?;
+        poll_fn(|cx| {
+            <MessageValidator as Service<Vec<u8>>>::poll_ready(&mut self.message_validator, cx)
+        })
+        .await?;
+        self.message_validator.call(enc_data).await
+    }
 }
 
 /// A service that processes requests from the beginning to the
@@ -111,8 +233,104 @@ pub struct PetMessageHandler {
     message_parser: MessageParser,
     task_validator: TaskValidator,
     state_machine: StateMachine,
+    message_validator: MessageValidator,
+    /// The thread pool the CPU-intensive tasks are offloaded to.
+    thread_pool: MessageThreadPool,
+    /// The order in which the messages are handed to the state machine.
+    sequencer: Sequencer,
+    /// The size limit of the encrypted messages.
+    max_message_size: Option<usize>,
 }
 
 pub type BoxedServiceFuture<Response, Error> = std::pin::Pin<
     Box<dyn futures::Future<Output = Result<Response, Error>> + 'static + Send + Sync>,
 >;
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+
+    use super::*;
+    use crate::{
+        services::tests::utils,
+        state_machine::{phases::PhaseName, requests::RequestReceiver},
+    };
+    use xaynet_core::{
+        crypto::{ByteObject, PublicEncryptKey},
+        message::{Payload, Sum},
+    };
+
+    #[tokio::test]
+    async fn test_same_sender_ordering() {
+        let (mut publisher, subscriber) = utils::new_event_channels();
+        let mut round_params = subscriber.params_listener().get_latest().event;
+        // make sure everyone is eligible
+        round_params.sum = 1.0;
+        publisher.broadcast_params(round_params.clone());
+        publisher.broadcast_phase(PhaseName::Sum);
+
+        // a state machine which records the order in which it receives the sum requests
+        let (mut request_rx, request_tx) = RequestReceiver::new();
+        let state_machine = tokio::spawn(async move {
+            let mut received = Vec::new();
+            while let Some((req, _, resp_tx)) = request_rx.recv().await {
+                if let StateMachineRequest::Sum(req) = req {
+                    received.push((req.participant_pk, req.ephm_pk));
+                }
+                let _ = resp_tx.send(Ok(()));
+            }
+            received
+        });
+        let thread_pool = MessageThreadPool::new(Some(2)).unwrap();
+        let handler = PetMessageHandler::with_thread_pool(&subscriber, request_tx, thread_pool);
+
+        // other participants flood the handler
+        let flood = (0..32)
+            .map(|_| {
+                let mut handler = handler.clone();
+                let (message, keys) = utils::new_sum_message(&round_params);
+                let enc_data = utils::encrypt_message(&message, &round_params, &keys);
+                tokio::spawn(async move { handler.handle_message(enc_data).await })
+            })
+            .collect::<Vec<_>>();
+
+        // meanwhile one participant sends its messages concurrently, but one after another
+        let (message, keys) = utils::new_sum_message(&round_params);
+        let sum_signature = match message.payload {
+            Payload::Sum(ref sum) => sum.sum_signature,
+            _ => unreachable!(),
+        };
+        let ephm_pks = (0..64)
+            .map(|_| PublicEncryptKey::generate())
+            .collect::<Vec<_>>();
+        let sends = ephm_pks
+            .iter()
+            .map(|ephm_pk| {
+                let mut handler = handler.clone();
+                let sum = Sum {
+                    sum_signature,
+                    ephm_pk: *ephm_pk,
+                };
+                let message = Message::new_sum(keys.public, round_params.pk, sum);
+                let enc_data = utils::encrypt_message(&message, &round_params, &keys);
+                async move { handler.handle_message(enc_data).await }
+            })
+            .collect::<Vec<_>>();
+        for result in join_all(sends).await {
+            result.unwrap();
+        }
+
+        for result in join_all(flood).await {
+            result.unwrap().unwrap();
+        }
+        drop(handler);
+        let received = state_machine.await.unwrap();
+        assert_eq!(received.len(), 32 + 64);
+        let received_ephm_pks = received
+            .into_iter()
+            .filter(|(participant_pk, _)| *participant_pk == keys.public)
+            .map(|(_, ephm_pk)| ephm_pk)
+            .collect::<Vec<_>>();
+        assert_eq!(received_ephm_pks, ephm_pks);
+    }
+}
@@ -13,7 +13,18 @@ use tracing::{debug, trace, warn};
 use crate::services::messages::{multipart::buffer::MultipartMessageBuffer, ServiceError};
 use xaynet_core::{
     crypto::{PublicEncryptKey, PublicSigningKey},
-    message::{Chunk, DecodeError, FromBytes, Message, Payload, Sum, Sum2, Tag, Update},
+    message::{
+        Chunk,
+        DecodeError,
+        Extension,
+        FromBytes,
+        Message,
+        Payload,
+        Sum,
+        Sum2,
+        Tag,
+        Update,
+    },
 };
 
 /// A `MessageBuilder` stores chunks of a multipart message. Once it
@@ -30,22 +41,33 @@ pub struct MessageBuilder {
     coordinator_pk: PublicEncryptKey,
     /// Message type
     tag: Tag,
+    /// Whether the masks of the message are bound to the round seed
+    round_bound_masks: bool,
     /// The ID of the last chunk is actually the total number of
     /// chunks this message is made of.
     last_chunk_id: Option<u16>,
     /// Chunks, ordered by ID
     data: BTreeMap<u16, Vec<u8>>,
+    /// The extensions of the message, which are taken from the first chunk
+    extensions: Vec<Extension>,
 }
 
 impl MessageBuilder {
     /// Create a new [`MessageBuilder`] that contains no chunk.
-    fn new(tag: Tag, participant_pk: PublicSigningKey, coordinator_pk: PublicEncryptKey) -> Self {
+    fn new(
+        tag: Tag,
+        participant_pk: PublicSigningKey,
+        coordinator_pk: PublicEncryptKey,
+        round_bound_masks: bool,
+    ) -> Self {
         MessageBuilder {
             tag,
+            round_bound_masks,
             participant_pk,
             coordinator_pk,
             data: BTreeMap::new(),
             last_chunk_id: None,
+            extensions: Vec::new(),
         }
     }
 
@@ -85,7 +107,9 @@ impl MessageBuilder {
             coordinator_pk: self.coordinator_pk,
             tag: self.tag,
             is_multipart: false,
+            round_bound_masks: self.round_bound_masks,
             payload,
+            extensions: self.extensions,
         };
         Ok(message)
     }
@@ -136,7 +160,9 @@ impl Service<Message> for MultipartHandler {
             tag,
             participant_pk,
             coordinator_pk,
+            round_bound_masks,
             payload: Payload::Chunk(chunk),
+            extensions,
             ..
         } = message
         {
@@ -148,9 +174,12 @@ impl Service<Message> for MultipartHandler {
             // an empty one.
             let mp_message = self.message_builders.entry(id.clone()).or_insert_with(|| {
                 debug!("new multipart message (id = {})", id.message_id);
-                MessageBuilder::new(tag, participant_pk, coordinator_pk)
+                MessageBuilder::new(tag, participant_pk, coordinator_pk, round_bound_masks)
             });
             // Add the chunk to the partial message
+            if chunk.id == 0 {
+                mp_message.extensions = extensions;
+            }
             mp_message.add_chunk(chunk);
 
             // Check if the message is complete, and if so parse it
@@ -222,7 +251,7 @@ mod tests {
         let participant_pk = PublicSigningKey::zeroed();
         let coordinator_pk = PublicEncryptKey::zeroed();
         let tag = Tag::Sum;
-        MessageBuilder::new(tag, participant_pk, coordinator_pk)
+        MessageBuilder::new(tag, participant_pk, coordinator_pk, false)
     }
 
     fn chunks(mut data: Vec<u8>) -> (Chunk, Chunk, Chunk, Chunk, Chunk) {
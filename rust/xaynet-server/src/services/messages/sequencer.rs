@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::oneshot;
+
+/// Hands the messages to the state machine in the order in which they arrived.
+///
+/// The messages are decrypted, parsed and validated concurrently on the thread pool, hence they
+/// may finish in any order. Every message draws a [`Ticket`] when it arrives and waits for its
+/// turn before it is handed to the state machine. This way, the messages of a participant are
+/// processed in the order in which they were sent and can't race their duplicate checks, even if
+/// they are sent concurrently. The sequencer is a cheaply cloneable handle, all clones share the
+/// same sequence.
+#[derive(Clone, Debug, Default)]
+pub struct Sequencer(Arc<Mutex<Sequence>>);
+
+#[derive(Debug, Default)]
+struct Sequence {
+    /// The number of the next ticket.
+    next_ticket: u64,
+    /// The number of the ticket whose turn it is.
+    turn: u64,
+    /// The tickets which were dropped before their turn.
+    dropped: BTreeSet<u64>,
+    /// The tickets which wait for their turn.
+    waiting: HashMap<u64, oneshot::Sender<()>>,
+}
+
+impl Sequencer {
+    /// Draws the ticket of an arriving message.
+    pub fn ticket(&self) -> Ticket {
+        let mut sequence = self.0.lock().unwrap();
+        let number = sequence.next_ticket;
+        sequence.next_ticket += 1;
+        Ticket {
+            sequencer: self.clone(),
+            number,
+        }
+    }
+}
+
+/// The place of a message in the [`Sequencer`].
+///
+/// The turn passes on to the next ticket once the ticket is dropped.
+#[derive(Debug)]
+pub struct Ticket {
+    sequencer: Sequencer,
+    number: u64,
+}
+
+impl Ticket {
+    /// Waits until the tickets of all earlier messages are dropped.
+    pub async fn turn(&self) {
+        let turn = {
+            let mut sequence = self.sequencer.0.lock().unwrap();
+            if sequence.turn == self.number {
+                return;
+            }
+            let (tx, rx) = oneshot::channel();
+            sequence.waiting.insert(self.number, tx);
+            rx
+        };
+        // the sender is only dropped together with this ticket
+        let _ = turn.await;
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let mut sequence = self.sequencer.0.lock().unwrap();
+        sequence.waiting.remove(&self.number);
+        sequence.dropped.insert(self.number);
+        let Sequence {
+            ref mut turn,
+            ref mut dropped,
+            ref mut waiting,
+            ..
+        } = *sequence;
+        while dropped.remove(turn) {
+            *turn += 1;
+        }
+        if let Some(tx) = waiting.remove(turn) {
+            let _ = tx.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::join_all;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_turns() {
+        let sequencer = Sequencer::default();
+        let tickets = (0..4).map(|_| sequencer.ticket()).collect::<Vec<_>>();
+
+        // the tickets take their turns in the order in which they were drawn, regardless of the
+        // order in which they wait for them
+        let turns = Arc::new(Mutex::new(Vec::new()));
+        let waits = tickets.into_iter().rev().map(|ticket| {
+            let turns = turns.clone();
+            async move {
+                ticket.turn().await;
+                turns.lock().unwrap().push(ticket.number);
+            }
+        });
+        join_all(waits).await;
+        assert_eq!(*turns.lock().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_ticket() {
+        let sequencer = Sequencer::default();
+        let first = sequencer.ticket();
+        let second = sequencer.ticket();
+        let third = sequencer.ticket();
+
+        // the turn skips the tickets which are dropped before their turn
+        drop(second);
+        drop(first);
+        third.turn().await;
+    }
+}
@@ -0,0 +1,324 @@
+//! Batch verification of the task signatures of the PET messages.
+//!
+//! The signatures are created and verified individually with `sodiumoxide` everywhere else. The
+//! batches are verified with `ed25519-dalek` instead, which only accepts a batch if all of its
+//! signatures satisfy the same cofactorless verification equation as `libsodium`. The encodings
+//! which `libsodium` rejects beyond that equation, i.e. non-canonical or small order public keys
+//! and `R` components of the signatures, are never batched but verified individually with
+//! `sodiumoxide`.
+//!
+//! A batch accepts a signature whose points carry a torsion component with a small probability,
+//! which the individual verification always rejects. Such signatures can only be created with
+//! the secret key and the signer could create other valid signatures for its task just as well.
+
+use std::{convert::TryFrom, time::Duration};
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ed25519_dalek::{verify_batch, Signature as BatchSignature, VerifyingKey};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{self, Instant},
+};
+use tracing::debug;
+use xaynet_core::{
+    crypto::{ByteObject, Signature},
+    ParticipantPublicKey,
+};
+
+use crate::services::messages::{MessageThreadPool, ServiceError};
+
+/// A signature to be verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureCheck {
+    /// The public key of the signer.
+    pub pk: ParticipantPublicKey,
+    /// The signature.
+    pub signature: Signature,
+    /// The signed bytes.
+    pub payload: Vec<u8>,
+}
+
+impl SignatureCheck {
+    /// Verifies the signature individually.
+    pub fn verify(&self) -> bool {
+        self.pk.verify_detached(&self.signature, &self.payload)
+    }
+
+    /// Converts the public key and the signature for the batch verification.
+    ///
+    /// Returns `None` if the batch verification might disagree with the individual verification
+    /// about the encodings.
+    fn to_batchable(&self) -> Option<(VerifyingKey, BatchSignature)> {
+        let pk = <[u8; 32]>::try_from(self.pk.as_slice()).ok()?;
+        let signature = <[u8; 64]>::try_from(self.signature.as_slice()).ok()?;
+        let r = <[u8; 32]>::try_from(&signature[..32]).ok()?;
+        if !is_canonical_large_order_point(&pk) || !is_canonical_large_order_point(&r) {
+            return None;
+        }
+        let pk = VerifyingKey::from_bytes(&pk).ok()?;
+        Some((pk, BatchSignature::from_bytes(&signature)))
+    }
+}
+
+/// Checks whether the bytes are the canonical encoding of a curve point which isn't of small
+/// order.
+fn is_canonical_large_order_point(bytes: &[u8; 32]) -> bool {
+    CompressedEdwardsY(*bytes)
+        .decompress()
+        .map(|point| point.compress().as_bytes() == bytes && !point.is_small_order())
+        .unwrap_or(false)
+}
+
+/// Verifies the signatures of the `checks` and returns whether each one of them is valid.
+///
+/// The signatures are verified in a batch first, which is considerably faster than verifying
+/// them one by one. If the batch fails, the signatures are verified individually to identify the
+/// invalid ones.
+pub fn verify_signatures(checks: &[SignatureCheck]) -> Vec<bool> {
+    let mut valid = vec![false; checks.len()];
+    let mut batch = Vec::with_capacity(checks.len());
+    for (index, check) in checks.iter().enumerate() {
+        match check.to_batchable() {
+            Some((pk, signature)) => batch.push((index, pk, signature)),
+            None => valid[index] = check.verify(),
+        }
+    }
+    if batch.is_empty() {
+        return valid;
+    }
+
+    let messages = batch
+        .iter()
+        .map(|(index, _, _)| checks[*index].payload.as_slice())
+        .collect::<Vec<_>>();
+    let signatures = batch.iter().map(|(_, _, sig)| *sig).collect::<Vec<_>>();
+    let pks = batch.iter().map(|(_, pk, _)| *pk).collect::<Vec<_>>();
+    if verify_batch(&messages, &signatures, &pks).is_ok() {
+        for (index, _, _) in batch {
+            valid[index] = true;
+        }
+    } else {
+        debug!(
+            "batch of {} signatures is invalid, verifying them individually",
+            batch.len()
+        );
+        for (index, _, _) in batch {
+            valid[index] = checks[index].verify();
+        }
+    }
+    valid
+}
+
+/// A signature to be verified together with the sender of its result.
+type PendingCheck = (SignatureCheck, oneshot::Sender<bool>);
+
+/// A service which collects the signatures of concurrent messages into micro-batches.
+///
+/// A batch is verified on the thread pool of the message processing once it holds
+/// `batch_size` signatures or once `max_delay` passed since its first signature was
+/// collected, whichever comes first. The handle is cheaply cloneable, all clones share the same
+/// batches.
+#[derive(Clone, Debug)]
+pub struct SignatureBatcher {
+    checks_tx: mpsc::UnboundedSender<PendingCheck>,
+}
+
+impl SignatureBatcher {
+    /// Creates a batcher and spawns the task which collects the batches.
+    ///
+    /// # Panics
+    /// Panics if called outside of a tokio runtime.
+    pub fn new(thread_pool: MessageThreadPool, batch_size: usize, max_delay: Duration) -> Self {
+        let (checks_tx, checks_rx) = mpsc::unbounded_channel();
+        tokio::spawn(collect_batches(
+            checks_rx,
+            thread_pool,
+            batch_size.max(1),
+            max_delay,
+        ));
+        Self { checks_tx }
+    }
+
+    /// Verifies the signatures of the `checks` as part of the next batches and returns whether
+    /// each one of them is valid.
+    pub async fn verify(&self, checks: Vec<SignatureCheck>) -> Result<Vec<bool>, ServiceError> {
+        let results = checks
+            .into_iter()
+            .map(|check| {
+                let (result_tx, result_rx) = oneshot::channel();
+                self.checks_tx.send((check, result_tx)).map_err(|_| {
+                    ServiceError::InternalError("signature batcher terminated".to_string())
+                })?;
+                Ok(result_rx)
+            })
+            .collect::<Result<Vec<_>, ServiceError>>()?;
+
+        let mut valid = Vec::with_capacity(results.len());
+        for result_rx in results {
+            valid.push(result_rx.await.map_err(|_| {
+                ServiceError::InternalError("failed to verify a batch of signatures".to_string())
+            })?);
+        }
+        Ok(valid)
+    }
+}
+
+/// Collects the pending checks into batches and verifies them on the thread pool.
+async fn collect_batches(
+    mut checks_rx: mpsc::UnboundedReceiver<PendingCheck>,
+    thread_pool: MessageThreadPool,
+    batch_size: usize,
+    max_delay: Duration,
+) {
+    while let Some(first) = checks_rx.recv().await {
+        let deadline = Instant::now() + max_delay;
+        let mut batch = vec![first];
+        while batch.len() < batch_size {
+            match time::timeout_at(deadline, checks_rx.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let (checks, results_tx): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+        let verified = thread_pool.spawn(move || verify_signatures(&checks));
+        // the next batch is collected while this one is verified
+        tokio::spawn(async move {
+            if let Ok(valid) = verified.await {
+                for (result_tx, valid) in results_tx.into_iter().zip(valid) {
+                    let _ = result_tx.send(valid);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xaynet_core::crypto::{PublicSigningKey, SigningKeyPair, SigningKeySeed};
+
+    /// Creates a check of a valid signature of a fresh key pair.
+    fn valid_check(payload: &[u8]) -> SignatureCheck {
+        let keys = SigningKeyPair::generate();
+        SignatureCheck {
+            pk: keys.public,
+            signature: keys.secret.sign_detached(payload),
+            payload: payload.to_vec(),
+        }
+    }
+
+    /// Creates a check of a signature which is valid for another payload.
+    fn invalid_check(payload: &[u8]) -> SignatureCheck {
+        let mut check = valid_check(payload);
+        check.payload.push(0);
+        check
+    }
+
+    #[test]
+    fn test_rfc8032_vectors() {
+        // the test vectors 1 and 2 of RFC 8032, section 7.1
+        let vectors = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ];
+        let checks = vectors
+            .iter()
+            .map(|(seed, pk, payload, signature)| {
+                let seed = SigningKeySeed::from_slice(&hex::decode(seed).unwrap()).unwrap();
+                let (public, secret) = seed.derive_signing_key_pair();
+                let pk = PublicSigningKey::from_slice(&hex::decode(pk).unwrap()).unwrap();
+                let payload = hex::decode(payload).unwrap();
+                let signature = Signature::from_slice(&hex::decode(signature).unwrap()).unwrap();
+                // sodiumoxide agrees with the vectors
+                assert_eq!(public, pk);
+                assert_eq!(secret.sign_detached(&payload), signature);
+                SignatureCheck {
+                    pk,
+                    signature,
+                    payload,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert!(checks.iter().all(|check| check.to_batchable().is_some()));
+        assert_eq!(verify_signatures(&checks), vec![true, true]);
+    }
+
+    #[test]
+    fn test_sodiumoxide_signatures() {
+        let checks = (0..64).map(|i| valid_check(&[i; 40])).collect::<Vec<_>>();
+        assert!(verify_signatures(&checks).into_iter().all(|valid| valid));
+    }
+
+    #[test]
+    fn test_bad_signature_in_batch() {
+        let mut checks = (0..16).map(|i| valid_check(&[i; 40])).collect::<Vec<_>>();
+        checks[7] = invalid_check(&[7; 40]);
+
+        let valid = verify_signatures(&checks);
+        assert!(!valid[7]);
+        assert!(valid
+            .iter()
+            .enumerate()
+            .all(|(index, valid)| *valid || index == 7));
+    }
+
+    #[test]
+    fn test_small_order_key() {
+        // the identity point is of small order, which is rejected by `libsodium`
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        let check = SignatureCheck {
+            pk: PublicSigningKey::from_slice(&identity).unwrap(),
+            signature: Signature::from_slice(&[0; 64]).unwrap(),
+            payload: b"payload".to_vec(),
+        };
+        assert!(check.to_batchable().is_none());
+        assert_eq!(
+            verify_signatures(&[valid_check(b"payload"), check]),
+            vec![true, false]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batcher() {
+        let thread_pool = MessageThreadPool::new(Some(2)).unwrap();
+        let batcher = SignatureBatcher::new(thread_pool, 16, Duration::from_millis(5));
+
+        // concurrent requests share batches, the results are attributed to their checks
+        let requests = (0..40_u8)
+            .map(|i| {
+                let batcher = batcher.clone();
+                let checks = if i % 10 == 3 {
+                    vec![valid_check(&[i]), invalid_check(&[i])]
+                } else {
+                    vec![valid_check(&[i]), valid_check(&[i])]
+                };
+                tokio::spawn(async move { (i, batcher.verify(checks).await.unwrap()) })
+            })
+            .collect::<Vec<_>>();
+        for request in requests {
+            let (i, valid) = request.await.unwrap();
+            if i % 10 == 3 {
+                assert_eq!(valid, vec![true, false]);
+            } else {
+                assert_eq!(valid, vec![true, true]);
+            }
+        }
+
+        // a single request is flushed after the delay
+        let valid = batcher.verify(vec![valid_check(b"alone")]).await.unwrap();
+        assert_eq!(valid, vec![true]);
+    }
+}
@@ -4,26 +4,65 @@ use futures::{future, task::Context};
 use tower::Service;
 
 use crate::{
-    services::messages::ServiceError,
-    state_machine::events::{EventListener, EventSubscriber},
+    services::messages::{
+        BoxedServiceFuture,
+        MessageThreadPool,
+        ServiceError,
+        SignatureBatcher,
+        SignatureCheck,
+    },
+    state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber},
 };
 use xaynet_core::{
     common::RoundParameters,
-    crypto::ByteObject,
+    crypto::{ByteObject, Signature},
     message::{Message, Payload},
+    ParticipantPublicKey,
+    SumDict,
 };
 
 /// A service for performing sanity checks and preparing incoming
 /// requests to be handled by the state machine.
+///
+/// Since verifying the task signatures is a CPU-intensive task, this
+/// service offloads the processing to a `rayon` thread-pool.
+///
+/// If sum-eligible participants may update, the update messages of
+/// the participants which already summed are rejected.
+///
+/// With a [`SignatureBatcher`], the task signatures of concurrent
+/// messages are verified in batches instead.
 #[derive(Clone, Debug)]
 pub struct TaskValidator {
     params_listener: EventListener<RoundParameters>,
+    sum_dict_listener: EventListener<DictionaryUpdate<SumDict>>,
+    /// Thread-pool the CPU-intensive tasks are offloaded to.
+    thread_pool: MessageThreadPool,
+    /// The batcher of the task signatures, if any.
+    signature_batcher: Option<SignatureBatcher>,
 }
 
 impl TaskValidator {
-    pub fn new(subscriber: &EventSubscriber) -> Self {
+    pub fn new(subscriber: &EventSubscriber, thread_pool: MessageThreadPool) -> Self {
         Self {
             params_listener: subscriber.params_listener(),
+            sum_dict_listener: subscriber.sum_dict_listener(),
+            thread_pool,
+            signature_batcher: None,
+        }
+    }
+
+    /// Verifies the task signatures in batches with the given batcher.
+    pub fn with_signature_batcher(mut self, signature_batcher: SignatureBatcher) -> Self {
+        self.signature_batcher = Some(signature_batcher);
+        self
+    }
+
+    /// Checks whether the participant is in the sum dictionary of the current round.
+    fn is_sum_participant(&self, pk: &ParticipantPublicKey) -> bool {
+        match self.sum_dict_listener.get_latest().event {
+            DictionaryUpdate::New(sum_dict) => sum_dict.contains_key(pk),
+            DictionaryUpdate::Invalidate => false,
         }
     }
 }
@@ -31,64 +70,127 @@ impl TaskValidator {
 impl Service<Message> for TaskValidator {
     type Response = Message;
     type Error = ServiceError;
-    type Future = future::Ready<Result<Self::Response, Self::Error>>;
+    type Future = BoxedServiceFuture<Self::Response, Self::Error>;
 
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Poll::Ready(Ok(()))
     }
 
     fn call(&mut self, message: Message) -> Self::Future {
-        let (sum_signature, update_signature) = match message.payload {
-            Payload::Sum(ref sum) => (sum.sum_signature, None),
-            Payload::Update(ref update) => (update.sum_signature, Some(update.update_signature)),
-            Payload::Sum2(ref sum2) => (sum2.sum_signature, None),
-            _ => return future::ready(Err(ServiceError::UnexpectedMessage)),
-        };
         let params = self.params_listener.get_latest().event;
-        let seed = params.seed.as_slice();
-
-        // Check whether the participant is eligible for the sum task
-        let has_valid_sum_signature = message
-            .participant_pk
-            .verify_detached(&sum_signature, &[seed, b"sum"].concat());
-        let is_summer = has_valid_sum_signature && sum_signature.is_eligible(params.sum);
-
-        // Check whether the participant is eligible for the update task
-        let has_valid_update_signature = update_signature
-            .map(|sig| {
-                message
-                    .participant_pk
-                    .verify_detached(&sig, &[seed, b"update"].concat())
-            })
-            .unwrap_or(false);
-        let is_updater = !is_summer
-            && has_valid_update_signature
-            && update_signature
-                .map(|sig| sig.is_eligible(params.update))
-                .unwrap_or(false);
-
-        match message.payload {
-            Payload::Sum(_) | Payload::Sum2(_) => {
-                if is_summer {
-                    future::ready(Ok(message))
-                } else {
-                    future::ready(Err(ServiceError::NotSumEligible))
-                }
-            }
-            Payload::Update(_) => {
-                if is_updater {
-                    future::ready(Ok(message))
-                } else {
-                    future::ready(Err(ServiceError::NotUpdateEligible))
-                }
-            }
-            _ => future::ready(Err(ServiceError::UnexpectedMessage)),
+        if params.allow_sum_eligible_updates
+            && matches!(message.payload, Payload::Update(_))
+            && self.is_sum_participant(&message.participant_pk)
+        {
+            return Box::pin(future::ready(Err(ServiceError::NotUpdateEligible)));
+        }
+        if let Some(ref batcher) = self.signature_batcher {
+            let batcher = batcher.clone();
+            return Box::pin(async move {
+                let checks = task_signature_checks(&params, &message)?;
+                let valid = batcher.verify(checks).await?;
+                check_eligibility(&params, &message, &valid).map(|_| message)
+            });
         }
+        let validated = self
+            .thread_pool
+            .spawn(move || validate_task(&params, &message).map(|_| message));
+        Box::pin(async move { validated.await? })
+    }
+}
+
+/// Checks whether the participant of a message is eligible for its task wrt the round parameters.
+///
+/// The sum task takes precedence over the update task, unless the round parameters allow
+/// sum-eligible participants to update.
+pub(super) fn validate_task(
+    params: &RoundParameters,
+    message: &Message,
+) -> Result<(), ServiceError> {
+    let valid = task_signature_checks(params, message)?
+        .iter()
+        .map(SignatureCheck::verify)
+        .collect::<Vec<_>>();
+    check_eligibility(params, message, &valid)
+}
+
+/// Gets the sum signature and the optional update signature of a message.
+fn task_signatures(message: &Message) -> Result<(Signature, Option<Signature>), ServiceError> {
+    match message.payload {
+        Payload::Sum(ref sum) => Ok((sum.sum_signature, None)),
+        Payload::Update(ref update) => Ok((update.sum_signature, Some(update.update_signature))),
+        Payload::Sum2(ref sum2) => Ok((sum2.sum_signature, None)),
+        _ => Err(ServiceError::UnexpectedMessage),
+    }
+}
+
+/// Prepares the verification of the task signatures of a message.
+///
+/// The sum signature is checked first, followed by the update signature for update messages.
+fn task_signature_checks(
+    params: &RoundParameters,
+    message: &Message,
+) -> Result<Vec<SignatureCheck>, ServiceError> {
+    let (sum_signature, update_signature) = task_signatures(message)?;
+    let seed = params.seed.as_slice();
+    let suite = params.crypto_suite;
+
+    // Check whether the masks of the message are bound to the round as negotiated
+    let has_masks = matches!(message.payload, Payload::Update(_) | Payload::Sum2(_));
+    if has_masks && message.round_bound_masks != params.round_bound_masks {
+        return Err(ServiceError::MaskBindingMismatch);
+    }
+
+    let mut checks = vec![SignatureCheck {
+        pk: message.participant_pk,
+        signature: sum_signature,
+        payload: suite.task_signature_payload(seed, b"sum"),
+    }];
+    if let Some(signature) = update_signature {
+        checks.push(SignatureCheck {
+            pk: message.participant_pk,
+            signature,
+            payload: suite.task_signature_payload(seed, b"update"),
+        });
+    }
+    Ok(checks)
+}
+
+/// Checks the task eligibility of the participant of a message, given the validity of the
+/// signatures of its [`task_signature_checks()`].
+fn check_eligibility(
+    params: &RoundParameters,
+    message: &Message,
+    valid: &[bool],
+) -> Result<(), ServiceError> {
+    let (sum_signature, update_signature) = task_signatures(message)?;
+    let has_valid_sum_signature = valid.first().copied().unwrap_or(false);
+    let has_valid_update_signature = valid.get(1).copied().unwrap_or(false);
+
+    // Check whether the participant is eligible for the sum task
+    let salt = &params.eligibility_salt;
+    let is_summer = has_valid_sum_signature && sum_signature.is_eligible_salted(params.sum, salt);
+
+    // Check whether the participant is eligible for the update task
+    let is_updater = (!is_summer || params.allow_sum_eligible_updates)
+        && has_valid_update_signature
+        && update_signature
+            .map(|sig| sig.is_eligible_salted(params.update, salt))
+            .unwrap_or(false);
+
+    match message.payload {
+        Payload::Sum(_) | Payload::Sum2(_) if is_summer => Ok(()),
+        Payload::Sum(_) | Payload::Sum2(_) => Err(ServiceError::NotSumEligible),
+        Payload::Update(_) if is_updater => Ok(()),
+        Payload::Update(_) => Err(ServiceError::NotUpdateEligible),
+        _ => Err(ServiceError::UnexpectedMessage),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{sync::Arc, time::Duration};
+
     use tokio_test::assert_ready;
     use tower_test::mock::Spawn;
 
@@ -101,10 +203,12 @@ mod tests {
     };
 
     use super::*;
+    use xaynet_core::crypto::{CryptoSuite, PublicEncryptKey};
 
     fn spawn_svc() -> (EventPublisher, EventSubscriber, Spawn<TaskValidator>) {
         let (publisher, subscriber) = utils::new_event_channels();
-        let task = Spawn::new(TaskValidator::new(&subscriber));
+        let thread_pool = MessageThreadPool::new(None).unwrap();
+        let task = Spawn::new(TaskValidator::new(&subscriber, thread_pool));
         (publisher, subscriber, task)
     }
 
@@ -127,6 +231,39 @@ mod tests {
         assert_eq!(resp, message);
     }
 
+    #[tokio::test]
+    async fn test_batched_signatures() {
+        let (mut publisher, subscriber) = utils::new_event_channels();
+        let thread_pool = MessageThreadPool::new(None).unwrap();
+        let batcher = SignatureBatcher::new(thread_pool.clone(), 8, Duration::from_millis(1));
+        let mut task = Spawn::new(
+            TaskValidator::new(&subscriber, thread_pool).with_signature_batcher(batcher),
+        );
+
+        let mut round_params = subscriber.params_listener().get_latest().event;
+        round_params.sum = 1.0;
+        publisher.broadcast_params(round_params.clone());
+        publisher.broadcast_phase(PhaseName::Sum);
+
+        let (message, _) = utils::new_sum_message(&round_params);
+        assert_ready!(task.poll_ready()).unwrap();
+        let resp = task.call(message.clone()).await.unwrap();
+        assert_eq!(resp, message);
+
+        // the sum signature of another participant is invalid
+        let (other, _) = utils::new_sum_message(&round_params);
+        let message = Message {
+            participant_pk: other.participant_pk,
+            ..message
+        };
+        assert_ready!(task.poll_ready()).unwrap();
+        let err = task.call(message).await.unwrap_err();
+        match err {
+            ServiceError::NotSumEligible => {}
+            _ => panic!("expected ServiceError::NotSumEligible got {:?}", err),
+        }
+    }
+
     #[tokio::test]
     async fn test_sum_not_eligible() {
         let (mut publisher, subscriber, mut task) = spawn_svc();
@@ -148,4 +285,106 @@ mod tests {
             _ => panic!("expected ServiceError::NotSumEligible got {:?}", err),
         }
     }
+
+    #[tokio::test]
+    async fn test_crypto_suite() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+
+        let mut round_params = subscriber.params_listener().get_latest().event;
+        round_params.sum = 1.0;
+        round_params.crypto_suite = CryptoSuite::BLAKE3;
+
+        publisher.broadcast_params(round_params.clone());
+        publisher.broadcast_phase(PhaseName::Sum);
+
+        let (message, _) = utils::new_sum_message(&round_params);
+        assert_ready!(task.poll_ready()).unwrap();
+        let resp = task.call(message.clone()).await.unwrap();
+        assert_eq!(resp, message);
+
+        // the task signatures of another suite are invalid
+        let (message, _) = utils::new_sum_message(&RoundParameters {
+            crypto_suite: CryptoSuite::SHA256,
+            ..round_params
+        });
+        assert_ready!(task.poll_ready()).unwrap();
+        let err = task.call(message).await.unwrap_err();
+        match err {
+            ServiceError::NotSumEligible => {}
+            _ => panic!("expected ServiceError::NotSumEligible got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sum_eligible_update() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+
+        let mut round_params = subscriber.params_listener().get_latest().event;
+
+        // make sure everyone is eligible for both tasks
+        round_params.sum = 1.0;
+        round_params.update = 1.0;
+
+        publisher.broadcast_params(round_params.clone());
+        publisher.broadcast_phase(PhaseName::Update);
+
+        let (message, _) = utils::new_update_message(&round_params);
+
+        // the sum task takes precedence by default
+        assert_ready!(task.poll_ready()).unwrap();
+        let err = task.call(message.clone()).await.unwrap_err();
+        match err {
+            ServiceError::NotUpdateEligible => {}
+            _ => panic!("expected ServiceError::NotUpdateEligible got {:?}", err),
+        }
+
+        round_params.allow_sum_eligible_updates = true;
+        publisher.broadcast_params(round_params.clone());
+        assert_ready!(task.poll_ready()).unwrap();
+        let resp = task.call(message.clone()).await.unwrap();
+        assert_eq!(resp, message);
+
+        // a participant which already summed can't update as well
+        let mut sum_dict = SumDict::new();
+        sum_dict.insert(message.participant_pk, PublicEncryptKey::fill_with(1));
+        publisher.broadcast_sum_dict(DictionaryUpdate::New(Arc::new(sum_dict)));
+        assert_ready!(task.poll_ready()).unwrap();
+        let err = task.call(message).await.unwrap_err();
+        match err {
+            ServiceError::NotUpdateEligible => {}
+            _ => panic!("expected ServiceError::NotUpdateEligible got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_mask_binding_mismatch() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+
+        let mut round_params = subscriber.params_listener().get_latest().event;
+
+        // make sure everyone is eligible for the update task
+        round_params.sum = 0.0;
+        round_params.update = 1.0;
+        round_params.round_bound_masks = true;
+
+        publisher.broadcast_params(round_params.clone());
+        publisher.broadcast_phase(PhaseName::Update);
+
+        let (message, _) = utils::new_update_message(&round_params);
+        assert!(!message.round_bound_masks);
+
+        assert_ready!(task.poll_ready()).unwrap();
+        let err = task.call(message.clone()).await.unwrap_err();
+        match err {
+            ServiceError::MaskBindingMismatch => {}
+            _ => panic!("expected ServiceError::MaskBindingMismatch got {:?}", err),
+        }
+
+        let message = Message {
+            round_bound_masks: true,
+            ..message
+        };
+        let resp = task.call(message.clone()).await.unwrap();
+        assert_eq!(resp, message);
+    }
 }
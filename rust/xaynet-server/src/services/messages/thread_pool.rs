@@ -0,0 +1,112 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use futures::future::Future;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use tokio::sync::oneshot;
+
+use crate::{metric, metrics::Measurement, services::messages::ServiceError};
+
+/// The thread pool which the CPU-intensive tasks of the message processing are offloaded to.
+///
+/// Decrypting, verifying the signatures and decoding the payloads of large messages blocks for a
+/// considerable amount of time. These tasks run on a dedicated `rayon` thread pool such that a
+/// flood of messages can't starve the tokio executor, which drives the timers of the phases and
+/// the state machine. The pool is a cheaply cloneable handle, all clones share the same threads.
+#[derive(Clone, Debug)]
+pub struct MessageThreadPool {
+    thread_pool: Arc<ThreadPool>,
+    /// The number of tasks which are spawned on the thread pool but didn't start yet.
+    queued: Arc<AtomicUsize>,
+}
+
+impl MessageThreadPool {
+    /// Creates a thread pool with the given number of `threads`.
+    ///
+    /// The pool has one thread per logical CPU if `threads` is `None`.
+    ///
+    /// # Errors
+    /// Fails if the threads can't be spawned.
+    pub fn new(threads: Option<usize>) -> Result<Self, ThreadPoolBuildError> {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads.unwrap_or(0))
+            .thread_name(|index| format!("message-processing-{}", index))
+            .build()?;
+        Ok(Self {
+            thread_pool: Arc::new(thread_pool),
+            queued: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Gets the number of threads of the pool.
+    pub fn current_num_threads(&self) -> usize {
+        self.thread_pool.current_num_threads()
+    }
+
+    /// Gets the number of tasks which wait for a free thread.
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Runs a `task` on the thread pool and returns its result.
+    ///
+    /// The task is spawned immediately, the returned future only waits for its result.
+    pub fn spawn<F, R>(
+        &self,
+        task: F,
+    ) -> impl Future<Output = Result<R, ServiceError>> + Send + Sync + 'static
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel::<R>();
+        let queued = self.queued.clone();
+        let queue_depth = queued.fetch_add(1, Ordering::Relaxed) + 1;
+        metric!(Measurement::MessageQueueDepth, queue_depth as u64);
+
+        self.thread_pool.spawn(move || {
+            queued.fetch_sub(1, Ordering::Relaxed);
+            let _ = tx.send(task());
+        });
+        async move {
+            rx.await.map_err(|_| {
+                ServiceError::InternalError(
+                    "failed to receive response from thread-pool".to_string(),
+                )
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Mutex};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_depth() {
+        let thread_pool = MessageThreadPool::new(Some(1)).unwrap();
+        assert_eq!(thread_pool.current_num_threads(), 1);
+
+        // block the only thread, such that the following tasks queue up
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        let blocked = thread_pool.spawn(move || {
+            let _ = release_rx.lock().unwrap().recv();
+        });
+        let queued = (0..3)
+            .map(|i| thread_pool.spawn(move || i))
+            .collect::<Vec<_>>();
+        assert!(thread_pool.queue_depth() >= 3);
+
+        drop(release_tx);
+        blocked.await.unwrap();
+        for (i, task) in queued.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap(), i);
+        }
+        assert_eq!(thread_pool.queue_depth(), 0);
+    }
+}
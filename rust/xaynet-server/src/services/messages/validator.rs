@@ -0,0 +1,460 @@
+use std::task::Poll;
+
+use futures::task::Context;
+use serde::Serialize;
+use tower::Service;
+use tracing::{debug, trace};
+
+use crate::{
+    services::messages::{
+        decryptor::decrypt,
+        message_parser::{check_coordinator_pk, check_phase, check_signature, decode_tag, parse},
+        task_validator::validate_task,
+        BoxedServiceFuture,
+        MessageThreadPool,
+        ServiceError,
+    },
+    state_machine::{
+        events::{EventListener, EventSubscriber},
+        phases::PhaseName,
+    },
+};
+use xaynet_core::{
+    common::RoundParameters,
+    crypto::EncryptKeyPair,
+    mask::Aggregation,
+    message::{Message, MessageBuffer, Payload},
+};
+
+/// A check of a PET message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// The message can be decrypted with the coordinator secret key.
+    Decryption,
+    /// The message header and its tag can be parsed.
+    Header,
+    /// The message is expected in the current phase.
+    Phase,
+    /// The message signature is valid.
+    Signature,
+    /// The message is addressed to the coordinator public key of the current round.
+    CoordinatorPublicKey,
+    /// The message payload can be decoded.
+    Payload,
+    /// The participant is eligible for the task of the message.
+    Eligibility,
+    /// The masked model or the mask of the message conforms to the masking configuration and the
+    /// model length of the current round.
+    Masks,
+}
+
+impl Check {
+    /// The checks in the order in which they are performed.
+    pub const ALL: [Check; 8] = [
+        Check::Decryption,
+        Check::Header,
+        Check::Phase,
+        Check::Signature,
+        Check::CoordinatorPublicKey,
+        Check::Payload,
+        Check::Eligibility,
+        Check::Masks,
+    ];
+}
+
+/// The outcome of a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// The check passed.
+    Passed,
+    /// The check failed.
+    Failed,
+    /// The check was not performed because a preceding check failed.
+    Skipped,
+}
+
+/// The outcome of a check together with the reason of a failure.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CheckOutcome {
+    /// The performed check.
+    pub check: Check,
+    /// The outcome of the check.
+    pub status: CheckStatus,
+    /// The reason why the check failed, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The verdict of the validation of a PET message.
+///
+/// Lists the outcomes of all checks in the order in which they are performed. The validation
+/// stops at the first failing check and the remaining checks are skipped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    /// Whether the message passed all checks.
+    pub valid: bool,
+    /// The outcomes of the checks.
+    pub checks: Vec<CheckOutcome>,
+}
+
+impl Verdict {
+    /// Gets the first failing check, if any.
+    pub fn first_failure(&self) -> Option<Check> {
+        self.checks
+            .iter()
+            .find(|outcome| outcome.status == CheckStatus::Failed)
+            .map(|outcome| outcome.check)
+    }
+
+    /// Records the outcome of a check and passes on its value if it passed.
+    fn record<R>(&mut self, check: Check, result: Result<R, ServiceError>) -> Option<R> {
+        let (status, reason, value) = match result {
+            Ok(value) => (CheckStatus::Passed, None, Some(value)),
+            Err(e) => {
+                debug!("message validation failed at {:?}: {}", check, e);
+                (CheckStatus::Failed, Some(e.to_string()), None)
+            }
+        };
+        self.checks.push(CheckOutcome {
+            check,
+            status,
+            reason,
+        });
+        value
+    }
+
+    /// Marks the checks which haven't been performed as skipped.
+    fn finish(mut self) -> Self {
+        for &check in Check::ALL.iter().skip(self.checks.len()) {
+            self.checks.push(CheckOutcome {
+                check,
+                status: CheckStatus::Skipped,
+                reason: None,
+            });
+        }
+        self.valid = self
+            .checks
+            .iter()
+            .all(|outcome| outcome.status == CheckStatus::Passed);
+        self
+    }
+}
+
+/// Checks whether the masked model or the mask of a message can be aggregated wrt the round
+/// parameters.
+fn validate_masks(params: &RoundParameters, message: &Message) -> Result<(), ServiceError> {
+    let object = match message.payload {
+        Payload::Update(ref update) => &update.masked_model,
+        Payload::Sum2(ref sum2) => &sum2.model_mask,
+        _ => return Ok(()),
+    };
+    Aggregation::new(params.mask_config, params.model_length)
+        .validate_aggregation(object)
+        .map_err(ServiceError::InvalidMasks)
+}
+
+/// Runs all checks of a PET message without handing it to the state machine.
+fn validate(
+    keys: &EncryptKeyPair,
+    phase: PhaseName,
+    params: &RoundParameters,
+    enc_data: &[u8],
+) -> Verdict {
+    let mut verdict = Verdict::default();
+    let _ = run_checks(keys, phase, params, enc_data, &mut verdict);
+    verdict.finish()
+}
+
+/// Runs the checks in order until the first one fails.
+fn run_checks(
+    keys: &EncryptKeyPair,
+    phase: PhaseName,
+    params: &RoundParameters,
+    enc_data: &[u8],
+    verdict: &mut Verdict,
+) -> Option<()> {
+    let data = verdict.record(Check::Decryption, decrypt(keys, enc_data))?;
+    let (buffer, tag) = verdict.record(
+        Check::Header,
+        MessageBuffer::new(data)
+            .map_err(ServiceError::Parsing)
+            .and_then(|buffer| decode_tag(&buffer).map(|tag| (buffer, tag))),
+    )?;
+    verdict.record(Check::Phase, check_phase(phase, tag))?;
+    verdict.record(Check::Signature, check_signature(&buffer))?;
+    verdict.record(
+        Check::CoordinatorPublicKey,
+        check_coordinator_pk(&keys.public, &buffer),
+    )?;
+    let message = verdict.record(
+        Check::Payload,
+        parse(&buffer).and_then(|message| {
+            if message.is_multipart {
+                Err(ServiceError::UnsupportedMultipart)
+            } else {
+                Ok(message)
+            }
+        }),
+    )?;
+    verdict.record(Check::Eligibility, validate_task(params, &message))?;
+    verdict.record(Check::Masks, validate_masks(params, &message))
+}
+
+/// A service for validating PET messages without processing them.
+///
+/// The message runs through the same checks as a message which is processed, but it is never
+/// handed to the state machine. Hence, the validation doesn't mutate the round state.
+///
+/// Since decrypting and verifying large messages is CPU-intensive, this service offloads the
+/// validation to a `rayon` thread-pool.
+#[derive(Clone)]
+pub struct MessageValidator {
+    /// A listener to retrieve the latest coordinator keys.
+    keys: EventListener<EncryptKeyPair>,
+    /// A listener to retrieve the current phase.
+    phase: EventListener<PhaseName>,
+    /// A listener to retrieve the latest round parameters.
+    params: EventListener<RoundParameters>,
+    /// Thread-pool the CPU-intensive tasks are offloaded to.
+    thread_pool: MessageThreadPool,
+}
+
+impl MessageValidator {
+    pub fn new(events: &EventSubscriber, thread_pool: MessageThreadPool) -> Self {
+        Self {
+            keys: events.keys_listener(),
+            phase: events.phase_listener(),
+            params: events.params_listener(),
+            thread_pool,
+        }
+    }
+}
+
+impl<T> Service<T> for MessageValidator
+where
+    T: AsRef<[u8]> + Sync + Send + 'static,
+{
+    type Response = Verdict;
+    type Error = ServiceError;
+    type Future = BoxedServiceFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, enc_data: T) -> Self::Future {
+        let keys = self.keys.get_latest().event;
+        let phase = self.phase.get_latest().event;
+        let params = self.params.get_latest().event;
+        trace!("spawning validation task on thread-pool");
+        Box::pin(
+            self.thread_pool
+                .spawn(move || validate(&keys, phase, &params, enc_data.as_ref())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{bigint::BigUint, traits::identities::Zero};
+    use tokio_test::assert_ready;
+    use tower_test::mock::Spawn;
+
+    use super::*;
+    use crate::{
+        services::tests::utils,
+        state_machine::events::{EventPublisher, EventSubscriber},
+    };
+    use xaynet_core::{
+        crypto::{ByteObject, Signature, SigningKeyPair},
+        mask::{MaskObject, MaskUnit, MaskVect},
+        message::MESSAGE_HEADER_LENGTH,
+    };
+
+    fn spawn_svc() -> (EventPublisher, EventSubscriber, Spawn<MessageValidator>) {
+        let (publisher, subscriber) = utils::new_event_channels();
+        let thread_pool = MessageThreadPool::new(None).unwrap();
+        let task = Spawn::new(MessageValidator::new(&subscriber, thread_pool));
+        (publisher, subscriber, task)
+    }
+
+    /// Broadcasts round parameters which select every participant for the sum task or for the
+    /// update task.
+    fn broadcast_round(
+        publisher: &mut EventPublisher,
+        subscriber: &EventSubscriber,
+        phase: PhaseName,
+        sum: f64,
+        update: f64,
+    ) -> RoundParameters {
+        let mut round_params = subscriber.params_listener().get_latest().event;
+        round_params.sum = sum;
+        round_params.update = update;
+        publisher.broadcast_params(round_params.clone());
+        publisher.broadcast_phase(phase);
+        round_params
+    }
+
+    /// Signs and encrypts a message after tampering with its serialized bytes.
+    fn encrypt_tampered<F>(
+        message: &Message,
+        round_params: &RoundParameters,
+        signing_keys: &SigningKeyPair,
+        tamper: F,
+    ) -> Vec<u8>
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        let mut serialized = utils::serialize_message(message, signing_keys);
+        tamper(&mut serialized);
+        round_params.pk.encrypt(&serialized[..])
+    }
+
+    async fn validate(task: &mut Spawn<MessageValidator>, enc_data: Vec<u8>) -> Verdict {
+        assert_ready!(task.poll_ready::<Vec<u8>>()).unwrap();
+        task.call(enc_data).await.unwrap()
+    }
+
+    fn assert_failed_at(verdict: &Verdict, check: Check) {
+        assert!(!verdict.valid);
+        assert_eq!(verdict.first_failure(), Some(check));
+        assert_eq!(verdict.checks.len(), Check::ALL.len());
+        for (outcome, expected) in verdict.checks.iter().zip(Check::ALL.iter()) {
+            assert_eq!(outcome.check, *expected);
+        }
+        let failed_at = Check::ALL.iter().position(|c| *c == check).unwrap();
+        let failure = &verdict.checks[failed_at];
+        assert!(failure.reason.is_some());
+        assert!(verdict.checks[..failed_at]
+            .iter()
+            .all(|outcome| outcome.status == CheckStatus::Passed));
+        assert!(verdict.checks[failed_at + 1..]
+            .iter()
+            .all(|outcome| outcome.status == CheckStatus::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_valid_message() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params = broadcast_round(&mut publisher, &subscriber, PhaseName::Sum, 1.0, 0.0);
+
+        let (message, signing_keys) = utils::new_sum_message(&round_params);
+        let enc_data = utils::encrypt_message(&message, &round_params, &signing_keys);
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert!(verdict.valid);
+        assert_eq!(verdict.first_failure(), None);
+        assert!(verdict
+            .checks
+            .iter()
+            .all(|outcome| outcome.status == CheckStatus::Passed));
+    }
+
+    #[tokio::test]
+    async fn test_externally_signed_message() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params = broadcast_round(&mut publisher, &subscriber, PhaseName::Sum, 1.0, 0.0);
+
+        // simulate a participant which signs outside of the library, e.g. with an HSM
+        let (mut message, signing_keys) = utils::new_sum_message(&round_params);
+        let sign = |data: &[u8]| -> Signature {
+            sodiumoxide::crypto::sign::sign_detached(data, signing_keys.secret.as_ref()).into()
+        };
+        if let Payload::Sum(ref mut sum) = message.payload {
+            sum.sum_signature = sign(&[round_params.seed.as_slice(), b"sum"].concat());
+        }
+        let signature = sign(&message.signing_payload());
+        let enc_data = message.seal_with_signature(&signature, &round_params.pk);
+
+        let mut expected = vec![0; message.buffer_length()];
+        message.to_bytes_with_signature(&mut expected, &signature);
+        assert_eq!(expected, utils::serialize_message(&message, &signing_keys));
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert!(verdict.valid);
+    }
+
+    #[tokio::test]
+    async fn test_undecryptable_message() {
+        let (_publisher, _subscriber, mut task) = spawn_svc();
+
+        let verdict = validate(&mut task, vec![0, 1, 2, 3, 4, 5, 6]).await;
+        assert_failed_at(&verdict, Check::Decryption);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_tag() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params = broadcast_round(&mut publisher, &subscriber, PhaseName::Sum, 1.0, 0.0);
+
+        let (message, signing_keys) = utils::new_sum_message(&round_params);
+        let enc_data = encrypt_tampered(&message, &round_params, &signing_keys, |bytes| {
+            bytes[MESSAGE_HEADER_LENGTH - 4] = 0xff;
+        });
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert_failed_at(&verdict, Check::Header);
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_message() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params =
+            broadcast_round(&mut publisher, &subscriber, PhaseName::Update, 1.0, 0.0);
+
+        let (message, signing_keys) = utils::new_sum_message(&round_params);
+        let enc_data = utils::encrypt_message(&message, &round_params, &signing_keys);
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert_failed_at(&verdict, Check::Phase);
+    }
+
+    #[tokio::test]
+    async fn test_bad_signature() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params = broadcast_round(&mut publisher, &subscriber, PhaseName::Sum, 1.0, 0.0);
+
+        let (message, signing_keys) = utils::new_sum_message(&round_params);
+        let enc_data = encrypt_tampered(&message, &round_params, &signing_keys, |bytes| {
+            bytes[0] ^= 0xff;
+        });
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert_failed_at(&verdict, Check::Signature);
+    }
+
+    #[tokio::test]
+    async fn test_ineligible_participant() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params = broadcast_round(&mut publisher, &subscriber, PhaseName::Sum, 0.0, 0.0);
+
+        let (message, signing_keys) = utils::new_sum_message(&round_params);
+        let enc_data = utils::encrypt_message(&message, &round_params, &signing_keys);
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert_failed_at(&verdict, Check::Eligibility);
+    }
+
+    #[tokio::test]
+    async fn test_oversize_model() {
+        let (mut publisher, subscriber, mut task) = spawn_svc();
+        let round_params =
+            broadcast_round(&mut publisher, &subscriber, PhaseName::Update, 0.0, 1.0);
+
+        let (mut message, signing_keys) = utils::new_update_message(&round_params);
+        if let Payload::Update(ref mut update) = message.payload {
+            let config = round_params.mask_config;
+            let len = round_params.model_length + 1;
+            update.masked_model = MaskObject::new_unchecked(
+                MaskVect::new_unchecked(config.vect, vec![BigUint::zero(); len]),
+                MaskUnit::default(config.unit),
+            );
+        }
+        let enc_data = utils::encrypt_message(&message, &round_params, &signing_keys);
+
+        let verdict = validate(&mut task, enc_data).await;
+        assert_failed_at(&verdict, Check::Masks);
+    }
+}
@@ -7,9 +7,20 @@
 //!   module
 //! - the services for processing PET message are provided by the
 //!   [`messages`] module.
+//!
+//! Additionally, the [`seed_export`] module provides the export of the
+//! seed dictionary for the sum participants which fetch their seeds
+//! out-of-band, the [`health`] module provides the health checks of the
+//! liveness and readiness probes, the [`submissions`] module tells the
+//! participants which of their messages are recorded and the [`webhooks`]
+//! module notifies downstream services about the outcome of every round.
 
 pub mod fetchers;
+pub mod health;
 pub mod messages;
+pub mod seed_export;
+pub mod submissions;
+pub mod webhooks;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
@@ -0,0 +1,256 @@
+//! This module provides the export of the seed dictionary to a [`BlobSink`].
+//!
+//! Once the seed dictionary of a round is frozen, the [`SeedDictExporter`] writes the
+//! [`UpdateSeedDict`] of every sum participant as a separate blob in its canonical binary
+//! encoding. The locations of the blobs are published via [`SeedDictLocations`], such that the
+//! REST API can point the sum participants to their blobs instead of serving their seeds inline.
+//!
+//! [`UpdateSeedDict`]: xaynet_core::UpdateSeedDict
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::{
+    metric,
+    metrics::Measurement,
+    state_machine::events::{
+        DictionaryUpdate,
+        Event,
+        EventListener,
+        EventSubscriber,
+        VersionedSeedDict,
+    },
+    storage::BlobSink,
+};
+use xaynet_core::{crypto::ByteObject, message::ToBytes, SumParticipantPublicKey};
+
+/// The locations of the exported seed dictionary of a round.
+#[derive(Debug)]
+struct ExportedSeedDict {
+    /// The exported seed dictionary.
+    seed_dict: Arc<VersionedSeedDict>,
+    /// The locations of the blobs of the sum participants.
+    locations: HashMap<SumParticipantPublicKey, String>,
+}
+
+/// A handle to the locations of the seed dictionary exported by a [`SeedDictExporter`].
+#[derive(Clone, Debug)]
+pub struct SeedDictLocations(watch::Receiver<Option<Arc<ExportedSeedDict>>>);
+
+impl SeedDictLocations {
+    /// Gets the location of the blob of the sum participant `pk` in the `seed_dict`.
+    ///
+    /// Returns `None` if the `seed_dict` hasn't been exported (yet), e.g. because it belongs to
+    /// another round, or if the export of the blob failed.
+    pub fn get(
+        &self,
+        seed_dict: &Arc<VersionedSeedDict>,
+        pk: &SumParticipantPublicKey,
+    ) -> Option<String> {
+        self.0
+            .borrow()
+            .as_ref()
+            .filter(|exported| Arc::ptr_eq(&exported.seed_dict, seed_dict))
+            .and_then(|exported| exported.locations.get(pk).cloned())
+    }
+}
+
+/// Gets the name of the blob of the sum participant `pk` in the round `round_id`.
+///
+/// The name is `<round_id>/<sum_pk>`, where the public key is URL-safe base64 encoded.
+pub fn blob_name(round_id: u64, pk: &SumParticipantPublicKey) -> String {
+    format!(
+        "{}/{}",
+        round_id,
+        base64::encode_config(pk.as_slice(), base64::URL_SAFE)
+    )
+}
+
+/// Exports every frozen seed dictionary to a [`BlobSink`].
+pub struct SeedDictExporter<S> {
+    sink: S,
+    listener: EventListener<DictionaryUpdate<VersionedSeedDict>>,
+    locations_tx: watch::Sender<Option<Arc<ExportedSeedDict>>>,
+}
+
+impl<S> SeedDictExporter<S>
+where
+    S: BlobSink,
+{
+    /// Creates an exporter of the seed dictionaries broadcasted to the `events` and the handle to
+    /// the locations of the exported blobs.
+    pub fn new(sink: S, events: &EventSubscriber) -> (Self, SeedDictLocations) {
+        let (locations_tx, locations_rx) = watch::channel(None);
+        let exporter = Self {
+            sink,
+            listener: events.seed_dict_listener(),
+            locations_tx,
+        };
+        (exporter, SeedDictLocations(locations_rx))
+    }
+
+    /// Exports the seed dictionaries until the state machine shuts down.
+    pub async fn run(mut self) {
+        loop {
+            let Event { round_id, event } = self.listener.get_latest();
+            let exported = match event {
+                DictionaryUpdate::Invalidate => None,
+                DictionaryUpdate::New(seed_dict) => Some(self.export(round_id, seed_dict).await),
+            };
+            // the locations are only published once all blobs have been written
+            let _ = self.locations_tx.send(exported.map(Arc::new));
+
+            if self.listener.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Writes the blobs of all sum participants of the `seed_dict`.
+    ///
+    /// Blobs which can't be written are skipped, their sum participants are served inline.
+    async fn export(&self, round_id: u64, seed_dict: Arc<VersionedSeedDict>) -> ExportedSeedDict {
+        let start = Instant::now();
+        let mut locations = HashMap::with_capacity(seed_dict.seeds.len());
+        let mut size = 0;
+        for (sum_pk, seeds) in seed_dict.seeds.iter() {
+            let mut data = vec![0; seeds.buffer_length()];
+            seeds.to_bytes(&mut data);
+            size += data.len();
+            match self.sink.put(&blob_name(round_id, sum_pk), data).await {
+                Ok(location) => {
+                    locations.insert(*sum_pk, location);
+                }
+                Err(e) => warn!(
+                    "failed to export the seeds of sum participant {:?}: {}",
+                    sum_pk, e
+                ),
+            }
+        }
+
+        let duration = start.elapsed();
+        info!(
+            "exported {} of {} seed dictionaries ({} bytes) in {:?}",
+            locations.len(),
+            seed_dict.seeds.len(),
+            size,
+            duration
+        );
+        metric!(
+            Measurement::SeedDictExportDuration,
+            duration.as_millis() as u64,
+            ("round_id", round_id),
+        );
+        metric!(
+            Measurement::SeedDictExportSize,
+            size as u64,
+            ("round_id", round_id),
+        );
+
+        ExportedSeedDict {
+            seed_dict,
+            locations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use tokio::fs;
+    use xaynet_core::{
+        crypto::{EncryptKeyPair, SigningKeyPair},
+        mask::MaskSeed,
+        message::FromBytes,
+        LocalSeedDict,
+        SumDict,
+        UpdateSeedDict,
+    };
+
+    use crate::{
+        state_machine::{
+            events::EventPublisher,
+            phases::PhaseName,
+            tests::{CoordinatorStateBuilder, EventBusBuilder},
+        },
+        storage::FsBlobSink,
+    };
+
+    fn seed_dict() -> VersionedSeedDict {
+        let sum_dict = (0..3)
+            .map(|_| {
+                (
+                    SigningKeyPair::generate().public,
+                    EncryptKeyPair::generate().public,
+                )
+            })
+            .collect::<SumDict>();
+        let local_seed_dicts = (0..5)
+            .map(|_| {
+                (
+                    SigningKeyPair::generate().public,
+                    LocalSeedDict::new(&sum_dict, &MaskSeed::generate()),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let seeds = sum_dict
+            .keys()
+            .map(|sum_pk| (*sum_pk, UpdateSeedDict::project(sum_pk, &local_seed_dicts)))
+            .collect();
+        VersionedSeedDict { version: 5, seeds }
+    }
+
+    fn event_bus() -> (EventPublisher, EventSubscriber) {
+        let state = CoordinatorStateBuilder::new().with_round_id(3).build();
+        EventBusBuilder::new(&state)
+            .broadcast_phase(PhaseName::Update)
+            .broadcast_seed_dict(DictionaryUpdate::Invalidate)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_export_seed_dict() {
+        let path = env::temp_dir().join(format!("xaynet_seed_export_{}", std::process::id()));
+        let sink = FsBlobSink::new(&path, "https://seeds.example.com");
+        let (mut publisher, subscriber) = event_bus();
+        let (exporter, locations) = SeedDictExporter::new(sink, &subscriber);
+        let exporter = tokio::spawn(exporter.run());
+
+        let seed_dict = Arc::new(seed_dict());
+        let sum_pk = *seed_dict.seeds.keys().next().unwrap();
+        let mut locations_rx = locations.0.clone();
+        publisher.broadcast_seed_dict(DictionaryUpdate::New(seed_dict.clone()));
+        while locations.get(&seed_dict, &sum_pk).is_none() {
+            locations_rx.changed().await.unwrap();
+        }
+
+        for (sum_pk, seeds) in seed_dict.seeds.iter() {
+            let name = blob_name(3, sum_pk);
+            assert_eq!(
+                locations.get(&seed_dict, sum_pk).unwrap(),
+                format!("https://seeds.example.com/{}", name)
+            );
+            // the exported blob decodes to the seeds which are served inline
+            let data = fs::read(path.join(&name)).await.unwrap();
+            assert_eq!(&UpdateSeedDict::from_byte_slice(&data).unwrap(), seeds);
+        }
+
+        // another seed dictionary with the same entries hasn't been exported
+        let other = Arc::new(seed_dict.as_ref().clone());
+        assert!(locations.get(&other, &sum_pk).is_none());
+
+        publisher.broadcast_seed_dict(DictionaryUpdate::Invalidate);
+        while locations.get(&seed_dict, &sum_pk).is_some() {
+            locations_rx.changed().await.unwrap();
+        }
+
+        drop(publisher);
+        exporter.await.unwrap();
+        fs::remove_dir_all(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+//! This module provides the submission status of the participants.
+//!
+//! A participant which didn't receive the response to one of its messages, e.g. because the
+//! coordinator restarted and restored its state from the storage meanwhile, can't tell whether
+//! the message has been recorded. The [`SubmissionChecker`] looks the participant up in the
+//! dictionaries of the current round, such that the participant can either consider its task done
+//! or resubmit the message.
+
+use tracing::debug;
+
+use crate::{
+    state_machine::events::{EventListener, EventSubscriber},
+    storage::{CoordinatorStorage, StorageResult},
+};
+use xaynet_core::{
+    common::{RoundParameters, RoundSubmissionStatus},
+    ParticipantPublicKey,
+};
+
+/// A handle to check which messages of a participant are recorded in the current round.
+#[derive(Debug, Clone)]
+pub struct SubmissionChecker<C> {
+    store: C,
+    params: EventListener<RoundParameters>,
+}
+
+impl<C> SubmissionChecker<C>
+where
+    C: CoordinatorStorage,
+{
+    /// Creates a new submission checker, which reads the dictionaries from the `store` and the
+    /// round parameters from the `events`.
+    pub fn new(store: C, events: &EventSubscriber) -> Self {
+        Self {
+            store,
+            params: events.params_listener(),
+        }
+    }
+
+    /// Gets the submission status of the participant `pk` in the current round.
+    ///
+    /// # Errors
+    /// Fails if the storage request fails.
+    pub async fn status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<RoundSubmissionStatus> {
+        loop {
+            let seed = self.params.get_latest().event.seed;
+            let status = self.store.submission_status(pk).await?;
+            // the dictionaries are replaced when a new round starts, hence the status only
+            // belongs to the round if the round didn't change meanwhile
+            if self.params.get_latest().event.seed == seed {
+                return Ok(RoundSubmissionStatus { seed, status });
+            }
+            debug!("round changed while checking the submission status, checking again");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use xaynet_core::{common::SubmissionStatus, crypto::SigningKeyPair};
+
+    use crate::{services::tests::utils::new_event_channels, storage::tests::MockCoordinatorStore};
+
+    #[tokio::test]
+    async fn test_submission_status() {
+        let (_publisher, subscriber) = new_event_channels();
+        let sum_pk = SigningKeyPair::generate().public;
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_submission_status()
+            .withf(move |pk| *pk == sum_pk)
+            .return_once(|_| {
+                Ok(SubmissionStatus {
+                    sum: true,
+                    update: false,
+                    sum2: true,
+                    evicted: false,
+                })
+            });
+        let mut checker = SubmissionChecker::new(cs, &subscriber);
+
+        let status = checker.status(&sum_pk).await.unwrap();
+        assert!(status.is_for(&subscriber.params_listener().get_latest().event));
+        assert_eq!(
+            status.status,
+            SubmissionStatus {
+                sum: true,
+                update: false,
+                sum2: true,
+                evicted: false,
+            }
+        );
+    }
+}
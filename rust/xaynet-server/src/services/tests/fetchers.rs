@@ -6,10 +6,15 @@ use tower_test::mock::Spawn;
 use crate::{
     services::{
         fetchers::{
+            ContentEncoding,
+            ModelDeltaRequest,
+            ModelDeltaService,
             ModelRequest,
             ModelService,
             RoundParamsRequest,
             RoundParamsService,
+            RoundSummaryRequest,
+            RoundSummaryService,
             SeedDictRequest,
             SeedDictService,
             SumDictRequest,
@@ -17,12 +22,15 @@ use crate::{
         },
         tests::utils::{mask_config, new_event_channels},
     },
-    state_machine::events::{DictionaryUpdate, ModelUpdate},
+    state_machine::{
+        events::{DictionaryUpdate, ModelUpdate, VersionedSeedDict},
+        phases::PhaseName,
+    },
 };
 use xaynet_core::{
-    common::{RoundParameters, RoundSeed},
-    crypto::{ByteObject, PublicEncryptKey, PublicSigningKey},
-    mask::{EncryptedMaskSeed, Model},
+    common::{GlobalModelMetadata, RoundParameters, RoundSeed, RoundSummary},
+    crypto::{ByteObject, CryptoSuite, PublicEncryptKey, PublicSigningKey},
+    mask::{EncryptedMaskSeed, Model, ModelDelta},
     SeedDict,
     SumDict,
     UpdateSeedDict,
@@ -32,22 +40,55 @@ use xaynet_core::{
 async fn test_model_svc() {
     let (mut publisher, subscriber) = new_event_channels();
 
-    let mut task = Spawn::new(ModelService::new(&subscriber));
+    let mut task = Spawn::new(ModelService::new(&subscriber, 6));
     assert_ready!(task.poll_ready()).unwrap();
 
     let resp = task.call(ModelRequest).await;
-    assert_eq!(resp, Ok(None));
+    assert!(resp.unwrap().is_none());
 
     let model = Arc::new(Model::from(vec![]));
-    publisher.broadcast_model(ModelUpdate::New(model.clone()));
+    let metadata = GlobalModelMetadata::new(1, mask_config().into(), 0, 1);
+    publisher.broadcast_model(ModelUpdate::New(model.clone(), Some(metadata.clone())));
     assert_ready!(task.poll_ready()).unwrap();
-    let resp = task.call(ModelRequest).await;
-    assert_eq!(resp, Ok(Some(model)));
+    let (resp_model, resp_metadata, body) = task.call(ModelRequest).await.unwrap().unwrap();
+    assert_eq!(resp_model, model);
+    assert_eq!(resp_metadata, Some(metadata));
+    assert_eq!(
+        body.encoded(ContentEncoding::Identity),
+        bincode::serialize(model.as_ref()).unwrap()
+    );
 
     publisher.broadcast_model(ModelUpdate::Invalidate);
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(ModelRequest).await;
-    assert_eq!(resp, Ok(None));
+    assert!(resp.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_model_delta_svc() {
+    let (mut publisher, subscriber) = new_event_channels();
+
+    let mut task = Spawn::new(ModelDeltaService::new(&subscriber, 6));
+    assert_ready!(task.poll_ready()).unwrap();
+
+    let resp = task.call(ModelDeltaRequest).await;
+    assert!(resp.unwrap().is_none());
+
+    let base = Model::from(vec![]);
+    let delta = Arc::new(ModelDelta::new(0, &base, 1, &base).unwrap());
+    publisher.broadcast_model_delta(Some(delta.clone()));
+    assert_ready!(task.poll_ready()).unwrap();
+    let (resp_delta, body) = task.call(ModelDeltaRequest).await.unwrap().unwrap();
+    assert_eq!(resp_delta, delta);
+    assert_eq!(
+        body.encoded(ContentEncoding::Identity),
+        bincode::serialize(delta.as_ref()).unwrap()
+    );
+
+    publisher.broadcast_model_delta(None);
+    assert_ready!(task.poll_ready()).unwrap();
+    let resp = task.call(ModelDeltaRequest).await;
+    assert!(resp.unwrap().is_none());
 }
 
 #[tokio::test]
@@ -68,6 +109,12 @@ async fn test_round_params_svc() {
         seed: RoundSeed::fill_with(0x11),
         mask_config: mask_config().into(),
         model_length: 42,
+        next_commitment: None,
+        round_bound_masks: false,
+        eligibility_salt: Vec::new(),
+        allow_sum_eligible_updates: false,
+        timeline: None,
+        crypto_suite: CryptoSuite::SHA256,
     };
     publisher.broadcast_params(params.clone());
     assert_ready!(task.poll_ready()).unwrap();
@@ -75,6 +122,63 @@ async fn test_round_params_svc() {
     assert_eq!(resp, Ok(params));
 }
 
+#[tokio::test]
+async fn test_round_summary_svc() {
+    let (mut publisher, subscriber) = new_event_channels();
+    let initial_params = subscriber.params_listener().get_latest().event;
+
+    let mut task = Spawn::new(RoundSummaryService::new(&subscriber));
+    assert_ready!(task.poll_ready()).unwrap();
+
+    let resp = task.call(RoundSummaryRequest).await;
+    assert_eq!(
+        resp,
+        Ok(RoundSummary {
+            round_id: 0,
+            round_token: initial_params.seed.token(),
+            seed: initial_params.seed,
+            phase: "Idle".to_string(),
+            sum: initial_params.sum,
+            update: initial_params.update,
+            eligibility_salt: initial_params.eligibility_salt,
+            crypto_suite: initial_params.crypto_suite,
+        })
+    );
+
+    let params = RoundParameters {
+        pk: PublicEncryptKey::fill_with(0x11),
+        sum: 0.42,
+        update: 0.24,
+        seed: RoundSeed::fill_with(0x11),
+        mask_config: mask_config().into(),
+        model_length: 42,
+        next_commitment: None,
+        round_bound_masks: false,
+        eligibility_salt: Vec::new(),
+        allow_sum_eligible_updates: false,
+        timeline: None,
+        crypto_suite: CryptoSuite::SHA256,
+    };
+    publisher.set_round_id(1);
+    publisher.broadcast_params(params);
+    publisher.broadcast_phase(PhaseName::Sum);
+    assert_ready!(task.poll_ready()).unwrap();
+    let resp = task.call(RoundSummaryRequest).await;
+    assert_eq!(
+        resp,
+        Ok(RoundSummary {
+            round_id: 1,
+            seed: RoundSeed::fill_with(0x11),
+            round_token: RoundSeed::fill_with(0x11).token(),
+            phase: "Sum".to_string(),
+            sum: 0.42,
+            update: 0.24,
+            eligibility_salt: Vec::new(),
+            crypto_suite: CryptoSuite::SHA256,
+        })
+    );
+}
+
 fn dummy_seed_dict() -> SeedDict {
     let mut dict = HashMap::new();
     dict.insert(PublicSigningKey::fill_with(0xaa), dummy_update_dict());
@@ -83,38 +187,47 @@ fn dummy_seed_dict() -> SeedDict {
 }
 
 fn dummy_update_dict() -> UpdateSeedDict {
-    let mut dict = HashMap::new();
-    dict.insert(
-        PublicSigningKey::fill_with(0x11),
-        EncryptedMaskSeed::fill_with(0x11),
-    );
-    dict.insert(
-        PublicSigningKey::fill_with(0x22),
-        EncryptedMaskSeed::fill_with(0x22),
-    );
-    dict
+    UpdateSeedDict::from_storage(vec![
+        (
+            PublicSigningKey::fill_with(0x11),
+            EncryptedMaskSeed::fill_with(0x11),
+        ),
+        (
+            PublicSigningKey::fill_with(0x22),
+            EncryptedMaskSeed::fill_with(0x22),
+        ),
+    ])
 }
 
 #[tokio::test]
 async fn test_seed_dict_svc() {
     let (mut publisher, subscriber) = new_event_channels();
 
-    let mut task = Spawn::new(SeedDictService::new(&subscriber));
+    let mut task = Spawn::new(SeedDictService::new(&subscriber, 6));
     assert_ready!(task.poll_ready()).unwrap();
 
     let resp = task.call(SeedDictRequest).await;
-    assert_eq!(resp, Ok(None));
+    assert!(resp.unwrap().is_none());
 
-    let seed_dict = Arc::new(dummy_seed_dict());
+    let seed_dict = Arc::new(VersionedSeedDict {
+        version: 1,
+        seeds: dummy_seed_dict(),
+    });
     publisher.broadcast_seed_dict(DictionaryUpdate::New(seed_dict.clone()));
     assert_ready!(task.poll_ready()).unwrap();
-    let resp = task.call(SeedDictRequest).await;
-    assert_eq!(resp, Ok(Some(seed_dict)));
+    let (resp_dict, entries) = task.call(SeedDictRequest).await.unwrap().unwrap();
+    assert_eq!(resp_dict, seed_dict);
+    let pk = PublicSigningKey::fill_with(0xaa);
+    let body = entries.get_or_insert(&pk, &seed_dict.seeds[&pk]);
+    assert_eq!(
+        body.encoded(ContentEncoding::Identity),
+        bincode::serialize(&seed_dict.seeds[&pk]).unwrap()
+    );
 
     publisher.broadcast_seed_dict(DictionaryUpdate::Invalidate);
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(SeedDictRequest).await;
-    assert_eq!(resp, Ok(None));
+    assert!(resp.unwrap().is_none());
 }
 
 fn dummy_sum_dict() -> SumDict {
@@ -134,20 +247,68 @@ fn dummy_sum_dict() -> SumDict {
 async fn test_sum_dict_svc() {
     let (mut publisher, subscriber) = new_event_channels();
 
-    let mut task = Spawn::new(SumDictService::new(&subscriber));
+    let mut task = Spawn::new(SumDictService::new(&subscriber, 6));
     assert_ready!(task.poll_ready()).unwrap();
 
     let resp = task.call(SumDictRequest).await;
-    assert_eq!(resp, Ok(None));
+    assert!(resp.unwrap().is_none());
 
     let sum_dict = Arc::new(dummy_sum_dict());
     publisher.broadcast_sum_dict(DictionaryUpdate::New(sum_dict.clone()));
     assert_ready!(task.poll_ready()).unwrap();
-    let resp = task.call(SumDictRequest).await;
-    assert_eq!(resp, Ok(Some(sum_dict)));
+    let (resp_dict, body, seed) = task.call(SumDictRequest).await.unwrap().unwrap();
+    assert_eq!(resp_dict, sum_dict);
+    assert_eq!(
+        body.encoded(ContentEncoding::Identity),
+        bincode::serialize(sum_dict.as_ref()).unwrap()
+    );
+    assert_eq!(seed, subscriber.params_listener().get_latest().event.seed);
 
     publisher.broadcast_sum_dict(DictionaryUpdate::Invalidate);
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(SumDictRequest).await;
-    assert_eq!(resp, Ok(None));
+    assert!(resp.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_sum_dict_svc_withholds_stale_dict() {
+    let (mut publisher, subscriber) = new_event_channels();
+    let mut task = Spawn::new(SumDictService::new(&subscriber, 6));
+
+    publisher.broadcast_sum_dict(DictionaryUpdate::New(Arc::new(dummy_sum_dict())));
+
+    // the parameters of the next round are published while the dictionary is still valid
+    let mut params = subscriber.params_listener().get_latest().event;
+    params.seed = RoundSeed::generate();
+    publisher.set_round_id(1);
+    publisher.broadcast_params(params);
+    assert_ready!(task.poll_ready()).unwrap();
+    let resp = task.call(SumDictRequest).await;
+    assert!(resp.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_sum_dict_svc_compresses_once_per_dict() {
+    let (mut publisher, subscriber) = new_event_channels();
+    let mut task = Spawn::new(SumDictService::new(&subscriber, 6));
+
+    publisher.broadcast_sum_dict(DictionaryUpdate::New(Arc::new(dummy_sum_dict())));
+    assert_ready!(task.poll_ready()).unwrap();
+    let (_, body, _) = task.call(SumDictRequest).await.unwrap().unwrap();
+    let gzip = body.encoded(ContentEncoding::Gzip);
+
+    // the compressed dictionary is reused by subsequent requests
+    assert_ready!(task.poll_ready()).unwrap();
+    let (_, cached_body, _) = task.call(SumDictRequest).await.unwrap().unwrap();
+    assert!(Arc::ptr_eq(&body, &cached_body));
+    assert_eq!(
+        cached_body.encoded(ContentEncoding::Gzip).as_ptr(),
+        gzip.as_ptr()
+    );
+
+    // a new dictionary is compressed anew
+    publisher.broadcast_sum_dict(DictionaryUpdate::New(Arc::new(dummy_sum_dict())));
+    assert_ready!(task.poll_ready()).unwrap();
+    let (_, new_body, _) = task.call(SumDictRequest).await.unwrap().unwrap();
+    assert!(!Arc::ptr_eq(&body, &new_body));
 }
@@ -1,2 +1,3 @@
 mod fetchers;
+mod participants;
 pub mod utils;
@@ -0,0 +1,211 @@
+//! Tests of the coordinator against the participants of the SDK.
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use displaydoc::Display;
+use thiserror::Error;
+use tokio::time::{sleep, timeout};
+use xaynet_core::{
+    common::{RoundParameters, RoundSubmissionStatus, RoundSumDict},
+    crypto::{CryptoSuite, SigningKeyPair},
+    mask::{FromPrimitives, Model},
+    ParticipantPublicKey,
+    SumParticipantPublicKey,
+    VersionedSeeds,
+};
+use xaynet_sdk::{
+    settings::PetSettings as ParticipantSettings,
+    ModelStore,
+    Notify,
+    StateMachine as Participant,
+    TransitionOutcome,
+    XaynetClient,
+};
+
+use crate::{
+    services::{
+        fetchers::{fetcher, FetchError, Fetcher},
+        messages::{PetMessageHandler, ServiceError},
+    },
+    settings::{PetSettingsCount, PetSettingsTime},
+    state_machine::{
+        events::ModelUpdate,
+        initializer::StateMachineInitializer,
+        tests::utils::{enable_logging, mask_settings, model_settings, pet_settings},
+    },
+    storage::{coordinator_storage::memory::MemoryStorage, model_storage::noop::NoOp, Store},
+};
+
+/// Errors of the in-process client.
+#[derive(Debug, Display, Error)]
+enum ClientError {
+    /// Fetching failed: {0}.
+    Fetch(FetchError),
+    /// Sending a message failed: {0}.
+    Send(ServiceError),
+}
+
+/// A client which talks to the services of the coordinator within the same process, i.e. without
+/// the REST API.
+struct InProcessClient<F> {
+    fetcher: F,
+    message_handler: PetMessageHandler,
+}
+
+#[async_trait]
+impl<F> XaynetClient for InProcessClient<F>
+where
+    F: Fetcher + Send,
+{
+    type Error = ClientError;
+
+    async fn get_round_params(&mut self) -> Result<RoundParameters, Self::Error> {
+        self.fetcher
+            .round_params()
+            .await
+            .map_err(ClientError::Fetch)
+    }
+
+    async fn get_sums(&mut self) -> Result<Option<RoundSumDict>, Self::Error> {
+        let sum_dict = self.fetcher.sum_dict().await.map_err(ClientError::Fetch)?;
+        Ok(sum_dict.map(|(sum_dict, _, seed)| RoundSumDict {
+            seed,
+            sum_dict: sum_dict.as_ref().clone(),
+        }))
+    }
+
+    async fn get_seeds(
+        &mut self,
+        pk: SumParticipantPublicKey,
+    ) -> Result<Option<VersionedSeeds>, Self::Error> {
+        let seed_dict = self.fetcher.seed_dict().await.map_err(ClientError::Fetch)?;
+        Ok(seed_dict.and_then(|(seed_dict, _)| {
+            seed_dict.seeds.get(&pk).map(|seeds| VersionedSeeds {
+                version: seed_dict.version,
+                seeds: seeds.clone(),
+            })
+        }))
+    }
+
+    async fn get_model(&mut self) -> Result<Option<Model>, Self::Error> {
+        let model = self.fetcher.model().await.map_err(ClientError::Fetch)?;
+        Ok(model.map(|(model, _, _)| model.as_ref().clone()))
+    }
+
+    async fn get_submission_status(
+        &mut self,
+        _pk: ParticipantPublicKey,
+    ) -> Result<Option<RoundSubmissionStatus>, Self::Error> {
+        Ok(None)
+    }
+
+    async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Self::Error> {
+        self.message_handler
+            .handle_message(msg)
+            .await
+            .map_err(ClientError::Send)
+    }
+}
+
+struct LocalModel(Arc<Model>);
+
+#[async_trait]
+impl ModelStore for LocalModel {
+    type Model = Arc<Model>;
+    type Error = std::convert::Infallible;
+
+    async fn load_model(&mut self) -> Result<Option<Self::Model>, Self::Error> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+struct Notifier;
+
+impl Notify for Notifier {}
+
+/// Runs a coordinator with the given crypto suite and participants of the SDK until the first
+/// round completes with a new global model.
+async fn run_round(suite: CryptoSuite) {
+    enable_logging();
+    let mut pet_settings = pet_settings();
+    pet_settings.crypto_suite = suite;
+    pet_settings.update.count = PetSettingsCount { min: 1, max: 1000 };
+    pet_settings.sum.time = PetSettingsTime { min: 1, max: 10 };
+    pet_settings.update.time = PetSettingsTime { min: 1, max: 10 };
+    pet_settings.sum2.time = PetSettingsTime { min: 1, max: 10 };
+    let (state_machine, requests_tx, event_subscriber) = StateMachineInitializer::new(
+        pet_settings,
+        mask_settings(),
+        model_settings(),
+        #[cfg(feature = "model-persistence")]
+        crate::settings::RestoreSettings { enable: false },
+        Store::new(MemoryStorage::new(), NoOp),
+    )
+    .init()
+    .await
+    .unwrap();
+    tokio::spawn(state_machine.run());
+
+    let fetcher = fetcher(&event_subscriber, 0);
+    let message_handler = PetMessageHandler::new(&event_subscriber, requests_tx);
+    let model = Arc::new(Model::from_primitives(vec![0.5_f32].into_iter()).unwrap());
+    let participants = (0..20)
+        .map(|_| {
+            let client = InProcessClient {
+                fetcher: fetcher.clone(),
+                message_handler: message_handler.clone(),
+            };
+            let settings = ParticipantSettings::new(SigningKeyPair::generate());
+            let mut participant =
+                Participant::new(settings, client, LocalModel(model.clone()), Notifier);
+            tokio::spawn(async move {
+                loop {
+                    participant = match participant.transition().await {
+                        TransitionOutcome::Pending(participant) => {
+                            sleep(Duration::from_millis(10)).await;
+                            participant
+                        }
+                        TransitionOutcome::Complete(participant) => participant,
+                    };
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // the sum and update participants complete the round of the suite
+    let mut model_listener = event_subscriber.model_listener();
+    let global_model = timeout(Duration::from_secs(30), async {
+        loop {
+            if let ModelUpdate::New(global_model, _) = model_listener.get_latest().event {
+                return global_model;
+            }
+            model_listener.changed().await.unwrap();
+        }
+    })
+    .await
+    .expect("the round didn't complete");
+    assert_eq!(global_model.len(), model.len());
+    assert_eq!(
+        event_subscriber
+            .params_listener()
+            .get_latest()
+            .event
+            .crypto_suite,
+        suite
+    );
+    for participant in participants {
+        participant.abort();
+    }
+}
+
+#[tokio::test]
+async fn test_sha256_round() {
+    run_round(CryptoSuite::SHA256).await;
+}
+
+#[cfg(feature = "blake3")]
+#[tokio::test]
+async fn test_blake3_round() {
+    run_round(CryptoSuite::BLAKE3).await;
+}
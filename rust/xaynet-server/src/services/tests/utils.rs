@@ -4,9 +4,10 @@ use crate::state_machine::{
 };
 use xaynet_core::{
     common::{RoundParameters, RoundSeed},
-    crypto::{ByteObject, EncryptKeyPair, PublicEncryptKey, SigningKeyPair},
-    mask::{self, MaskConfig},
-    message::{Message, Sum},
+    crypto::{ByteObject, CryptoSuite, EncryptKeyPair, PublicEncryptKey, Sha256, SigningKeyPair},
+    mask::{self, MaskConfig, MaskObject, SealedBox, SeedCipher},
+    message::{Message, Sum, Update},
+    LocalSeedDict,
 };
 
 pub fn mask_config() -> MaskConfig {
@@ -30,6 +31,12 @@ pub fn new_event_channels() -> (EventPublisher, EventSubscriber) {
         seed: RoundSeed::generate(),
         mask_config: mask_config().into(),
         model_length: 0,
+        next_commitment: None,
+        round_bound_masks: false,
+        eligibility_salt: Vec::new(),
+        allow_sum_eligible_updates: false,
+        timeline: None,
+        crypto_suite: CryptoSuite::SHA256,
     };
     let phase = PhaseName::Idle;
     let round_id = 0;
@@ -42,16 +49,41 @@ pub fn new_event_channels() -> (EventPublisher, EventSubscriber) {
 /// participants are returned along with the message.
 pub fn new_sum_message(round_params: &RoundParameters) -> (Message, SigningKeyPair) {
     let signing_keys = SigningKeyPair::generate();
+    let payload = round_params
+        .crypto_suite
+        .task_signature_payload(round_params.seed.as_slice(), b"sum");
     let sum = Sum {
-        sum_signature: signing_keys
-            .secret
-            .sign_detached(&[round_params.seed.as_slice(), b"sum"].concat()),
+        sum_signature: signing_keys.secret.sign_detached(&payload),
         ephm_pk: PublicEncryptKey::generate(),
     };
     let message = Message::new_sum(signing_keys.public, round_params.pk, sum);
     (message, signing_keys)
 }
 
+/// Simulate a participant generating keys and crafting a valid update
+/// message with an empty masked model for the given round
+/// parameters. The keys generated by the participants are returned
+/// along with the message.
+pub fn new_update_message(round_params: &RoundParameters) -> (Message, SigningKeyPair) {
+    let signing_keys = SigningKeyPair::generate();
+    let seed = round_params.seed.as_slice();
+    let suite = round_params.crypto_suite;
+    let update = Update {
+        sum_signature: signing_keys
+            .secret
+            .sign_detached(&suite.task_signature_payload(seed, b"sum")),
+        update_signature: signing_keys
+            .secret
+            .sign_detached(&suite.task_signature_payload(seed, b"update")),
+        model_checksum: Sha256::zeroed(),
+        seed_cipher: SealedBox::ID,
+        masked_model: MaskObject::empty(round_params.mask_config, 0),
+        local_seed_dict: LocalSeedDict::default(),
+    };
+    let message = Message::new_update(signing_keys.public, round_params.pk, update);
+    (message, signing_keys)
+}
+
 /// Sign and encrypt the given message using the given round
 /// parameters and particpant keys.
 pub fn encrypt_message(
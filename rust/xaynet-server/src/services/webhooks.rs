@@ -0,0 +1,611 @@
+//! This module provides the webhooks which notify downstream services about the outcome of the
+//! rounds.
+//!
+//! The [`WebhookNotifier`] watches the events of the state machine and queues a [`RoundOutcome`]
+//! for every endpoint once a round completed or failed. Every endpoint has its own queue and
+//! delivery task, hence a slow or unreachable endpoint neither blocks the state machine nor the
+//! other endpoints.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{header::CONTENT_TYPE, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::auth::hmacsha256;
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time::sleep,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    metric,
+    metrics::Measurement,
+    settings::{WebhookEndpointSettings, WebhookSettings},
+    state_machine::{
+        events::{Event, EventListener, EventSubscriber, ModelUpdate},
+        phases::PhaseName,
+    },
+};
+use xaynet_core::{common::RoundParameters, crypto::ByteObject};
+
+/// The header which contains the hex encoded HMAC-SHA256 signature of the body.
+pub const SIGNATURE_HEADER: &str = "X-Xaynet-Signature";
+
+/// The maximal number of notifications which are queued per endpoint.
+const QUEUE_CAPACITY: usize = 16;
+
+/// The time after which an unanswered delivery attempt fails.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximal backoff between two delivery attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// The outcome of a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The round produced a new global model.
+    Completed,
+    /// The round failed.
+    Failed,
+}
+
+/// The notification about the outcome of a round.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundOutcome {
+    /// The id of the round.
+    pub round_id: u64,
+    /// The token of the round, see [`RoundSeed::token()`], if known.
+    ///
+    /// [`RoundSeed::token()`]: xaynet_core::common::RoundSeed::token
+    pub round_token: Option<String>,
+    /// The outcome of the round.
+    pub outcome: Outcome,
+    /// The length of the new global model of a completed round.
+    pub model_length: Option<usize>,
+    /// The hex encoded checksum of the new global model of a completed round, see
+    /// [`Model::checksum()`].
+    ///
+    /// [`Model::checksum()`]: xaynet_core::mask::Model::checksum
+    pub model_hash: Option<String>,
+    /// The URL under which the new global model of a completed round is available, if configured.
+    pub model_url: Option<String>,
+    /// The time at which the outcome was observed, in seconds since the Unix epoch.
+    pub finished_at: u64,
+    /// The time at which the notification was sent, in seconds since the Unix epoch.
+    ///
+    /// The timestamp is part of the signed body and renewed with every delivery attempt, hence
+    /// endpoints can reject replayed notifications which are older than a few minutes.
+    pub sent_at: u64,
+}
+
+/// Gets the current time in seconds since the Unix epoch.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+/// Signs the `body` with the shared `secret`.
+///
+/// The signature is the hex encoded HMAC-SHA256 of the body, see [`SIGNATURE_HEADER`].
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut state = hmacsha256::State::init(secret);
+    state.update(body);
+    hex::encode(state.finalize().0)
+}
+
+/// The result of the delivery of a notification to an endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    /// The endpoint accepted the notification after the given number of attempts.
+    Delivered { attempts: u32 },
+    /// The delivery was given up after the given number of attempts.
+    GaveUp { attempts: u32 },
+}
+
+/// The reasons why a delivery attempt fails.
+enum AttemptError {
+    /// The attempt may succeed if it is retried, e.g. after a server error.
+    Transient(String),
+    /// The endpoint rejected the notification.
+    Permanent(String),
+}
+
+/// A webhook endpoint.
+#[derive(Clone, Debug)]
+pub struct WebhookEndpoint {
+    client: Client,
+    url: String,
+    secret: Vec<u8>,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl WebhookEndpoint {
+    /// Creates an endpoint to which a notification is delivered in at most `max_attempts`. The
+    /// `backoff` before the first retry doubles with each further retry.
+    pub fn new(settings: WebhookEndpointSettings, max_attempts: u32, backoff: Duration) -> Self {
+        let client = Client::builder()
+            .timeout(ATTEMPT_TIMEOUT)
+            .build()
+            .expect("failed to create the webhook client");
+        Self {
+            client,
+            url: settings.url,
+            secret: settings.secret.into_bytes(),
+            max_attempts,
+            backoff,
+        }
+    }
+
+    /// Delivers the `outcome` to the endpoint.
+    ///
+    /// Transport errors, server errors and the statuses `408 Request Timeout` and `429 Too Many
+    /// Requests` are retried, any other status is final.
+    pub async fn deliver(&self, mut outcome: RoundOutcome) -> Delivery {
+        for attempt in 1..=self.max_attempts {
+            match self.attempt(&mut outcome).await {
+                Ok(()) => return Delivery::Delivered { attempts: attempt },
+                Err(AttemptError::Permanent(reason)) => {
+                    warn!("webhook {} rejected the notification: {}", self.url, reason);
+                    return Delivery::GaveUp { attempts: attempt };
+                }
+                Err(AttemptError::Transient(reason)) => {
+                    warn!(
+                        "delivery attempt {} of {} to webhook {} failed: {}",
+                        attempt, self.max_attempts, self.url, reason
+                    );
+                    if attempt < self.max_attempts {
+                        sleep(self.backoff(attempt)).await;
+                    }
+                }
+            }
+        }
+        Delivery::GaveUp {
+            attempts: self.max_attempts,
+        }
+    }
+
+    /// Gets the backoff after the given failed attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff
+            .checked_mul(2_u32.saturating_pow(attempt - 1))
+            .map_or(MAX_BACKOFF, |backoff| backoff.min(MAX_BACKOFF))
+    }
+
+    /// Posts the signed `outcome` once.
+    async fn attempt(&self, outcome: &mut RoundOutcome) -> Result<(), AttemptError> {
+        outcome.sent_at = unix_time();
+        let body = serde_json::to_vec(outcome).expect("failed to serialize the round outcome");
+        let response = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign(&self.secret, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| AttemptError::Transient(err.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else if status.is_server_error()
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS
+        {
+            Err(AttemptError::Transient(status.to_string()))
+        } else {
+            Err(AttemptError::Permanent(status.to_string()))
+        }
+    }
+}
+
+/// Delivers the queued notifications to the `endpoint` until the queue is closed.
+async fn deliver_queued(endpoint: WebhookEndpoint, mut queue: mpsc::Receiver<RoundOutcome>) {
+    while let Some(outcome) = queue.recv().await {
+        let round_id = outcome.round_id;
+        match endpoint.deliver(outcome).await {
+            Delivery::Delivered { attempts } => {
+                debug!(
+                    "delivered the outcome of round {} to webhook {}",
+                    round_id, endpoint.url
+                );
+                metric!(
+                    Measurement::WebhookDelivered,
+                    u64::from(attempts),
+                    ("round_id", round_id),
+                );
+            }
+            Delivery::GaveUp { attempts } => {
+                warn!(
+                    "gave up the delivery of the outcome of round {} to webhook {}",
+                    round_id, endpoint.url
+                );
+                metric!(
+                    Measurement::WebhookGaveUp,
+                    u64::from(attempts),
+                    ("round_id", round_id),
+                );
+            }
+        }
+    }
+}
+
+/// Notifies the webhook endpoints about the outcome of every round.
+pub struct WebhookNotifier {
+    endpoints: Vec<WebhookEndpoint>,
+    on_failure: bool,
+    model_url: Option<String>,
+    params: EventListener<RoundParameters>,
+    phase: EventListener<PhaseName>,
+    model: EventListener<ModelUpdate>,
+    /// The tokens of the current and the previous round.
+    tokens: HashMap<u64, String>,
+    /// The last round which completed.
+    completed: Option<u64>,
+    /// The last round which failed.
+    failed: Option<u64>,
+}
+
+impl WebhookNotifier {
+    /// Creates a notifier of the endpoints about the rounds broadcasted to the `events`.
+    pub fn new(settings: WebhookSettings, events: &EventSubscriber) -> Self {
+        let WebhookSettings {
+            endpoints,
+            on_failure,
+            max_attempts,
+            backoff,
+            model_url,
+        } = settings;
+        let backoff = Duration::from_millis(backoff);
+        let endpoints = endpoints
+            .into_iter()
+            .map(|endpoint| WebhookEndpoint::new(endpoint, max_attempts, backoff))
+            .collect();
+
+        // the outcomes which have been broadcasted before are not notified
+        let model = events.model_listener();
+        let completed = match model.get_latest() {
+            Event {
+                round_id,
+                event: ModelUpdate::New(_, Some(_)),
+            } => Some(round_id),
+            _ => None,
+        };
+        let phase = events.phase_listener();
+        let failed = match phase.get_latest() {
+            Event {
+                round_id,
+                event: PhaseName::Failure,
+            } => Some(round_id),
+            _ => None,
+        };
+
+        Self {
+            endpoints,
+            on_failure,
+            model_url,
+            params: events.params_listener(),
+            phase,
+            model,
+            tokens: HashMap::new(),
+            completed,
+            failed,
+        }
+    }
+
+    /// Notifies the endpoints until the state machine shuts down.
+    pub async fn run(mut self) {
+        let queues = self
+            .endpoints
+            .drain(..)
+            .map(|endpoint| {
+                let (queue_tx, queue_rx) = mpsc::channel(QUEUE_CAPACITY);
+                tokio::spawn(deliver_queued(endpoint, queue_rx));
+                queue_tx
+            })
+            .collect::<Vec<_>>();
+
+        loop {
+            self.record_token();
+            for outcome in self
+                .completed_round()
+                .into_iter()
+                .chain(self.failed_round())
+            {
+                info!(
+                    "notifying {} webhooks about the outcome of round {}",
+                    queues.len(),
+                    outcome.round_id
+                );
+                for queue in queues.iter() {
+                    if let Err(TrySendError::Full(_)) = queue.try_send(outcome.clone()) {
+                        warn!(
+                            "webhook queue is full, dropping the outcome of round {}",
+                            outcome.round_id
+                        );
+                    }
+                }
+            }
+
+            let changed = tokio::select! {
+                changed = self.params.changed() => changed,
+                changed = self.phase.changed() => changed,
+                changed = self.model.changed() => changed,
+            };
+            if changed.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Records the token of the current round.
+    fn record_token(&mut self) {
+        let Event { round_id, event } = self.params.get_latest();
+        self.tokens.insert(round_id, event.seed.token());
+        self.tokens.retain(|id, _| id + 1 >= round_id);
+    }
+
+    /// Gets the outcome of a newly completed round.
+    fn completed_round(&mut self) -> Option<RoundOutcome> {
+        let Event { round_id, event } = self.model.get_latest();
+        match event {
+            ModelUpdate::New(model, Some(_)) if self.completed != Some(round_id) => {
+                self.completed = Some(round_id);
+                Some(RoundOutcome {
+                    round_id,
+                    round_token: self.tokens.get(&round_id).cloned(),
+                    outcome: Outcome::Completed,
+                    model_length: Some(model.len()),
+                    model_hash: Some(hex::encode(model.checksum().as_slice())),
+                    model_url: self.model_url.clone(),
+                    finished_at: unix_time(),
+                    sent_at: 0,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the outcome of a newly failed round, if failures are notified.
+    fn failed_round(&mut self) -> Option<RoundOutcome> {
+        let Event { round_id, event } = self.phase.get_latest();
+        if event != PhaseName::Failure || self.failed == Some(round_id) {
+            return None;
+        }
+        self.failed = Some(round_id);
+        if !self.on_failure {
+            return None;
+        }
+        Some(RoundOutcome {
+            round_id,
+            round_token: self.tokens.get(&round_id).cloned(),
+            outcome: Outcome::Failed,
+            model_length: None,
+            model_hash: None,
+            model_url: None,
+            finished_at: unix_time(),
+            sent_at: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use tokio::time::timeout;
+    use warp::Filter;
+    use xaynet_core::{
+        common::GlobalModelMetadata,
+        mask::{FromPrimitives, MaskConfig, Model},
+    };
+
+    use crate::state_machine::tests::{
+        utils::mask_settings,
+        CoordinatorStateBuilder,
+        EventBusBuilder,
+    };
+
+    /// The signatures and bodies of the captured notifications.
+    type Captured = Arc<Mutex<Vec<(String, Bytes)>>>;
+
+    /// Serves a webhook which answers with the `statuses` in turn, repeating the last one.
+    fn serve_webhook(statuses: Vec<u16>) -> (String, Captured) {
+        let captured = Captured::default();
+        let hook = {
+            let captured = captured.clone();
+            warp::post()
+                .and(warp::path!("hook"))
+                .and(warp::header::<String>(SIGNATURE_HEADER))
+                .and(warp::body::bytes())
+                .map(move |signature: String, body: Bytes| {
+                    let mut captured = captured.lock().unwrap();
+                    captured.push((signature, body));
+                    let status = statuses[(captured.len() - 1).min(statuses.len() - 1)];
+                    warp::reply::with_status(warp::reply(), StatusCode::from_u16(status).unwrap())
+                })
+        };
+        let (addr, server) = warp::serve(hook).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (format!("http://{}/hook", addr), captured)
+    }
+
+    /// The secret shared with the webhook, which is a valid key of the plain HMAC-SHA256.
+    fn secret() -> String {
+        "s".repeat(hmacsha256::KEYBYTES)
+    }
+
+    fn key() -> hmacsha256::Key {
+        hmacsha256::Key::from_slice(secret().as_bytes()).unwrap()
+    }
+
+    fn endpoint(url: String, max_attempts: u32) -> WebhookEndpoint {
+        let settings = WebhookEndpointSettings {
+            url,
+            secret: secret(),
+        };
+        WebhookEndpoint::new(settings, max_attempts, Duration::from_millis(1))
+    }
+
+    fn outcome() -> RoundOutcome {
+        RoundOutcome {
+            round_id: 3,
+            round_token: Some("0123456789abcdef".to_string()),
+            outcome: Outcome::Completed,
+            model_length: Some(4),
+            model_hash: Some("00".repeat(32)),
+            model_url: Some("https://coordinator.example.com/model".to_string()),
+            finished_at: unix_time(),
+            sent_at: 0,
+        }
+    }
+
+    /// Verifies the signature of a captured notification and decodes it.
+    fn verify(signature: &str, body: &[u8]) -> RoundOutcome {
+        let tag = hmacsha256::Tag::from_slice(&hex::decode(signature).unwrap()).unwrap();
+        assert!(hmacsha256::verify(&tag, body, &key()));
+        serde_json::from_slice(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_deliver_signed_notification() {
+        let (url, captured) = serve_webhook(vec![200]);
+        let outcome = outcome();
+        let delivery = endpoint(url, 3).deliver(outcome.clone()).await;
+        assert_eq!(delivery, Delivery::Delivered { attempts: 1 });
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.len(), 1);
+        let (signature, body) = &captured[0];
+        let delivered = verify(signature, body);
+        assert!(delivered.sent_at >= delivered.finished_at);
+        assert_eq!(
+            delivered,
+            RoundOutcome {
+                sent_at: delivered.sent_at,
+                ..outcome
+            }
+        );
+
+        // a tampered body doesn't match the signature
+        let tag = hmacsha256::Tag::from_slice(&hex::decode(signature).unwrap()).unwrap();
+        let tampered = body.iter().rev().copied().collect::<Vec<_>>();
+        assert!(!hmacsha256::verify(&tag, &tampered, &key()));
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_server_error() {
+        let (url, captured) = serve_webhook(vec![500, 503, 200]);
+        let delivery = endpoint(url, 5).deliver(outcome()).await;
+        assert_eq!(delivery, Delivery::Delivered { attempts: 3 });
+
+        // every attempt is signed
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.len(), 3);
+        for (signature, body) in captured.iter() {
+            assert_eq!(verify(signature, body).round_id, 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_give_up() {
+        // the endpoint keeps failing
+        let (url, captured) = serve_webhook(vec![500]);
+        let delivery = endpoint(url, 3).deliver(outcome()).await;
+        assert_eq!(delivery, Delivery::GaveUp { attempts: 3 });
+        assert_eq!(captured.lock().unwrap().len(), 3);
+
+        // the endpoint rejects the notification
+        let (url, captured) = serve_webhook(vec![400, 200]);
+        let delivery = endpoint(url, 3).deliver(outcome()).await;
+        assert_eq!(delivery, Delivery::GaveUp { attempts: 1 });
+        assert_eq!(captured.lock().unwrap().len(), 1);
+
+        // the endpoint is unreachable
+        let delivery = endpoint("http://127.0.0.1:1/hook".to_string(), 2)
+            .deliver(outcome())
+            .await;
+        assert_eq!(delivery, Delivery::GaveUp { attempts: 2 });
+    }
+
+    #[test]
+    fn test_backoff() {
+        let endpoint = WebhookEndpoint::new(
+            WebhookEndpointSettings {
+                url: "https://ml.example.com/hook".to_string(),
+                secret: "secret".to_string(),
+            },
+            100,
+            Duration::from_secs(1),
+        );
+        assert_eq!(endpoint.backoff(1), Duration::from_secs(1));
+        assert_eq!(endpoint.backoff(3), Duration::from_secs(4));
+        assert_eq!(endpoint.backoff(99), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_notify_round_outcomes() {
+        let (url, captured) = serve_webhook(vec![200]);
+        let state = CoordinatorStateBuilder::new().with_round_id(3).build();
+        let (mut publisher, subscriber) = EventBusBuilder::new(&state).build();
+        let settings = WebhookSettings {
+            endpoints: vec![WebhookEndpointSettings {
+                url,
+                secret: secret(),
+            }],
+            on_failure: true,
+            max_attempts: 1,
+            backoff: 1,
+            model_url: None,
+        };
+        let notifier = tokio::spawn(WebhookNotifier::new(settings, &subscriber).run());
+
+        let model = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        let metadata = GlobalModelMetadata::new(3, MaskConfig::from(mask_settings()).into(), 3, 1);
+        publisher.broadcast_model(ModelUpdate::New(Arc::new(model.clone()), Some(metadata)));
+        publisher.set_round_id(4);
+        publisher.broadcast_phase(PhaseName::Failure);
+
+        timeout(Duration::from_secs(5), async {
+            while captured.lock().unwrap().len() < 2 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let outcomes = captured
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(signature, body)| verify(signature, body))
+            .collect::<Vec<_>>();
+        assert_eq!(outcomes[0].round_id, 3);
+        assert_eq!(outcomes[0].outcome, Outcome::Completed);
+        assert_eq!(
+            outcomes[0].round_token,
+            Some(state.round_params.seed.token())
+        );
+        assert_eq!(outcomes[0].model_length, Some(3));
+        assert_eq!(
+            outcomes[0].model_hash,
+            Some(hex::encode(model.checksum().as_slice()))
+        );
+        assert_eq!(outcomes[1].round_id, 4);
+        assert_eq!(outcomes[1].outcome, Outcome::Failed);
+        assert!(outcomes[1].model_hash.is_none());
+
+        drop(publisher);
+        notifier.await.unwrap();
+    }
+}
@@ -3,9 +3,10 @@
 //! Values defined in the configuration file can be overridden by environment variables. Examples of
 //! configuration files can be found in the `configs/` directory located in the repository root.
 
-#[cfg(feature = "tls")]
-use std::path::PathBuf;
-use std::{fmt, path::Path};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 use config::{Config, ConfigError, Environment, File};
 use displaydoc::Display;
@@ -13,14 +14,18 @@ use redis::{ConnectionInfo, IntoConnectionInfo};
 use serde::{
     de::{self, Deserializer, Visitor},
     Deserialize,
+    Serialize,
 };
 use thiserror::Error;
 use tracing_subscriber::filter::EnvFilter;
 use validator::{Validate, ValidationError, ValidationErrors};
 
 use xaynet_core::{
+    common::{from_basis_points, to_basis_points, BASIS_POINTS},
+    crypto::{ByteObject, CryptoSuite},
     mask::{BoundType, DataType, GroupType, MaskConfig, ModelType},
     message::{SUM_COUNT_MIN, UPDATE_COUNT_MIN},
+    ParticipantPublicKey,
 };
 
 #[cfg(feature = "model-persistence")]
@@ -51,6 +56,7 @@ pub struct Settings {
     pub model: ModelSettings,
     #[validate]
     pub metrics: MetricsSettings,
+    #[validate]
     pub redis: RedisSettings,
     #[cfg(feature = "model-persistence")]
     #[validate]
@@ -60,6 +66,20 @@ pub struct Settings {
     pub restore: RestoreSettings,
     #[serde(default)]
     pub trust_anchor: TrustAnchorSettings,
+    #[serde(default)]
+    #[validate]
+    pub denylist: DenylistSettings,
+    #[serde(default)]
+    #[validate]
+    pub circuit_breaker: CircuitBreakerSettings,
+    #[serde(default)]
+    #[validate]
+    pub leader: Option<LeaderSettings>,
+    #[serde(default)]
+    pub seed_export: Option<SeedExportSettings>,
+    #[serde(default)]
+    #[validate]
+    pub webhooks: Option<WebhookSettings>,
 }
 
 impl Settings {
@@ -102,6 +122,9 @@ pub struct PetSettingsTime {
     pub max: u64,
 }
 
+/// The minimal number of distinct sum participants if multiparty sums are required.
+pub const MULTIPARTY_SUM_COUNT_MIN: u64 = 2;
+
 /// The PET protocol `sum` phase settings.
 #[derive(Debug, Deserialize, Clone, Copy)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -109,18 +132,25 @@ pub struct PetSettingsSum {
     /// The probability of participants selected for preparing and computing the aggregated mask.
     /// The value must be between `0` and `1` (i.e. `0 < sum.prob < 1`).
     ///
+    /// The value can also be given in basis points, i.e. in multiples of `0.0001`, with the `bp`
+    /// suffix. This avoids any ambiguity in the floating point representation of the value.
+    ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
     /// [pet.sum]
     /// prob = 0.01
+    /// # or
+    /// prob = "100bp"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
     /// XAYNET__PET__SUM__PROB=0.01
+    /// XAYNET__PET__SUM__PROB=100bp
     /// ```
+    #[serde(deserialize_with = "deserialize_fraction")]
     pub prob: f64,
 
     /// The minimal and maximal number of participants selected for preparing the unmasking.
@@ -169,6 +199,77 @@ pub struct PetSettingsSum {
     /// XAYNET__PET__SUM__TIME__MAX=3600
     /// ```
     pub time: PetSettingsTime,
+
+    /// The maximal number of sum participants kept in the sum dictionary. Unbounded by default.
+    ///
+    /// If a new sum participant exceeds the capacity, the oldest sum participant is evicted from
+    /// the sum dictionary together with its seed dictionary entry. This caps the memory of the
+    /// coordinator under adversarial conditions, e.g. if stale entries accumulate over several
+    /// rounds. The capacity must be greater or equal to the minimal number of sum participants
+    /// (i.e. `sum.count.min <= sum.dict_capacity`).
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum]
+    /// dict_capacity = 1000
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM__DICT_CAPACITY=1000
+    /// ```
+    #[serde(default)]
+    pub dict_capacity: Option<u64>,
+
+    /// Whether the sum phase requires at least two distinct sum participants. Defaults to
+    /// `false`.
+    ///
+    /// A single sum participant sees all the mask seeds of a round and can therefore unmask the
+    /// individual models of the update participants. If enabled, the sum phase doesn't end before
+    /// at least two distinct sum participants are present, even if `sum.count.min` (or the warm-up
+    /// count) is `1`. The maximal number of sum participants and the capacity of the sum
+    /// dictionary must allow for two sum participants then.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum]
+    /// require_multiparty = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM__REQUIRE_MULTIPARTY=true
+    /// ```
+    #[serde(default)]
+    pub require_multiparty: bool,
+
+    /// Whether the sum participants of a round must present distinct client certificate
+    /// identities. Defaults to `false`.
+    ///
+    /// Several sum participants run by one enrolled device undermine the privacy of the update
+    /// participants. If enabled, a sum message is rejected if another sum participant with the
+    /// same client certificate identity is already present in the round, and also if the identity
+    /// is unknown. The identity is taken from the `api.client_identity_header`, which must be set
+    /// by a TLS terminating proxy.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum]
+    /// distinct_identities = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM__DISTINCT_IDENTITIES=true
+    /// ```
+    #[serde(default)]
+    pub distinct_identities: bool,
 }
 
 /// The PET protocol `update` phase settings.
@@ -180,18 +281,25 @@ pub struct PetSettingsUpdate {
     /// is included to be able to express that every participant who is not a sum participant must be
     /// an update participant.
     ///
+    /// The value can also be given in basis points, i.e. in multiples of `0.0001`, with the `bp`
+    /// suffix. This avoids any ambiguity in the floating point representation of the value.
+    ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
     /// [pet.update]
     /// prob = 0.1
+    /// # or
+    /// prob = "1000bp"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
     /// XAYNET__PET__UPDATE__PROB=0.1
+    /// XAYNET__PET__UPDATE__PROB=1000bp
     /// ```
+    #[serde(deserialize_with = "deserialize_fraction")]
     pub prob: f64,
 
     /// The minimal and maximal number of participants selected for submitting an updated local
@@ -243,267 +351,1411 @@ pub struct PetSettingsUpdate {
     /// XAYNET__PET__UPDATE__TIME__MAX=10
     /// ```
     pub time: PetSettingsTime,
-}
 
-/// The PET protocol `sum2` phase settings.
-#[derive(Debug, Deserialize, Clone, Copy)]
-#[cfg_attr(test, derive(PartialEq))]
-pub struct PetSettingsSum2 {
-    /// The minimal and maximal number of participants selected for submitting the aggregated masks.
+    /// The maximal memory in bytes which the aggregation of the masked models may occupy, if
+    /// limited.
     ///
-    /// The minimal value must be greater or equal to `1` (i.e. `sum2.count.min >= 1`) for the PET
-    /// protocol to function correctly and less or equal to the maximal value of the `sum` phase
-    /// (i.e. `sum2.count.sum <= sum.count.max`). The maximal value must be greater or equal to the
-    /// minimal value (i.e. `sum2.count.min <= sum2.count.max`) and less or equal to the maximal
-    /// value of the `sum` phase (i.e. `sum2.count.max <= sum.count.max`). No more than
-    /// `sum2.count.max` messages will be processed in the `sum2` phase if the `sum2.time.min` has
-    /// not yet elapsed.
+    /// The memory of the aggregation is predictable from the model length and the masking
+    /// configuration, including the copy of the aggregated masked models which checkpoints and the
+    /// unmasking materialize. A round whose aggregation would exceed the limit is refused to start
+    /// with an error instead of running out of memory in the middle of the round.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [pet.sum2.count]
-    /// min = 10
-    /// max = 100
+    /// [pet.update]
+    /// aggregation_memory_limit = 1073741824
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__PET__SUM2__COUNT__MIN=10
-    /// XAYNET__PET__SUM2__COUNT__MAX=100
+    /// XAYNET__PET__UPDATE__AGGREGATION_MEMORY_LIMIT=1073741824
     /// ```
-    pub count: PetSettingsCount,
+    #[serde(default)]
+    pub aggregation_memory_limit: Option<u64>,
 
-    /// The minimal and maximal amount of time reserved for processing messages in the `sum2` phase,
-    /// in seconds.
+    /// The policy for a mismatch between the number of update participants in the seed
+    /// dictionary and the number of aggregated masked models at the end of the `update` phase.
+    /// Defaults to `warn`.
     ///
-    /// Once the minimal time has passed, the `sum2` phase ends *as soon as* `sum2.count.min`
-    /// messages have been processed. Set this higher to allow for the possibility of more than
-    /// `sum2.count.min` messages to be processed in the `sum2` phase. Set the maximal time lower to
-    /// allow for the processing of `sum2.count.min` messages to time-out sooner in the `sum2`
-    /// phase.
+    /// Both numbers must agree, otherwise the sum participants can't derive a consistent mask and
+    /// the round likely fails later on with ambiguous masks. With `fail` the round fails right
+    /// after the `update` phase, with `warn` the mismatch is only logged and recorded in the round
+    /// stats.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [pet.sum2.time]
-    /// min = 5
-    /// max = 3600
+    /// [pet.update]
+    /// on_seed_dict_mismatch = "fail"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__PET__SUM2__TIME__MIN=5
-    /// XAYNET__PET__SUM2__TIME__MAX=3600
+    /// XAYNET__PET__UPDATE__ON_SEED_DICT_MISMATCH=fail
     /// ```
-    pub time: PetSettingsTime,
-}
-
-/// The PET protocol settings.
-#[derive(Debug, Validate, Deserialize, Clone, Copy)]
-#[cfg_attr(test, derive(PartialEq))]
-#[validate(schema(function = "validate_pet"))]
-pub struct PetSettings {
-    /// The PET settings for the `sum` phase.
-    pub sum: PetSettingsSum,
-    /// The PET settings for the `update` phase.
-    pub update: PetSettingsUpdate,
-    /// The PET settings for the `sum2` phase.
-    pub sum2: PetSettingsSum2,
-}
-
-impl PetSettings {
-    /// Checks the PET settings.
-    fn validate_pet(&self) -> Result<(), ValidationError> {
-        self.validate_counts()?;
-        self.validate_times()?;
-        self.validate_probabilities()
-    }
-
-    /// Checks the validity of phase count ranges.
-    fn validate_counts(&self) -> Result<(), ValidationError> {
-        // the validate attribute only accepts literals, therefore we check the invariants here
-        if SUM_COUNT_MIN <= self.sum.count.min
-            && self.sum.count.min <= self.sum.count.max
-            && UPDATE_COUNT_MIN <= self.update.count.min
-            && self.update.count.min <= self.update.count.max
-            && SUM_COUNT_MIN <= self.sum2.count.min
-            && self.sum2.count.min <= self.sum2.count.max
-            && self.sum2.count.min <= self.sum.count.max
-            && self.sum2.count.max <= self.sum.count.max
-        {
-            Ok(())
-        } else {
-            Err(ValidationError::new("invalid phase count range(s)"))
-        }
-    }
-
-    /// Checks the validity of phase time ranges.
-    fn validate_times(&self) -> Result<(), ValidationError> {
-        if self.sum.time.min <= self.sum.time.max
-            && self.update.time.min <= self.update.time.max
-            && self.sum2.time.min <= self.sum2.time.max
-        {
-            Ok(())
-        } else {
-            Err(ValidationError::new("invalid phase time range(s)"))
-        }
-    }
-
-    /// Checks the validity of fraction ranges including pathological cases of deadlocks.
-    fn validate_probabilities(&self) -> Result<(), ValidationError> {
-        if 0. < self.sum.prob
-            && self.sum.prob < 1.
-            && 0. < self.update.prob
-            && self.update.prob <= 1.
-            && 0. < self.sum.prob + self.update.prob - self.sum.prob * self.update.prob
-            && self.sum.prob + self.update.prob - self.sum.prob * self.update.prob <= 1.
-        {
-            Ok(())
-        } else {
-            Err(ValidationError::new("starvation"))
-        }
-    }
-}
-
-/// A wrapper for validate derive.
-fn validate_pet(s: &PetSettings) -> Result<(), ValidationError> {
-    s.validate_pet()
-}
+    #[serde(default)]
+    pub on_seed_dict_mismatch: SeedDictMismatchPolicy,
 
-#[derive(Debug, Deserialize, Clone)]
-#[cfg_attr(
-    feature = "tls",
-    derive(Validate),
-    validate(schema(function = "validate_api"))
-)]
-/// REST API settings.
-///
-/// Requires at least one of the following arguments if the `tls` feature is enabled:
-/// - `tls_certificate` together with `tls_key` for TLS server authentication
-// - `tls_client_auth` for TLS client authentication
-pub struct ApiSettings {
-    /// The address to which the REST API should be bound.
+    /// The aggregation of the masked models into the global model. Defaults to `mean`.
+    ///
+    /// With `trimmed_mean`, the `update.trim` lowest and highest weights of each coordinate are
+    /// removed before the remaining weights are averaged, which bounds the influence of outlier
+    /// models on the global model. The trimming compares the weights of the individual models,
+    /// hence it is only feasible if the coordinator runs in trusted mode, where it unmasks each
+    /// masked model on its own. The masked models are kept in memory until the end of the
+    /// `update` phase for that, and they can't be restored from checkpoints, which therefore
+    /// must be disabled. The minimal number of update participants, also during the warm-up
+    /// rounds, must be greater than `2 * update.trim`.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [api]
-    /// bind_address = "0.0.0.0:8081"
-    /// # or
-    /// bind_address = "127.0.0.1:8081"
+    /// [pet.update]
+    /// aggregation = "trimmed_mean"
+    /// trim = 1
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__API__BIND_ADDRESS=127.0.0.1:8081
+    /// XAYNET__PET__UPDATE__AGGREGATION=trimmed_mean
+    /// XAYNET__PET__UPDATE__TRIM=1
     /// ```
-    pub bind_address: std::net::SocketAddr,
+    #[serde(default)]
+    pub aggregation: AggregationStrategy,
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
-    /// The path to the server certificate to enable TLS server authentication. Leave this out to
-    /// disable server authentication. If this is present, then `tls_key` must also be present.
+    /// The number of weights which are trimmed from each end of a coordinate, if the aggregation
+    /// is the `trimmed_mean`. Defaults to `0`. See `update.aggregation` for the examples.
+    #[serde(default)]
+    pub trim: u64,
+
+    /// Whether a participant which is eligible for both the `sum` and the `update` task may
+    /// choose to submit an update message instead of a sum message. Defaults to `false`, i.e. the
+    /// sum task takes precedence and the update messages of sum-eligible participants are
+    /// rejected.
     ///
-    /// Requires the `tls` feature to be enabled.
+    /// The flag is advertised in the round parameters. A participant which submitted a sum
+    /// message still can't submit an update message in the same round, hence every participant
+    /// takes on at most one task.
+    ///
+    /// Privacy: the separation of the tasks no longer follows from the eligibility alone, but
+    /// from the choice of the participant. An update message of a sum-eligible participant
+    /// reveals that it deliberately didn't sum, and the participants which update grow from the
+    /// fraction `(1 - sum.prob) * update.prob` to `update.prob`. The more sum-eligible
+    /// participants choose to update, the fewer sum participants are left to share the unmasking,
+    /// so `sum.count.min` should be chosen with that in mind.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [api]
-    /// tls_certificate = path/to/tls/files/cert.pem
+    /// [pet.update]
+    /// allow_sum_eligible_updates = true
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__API__TLS_CERTIFICATE=path/to/tls/files/certificate.pem
+    /// XAYNET__PET__UPDATE__ALLOW_SUM_ELIGIBLE_UPDATES=true
     /// ```
-    pub tls_certificate: Option<PathBuf>,
+    #[serde(default)]
+    pub allow_sum_eligible_updates: bool,
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
-    /// The path to the server private key to enable TLS server authentication. Leave this out to
-    /// disable server authentication. If this is present, then `tls_certificate` must also be
-    /// present.
+    /// The checkpoints of the aggregation of the masked models. Disabled by default.
     ///
-    /// Requires the `tls` feature to be enabled.
+    /// The aggregation is only kept in memory, hence it is lost if the coordinator restarts in
+    /// the middle of the `update` phase, although the seed dictionary is persisted. With
+    /// checkpoints, a restored coordinator rolls the seed dictionary back to the update
+    /// participants of the latest checkpoint and resumes the `update` phase of the same round,
+    /// where the rolled back participants may submit their update messages again. Otherwise the
+    /// round is started over. Restoring requires the `model-persistence` feature. See
+    /// [`PetSettingsUpdateCheckpoint`] for the individual settings.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [api]
-    /// tls_key = path/to/tls/files/key.rsa
+    /// [pet.update.checkpoint]
+    /// updates = 100
+    /// interval = 60
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__API__TLS_KEY=path/to/tls/files/key.rsa
+    /// XAYNET__PET__UPDATE__CHECKPOINT__UPDATES=100
+    /// XAYNET__PET__UPDATE__CHECKPOINT__INTERVAL=60
     /// ```
-    pub tls_key: Option<PathBuf>,
+    #[serde(default)]
+    pub checkpoint: Option<PetSettingsUpdateCheckpoint>,
+}
 
-    #[cfg(feature = "tls")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
-    /// The path to the trust anchor to enable TLS client authentication. Leave this out to disable
-    /// client authentication.
+/// The PET protocol `update` phase checkpoint settings.
+///
+/// A checkpoint is taken after an update message has been accepted, as soon as any of the set
+/// conditions is met. At least one of them must be set.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct PetSettingsUpdateCheckpoint {
+    /// The number of accepted update messages after which a checkpoint is taken, if any.
     ///
-    /// Requires the `tls` feature to be enabled.
+    /// The value must be greater than `0`.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [api]
-    /// tls_client_auth = path/to/tls/files/trust_anchor.pem
+    /// [pet.update.checkpoint]
+    /// updates = 100
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__API__TLS_CLIENT_AUTH=path/to/tls/files/trust_anchor.pem
+    /// XAYNET__PET__UPDATE__CHECKPOINT__UPDATES=100
     /// ```
-    pub tls_client_auth: Option<PathBuf>,
-}
-
-#[cfg(feature = "tls")]
-impl ApiSettings {
-    /// Checks API settings.
-    fn validate_api(&self) -> Result<(), ValidationError> {
-        match (&self.tls_certificate, &self.tls_key, &self.tls_client_auth) {
-            (Some(_), Some(_), _) | (None, None, Some(_)) => Ok(()),
-            _ => Err(ValidationError::new("invalid tls settings")),
-        }
-    }
-}
-
-/// A wrapper for validate derive.
-#[cfg(feature = "tls")]
-fn validate_api(s: &ApiSettings) -> Result<(), ValidationError> {
-    s.validate_api()
-}
-
-#[derive(Debug, Validate, Deserialize, Clone, Copy)]
-#[cfg_attr(test, derive(PartialEq, Eq))]
-/// Masking settings.
-pub struct MaskSettings {
-    /// The order of the finite group.
+    #[serde(default)]
+    pub updates: Option<u64>,
+    /// The time in seconds after which a checkpoint is taken, if any.
+    ///
+    /// The value must be greater than `0`.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [mask]
-    /// group_type = "Integer"
+    /// [pet.update.checkpoint]
+    /// interval = 60
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__MASK__GROUP_TYPE=Integer
+    /// XAYNET__PET__UPDATE__CHECKPOINT__INTERVAL=60
     /// ```
-    pub group_type: GroupType,
+    #[serde(default)]
+    pub interval: Option<u64>,
+}
+
+/// The policy for a mismatch between the seed dictionary and the aggregated masked models.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedDictMismatchPolicy {
+    /// Log the mismatch and proceed with the round.
+    Warn,
+    /// Fail the round.
+    Fail,
+}
+
+impl Default for SeedDictMismatchPolicy {
+    fn default() -> Self {
+        Self::Warn
+    }
+}
+
+/// The aggregation of the masked models into the global model.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationStrategy {
+    /// Average the models wrt their scalars.
+    Mean,
+    /// Average the models wrt their scalars after trimming the lowest and highest weights of each
+    /// coordinate. Requires the trusted mode.
+    TrimmedMean,
+}
+
+impl Default for AggregationStrategy {
+    fn default() -> Self {
+        Self::Mean
+    }
+}
+
+/// The PET protocol `sum2` phase settings.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct PetSettingsSum2 {
+    /// The minimal and maximal number of participants selected for submitting the aggregated masks.
+    ///
+    /// The minimal value must be greater or equal to `1` (i.e. `sum2.count.min >= 1`) for the PET
+    /// protocol to function correctly and less or equal to the maximal value of the `sum` phase
+    /// (i.e. `sum2.count.sum <= sum.count.max`). The maximal value must be greater or equal to the
+    /// minimal value (i.e. `sum2.count.min <= sum2.count.max`) and less or equal to the maximal
+    /// value of the `sum` phase (i.e. `sum2.count.max <= sum.count.max`). No more than
+    /// `sum2.count.max` messages will be processed in the `sum2` phase if the `sum2.time.min` has
+    /// not yet elapsed.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum2.count]
+    /// min = 10
+    /// max = 100
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM2__COUNT__MIN=10
+    /// XAYNET__PET__SUM2__COUNT__MAX=100
+    /// ```
+    pub count: PetSettingsCount,
+
+    /// The minimal and maximal amount of time reserved for processing messages in the `sum2` phase,
+    /// in seconds.
+    ///
+    /// Once the minimal time has passed, the `sum2` phase ends *as soon as* `sum2.count.min`
+    /// messages have been processed. Set this higher to allow for the possibility of more than
+    /// `sum2.count.min` messages to be processed in the `sum2` phase. Set the maximal time lower to
+    /// allow for the processing of `sum2.count.min` messages to time-out sooner in the `sum2`
+    /// phase.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum2.time]
+    /// min = 5
+    /// max = 3600
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM2__TIME__MIN=5
+    /// XAYNET__PET__SUM2__TIME__MAX=3600
+    /// ```
+    pub time: PetSettingsTime,
+
+    /// The policy to resolve a tie between the masks with the highest number of submissions at the
+    /// end of the `sum2` phase. Defaults to `"fail"`, which fails the round.
+    ///
+    /// Resolving a tie is only sound if the chosen mask has been derived from the same update
+    /// participants as the aggregated masked models. Otherwise, unmasking fails if the masking
+    /// configurations or lengths don't match and yields a wrong global model if they do.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum2]
+    /// on_tie = "lowest_hash"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM2__ON_TIE=lowest_hash
+    /// ```
+    #[serde(default)]
+    pub on_tie: MaskTiePolicy,
+
+    /// The fraction of all submitted masks which the chosen mask must have been submitted with if
+    /// a tie is resolved with the `"require_majority_fraction"` policy. The value must be between
+    /// `0` and `1` (i.e. `0 < sum2.majority_fraction <= 1`). Defaults to `0.5`.
+    ///
+    /// The value can also be given in basis points with the `bp` suffix.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.sum2]
+    /// on_tie = "require_majority_fraction"
+    /// majority_fraction = 0.5
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__SUM2__ON_TIE=require_majority_fraction
+    /// XAYNET__PET__SUM2__MAJORITY_FRACTION=0.5
+    /// ```
+    #[serde(
+        default = "default_majority_fraction",
+        deserialize_with = "deserialize_fraction"
+    )]
+    pub majority_fraction: f64,
+}
+
+/// The default fraction of all submitted masks required to resolve a tie between masks.
+fn default_majority_fraction() -> f64 {
+    0.5
+}
+
+/// The PET protocol warm-up settings.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct PetSettingsWarmUp {
+    /// The number of rounds at the start of the coordinator which run with the relaxed warm-up
+    /// counts.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.warm_up]
+    /// rounds = 3
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__WARM_UP__ROUNDS=3
+    /// ```
+    pub rounds: u64,
+    /// The minimal number of sum messages required during the warm-up rounds. Defaults to the
+    /// protocol minimum of `1`.
+    ///
+    /// The value must be between the protocol minimum and `sum.count.min`. The minimal number of
+    /// sum2 messages is reduced to this value as well, if it exceeds it.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.warm_up]
+    /// sum_min = 1
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__WARM_UP__SUM_MIN=1
+    /// ```
+    #[serde(default = "default_warm_up_sum_min")]
+    pub sum_min: u64,
+    /// The minimal number of update messages required during the warm-up rounds. Defaults to the
+    /// protocol minimum of `3`.
+    ///
+    /// The value must be between the protocol minimum and `update.count.min`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.warm_up]
+    /// update_min = 3
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__WARM_UP__UPDATE_MIN=3
+    /// ```
+    #[serde(default = "default_warm_up_update_min")]
+    pub update_min: u64,
+}
+
+/// The PET protocol request processing budget settings.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct PetSettingsProcessingBudget {
+    /// The maximal number of requests which are processed in a row before the state machine
+    /// yields to the runtime. Defaults to `64`.
+    ///
+    /// The value must be greater than `0`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.processing_budget]
+    /// requests = 64
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__PROCESSING_BUDGET__REQUESTS=64
+    /// ```
+    #[serde(default = "default_processing_budget_requests")]
+    pub requests: u64,
+    /// The processing time of a single request in milliseconds after which the state machine
+    /// yields to the runtime right away. Defaults to `10`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.processing_budget]
+    /// slow_request = 10
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__PROCESSING_BUDGET__SLOW_REQUEST=10
+    /// ```
+    #[serde(default = "default_processing_budget_slow_request")]
+    pub slow_request: u64,
+}
+
+/// The default number of requests processed in a row.
+fn default_processing_budget_requests() -> u64 {
+    64
+}
+
+/// The default processing time of a slow request in milliseconds.
+fn default_processing_budget_slow_request() -> u64 {
+    10
+}
+
+/// The default minimal number of sum messages during the warm-up rounds.
+fn default_warm_up_sum_min() -> u64 {
+    SUM_COUNT_MIN
+}
+
+/// The default minimal number of update messages during the warm-up rounds.
+fn default_warm_up_update_min() -> u64 {
+    UPDATE_COUNT_MIN
+}
+
+/// The policy to resolve a tie between the masks with the highest number of submissions.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskTiePolicy {
+    /// Fail the round.
+    Fail,
+    /// Choose the tied mask with the lexicographically smallest hash.
+    LowestHash,
+    /// Choose the tied mask with the lexicographically smallest hash, but only if it has been
+    /// submitted with at least the majority fraction of all submitted masks.
+    RequireMajorityFraction,
+}
+
+impl Default for MaskTiePolicy {
+    fn default() -> Self {
+        Self::Fail
+    }
+}
+
+/// The mode in which the coordinator runs the PET protocol.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PetMode {
+    /// Run the full PET protocol with external sum participants.
+    Pet,
+    /// Act as the trusted aggregator of the round instead of external sum participants.
+    Trusted,
+}
+
+impl Default for PetMode {
+    fn default() -> Self {
+        Self::Pet
+    }
+}
+
+/// The extent of a round which the coordinator runs.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoordinatorMode {
+    /// Run all phases of a round and unmask the global model.
+    Full,
+    /// End the round after the update phase and persist the masked models and the seed dictionary
+    /// for offline processing instead of aggregating them.
+    CollectOnly,
+}
+
+impl Default for CoordinatorMode {
+    fn default() -> Self {
+        Self::Full
+    }
+}
+
+/// The PET protocol settings.
+#[derive(Debug, Validate, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+#[validate(schema(function = "validate_pet"))]
+pub struct PetSettings {
+    /// The mode in which the coordinator runs the PET protocol. Defaults to `pet`.
+    ///
+    /// In `trusted` mode the coordinator generates an ephemeral key pair per round and advertises
+    /// itself as the only entry of the sum dictionary, hence no participant is selected for the
+    /// sum task. The `sum` and `sum2` phases are skipped and the coordinator decrypts the seeds of
+    /// the update participants, derives and aggregates their masks and unmasks the global model
+    /// by itself. The update participants are unaffected, but they must trust the coordinator
+    /// not to look at their individual models.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// mode = "trusted"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__MODE=trusted
+    /// ```
+    #[serde(default)]
+    pub mode: PetMode,
+    /// The extent of a round which the coordinator runs. Defaults to `full`.
+    ///
+    /// In `collect_only` mode the coordinator runs the `sum` and `update` phases as usual, but it
+    /// ends the round right after the `update` phase. The masked models and the seed dictionary of
+    /// the round are persisted for offline processing instead, hence neither the `sum2` phase nor
+    /// the unmasking takes place and the global model stays the same. This can't be combined with
+    /// the `trusted` mode, because the seeds are encrypted for the trusted aggregator only.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// coordinator_mode = "collect_only"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__COORDINATOR_MODE=collect_only
+    /// ```
+    #[serde(default)]
+    pub coordinator_mode: CoordinatorMode,
+    /// The PET settings for the `sum` phase.
+    pub sum: PetSettingsSum,
+    /// The PET settings for the `update` phase.
+    pub update: PetSettingsUpdate,
+    /// The PET settings for the `sum2` phase.
+    pub sum2: PetSettingsSum2,
+    /// The PET settings for the warm-up rounds. Disabled by default.
+    ///
+    /// The first rounds of a coordinator often have few participants online. During the warm-up
+    /// rounds, the minimal numbers of sum and update messages are reduced to the warm-up values
+    /// and they snap back to the `sum.count.min` and `update.count.min` values afterwards. See
+    /// [`PetSettingsWarmUp`] for the individual settings.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.warm_up]
+    /// rounds = 3
+    /// sum_min = 1
+    /// update_min = 3
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__WARM_UP__ROUNDS=3
+    /// XAYNET__PET__WARM_UP__SUM_MIN=1
+    /// XAYNET__PET__WARM_UP__UPDATE_MIN=3
+    /// ```
+    #[serde(default)]
+    pub warm_up: Option<PetSettingsWarmUp>,
+    /// Whether the coordinator commits to the seed and fractions of the next round in advance.
+    ///
+    /// If enabled, the round parameters of each round contain a hash of the seed and fractions of
+    /// the next round. This prevents the coordinator from adaptively choosing the parameters of a
+    /// round after having seen the messages of the participants, since participants can verify
+    /// the revealed parameters against the commitment. Participants don't take part in a round
+    /// whose parameters don't match the commitment. Defaults to `false`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// commit_round_params = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__COMMIT_ROUND_PARAMS=true
+    /// ```
+    #[serde(default)]
+    pub commit_round_params: bool,
+    /// The soft deadline of a phase in seconds. Disabled by default.
+    ///
+    /// A phase which takes longer than this is reported with a warning and a metric, but unlike
+    /// the maximal phase times it is not aborted. This helps to track the duration of the rounds
+    /// against a service level agreement.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// phase_soft_deadline = 600
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__PHASE_SOFT_DEADLINE=600
+    /// ```
+    #[serde(default)]
+    pub phase_soft_deadline: Option<u64>,
+    /// The delay in seconds between the end of a round and the start of the next round. Disabled
+    /// by default.
+    ///
+    /// The coordinator stays idle during the delay and keeps serving the global model of the
+    /// finished round, which gives the participants a window to download it before the round
+    /// parameters change. The delay only follows rounds which produced a global model and counts
+    /// towards the duration of the idle phase.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// inter_round_delay = 30
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__INTER_ROUND_DELAY=30
+    /// ```
+    #[serde(default)]
+    pub inter_round_delay: Option<u64>,
+    /// The budget of the request processing per scheduling of the state machine. Disabled by
+    /// default.
+    ///
+    /// A burst of requests is otherwise processed in one go, which starves other tasks on the same
+    /// runtime, like the REST API. With a budget, the state machine yields to the runtime after
+    /// processing a number of requests in a row or a single slow request. See
+    /// [`PetSettingsProcessingBudget`] for the individual settings.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet.processing_budget]
+    /// requests = 64
+    /// slow_request = 10
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__PROCESSING_BUDGET__REQUESTS=64
+    /// XAYNET__PET__PROCESSING_BUDGET__SLOW_REQUEST=10
+    /// ```
+    #[serde(default)]
+    pub processing_budget: Option<PetSettingsProcessingBudget>,
+    /// The salt which is mixed into the eligibility computation of the participants. Disabled by
+    /// default.
+    ///
+    /// Without a salt, the selection of a participant only depends on its signing key and the
+    /// round seed, hence a participant is selected alike by every coordinator which shares the
+    /// seeds. A salt per deployment separates the selections. The salt is announced to the
+    /// participants in the round parameters, hence it is not a secret.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// eligibility_salt = "production-eu"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__ELIGIBILITY_SALT=production-eu
+    /// ```
+    #[serde(default)]
+    pub eligibility_salt: Option<String>,
+    /// The identifier of the crypto suite of the rounds, which determines the hash function of the
+    /// protocol. Defaults to `0`, i.e. `SHA256`.
+    ///
+    /// The suite is announced to the participants in the round parameters and participants which
+    /// don't support it refuse to take part in the rounds. The suite `1`, i.e. `BLAKE3`, requires
+    /// the `blake3` feature.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// crypto_suite = 1
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__PET__CRYPTO_SUITE=1
+    /// ```
+    #[serde(default)]
+    pub crypto_suite: CryptoSuite,
+}
+
+impl PetSettings {
+    /// Checks the PET settings.
+    fn validate_pet(&self) -> Result<(), ValidationError> {
+        self.validate_counts()?;
+        self.validate_warm_up()?;
+        self.validate_times()?;
+        self.validate_probabilities()?;
+        self.validate_majority_fraction()?;
+        self.validate_sum_dict_capacity()?;
+        self.validate_multiparty()?;
+        self.validate_processing_budget()?;
+        self.validate_update_checkpoint()?;
+        self.validate_crypto_suite()?;
+        self.validate_aggregation()?;
+        self.validate_modes()
+    }
+
+    /// Checks the validity of phase count ranges.
+    fn validate_counts(&self) -> Result<(), ValidationError> {
+        // the validate attribute only accepts literals, therefore we check the invariants here
+        if SUM_COUNT_MIN <= self.sum.count.min
+            && self.sum.count.min <= self.sum.count.max
+            && UPDATE_COUNT_MIN <= self.update.count.min
+            && self.update.count.min <= self.update.count.max
+            && SUM_COUNT_MIN <= self.sum2.count.min
+            && self.sum2.count.min <= self.sum2.count.max
+            && self.sum2.count.min <= self.sum.count.max
+            && self.sum2.count.max <= self.sum.count.max
+        {
+            Ok(())
+        } else {
+            Err(ValidationError::new("invalid phase count range(s)"))
+        }
+    }
+
+    /// Checks that the warm-up counts relax the phase counts without undercutting the protocol
+    /// minimums.
+    fn validate_warm_up(&self) -> Result<(), ValidationError> {
+        match self.warm_up {
+            Some(warm_up)
+                if warm_up.sum_min < SUM_COUNT_MIN
+                    || self.sum.count.min < warm_up.sum_min
+                    || warm_up.update_min < UPDATE_COUNT_MIN
+                    || self.update.count.min < warm_up.update_min =>
+            {
+                Err(ValidationError::new("invalid warm-up count(s)"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the processing budget allows to process at least one request in a row.
+    fn validate_processing_budget(&self) -> Result<(), ValidationError> {
+        match self.processing_budget {
+            Some(budget) if budget.requests == 0 => {
+                Err(ValidationError::new("invalid processing budget"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the update checkpoints have at least one valid condition.
+    fn validate_update_checkpoint(&self) -> Result<(), ValidationError> {
+        match self.update.checkpoint {
+            Some(PetSettingsUpdateCheckpoint { updates, interval })
+                if (updates.is_none() && interval.is_none())
+                    || updates == Some(0)
+                    || interval == Some(0) =>
+            {
+                Err(ValidationError::new("invalid update checkpoint"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the validity of phase time ranges.
+    fn validate_times(&self) -> Result<(), ValidationError> {
+        if self.sum.time.min <= self.sum.time.max
+            && self.update.time.min <= self.update.time.max
+            && self.sum2.time.min <= self.sum2.time.max
+        {
+            Ok(())
+        } else {
+            Err(ValidationError::new("invalid phase time range(s)"))
+        }
+    }
+
+    /// Checks the validity of fraction ranges including pathological cases of deadlocks.
+    ///
+    /// The eligibility of the participants is checked against the fractions in basis points,
+    /// hence the fractions must also be valid after they are quantized to basis points.
+    fn validate_probabilities(&self) -> Result<(), ValidationError> {
+        let sum = to_basis_points(self.sum.prob);
+        let update = to_basis_points(self.update.prob);
+        if 0. < self.sum.prob
+            && self.sum.prob < 1.
+            && 0. < self.update.prob
+            && self.update.prob <= 1.
+            && (1..BASIS_POINTS).contains(&sum)
+            && (1..=BASIS_POINTS).contains(&update)
+            && 0. < self.participation_prob()
+            && self.participation_prob() <= 1.
+        {
+            Ok(())
+        } else {
+            Err(ValidationError::new("starvation"))
+        }
+    }
+
+    /// Gets the probability that a participant is eligible for the sum or the update task.
+    ///
+    /// The fractions are quantized to basis points like in the eligibility check.
+    fn participation_prob(&self) -> f64 {
+        let sum = from_basis_points(to_basis_points(self.sum.prob));
+        let update = from_basis_points(to_basis_points(self.update.prob));
+        sum + update - sum * update
+    }
+
+    /// Gets the distance of the participation probability to the bounds of `(0, 1]`.
+    ///
+    /// The fractions are valid only if the participation probability lies in these bounds,
+    /// otherwise no participant takes part in a round or a round deadlocks. A small margin
+    /// indicates that the fractions are close to such a pathological case. The margin is
+    /// negative if the fractions are invalid.
+    pub fn deadlock_margin(&self) -> f64 {
+        let prob = self.participation_prob();
+        prob.min(1. - prob)
+    }
+
+    /// Checks the validity of the majority fraction to resolve a tie between masks.
+    fn validate_majority_fraction(&self) -> Result<(), ValidationError> {
+        if 0. < self.sum2.majority_fraction && self.sum2.majority_fraction <= 1. {
+            Ok(())
+        } else {
+            Err(ValidationError::new("invalid majority fraction"))
+        }
+    }
+
+    /// Checks that the sum dictionary can hold the minimal number of sum participants.
+    fn validate_sum_dict_capacity(&self) -> Result<(), ValidationError> {
+        match self.sum.dict_capacity {
+            Some(capacity) if capacity < self.sum.count.min => {
+                Err(ValidationError::new("insufficient sum dictionary capacity"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the sum phase allows for two distinct sum participants, if they are required.
+    fn validate_multiparty(&self) -> Result<(), ValidationError> {
+        if !self.sum.require_multiparty {
+            return Ok(());
+        }
+        let capacity = self.sum.dict_capacity.unwrap_or(u64::MAX);
+        if self.sum.count.max < MULTIPARTY_SUM_COUNT_MIN || capacity < MULTIPARTY_SUM_COUNT_MIN {
+            Err(ValidationError::new(
+                "multiparty sums require room for two sum participants",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that the crypto suite is supported by this build.
+    fn validate_crypto_suite(&self) -> Result<(), ValidationError> {
+        if self.crypto_suite.is_supported() {
+            Ok(())
+        } else {
+            Err(ValidationError::new("unsupported crypto suite"))
+        }
+    }
+
+    /// Checks that the trimmed mean aggregation is feasible and leaves weights to average.
+    fn validate_aggregation(&self) -> Result<(), ValidationError> {
+        if self.update.aggregation == AggregationStrategy::Mean {
+            return Ok(());
+        }
+        if self.mode != PetMode::Trusted {
+            return Err(ValidationError::new(
+                "trimmed mean aggregation requires trusted mode",
+            ));
+        }
+        if self.update.checkpoint.is_some() {
+            return Err(ValidationError::new(
+                "trimmed mean aggregation is incompatible with update checkpoints",
+            ));
+        }
+        let update_min = self
+            .warm_up
+            .map_or(self.update.count.min, |warm_up| warm_up.update_min);
+        if update_min <= self.update.trim.saturating_mul(2) {
+            Err(ValidationError::new(
+                "trimmed mean aggregation trims all update participants",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the compatibility of the PET mode and the coordinator mode.
+    fn validate_modes(&self) -> Result<(), ValidationError> {
+        if self.mode == PetMode::Trusted && self.coordinator_mode == CoordinatorMode::CollectOnly {
+            Err(ValidationError::new(
+                "collect only mode is incompatible with trusted mode",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_pet(s: &PetSettings) -> Result<(), ValidationError> {
+    s.validate_pet()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(
+    feature = "tls",
+    derive(Validate),
+    validate(schema(function = "validate_api"))
+)]
+/// REST API settings.
+///
+/// Requires at least one of the following arguments if the `tls` feature is enabled:
+/// - `tls_certificate` together with `tls_key` for TLS server authentication
+/// - `tls_client_auth` for TLS client authentication
+pub struct ApiSettings {
+    /// The address to which the REST API should be bound.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// bind_address = "0.0.0.0:8081"
+    /// # or
+    /// bind_address = "127.0.0.1:8081"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__BIND_ADDRESS=127.0.0.1:8081
+    /// ```
+    pub bind_address: std::net::SocketAddr,
+
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    /// The path to the server certificate to enable TLS server authentication. Leave this out to
+    /// disable server authentication. If this is present, then `tls_key` must also be present.
+    ///
+    /// Requires the `tls` feature to be enabled.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// tls_certificate = path/to/tls/files/cert.pem
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__TLS_CERTIFICATE=path/to/tls/files/certificate.pem
+    /// ```
+    pub tls_certificate: Option<PathBuf>,
+
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    /// The path to the server private key to enable TLS server authentication. Leave this out to
+    /// disable server authentication. If this is present, then `tls_certificate` must also be
+    /// present.
+    ///
+    /// Requires the `tls` feature to be enabled.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// tls_key = path/to/tls/files/key.rsa
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__TLS_KEY=path/to/tls/files/key.rsa
+    /// ```
+    pub tls_key: Option<PathBuf>,
+
+    #[cfg(feature = "tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+    /// The path to the trust anchor to enable TLS client authentication. Leave this out to disable
+    /// client authentication.
+    ///
+    /// If this is present, then the REST API requires every client to present a certificate
+    /// signed by one of the CA certificates in this file and rejects all other connections. The
+    /// setting can also be given as `tls_client_ca`.
+    ///
+    /// Requires the `tls` feature to be enabled.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// tls_client_auth = path/to/tls/files/trust_anchor.pem
+    /// # or
+    /// tls_client_ca = path/to/tls/files/trust_anchor.pem
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__TLS_CLIENT_AUTH=path/to/tls/files/trust_anchor.pem
+    /// # or
+    /// XAYNET__API__TLS_CLIENT_CA=path/to/tls/files/trust_anchor.pem
+    /// ```
+    #[serde(alias = "tls_client_ca")]
+    pub tls_client_auth: Option<PathBuf>,
+
+    /// The bearer token which grants access to the `GET /admin/state` and
+    /// `GET /admin/dictionaries` debugging endpoints. Leave this out to disable the endpoints.
+    ///
+    /// The endpoints dump the coordinator state and dictionaries without any secret keys.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// admin_token = "a-long-random-token"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__ADMIN_TOKEN=a-long-random-token
+    /// ```
+    pub admin_token: Option<String>,
+
+    /// The time in seconds after which a request which hasn't been answered yet is aborted with
+    /// `503 Service Unavailable` and a `Retry-After` header. Defaults to `30`.
+    ///
+    /// This applies to all endpoints except for the model download, see `model_request_timeout`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// request_timeout = 30
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__REQUEST_TIMEOUT=30
+    /// ```
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: u64,
+
+    /// The time in seconds after which a request for the global model which hasn't been answered
+    /// yet is aborted like any other request, see `request_timeout`. Defaults to `300`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// model_request_timeout = 300
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__MODEL_REQUEST_TIMEOUT=300
+    /// ```
+    #[serde(default = "default_model_request_timeout")]
+    pub model_request_timeout: u64,
+
+    /// The time in seconds which the REST API waits for in-flight requests to finish once the
+    /// coordinator is shut down. No new connections are accepted meanwhile. Defaults to `30`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// shutdown_grace_period = 30
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__SHUTDOWN_GRACE_PERIOD=30
+    /// ```
+    #[serde(default = "default_shutdown_grace_period")]
+    pub shutdown_grace_period: u64,
+
+    /// The level with which the sum dictionary, the seed dictionary and the global model are
+    /// compressed for clients which accept a `gzip` or `deflate` content encoding. Ranges from `0`
+    /// (no compression) to `9` (best compression). Defaults to `6`.
+    ///
+    /// The data is compressed at most once per phase and the compressed data is reused for all
+    /// requests of the phase.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// compression_level = 6
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__COMPRESSION_LEVEL=6
+    /// ```
+    #[serde(
+        default = "default_compression_level",
+        deserialize_with = "deserialize_compression_level"
+    )]
+    pub compression_level: u32,
+
+    /// The maximal number of requests per second to the `POST /messages/validate` endpoint, which
+    /// validates PET messages without processing them. Requests exceeding the limit are rejected
+    /// with `429 Too Many Requests`. Set this to `0` to disable the endpoint. Defaults to `10`.
+    ///
+    /// The limit applies to the endpoint independently of the other endpoints.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// validate_rate_limit = 10
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__VALIDATE_RATE_LIMIT=10
+    /// ```
+    #[serde(default = "default_validate_rate_limit")]
+    pub validate_rate_limit: u32,
+
+    /// The maximal size in bytes of an encrypted PET message, which applies to the
+    /// `POST /message` and the `POST /messages/validate` endpoints. Larger requests are rejected
+    /// with `413 Payload Too Large` without reading the full body.
+    ///
+    /// Leave this out to derive the limit from the PET, mask and model settings, which allows for
+    /// the largest message of a round plus a headroom of 4096 bytes.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// max_message_size = 1048576
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__MAX_MESSAGE_SIZE=1048576
+    /// ```
+    #[serde(default)]
+    pub max_message_size: Option<u64>,
+
+    /// The name of the request header which carries the identity of the client certificate of a
+    /// participant. Leave this out to ignore client certificate identities.
+    ///
+    /// The REST API can't inspect the client certificates itself, hence the identity (e.g. the
+    /// subject or the fingerprint of the certificate) must be forwarded by a TLS terminating
+    /// proxy, which must also strip the header from the incoming requests. The identity is
+    /// required by the `pet.sum.distinct_identities` setting.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// client_identity_header = "x-client-cert-fingerprint"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__CLIENT_IDENTITY_HEADER=x-client-cert-fingerprint
+    /// ```
+    #[serde(default)]
+    pub client_identity_header: Option<String>,
+
+    /// The number of threads which decrypt, verify and decode the PET messages. Leave this out
+    /// to use one thread per logical CPU.
+    ///
+    /// The processing of the messages runs on a dedicated thread pool, hence a flood of large
+    /// messages doesn't delay the phase timers of the coordinator. The number of messages which
+    /// wait for a free thread is reported by the `message_queue_depth` metric.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// message_threads = 4
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__MESSAGE_THREADS=4
+    /// ```
+    #[serde(default)]
+    pub message_threads: Option<usize>,
+
+    /// The maximal number of task signatures of concurrent PET messages which are verified in a
+    /// single batch. Leave this out to verify the task signatures one by one.
+    ///
+    /// A batch is verified considerably faster than its signatures one by one, at the cost of a
+    /// delay of at most `signature_batch_delay` milliseconds per message. If a batch contains an
+    /// invalid signature, its signatures are verified one by one again.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// signature_batch_size = 64
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__SIGNATURE_BATCH_SIZE=64
+    /// ```
+    #[serde(default)]
+    pub signature_batch_size: Option<usize>,
+
+    /// The maximal time in milliseconds a task signature waits for its batch to fill up before
+    /// the batch is verified anyway. Only applies if `signature_batch_size` is set.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// signature_batch_delay = 2
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__API__SIGNATURE_BATCH_DELAY=2
+    /// ```
+    #[serde(default = "default_signature_batch_delay")]
+    pub signature_batch_delay: u64,
+}
+
+/// The default request timeout of the REST API in seconds.
+fn default_request_timeout() -> u64 {
+    30
+}
+
+/// The default request timeout of the REST API for the global model in seconds.
+fn default_model_request_timeout() -> u64 {
+    300
+}
+
+/// The default shutdown grace period of the REST API in seconds.
+fn default_shutdown_grace_period() -> u64 {
+    30
+}
+
+/// The default compression level of the REST API.
+fn default_compression_level() -> u32 {
+    6
+}
+
+/// The default rate limit of the message validation endpoint in requests per second.
+fn default_validate_rate_limit() -> u32 {
+    10
+}
+
+/// The default maximal delay of a batch of task signatures in milliseconds.
+fn default_signature_batch_delay() -> u64 {
+    2
+}
+
+/// Deserializes a compression level, which must not exceed `9`.
+fn deserialize_compression_level<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let level = u32::deserialize(deserializer)?;
+    if level > 9 {
+        Err(de::Error::invalid_value(
+            de::Unexpected::Unsigned(level as u64),
+            &"a compression level between 0 and 9",
+        ))
+    } else {
+        Ok(level)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl ApiSettings {
+    /// Checks API settings.
+    fn validate_api(&self) -> Result<(), ValidationError> {
+        match (&self.tls_certificate, &self.tls_key, &self.tls_client_auth) {
+            (Some(_), Some(_), _) | (None, None, Some(_)) => Ok(()),
+            _ => Err(ValidationError::new("invalid tls settings")),
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+#[cfg(feature = "tls")]
+fn validate_api(s: &ApiSettings) -> Result<(), ValidationError> {
+    s.validate_api()
+}
+
+/// Deserializes a fraction either from a number or from a string. A string is either a number or
+/// a number of basis points with the `bp` suffix, e.g. `"100bp"` for `0.01`.
+fn deserialize_fraction<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct FractionVisitor;
+
+    impl<'de> Visitor<'de> for FractionVisitor {
+        type Value = f64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a fraction as a number (e.g. 0.01) or in basis points (e.g. \"100bp\")"
+            )
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(value)
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(value as f64)
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(value as f64)
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            let value = value.trim();
+            match value.strip_suffix("bp") {
+                Some(basis_points) => basis_points
+                    .trim_end()
+                    .parse::<u32>()
+                    .ok()
+                    .map(from_basis_points),
+                None => value.parse::<f64>().ok(),
+            }
+            .ok_or_else(|| de::Error::invalid_value(serde::de::Unexpected::Str(value), &self))
+        }
+    }
+
+    deserializer.deserialize_any(FractionVisitor)
+}
+
+#[derive(Debug, Validate, Deserialize, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+/// Masking settings.
+pub struct MaskSettings {
+    /// The order of the finite group.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [mask]
+    /// group_type = "Integer"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__MASK__GROUP_TYPE=Integer
+    /// ```
+    pub group_type: GroupType,
 
     /// The data type of the numbers to be masked.
     ///
@@ -511,194 +1763,760 @@ pub struct MaskSettings {
     ///
     /// **TOML**
     /// ```text
-    /// [mask]
-    /// data_type = "F32"
+    /// [mask]
+    /// data_type = "F32"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__MASK__DATA_TYPE=F32
+    /// ```
+    pub data_type: DataType,
+
+    /// The bounds of the numbers to be masked.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [mask]
+    /// bound_type = "B0"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__MASK__BOUND_TYPE=B0
+    /// ```
+    pub bound_type: BoundType,
+
+    /// The maximum number of models to be aggregated.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [mask]
+    /// model_type = "M3"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__MASK__MODEL_TYPE=M3
+    /// ```
+    pub model_type: ModelType,
+
+    /// Whether the masks are bound to the round seed. Defaults to `false`.
+    ///
+    /// If enabled, the participants derive their masks from their mask seeds and the round seed,
+    /// hence a mask seed which is accidentally reused in another round yields an unrelated mask.
+    /// The participants announce the derivation they use in the header of their update and sum2
+    /// messages and the coordinator rejects the messages which don't match this setting.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [mask]
+    /// round_bound_masks = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__MASK__ROUND_BOUND_MASKS=true
+    /// ```
+    #[serde(default)]
+    pub round_bound_masks: bool,
+}
+
+impl From<MaskSettings> for MaskConfig {
+    fn from(
+        MaskSettings {
+            group_type,
+            data_type,
+            bound_type,
+            model_type,
+            ..
+        }: MaskSettings,
+    ) -> MaskConfig {
+        MaskConfig {
+            group_type,
+            data_type,
+            bound_type,
+            model_type,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// Model settings.
+pub struct ModelSettings {
+    /// The expected length of the model. The model length corresponds to the number of elements.
+    /// This value is used to validate the uniform length of the submitted models/masks.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [model]
+    /// length = 100
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__MODEL__LENGTH=100
+    /// ```
+    pub length: usize,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+/// Metrics settings.
+pub struct MetricsSettings {
+    #[validate]
+    /// Settings for the InfluxDB backend.
+    pub influxdb: InfluxSettings,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+/// InfluxDB settings.
+pub struct InfluxSettings {
+    #[validate(url)]
+    /// The URL where InfluxDB is running.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [metrics.influxdb]
+    /// url = "http://localhost:8086"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__METRICS__INFLUXDB__URL=http://localhost:8086
+    /// ```
+    pub url: String,
+
+    /// The InfluxDB database name.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [metrics.influxdb]
+    /// db = "test"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__MASK__DATA_TYPE=F32
+    /// XAYNET__METRICS__INFLUXDB__DB=test
     /// ```
-    pub data_type: DataType,
+    pub db: String,
+}
 
-    /// The bounds of the numbers to be masked.
+#[derive(Debug, Deserialize, Validate)]
+/// Redis settings.
+pub struct RedisSettings {
+    /// The URL where Redis is running.
+    ///
+    /// The format of the URL is `redis://[<username>][:<passwd>@]<hostname>[:port][/<db>]`.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [mask]
-    /// bound_type = "B0"
+    /// [redis]
+    /// url = "redis://127.0.0.1/"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__MASK__BOUND_TYPE=B0
+    /// XAYNET__REDIS__URL=redis://127.0.0.1/
     /// ```
-    pub bound_type: BoundType,
+    #[serde(deserialize_with = "deserialize_redis_url")]
+    pub url: ConnectionInfo,
 
-    /// The maximum number of models to be aggregated.
+    /// The maximal number of local seed dicts which are written to Redis in a single pipelined
+    /// transaction.
+    ///
+    /// Leave this out to write every local seed dict on its own.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [mask]
-    /// model_type = "M3"
+    /// [redis]
+    /// seed_dict_batch_size = 64
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__MASK__MODEL_TYPE=M3
+    /// XAYNET__REDIS__SEED_DICT_BATCH_SIZE=64
     /// ```
-    pub model_type: ModelType,
+    #[serde(default)]
+    #[validate(range(min = 1))]
+    pub seed_dict_batch_size: Option<usize>,
+
+    /// The maximal time in milliseconds a local seed dict waits for its batch to fill up before
+    /// the batch is written anyway. Defaults to `10`. Only applies if `seed_dict_batch_size` is
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [redis]
+    /// seed_dict_flush_interval = 10
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__REDIS__SEED_DICT_FLUSH_INTERVAL=10
+    /// ```
+    #[serde(default = "default_seed_dict_flush_interval")]
+    pub seed_dict_flush_interval: u64,
 }
 
-impl From<MaskSettings> for MaskConfig {
-    fn from(
-        MaskSettings {
-            group_type,
-            data_type,
-            bound_type,
-            model_type,
-        }: MaskSettings,
-    ) -> MaskConfig {
-        MaskConfig {
-            group_type,
-            data_type,
-            bound_type,
-            model_type,
+/// The default flush interval of batched local seed dicts in milliseconds.
+fn default_seed_dict_flush_interval() -> u64 {
+    10
+}
+
+fn deserialize_redis_url<'de, D>(deserializer: D) -> Result<ConnectionInfo, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ConnectionInfoVisitor;
+
+    impl<'de> Visitor<'de> for ConnectionInfoVisitor {
+        type Value = ConnectionInfo;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "redis://[<username>][:<passwd>@]<hostname>[:port][/<db>]"
+            )
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            value
+                .into_connection_info()
+                .map_err(|_| de::Error::invalid_value(serde::de::Unexpected::Str(value), &self))
+        }
+    }
+
+    deserializer.deserialize_str(ConnectionInfoVisitor)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+/// Trust anchor settings.
+pub struct TrustAnchorSettings {}
+
+// Default value for the global models bucket
+impl Default for TrustAnchorSettings {
+    fn default() -> Self {
+        Self {}
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+#[validate(schema(function = "validate_denylist"))]
+/// Denylist settings.
+///
+/// Banned participants are rejected right after the signature of their messages is verified. The
+/// denylist can be modified at runtime via the token-protected `PUT /admin/denylist/<pk>` and
+/// `DELETE /admin/denylist/<pk>` endpoints, where the public key is URL-safe base64 encoded.
+pub struct DenylistSettings {
+    /// The base64 encoded public keys of the participants which are banned on start. Defaults to
+    /// none.
+    ///
+    /// The keys are added to the denylist persisted in the coordinator storage, hence a key which
+    /// is removed at runtime is banned again after a restart unless it is removed from the
+    /// settings as well.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [denylist]
+    /// keys = ["bR0mrcpVaVvm2K6fXbEgWtBsSn6H2OJhKwJJeJQUm9g="]
+    /// ```
+    #[serde(default, deserialize_with = "deserialize_participant_pks")]
+    pub keys: Vec<ParticipantPublicKey>,
+    /// The maximal number of banned participants. Defaults to `10000`.
+    ///
+    /// Bans which would exceed the capacity are refused.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [denylist]
+    /// capacity = 10000
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__DENYLIST__CAPACITY=10000
+    /// ```
+    #[serde(default = "default_denylist_capacity")]
+    pub capacity: usize,
+}
+
+impl Default for DenylistSettings {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            capacity: default_denylist_capacity(),
+        }
+    }
+}
+
+/// The default maximal number of banned participants.
+fn default_denylist_capacity() -> usize {
+    10_000
+}
+
+/// Deserializes a list of base64 encoded participant public keys.
+fn deserialize_participant_pks<'de, D>(
+    deserializer: D,
+) -> Result<Vec<ParticipantPublicKey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pk| {
+            base64::decode(pk)
+                .ok()
+                .and_then(|bytes| ParticipantPublicKey::from_slice(&bytes))
+                .ok_or_else(|| {
+                    de::Error::invalid_value(
+                        de::Unexpected::Str(pk),
+                        &"a base64 encoded participant public key",
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Checks that the denylist can hold the keys of the settings.
+fn validate_denylist(s: &DenylistSettings) -> Result<(), ValidationError> {
+    if s.keys.len() <= s.capacity {
+        Ok(())
+    } else {
+        Err(ValidationError::new("insufficient denylist capacity"))
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, Clone, Copy)]
+#[cfg_attr(test, derive(PartialEq))]
+/// Circuit breaker settings.
+///
+/// Transient errors of the coordinator storage, like dropped connections or timeouts, are retried
+/// a few times before an operation fails. If the rate of transient errors exceeds a threshold,
+/// the circuit breaker opens and operations fail right away, until a probe succeeds after the
+/// breaker has been open for a while.
+pub struct CircuitBreakerSettings {
+    /// The number of retries of an operation which failed with a transient error. Defaults to
+    /// `2`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [circuit_breaker]
+    /// retries = 2
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__CIRCUIT_BREAKER__RETRIES=2
+    /// ```
+    #[serde(default = "default_circuit_breaker_retries")]
+    pub retries: u32,
+    /// The backoff in milliseconds before the first retry, which doubles with each further
+    /// retry. Defaults to `50`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [circuit_breaker]
+    /// backoff = 50
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__CIRCUIT_BREAKER__BACKOFF=50
+    /// ```
+    #[serde(default = "default_circuit_breaker_backoff")]
+    pub backoff: u64,
+    /// The sliding window in seconds over which the rate of transient errors is measured.
+    /// Defaults to `10`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [circuit_breaker]
+    /// window = 10
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__CIRCUIT_BREAKER__WINDOW=10
+    /// ```
+    #[serde(default = "default_circuit_breaker_window")]
+    pub window: u64,
+    /// The minimal number of storage operations within the window before the breaker may open.
+    /// Defaults to `20`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [circuit_breaker]
+    /// min_operations = 20
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__CIRCUIT_BREAKER__MIN_OPERATIONS=20
+    /// ```
+    #[serde(default = "default_circuit_breaker_min_operations")]
+    pub min_operations: u32,
+    /// The rate of transient errors within the window above which the breaker opens. Defaults to
+    /// `0.5`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [circuit_breaker]
+    /// error_rate = 0.5
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__CIRCUIT_BREAKER__ERROR_RATE=0.5
+    /// ```
+    #[serde(default = "default_circuit_breaker_error_rate")]
+    #[validate(range(min = 0., max = 1.))]
+    pub error_rate: f64,
+    /// The time in seconds the breaker stays open before a probe is let through. Defaults to
+    /// `5`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [circuit_breaker]
+    /// open_duration = 5
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__CIRCUIT_BREAKER__OPEN_DURATION=5
+    /// ```
+    #[serde(default = "default_circuit_breaker_open_duration")]
+    pub open_duration: u64,
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        Self {
+            retries: default_circuit_breaker_retries(),
+            backoff: default_circuit_breaker_backoff(),
+            window: default_circuit_breaker_window(),
+            min_operations: default_circuit_breaker_min_operations(),
+            error_rate: default_circuit_breaker_error_rate(),
+            open_duration: default_circuit_breaker_open_duration(),
         }
     }
 }
 
+/// The default number of retries of a transient storage error.
+fn default_circuit_breaker_retries() -> u32 {
+    2
+}
+
+/// The default backoff before the first retry in milliseconds.
+fn default_circuit_breaker_backoff() -> u64 {
+    50
+}
+
+/// The default window of the transient error rate in seconds.
+fn default_circuit_breaker_window() -> u64 {
+    10
+}
+
+/// The default minimal number of storage operations before the breaker may open.
+fn default_circuit_breaker_min_operations() -> u32 {
+    20
+}
+
+/// The default transient error rate above which the breaker opens.
+fn default_circuit_breaker_error_rate() -> f64 {
+    0.5
+}
+
+/// The default time the breaker stays open in seconds.
+fn default_circuit_breaker_open_duration() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize, Validate, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+#[validate(schema(function = "validate_leader"))]
+/// Leader election settings. Disabled by default.
+///
+/// Several coordinator replicas which share the same Redis elect a leader via a lock in Redis.
+/// Only the leader runs the state machine, the other replicas stand by and answer all requests
+/// with `503 Service Unavailable` and the address of the leader. If the leader fails to renew the
+/// lock in time, a standby replica takes over and restores the coordinator state from Redis.
+pub struct LeaderSettings {
+    /// The address under which the REST API of this replica is reachable, which is advertised to
+    /// the clients of the other replicas while this replica is the leader.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [leader]
+    /// address = "http://10.0.0.1:8081"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__LEADER__ADDRESS=http://10.0.0.1:8081
+    /// ```
+    pub address: String,
+    /// The time to live of the leader lock in milliseconds. Defaults to `5000`.
+    ///
+    /// A standby replica takes over at the latest this long after the leader stopped renewing
+    /// the lock.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [leader]
+    /// ttl = 5000
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__LEADER__TTL=5000
+    /// ```
+    #[serde(default = "default_leader_ttl")]
+    pub ttl: u64,
+    /// The interval in milliseconds in which the leader renews the lock and a standby replica
+    /// tries to acquire it. Defaults to `1000`.
+    ///
+    /// The interval must be shorter than the time to live of the lock.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [leader]
+    /// renew_interval = 1000
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__LEADER__RENEW_INTERVAL=1000
+    /// ```
+    #[serde(default = "default_leader_renew_interval")]
+    pub renew_interval: u64,
+}
+
+/// The default time to live of the leader lock in milliseconds.
+fn default_leader_ttl() -> u64 {
+    5000
+}
+
+/// The default renewal interval of the leader lock in milliseconds.
+fn default_leader_renew_interval() -> u64 {
+    1000
+}
+
+/// Checks that the leader renews the lock before it expires.
+fn validate_leader(s: &LeaderSettings) -> Result<(), ValidationError> {
+    if 0 < s.renew_interval && s.renew_interval < s.ttl {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid leader lock renewal interval"))
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
-/// Model settings.
-pub struct ModelSettings {
-    /// The expected length of the model. The model length corresponds to the number of elements.
-    /// This value is used to validate the uniform length of the submitted models/masks.
+/// Seed dictionary export settings. Disabled by default.
+///
+/// Once the seed dictionary of a round is frozen, the seeds of every sum participant are written
+/// as a separate file in their canonical binary encoding. The files are named by the round id and
+/// the URL-safe base64 encoded public key of the sum participant, i.e.
+/// `<round_id>/<sum_pk>`. The sum participants which ask for it are pointed to the location of
+/// their file instead of receiving their seeds inline, so that they can fetch them independently
+/// of the availability of the coordinator.
+pub struct SeedExportSettings {
+    /// The directory to which the seed dictionaries are exported.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [model]
-    /// length = 100
+    /// [seed_export]
+    /// path = "/var/lib/xaynet/seeds"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__MODEL__LENGTH=100
+    /// XAYNET__SEED_EXPORT__PATH=/var/lib/xaynet/seeds
     /// ```
-    pub length: usize,
+    pub path: PathBuf,
+    /// The location under which the exported files are available to the sum participants, e.g.
+    /// the URL of a bucket which is synchronized with the `path`. The location of a file is the
+    /// base location followed by `/<round_id>/<sum_pk>`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [seed_export]
+    /// base_location = "https://seeds.example.com"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__SEED_EXPORT__BASE_LOCATION=https://seeds.example.com
+    /// ```
+    pub base_location: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
-/// Metrics settings.
-pub struct MetricsSettings {
+#[derive(Debug, Deserialize, Validate, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// Webhook settings. Disabled by default.
+///
+/// Once a round produced a new global model, the coordinator posts a JSON description of the
+/// outcome of the round to every endpoint. The body is signed with the HMAC-SHA256 of the shared
+/// secret of the endpoint, which is sent hex encoded in the `X-Xaynet-Signature` header. Failed
+/// deliveries are retried with an exponential backoff. The deliveries never block the round.
+pub struct WebhookSettings {
+    /// The endpoints which are notified about the outcome of each round.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [[webhooks.endpoints]]
+    /// url = "https://ml.example.com/hooks/xaynet"
+    /// secret = "a shared secret"
+    /// ```
     #[validate]
-    /// Settings for the InfluxDB backend.
-    pub influxdb: InfluxSettings,
-}
-
-#[derive(Debug, Deserialize, Validate)]
-/// InfluxDB settings.
-pub struct InfluxSettings {
-    #[validate(url)]
-    /// The URL where InfluxDB is running.
+    pub endpoints: Vec<WebhookEndpointSettings>,
+    /// Whether the endpoints are also notified about failed rounds. Defaults to `false`.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [metrics.influxdb]
-    /// url = "http://localhost:8086"
+    /// [webhooks]
+    /// on_failure = true
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__METRICS__INFLUXDB__URL=http://localhost:8086
+    /// XAYNET__WEBHOOKS__ON_FAILURE=true
     /// ```
-    pub url: String,
-
-    /// The InfluxDB database name.
+    #[serde(default)]
+    pub on_failure: bool,
+    /// The maximal number of delivery attempts per notification and endpoint. Defaults to `5`.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [metrics.influxdb]
-    /// db = "test"
+    /// [webhooks]
+    /// max_attempts = 5
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__METRICS__INFLUXDB__DB=test
+    /// XAYNET__WEBHOOKS__MAX_ATTEMPTS=5
     /// ```
-    pub db: String,
-}
-
-#[derive(Debug, Deserialize)]
-/// Redis settings.
-pub struct RedisSettings {
-    /// The URL where Redis is running.
+    #[serde(default = "default_webhook_max_attempts")]
+    #[validate(range(min = 1))]
+    pub max_attempts: u32,
+    /// The backoff in milliseconds before the first retry, which doubles with each further
+    /// retry. Defaults to `1000`.
     ///
-    /// The format of the URL is `redis://[<username>][:<passwd>@]<hostname>[:port][/<db>]`.
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [webhooks]
+    /// backoff = 1000
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET__WEBHOOKS__BACKOFF=1000
+    /// ```
+    #[serde(default = "default_webhook_backoff")]
+    pub backoff: u64,
+    /// The URL under which the global model is available for download, which is included in the
+    /// notifications about completed rounds. Disabled by default.
     ///
     /// # Examples
     ///
     /// **TOML**
     /// ```text
-    /// [redis]
-    /// url = "redis://127.0.0.1/"
+    /// [webhooks]
+    /// model_url = "https://coordinator.example.com/model"
     /// ```
     ///
     /// **Environment variable**
     /// ```text
-    /// XAYNET__REDIS__URL=redis://127.0.0.1/
+    /// XAYNET__WEBHOOKS__MODEL_URL=https://coordinator.example.com/model
     /// ```
-    #[serde(deserialize_with = "deserialize_redis_url")]
-    pub url: ConnectionInfo,
+    #[serde(default)]
+    pub model_url: Option<String>,
 }
 
-fn deserialize_redis_url<'de, D>(deserializer: D) -> Result<ConnectionInfo, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    struct ConnectionInfoVisitor;
-
-    impl<'de> Visitor<'de> for ConnectionInfoVisitor {
-        type Value = ConnectionInfo;
-
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            write!(
-                formatter,
-                "redis://[<username>][:<passwd>@]<hostname>[:port][/<db>]"
-            )
-        }
-
-        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-        where
-            E: de::Error,
-        {
-            value
-                .into_connection_info()
-                .map_err(|_| de::Error::invalid_value(serde::de::Unexpected::Str(value), &self))
-        }
-    }
+#[derive(Debug, Deserialize, Validate, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+#[validate(schema(function = "validate_webhook_endpoint"))]
+/// A webhook endpoint.
+pub struct WebhookEndpointSettings {
+    /// The HTTPS URL to which the notifications are posted.
+    pub url: String,
+    /// The secret shared with the endpoint from which the signatures are derived.
+    pub secret: String,
+}
 
-    deserializer.deserialize_str(ConnectionInfoVisitor)
+/// The default maximal number of delivery attempts of a webhook notification.
+fn default_webhook_max_attempts() -> u32 {
+    5
 }
 
-#[derive(Debug, Deserialize, Validate)]
-/// Trust anchor settings.
-pub struct TrustAnchorSettings {}
+/// The default backoff before the first retry of a webhook notification in milliseconds.
+fn default_webhook_backoff() -> u64 {
+    1000
+}
 
-// Default value for the global models bucket
-impl Default for TrustAnchorSettings {
-    fn default() -> Self {
-        Self {}
+/// Checks that a webhook endpoint is served via HTTPS and has a secret.
+fn validate_webhook_endpoint(s: &WebhookEndpointSettings) -> Result<(), ValidationError> {
+    if s.url.starts_with("https://") && !s.secret.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid webhook endpoint"))
     }
 }
 
@@ -758,6 +2576,8 @@ mod tests {
     impl Default for PetSettings {
         fn default() -> Self {
             Self {
+                mode: PetMode::Pet,
+                coordinator_mode: CoordinatorMode::Full,
                 sum: PetSettingsSum {
                     prob: 0.01,
                     count: PetSettingsCount { min: 10, max: 100 },
@@ -765,6 +2585,9 @@ mod tests {
                         min: 0,
                         max: 604800,
                     },
+                    dict_capacity: None,
+                    require_multiparty: false,
+                    distinct_identities: false,
                 },
                 update: PetSettingsUpdate {
                     prob: 0.1,
@@ -776,6 +2599,12 @@ mod tests {
                         min: 0,
                         max: 604800,
                     },
+                    aggregation_memory_limit: None,
+                    on_seed_dict_mismatch: SeedDictMismatchPolicy::Warn,
+                    aggregation: AggregationStrategy::Mean,
+                    trim: 0,
+                    allow_sum_eligible_updates: false,
+                    checkpoint: None,
                 },
                 sum2: PetSettingsSum2 {
                     count: PetSettingsCount { min: 10, max: 100 },
@@ -783,7 +2612,16 @@ mod tests {
                         min: 0,
                         max: 604800,
                     },
+                    on_tie: MaskTiePolicy::Fail,
+                    majority_fraction: 0.5,
                 },
+                warm_up: None,
+                commit_round_params: false,
+                phase_soft_deadline: None,
+                inter_round_delay: None,
+                processing_budget: None,
+                eligibility_salt: None,
+                crypto_suite: CryptoSuite::SHA256,
             }
         }
     }
@@ -795,6 +2633,7 @@ mod tests {
                 data_type: DataType::F32,
                 bound_type: BoundType::B0,
                 model_type: ModelType::M3,
+                round_bound_masks: false,
             }
         }
     }
@@ -805,6 +2644,195 @@ mod tests {
         assert!(Settings::new("").is_err());
     }
 
+    #[test]
+    fn test_deserialize_fraction() {
+        #[derive(Deserialize)]
+        struct Fraction {
+            #[serde(deserialize_with = "deserialize_fraction")]
+            prob: f64,
+        }
+        let prob = |value| {
+            serde_json::from_value::<Fraction>(serde_json::json!({ "prob": value }))
+                .map(|fraction| fraction.prob)
+        };
+
+        assert!((prob(serde_json::json!(0.01)).unwrap() - 0.01).abs() <= f64::EPSILON);
+        assert!((prob(serde_json::json!(1)).unwrap() - 1.).abs() <= f64::EPSILON);
+        assert!((prob(serde_json::json!("0.01")).unwrap() - 0.01).abs() <= f64::EPSILON);
+        assert!((prob(serde_json::json!("100bp")).unwrap() - 0.01).abs() <= f64::EPSILON);
+        assert!((prob(serde_json::json!("10000 bp")).unwrap() - 1.).abs() <= f64::EPSILON);
+        assert!(prob(serde_json::json!("-1bp")).is_err());
+        assert!(prob(serde_json::json!("1%")).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_mask_tie_policy() {
+        let sum2 = |value| {
+            serde_json::from_value::<PetSettingsSum2>(serde_json::json!({
+                "count": { "min": 1, "max": 10 },
+                "time": { "min": 0, "max": 10 },
+                "on_tie": value,
+            }))
+            .map(|sum2| (sum2.on_tie, sum2.majority_fraction))
+        };
+
+        assert_eq!(
+            sum2(serde_json::json!("fail")).unwrap().0,
+            MaskTiePolicy::Fail
+        );
+        assert_eq!(
+            sum2(serde_json::json!("lowest_hash")).unwrap().0,
+            MaskTiePolicy::LowestHash
+        );
+        let (on_tie, majority_fraction) =
+            sum2(serde_json::json!("require_majority_fraction")).unwrap();
+        assert_eq!(on_tie, MaskTiePolicy::RequireMajorityFraction);
+        assert!((majority_fraction - 0.5).abs() <= f64::EPSILON);
+        assert!(sum2(serde_json::json!("highest_hash")).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_seed_dict_mismatch_policy() {
+        let update = |value: Option<&str>| {
+            let mut update = serde_json::json!({
+                "prob": 0.1,
+                "count": { "min": 3, "max": 10 },
+                "time": { "min": 0, "max": 10 },
+            });
+            if let Some(value) = value {
+                update["on_seed_dict_mismatch"] = value.into();
+            }
+            serde_json::from_value::<PetSettingsUpdate>(update)
+                .map(|update| update.on_seed_dict_mismatch)
+        };
+
+        assert_eq!(update(None).unwrap(), SeedDictMismatchPolicy::Warn);
+        assert_eq!(update(Some("warn")).unwrap(), SeedDictMismatchPolicy::Warn);
+        assert_eq!(update(Some("fail")).unwrap(), SeedDictMismatchPolicy::Fail);
+        assert!(update(Some("ignore")).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_pet_mode() {
+        let pet = |mode: Option<&str>| {
+            let mut pet = serde_json::json!({
+                "sum": {
+                    "prob": 0.01,
+                    "count": { "min": 10, "max": 100 },
+                    "time": { "min": 0, "max": 10 },
+                },
+                "update": {
+                    "prob": 0.1,
+                    "count": { "min": 100, "max": 1000 },
+                    "time": { "min": 0, "max": 10 },
+                },
+                "sum2": {
+                    "count": { "min": 10, "max": 100 },
+                    "time": { "min": 0, "max": 10 },
+                },
+            });
+            if let Some(mode) = mode {
+                pet["mode"] = mode.into();
+            }
+            serde_json::from_value::<PetSettings>(pet).map(|pet| pet.mode)
+        };
+
+        assert_eq!(pet(None).unwrap(), PetMode::Pet);
+        assert_eq!(pet(Some("pet")).unwrap(), PetMode::Pet);
+        assert_eq!(pet(Some("trusted")).unwrap(), PetMode::Trusted);
+        assert!(pet(Some("untrusted")).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_aggregation_strategy() {
+        let update = |value: Option<&str>| {
+            let mut update = serde_json::json!({
+                "prob": 0.1,
+                "count": { "min": 3, "max": 10 },
+                "time": { "min": 0, "max": 10 },
+            });
+            if let Some(value) = value {
+                update["aggregation"] = value.into();
+                update["trim"] = 1.into();
+            }
+            serde_json::from_value::<PetSettingsUpdate>(update)
+                .map(|update| (update.aggregation, update.trim))
+        };
+
+        assert_eq!(update(None).unwrap(), (AggregationStrategy::Mean, 0));
+        assert_eq!(
+            update(Some("mean")).unwrap(),
+            (AggregationStrategy::Mean, 1)
+        );
+        assert_eq!(
+            update(Some("trimmed_mean")).unwrap(),
+            (AggregationStrategy::TrimmedMean, 1)
+        );
+        assert!(update(Some("median")).is_err());
+    }
+
+    #[test]
+    fn test_validate_trimmed_mean_aggregation() {
+        let mut pet = PetSettings::default();
+        pet.update.aggregation = AggregationStrategy::TrimmedMean;
+        pet.update.trim = 1;
+        assert!(pet.validate().is_err());
+
+        pet.mode = PetMode::Trusted;
+        assert!(pet.validate().is_ok());
+
+        pet.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+            updates: Some(1),
+            interval: None,
+        });
+        assert!(pet.validate().is_err());
+        pet.update.checkpoint = None;
+
+        pet.update.trim = 2;
+        pet.update.count.min = 4;
+        assert!(pet.validate().is_err());
+        pet.update.count.min = 5;
+        assert!(pet.validate().is_ok());
+
+        pet.warm_up = Some(PetSettingsWarmUp {
+            rounds: 1,
+            sum_min: SUM_COUNT_MIN,
+            update_min: 4,
+        });
+        assert!(pet.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_pet_modes() {
+        let mut pet = PetSettings {
+            coordinator_mode: CoordinatorMode::CollectOnly,
+            ..PetSettings::default()
+        };
+        assert!(pet.validate().is_ok());
+
+        pet.mode = PetMode::Trusted;
+        assert!(pet.validate().is_err());
+
+        pet.coordinator_mode = CoordinatorMode::Full;
+        assert!(pet.validate().is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_compression_level() {
+        let api = |value: Option<u32>| {
+            let mut api = serde_json::json!({ "bind_address": "127.0.0.1:8081" });
+            if let Some(value) = value {
+                api["compression_level"] = value.into();
+            }
+            serde_json::from_value::<ApiSettings>(api).map(|api| api.compression_level)
+        };
+
+        assert_eq!(api(None).unwrap(), 6);
+        assert_eq!(api(Some(0)).unwrap(), 0);
+        assert_eq!(api(Some(9)).unwrap(), 9);
+        assert!(api(Some(10)).is_err());
+    }
+
     #[test]
     fn test_validate_pet() {
         assert!(PetSettings::default().validate_pet().is_ok());
@@ -888,6 +2916,344 @@ mod tests {
         let mut pet = PetSettings::default();
         pet.update.prob = 1. + f64::EPSILON;
         assert!(pet.validate().is_err());
+
+        // the fractions round to 0 basis points
+        let mut pet = PetSettings::default();
+        pet.sum.prob = 0.00004;
+        assert!(pet.validate().is_err());
+
+        let mut pet = PetSettings::default();
+        pet.update.prob = 0.00004;
+        assert!(pet.validate().is_err());
+
+        // the sum fraction rounds to 10000 basis points
+        let mut pet = PetSettings::default();
+        pet.sum.prob = 0.99996;
+        assert!(pet.validate().is_err());
+
+        let mut pet = PetSettings::default();
+        pet.update.prob = 0.99996;
+        assert!(pet.validate().is_ok());
+    }
+
+    #[test]
+    fn test_pet_deadlock_margin() {
+        let mut pet = PetSettings::default();
+        pet.sum.prob = 0.5;
+        pet.update.prob = 0.5;
+        assert!((pet.deadlock_margin() - 0.25).abs() < f64::EPSILON);
+
+        let mut pet = PetSettings::default();
+        pet.sum.prob = 0.0001;
+        pet.update.prob = 0.0001;
+        assert!(pet.validate().is_ok());
+        assert!(pet.deadlock_margin() < 0.001);
+
+        let mut pet = PetSettings::default();
+        pet.sum.prob = 0.9999;
+        pet.update.prob = 0.9;
+        assert!(pet.validate().is_ok());
+        assert!(pet.deadlock_margin() < 0.001);
+    }
+
+    #[test]
+    fn test_validate_pet_majority_fraction() {
+        let mut pet = PetSettings::default();
+        pet.sum2.majority_fraction = 1.;
+        assert!(pet.validate().is_ok());
+
+        pet.sum2.majority_fraction = 0.;
+        assert!(pet.validate().is_err());
+
+        pet.sum2.majority_fraction = 1. + f64::EPSILON;
+        assert!(pet.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_pet_warm_up() {
+        let mut pet = PetSettings::default();
+        let warm_up = PetSettingsWarmUp {
+            rounds: 3,
+            sum_min: SUM_COUNT_MIN,
+            update_min: UPDATE_COUNT_MIN,
+        };
+        pet.warm_up = Some(warm_up);
+        assert!(pet.validate().is_ok());
+
+        // the warm-up counts may equal the phase counts
+        pet.warm_up = Some(PetSettingsWarmUp {
+            sum_min: pet.sum.count.min,
+            update_min: pet.update.count.min,
+            ..warm_up
+        });
+        assert!(pet.validate().is_ok());
+
+        // the warm-up counts must not undercut the protocol minimums
+        pet.warm_up = Some(PetSettingsWarmUp {
+            sum_min: SUM_COUNT_MIN - 1,
+            ..warm_up
+        });
+        assert!(pet.validate().is_err());
+        pet.warm_up = Some(PetSettingsWarmUp {
+            update_min: UPDATE_COUNT_MIN - 1,
+            ..warm_up
+        });
+        assert!(pet.validate().is_err());
+
+        // the warm-up counts must not exceed the phase counts
+        pet.warm_up = Some(PetSettingsWarmUp {
+            sum_min: pet.sum.count.min + 1,
+            ..warm_up
+        });
+        assert!(pet.validate().is_err());
+        pet.warm_up = Some(PetSettingsWarmUp {
+            update_min: pet.update.count.min + 1,
+            ..warm_up
+        });
+        assert!(pet.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_pet_warm_up() {
+        let warm_up = serde_json::from_value::<PetSettingsWarmUp>(serde_json::json!({
+            "rounds": 3,
+        }))
+        .unwrap();
+        assert_eq!(
+            warm_up,
+            PetSettingsWarmUp {
+                rounds: 3,
+                sum_min: SUM_COUNT_MIN,
+                update_min: UPDATE_COUNT_MIN,
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_pet_processing_budget() {
+        let mut pet = PetSettings::default();
+        let budget = PetSettingsProcessingBudget {
+            requests: 1,
+            slow_request: 0,
+        };
+        pet.processing_budget = Some(budget);
+        assert!(pet.validate().is_ok());
+
+        pet.processing_budget = Some(PetSettingsProcessingBudget {
+            requests: 0,
+            ..budget
+        });
+        assert!(pet.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_pet_crypto_suite() {
+        let mut pet = PetSettings::default();
+        assert!(pet.validate().is_ok());
+
+        pet.crypto_suite = CryptoSuite::new(u8::MAX);
+        assert!(pet.validate().is_err());
+
+        pet.crypto_suite = CryptoSuite::BLAKE3;
+        assert_eq!(pet.validate().is_ok(), cfg!(feature = "blake3"));
+    }
+
+    #[test]
+    fn test_deserialize_pet_processing_budget() {
+        let budget = serde_json::from_value::<PetSettingsProcessingBudget>(serde_json::json!({
+            "requests": 16,
+        }))
+        .unwrap();
+        assert_eq!(
+            budget,
+            PetSettingsProcessingBudget {
+                requests: 16,
+                slow_request: default_processing_budget_slow_request(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_pet_update_checkpoint() {
+        let mut pet = PetSettings::default();
+        let checkpoint = PetSettingsUpdateCheckpoint {
+            updates: Some(1),
+            interval: None,
+        };
+        pet.update.checkpoint = Some(checkpoint);
+        assert!(pet.validate().is_ok());
+        pet.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+            updates: None,
+            interval: Some(1),
+        });
+        assert!(pet.validate().is_ok());
+
+        pet.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+            updates: None,
+            ..checkpoint
+        });
+        assert!(pet.validate().is_err());
+        pet.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+            updates: Some(0),
+            ..checkpoint
+        });
+        assert!(pet.validate().is_err());
+        pet.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+            interval: Some(0),
+            ..checkpoint
+        });
+        assert!(pet.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_denylist() {
+        let pk = ParticipantPublicKey::fill_with(1);
+        let denylist = serde_json::from_value::<DenylistSettings>(serde_json::json!({
+            "keys": [base64::encode(pk.as_slice())],
+        }))
+        .unwrap();
+        assert_eq!(
+            denylist,
+            DenylistSettings {
+                keys: vec![pk],
+                capacity: default_denylist_capacity(),
+            }
+        );
+        assert!(denylist.validate().is_ok());
+
+        assert!(
+            serde_json::from_value::<DenylistSettings>(serde_json::json!({
+                "keys": ["not a key"],
+            }))
+            .is_err()
+        );
+
+        let denylist = DenylistSettings {
+            capacity: 0,
+            ..denylist
+        };
+        assert!(denylist.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_circuit_breaker() {
+        let circuit_breaker =
+            serde_json::from_value::<CircuitBreakerSettings>(serde_json::json!({
+                "retries": 3,
+            }))
+            .unwrap();
+        assert_eq!(
+            circuit_breaker,
+            CircuitBreakerSettings {
+                retries: 3,
+                ..CircuitBreakerSettings::default()
+            }
+        );
+        assert!(circuit_breaker.validate().is_ok());
+
+        let circuit_breaker = CircuitBreakerSettings {
+            error_rate: 1.5,
+            ..circuit_breaker
+        };
+        assert!(circuit_breaker.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_leader() {
+        let leader = serde_json::from_value::<LeaderSettings>(serde_json::json!({
+            "address": "http://10.0.0.1:8081",
+        }))
+        .unwrap();
+        assert_eq!(
+            leader,
+            LeaderSettings {
+                address: "http://10.0.0.1:8081".to_string(),
+                ttl: default_leader_ttl(),
+                renew_interval: default_leader_renew_interval(),
+            }
+        );
+        assert!(leader.validate().is_ok());
+
+        let leader = LeaderSettings {
+            renew_interval: leader.ttl,
+            ..leader
+        };
+        assert!(leader.validate().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_webhooks() {
+        let webhooks = serde_json::from_value::<WebhookSettings>(serde_json::json!({
+            "endpoints": [{"url": "https://ml.example.com/hooks", "secret": "secret"}],
+        }))
+        .unwrap();
+        assert_eq!(
+            webhooks,
+            WebhookSettings {
+                endpoints: vec![WebhookEndpointSettings {
+                    url: "https://ml.example.com/hooks".to_string(),
+                    secret: "secret".to_string(),
+                }],
+                on_failure: false,
+                max_attempts: default_webhook_max_attempts(),
+                backoff: default_webhook_backoff(),
+                model_url: None,
+            }
+        );
+        assert!(webhooks.validate().is_ok());
+
+        for endpoint in &[
+            WebhookEndpointSettings {
+                url: "http://ml.example.com/hooks".to_string(),
+                secret: "secret".to_string(),
+            },
+            WebhookEndpointSettings {
+                url: "https://ml.example.com/hooks".to_string(),
+                secret: String::new(),
+            },
+        ] {
+            let webhooks = WebhookSettings {
+                endpoints: vec![endpoint.clone()],
+                ..webhooks.clone()
+            };
+            assert!(webhooks.validate().is_err());
+        }
+
+        let webhooks = WebhookSettings {
+            max_attempts: 0,
+            ..webhooks
+        };
+        assert!(webhooks.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_pet_multiparty() {
+        let mut pet = PetSettings::default();
+        pet.sum.count.min = 1;
+        pet.sum.require_multiparty = true;
+        assert!(pet.validate().is_ok());
+
+        pet.sum.dict_capacity = Some(1);
+        assert!(pet.validate().is_err());
+        pet.sum.dict_capacity = Some(MULTIPARTY_SUM_COUNT_MIN);
+        assert!(pet.validate().is_ok());
+
+        pet.sum.count.max = 1;
+        pet.sum2.count.min = 1;
+        pet.sum2.count.max = 1;
+        assert!(pet.validate().is_err());
+        pet.sum.require_multiparty = false;
+        assert!(pet.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_pet_sum_dict_capacity() {
+        let mut pet = PetSettings::default();
+        pet.sum.dict_capacity = Some(pet.sum.count.min);
+        assert!(pet.validate().is_ok());
+
+        pet.sum.dict_capacity = Some(pet.sum.count.min - 1);
+        assert!(pet.validate().is_err());
     }
 
     #[cfg(feature = "tls")]
@@ -901,6 +3267,17 @@ mod tests {
             tls_certificate: some_path.clone(),
             tls_key: some_path.clone(),
             tls_client_auth: some_path.clone(),
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_ok());
@@ -909,6 +3286,17 @@ mod tests {
             tls_certificate: some_path.clone(),
             tls_key: some_path.clone(),
             tls_client_auth: None,
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_ok());
@@ -917,6 +3305,17 @@ mod tests {
             tls_certificate: None,
             tls_key: None,
             tls_client_auth: some_path.clone(),
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_ok());
@@ -926,6 +3325,17 @@ mod tests {
             tls_certificate: some_path.clone(),
             tls_key: None,
             tls_client_auth: some_path.clone(),
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_err());
@@ -934,6 +3344,17 @@ mod tests {
             tls_certificate: None,
             tls_key: some_path.clone(),
             tls_client_auth: some_path.clone(),
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_err());
@@ -942,6 +3363,17 @@ mod tests {
             tls_certificate: some_path.clone(),
             tls_key: None,
             tls_client_auth: None,
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_err());
@@ -950,6 +3382,17 @@ mod tests {
             tls_certificate: None,
             tls_key: some_path,
             tls_client_auth: None,
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_err());
@@ -958,8 +3401,40 @@ mod tests {
             tls_certificate: None,
             tls_key: None,
             tls_client_auth: None,
+            admin_token: None,
+            request_timeout: 30,
+            model_request_timeout: 300,
+            shutdown_grace_period: 30,
+            compression_level: 6,
+            validate_rate_limit: 10,
+            max_message_size: None,
+            client_identity_header: None,
+            message_threads: None,
+            signature_batch_size: None,
+            signature_batch_delay: default_signature_batch_delay(),
         }
         .validate()
         .is_err());
     }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_client_ca_alias() {
+        let api: ApiSettings = Config::builder()
+            .add_source(File::from_str(
+                r#"
+                bind_address = "127.0.0.1:8081"
+                tls_certificate = "cert.pem"
+                tls_key = "key.rsa"
+                tls_client_ca = "trust_anchor.pem"
+                "#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        assert_eq!(api.tls_client_auth, Some(PathBuf::from("trust_anchor.pem")));
+        assert!(api.validate().is_ok());
+    }
 }
@@ -1,21 +1,43 @@
 //! Coordinator state and round parameter types.
 
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
 use serde::{Deserialize, Serialize};
 
-use crate::settings::{
-    MaskSettings,
-    ModelSettings,
-    PetSettings,
-    PetSettingsCount,
-    PetSettingsSum,
-    PetSettingsSum2,
-    PetSettingsTime,
-    PetSettingsUpdate,
+use crate::{
+    settings::{
+        AggregationStrategy,
+        CoordinatorMode,
+        MaskSettings,
+        MaskTiePolicy,
+        ModelSettings,
+        PetMode,
+        PetSettings,
+        PetSettingsCount,
+        PetSettingsProcessingBudget,
+        PetSettingsSum,
+        PetSettingsSum2,
+        PetSettingsTime,
+        PetSettingsUpdate,
+        PetSettingsUpdateCheckpoint,
+        PetSettingsWarmUp,
+        SeedDictMismatchPolicy,
+        MULTIPARTY_SUM_COUNT_MIN,
+    },
+    state_machine::{
+        requests::{RejectionReason, RequestError},
+        timings::RoundTimings,
+    },
 };
 use xaynet_core::{
-    common::{RoundParameters, RoundSeed},
-    crypto::{ByteObject, EncryptKeyPair},
+    common::{PhaseTimeline, RoundParameters, RoundSeed},
+    crypto::{ByteObject, EncryptKeyPair, SigningKeyPair},
     mask::MaskConfig,
+    SumDict,
+    SumParticipantPublicKey,
 };
 
 /// The phase count parameters.
@@ -81,7 +103,7 @@ impl From<PetSettingsUpdate> for PhaseParameters {
 
 impl From<PetSettingsSum2> for PhaseParameters {
     fn from(sum2: PetSettingsSum2) -> Self {
-        let PetSettingsSum2 { count, time } = sum2;
+        let PetSettingsSum2 { count, time, .. } = sum2;
         Self {
             count: count.into(),
             time: time.into(),
@@ -89,6 +111,130 @@ impl From<PetSettingsSum2> for PhaseParameters {
     }
 }
 
+/// The warm-up parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmUpParameters {
+    /// The number of warm-up rounds.
+    pub rounds: u64,
+    /// The minimal number of required sum messages during the warm-up rounds.
+    pub sum_min: u64,
+    /// The minimal number of required update messages during the warm-up rounds.
+    pub update_min: u64,
+}
+
+impl From<PetSettingsWarmUp> for WarmUpParameters {
+    fn from(warm_up: PetSettingsWarmUp) -> Self {
+        let PetSettingsWarmUp {
+            rounds,
+            sum_min,
+            update_min,
+        } = warm_up;
+        Self {
+            rounds,
+            sum_min,
+            update_min,
+        }
+    }
+}
+
+/// The budget of the request processing per scheduling of the state machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessingBudget {
+    /// The maximal number of requests processed in a row before yielding to the runtime.
+    pub requests: u64,
+    /// The processing time of a single request in milliseconds after which the state machine
+    /// yields to the runtime right away.
+    pub slow_request: u64,
+}
+
+impl From<PetSettingsProcessingBudget> for ProcessingBudget {
+    fn from(budget: PetSettingsProcessingBudget) -> Self {
+        let PetSettingsProcessingBudget {
+            requests,
+            slow_request,
+        } = budget;
+        Self {
+            requests,
+            slow_request,
+        }
+    }
+}
+
+/// The conditions to checkpoint the aggregation of the masked models in the update phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointParameters {
+    /// The number of accepted update messages after which a checkpoint is taken, if any.
+    pub updates: Option<u64>,
+    /// The time in seconds after which a checkpoint is taken, if any.
+    pub interval: Option<u64>,
+}
+
+impl From<PetSettingsUpdateCheckpoint> for CheckpointParameters {
+    fn from(checkpoint: PetSettingsUpdateCheckpoint) -> Self {
+        let PetSettingsUpdateCheckpoint { updates, interval } = checkpoint;
+        Self { updates, interval }
+    }
+}
+
+/// The policy to resolve a tie between the masks with the highest number of submissions.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TieBreaking {
+    /// Fail the round.
+    Fail,
+    /// Choose the tied mask with the lexicographically smallest hash.
+    LowestHash,
+    /// Choose the tied mask with the lexicographically smallest hash if it has been submitted with
+    /// at least the given fraction of all submitted masks.
+    RequireMajorityFraction(f64),
+}
+
+impl From<PetSettingsSum2> for TieBreaking {
+    fn from(sum2: PetSettingsSum2) -> Self {
+        match sum2.on_tie {
+            MaskTiePolicy::Fail => Self::Fail,
+            MaskTiePolicy::LowestHash => Self::LowestHash,
+            MaskTiePolicy::RequireMajorityFraction => {
+                Self::RequireMajorityFraction(sum2.majority_fraction)
+            }
+        }
+    }
+}
+
+/// A mismatch between the seed dictionary and the aggregation of the masked models of a round.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedDictMismatch {
+    /// The number of distinct update participants in the seed dictionary.
+    pub seed_dict_participants: u64,
+    /// The number of aggregated masked models.
+    pub aggregated_models: u64,
+}
+
+/// The credentials of the coordinator as the trusted aggregator of a round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrustedAggregator {
+    /// The public key under which the coordinator appears in the sum dictionary.
+    pub pk: SumParticipantPublicKey,
+    /// The ephemeral key pair for the encryption of the mask seeds of the update participants.
+    pub ephm_keys: EncryptKeyPair,
+}
+
+impl TrustedAggregator {
+    /// Generates fresh credentials for the trusted aggregator.
+    pub fn generate() -> Self {
+        Self {
+            pk: SigningKeyPair::generate().public,
+            ephm_keys: EncryptKeyPair::generate(),
+        }
+    }
+
+    /// Gets the sum dictionary which contains the trusted aggregator as the only entry.
+    pub fn sum_dict(&self) -> SumDict {
+        let mut sum_dict = SumDict::new();
+        sum_dict.insert(self.pk, self.ephm_keys.public);
+        sum_dict
+    }
+}
+
 /// The coordinator state.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CoordinatorState {
@@ -100,10 +246,55 @@ pub struct CoordinatorState {
     pub round_params: RoundParameters,
     /// The sum phase parameters.
     pub sum: PhaseParameters,
+    /// The maximal number of sum participants kept in the sum dictionary, if bounded.
+    pub sum_dict_capacity: Option<u64>,
+    /// Whether the sum phase requires at least two distinct sum participants.
+    pub require_multiparty: bool,
+    /// Whether the sum participants must present distinct client certificate identities.
+    pub distinct_sum_identities: bool,
     /// The update phase parameters.
     pub update: PhaseParameters,
+    /// The maximal memory in bytes of the aggregation of the masked models, if limited.
+    pub aggregation_memory_limit: Option<u64>,
+    /// The policy for a mismatch between the seed dictionary and the aggregated masked models.
+    pub seed_dict_mismatch_policy: SeedDictMismatchPolicy,
+    /// The conditions to checkpoint the aggregation of the masked models, if enabled.
+    pub aggregation_checkpoint: Option<CheckpointParameters>,
+    /// The number of weights trimmed from each end of a coordinate, if the global model is the
+    /// trimmed mean of the masked models instead of their mean.
+    pub trimmed_mean: Option<u64>,
     /// The sum2 phase parameters.
     pub sum2: PhaseParameters,
+    /// The warm-up parameters, if the first rounds run with relaxed counts.
+    pub warm_up: Option<WarmUpParameters>,
+    /// The policy to resolve a tie between the best masks of the sum2 phase.
+    pub tie_breaking: TieBreaking,
+    /// Whether the coordinator commits to the round parameters of the next round in advance.
+    pub commit_round_params: bool,
+    /// The seed of the next round, if the coordinator already committed to it.
+    pub next_seed: Option<RoundSeed>,
+    /// The soft deadline of a phase in seconds. Exceeding it only raises a warning.
+    pub phase_soft_deadline: Option<u64>,
+    /// The delay in seconds between the end of a round and the start of the next round, if any.
+    pub inter_round_delay: Option<u64>,
+    /// The budget of the request processing, if the state machine yields to the runtime regularly.
+    pub processing_budget: Option<ProcessingBudget>,
+    /// The timestamps of the phase transitions of the current round.
+    pub timings: RoundTimings,
+    /// The timestamps of the phase transitions of the previous round.
+    pub last_timings: Option<RoundTimings>,
+    /// The numbers of rejected requests of the current round per reason.
+    pub rejections: HashMap<RejectionReason, u64>,
+    /// The policy which resolved a tie between the best masks of the current round, if any.
+    pub broken_tie: Option<TieBreaking>,
+    /// The mismatch between the seed dictionary and the aggregated masked models of the current
+    /// round, if any.
+    pub seed_dict_mismatch: Option<SeedDictMismatch>,
+    /// The credentials of the trusted aggregator of the current round, if the coordinator runs in
+    /// trusted mode.
+    pub trusted_aggregator: Option<TrustedAggregator>,
+    /// The extent of the rounds which the coordinator runs.
+    pub mode: CoordinatorMode,
 }
 
 impl CoordinatorState {
@@ -113,13 +304,32 @@ impl CoordinatorState {
         model_settings: ModelSettings,
     ) -> Self {
         let keys = EncryptKeyPair::generate();
+        let trusted_aggregator = match pet_settings.mode {
+            PetMode::Pet => None,
+            PetMode::Trusted => Some(TrustedAggregator::generate()),
+        };
         let round_params = RoundParameters {
             pk: keys.public,
-            sum: pet_settings.sum.prob,
+            // the trusted aggregator replaces the sum participants, hence nobody is selected
+            sum: if trusted_aggregator.is_some() {
+                0.
+            } else {
+                pet_settings.sum.prob
+            },
             update: pet_settings.update.prob,
             seed: RoundSeed::zeroed(),
             mask_config: MaskConfig::from(mask_settings).into(),
             model_length: model_settings.length,
+            next_commitment: None,
+            round_bound_masks: mask_settings.round_bound_masks,
+            eligibility_salt: pet_settings
+                .eligibility_salt
+                .clone()
+                .map(String::into_bytes)
+                .unwrap_or_default(),
+            allow_sum_eligible_updates: pet_settings.update.allow_sum_eligible_updates,
+            timeline: None,
+            crypto_suite: pet_settings.crypto_suite,
         };
         let round_id = 0;
         Self {
@@ -127,8 +337,139 @@ impl CoordinatorState {
             round_params,
             round_id,
             sum: pet_settings.sum.into(),
+            sum_dict_capacity: pet_settings.sum.dict_capacity,
+            require_multiparty: pet_settings.sum.require_multiparty,
+            distinct_sum_identities: pet_settings.sum.distinct_identities,
             update: pet_settings.update.into(),
+            aggregation_memory_limit: pet_settings.update.aggregation_memory_limit,
+            seed_dict_mismatch_policy: pet_settings.update.on_seed_dict_mismatch,
+            aggregation_checkpoint: pet_settings.update.checkpoint.map(Into::into),
+            trimmed_mean: match pet_settings.update.aggregation {
+                AggregationStrategy::Mean => None,
+                AggregationStrategy::TrimmedMean => Some(pet_settings.update.trim),
+            },
             sum2: pet_settings.sum2.into(),
+            warm_up: pet_settings.warm_up.map(Into::into),
+            tie_breaking: pet_settings.sum2.into(),
+            commit_round_params: pet_settings.commit_round_params,
+            next_seed: None,
+            phase_soft_deadline: pet_settings.phase_soft_deadline,
+            inter_round_delay: pet_settings.inter_round_delay,
+            processing_budget: pet_settings.processing_budget.map(Into::into),
+            timings: RoundTimings::new(round_id),
+            last_timings: None,
+            rejections: HashMap::new(),
+            broken_tie: None,
+            seed_dict_mismatch: None,
+            trusted_aggregator,
+            mode: pet_settings.coordinator_mode,
+        }
+    }
+
+    /// Checks whether the coordinator acts as the trusted aggregator instead of sum participants.
+    pub fn is_trusted(&self) -> bool {
+        self.trusted_aggregator.is_some()
+    }
+
+    /// Checks whether the current round is a warm-up round.
+    ///
+    /// The warm-up rounds are the first rounds of the coordinator, counted by the round id.
+    pub fn is_warm_up(&self) -> bool {
+        matches!(self.warm_up, Some(warm_up) if self.round_id <= warm_up.rounds)
+    }
+
+    /// Gets the sum phase parameters of the current round.
+    ///
+    /// The minimal count is relaxed during the warm-up rounds, but it never falls below two if
+    /// multiparty sums are required.
+    pub fn sum_params(&self) -> PhaseParameters {
+        let mut sum = self.sum;
+        sum.count.min = if self.is_multiparty_enforced() {
+            MULTIPARTY_SUM_COUNT_MIN
+        } else {
+            self.relaxed_sum_min()
+        };
+        sum
+    }
+
+    /// Checks whether the multiparty requirement raises the minimal sum count of the current
+    /// round.
+    pub fn is_multiparty_enforced(&self) -> bool {
+        self.require_multiparty && self.relaxed_sum_min() < MULTIPARTY_SUM_COUNT_MIN
+    }
+
+    /// Gets the minimal sum count of the current round, relaxed during the warm-up rounds.
+    fn relaxed_sum_min(&self) -> u64 {
+        match self.warm_up {
+            Some(warm_up) if self.is_warm_up() => warm_up.sum_min,
+            _ => self.sum.count.min,
+        }
+    }
+
+    /// Gets the update phase parameters of the current round.
+    ///
+    /// The minimal count is relaxed during the warm-up rounds.
+    pub fn update_params(&self) -> PhaseParameters {
+        let mut update = self.update;
+        if let Some(warm_up) = self.warm_up.filter(|_| self.is_warm_up()) {
+            update.count.min = warm_up.update_min;
+        }
+        update
+    }
+
+    /// Gets the sum2 phase parameters of the current round.
+    ///
+    /// The minimal count is relaxed to the minimal sum count during the warm-up rounds, since
+    /// there may not be more sum participants.
+    pub fn sum2_params(&self) -> PhaseParameters {
+        let mut sum2 = self.sum2;
+        if let Some(warm_up) = self.warm_up.filter(|_| self.is_warm_up()) {
+            sum2.count.min = sum2.count.min.min(warm_up.sum_min);
+        }
+        sum2
+    }
+
+    /// Checks whether the coordinator ends the rounds after the update phase.
+    pub fn is_collect_only(&self) -> bool {
+        self.mode == CoordinatorMode::CollectOnly
+    }
+
+    /// Gets the timeline of the phases of the current round, which starts at `round_start`.
+    ///
+    /// The deadlines follow from the maximal phase times. The sum phase is skipped in trusted
+    /// mode and the sum2 phase is skipped in trusted and collect only mode.
+    pub fn phase_timeline(&self, round_start: SystemTime) -> PhaseTimeline {
+        let sum_max = if self.is_trusted() {
+            0
+        } else {
+            self.sum_params().time.max
+        };
+        let sum2_max = if self.is_trusted() || self.is_collect_only() {
+            0
+        } else {
+            self.sum2_params().time.max
+        };
+        PhaseTimeline::new(
+            round_start,
+            Duration::from_secs(sum_max),
+            Duration::from_secs(self.update_params().time.max),
+            Duration::from_secs(sum2_max),
+        )
+    }
+
+    /// Gets the numbers of rejected requests of the current round per reason.
+    ///
+    /// The numbers are reset when a new round starts.
+    pub fn rejection_stats(&self) -> &HashMap<RejectionReason, u64> {
+        &self.rejections
+    }
+
+    /// Counts a request of the current round which failed with the given `error`.
+    ///
+    /// Discarded requests are not counted as rejected.
+    pub(in crate::state_machine) fn record_rejection(&mut self, error: &RequestError) {
+        if let Some(reason) = error.rejection_reason() {
+            *self.rejections.entry(reason).or_default() += 1;
         }
     }
 }
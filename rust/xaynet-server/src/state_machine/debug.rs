@@ -0,0 +1,624 @@
+//! Human-readable dumps of the coordinator state for debugging purposes.
+//!
+//! The coordinator state and dictionaries are only stored in a binary representation. The dumps
+//! provided by this module serialize them as JSON instead, with public keys encoded as base64 and
+//! encrypted mask seeds truncated to a short prefix.
+//!
+//! Secrets are left out structurally: only the types implementing [`RedactedSerialize`] can be
+//! dumped and the types that wrap secrets implement it by serializing their public parts only.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    settings::{CoordinatorMode, SeedDictMismatchPolicy},
+    state_machine::{
+        coordinator::{
+            CheckpointParameters,
+            CoordinatorState,
+            PhaseParameters,
+            ProcessingBudget,
+            SeedDictMismatch,
+            TieBreaking,
+            TrustedAggregator,
+            WarmUpParameters,
+        },
+        events::{EventListener, EventSubscriber},
+        phases::PhaseName,
+        requests::RejectionReason,
+        timings::{PhaseTiming, RoundTimings},
+    },
+    storage::{CoordinatorStorage, StorageResult},
+};
+use xaynet_core::{
+    common::{PhaseTimeline, RoundParameters, RoundSeed},
+    crypto::{ByteObject, EncryptKeyPair, PublicEncryptKey, PublicSigningKey, Sha256},
+    mask::{EncryptedMaskSeed, MaskConfigPair},
+    SeedDict,
+    SumDict,
+    UpdateSeedDict,
+};
+
+/// The number of leading bytes of an encrypted mask seed which are kept in a dump.
+const ENCRYPTED_SEED_PREFIX_LENGTH: usize = 8;
+
+/// A serialization into a human-readable representation which leaves out all secrets.
+///
+/// Unlike [`serde::Serialize`], this is only implemented for types which are safe to dump. Types
+/// that wrap secrets implement it by serializing their public parts only.
+pub trait RedactedSerialize {
+    /// Serializes this value into a JSON value without any secrets.
+    fn redacted(&self) -> Value;
+}
+
+/// Implements [`RedactedSerialize`] for public byte objects as their base64 encoding.
+macro_rules! impl_redacted_serialize_base64 {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl RedactedSerialize for $ty {
+                fn redacted(&self) -> Value {
+                    Value::String(base64::encode(self.as_slice()))
+                }
+            }
+        )+
+    };
+}
+
+impl_redacted_serialize_base64!(PublicEncryptKey, PublicSigningKey, RoundSeed, Sha256);
+
+impl RedactedSerialize for EncryptKeyPair {
+    fn redacted(&self) -> Value {
+        // the secret key is deliberately left out
+        json!({ "public": self.public.redacted() })
+    }
+}
+
+impl RedactedSerialize for EncryptedMaskSeed {
+    fn redacted(&self) -> Value {
+        let bytes = self.as_slice();
+        let prefix = &bytes[..bytes.len().min(ENCRYPTED_SEED_PREFIX_LENGTH)];
+        json!({
+            "prefix": base64::encode(prefix),
+            "length": bytes.len(),
+        })
+    }
+}
+
+/// Implements [`RedactedSerialize`] for types without secrets as their serde serialization.
+macro_rules! impl_redacted_serialize_public {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl RedactedSerialize for $ty {
+                fn redacted(&self) -> Value {
+                    // safe unwrap: the types only consist of numbers, booleans and enums
+                    serde_json::to_value(self).unwrap()
+                }
+            }
+        )+
+    };
+}
+
+impl_redacted_serialize_public!(
+    CheckpointParameters,
+    CoordinatorMode,
+    MaskConfigPair,
+    PhaseParameters,
+    PhaseTimeline,
+    ProcessingBudget,
+    RejectionReason,
+    SeedDictMismatch,
+    SeedDictMismatchPolicy,
+    TieBreaking,
+    WarmUpParameters,
+    u64,
+);
+
+impl RedactedSerialize for RoundParameters {
+    fn redacted(&self) -> Value {
+        json!({
+            "pk": self.pk.redacted(),
+            "sum": self.sum,
+            "update": self.update,
+            "seed": self.seed.redacted(),
+            "round_token": self.seed.token(),
+            "mask_config": self.mask_config.redacted(),
+            "model_length": self.model_length,
+            "next_commitment": self.next_commitment.redacted(),
+            "round_bound_masks": self.round_bound_masks,
+            "eligibility_salt": String::from_utf8_lossy(&self.eligibility_salt),
+            "allow_sum_eligible_updates": self.allow_sum_eligible_updates,
+            "timeline": self.timeline.redacted(),
+            "crypto_suite": self.crypto_suite.id(),
+        })
+    }
+}
+
+impl RedactedSerialize for PhaseTiming {
+    fn redacted(&self) -> Value {
+        json!({
+            "phase": self.phase.to_string(),
+            "entered_at": self.entered_at,
+            "left_at": self.left_at,
+            "duration_ms": self.duration().map(|duration| duration.as_millis() as u64),
+            "soft_deadline_exceeded": self.soft_deadline_exceeded,
+        })
+    }
+}
+
+impl RedactedSerialize for RoundTimings {
+    fn redacted(&self) -> Value {
+        json!({
+            "round_id": self.round_id,
+            "phases": self.phases.redacted(),
+            "duration_ms": self.duration().map(|duration| duration.as_millis() as u64),
+        })
+    }
+}
+
+impl RedactedSerialize for TrustedAggregator {
+    fn redacted(&self) -> Value {
+        json!({
+            "pk": self.pk.redacted(),
+            "ephm_keys": self.ephm_keys.redacted(),
+        })
+    }
+}
+
+impl RedactedSerialize for CoordinatorState {
+    fn redacted(&self) -> Value {
+        json!({
+            "round_id": self.round_id,
+            "keys": self.keys.redacted(),
+            "round_params": self.round_params.redacted(),
+            "sum": self.sum.redacted(),
+            "sum_dict_capacity": self.sum_dict_capacity,
+            "require_multiparty": self.require_multiparty,
+            "distinct_sum_identities": self.distinct_sum_identities,
+            "update": self.update.redacted(),
+            "aggregation_memory_limit": self.aggregation_memory_limit,
+            "seed_dict_mismatch_policy": self.seed_dict_mismatch_policy.redacted(),
+            "aggregation_checkpoint": self.aggregation_checkpoint.redacted(),
+            "sum2": self.sum2.redacted(),
+            "warm_up": self.warm_up.redacted(),
+            "commit_round_params": self.commit_round_params,
+            // the seed of the next round must not be revealed before the round starts
+            "next_seed_committed": self.next_seed.is_some(),
+            "phase_soft_deadline": self.phase_soft_deadline,
+            "inter_round_delay": self.inter_round_delay,
+            "processing_budget": self.processing_budget.redacted(),
+            "timings": self.timings.redacted(),
+            "last_timings": self.last_timings.redacted(),
+            "rejections": self.rejections.redacted(),
+            "tie_breaking": self.tie_breaking.redacted(),
+            "broken_tie": self.broken_tie.redacted(),
+            "seed_dict_mismatch": self.seed_dict_mismatch.redacted(),
+            "trusted_aggregator": self.trusted_aggregator.redacted(),
+            "mode": self.mode.redacted(),
+        })
+    }
+}
+
+impl<T: RedactedSerialize> RedactedSerialize for Option<T> {
+    fn redacted(&self) -> Value {
+        self.as_ref()
+            .map_or(Value::Null, RedactedSerialize::redacted)
+    }
+}
+
+impl<T: RedactedSerialize> RedactedSerialize for Vec<T> {
+    fn redacted(&self) -> Value {
+        Value::Array(self.iter().map(RedactedSerialize::redacted).collect())
+    }
+}
+
+impl RedactedSerialize for UpdateSeedDict {
+    fn redacted(&self) -> Value {
+        (**self).redacted()
+    }
+}
+
+impl<K, V, S> RedactedSerialize for HashMap<K, V, S>
+where
+    K: RedactedSerialize,
+    V: RedactedSerialize,
+{
+    fn redacted(&self) -> Value {
+        let mut map = self
+            .iter()
+            .map(|(key, value)| {
+                let key = match key.redacted() {
+                    Value::String(key) => key,
+                    key => key.to_string(),
+                };
+                (key, value.redacted())
+            })
+            .collect::<Vec<_>>();
+        // sort the entries to get reproducible dumps
+        map.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+        Value::Object(map.into_iter().collect::<Map<_, _>>())
+    }
+}
+
+impl CoordinatorState {
+    /// Dumps the coordinator state as a human-readable JSON value.
+    ///
+    /// Keys are encoded as base64 and the secret key of the coordinator is left out.
+    pub fn to_debug_json(&self) -> Value {
+        self.redacted()
+    }
+}
+
+/// Dumps the coordinator state together with its dictionaries as a human-readable JSON value.
+///
+/// The dump contains the round id, the current `phase` if it is known, the number of entries of
+/// the dictionaries and the dictionaries themselves.
+pub fn dump_state(
+    state: &CoordinatorState,
+    phase: Option<PhaseName>,
+    sum_dict: Option<&SumDict>,
+    seed_dict: Option<&SeedDict>,
+) -> Value {
+    json!({
+        "round_id": state.round_id,
+        "phase": phase.map(|phase| phase.to_string()),
+        "counts": {
+            "sum_participants": sum_dict.map(SumDict::len),
+            "update_participants": seed_dict
+                .and_then(|dict| dict.values().map(|seeds| seeds.len()).max()),
+        },
+        "state": state.to_debug_json(),
+        "sum_dict": sum_dict.map(RedactedSerialize::redacted),
+        "seed_dict": seed_dict.map(RedactedSerialize::redacted),
+    })
+}
+
+/// Reads the coordinator state and its dictionaries from the `store` and dumps them.
+///
+/// Returns `Ok(None)` if no coordinator state has been stored yet.
+///
+/// # Errors
+/// Fails if the storage requests fail.
+pub async fn dump_stored_state<C>(
+    store: &mut C,
+    phase: Option<PhaseName>,
+) -> StorageResult<Option<Value>>
+where
+    C: CoordinatorStorage,
+{
+    let state = match store.coordinator_state().await? {
+        Some(state) => state,
+        None => return Ok(None),
+    };
+    let sum_dict = store.sum_dict().await?;
+    let seed_dict = store.seed_dict().await?;
+    Ok(Some(dump_state(
+        &state,
+        phase,
+        sum_dict.as_ref(),
+        seed_dict.as_ref(),
+    )))
+}
+
+/// A human-readable summary of the dictionaries of the current round.
+///
+/// Unlike [`dump_state()`], the summary is typed and condenses the seed dictionary and the mask
+/// dictionary to the counts which are relevant to tell why a round is stuck. Public keys are
+/// encoded as base64.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DictionariesDump {
+    /// The sum dictionary, which maps the public keys of the sum participants to their ephemeral
+    /// public keys, if it exists.
+    pub sum_dict: Option<BTreeMap<String, String>>,
+    /// The coverage of the seed dictionary, if it exists.
+    pub seed_dict: Option<SeedDictCoverage>,
+    /// The counts of the mask dictionary.
+    pub mask_dict: MaskDictCounts,
+}
+
+/// The coverage of the seed dictionary.
+///
+/// Every update participant must have sent a seed for every sum participant, hence a complete
+/// seed dictionary has the same number of seeds for every sum participant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeedDictCoverage {
+    /// The number of distinct update participants with at least one seed.
+    pub update_participants: usize,
+    /// The number of seeds per sum participant.
+    pub seeds: BTreeMap<String, usize>,
+    /// The update participants which lack a seed for at least one sum participant.
+    pub incomplete: BTreeSet<String>,
+}
+
+impl SeedDictCoverage {
+    /// Computes the coverage of the `seed_dict`.
+    pub fn new(seed_dict: &SeedDict) -> Self {
+        let update_pks = seed_dict
+            .values()
+            .flat_map(|seeds| seeds.keys())
+            .collect::<BTreeSet<_>>();
+        let incomplete = update_pks
+            .iter()
+            .filter(|update_pk| seed_dict.values().any(|seeds| !seeds.contains_key(update_pk)))
+            .map(|update_pk| base64::encode(update_pk.as_slice()))
+            .collect();
+        let seeds = seed_dict
+            .iter()
+            .map(|(sum_pk, seeds)| (base64::encode(sum_pk.as_slice()), seeds.len()))
+            .collect();
+        Self {
+            update_participants: update_pks.len(),
+            seeds,
+            incomplete,
+        }
+    }
+}
+
+/// The counts of the mask dictionary.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MaskDictCounts {
+    /// The number of distinct masks.
+    pub unique_masks: u64,
+    /// The scores of the two best masks in descending order.
+    pub best_scores: Vec<u64>,
+}
+
+impl DictionariesDump {
+    /// Summarizes the given dictionaries.
+    pub fn new(
+        sum_dict: Option<&SumDict>,
+        seed_dict: Option<&SeedDict>,
+        mask_dict: MaskDictCounts,
+    ) -> Self {
+        let sum_dict = sum_dict.map(|dict| {
+            dict.iter()
+                .map(|(pk, ephm_pk)| {
+                    (
+                        base64::encode(pk.as_slice()),
+                        base64::encode(ephm_pk.as_slice()),
+                    )
+                })
+                .collect()
+        });
+        Self {
+            sum_dict,
+            seed_dict: seed_dict.map(SeedDictCoverage::new),
+            mask_dict,
+        }
+    }
+}
+
+/// Reads the dictionaries of the current round from the `store` and summarizes them.
+///
+/// # Errors
+/// Fails if the storage requests fail.
+pub async fn dump_dictionaries<C>(store: &mut C) -> StorageResult<DictionariesDump>
+where
+    C: CoordinatorStorage,
+{
+    let sum_dict = store.sum_dict().await?;
+    let seed_dict = store.seed_dict().await?;
+    let mask_dict = MaskDictCounts {
+        unique_masks: store.number_of_unique_masks().await?,
+        best_scores: store
+            .best_masks()
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, score)| score)
+            .collect(),
+    };
+    Ok(DictionariesDump::new(
+        sum_dict.as_ref(),
+        seed_dict.as_ref(),
+        mask_dict,
+    ))
+}
+
+/// A handle to dump the state of a running coordinator.
+#[derive(Debug, Clone)]
+pub struct StateDumper<C> {
+    store: C,
+    phase: EventListener<PhaseName>,
+}
+
+impl<C> StateDumper<C>
+where
+    C: CoordinatorStorage,
+{
+    /// Creates a new state dumper, which reads the state from the `store` and the current phase
+    /// from the `events`.
+    pub fn new(store: C, events: &EventSubscriber) -> Self {
+        Self {
+            store,
+            phase: events.phase_listener(),
+        }
+    }
+
+    /// Dumps the coordinator state.
+    ///
+    /// See [`dump_stored_state()`] for details.
+    pub async fn dump(&mut self) -> StorageResult<Option<Value>> {
+        let phase = self.phase.get_latest().event;
+        dump_stored_state(&mut self.store, Some(phase)).await
+    }
+
+    /// Summarizes the dictionaries of the current round.
+    ///
+    /// See [`dump_dictionaries()`] for details.
+    pub async fn dump_dictionaries(&mut self) -> StorageResult<DictionariesDump> {
+        dump_dictionaries(&mut self.store).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        iter,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use xaynet_core::{
+        crypto::{EncryptKeyPair, SigningKeyPair},
+        mask::MaskSeed,
+        UpdateSeedDict,
+    };
+
+    use crate::state_machine::tests::CoordinatorStateBuilder;
+
+    /// Asserts that the secret key doesn't appear in the serialized `dump` in any common encoding.
+    fn assert_no_secret(dump: &Value, keys: &EncryptKeyPair) {
+        let dump = dump.to_string();
+        let secret = keys.secret.as_slice();
+        assert!(!dump.contains(&base64::encode(secret)));
+        assert!(!dump.contains(&base64::encode_config(secret, base64::URL_SAFE)));
+        assert!(!dump.contains(&hex::encode(secret)));
+        assert!(!dump.contains(&serde_json::to_string(secret).unwrap()));
+        assert!(!dump.contains(&format!("{:?}", secret)));
+    }
+
+    fn seed_dict(sum_pk: PublicSigningKey, ephm_pk: &PublicEncryptKey) -> SeedDict {
+        let update_seed_dict = UpdateSeedDict::from_storage((0..3).map(|_| {
+            let update_pk = SigningKeyPair::generate().public;
+            (update_pk, MaskSeed::generate().encrypt(ephm_pk))
+        }));
+        let mut seed_dict = SeedDict::new();
+        seed_dict.insert(sum_pk, update_seed_dict);
+        seed_dict
+    }
+
+    #[test]
+    fn test_to_debug_json() {
+        let mut state = CoordinatorStateBuilder::new().with_round_id(7).build();
+        state.timings.enter(PhaseName::Sum, UNIX_EPOCH);
+        state
+            .timings
+            .leave(UNIX_EPOCH + Duration::from_secs(2), None);
+        state.rejections.insert(RejectionReason::Duplicate, 2);
+        let dump = state.to_debug_json();
+
+        assert_eq!(dump["round_id"], 7);
+        assert_eq!(
+            dump["keys"]["public"],
+            base64::encode(state.keys.public.as_slice())
+        );
+        assert!(dump["keys"].get("secret").is_none());
+        assert_eq!(dump["timings"]["phases"][0]["phase"], "Sum");
+        assert_eq!(dump["timings"]["phases"][0]["duration_ms"], 2_000);
+        assert_eq!(dump["timings"]["duration_ms"], 2_000);
+        assert_eq!(dump["rejections"]["Duplicate"], 2);
+        assert_no_secret(&dump, &state.keys);
+    }
+
+    #[test]
+    fn test_dump_state() {
+        let state = CoordinatorStateBuilder::new().with_round_id(3).build();
+        let ephm_keys = EncryptKeyPair::generate();
+        let sum_pk = SigningKeyPair::generate().public;
+        let mut sum_dict = SumDict::new();
+        sum_dict.insert(sum_pk, ephm_keys.public);
+        let seed_dict = seed_dict(sum_pk, &ephm_keys.public);
+
+        let dump = dump_state(
+            &state,
+            Some(PhaseName::Sum2),
+            Some(&sum_dict),
+            Some(&seed_dict),
+        );
+
+        assert_eq!(dump["round_id"], 3);
+        assert_eq!(dump["phase"], "Sum2");
+        assert_eq!(dump["counts"]["sum_participants"], 1);
+        assert_eq!(dump["counts"]["update_participants"], 3);
+        let sum_pk = base64::encode(sum_pk.as_slice());
+        assert_eq!(
+            dump["sum_dict"][&sum_pk],
+            base64::encode(ephm_keys.public.as_slice())
+        );
+        let seeds = dump["seed_dict"][&sum_pk].as_object().unwrap();
+        assert_eq!(seeds.len(), 3);
+        for seed in seeds.values() {
+            assert_eq!(seed["length"], EncryptedMaskSeed::LENGTH);
+            assert_eq!(
+                base64::decode(seed["prefix"].as_str().unwrap())
+                    .unwrap()
+                    .len(),
+                ENCRYPTED_SEED_PREFIX_LENGTH
+            );
+        }
+        assert_no_secret(&dump, &state.keys);
+        assert_no_secret(&dump, &ephm_keys);
+    }
+
+    #[test]
+    fn test_redacted_nested_secrets() {
+        let keys = EncryptKeyPair::generate();
+        let mut nested = HashMap::new();
+        nested.insert(
+            SigningKeyPair::generate().public,
+            vec![Some(keys.clone()), None, Some(keys.clone())],
+        );
+        let mut outer = HashMap::new();
+        outer.insert(keys.public, nested);
+
+        let dump = outer.redacted();
+
+        let public = base64::encode(keys.public.as_slice());
+        let inner = dump[&public].as_object().unwrap().values().next().unwrap();
+        assert_eq!(inner[0]["public"], public);
+        assert_eq!(inner[1], Value::Null);
+        assert_no_secret(&dump, &keys);
+    }
+
+    #[test]
+    fn test_dump_dictionaries() {
+        let ephm_keys = EncryptKeyPair::generate();
+        let complete_pk = SigningKeyPair::generate().public;
+        let partial_pk = SigningKeyPair::generate().public;
+        let mut sum_dict = SumDict::new();
+        sum_dict.insert(complete_pk, ephm_keys.public);
+        sum_dict.insert(partial_pk, ephm_keys.public);
+
+        // the update participants of the complete sum participant lack a seed for the other one
+        let mut seed_dict = seed_dict(complete_pk, &ephm_keys.public);
+        let shared_pk = *seed_dict[&complete_pk].keys().next().unwrap();
+        let partial_seeds = UpdateSeedDict::from_storage(iter::once((
+            shared_pk,
+            MaskSeed::generate().encrypt(&ephm_keys.public),
+        )));
+        seed_dict.insert(partial_pk, partial_seeds);
+
+        let mask_dict = MaskDictCounts {
+            unique_masks: 2,
+            best_scores: vec![3, 1],
+        };
+        let dump = DictionariesDump::new(Some(&sum_dict), Some(&seed_dict), mask_dict.clone());
+
+        let sum = dump.sum_dict.unwrap();
+        assert_eq!(sum.len(), 2);
+        assert_eq!(
+            sum[&base64::encode(complete_pk.as_slice())],
+            base64::encode(ephm_keys.public.as_slice())
+        );
+
+        let coverage = dump.seed_dict.unwrap();
+        assert_eq!(coverage.update_participants, 3);
+        assert_eq!(coverage.seeds[&base64::encode(complete_pk.as_slice())], 3);
+        assert_eq!(coverage.seeds[&base64::encode(partial_pk.as_slice())], 1);
+        assert_eq!(coverage.incomplete.len(), 2);
+        assert!(!coverage
+            .incomplete
+            .contains(&base64::encode(shared_pk.as_slice())));
+        assert_eq!(dump.mask_dict, mask_dict);
+
+        // the dictionaries of a round which hasn't reached them yet are absent
+        let dump = DictionariesDump::new(None, None, MaskDictCounts::default());
+        let json = serde_json::to_value(&dump).unwrap();
+        assert_eq!(json["sum_dict"], Value::Null);
+        assert_eq!(json["seed_dict"], Value::Null);
+        assert_eq!(json["mask_dict"]["unique_masks"], 0);
+    }
+}
@@ -0,0 +1,256 @@
+//! A denylist of participants which are banned from contributing to the rounds.
+//!
+//! The [`Denylist`] is shared between the state machine, which rejects the messages of banned
+//! participants right after their signatures are verified, and the [`DenylistManager`], which
+//! modifies the denylist at runtime. The denylist is persisted in the coordinator storage, hence
+//! it survives restarts of the coordinator.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+
+use displaydoc::Display;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::{
+    settings::DenylistSettings,
+    storage::{CoordinatorStorage, StorageError, StorageResult},
+};
+use xaynet_core::ParticipantPublicKey;
+
+/// Errors which can occur while modifying the denylist.
+#[derive(Debug, Display, Error)]
+pub enum DenylistError {
+    /// The denylist is full (capacity {0}).
+    Full(usize),
+    /// Storage request failed: {0}.
+    Storage(#[from] StorageError),
+}
+
+/// A bounded set of banned participants.
+///
+/// The denylist is a cheaply cloneable handle, all clones share the same set of participants.
+#[derive(Clone, Debug)]
+pub struct Denylist {
+    /// The banned participants.
+    keys: Arc<RwLock<HashSet<ParticipantPublicKey>>>,
+    /// The maximal number of banned participants.
+    capacity: usize,
+}
+
+impl Default for Denylist {
+    fn default() -> Self {
+        Self::new(DenylistSettings::default().capacity)
+    }
+}
+
+impl Denylist {
+    /// Creates an empty denylist which holds at most `capacity` participants.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            keys: Arc::new(RwLock::new(HashSet::new())),
+            capacity,
+        }
+    }
+
+    /// Restores the denylist from the coordinator storage and adds the keys of the settings.
+    ///
+    /// The keys of the settings are persisted as well. Stored keys which exceed the capacity of
+    /// the settings are left out with a warning.
+    ///
+    /// # Errors
+    /// Fails if the denylist can't be read from or written to the storage.
+    pub async fn restore<C>(settings: &DenylistSettings, store: &mut C) -> StorageResult<Self>
+    where
+        C: CoordinatorStorage,
+    {
+        let denylist = Self::new(settings.capacity);
+        for pk in settings.keys.iter() {
+            store.add_denied_participant(pk).await?;
+        }
+        let mut skipped = 0_usize;
+        for pk in store.denied_participants().await? {
+            if denylist.insert(pk).is_err() {
+                skipped += 1;
+            }
+        }
+        if skipped > 0 {
+            warn!(
+                "left out {} banned participants which exceed the denylist capacity of {}",
+                skipped, settings.capacity,
+            );
+        }
+        info!(
+            "restored denylist with {} banned participants",
+            denylist.len()
+        );
+        Ok(denylist)
+    }
+
+    /// Checks whether the participant is banned.
+    pub fn contains(&self, pk: &ParticipantPublicKey) -> bool {
+        self.keys.read().unwrap().contains(pk)
+    }
+
+    /// Gets the number of banned participants.
+    pub fn len(&self) -> usize {
+        self.keys.read().unwrap().len()
+    }
+
+    /// Checks whether no participant is banned.
+    pub fn is_empty(&self) -> bool {
+        self.keys.read().unwrap().is_empty()
+    }
+
+    /// Gets the maximal number of banned participants.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Bans a participant.
+    ///
+    /// Returns whether the participant has been newly banned.
+    ///
+    /// # Errors
+    /// Fails if the participant isn't banned yet and the denylist is full.
+    fn insert(&self, pk: ParticipantPublicKey) -> Result<bool, DenylistError> {
+        let mut keys = self.keys.write().unwrap();
+        if keys.contains(&pk) {
+            Ok(false)
+        } else if keys.len() >= self.capacity {
+            Err(DenylistError::Full(self.capacity))
+        } else {
+            Ok(keys.insert(pk))
+        }
+    }
+
+    /// Lifts the ban of a participant.
+    ///
+    /// Returns whether the participant has been banned.
+    fn remove(&self, pk: &ParticipantPublicKey) -> bool {
+        self.keys.write().unwrap().remove(pk)
+    }
+}
+
+/// A handle to modify the denylist of a running coordinator.
+#[derive(Debug, Clone)]
+pub struct DenylistManager<C> {
+    store: C,
+    denylist: Denylist,
+}
+
+impl<C> DenylistManager<C>
+where
+    C: CoordinatorStorage,
+{
+    /// Creates a new denylist manager, which persists the modifications of the `denylist` in the
+    /// `store`.
+    pub fn new(store: C, denylist: Denylist) -> Self {
+        Self { store, denylist }
+    }
+
+    /// Bans a participant.
+    ///
+    /// Returns whether the participant has been newly banned.
+    ///
+    /// # Errors
+    /// Fails if the denylist is full or if the ban can't be persisted, in which case the
+    /// participant isn't banned.
+    pub async fn ban(&mut self, pk: ParticipantPublicKey) -> Result<bool, DenylistError> {
+        let banned = self.denylist.insert(pk)?;
+        if let Err(e) = self.store.add_denied_participant(&pk).await {
+            if banned {
+                self.denylist.remove(&pk);
+            }
+            return Err(e.into());
+        }
+        if banned {
+            info!("banned participant with pk {:?}", pk);
+        }
+        Ok(banned)
+    }
+
+    /// Lifts the ban of a participant.
+    ///
+    /// Returns whether the participant has been banned.
+    ///
+    /// # Errors
+    /// Fails if the removal can't be persisted, in which case the participant stays banned.
+    pub async fn unban(&mut self, pk: &ParticipantPublicKey) -> Result<bool, DenylistError> {
+        let stored = self.store.remove_denied_participant(pk).await?;
+        let banned = self.denylist.remove(pk) || stored;
+        if banned {
+            info!("lifted the ban of participant with pk {:?}", pk);
+        }
+        Ok(banned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serial_test::serial;
+    use xaynet_core::crypto::{ByteObject, SigningKeyPair};
+
+    use crate::storage::tests::{init_store, MockCoordinatorStore};
+
+    #[test]
+    fn test_denylist_capacity() {
+        let denylist = Denylist::new(1);
+        let pk = SigningKeyPair::generate().public;
+        assert!(denylist.insert(pk).unwrap());
+        assert!(!denylist.insert(pk).unwrap());
+        assert!(matches!(
+            denylist.insert(SigningKeyPair::generate().public),
+            Err(DenylistError::Full(1))
+        ));
+        assert_eq!(denylist.len(), 1);
+
+        assert!(denylist.remove(&pk));
+        assert!(!denylist.remove(&pk));
+        assert!(denylist.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ban_is_rolled_back_on_storage_error() {
+        let mut store = MockCoordinatorStore::new();
+        store
+            .expect_add_denied_participant()
+            .return_once(|_| Err(anyhow::anyhow!("")));
+        let denylist = Denylist::new(1);
+        let mut manager = DenylistManager::new(store, denylist.clone());
+
+        let pk = ParticipantPublicKey::fill_with(1);
+        assert!(matches!(
+            manager.ban(pk).await,
+            Err(DenylistError::Storage(_))
+        ));
+        assert!(!denylist.contains(&pk));
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_restore_denylist() {
+        let mut store = init_store().await;
+        let settings = DenylistSettings {
+            keys: vec![SigningKeyPair::generate().public],
+            capacity: 10,
+        };
+        let denylist = Denylist::restore(&settings, &mut store).await.unwrap();
+        let mut manager = DenylistManager::new(store.clone(), denylist);
+        let pk = SigningKeyPair::generate().public;
+        assert!(manager.ban(pk).await.unwrap());
+
+        // restart without the keys of the settings
+        let restored = Denylist::restore(&DenylistSettings::default(), &mut store)
+            .await
+            .unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(restored.contains(&settings.keys[0]));
+        assert!(restored.contains(&pk));
+    }
+}
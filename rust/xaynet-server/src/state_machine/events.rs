@@ -6,11 +6,12 @@ use tokio::sync::watch;
 
 use crate::state_machine::phases::PhaseName;
 use xaynet_core::{
-    common::RoundParameters,
+    common::{GlobalModelMetadata, RoundParameters},
     crypto::EncryptKeyPair,
-    mask::Model,
+    mask::{Model, ModelDelta},
     SeedDict,
     SumDict,
+    VersionedSeeds,
 };
 
 /// An event emitted by the coordinator.
@@ -25,10 +26,13 @@ pub struct Event<E> {
 
 // FIXME: should we simply use `Option`s here?
 /// Global model update event.
+///
+/// A new global model comes with its metadata, unless the model has been restored from the
+/// storage.
 #[derive(Debug, Clone, PartialEq)]
 pub enum ModelUpdate {
     Invalidate,
-    New(Arc<Model>),
+    New(Arc<Model>, Option<GlobalModelMetadata>),
 }
 
 /// Dictionary update event.
@@ -38,6 +42,12 @@ pub enum DictionaryUpdate<D> {
     New(Arc<D>),
 }
 
+/// A seed dictionary together with its version.
+///
+/// The version is bumped whenever an update message is accepted, such that the sum2 messages
+/// can be checked against the final seed dictionary of the update phase.
+pub type VersionedSeedDict = VersionedSeeds<SeedDict>;
+
 /// A convenience type to emit any coordinator event.
 #[derive(Debug)]
 pub struct EventPublisher {
@@ -47,8 +57,9 @@ pub struct EventPublisher {
     params_tx: EventBroadcaster<RoundParameters>,
     phase_tx: EventBroadcaster<PhaseName>,
     model_tx: EventBroadcaster<ModelUpdate>,
+    model_delta_tx: EventBroadcaster<Option<Arc<ModelDelta>>>,
     sum_dict_tx: EventBroadcaster<DictionaryUpdate<SumDict>>,
-    seed_dict_tx: EventBroadcaster<DictionaryUpdate<SeedDict>>,
+    seed_dict_tx: EventBroadcaster<DictionaryUpdate<VersionedSeedDict>>,
 }
 
 /// The `EventSubscriber` hands out `EventListener`s for any
@@ -59,8 +70,9 @@ pub struct EventSubscriber {
     params_rx: EventListener<RoundParameters>,
     phase_rx: EventListener<PhaseName>,
     model_rx: EventListener<ModelUpdate>,
+    model_delta_rx: EventListener<Option<Arc<ModelDelta>>>,
     sum_dict_rx: EventListener<DictionaryUpdate<SumDict>>,
-    seed_dict_rx: EventListener<DictionaryUpdate<SeedDict>>,
+    seed_dict_rx: EventListener<DictionaryUpdate<VersionedSeedDict>>,
 }
 
 impl EventPublisher {
@@ -92,6 +104,12 @@ impl EventPublisher {
             event: model,
         });
 
+        let (model_delta_tx, model_delta_rx) =
+            watch::channel::<Event<Option<Arc<ModelDelta>>>>(Event {
+                round_id,
+                event: None,
+            });
+
         let (sum_dict_tx, sum_dict_rx) =
             watch::channel::<Event<DictionaryUpdate<SumDict>>>(Event {
                 round_id,
@@ -99,7 +117,7 @@ impl EventPublisher {
             });
 
         let (seed_dict_tx, seed_dict_rx) =
-            watch::channel::<Event<DictionaryUpdate<SeedDict>>>(Event {
+            watch::channel::<Event<DictionaryUpdate<VersionedSeedDict>>>(Event {
                 round_id,
                 event: DictionaryUpdate::Invalidate,
             });
@@ -110,6 +128,7 @@ impl EventPublisher {
             params_tx: params_tx.into(),
             phase_tx: phase_tx.into(),
             model_tx: model_tx.into(),
+            model_delta_tx: model_delta_tx.into(),
             sum_dict_tx: sum_dict_tx.into(),
             seed_dict_tx: seed_dict_tx.into(),
         };
@@ -119,6 +138,7 @@ impl EventPublisher {
             params_rx: params_rx.into(),
             phase_rx: phase_rx.into(),
             model_rx: model_rx.into(),
+            model_delta_rx: model_delta_rx.into(),
             sum_dict_rx: sum_dict_rx.into(),
             seed_dict_rx: seed_dict_rx.into(),
         };
@@ -158,13 +178,24 @@ impl EventPublisher {
         let _ = self.model_tx.broadcast(self.event(update));
     }
 
+    /// Emit a model delta event, where `None` means that no delta to the previous global model is
+    /// available
+    pub fn broadcast_model_delta(&mut self, delta: Option<Arc<ModelDelta>>) {
+        let _ = self.model_delta_tx.broadcast(self.event(delta));
+    }
+
+    /// Get the latest model event
+    pub fn latest_model(&self) -> Event<ModelUpdate> {
+        self.model_tx.latest()
+    }
+
     /// Emit a sum dictionary update
     pub fn broadcast_sum_dict(&mut self, update: DictionaryUpdate<SumDict>) {
         let _ = self.sum_dict_tx.broadcast(self.event(update));
     }
 
     /// Emit a seed dictionary update
-    pub fn broadcast_seed_dict(&mut self, update: DictionaryUpdate<SeedDict>) {
+    pub fn broadcast_seed_dict(&mut self, update: DictionaryUpdate<VersionedSeedDict>) {
         let _ = self.seed_dict_tx.broadcast(self.event(update));
     }
 }
@@ -191,13 +222,18 @@ impl EventSubscriber {
         self.model_rx.clone()
     }
 
+    /// Get a listener for the delta from the previous to the latest global model, if any
+    pub fn model_delta_listener(&self) -> EventListener<Option<Arc<ModelDelta>>> {
+        self.model_delta_rx.clone()
+    }
+
     /// Get a listener for sum dictionary updates
     pub fn sum_dict_listener(&self) -> EventListener<DictionaryUpdate<SumDict>> {
         self.sum_dict_rx.clone()
     }
 
     /// Get a listener for seed dictionary updates
-    pub fn seed_dict_listener(&self) -> EventListener<DictionaryUpdate<SeedDict>> {
+    pub fn seed_dict_listener(&self) -> EventListener<DictionaryUpdate<VersionedSeedDict>> {
         self.seed_dict_rx.clone()
     }
 }
@@ -222,7 +258,9 @@ where
         self.0.borrow().clone()
     }
 
-    #[cfg(test)]
+    /// Waits until a new event is broadcasted.
+    ///
+    /// Fails if the state machine shut down, i.e. if the publisher has been dropped.
     pub async fn changed(&mut self) -> Result<(), watch::error::RecvError> {
         self.0.changed().await
     }
@@ -238,6 +276,14 @@ impl<E> EventBroadcaster<E> {
         // We don't care whether there's a listener or not
         let _ = self.0.send(event);
     }
+
+    /// Get the latest `event` sent to all the `EventListener<E>`
+    fn latest(&self) -> Event<E>
+    where
+        E: Clone,
+    {
+        self.0.borrow().clone()
+    }
 }
 
 impl<E> From<watch::Sender<Event<E>>> for EventBroadcaster<E> {
@@ -1,25 +1,44 @@
 //! A state machine initializer.
 
+use std::sync::Arc;
+#[cfg(feature = "model-persistence")]
+use std::time::Duration;
+
 use displaydoc::Display;
 use thiserror::Error;
-#[cfg(feature = "model-persistence")]
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 #[cfg(feature = "model-persistence")]
-use crate::settings::RestoreSettings;
+use crate::{settings::RestoreSettings, storage::StorageResult};
 use crate::{
     settings::{MaskSettings, ModelSettings, PetSettings},
     state_machine::{
         coordinator::CoordinatorState,
-        events::{EventPublisher, EventSubscriber, ModelUpdate},
-        phases::{Idle, PhaseName, PhaseState, Shared},
+        denylist::Denylist,
+        events::{
+            DictionaryUpdate,
+            EventPublisher,
+            EventSubscriber,
+            ModelUpdate,
+            VersionedSeedDict,
+        },
+        pause::Pause,
+        phases::{Idle, PhaseName, PhaseState, RestoredAggregation, Shared, Sum, Sum2, Update},
         requests::{RequestReceiver, RequestSender},
         StateMachine,
     },
-    storage::{Storage, StorageError},
+    storage::{
+        coordinator_storage::memory::{MaskDictSnapshot, MemoryStorage},
+        ModelStorage,
+        Storage,
+        StorageError,
+        Store,
+        TrustAnchor,
+    },
 };
 #[cfg(feature = "model-persistence")]
 use xaynet_core::mask::Model;
+use xaynet_core::{SeedDict, SumDict};
 
 type StateMachineInitializationResult<T> = Result<T, StateMachineInitializationError>;
 
@@ -40,6 +59,87 @@ pub enum StateMachineInitializationError {
     GlobalModelUnavailable(String),
     /// Global model is invalid: {0}.
     GlobalModelInvalid(String),
+    /// Restoring aggregation checkpoint failed: {0}.
+    RestoreAggregationCheckpoint(StorageError),
+    /// Fetching sum dictionary failed: {0}.
+    FetchSumDict(StorageError),
+    /// Fetching seed dictionary failed: {0}.
+    FetchSeedDict(StorageError),
+    /// Fetching submission status failed: {0}.
+    FetchSubmissionStatus(StorageError),
+}
+
+/// An interrupted phase which can be resumed.
+enum ResumedPhase {
+    /// The sum phase with the number of sum participants in the store.
+    Sum(u64),
+    /// The update phase with the restored aggregation and the sum dict.
+    Update(Box<RestoredAggregation>, SumDict),
+    /// The sum2 phase with the final aggregation, the sum dict, the seed dict and the number of
+    /// masks in the store.
+    Sum2(Box<RestoredAggregation>, SumDict, SeedDict, u64),
+}
+
+// Gets the interrupted phase of the round of the coordinator state, if any.
+fn running_phase(coordinator_state: &CoordinatorState) -> Option<PhaseName> {
+    Some(&coordinator_state.timings)
+        .filter(|timings| timings.round_id == coordinator_state.round_id)
+        .and_then(|timings| timings.running_phase())
+}
+
+/// A snapshot of the latest global model which a standby coordinator replica keeps restored
+/// while another replica is the leader.
+///
+/// Fetching the global model from the model store is the slow part of restoring a coordinator.
+/// With an up-to-date snapshot, a standby replica which takes over after a failover continues the
+/// round of the deposed leader right away, see [`StateMachineInitializer::with_standby_snapshot()`].
+#[cfg(feature = "model-persistence")]
+#[cfg_attr(docsrs, doc(cfg(feature = "model-persistence")))]
+#[derive(Clone, Debug, Default)]
+pub struct StandbySnapshot {
+    /// The round of the latest coordinator state, if any.
+    pub round_id: Option<u64>,
+    /// The id of the latest global model together with the model, if any.
+    pub global_model: Option<(String, Model)>,
+}
+
+#[cfg(feature = "model-persistence")]
+impl StandbySnapshot {
+    /// Updates the snapshot from the `store`.
+    ///
+    /// The global model is only fetched if the latest global model id changed.
+    ///
+    /// # Errors
+    /// Fails if an error occurs in the storage layer.
+    pub async fn update<T: Storage>(&mut self, store: &mut T) -> StorageResult<()> {
+        self.round_id = store
+            .coordinator_state()
+            .await?
+            .map(|coordinator_state| coordinator_state.round_id);
+        match store.latest_global_model_id().await? {
+            Some(id) if matches!(self.global_model, Some((ref cached, _)) if *cached == id) => {}
+            Some(id) => {
+                debug!("restore global model {} on standby", id);
+                self.global_model = store.global_model(&id).await?.map(|model| (id, model));
+            }
+            None => self.global_model = None,
+        }
+        Ok(())
+    }
+
+    /// Updates the snapshot from the `store` in the given `interval` until the returned future
+    /// is dropped, e.g. when the standby replica becomes the leader.
+    ///
+    /// Failed updates are retried in the next interval.
+    pub async fn restore_continuously<T: Storage>(&mut self, mut store: T, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.update(&mut store).await {
+                warn!("failed to restore the standby snapshot: {}", err);
+            }
+        }
+    }
 }
 
 /// The state machine initializer that initializes a new state machine.
@@ -49,7 +149,11 @@ pub struct StateMachineInitializer<T> {
     model_settings: ModelSettings,
     #[cfg(feature = "model-persistence")]
     restore_settings: RestoreSettings,
+    #[cfg(feature = "model-persistence")]
+    standby_snapshot: StandbySnapshot,
     store: T,
+    denylist: Denylist,
+    pause: Pause,
 }
 
 impl<T> StateMachineInitializer<T> {
@@ -67,15 +171,47 @@ impl<T> StateMachineInitializer<T> {
             model_settings,
             #[cfg(feature = "model-persistence")]
             restore_settings,
+            #[cfg(feature = "model-persistence")]
+            standby_snapshot: StandbySnapshot::default(),
             store,
+            denylist: Denylist::default(),
+            pause: Pause::default(),
         }
     }
 
+    /// Sets the denylist of banned participants.
+    ///
+    /// Without a denylist, an empty denylist is used.
+    pub fn with_denylist(mut self, denylist: Denylist) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Sets the switch to pause and resume the acceptance of messages.
+    ///
+    /// Without a switch, the acceptance of messages can't be paused.
+    pub fn with_pause(mut self, pause: Pause) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Sets the snapshot which has been restored while the coordinator replica stood by.
+    ///
+    /// The global model of the snapshot is used instead of fetching it again, if it is still the
+    /// latest global model.
+    #[cfg(feature = "model-persistence")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "model-persistence")))]
+    pub fn with_standby_snapshot(mut self, snapshot: StandbySnapshot) -> Self {
+        self.standby_snapshot = snapshot;
+        self
+    }
+
     // Initializes a new [`StateMachine`] with its components.
     fn init_state_machine(
         self,
         coordinator_state: CoordinatorState,
         global_model: ModelUpdate,
+        resumed: Option<ResumedPhase>,
     ) -> (StateMachine<T>, RequestSender, EventSubscriber) {
         let (event_publisher, event_subscriber) = EventPublisher::init(
             coordinator_state.round_id,
@@ -87,13 +223,62 @@ impl<T> StateMachineInitializer<T> {
 
         let (request_rx, request_tx) = RequestReceiver::new();
 
-        let shared = Shared::new(coordinator_state, event_publisher, request_rx, self.store);
+        let mut shared = Shared::new(coordinator_state, event_publisher, request_rx, self.store);
+        shared.denylist = self.denylist;
+        shared.pause = self.pause;
 
-        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = match resumed {
+            Some(ResumedPhase::Sum(nb_restored)) => {
+                StateMachine::from(PhaseState::<Sum, _>::resume(shared, nb_restored))
+            }
+            Some(ResumedPhase::Update(restored, sum_dict)) => {
+                shared
+                    .events
+                    .broadcast_sum_dict(DictionaryUpdate::New(Arc::new(sum_dict)));
+                StateMachine::from(PhaseState::<Update, _>::resume(shared, *restored))
+            }
+            Some(ResumedPhase::Sum2(restored, sum_dict, seed_dict, nb_masks)) => {
+                shared
+                    .events
+                    .broadcast_sum_dict(DictionaryUpdate::New(Arc::new(sum_dict)));
+                let seed_dict = VersionedSeedDict {
+                    version: restored.nb_models() as u64,
+                    seeds: seed_dict,
+                };
+                shared
+                    .events
+                    .broadcast_seed_dict(DictionaryUpdate::New(Arc::new(seed_dict)));
+                StateMachine::from(PhaseState::<Sum2, _>::resume(shared, *restored, nb_masks))
+            }
+            None => StateMachine::from(PhaseState::<Idle, _>::new(shared)),
+        };
         (state_machine, request_tx, event_subscriber)
     }
 }
 
+impl<M, A> StateMachineInitializer<Store<MemoryStorage, M, A>>
+where
+    M: ModelStorage,
+    A: TrustAnchor,
+{
+    /// Imports the mask dictionary of an interrupted sum2 phase into the in-memory coordinator
+    /// store.
+    ///
+    /// The in-memory store loses the received masks when the coordinator stops. With a snapshot
+    /// which has been exported with [`MemoryStorage::export_mask_dict()`] before the shutdown,
+    /// [`take_over()`] resumes the sum2 phase with the masks of the snapshot, such that the sum
+    /// participants don't have to submit their masks again. A snapshot of another round than the
+    /// coordinator state is ignored.
+    ///
+    /// [`take_over()`]: StateMachineInitializer::take_over
+    pub fn with_mask_dict(self, snapshot: MaskDictSnapshot) -> Self {
+        if let Err(err) = self.store.coordinator().import_mask_dict(snapshot) {
+            warn!("failed to import the mask dictionary: {}", err);
+        }
+        self
+    }
+}
+
 impl<T> StateMachineInitializer<T>
 where
     T: Storage,
@@ -107,7 +292,170 @@ where
         sodiumoxide::init().or(Err(StateMachineInitializationError::CryptoInit))?;
 
         let (coordinator_state, global_model) = { self.from_settings().await? };
-        Ok(self.init_state_machine(coordinator_state, global_model))
+        Ok(self.init_state_machine(coordinator_state, global_model, None))
+    }
+
+    /// Initializes a new [`StateMachine`] for a coordinator replica which takes over from a
+    /// deposed leader.
+    ///
+    /// Unlike [`init()`], the coordinator data is never deleted and the round of the deposed
+    /// leader is continued in its phase, such that the participants of the round don't have to
+    /// start over:
+    /// - If the deposed leader ran the sum phase, the sum phase is resumed with the sum
+    ///   participants in the store.
+    /// - If the deposed leader ran the update phase, the update phase is resumed with the
+    ///   aggregation of the latest checkpoint, if any. Otherwise the seed dict is rolled back and
+    ///   the update participants may submit their update messages again.
+    /// - If the deposed leader ran the sum2 phase, the sum2 phase is resumed with the final
+    ///   aggregation checkpoint of the update phase and the masks in the store, if the checkpoint
+    ///   covers all update participants. Otherwise a new round is started.
+    /// - In any other phase, a new round is started from the coordinator state.
+    /// - If no coordinator state exists, the [`StateMachine`] is created with the given settings.
+    ///
+    /// The latest global model is restored as well, if the models are persisted.
+    ///
+    /// [`init()`]: StateMachineInitializer::init
+    pub async fn take_over(
+        mut self,
+    ) -> StateMachineInitializationResult<(StateMachine<T>, RequestSender, EventSubscriber)> {
+        // crucial: init must be called before anything else in this module
+        sodiumoxide::init().or(Err(StateMachineInitializationError::CryptoInit))?;
+
+        let coordinator_state = match self
+            .store
+            .coordinator_state()
+            .await
+            .map_err(StateMachineInitializationError::FetchCoordinatorState)?
+        {
+            Some(coordinator_state) => coordinator_state,
+            None => {
+                info!("no coordinator state to take over, initialize state machine from settings");
+                let coordinator_state = CoordinatorState::new(
+                    self.pet_settings.clone(),
+                    self.mask_settings,
+                    self.model_settings.clone(),
+                );
+                return Ok(self.init_state_machine(
+                    coordinator_state,
+                    ModelUpdate::Invalidate,
+                    None,
+                ));
+            }
+        };
+
+        #[cfg(feature = "model-persistence")]
+        let (coordinator_state, global_model) = self.try_restore_state(coordinator_state).await?;
+        #[cfg(not(feature = "model-persistence"))]
+        let global_model = ModelUpdate::Invalidate;
+
+        let resumed = self.try_resume_phase(&coordinator_state).await?;
+        Ok(self.init_state_machine(coordinator_state, global_model, resumed))
+    }
+
+    // Restores the phase of the round of the coordinator state, if it can be resumed.
+    async fn try_resume_phase(
+        &mut self,
+        coordinator_state: &CoordinatorState,
+    ) -> StateMachineInitializationResult<Option<ResumedPhase>> {
+        let phase = match running_phase(coordinator_state) {
+            Some(phase @ PhaseName::Sum)
+            | Some(phase @ PhaseName::Update)
+            | Some(phase @ PhaseName::Sum2) => phase,
+            phase => {
+                debug!("the {:?} phase can't be resumed, start a new round", phase);
+                return Ok(None);
+            }
+        };
+
+        let sum_dict = self
+            .store
+            .sum_dict()
+            .await
+            .map_err(StateMachineInitializationError::FetchSumDict)?;
+        let resumed = match (phase, sum_dict) {
+            (PhaseName::Sum, sum_dict) => {
+                let nb_restored = sum_dict.map(|sum_dict| sum_dict.len()).unwrap_or_default();
+                ResumedPhase::Sum(nb_restored as u64)
+            }
+            (_, None) => {
+                debug!(
+                    "no sum dictionary available, the {} phase can't be resumed",
+                    phase
+                );
+                return Ok(None);
+            }
+            (PhaseName::Update, Some(sum_dict)) => {
+                let restored =
+                    RestoredAggregation::load_or_empty(&mut self.store, coordinator_state)
+                        .await
+                        .map_err(StateMachineInitializationError::RestoreAggregationCheckpoint)?;
+                ResumedPhase::Update(Box::new(restored), sum_dict)
+            }
+            (_, Some(sum_dict)) => match self.try_resume_sum2(coordinator_state, sum_dict).await? {
+                Some(resumed) => resumed,
+                None => return Ok(None),
+            },
+        };
+        info!(
+            "resume the {} phase of round {}",
+            phase, coordinator_state.round_id
+        );
+        Ok(Some(resumed))
+    }
+
+    // Restores the final aggregation and the masks of an interrupted sum2 phase, if the final
+    // aggregation checkpoint of the update phase covers all update participants.
+    async fn try_resume_sum2(
+        &mut self,
+        coordinator_state: &CoordinatorState,
+        sum_dict: SumDict,
+    ) -> StateMachineInitializationResult<Option<ResumedPhase>> {
+        let restored = match RestoredAggregation::load_final(&mut self.store, coordinator_state)
+            .await
+            .map_err(StateMachineInitializationError::RestoreAggregationCheckpoint)?
+        {
+            Some(restored) => restored,
+            None => {
+                debug!("no final aggregation available, the sum2 phase can't be resumed");
+                return Ok(None);
+            }
+        };
+        let seed_dict = match self
+            .store
+            .seed_dict()
+            .await
+            .map_err(StateMachineInitializationError::FetchSeedDict)?
+        {
+            Some(seed_dict) => seed_dict,
+            None => {
+                debug!("no seed dictionary available, the sum2 phase can't be resumed");
+                return Ok(None);
+            }
+        };
+
+        let mut nb_masks = 0;
+        for sum_pk in sum_dict.keys() {
+            let status = self
+                .store
+                .submission_status(sum_pk)
+                .await
+                .map_err(StateMachineInitializationError::FetchSubmissionStatus)?;
+            if status.sum2 {
+                nb_masks += 1;
+            }
+        }
+        info!(
+            "resume the sum2 phase of round {} with {} aggregated masked models and {} masks",
+            coordinator_state.round_id,
+            restored.nb_models(),
+            nb_masks,
+        );
+        Ok(Some(ResumedPhase::Sum2(
+            Box::new(restored),
+            sum_dict,
+            seed_dict,
+            nb_masks,
+        )))
     }
 
     // Creates a new [`CoordinatorState`] from the given settings and deletes
@@ -122,7 +470,7 @@ where
             .map_err(StateMachineInitializationError::DeleteCoordinatorData)?;
         Ok((
             CoordinatorState::new(
-                self.pet_settings,
+                self.pet_settings.clone(),
                 self.mask_settings,
                 self.model_settings.clone(),
             ),
@@ -139,9 +487,9 @@ where
 {
     /// Initializes a new [`StateMachine`] by trying to restore the previous coordinator state
     /// along with the latest global model. After a successful initialization, the state machine
-    /// always starts from a new round. This means that the round id is increased by one.
-    /// If the state machine is reset during the initialization, the state machine starts
-    /// with the round id `1`.
+    /// starts from a new round, unless an interrupted update or sum2 phase can be resumed. This
+    /// means that the round id is increased by one. If the state machine is reset during the
+    /// initialization, the state machine starts with the round id `1`.
     ///
     /// # Behavior
     /// ![](https://mermaid.ink/svg/eyJjb2RlIjoic2VxdWVuY2VEaWFncmFtXG4gICAgYWx0IHJlc3RvcmUuZW5hYmxlID0gZmFsc2VcbiAgICAgICAgQ29vcmRpbmF0b3ItPj4rUmVkaXM6IGZsdXNoIGRiXG4gICAgICAgIE5vdGUgb3ZlciBDb29yZGluYXRvcixSZWRpczogc3RhcnQgZnJvbSBzZXR0aW5nc1xuICAgIGVsc2VcbiAgICAgICAgQ29vcmRpbmF0b3ItPj4rUmVkaXM6IGdldCBzdGF0ZVxuICAgICAgICBSZWRpcy0tPj4tQ29vcmRpbmF0b3I6IHN0YXRlXG4gICAgICAgIGFsdCBzdGF0ZSBub24tZXhpc3RlbnRcbiAgICAgICAgICAgIENvb3JkaW5hdG9yLT4-K1JlZGlzOiBmbHVzaCBkYlxuICAgICAgICAgICAgTm90ZSBvdmVyIENvb3JkaW5hdG9yLFJlZGlzOiBzdGFydCBmcm9tIHNldHRpbmdzXG4gICAgICAgIGVsc2Ugc3RhdGUgZXhpc3RcbiAgICAgICAgICAgIENvb3JkaW5hdG9yLT4-K1JlZGlzOiBnZXQgbGF0ZXN0IGdsb2JhbCBtb2RlbCBpZFxuICAgICAgICAgICAgUmVkaXMtLT4-LUNvb3JkaW5hdG9yOiBnbG9iYWwgbW9kZWwgaWRcbiAgICAgICAgICAgIGFsdCBnbG9iYWwgbW9kZWwgaWQgbm9uLWV4aXN0ZW50XG4gICAgICAgICAgICAgICAgTm90ZSBvdmVyIENvb3JkaW5hdG9yLFMzOiByZXN0b3JlIGNvb3JkaW5hdG9yIHdpdGggbGF0ZXN0IHN0YXRlIGJ1dCB3aXRob3V0IGEgZ2xvYmFsIG1vZGVsXG4gICAgICAgICAgICBlbHNlIGdsb2JhbCBtb2RlbCBpZCBleGlzdFxuICAgICAgICAgICAgICBDb29yZGluYXRvci0-PitTMzogZ2V0IGdsb2JhbCBtb2RlbFxuICAgICAgICAgICAgICBTMy0tPj4tQ29vcmRpbmF0b3I6IGdsb2JhbCBtb2RlbFxuICAgICAgICAgICAgICBhbHQgZ2xvYmFsIG1vZGVsIG5vbi1leGlzdGVudFxuICAgICAgICAgICAgICAgIE5vdGUgb3ZlciBDb29yZGluYXRvcixTMzogZXhpdCB3aXRoIGVycm9yXG4gICAgICAgICAgICAgIGVsc2UgZ2xvYmFsIG1vZGVsIGV4aXN0XG4gICAgICAgICAgICAgICAgTm90ZSBvdmVyIENvb3JkaW5hdG9yLFMzOiByZXN0b3JlIGNvb3JkaW5hdG9yIHdpdGggbGF0ZXN0IHN0YXRlIGFuZCBsYXRlc3QgZ2xvYmFsIG1vZGVsXG4gICAgICAgICAgICAgIGVuZFxuICAgICAgICAgICAgZW5kXG4gICAgICAgICAgZW5kXG4gICAgICAgIGVuZCIsIm1lcm1haWQiOnsidGhlbWUiOiJkZWZhdWx0IiwidGhlbWVWYXJpYWJsZXMiOnsiYmFja2dyb3VuZCI6IndoaXRlIiwicHJpbWFyeUNvbG9yIjoiI0VDRUNGRiIsInNlY29uZGFyeUNvbG9yIjoiI2ZmZmZkZSIsInRlcnRpYXJ5Q29sb3IiOiJoc2woODAsIDEwMCUsIDk2LjI3NDUwOTgwMzklKSIsInByaW1hcnlCb3JkZXJDb2xvciI6ImhzbCgyNDAsIDYwJSwgODYuMjc0NTA5ODAzOSUpIiwic2Vjb25kYXJ5Qm9yZGVyQ29sb3IiOiJoc2woNjAsIDYwJSwgODMuNTI5NDExNzY0NyUpIiwidGVydGlhcnlCb3JkZXJDb2xvciI6ImhzbCg4MCwgNjAlLCA4Ni4yNzQ1MDk4MDM5JSkiLCJwcmltYXJ5VGV4dENvbG9yIjoiIzEzMTMwMCIsInNlY29uZGFyeVRleHRDb2xvciI6IiMwMDAwMjEiLCJ0ZXJ0aWFyeVRleHRDb2xvciI6InJnYig5LjUwMDAwMDAwMDEsIDkuNTAwMDAwMDAwMSwgOS41MDAwMDAwMDAxKSIsImxpbmVDb2xvciI6IiMzMzMzMzMiLCJ0ZXh0Q29sb3IiOiIjMzMzIiwibWFpbkJrZyI6IiNFQ0VDRkYiLCJzZWNvbmRCa2ciOiIjZmZmZmRlIiwiYm9yZGVyMSI6IiM5MzcwREIiLCJib3JkZXIyIjoiI2FhYWEzMyIsImFycm93aGVhZENvbG9yIjoiIzMzMzMzMyIsImZvbnRGYW1pbHkiOiJcInRyZWJ1Y2hldCBtc1wiLCB2ZXJkYW5hLCBhcmlhbCIsImZvbnRTaXplIjoiMTZweCIsImxhYmVsQmFja2dyb3VuZCI6IiNlOGU4ZTgiLCJub2RlQmtnIjoiI0VDRUNGRiIsIm5vZGVCb3JkZXIiOiIjOTM3MERCIiwiY2x1c3RlckJrZyI6IiNmZmZmZGUiLCJjbHVzdGVyQm9yZGVyIjoiI2FhYWEzMyIsImRlZmF1bHRMaW5rQ29sb3IiOiIjMzMzMzMzIiwidGl0bGVDb2xvciI6IiMzMzMiLCJlZGdlTGFiZWxCYWNrZ3JvdW5kIjoiI2U4ZThlOCIsImFjdG9yQm9yZGVyIjoiaHNsKDI1OS42MjYxNjgyMjQzLCA1OS43NzY1MzYzMTI4JSwgODcuOTAxOTYwNzg0MyUpIiwiYWN0b3JCa2ciOiIjRUNFQ0ZGIiwiYWN0b3JUZXh0Q29sb3IiOiJibGFjayIsImFjdG9yTGluZUNvbG9yIjoiZ3JleSIsInNpZ25hbENvbG9yIjoiIzMzMyIsInNpZ25hbFRleHRDb2xvciI6IiMzMzMiLCJsYWJlbEJveEJrZ0NvbG9yIjoiI0VDRUNGRiIsImxhYmVsQm94Qm9yZGVyQ29sb3IiOiJoc2woMjU5LjYyNjE2ODIyNDMsIDU5Ljc3NjUzNjMxMjglLCA4Ny45MDE5NjA3ODQzJSkiLCJsYWJlbFRleHRDb2xvciI6ImJsYWNrIiwibG9vcFRleHRDb2xvciI6ImJsYWNrIiwibm90ZUJvcmRlckNvbG9yIjoiI2FhYWEzMyIsIm5vdGVCa2dDb2xvciI6IiNmZmY1YWQiLCJub3RlVGV4dENvbG9yIjoiYmxhY2siLCJhY3RpdmF0aW9uQm9yZGVyQ29sb3IiOiIjNjY2IiwiYWN0aXZhdGlvbkJrZ0NvbG9yIjoiI2Y0ZjRmNCIsInNlcXVlbmNlTnVtYmVyQ29sb3IiOiJ3aGl0ZSIsInNlY3Rpb25Ca2dDb2xvciI6InJnYmEoMTAyLCAxMDIsIDI1NSwgMC40OSkiLCJhbHRTZWN0aW9uQmtnQ29sb3IiOiJ3aGl0ZSIsInNlY3Rpb25Ca2dDb2xvcjIiOiIjZmZmNDAwIiwidGFza0JvcmRlckNvbG9yIjoiIzUzNGZiYyIsInRhc2tCa2dDb2xvciI6IiM4YTkwZGQiLCJ0YXNrVGV4dExpZ2h0Q29sb3IiOiJ3aGl0ZSIsInRhc2tUZXh0Q29sb3IiOiJ3aGl0ZSIsInRhc2tUZXh0RGFya0NvbG9yIjoiYmxhY2siLCJ0YXNrVGV4dE91dHNpZGVDb2xvciI6ImJsYWNrIiwidGFza1RleHRDbGlja2FibGVDb2xvciI6IiMwMDMxNjMiLCJhY3RpdmVUYXNrQm9yZGVyQ29sb3IiOiIjNTM0ZmJjIiwiYWN0aXZlVGFza0JrZ0NvbG9yIjoiI2JmYzdmZiIsImdyaWRDb2xvciI6ImxpZ2h0Z3JleSIsImRvbmVUYXNrQmtnQ29sb3IiOiJsaWdodGdyZXkiLCJkb25lVGFza0JvcmRlckNvbG9yIjoiZ3JleSIsImNyaXRCb3JkZXJDb2xvciI6IiNmZjg4ODgiLCJjcml0QmtnQ29sb3IiOiJyZWQiLCJ0b2RheUxpbmVDb2xvciI6InJlZCIsImxhYmVsQ29sb3IiOiJibGFjayIsImVycm9yQmtnQ29sb3IiOiIjNTUyMjIyIiwiZXJyb3JUZXh0Q29sb3IiOiIjNTUyMjIyIiwiY2xhc3NUZXh0IjoiIzEzMTMwMCIsImZpbGxUeXBlMCI6IiNFQ0VDRkYiLCJmaWxsVHlwZTEiOiIjZmZmZmRlIiwiZmlsbFR5cGUyIjoiaHNsKDMwNCwgMTAwJSwgOTYuMjc0NTA5ODAzOSUpIiwiZmlsbFR5cGUzIjoiaHNsKDEyNCwgMTAwJSwgOTMuNTI5NDExNzY0NyUpIiwiZmlsbFR5cGU0IjoiaHNsKDE3NiwgMTAwJSwgOTYuMjc0NTA5ODAzOSUpIiwiZmlsbFR5cGU1IjoiaHNsKC00LCAxMDAlLCA5My41Mjk0MTE3NjQ3JSkiLCJmaWxsVHlwZTYiOiJoc2woOCwgMTAwJSwgOTYuMjc0NTA5ODAzOSUpIiwiZmlsbFR5cGU3IjoiaHNsKDE4OCwgMTAwJSwgOTMuNTI5NDExNzY0NyUpIn19LCJ1cGRhdGVFZGl0b3IiOmZhbHNlfQ)
@@ -158,6 +506,13 @@ where
     ///   [`StateMachineInitializationError::GlobalModelUnavailable`].
     /// - If a global model exists but its properties do not match the coordinator model settings,
     ///   the initialization will fail with [`StateMachineInitializationError::GlobalModelInvalid`].
+    /// - If the coordinator state is restored in the update phase and an aggregation checkpoint
+    ///   of its round exists, the seed dict is rolled back to the checkpoint and the
+    ///   [`StateMachine`] resumes the update phase of the round with the restored aggregation
+    ///   instead of starting a new round.
+    /// - If the coordinator state is restored in the sum2 phase and the final aggregation
+    ///   checkpoint of its round covers all update participants, the [`StateMachine`] resumes the
+    ///   sum2 phase of the round with the masks in the store instead of starting a new round.
     /// - Any network error will cause the initialization to fail.
     pub async fn init(
         mut self,
@@ -165,34 +520,76 @@ where
         // crucial: init must be called before anything else in this module
         sodiumoxide::init().or(Err(StateMachineInitializationError::CryptoInit))?;
 
-        let (coordinator_state, global_model) = if self.restore_settings.enable {
+        let (coordinator_state, global_model, resumed) = if self.restore_settings.enable {
             self.from_previous_state().await?
         } else {
             info!("restoring coordinator state is disabled");
             info!("initialize state machine from settings");
-            self.from_settings().await?
+            let (coordinator_state, global_model) = self.from_settings().await?;
+            (coordinator_state, global_model, None)
         };
 
-        Ok(self.init_state_machine(coordinator_state, global_model))
+        Ok(self.init_state_machine(coordinator_state, global_model, resumed))
     }
 
     // see [`StateMachineInitializer::init`]
     async fn from_previous_state(
         &mut self,
-    ) -> StateMachineInitializationResult<(CoordinatorState, ModelUpdate)> {
-        let (coordinator_state, global_model) = if let Some(coordinator_state) = self
+    ) -> StateMachineInitializationResult<(CoordinatorState, ModelUpdate, Option<ResumedPhase>)>
+    {
+        let (coordinator_state, global_model, resumed) = if let Some(coordinator_state) = self
             .store
             .coordinator_state()
             .await
             .map_err(StateMachineInitializationError::FetchCoordinatorState)?
         {
-            self.try_restore_state(coordinator_state).await?
+            let (coordinator_state, global_model) =
+                self.try_restore_state(coordinator_state).await?;
+            let resumed = match running_phase(&coordinator_state) {
+                Some(PhaseName::Update) => self.try_resume_update(&coordinator_state).await?,
+                Some(PhaseName::Sum2) => self.try_resume_phase(&coordinator_state).await?,
+                _ => None,
+            };
+            (coordinator_state, global_model, resumed)
         } else {
             // no coordinator state available seems to be a fresh start
-            self.from_settings().await?
+            let (coordinator_state, global_model) = self.from_settings().await?;
+            (coordinator_state, global_model, None)
+        };
+
+        Ok((coordinator_state, global_model, resumed))
+    }
+
+    // Restores the aggregation of an interrupted update phase from its checkpoint, if any.
+    async fn try_resume_update(
+        &mut self,
+        coordinator_state: &CoordinatorState,
+    ) -> StateMachineInitializationResult<Option<ResumedPhase>> {
+        let sum_dict = match self
+            .store
+            .sum_dict()
+            .await
+            .map_err(StateMachineInitializationError::FetchSumDict)?
+        {
+            Some(sum_dict) => sum_dict,
+            None => {
+                debug!("no sum dictionary available, the update phase can't be resumed");
+                return Ok(None);
+            }
         };
 
-        Ok((coordinator_state, global_model))
+        // the seed dict may be rolled back, hence the sum dict must be fetched first
+        let restored = RestoredAggregation::load(&mut self.store, coordinator_state)
+            .await
+            .map_err(StateMachineInitializationError::RestoreAggregationCheckpoint)?;
+        Ok(restored.map(|restored| {
+            info!(
+                "resume the update phase of round {} with {} aggregated masked models",
+                coordinator_state.round_id,
+                restored.nb_models(),
+            );
+            ResumedPhase::Update(Box::new(restored), sum_dict)
+        }))
     }
 
     // see [`StateMachineInitializer::init`]
@@ -228,7 +625,7 @@ where
         );
         Ok((
             coordinator_state,
-            ModelUpdate::New(std::sync::Arc::new(global_model)),
+            ModelUpdate::New(Arc::new(global_model), None),
         ))
     }
 
@@ -238,12 +635,18 @@ where
         coordinator_state: &CoordinatorState,
         global_model_id: &str,
     ) -> StateMachineInitializationResult<Model> {
-        match self
-            .store
-            .global_model(global_model_id)
-            .await
-            .map_err(StateMachineInitializationError::FetchGlobalModel)?
-        {
+        let global_model = match self.standby_snapshot.global_model.take() {
+            Some((id, global_model)) if id == global_model_id => {
+                debug!("reuse global model {} of the standby snapshot", id);
+                Some(global_model)
+            }
+            _ => self
+                .store
+                .global_model(global_model_id)
+                .await
+                .map_err(StateMachineInitializationError::FetchGlobalModel)?,
+        };
+        match global_model {
             Some(global_model) => {
                 if Self::model_properties_matches_settings(coordinator_state, &global_model) {
                     Ok(global_model)
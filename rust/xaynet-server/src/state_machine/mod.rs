@@ -19,7 +19,9 @@
 //!
 //! Publishes [`PhaseName::Idle`] and increments the `round_id` by `1`. Invalidates the [`SumDict`],
 //! [`SeedDict`], `scalar` and `mask length`. Updates the [`EncryptKeyPair`], `probabilities` for
-//! the tasks and the `seed`. Publishes the [`EncryptKeyPair`] and the [`RoundParameters`].
+//! the tasks and the `seed`. Publishes the [`EncryptKeyPair`] and the [`RoundParameters`]. Refuses
+//! to start the round if the aggregation of the masked models would exceed the configured memory
+//! limit.
 //!
 //! **Sum**
 //!
@@ -31,6 +33,10 @@
 //! Publishes [`PhaseName::Update`], builds and publishes the [`SeedDict`], ensures that enough
 //! update messages have been submitted and aggregates the masked model.
 //!
+//! In collect only mode, the masked models and the [`SeedDict`] are persisted for offline
+//! processing instead of being aggregated and published, and the round ends right after this
+//! phase.
+//!
 //! **Sum2**
 //!
 //! Publishes [`PhaseName::Sum2`], builds the mask dictionary, ensures that enough sum2
@@ -97,10 +103,14 @@
 //! [`EventSubscriber`]: crate::state_machine::events::EventSubscriber
 
 pub mod coordinator;
+pub mod debug;
+pub mod denylist;
 pub mod events;
 pub mod initializer;
+pub mod pause;
 pub mod phases;
 pub mod requests;
+pub mod timings;
 
 use derive_more::From;
 
@@ -218,5 +228,19 @@ macro_rules! discarded {
     };
 }
 
+/// Records a message banned metric.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! banned {
+    ($round_id: expr, $phase: expr $(,)?) => {
+        crate::metric!(
+            crate::metrics::Measurement::MessageBanned,
+            1,
+            ("round_id", $round_id),
+            ("phase", $phase as u8),
+        );
+    };
+}
+
 #[cfg(test)]
 pub(crate) mod tests;
@@ -0,0 +1,107 @@
+//! Pausing the acceptance of messages, e.g. during maintenance.
+//!
+//! The [`Pause`] is shared between the state machine and whoever pauses it at runtime. While
+//! paused, the state machine rejects every request with [`RequestError::Paused`] without touching
+//! the round state and doesn't transition to the next phase. The time limits of the current phase
+//! don't elapse while paused. Unlike a reset, the round is kept and continues once resumed.
+//!
+//! [`RequestError::Paused`]: crate::state_machine::requests::RequestError::Paused
+
+use std::sync::Arc;
+
+use tokio::sync::watch;
+use tracing::info;
+
+/// A switch to pause and resume the acceptance of messages.
+///
+/// The pause is a cheaply cloneable handle, all clones share the same switch.
+#[derive(Clone, Debug)]
+pub struct Pause {
+    paused_tx: Arc<watch::Sender<bool>>,
+    paused_rx: watch::Receiver<bool>,
+}
+
+impl Default for Pause {
+    fn default() -> Self {
+        let (paused_tx, paused_rx) = watch::channel(false);
+        Self {
+            paused_tx: Arc::new(paused_tx),
+            paused_rx,
+        }
+    }
+}
+
+impl Pause {
+    /// Pauses the acceptance of messages.
+    ///
+    /// Returns whether the acceptance has been newly paused.
+    pub fn pause(&self) -> bool {
+        let was_paused = self.paused_tx.send_replace(true);
+        if !was_paused {
+            info!("pausing the acceptance of messages");
+        }
+        !was_paused
+    }
+
+    /// Resumes the acceptance of messages.
+    ///
+    /// Returns whether the acceptance has been paused.
+    pub fn resume(&self) -> bool {
+        let was_paused = self.paused_tx.send_replace(false);
+        if was_paused {
+            info!("resuming the acceptance of messages");
+        }
+        was_paused
+    }
+
+    /// Checks whether the acceptance of messages is paused.
+    pub fn is_paused(&self) -> bool {
+        *self.paused_rx.borrow()
+    }
+
+    /// Waits until the acceptance of messages is paused.
+    pub(in crate::state_machine) async fn paused(&mut self) {
+        self.wait_for(true).await
+    }
+
+    /// Waits until the acceptance of messages is resumed.
+    pub(in crate::state_machine) async fn resumed(&mut self) {
+        self.wait_for(false).await
+    }
+
+    async fn wait_for(&mut self, paused: bool) {
+        while *self.paused_rx.borrow_and_update() != paused {
+            // the sender lives as long as this handle, hence it can't be dropped
+            let _ = self.paused_rx.changed().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let pause = Pause::default();
+        let mut waiter = pause.clone();
+        assert!(!pause.is_paused());
+        assert!(timeout(Duration::from_millis(10), waiter.paused())
+            .await
+            .is_err());
+
+        assert!(pause.pause());
+        assert!(!pause.pause());
+        assert!(waiter.is_paused());
+        waiter.paused().await;
+
+        assert!(pause.resume());
+        assert!(!pause.resume());
+        assert!(!waiter.is_paused());
+        waiter.resumed().await;
+    }
+}
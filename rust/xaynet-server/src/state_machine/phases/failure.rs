@@ -24,7 +24,7 @@ use crate::{
         },
         StateMachine,
     },
-    storage::Storage,
+    storage::{Storage, StorageError},
 };
 
 /// Errors which can occur during the execution of the [`StateMachine`].
@@ -34,6 +34,8 @@ pub enum PhaseError {
     RequestChannel(&'static str),
     /// Phase timeout.
     PhaseTimeout(#[from] tokio::time::error::Elapsed),
+    /// Setting the coordinator state failed: {0}.
+    SetCoordinatorState(StorageError),
     /// Idle phase failed: {0}.
     Idle(#[from] IdleError),
     /// Sum phase failed: {0}.
@@ -125,9 +127,14 @@ mod tests {
     use crate::{
         state_machine::{
             coordinator::CoordinatorState,
-            events::{EventPublisher, EventSubscriber, ModelUpdate},
+            events::{EventPublisher, EventSubscriber, ModelUpdate, VersionedSeedDict},
             tests::{
-                utils::{enable_logging, init_shared, EventSnapshot},
+                utils::{
+                    assert_state_eq_except_round_stats,
+                    enable_logging,
+                    init_shared,
+                    EventSnapshot,
+                },
                 CoordinatorStateBuilder,
                 EventBusBuilder,
             },
@@ -144,8 +151,11 @@ mod tests {
         let (event_publisher, event_subscriber) = EventBusBuilder::new(&state)
             .broadcast_phase(PhaseName::Sum2)
             .broadcast_sum_dict(DictionaryUpdate::New(Arc::new(SumDict::new())))
-            .broadcast_seed_dict(DictionaryUpdate::New(Arc::new(SeedDict::new())))
-            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1))))
+            .broadcast_seed_dict(DictionaryUpdate::New(Arc::new(VersionedSeedDict {
+                version: 0,
+                seeds: SeedDict::new(),
+            })))
+            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1)), None))
             .build();
 
         (state, event_publisher, event_subscriber)
@@ -260,7 +270,7 @@ mod tests {
 
         let state_after_error = state_machine.as_ref().clone();
 
-        assert_eq!(state_after_error, state_before_error);
+        assert_state_eq_except_round_stats(&state_after_error, &state_before_error);
 
         let events_after_error = EventSnapshot::from(&event_subscriber);
         assert_ne!(events_after_error.phase, events_before_error.phase);
@@ -1,18 +1,20 @@
 use async_trait::async_trait;
-use tokio::time::{timeout, Duration};
+use tokio::time::{timeout, Duration, Instant};
 use tracing::{debug, info, Span};
 
 use crate::{
     accepted,
+    banned,
     discarded,
     rejected,
     state_machine::{
-        coordinator::{CountParameters, PhaseParameters},
+        coordinator::{CountParameters, PhaseParameters, ProcessingBudget},
         phases::{Phase, PhaseError, PhaseState},
         requests::{RequestError, ResponseSender, StateMachineRequest},
     },
     storage::Storage,
 };
+use xaynet_core::ParticipantPublicKey;
 
 /// A trait that must be implemented by a state to handle a request.
 #[async_trait]
@@ -88,6 +90,45 @@ impl Counter {
     }
 }
 
+/// A budget to keep track of the requests processed without yielding to the runtime.
+struct Budget {
+    /// The processing budget, if the state machine yields to the runtime regularly.
+    budget: Option<ProcessingBudget>,
+    /// The number of requests processed since the last yield.
+    processed: u64,
+}
+
+impl Budget {
+    /// Creates a new full budget.
+    fn new(budget: Option<ProcessingBudget>) -> Self {
+        Self {
+            budget,
+            processed: 0,
+        }
+    }
+
+    /// Consumes the budget for a request which took `elapsed` to process.
+    ///
+    /// Returns `true` and refills the budget if the state machine should yield to the runtime.
+    fn consume(&mut self, elapsed: Duration) -> bool {
+        let ProcessingBudget {
+            requests,
+            slow_request,
+        } = match self.budget {
+            Some(budget) => budget,
+            None => return false,
+        };
+
+        self.processed += 1;
+        if self.processed >= requests || elapsed >= Duration::from_millis(slow_request) {
+            self.processed = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 impl<S, T> PhaseState<S, T>
 where
     T: Storage,
@@ -100,26 +141,39 @@ where
     /// `[now + time.min, now + time.max]`.
     /// - Aborts if either all connections were dropped or not enough requests were processed until
     /// timeout.
+    /// - Yields to the runtime whenever the processing budget is exhausted, such that a burst of
+    /// requests doesn't starve other tasks.
+    /// - Rejects all requests while the acceptance of messages is paused. The time intervals are
+    /// extended by the time spent paused.
     pub(super) async fn process(
         &mut self,
         PhaseParameters { count, time }: PhaseParameters,
     ) -> Result<(), PhaseError> {
         let mut counter = Counter::new(count);
+        let mut budget = Budget::new(self.shared.state.processing_budget);
 
         info!("processing requests");
         debug!(
             "processing for min {} and max {} seconds",
             time.min, time.max
         );
-        self.process_during(Duration::from_secs(time.min), counter.as_mut())
+        self.process_during(Duration::from_secs(time.min), counter.as_mut(), &mut budget)
             .await?;
 
-        let time_left = time.max - time.min;
-        timeout(
-            Duration::from_secs(time_left),
-            self.process_until_enough(counter.as_mut()),
-        )
-        .await??;
+        let mut time_left = Duration::from_secs(time.max - time.min);
+        loop {
+            let started = Instant::now();
+            let enough = timeout(
+                time_left,
+                self.process_until_enough(counter.as_mut(), &mut budget),
+            )
+            .await??;
+            if enough {
+                break;
+            }
+            time_left = time_left.saturating_sub(started.elapsed());
+            self.reject_while_paused().await?;
+        }
 
         info!(
             "in total {} messages accepted (min {} and max {} required)",
@@ -139,9 +193,11 @@ where
         &mut self,
         dur: tokio::time::Duration,
         counter: &mut Counter,
+        budget: &mut Budget,
     ) -> Result<(), PhaseError> {
         let deadline = tokio::time::sleep(dur);
         tokio::pin!(deadline);
+        let mut pause = self.shared.pause.clone();
 
         loop {
             tokio::select! {
@@ -151,34 +207,56 @@ where
                     debug!("duration elapsed");
                     break Ok(());
                 }
+                _ = pause.paused() => {
+                    let paused_for = self.reject_while_paused().await?;
+                    let extended = deadline.deadline() + paused_for;
+                    deadline.as_mut().reset(extended);
+                }
                 next = self.next_request() => {
                     let (req, span, resp_tx) = next?;
-                    self.process_single(req, span, resp_tx, counter).await;
+                    self.process_single(req, span, resp_tx, counter, budget).await;
                 }
             }
         }
     }
 
     /// Processes requests until there are enough.
-    async fn process_until_enough(&mut self, counter: &mut Counter) -> Result<(), PhaseError> {
+    ///
+    /// Returns `false` if the acceptance of messages is paused before there are enough requests.
+    async fn process_until_enough(
+        &mut self,
+        counter: &mut Counter,
+        budget: &mut Budget,
+    ) -> Result<bool, PhaseError> {
+        let mut pause = self.shared.pause.clone();
         while !counter.has_enough_messages() {
-            let (req, span, resp_tx) = self.next_request().await?;
-            self.process_single(req, span, resp_tx, counter).await;
+            tokio::select! {
+                biased;
+
+                _ = pause.paused() => return Ok(false),
+                next = self.next_request() => {
+                    let (req, span, resp_tx) = next?;
+                    self.process_single(req, span, resp_tx, counter, budget).await;
+                }
+            }
         }
-        Ok(())
+        Ok(true)
     }
 
     /// Processes a single request.
     ///
     /// The request is discarded if the maximum message count is reached, accepted if processed
-    /// successfully and rejected otherwise.
+    /// successfully and rejected otherwise, unless it resubmits an already recorded message.
+    /// Afterwards, the state machine yields to the runtime if the processing budget is exhausted.
     async fn process_single(
         &mut self,
         req: StateMachineRequest,
         span: Span,
         resp_tx: ResponseSender,
         counter: &mut Counter,
+        budget: &mut Budget,
     ) {
+        let started = Instant::now();
         let _span_guard = span.enter();
 
         let response = if counter.has_overmuch_messages() {
@@ -187,18 +265,45 @@ where
             Err(RequestError::MessageDiscarded)
         } else {
             let response = self.handle_request(req).await;
-            if response.is_ok() {
-                counter.increment_accepted();
-                accepted!(self.shared.state.round_id, Self::NAME);
-            } else {
-                counter.increment_rejected();
-                rejected!(self.shared.state.round_id, Self::NAME);
+            match response {
+                Ok(()) => {
+                    counter.increment_accepted();
+                    accepted!(self.shared.state.round_id, Self::NAME);
+                }
+                Err(ref error) if error.is_already_recorded() => {
+                    // the message has been counted already when it was accepted originally
+                    debug!("message already recorded: {}", error);
+                }
+                Err(ref error) => {
+                    counter.increment_rejected();
+                    self.shared.state.record_rejection(error);
+                    rejected!(self.shared.state.round_id, Self::NAME);
+                }
             }
             response
         };
 
         // This may error out if the receiver has already been dropped but it doesn't matter for us.
         let _ = resp_tx.send(response);
+
+        if budget.consume(started.elapsed()) {
+            debug!("processing budget exhausted, yielding to the runtime");
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Checks whether the participant is banned.
+    ///
+    /// # Errors
+    /// Fails if the participant is on the denylist.
+    pub(super) fn check_denylist(&self, pk: &ParticipantPublicKey) -> Result<(), RequestError> {
+        if self.shared.denylist.contains(pk) {
+            debug!("participant with pk {:?} is banned", pk);
+            banned!(self.shared.state.round_id, Self::NAME);
+            Err(RequestError::Banned)
+        } else {
+            Ok(())
+        }
     }
 }
 
@@ -228,4 +333,30 @@ mod tests {
         assert!(counter.has_enough_messages());
         assert!(counter.has_overmuch_messages());
     }
+
+    #[test]
+    fn test_budget() {
+        // no budget
+        let mut budget = Budget::new(None);
+        assert!(!budget.consume(Duration::from_secs(1)));
+
+        let mut budget = Budget::new(Some(ProcessingBudget {
+            requests: 2,
+            slow_request: 10,
+        }));
+        let fast = Duration::from_millis(1);
+        let slow = Duration::from_millis(10);
+
+        // exhausted by the number of requests
+        assert!(!budget.consume(fast));
+        assert!(budget.consume(fast));
+
+        // refilled after exhaustion
+        assert!(!budget.consume(fast));
+        assert!(budget.consume(fast));
+
+        // exhausted by a single slow request
+        assert!(budget.consume(slow));
+        assert!(!budget.consume(fast));
+    }
 }
@@ -1,21 +1,26 @@
+use std::{sync::Arc, time::Duration};
+
 use async_trait::async_trait;
 use displaydoc::Display;
-use sodiumoxide::crypto::hash::sha256;
 use thiserror::Error;
+use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
 use crate::{
     metric,
     metrics::Measurement,
     state_machine::{
-        phases::{Phase, PhaseError, PhaseName, PhaseState, Shared, Sum},
+        coordinator::{CoordinatorState, TrustedAggregator},
+        events::DictionaryUpdate,
+        phases::{Phase, PhaseError, PhaseName, PhaseState, Shared, Sum, Update},
         StateMachine,
     },
-    storage::{Storage, StorageError},
+    storage::{Storage, StorageError, SumPartAddError},
 };
 use xaynet_core::{
-    common::RoundSeed,
+    common::{RoundParameters, RoundSeed},
     crypto::{ByteObject, EncryptKeyPair, SigningKeySeed},
+    mask::Aggregation,
 };
 
 /// Errors which can occur during the idle phase.
@@ -25,11 +30,77 @@ pub enum IdleError {
     SetCoordinatorState(StorageError),
     /// Deleting the dictionaries failed: {0}.
     DeleteDictionaries(StorageError),
+    /// Adding the trusted aggregator to the sum dictionary failed: {0}.
+    AddTrustedAggregator(StorageError),
+    /// The trusted aggregator was rejected as sum participant: {0}.
+    RejectedTrustedAggregator(SumPartAddError),
+    /// The aggregation needs {required} bytes of memory, exceeding the limit of {limit} bytes.
+    AggregationMemoryLimitExceeded { required: u64, limit: u64 },
 }
 
 /// The idle state.
 #[derive(Debug)]
-pub struct Idle;
+pub struct Idle {
+    /// The delay before the next round starts.
+    delay: Option<Duration>,
+}
+
+/// The pre-generated credentials and seed of a round.
+///
+/// A round is staged at the end of the previous round and swapped in by the next idle phase. The
+/// idle phase consumes the staged round whether it succeeds or not, hence the credentials are
+/// never reused across attempts.
+#[derive(Debug)]
+pub struct StagedRound {
+    /// The keys of the round.
+    keys: EncryptKeyPair,
+    /// The credentials of the trusted aggregator, if the coordinator runs in trusted mode.
+    trusted_aggregator: Option<TrustedAggregator>,
+    /// The seed derived from the parameters of the previous round.
+    seed: RoundSeed,
+}
+
+impl StagedRound {
+    /// Generates fresh credentials and derives the seed of the round which follows the round of
+    /// the coordinator `state`.
+    pub(in crate::state_machine) fn generate(state: &CoordinatorState) -> Self {
+        Self {
+            keys: EncryptKeyPair::generate(),
+            trusted_aggregator: state
+                .trusted_aggregator
+                .as_ref()
+                .map(|_| TrustedAggregator::generate()),
+            seed: derive_round_seed(state),
+        }
+    }
+}
+
+/// Derives a new round seed from the keys and the round parameters of the coordinator `state`,
+/// with the hash function of the crypto suite of the round.
+///
+/// The fractions are hashed in basis points, hence the seed doesn't depend on the last bits of
+/// their floating point representation.
+fn derive_round_seed(state: &CoordinatorState) -> RoundSeed {
+    // Safe unwrap: `sk` and `seed` have same number of bytes
+    let (_, sk) = SigningKeySeed::from_slice_unchecked(state.keys.secret.as_slice())
+        .derive_signing_key_pair();
+    let signature = sk.sign_detached(
+        &[
+            state.round_params.seed.as_slice(),
+            &state.round_params.sum_basis_points().to_le_bytes(),
+            &state.round_params.update_basis_points().to_le_bytes(),
+        ]
+        .concat(),
+    );
+    // Safe unwraps: the crypto suite is validated by the settings and the length of its digests
+    // is the length of a round seed
+    let digest = state
+        .round_params
+        .crypto_suite
+        .hash(signature.as_slice())
+        .unwrap();
+    RoundSeed::from_slice_unchecked(&digest)
+}
 
 #[async_trait]
 impl<T> Phase<T> for PhaseState<Idle, T>
@@ -39,13 +110,26 @@ where
     const NAME: PhaseName = PhaseName::Idle;
 
     async fn process(&mut self) -> Result<(), PhaseError> {
+        self.wait_inter_round_delay().await;
+
+        // a failed attempt discards the staged round, hence the next attempt uses fresh keys
+        let StagedRound {
+            keys,
+            trusted_aggregator,
+            seed,
+        } = self.take_staged_round();
+        self.check_aggregation_memory()?;
         self.delete_dicts().await?;
 
-        self.gen_round_keypair();
+        self.update_round_keypair(keys);
+        self.update_trusted_aggregator(trusted_aggregator);
+        self.update_round_thresholds();
         self.update_round_probabilities();
-        self.update_round_seed();
+        self.update_round_seed(seed);
+        self.update_round_timeline();
 
         self.set_coordinator_state().await?;
+        self.add_trusted_aggregator().await?;
 
         Ok(())
     }
@@ -53,11 +137,17 @@ where
     fn broadcast(&mut self) {
         self.broadcast_keys();
         self.broadcast_params();
+        self.broadcast_trusted_sum_dict();
         self.broadcast_metrics();
     }
 
     async fn next(self) -> Option<StateMachine<T>> {
-        Some(PhaseState::<Sum, _>::new(self.shared).into())
+        if self.shared.state.is_trusted() {
+            // the trusted aggregator replaces the sum participants of the sum and sum2 phases
+            Some(PhaseState::<Update, _>::new(self.shared).into())
+        } else {
+            Some(PhaseState::<Sum, _>::new(self.shared).into())
+        }
     }
 }
 
@@ -70,11 +160,94 @@ impl<T> PhaseState<Idle, T> {
         shared.set_round_id(shared.round_id() + 1);
         debug!("new round ID = {}", shared.round_id());
         Self {
-            private: Idle,
+            private: Idle { delay: None },
             shared,
         }
     }
 
+    /// Creates a new idle state after a round which produced a global model.
+    ///
+    /// The next round starts after the inter-round delay, if any.
+    pub fn after_round(shared: Shared<T>) -> Self {
+        let delay = shared.state.inter_round_delay.map(Duration::from_secs);
+        let mut idle = Self::new(shared);
+        idle.private.delay = delay;
+        idle
+    }
+
+    /// Waits for the inter-round delay, if any.
+    ///
+    /// The round parameters and the global model of the previous round are still published
+    /// meanwhile, hence the participants can download the global model.
+    async fn wait_inter_round_delay(&mut self) {
+        if let Some(delay) = self.private.delay.take() {
+            info!("waiting {:?} before starting the next round", delay);
+            sleep(delay).await;
+        }
+    }
+
+    /// Takes the staged round or generates it if no round has been staged.
+    fn take_staged_round(&mut self) -> StagedRound {
+        match self.shared.staged_round.take() {
+            Some(staged_round) => {
+                debug!("swapping in the staged round");
+                staged_round
+            }
+            None => StagedRound::generate(&self.shared.state),
+        }
+    }
+
+    /// Checks that the aggregation of the masked models fits into the memory limit, if any.
+    ///
+    /// The memory of the aggregation is predictable from the model length and the masking
+    /// configuration, hence a round which would exceed the limit is refused right from the start.
+    fn check_aggregation_memory(&self) -> Result<(), IdleError> {
+        let limit = match self.shared.state.aggregation_memory_limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let round_params = &self.shared.state.round_params;
+        let required = Aggregation::predicted_memory_usage(
+            round_params.mask_config,
+            round_params.model_length,
+        ) as u64;
+        if required > limit {
+            Err(IdleError::AggregationMemoryLimitExceeded { required, limit })
+        } else {
+            debug!(
+                "the aggregation needs {} of {} bytes of memory",
+                required, limit
+            );
+            Ok(())
+        }
+    }
+
+    /// Updates the minimal message counts of the phases.
+    ///
+    /// The counts are relaxed during the warm-up rounds and snap to the configured counts
+    /// afterwards, see [`CoordinatorState::sum_params()`] and its siblings.
+    ///
+    /// [`CoordinatorState::sum_params()`]: crate::state_machine::coordinator::CoordinatorState::sum_params
+    fn update_round_thresholds(&mut self) {
+        let state = &self.shared.state;
+        match state.warm_up {
+            Some(warm_up) if state.is_warm_up() => info!(
+                "warm-up round {} of {}: requiring at least {} sum and {} update messages",
+                state.round_id,
+                warm_up.rounds,
+                state.sum_params().count.min,
+                state.update_params().count.min,
+            ),
+            Some(warm_up) if state.round_id == warm_up.rounds + 1 => info!(
+                "warm-up finished: requiring at least {} sum and {} update messages",
+                state.sum_params().count.min,
+                state.update_params().count.min,
+            ),
+            _ => {}
+        }
+    }
+
     /// Updates the participant probabilities round parameters.
     fn update_round_probabilities(&mut self) {
         info!("updating round probabilities");
@@ -82,30 +255,69 @@ impl<T> PhaseState<Idle, T> {
     }
 
     /// Updates the seed round parameter.
-    fn update_round_seed(&mut self) {
+    ///
+    /// If the coordinator committed to the seed of this round in the previous round, the
+    /// committed seed is revealed, otherwise the `derived` seed is used. If commitments are
+    /// enabled, the seed of the next round is derived in advance and a commitment to it is
+    /// published with the round parameters.
+    fn update_round_seed(&mut self, derived: RoundSeed) {
         info!("updating round seed");
-        // Safe unwrap: `sk` and `seed` have same number of bytes
-        let (_, sk) =
-            SigningKeySeed::from_slice_unchecked(self.shared.state.keys.secret.as_slice())
-                .derive_signing_key_pair();
-        let signature = sk.sign_detached(
-            &[
-                self.shared.state.round_params.seed.as_slice(),
-                &self.shared.state.round_params.sum.to_le_bytes(),
-                &self.shared.state.round_params.update.to_le_bytes(),
-            ]
-            .concat(),
+        self.shared.state.round_params.seed = match self.shared.state.next_seed.take() {
+            Some(seed) => {
+                debug!("revealing the committed round seed");
+                seed
+            }
+            None => derived,
+        };
+        info!(
+            "round seed updated with token {}",
+            self.shared.state.round_params.seed.token(),
+        );
+
+        self.shared.state.round_params.next_commitment = if self.shared.state.commit_round_params {
+            info!("committing to the round parameters of the next round");
+            let next_seed = self.derive_round_seed();
+            let round_params = &self.shared.state.round_params;
+            // the fractions stay constant, see `update_round_probabilities()`
+            let commitment =
+                RoundParameters::commit(&next_seed, round_params.sum, round_params.update);
+            self.shared.state.next_seed = Some(next_seed);
+            Some(commitment)
+        } else {
+            None
+        };
+    }
+
+    /// Updates the timeline of the phases, which starts with the round now.
+    fn update_round_timeline(&mut self) {
+        let timeline = self.shared.state.phase_timeline(self.shared.clock.now());
+        debug!(
+            "round starts at {} with the update deadline at {}",
+            timeline.round_start, timeline.update_deadline,
         );
-        // Safe unwrap: the length of the hash is 32 bytes
-        self.shared.state.round_params.seed =
-            RoundSeed::from_slice_unchecked(sha256::hash(signature.as_slice()).as_ref());
+        self.shared.state.round_params.timeline = Some(timeline);
+    }
+
+    /// Derives a new round seed from the current round parameters, see [`derive_round_seed()`].
+    fn derive_round_seed(&self) -> RoundSeed {
+        derive_round_seed(&self.shared.state)
     }
 
-    /// Generates fresh round credentials.
-    fn gen_round_keypair(&mut self) {
+    /// Updates the round credentials.
+    fn update_round_keypair(&mut self, keys: EncryptKeyPair) {
         info!("updating the keys");
-        self.shared.state.keys = EncryptKeyPair::generate();
-        self.shared.state.round_params.pk = self.shared.state.keys.public;
+        self.shared.state.round_params.pk = keys.public;
+        self.shared.state.keys = keys;
+    }
+
+    /// Updates the credentials of the trusted aggregator, if the coordinator runs in trusted mode.
+    fn update_trusted_aggregator(&mut self, staged: Option<TrustedAggregator>) {
+        if let (Some(trusted_aggregator), Some(staged)) =
+            (self.shared.state.trusted_aggregator.as_mut(), staged)
+        {
+            info!("updating the keys of the trusted aggregator");
+            *trusted_aggregator = staged;
+        }
     }
 
     /// Broadcasts the keys.
@@ -123,6 +335,18 @@ impl<T> PhaseState<Idle, T> {
             .events
             .broadcast_params(self.shared.state.round_params.clone());
     }
+
+    /// Broadcasts the sum dictionary with the trusted aggregator as the only entry, if the
+    /// coordinator runs in trusted mode.
+    fn broadcast_trusted_sum_dict(&mut self) {
+        if let Some(trusted_aggregator) = self.shared.state.trusted_aggregator.as_ref() {
+            info!("broadcasting the sum dictionary of the trusted aggregator");
+            let sum_dict = trusted_aggregator.sum_dict();
+            self.shared
+                .events
+                .broadcast_sum_dict(DictionaryUpdate::New(Arc::new(sum_dict)));
+        }
+    }
 }
 
 impl<T> PhaseState<Idle, T>
@@ -148,6 +372,26 @@ where
             .await
             .map_err(IdleError::SetCoordinatorState)
     }
+
+    /// Adds the trusted aggregator as the only sum participant to the store, if the coordinator
+    /// runs in trusted mode.
+    async fn add_trusted_aggregator(&mut self) -> Result<(), IdleError> {
+        let (pk, ephm_pk) = match self.shared.state.trusted_aggregator.as_ref() {
+            Some(trusted_aggregator) => {
+                (trusted_aggregator.pk, trusted_aggregator.ephm_keys.public)
+            }
+            None => return Ok(()),
+        };
+
+        info!("adding the trusted aggregator to the sum dictionary");
+        self.shared
+            .store
+            .add_sum_participant(&pk, &ephm_pk)
+            .await
+            .map_err(IdleError::AddTrustedAggregator)?
+            .into_inner()
+            .map_err(IdleError::RejectedTrustedAggregator)
+    }
 }
 
 impl<T> PhaseState<Idle, T>
@@ -157,17 +401,20 @@ where
 {
     /// Broadcasts idle phase metrics.
     fn broadcast_metrics(&self) {
+        let round_token = self.shared.state.round_params.seed.token();
         metric!(Measurement::RoundTotalNumber, self.shared.state.round_id);
         metric!(
             Measurement::RoundParamSum,
             self.shared.state.round_params.sum,
             ("round_id", self.shared.state.round_id),
+            ("round_token", round_token.clone()),
             ("phase", Self::NAME as u8),
         );
         metric!(
             Measurement::RoundParamUpdate,
             self.shared.state.round_params.update,
             ("round_id", self.shared.state.round_id),
+            ("round_token", round_token),
             ("phase", Self::NAME as u8),
         );
     }
@@ -177,12 +424,15 @@ where
 mod tests {
     use super::*;
 
-    use std::sync::Arc;
+    use std::{
+        sync::Arc,
+        time::{Duration, UNIX_EPOCH},
+    };
 
     use anyhow::anyhow;
-    use xaynet_core::common::RoundParameters;
 
     use crate::{
+        settings::CoordinatorMode,
         state_machine::{
             coordinator::CoordinatorState,
             events::{DictionaryUpdate, EventPublisher, EventSubscriber, ModelUpdate},
@@ -191,12 +441,14 @@ mod tests {
                 CoordinatorStateBuilder,
                 EventBusBuilder,
             },
+            timings::Clock,
         },
         storage::{
             tests::{utils::create_global_model, MockCoordinatorStore, MockModelStore},
             Store,
         },
     };
+    use xaynet_core::common::PhaseTimeline;
 
     fn state_and_events_from_unmask_phase() -> (CoordinatorState, EventPublisher, EventSubscriber) {
         let state = CoordinatorStateBuilder::new().build();
@@ -205,7 +457,7 @@ mod tests {
             .broadcast_phase(PhaseName::Unmask)
             .broadcast_sum_dict(DictionaryUpdate::Invalidate)
             .broadcast_seed_dict(DictionaryUpdate::Invalidate)
-            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1))))
+            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1)), None))
             .build();
 
         (state, event_publisher, event_subscriber)
@@ -274,7 +526,7 @@ mod tests {
         // 2. broadcast Idle phase
         // 3. delete the sum/seed/mask dict
         // 4. update coordinator keys
-        // 5. update round thresholds (not implemented yet)
+        // 5. update round thresholds
         // 6. update round seeds
         // 7. save the new coordinator state
         // 8. broadcast updated keys
@@ -329,6 +581,396 @@ mod tests {
         assert!(state_machine.is_sum());
     }
 
+    #[tokio::test]
+    async fn test_idle_commits_to_next_round_params() {
+        // No Storage errors
+        // lets pretend we come from the unmask phase with enabled commitments
+        //
+        // What should happen:
+        // 1. the round parameters contain a commitment to the next round parameters
+        // 2. the next idle phase reveals round parameters that match the commitment
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts().times(2).returning(move || Ok(()));
+        cs.expect_set_coordinator_state()
+            .times(2)
+            .returning(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
+
+        let state = CoordinatorStateBuilder::new()
+            .with_commit_round_params(true)
+            .build();
+        let (event_publisher, event_subscriber) = EventBusBuilder::new(&state)
+            .broadcast_phase(PhaseName::Unmask)
+            .build();
+
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+
+        let params = event_subscriber.params_listener().get_latest().event;
+        let commitment = params.next_commitment.unwrap();
+        assert!(!params.verify_commitment(&commitment));
+
+        // skip the remaining phases of the round
+        let shared = state_machine.into_sum_phase_state().shared;
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+
+        let revealed = event_subscriber.params_listener().get_latest().event;
+        assert!(revealed.verify_commitment(&commitment));
+        assert_ne!(revealed.seed, params.seed);
+        assert_ne!(revealed.next_commitment.unwrap(), commitment);
+    }
+
+    #[tokio::test]
+    async fn test_idle_warm_up_thresholds() {
+        // No Storage errors
+        // lets pretend the coordinator starts with two warm-up rounds
+        //
+        // What should happen:
+        // 1. the first two rounds require the warm-up counts
+        // 2. the subsequent rounds require the configured counts
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts().times(4).returning(move || Ok(()));
+        cs.expect_set_coordinator_state()
+            .times(4)
+            .returning(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
+
+        let state = CoordinatorStateBuilder::new()
+            .with_sum_count_min(5)
+            .with_update_count_min(10)
+            .with_sum2_count_min(4)
+            .with_warm_up(2, 2, 3)
+            .build();
+        let (event_publisher, _event_subscriber) = EventBusBuilder::new(&state)
+            .broadcast_phase(PhaseName::Unmask)
+            .build();
+
+        let (mut shared, _request_tx) = init_shared(state, store, event_publisher);
+        for round_id in 1..=4 {
+            let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+            let state_machine = state_machine.next().await.unwrap();
+            assert!(state_machine.is_sum());
+
+            let state = state_machine.as_ref();
+            assert_eq!(state.round_id, round_id);
+            let expected = if round_id <= 2 { (2, 3, 2) } else { (5, 10, 4) };
+            assert_eq!(state.is_warm_up(), round_id <= 2);
+            assert_eq!(
+                (
+                    state.sum_params().count.min,
+                    state.update_params().count.min,
+                    state.sum2_params().count.min,
+                ),
+                expected,
+            );
+            // the maximal counts and the times are never relaxed
+            assert_eq!(state.sum_params().count.max, state.sum.count.max);
+            assert_eq!(state.update_params().time, state.update.time);
+
+            // skip the remaining phases of the round
+            shared = state_machine.into_sum_phase_state().shared;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_records_phase_timings() {
+        // lets pretend the idle phase takes longer than the soft deadline
+        //
+        // What should happen:
+        // 1. the timings of the previous round are kept
+        // 2. the idle phase is recorded with its duration and flagged
+        // 3. the sum phase is entered
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let clock = Clock::mocked(start);
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts().return_once(move || Ok(()));
+        let clock_ = clock.clone();
+        cs.expect_set_coordinator_state().return_once(move |_| {
+            clock_.advance(Duration::from_secs(90));
+            Ok(())
+        });
+        let store = Store::new(cs, MockModelStore::new());
+
+        let (mut state, event_publisher, _event_subscriber) = state_and_events_from_unmask_phase();
+        state.phase_soft_deadline = Some(60);
+        state.timings.enter(PhaseName::Unmask, start);
+        state.timings.leave(start, None);
+        let previous_timings = state.timings.clone();
+
+        let (mut shared, _request_tx) = init_shared(state, store, event_publisher);
+        shared.clock = clock;
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+
+        let state = state_machine.as_ref();
+        assert_eq!(state.last_timings, Some(previous_timings));
+        assert_eq!(state.timings.round_id, 1);
+        let idle = &state.timings.phases[0];
+        assert_eq!(idle.phase, PhaseName::Idle);
+        assert_eq!(idle.entered_at, 1_000_000);
+        assert_eq!(idle.duration(), Some(Duration::from_secs(90)));
+        assert!(idle.soft_deadline_exceeded);
+        assert_eq!(state.timings.phases.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idle_publishes_phase_timeline() {
+        // No Storage errors
+        // lets pretend we come from the unmask phase
+        //
+        // What should happen:
+        // 1. the round starts when the round parameters are updated
+        // 2. the deadlines follow from the maximal phase times
+        // 3. broadcast the timeline with the new round parameters
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts().return_once(move || Ok(()));
+        cs.expect_set_coordinator_state()
+            .return_once(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
+
+        let state = CoordinatorStateBuilder::new()
+            .with_sum_time_max(10)
+            .with_update_time_max(20)
+            .with_sum2_time_max(30)
+            .build();
+        let (event_publisher, event_subscriber) = EventBusBuilder::new(&state)
+            .broadcast_phase(PhaseName::Unmask)
+            .build();
+        let (mut shared, _request_tx) = init_shared(state, store, event_publisher);
+        shared.clock = Clock::mocked(start);
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+
+        let expected = PhaseTimeline {
+            round_start: 1_000_000,
+            sum_deadline: 1_010_000,
+            update_deadline: 1_030_000,
+            sum2_deadline: 1_060_000,
+        };
+        let params = event_subscriber.params_listener().get_latest().event;
+        assert_eq!(params.timeline, Some(expected));
+        assert_eq!(state_machine.as_ref().round_params.timeline, Some(expected));
+
+        // the skipped phases don't take any time
+        let state = CoordinatorStateBuilder::new()
+            .with_sum_time_max(10)
+            .with_update_time_max(20)
+            .with_sum2_time_max(30)
+            .with_coordinator_mode(CoordinatorMode::CollectOnly)
+            .build();
+        assert_eq!(
+            state.phase_timeline(start),
+            PhaseTimeline {
+                sum2_deadline: 1_030_000,
+                ..expected
+            }
+        );
+        let state = CoordinatorStateBuilder::new()
+            .with_sum_time_max(10)
+            .with_update_time_max(20)
+            .with_sum2_time_max(30)
+            .with_trusted_aggregator()
+            .build();
+        assert_eq!(
+            state.phase_timeline(start),
+            PhaseTimeline {
+                round_start: 1_000_000,
+                sum_deadline: 1_000_000,
+                update_deadline: 1_020_000,
+                sum2_deadline: 1_020_000,
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_waits_inter_round_delay() {
+        // lets pretend we come from the unmask phase and the inter-round delay is 30 seconds
+        //
+        // What should happen:
+        // 1. the idle phase is broadcasted right away
+        // 2. the previous round parameters and global model are kept during the delay
+        // 3. the next round starts once the delay elapsed
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts().return_once(move || Ok(()));
+        cs.expect_set_coordinator_state()
+            .return_once(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
+
+        let (mut state, event_publisher, event_subscriber) = state_and_events_from_unmask_phase();
+        state.inter_round_delay = Some(30);
+        let events_before_idle = EventSnapshot::from(&event_subscriber);
+
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::after_round(shared));
+        let start = tokio::time::Instant::now();
+        let mut next = tokio::spawn(state_machine.next());
+
+        // the mocked clock skips ahead to the earliest timer, which is the timeout
+        assert!(tokio::time::timeout(Duration::from_secs(29), &mut next)
+            .await
+            .is_err());
+        let events_during_delay = EventSnapshot::from(&event_subscriber);
+        assert_eq!(events_during_delay.phase.event, PhaseName::Idle);
+        assert_eq!(events_during_delay.params, events_before_idle.params);
+        assert_eq!(events_during_delay.model, events_before_idle.model);
+
+        let state_machine = next.await.unwrap().unwrap();
+        assert!(state_machine.is_sum());
+        assert!(start.elapsed() >= Duration::from_secs(30));
+        let events_after_idle = EventSnapshot::from(&event_subscriber);
+        assert_ne!(events_after_idle.params, events_before_idle.params);
+    }
+
+    #[tokio::test]
+    async fn test_idle_swaps_in_staged_round() {
+        // lets pretend we come from the unmask phase which staged the next round and the storage
+        // is slow
+        //
+        // What should happen:
+        // 1. the staged keys and seed are published with the round parameters
+        // 2. the staged round is consumed
+        // 3. the storage latency is the only delay until the round parameters are published
+        enable_logging();
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        let clock = Clock::mocked(start);
+
+        let mut cs = MockCoordinatorStore::new();
+        let clock_ = clock.clone();
+        cs.expect_delete_dicts().return_once(move || {
+            clock_.advance(Duration::from_secs(3));
+            Ok(())
+        });
+        let clock_ = clock.clone();
+        cs.expect_set_coordinator_state().return_once(move |_| {
+            clock_.advance(Duration::from_secs(2));
+            Ok(())
+        });
+        let store = Store::new(cs, MockModelStore::new());
+
+        let (state, event_publisher, event_subscriber) = state_and_events_from_unmask_phase();
+        let (mut shared, _request_tx) = init_shared(state, store, event_publisher);
+        shared.clock = clock;
+        shared.stage_next_round();
+        let staged = shared.staged_round.as_ref().unwrap();
+        let staged_pk = staged.keys.public;
+        let staged_seed = staged.seed.clone();
+
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+
+        let params = event_subscriber.params_listener().get_latest().event;
+        assert_eq!(params.pk, staged_pk);
+        assert_eq!(params.seed, staged_seed);
+        assert_eq!(state_machine.as_ref().keys.public, staged_pk);
+
+        let idle = &state_machine.as_ref().timings.phases[0];
+        assert_eq!(idle.duration(), Some(Duration::from_secs(5)));
+        assert!(state_machine
+            .into_sum_phase_state()
+            .shared
+            .staged_round
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idle_discards_staged_round_on_failure() {
+        // Storage:
+        // - delete_dicts fails once
+        //
+        // What should happen:
+        // 1. the failed idle phase discards the staged round
+        // 2. the next idle phase generates fresh keys
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        let mut failed = false;
+        cs.expect_delete_dicts().times(2).returning(move || {
+            if failed {
+                Ok(())
+            } else {
+                failed = true;
+                Err(anyhow!(""))
+            }
+        });
+        cs.expect_set_coordinator_state()
+            .return_once(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
+
+        let (state, event_publisher, event_subscriber) = state_and_events_from_unmask_phase();
+        let (mut shared, _request_tx) = init_shared(state, store, event_publisher);
+        shared.stage_next_round();
+        let staged_pk = shared.staged_round.as_ref().unwrap().keys.public;
+
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_failure());
+
+        let shared = state_machine.into_failure_phase_state().shared;
+        assert!(shared.staged_round.is_none());
+
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+
+        let params = event_subscriber.params_listener().get_latest().event;
+        assert_ne!(params.pk, staged_pk);
+        assert_eq!(state_machine.as_ref().keys.public, params.pk);
+    }
+
+    #[tokio::test]
+    async fn test_derive_round_seed_is_stable() {
+        // the same fractions in basis points must yield the same seed, even if their floating
+        // point representations differ in the last bit
+        let store = Store::new(MockCoordinatorStore::new(), MockModelStore::new());
+        let state = CoordinatorStateBuilder::new().build();
+        let (event_publisher, _event_subscriber) = EventBusBuilder::new(&state).build();
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let mut idle = PhaseState::<Idle, _>::new(shared);
+
+        let seed = idle.derive_round_seed();
+        assert_eq!(idle.derive_round_seed(), seed);
+
+        let round_params = &mut idle.shared.state.round_params;
+        round_params.sum = f64::from_bits(round_params.sum.to_bits() + 1);
+        round_params.update = f64::from_bits(round_params.update.to_bits() - 1);
+        assert_eq!(idle.derive_round_seed(), seed);
+
+        // a difference of a basis point changes the seed
+        idle.shared.state.round_params.sum += 0.0001;
+        assert_ne!(idle.derive_round_seed(), seed);
+    }
+
+    #[cfg(feature = "blake3")]
+    #[tokio::test]
+    async fn test_derive_round_seed_with_crypto_suite() {
+        let store = Store::new(MockCoordinatorStore::new(), MockModelStore::new());
+        let state = CoordinatorStateBuilder::new().build();
+        let (event_publisher, _event_subscriber) = EventBusBuilder::new(&state).build();
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let mut idle = PhaseState::<Idle, _>::new(shared);
+
+        let sha256_seed = idle.derive_round_seed();
+        idle.shared.state.round_params.crypto_suite = xaynet_core::crypto::CryptoSuite::BLAKE3;
+        let blake3_seed = idle.derive_round_seed();
+        assert_eq!(idle.derive_round_seed(), blake3_seed);
+        assert_ne!(blake3_seed, sha256_seed);
+    }
+
     #[tokio::test]
     async fn test_idle_to_sum_delete_dicts_failed() {
         // Storage:
@@ -388,7 +1030,7 @@ mod tests {
         // 2. broadcast Idle phase
         // 3. delete the sum/seed/mask dict
         // 4. update coordinator keys
-        // 5. update round thresholds (not implemented yet)
+        // 5. update round thresholds
         // 6. update round seeds
         // 7. save the new coordinator state (fails)
 
@@ -450,4 +1092,59 @@ mod tests {
             PhaseError::Idle(IdleError::SetCoordinatorState(_))
         ))
     }
+
+    #[tokio::test]
+    async fn test_idle_aggregation_memory_limit_exceeded() {
+        // No storage interaction
+        //
+        // What should happen:
+        // 1. increase round id by 1
+        // 2. broadcast Idle phase
+        // 3. refuse the round because the aggregation exceeds the memory limit
+        // 4. move into error phase
+        //
+        // What should not happen:
+        // - the dicts have been deleted
+        // - new keys have been broadcasted
+        // - new round parameters have been broadcasted
+        // - the state machine has moved into sum phase
+        enable_logging();
+
+        let store = Store::new(MockCoordinatorStore::new(), MockModelStore::new());
+
+        let state = CoordinatorStateBuilder::new()
+            .with_model_length(1000)
+            .with_aggregation_memory_limit(1000)
+            .build();
+        let (event_publisher, event_subscriber) = EventBusBuilder::new(&state)
+            .broadcast_phase(PhaseName::Unmask)
+            .build();
+        let events_before_idle = EventSnapshot::from(&event_subscriber);
+        let state_before_idle = state.clone();
+
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        assert!(state_machine.is_idle());
+
+        let state_machine = state_machine.next().await.unwrap();
+
+        let state_after_idle = state_machine.as_ref().clone();
+        let events_after_idle = EventSnapshot::from(&event_subscriber);
+        assert_after_delete_dict_failure(
+            &state_before_idle,
+            &events_before_idle,
+            &state_after_idle,
+            &events_after_idle,
+        );
+
+        assert!(state_machine.is_failure());
+        let required =
+            Aggregation::predicted_memory_usage(state_before_idle.round_params.mask_config, 1000)
+                as u64;
+        assert!(matches!(
+            state_machine.into_failure_phase_state().private.error,
+            PhaseError::Idle(IdleError::AggregationMemoryLimitExceeded { required: r, limit: 1000 })
+                if r == required
+        ))
+    }
 }
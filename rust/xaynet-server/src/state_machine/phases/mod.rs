@@ -15,11 +15,11 @@ mod update;
 pub use self::{
     failure::{Failure, PhaseError},
     handler::Handler,
-    idle::{Idle, IdleError},
+    idle::{Idle, IdleError, StagedRound},
     phase::{Phase, PhaseName, PhaseState, Shared},
     shutdown::Shutdown,
     sum::{Sum, SumError},
     sum2::Sum2,
     unmask::{Unmask, UnmaskError},
-    update::{Update, UpdateError},
+    update::{RestoredAggregation, Update, UpdateError},
 };
@@ -1,8 +1,10 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use async_trait::async_trait;
 use derive_more::Display;
 use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
 use tracing::{debug, error, error_span, info, warn, Span};
 use tracing_futures::Instrument;
 
@@ -12,16 +14,19 @@ use crate::{
     metrics::Measurement,
     state_machine::{
         coordinator::CoordinatorState,
+        denylist::Denylist,
         events::EventPublisher,
-        phases::{Failure, PhaseError},
+        pause::Pause,
+        phases::{Failure, PhaseError, StagedRound},
         requests::{RequestError, RequestReceiver, ResponseSender, StateMachineRequest},
+        timings::{Clock, RoundTimings},
         StateMachine,
     },
     storage::Storage,
 };
 
 /// The name of the current phase.
-#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PhaseName {
     #[display(fmt = "Idle")]
     Idle,
@@ -80,6 +85,14 @@ pub struct Shared<T> {
     pub(in crate::state_machine) events: EventPublisher,
     /// The store for storing coordinator and model data.
     pub(in crate::state_machine) store: T,
+    /// The clock for recording the phase transitions.
+    pub(in crate::state_machine) clock: Clock,
+    /// The denylist of banned participants.
+    pub(in crate::state_machine) denylist: Denylist,
+    /// The switch to pause the acceptance of messages.
+    pub(in crate::state_machine) pause: Pause,
+    /// The pre-generated credentials and seed of the next round, if any.
+    pub(in crate::state_machine) staged_round: Option<StagedRound>,
 }
 
 impl<T> fmt::Debug for Shared<T> {
@@ -105,12 +118,30 @@ impl<T> Shared<T> {
             request_rx,
             events: publisher,
             store,
+            clock: Clock::default(),
+            denylist: Denylist::default(),
+            pause: Pause::default(),
+            staged_round: None,
         }
     }
 
+    /// Pre-generates the credentials and the seed of the next round.
+    ///
+    /// The staged round is swapped in by the next idle phase, which keeps the generation out of
+    /// the time span in which no valid round parameters are published.
+    pub(in crate::state_machine) fn stage_next_round(&mut self) {
+        debug!("staging the credentials and the seed of the next round");
+        self.staged_round = Some(StagedRound::generate(&self.state));
+    }
+
     /// Sets the round ID to the given value.
+    ///
+    /// This resets the rejection, tie breaking and seed dictionary stats of the previous round.
     pub fn set_round_id(&mut self, id: u64) {
         self.state.round_id = id;
+        self.state.rejections.clear();
+        self.state.broken_tie = None;
+        self.state.seed_dict_mismatch = None;
         self.events.set_round_id(id);
     }
 
@@ -118,6 +149,71 @@ impl<T> Shared<T> {
     pub fn round_id(&self) -> u64 {
         self.state.round_id
     }
+
+    /// Records that the `phase` is entered.
+    ///
+    /// Entering the idle phase starts the timings of a new round, the timings of the previous
+    /// round are kept until the next round starts.
+    fn enter_phase(&mut self, phase: PhaseName) {
+        let round_id = self.round_id();
+        if self.state.timings.round_id != round_id {
+            let timings = std::mem::replace(&mut self.state.timings, RoundTimings::new(round_id));
+            if !timings.phases.is_empty() {
+                self.state.last_timings = Some(timings);
+            }
+        }
+        let now = self.clock.now();
+        self.state.timings.enter(phase, now);
+    }
+
+    /// Records that the current phase is left.
+    ///
+    /// Warns if the phase exceeded the soft deadline.
+    fn leave_phase(&mut self) {
+        let now = self.clock.now();
+        let soft_deadline = self.state.phase_soft_deadline.map(Duration::from_secs);
+        let round_id = self.round_id();
+        let timing = match self.state.timings.leave(now, soft_deadline) {
+            Some(timing) => timing,
+            None => return,
+        };
+        // safe unwrap: the phase has been left
+        let duration = timing.duration().unwrap();
+        metric!(
+            Measurement::PhaseDuration,
+            duration.as_secs_f64(),
+            ("round_id", round_id),
+            ("phase", timing.phase as u8),
+        );
+        if timing.soft_deadline_exceeded {
+            warn!(
+                round_id,
+                phase = %timing.phase,
+                duration_secs = duration.as_secs_f64(),
+                soft_deadline_secs = self.state.phase_soft_deadline,
+                "phase exceeded its soft deadline",
+            );
+            metric!(
+                Measurement::PhaseSoftDeadlineExceeded,
+                1,
+                ("round_id", round_id),
+                ("phase", timing.phase as u8),
+            );
+        }
+    }
+}
+
+impl<T> Shared<T>
+where
+    T: Storage,
+{
+    /// Persists the coordinator state to the store.
+    async fn set_coordinator_state(&mut self) -> Result<(), PhaseError> {
+        self.store
+            .set_coordinator_state(&self.state)
+            .await
+            .map_err(PhaseError::SetCoordinatorState)
+    }
 }
 
 /// The state corresponding to a phase of the PET protocol.
@@ -149,15 +245,33 @@ where
 
         async move {
             info!("starting phase");
+            self.shared.enter_phase(phase);
             self.shared.events.broadcast_phase(phase);
             metric!(Measurement::Phase, phase as u8);
 
+            // the phases which accept messages are persisted, such that a replica which takes
+            // over from a deposed leader resumes the phase
+            if let PhaseName::Sum | PhaseName::Update | PhaseName::Sum2 = phase {
+                if let Err(err) = self.shared.set_coordinator_state().await {
+                    warn!("failed to store the coordinator state");
+                    return Some(self.into_failure_state(err));
+                }
+            }
+
             if let Err(err) = self.process().await {
                 warn!("failed to perform the phase tasks");
                 return Some(self.into_failure_state(err));
             }
             info!("phase ran successfully");
 
+            // the phase transitions are suspended while the acceptance of messages is paused
+            if phase != PhaseName::Shutdown && self.shared.pause.is_paused() {
+                if let Err(err) = self.reject_while_paused().await {
+                    warn!("failed to wait for the acceptance of messages to resume");
+                    return Some(self.into_failure_state(err));
+                }
+            }
+
             if let Err(err) = self.purge_outdated_requests() {
                 warn!("failed to purge outdated requests");
                 if let PhaseName::Failure | PhaseName::Shutdown = phase {
@@ -171,6 +285,7 @@ where
             }
 
             self.broadcast();
+            self.shared.leave_phase();
 
             info!("transitioning to the next phase");
             self.next().await
@@ -207,6 +322,34 @@ impl<S, T> PhaseState<S, T> {
         })
     }
 
+    /// Rejects all requests until the acceptance of messages is resumed.
+    ///
+    /// Returns the time spent paused.
+    ///
+    /// # Errors
+    /// Returns [`PhaseError::RequestChannel`] when all sender halves have been dropped.
+    pub(in crate::state_machine) async fn reject_while_paused(
+        &mut self,
+    ) -> Result<Duration, PhaseError> {
+        let started = Instant::now();
+        let mut pause = self.shared.pause.clone();
+        debug!("waiting for the acceptance of messages to resume");
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = pause.resumed() => break,
+                next = self.next_request() => {
+                    let (_, span, resp_tx) = next?;
+                    let _span_guard = span.enter();
+                    debug!("rejecting request: the acceptance of messages is paused");
+                    let _ = resp_tx.send(Err(RequestError::Paused));
+                }
+            }
+        }
+        Ok(started.elapsed())
+    }
+
     pub fn try_next_request(
         &mut self,
     ) -> Result<Option<(StateMachineRequest, Span, ResponseSender)>, PhaseError> {
@@ -225,7 +368,8 @@ impl<S, T> PhaseState<S, T> {
         }
     }
 
-    fn into_failure_state(self, err: PhaseError) -> StateMachine<T> {
+    fn into_failure_state(mut self, err: PhaseError) -> StateMachine<T> {
+        self.shared.leave_phase();
         PhaseState::<Failure, _>::new(self.shared, err).into()
     }
 }
@@ -1,18 +1,22 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use async_trait::async_trait;
 use displaydoc::Display;
 use thiserror::Error;
-use tracing::info;
+use tracing::{error, info, warn};
 
 use crate::{
+    metric,
+    metrics::Measurement,
+    settings::MULTIPARTY_SUM_COUNT_MIN,
     state_machine::{
+        coordinator::PhaseParameters,
         events::DictionaryUpdate,
         phases::{Handler, Phase, PhaseError, PhaseName, PhaseState, Shared, Update},
         requests::{RequestError, StateMachineRequest, SumRequest},
         StateMachine,
     },
-    storage::{Storage, StorageError},
+    storage::{Storage, StorageError, SumPartAddError},
 };
 use xaynet_core::{SumDict, SumParticipantEphemeralPublicKey, SumParticipantPublicKey};
 
@@ -30,6 +34,10 @@ pub enum SumError {
 pub struct Sum {
     /// The sum dictionary which gets assembled during the sum phase.
     sum_dict: Option<SumDict>,
+    /// The client certificate identities of the accepted sum participants.
+    identities: HashSet<String>,
+    /// The number of sum participants which have been restored from the store.
+    nb_restored: u64,
 }
 
 #[async_trait]
@@ -41,7 +49,13 @@ where
     const NAME: PhaseName = PhaseName::Sum;
 
     async fn process(&mut self) -> Result<(), PhaseError> {
-        self.process(self.shared.state.sum).await?;
+        if self.shared.state.is_multiparty_enforced() {
+            warn!(
+                "enforcing at least {} distinct sum participants to protect the update participants",
+                MULTIPARTY_SUM_COUNT_MIN
+            );
+        }
+        self.process(self.sum_params()).await?;
         self.sum_dict().await?;
 
         Ok(())
@@ -73,9 +87,21 @@ where
         if let StateMachineRequest::Sum(SumRequest {
             participant_pk,
             ephm_pk,
+            identity,
         }) = req
         {
-            self.update_sum_dict(participant_pk, ephm_pk).await
+            self.check_denylist(&participant_pk)?;
+            if !self.shared.state.distinct_sum_identities {
+                return self.update_sum_dict(participant_pk, ephm_pk).await;
+            }
+
+            let identity = identity.ok_or(RequestError::MissingIdentity)?;
+            if self.private.identities.contains(&identity) {
+                return Err(RequestError::DuplicateIdentity);
+            }
+            self.update_sum_dict(participant_pk, ephm_pk).await?;
+            self.private.identities.insert(identity);
+            Ok(())
         } else {
             Err(RequestError::MessageRejected)
         }
@@ -86,10 +112,39 @@ impl<T> PhaseState<Sum, T> {
     /// Creates a new sum state.
     pub fn new(shared: Shared<T>) -> Self {
         Self {
-            private: Sum { sum_dict: None },
+            private: Sum {
+                sum_dict: None,
+                identities: HashSet::new(),
+                nb_restored: 0,
+            },
             shared,
         }
     }
+
+    /// Creates a sum state which resumes the sum phase of a deposed leader with the given number
+    /// of sum participants in the store.
+    ///
+    /// The restored sum participants count towards the accepted sum messages of the phase,
+    /// whereas the time of the phase starts over. Their client certificate identities are
+    /// unknown, hence they aren't taken into account for distinct sum identities.
+    pub fn resume(shared: Shared<T>, nb_restored: u64) -> Self {
+        Self {
+            private: Sum {
+                sum_dict: None,
+                identities: HashSet::new(),
+                nb_restored,
+            },
+            shared,
+        }
+    }
+
+    /// Gets the sum phase parameters, where the restored sum participants count as accepted.
+    fn sum_params(&self) -> PhaseParameters {
+        let mut params = self.shared.state.sum_params();
+        params.count.min = params.count.min.saturating_sub(self.private.nb_restored);
+        params.count.max = params.count.max.saturating_sub(self.private.nb_restored);
+        params
+    }
 }
 
 impl<T> PhaseState<Sum, T>
@@ -97,6 +152,9 @@ where
     T: Storage,
 {
     /// Updates the sum dict with a sum participant request.
+    ///
+    /// If the sum dict is bounded, the oldest sum participants which exceed its capacity are
+    /// evicted.
     async fn update_sum_dict(
         &mut self,
         participant_pk: SumParticipantPublicKey,
@@ -107,7 +165,40 @@ where
             .add_sum_participant(&participant_pk, &ephm_pk)
             .await?
             .into_inner()
-            .map_err(RequestError::from)
+            .map_err(|err| {
+                if let SumPartAddError::EphemeralKeyCollision = err {
+                    error!(
+                        "ephemeral key of sum participant {:?} collides with another one: the client rng may be broken",
+                        participant_pk
+                    );
+                    metric!(
+                        Measurement::EphemeralKeyCollisionRejected,
+                        1,
+                        ("round_id", self.shared.state.round_id),
+                    );
+                }
+                RequestError::from(err)
+            })?;
+
+        if let Some(capacity) = self.shared.state.sum_dict_capacity {
+            self.evict_sum_participants(capacity).await?;
+        }
+        Ok(())
+    }
+
+    /// Evicts the oldest sum participants which exceed the `capacity` of the sum dict.
+    ///
+    /// The store records the evictions, such that the evicted sum participants learn about them
+    /// from their submission status.
+    async fn evict_sum_participants(&mut self, capacity: u64) -> Result<(), RequestError> {
+        let evicted = self.shared.store.evict_sum_participants(capacity).await?;
+        for sum_pk in evicted {
+            warn!(
+                "sum dictionary capacity of {} exceeded: evicted sum participant {:?}",
+                capacity, sum_pk
+            );
+        }
+        Ok(())
     }
 
     /// Gets the sum dict from the store.
@@ -129,17 +220,30 @@ where
 mod tests {
     use super::*;
 
+    use std::sync::atomic::{AtomicBool, Ordering};
+
     use anyhow::anyhow;
-    use tokio::time::{timeout, Duration};
-    use xaynet_core::SumDict;
+    use mockall::Sequence;
+    use tokio::time::{timeout, Duration, Instant};
+    use tracing::Span;
+    use xaynet_core::{
+        crypto::{EncryptKeyPair, SigningKeyPair},
+        SumDict,
+    };
 
     use crate::{
         state_machine::{
             coordinator::CoordinatorState,
             events::{EventPublisher, EventSubscriber, ModelUpdate},
+            pause::Pause,
+            requests::{RejectionReason, RequestSender},
             tests::{
                 utils::{
                     assert_event_updated,
+                    assert_state_eq_except_round_stats,
+                    compose_sum2_message,
+                    compose_sum_message,
+                    compose_update_message,
                     enable_logging,
                     init_shared,
                     send_sum2_messages,
@@ -152,7 +256,13 @@ mod tests {
             },
         },
         storage::{
-            tests::{utils::create_global_model, MockCoordinatorStore, MockModelStore},
+            coordinator_storage::memory::MemoryStorage,
+            tests::{
+                utils::{create_global_model, create_mask},
+                MockCoordinatorStore,
+                MockModelStore,
+            },
+            CoordinatorStorage,
             Store,
             SumPartAdd,
             SumPartAddError,
@@ -164,7 +274,7 @@ mod tests {
             .broadcast_phase(PhaseName::Idle)
             .broadcast_sum_dict(DictionaryUpdate::Invalidate)
             .broadcast_seed_dict(DictionaryUpdate::Invalidate)
-            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1))))
+            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1)), None))
             .build()
     }
 
@@ -174,7 +284,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_event_updated(&events_after.sum_dict, &events_before.sum_dict);
@@ -191,7 +301,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_eq!(events_after.keys, events_before.keys);
@@ -220,6 +330,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_sum_participant()
             .times(10)
             .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
@@ -257,6 +368,161 @@ mod tests {
         assert!(state_machine.is_update());
     }
 
+    #[tokio::test]
+    async fn test_sum_phase_requires_multiparty() {
+        // No Storage errors
+        // lets pretend a single sum participant is enough wrt the minimal sum count
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. accept a single sum message
+        // 3. wait for a second sum participant until the phase times out
+        // 4. move into error phase
+        //
+        // What should not happen:
+        // - the sum dict has been fetched
+        // - the state machine has moved into update phase
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_sum_participant()
+            .times(1)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum_count_min(1)
+            .with_require_multiparty(true)
+            .with_sum_time_min(0)
+            .with_sum_time_max(2)
+            .build();
+        assert!(state.is_multiparty_enforced());
+        assert_eq!(state.sum_params().count.min, MULTIPARTY_SUM_COUNT_MIN);
+
+        let (event_publisher, event_subscriber) = events_from_idle_phase(&state);
+        let events_before_sum = EventSnapshot::from(&event_subscriber);
+
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+        assert!(state_machine.is_sum());
+
+        send_sum_messages(1, request_tx.clone());
+
+        let state_machine = timeout(Duration::from_secs(4), state_machine.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let events_after_sum = EventSnapshot::from(&event_subscriber);
+        assert_eq!(events_after_sum.sum_dict, events_before_sum.sum_dict);
+        assert!(state_machine.is_failure());
+        assert!(matches!(
+            state_machine.into_failure_phase_state().private.error,
+            PhaseError::PhaseTimeout(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sum_phase_multiparty_to_update_phase() {
+        // No Storage errors
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. accept two sum messages
+        // 3. fetch sum dict
+        // 4. move into update phase
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_sum_participant()
+            .times(2)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        cs.expect_sum_dict()
+            .return_once(move || Ok(Some(SumDict::new())));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum_count_min(1)
+            .with_require_multiparty(true)
+            .with_sum_time_min(0)
+            .build();
+
+        let (event_publisher, _event_subscriber) = events_from_idle_phase(&state);
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+
+        send_sum_messages(2, request_tx.clone());
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_update());
+    }
+
+    /// Runs the sum phase for a flood of slow sum messages and measures the longest time another
+    /// task on the same runtime waits to be scheduled meanwhile.
+    async fn max_scheduling_latency(state: CoordinatorState) -> Duration {
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_sum_participant()
+            .times(100)
+            .returning(move |_, _| {
+                // blocks the runtime like an expensive request
+                std::thread::sleep(std::time::Duration::from_millis(2));
+                Ok(SumPartAdd(Ok(())))
+            });
+        cs.expect_sum_dict()
+            .return_once(move || Ok(Some(SumDict::new())));
+        let store = Store::new(cs, MockModelStore::new());
+
+        let (event_publisher, _event_subscriber) = events_from_idle_phase(&state);
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+
+        // the ticker is spawned before the messages, such that it already measures while the
+        // first batch of messages is processed
+        let done = Arc::new(AtomicBool::new(false));
+        let ticker = tokio::spawn({
+            let done = done.clone();
+            async move {
+                let mut max_latency = Duration::from_secs(0);
+                let mut last = Instant::now();
+                while !done.load(Ordering::SeqCst) {
+                    tokio::task::yield_now().await;
+                    let now = Instant::now();
+                    max_latency = max_latency.max(now - last);
+                    last = now;
+                }
+                max_latency
+            }
+        });
+        send_sum_messages(100, request_tx.clone());
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_update());
+        done.store(true, Ordering::SeqCst);
+        ticker.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sum_phase_processing_budget() {
+        // the messages of 2ms each are processed in batches which block the runtime for more than
+        // 100ms in a row
+        let builder = || {
+            CoordinatorStateBuilder::new()
+                .with_round_id(1)
+                .with_sum_count_min(100)
+                .with_sum_count_max(100)
+                .with_sum_time_min(0)
+        };
+        let state = builder().build();
+        assert!(max_scheduling_latency(state).await > Duration::from_millis(100));
+
+        // the state machine yields after every 4 messages
+        let state = builder().with_processing_budget(4, 100).build();
+        assert!(max_scheduling_latency(state).await < Duration::from_millis(100));
+    }
+
     #[tokio::test]
     async fn test_sum_phase_timeout() {
         // No Storage errors
@@ -273,7 +539,9 @@ mod tests {
         // - the sum dict has been broadcasted
         enable_logging();
 
-        let store = Store::new(MockCoordinatorStore::new(), MockModelStore::new());
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
         let state = CoordinatorStateBuilder::new()
             .with_round_id(1)
             .with_sum_time_min(1)
@@ -327,6 +595,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_sum_participant()
             .times(7)
             .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
@@ -365,6 +634,68 @@ mod tests {
         assert!(state_machine.is_update());
     }
 
+    #[tokio::test]
+    async fn test_paused_sum_phase() {
+        // No Storage errors
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. reject sum messages while paused without changing the round state
+        // 3. accept 3 sum messages after resuming
+        // 4. fetch sum dict
+        // 5. broadcast sum dict
+        // 6. move into update phase
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_sum_participant()
+            .times(3)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        cs.expect_sum_dict()
+            .return_once(move || Ok(Some(SumDict::new())));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum_count_min(3)
+            .with_sum_count_max(3)
+            .build();
+
+        let (event_publisher, event_subscriber) = events_from_idle_phase(&state);
+        let events_before_sum = EventSnapshot::from(&event_subscriber);
+        let state_before_sum = state.clone();
+
+        let (mut shared, request_tx) = init_shared(state, store, event_publisher);
+        let pause = Pause::default();
+        shared.pause = pause.clone();
+        pause.pause();
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+        let mut next = tokio::spawn(state_machine.next());
+
+        for _ in 0..3 {
+            let response = request_tx.msg(&compose_sum_message()).await;
+            assert!(matches!(response, Err(RequestError::Paused)));
+        }
+        // the phase doesn't transition while paused
+        assert!(timeout(Duration::from_millis(100), &mut next).await.is_err());
+
+        pause.resume();
+        send_sum_messages(3, request_tx.clone());
+        let state_machine = next.await.unwrap().unwrap();
+
+        let state_after_sum = state_machine.as_ref().clone();
+        assert!(state_after_sum.rejections.is_empty());
+        let events_after_sum = EventSnapshot::from(&event_subscriber);
+        assert_after_phase_success(
+            &state_before_sum,
+            &events_before_sum,
+            &state_after_sum,
+            &events_after_sum,
+        );
+
+        assert!(state_machine.is_update());
+    }
+
     #[tokio::test]
     async fn test_discarded_messages() {
         // No Storage errors
@@ -383,6 +714,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_sum_participant()
             .times(5)
             .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
@@ -437,7 +769,9 @@ mod tests {
         // - the sum dict has been broadcasted
         enable_logging();
 
-        let store = Store::new(MockCoordinatorStore::new(), MockModelStore::new());
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
         let state = CoordinatorStateBuilder::new()
             .with_round_id(1)
             .with_sum_count_min(1)
@@ -491,6 +825,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_sum_participant()
             .times(1)
             .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
@@ -548,6 +883,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_sum_participant()
             .times(1)
             .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
@@ -606,6 +942,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_sum_participant()
             .times(3)
             .returning(move |_, _| Ok(SumPartAdd(Err(SumPartAddError::AlreadyExists))));
@@ -646,6 +983,266 @@ mod tests {
         ))
     }
 
+    #[tokio::test]
+    async fn test_rejection_stats() {
+        // Storage error for one sum message
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. reject 1 update and 1 sum2 message (unexpected messages)
+        // 3. reject 2 sum messages (pet error SumPartAddError::AlreadyExists)
+        // 4. reject 1 sum message (storage error)
+        // 5. accept 1 sum message
+        // 6. count the rejected messages per reason
+        // 7. move into update phase
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        let mut seq = Sequence::new();
+        cs.expect_add_sum_participant()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(move |_, _| Ok(SumPartAdd(Err(SumPartAddError::AlreadyExists))));
+        cs.expect_add_sum_participant()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_, _| Err(anyhow!("")));
+        cs.expect_add_sum_participant()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        cs.expect_sum_dict()
+            .return_once(move || Ok(Some(SumDict::new())));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum_count_min(1)
+            .with_sum_count_max(1)
+            .with_sum_time_min(0)
+            .build();
+
+        let (event_publisher, _event_subscriber) = events_from_idle_phase(&state);
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+
+        // send the messages one after another, such that they are processed in order
+        let send_messages = async move {
+            let update = compose_update_message(create_mask(1, 1));
+            assert!(request_tx.msg(&update).await.is_err());
+            assert!(request_tx.msg(&compose_sum2_message()).await.is_err());
+            for _ in 0..3 {
+                assert!(request_tx.msg(&compose_sum_message()).await.is_err());
+            }
+            assert!(request_tx.msg(&compose_sum_message()).await.is_ok());
+            // keep the request channel open
+            request_tx
+        };
+        let (state_machine, _request_tx) = tokio::join!(state_machine.next(), send_messages);
+        let state_machine = state_machine.unwrap();
+        assert!(state_machine.is_update());
+
+        let stats = state_machine.as_ref().rejection_stats();
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[&RejectionReason::UnexpectedMessage], 2);
+        assert_eq!(stats[&RejectionReason::Duplicate], 2);
+        assert_eq!(stats[&RejectionReason::Internal], 1);
+    }
+
+    #[tokio::test]
+    async fn test_already_recorded_messages() {
+        // No Storage errors
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. answer 2 resubmitted sum messages (pet error SumPartAddError::AlreadyRecorded)
+        // 3. accept 1 sum message
+        // 4. move into update phase
+        //
+        // What should not happen:
+        // - the resubmitted messages are counted as rejected
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        let mut seq = Sequence::new();
+        cs.expect_add_sum_participant()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(move |_, _| Ok(SumPartAdd(Err(SumPartAddError::AlreadyRecorded))));
+        cs.expect_add_sum_participant()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        cs.expect_sum_dict()
+            .return_once(move || Ok(Some(SumDict::new())));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum_count_min(1)
+            .with_sum_count_max(1)
+            .with_sum_time_min(0)
+            .build();
+
+        let (event_publisher, _event_subscriber) = events_from_idle_phase(&state);
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+
+        // send the messages one after another, such that they are processed in order
+        let send_messages = async move {
+            for _ in 0..2 {
+                let error = request_tx.msg(&compose_sum_message()).await.unwrap_err();
+                assert!(error.is_already_recorded());
+            }
+            assert!(request_tx.msg(&compose_sum_message()).await.is_ok());
+            // keep the request channel open
+            request_tx
+        };
+        let (state_machine, _request_tx) = tokio::join!(state_machine.next(), send_messages);
+        let state_machine = state_machine.unwrap();
+        assert!(state_machine.is_update());
+        assert!(state_machine.as_ref().rejection_stats().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sum_dict_eviction() {
+        // No Storage errors
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. accept 4 sum messages
+        // 3. evict the oldest sum participant after the fourth message exceeded the capacity
+        // 4. record the eviction in the submission status of the evicted sum participant
+        // 5. fetch and broadcast the sum dict
+        // 6. move into update phase
+        enable_logging();
+
+        let mut storage = MemoryStorage::new();
+        let store = Store::new(storage.clone(), MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum_dict_capacity(3)
+            .with_sum_count_min(3)
+            .with_sum_count_max(4)
+            .with_sum_time_min(1)
+            .build();
+
+        let (event_publisher, event_subscriber) = events_from_idle_phase(&state);
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+
+        let sum_pks = (0..4)
+            .map(|_| SigningKeyPair::generate().public)
+            .collect::<Vec<_>>();
+        let send_messages = async {
+            for participant_pk in sum_pks.iter() {
+                let request = StateMachineRequest::Sum(SumRequest {
+                    participant_pk: *participant_pk,
+                    ephm_pk: EncryptKeyPair::generate().public,
+                    identity: None,
+                });
+                request_tx.request(request, Span::none()).await.unwrap();
+            }
+            request_tx
+        };
+        let (state_machine, _request_tx) = tokio::join!(state_machine.next(), send_messages);
+        let state_machine = state_machine.unwrap();
+        assert!(state_machine.is_update());
+
+        let status = storage.submission_status(&sum_pks[0]).await.unwrap();
+        assert!(status.evicted);
+        assert!(!status.sum);
+        for sum_pk in &sum_pks[1..] {
+            let status = storage.submission_status(sum_pk).await.unwrap();
+            assert!(!status.evicted);
+            assert!(status.sum);
+        }
+
+        let events_after_sum = EventSnapshot::from(&event_subscriber);
+        assert_eq!(events_after_sum.sum_dict.round_id, 1);
+        match events_after_sum.sum_dict.event {
+            DictionaryUpdate::New(sum_dict) => {
+                assert_eq!(sum_dict.len(), 3);
+                assert!(!sum_dict.contains_key(&sum_pks[0]));
+            }
+            DictionaryUpdate::Invalidate => panic!("the sum dict is not broadcasted"),
+        }
+    }
+
+    async fn send_sum_message_with_identity(
+        request_tx: &RequestSender,
+        identity: Option<&str>,
+    ) -> Result<(), RequestError> {
+        let request = StateMachineRequest::from(compose_sum_message())
+            .with_identity(identity.map(String::from));
+        request_tx.request(request, Span::none()).await
+    }
+
+    #[tokio::test]
+    async fn test_sum_phase_distinct_identities() {
+        // No Storage errors
+        //
+        // What should happen:
+        // 1. broadcast Sum phase
+        // 2. accept a sum message of the first identity
+        // 3. reject another sum message of the first identity
+        // 4. reject a sum message without an identity
+        // 5. accept a sum message of the second identity
+        // 6. fetch sum dict and move into update phase
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_sum_participant()
+            .times(2)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        cs.expect_sum_dict()
+            .return_once(move || Ok(Some(SumDict::new())));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_distinct_sum_identities(true)
+            .with_sum_count_min(2)
+            .with_sum_count_max(2)
+            .with_sum_time_min(0)
+            .build();
+
+        let (event_publisher, _event_subscriber) = events_from_idle_phase(&state);
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Sum, _>::new(shared));
+
+        // send the messages one after another, such that they are processed in order
+        let send_messages = async move {
+            assert!(
+                send_sum_message_with_identity(&request_tx, Some("device-a"))
+                    .await
+                    .is_ok()
+            );
+            assert!(matches!(
+                send_sum_message_with_identity(&request_tx, Some("device-a")).await,
+                Err(RequestError::DuplicateIdentity)
+            ));
+            assert!(matches!(
+                send_sum_message_with_identity(&request_tx, None).await,
+                Err(RequestError::MissingIdentity)
+            ));
+            assert!(
+                send_sum_message_with_identity(&request_tx, Some("device-b"))
+                    .await
+                    .is_ok()
+            );
+            // keep the request channel open
+            request_tx
+        };
+        let (state_machine, _request_tx) = tokio::join!(state_machine.next(), send_messages);
+        let state_machine = state_machine.unwrap();
+        assert!(state_machine.is_update());
+
+        let stats = state_machine.as_ref().rejection_stats();
+        assert_eq!(stats[&RejectionReason::DuplicateIdentity], 1);
+        assert_eq!(stats[&RejectionReason::MissingIdentity], 1);
+    }
+
     // #[tokio::test]
     // async fn test_sum_phase_publish_after_purge() {
     //     // Publish sum dict after purging all remaining messages.
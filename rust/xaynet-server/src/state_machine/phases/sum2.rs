@@ -1,10 +1,20 @@
 use async_trait::async_trait;
-use tracing::info;
+use tracing::{debug, info, warn};
 
 use crate::{
     state_machine::{
+        coordinator::PhaseParameters,
         events::DictionaryUpdate,
-        phases::{Handler, Phase, PhaseError, PhaseName, PhaseState, Shared, Unmask},
+        phases::{
+            Handler,
+            Phase,
+            PhaseError,
+            PhaseName,
+            PhaseState,
+            RestoredAggregation,
+            Shared,
+            Unmask,
+        },
         requests::{RequestError, StateMachineRequest, Sum2Request},
         StateMachine,
     },
@@ -20,6 +30,12 @@ use xaynet_core::{
 pub struct Sum2 {
     /// The aggregator for masked models.
     model_agg: Aggregation,
+    /// The number of accepted masks.
+    nb_masks: u64,
+    /// The number of masks which were accepted before the phase was resumed.
+    nb_restored: u64,
+    /// The version of the final seed dict.
+    seed_dict_version: u64,
 }
 
 #[async_trait]
@@ -31,7 +47,9 @@ where
     const NAME: PhaseName = PhaseName::Sum2;
 
     async fn process(&mut self) -> Result<(), PhaseError> {
-        self.process(self.shared.state.sum2).await
+        self.process(self.sum2_params()).await?;
+        self.delete_checkpoint().await;
+        Ok(())
     }
 
     fn broadcast(&mut self) {
@@ -47,7 +65,14 @@ where
     }
 
     async fn next(self) -> Option<StateMachine<T>> {
-        Some(PhaseState::<Unmask, _>::new(self.shared, self.private.model_agg).into())
+        Some(
+            PhaseState::<Unmask, _>::new(
+                self.shared,
+                self.private.model_agg,
+                self.private.nb_masks,
+            )
+            .into(),
+        )
     }
 }
 
@@ -59,9 +84,12 @@ where
     async fn handle_request(&mut self, req: StateMachineRequest) -> Result<(), RequestError> {
         if let StateMachineRequest::Sum2(Sum2Request {
             participant_pk,
+            seed_dict_version,
             model_mask,
         }) = req
         {
+            self.check_denylist(&participant_pk)?;
+            self.check_seed_dict_version(seed_dict_version)?;
             self.update_mask_dict(participant_pk, model_mask).await
         } else {
             Err(RequestError::MessageRejected)
@@ -71,18 +99,90 @@ where
 
 impl<T> PhaseState<Sum2, T> {
     /// Creates a new sum2 state.
-    pub fn new(shared: Shared<T>, model_agg: Aggregation) -> Self {
+    pub fn new(shared: Shared<T>, model_agg: Aggregation, seed_dict_version: u64) -> Self {
         Self {
-            private: Sum2 { model_agg },
+            private: Sum2 {
+                model_agg,
+                nb_masks: 0,
+                nb_restored: 0,
+                seed_dict_version,
+            },
             shared,
         }
     }
+
+    /// Creates a sum2 state which resumes an interrupted sum2 phase with the final aggregation of
+    /// the update phase.
+    ///
+    /// The `nb_restored` masks in the store count towards the accepted sum2 messages of the phase,
+    /// whereas the time of the phase starts over. The seed dict has been bumped once per
+    /// aggregated masked model, hence its final version is the number of aggregated models.
+    pub fn resume(shared: Shared<T>, restored: RestoredAggregation, nb_restored: u64) -> Self {
+        let model_agg = restored.into_aggregation();
+        let seed_dict_version = model_agg.nb_models() as u64;
+        Self {
+            private: Sum2 {
+                model_agg,
+                nb_masks: nb_restored,
+                nb_restored,
+                seed_dict_version,
+            },
+            shared,
+        }
+    }
+
+    /// Gets the sum2 phase parameters, where the restored masks count as accepted.
+    fn sum2_params(&self) -> PhaseParameters {
+        let mut params = self.shared.state.sum2_params();
+        params.count.min = params.count.min.saturating_sub(self.private.nb_restored);
+        params.count.max = params.count.max.saturating_sub(self.private.nb_restored);
+        params
+    }
 }
 
 impl<T> PhaseState<Sum2, T>
 where
     T: Storage,
 {
+    /// Checks that a sum2 participant derived its mask from the final seed dict.
+    ///
+    /// The version of the seed dict is bumped by the update phase for every accepted local seed
+    /// dict. Masks derived from an older version would not match the aggregated masked models,
+    /// hence they are rejected. Legacy sum2 messages don't carry a version and are accepted as is.
+    fn check_seed_dict_version(&self, seed_dict_version: Option<u64>) -> Result<(), RequestError> {
+        let current_version = self.private.seed_dict_version;
+        match seed_dict_version {
+            None => Ok(()),
+            Some(version) if version == current_version => Ok(()),
+            Some(seed_dict_version) => {
+                warn!(
+                    "rejecting sum2 message: stale seed dict version {} (current version {})",
+                    seed_dict_version, current_version
+                );
+                Err(RequestError::StaleSeedDict(
+                    seed_dict_version,
+                    current_version,
+                ))
+            }
+        }
+    }
+
+    /// Deletes the final aggregation checkpoint of the update phase once no more masks are
+    /// accepted, if checkpoints are enabled, such that a crash in a later phase starts a new round.
+    ///
+    /// A checkpoint which outlives its round is harmless, because checkpoints of other rounds are
+    /// never restored.
+    async fn delete_checkpoint(&mut self) {
+        if self.shared.state.aggregation_checkpoint.is_none() {
+            return;
+        }
+
+        debug!("deleting the aggregation checkpoint");
+        if let Err(err) = self.shared.store.delete_aggregation_checkpoint().await {
+            warn!("failed to delete the aggregation checkpoint: {}", err);
+        }
+    }
+
     /// Updates the mask dict with a sum2 participant request.
     async fn update_mask_dict(
         &mut self,
@@ -94,7 +194,9 @@ where
             .incr_mask_score(&participant_pk, &model_mask)
             .await?
             .into_inner()
-            .map_err(RequestError::from)
+            .map_err(RequestError::from)?;
+        self.private.nb_masks += 1;
+        Ok(())
     }
 }
 
@@ -109,10 +211,18 @@ mod tests {
     use crate::{
         state_machine::{
             coordinator::CoordinatorState,
-            events::{DictionaryUpdate, EventPublisher, EventSubscriber, ModelUpdate},
+            events::{
+                DictionaryUpdate,
+                EventPublisher,
+                EventSubscriber,
+                ModelUpdate,
+                VersionedSeedDict,
+            },
             tests::{
                 utils::{
                     assert_event_updated,
+                    assert_state_eq_except_round_stats,
+                    compose_sum2_message_with_seed_dict_version,
                     enable_logging,
                     init_shared,
                     send_sum2_messages,
@@ -123,7 +233,11 @@ mod tests {
             },
         },
         storage::{
-            tests::{utils::create_global_model, MockCoordinatorStore, MockModelStore},
+            tests::{
+                utils::{create_global_model, create_mask},
+                MockCoordinatorStore,
+                MockModelStore,
+            },
             MaskScoreIncr,
             MaskScoreIncrError,
             Store,
@@ -134,8 +248,11 @@ mod tests {
         EventBusBuilder::new(state)
             .broadcast_phase(PhaseName::Update)
             .broadcast_sum_dict(DictionaryUpdate::New(Arc::new(SumDict::new())))
-            .broadcast_seed_dict(DictionaryUpdate::New(Arc::new(SeedDict::new())))
-            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1))))
+            .broadcast_seed_dict(DictionaryUpdate::New(Arc::new(VersionedSeedDict {
+                version: 0,
+                seeds: SeedDict::new(),
+            })))
+            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1)), None))
             .build()
     }
 
@@ -145,7 +262,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_event_updated(&events_after.sum_dict, &events_before.sum_dict);
@@ -164,7 +281,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_eq!(events_after.keys, events_before.keys);
@@ -193,6 +310,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_incr_mask_score()
             .times(10)
             .returning(move |_, _| Ok(MaskScoreIncr(Ok(()))));
@@ -214,7 +332,7 @@ mod tests {
             state_before_sum2.round_params.mask_config,
             state_before_sum2.round_params.model_length,
         );
-        let state_machine = StateMachine::from(PhaseState::<Sum2, _>::new(shared, agg));
+        let state_machine = StateMachine::from(PhaseState::<Sum2, _>::new(shared, agg, 0));
         assert!(state_machine.is_sum2());
 
         send_sum2_messages(10, request_tx.clone());
@@ -251,6 +369,7 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_incr_mask_score()
             .times(3)
             .returning(move |_, _| Ok(MaskScoreIncr(Err(MaskScoreIncrError::UnknownSumPk))));
@@ -272,7 +391,7 @@ mod tests {
             state_before_sum2.round_params.mask_config,
             state_before_sum2.round_params.model_length,
         );
-        let state_machine = StateMachine::from(PhaseState::<Sum2, _>::new(shared, agg));
+        let state_machine = StateMachine::from(PhaseState::<Sum2, _>::new(shared, agg, 0));
         assert!(state_machine.is_sum2());
 
         send_sum2_messages(3, request_tx.clone());
@@ -294,4 +413,78 @@ mod tests {
             PhaseError::PhaseTimeout(_)
         ))
     }
+
+    #[tokio::test]
+    async fn test_rejected_messages_stale_seed_dict() {
+        // No Storage errors
+        // lets pretend we come from the update phase with 2 accepted update messages
+        //
+        // What should happen:
+        // 1. broadcast Sum2 phase
+        // 2. reject 2 sum2 messages (stale seed dict version 1)
+        // 3. accept 2 sum2 messages (current seed dict version 2) and 1 legacy sum2 message
+        //    (no seed dict version)
+        // 4. broadcast invalidation of sum and seed dict
+        // 5. move into unmask phase
+        //
+        // What should not happen:
+        // - the masks of the stale messages have been counted
+        // - the shared state has been changed
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_incr_mask_score()
+            .times(3)
+            .returning(move |_, _| Ok(MaskScoreIncr(Ok(()))));
+
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_sum2_count_min(3)
+            .with_sum2_count_max(3)
+            .with_sum2_time_min(1)
+            .build();
+
+        let (event_publisher, event_subscriber) = events_from_update_phase(&state);
+        let events_before_sum2 = EventSnapshot::from(&event_subscriber);
+        let state_before_sum2 = state.clone();
+
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let model_length = state_before_sum2.round_params.model_length;
+        let mut agg = Aggregation::from(create_mask(model_length, 1));
+        agg.aggregate(create_mask(model_length, 1));
+        assert_eq!(agg.nb_models(), 2);
+        let state_machine = StateMachine::from(PhaseState::<Sum2, _>::new(shared, agg, 2));
+        assert!(state_machine.is_sum2());
+
+        let stale_request_tx = request_tx.clone();
+        let stale = tokio::spawn(async move {
+            let stale_msg = compose_sum2_message_with_seed_dict_version(Some(1));
+            for _ in 0..2 {
+                let res = stale_request_tx.msg(&stale_msg).await;
+                assert!(matches!(res, Err(RequestError::StaleSeedDict(1, 2))));
+            }
+            let current_msg = compose_sum2_message_with_seed_dict_version(Some(2));
+            for _ in 0..2 {
+                stale_request_tx.msg(&current_msg).await.unwrap();
+            }
+            let legacy_msg = compose_sum2_message_with_seed_dict_version(None);
+            stale_request_tx.msg(&legacy_msg).await.unwrap();
+        });
+
+        let state_machine = state_machine.next().await.unwrap();
+        stale.await.unwrap();
+
+        let state_after_sum2 = state_machine.as_ref().clone();
+        let events_after_sum2 = EventSnapshot::from(&event_subscriber);
+        assert_after_phase_success(
+            &state_before_sum2,
+            &events_before_sum2,
+            &state_after_sum2,
+            &events_after_sum2,
+        );
+
+        assert!(state_machine.is_unmask());
+    }
 }
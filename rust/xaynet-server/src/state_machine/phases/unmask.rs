@@ -3,21 +3,25 @@ use std::{cmp::Ordering, sync::Arc};
 use async_trait::async_trait;
 use displaydoc::Display;
 use thiserror::Error;
-#[cfg(feature = "model-persistence")]
-use tracing::warn;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     metric,
     metrics::{GlobalRecorder, Measurement},
     state_machine::{
+        coordinator::TieBreaking,
         events::ModelUpdate,
         phases::{Idle, Phase, PhaseError, PhaseName, PhaseState, Shared},
         StateMachine,
     },
     storage::{Storage, StorageError},
 };
-use xaynet_core::mask::{Aggregation, MaskObject, Model, UnmaskingError};
+use xaynet_core::{
+    common::GlobalModelMetadata,
+    crypto::CryptoSuite,
+    mask::{Aggregation, MaskObject, Model, ModelDelta, UnmaskingError},
+    message::ToBytes,
+};
 
 /// Errors which can occur during the unmask phase.
 #[derive(Debug, Display, Error)]
@@ -42,8 +46,17 @@ pub enum UnmaskError {
 pub struct Unmask {
     /// The aggregator for masked models.
     model_agg: Option<Aggregation>,
+    /// The number of masks submitted in the sum2 phase.
+    nb_masks: u64,
+    /// The mask aggregated by the coordinator, if it runs in trusted mode.
+    trusted_mask: Option<MaskObject>,
+    /// The trimmed mean of the masked models computed by the coordinator, if it runs in trusted
+    /// mode with the trimmed mean aggregation. It replaces the unmasked aggregated masked models.
+    trimmed_mean: Option<Model>,
     /// The global model of the current round.
     global_model: Option<Arc<Model>>,
+    /// The metadata of the global model of the current round.
+    global_model_metadata: Option<GlobalModelMetadata>,
 }
 
 #[async_trait]
@@ -55,7 +68,10 @@ where
 
     async fn process(&mut self) -> Result<(), PhaseError> {
         self.emit_number_of_unique_masks_metrics();
-        let best_masks = self.best_masks().await?;
+        let best_masks = match self.private.trusted_mask.take() {
+            Some(mask) => vec![(mask, 1)],
+            None => self.best_masks().await?,
+        };
         self.end_round(best_masks).await?;
 
         #[cfg(feature = "model-persistence")]
@@ -71,50 +87,120 @@ where
             self.private.global_model.take().expect(
                 "unreachable: never fails when `broadcast()` is called after `end_round()`",
             );
+        let metadata = self.private.global_model_metadata.take();
+        let delta = self.model_delta(&global_model);
+        self.shared.events.broadcast_model_delta(delta);
         self.shared
             .events
-            .broadcast_model(ModelUpdate::New(global_model));
+            .broadcast_model(ModelUpdate::New(global_model, metadata));
     }
 
-    async fn next(self) -> Option<StateMachine<T>> {
-        Some(PhaseState::<Idle, _>::new(self.shared).into())
+    async fn next(mut self) -> Option<StateMachine<T>> {
+        self.shared.stage_next_round();
+        Some(PhaseState::<Idle, _>::after_round(self.shared).into())
     }
 }
 
 impl<T> PhaseState<Unmask, T> {
-    /// Creates a new unmask state.
-    pub fn new(shared: Shared<T>, model_agg: Aggregation) -> Self {
+    /// Creates a new unmask state for the given number of masks submitted in the sum2 phase.
+    pub fn new(shared: Shared<T>, model_agg: Aggregation, nb_masks: u64) -> Self {
+        Self {
+            private: Unmask {
+                model_agg: Some(model_agg),
+                nb_masks,
+                trusted_mask: None,
+                trimmed_mean: None,
+                global_model: None,
+                global_model_metadata: None,
+            },
+            shared,
+        }
+    }
+
+    /// Creates a new unmask state for the mask aggregated by the coordinator in trusted mode.
+    ///
+    /// The `trimmed_mean` of the masked models becomes the global model instead of the unmasked
+    /// aggregation, if the coordinator computed it.
+    pub fn new_trusted(
+        shared: Shared<T>,
+        model_agg: Aggregation,
+        mask: MaskObject,
+        trimmed_mean: Option<Model>,
+    ) -> Self {
         Self {
             private: Unmask {
                 model_agg: Some(model_agg),
+                nb_masks: 1,
+                trusted_mask: Some(mask),
+                trimmed_mean,
                 global_model: None,
+                global_model_metadata: None,
             },
             shared,
         }
     }
 
+    /// Computes the delta from the previous global model to the new `global_model`.
+    ///
+    /// There is no delta if the round of the previous global model is unknown, e.g. because the
+    /// model has been restored from the storage, or if the model lengths differ.
+    fn model_delta(&self, global_model: &Model) -> Option<Arc<ModelDelta>> {
+        let (base, base_metadata) = match self.shared.events.latest_model().event {
+            ModelUpdate::New(base, Some(base_metadata)) => (base, base_metadata),
+            _ => return None,
+        };
+        match ModelDelta::new(
+            base_metadata.round_id,
+            &base,
+            self.shared.state.round_id,
+            global_model,
+        ) {
+            Ok(delta) => Some(Arc::new(delta)),
+            Err(err) => {
+                warn!("no delta to the previous global model: {}", err);
+                None
+            }
+        }
+    }
+
     /// Freezes the mask dictionary.
+    ///
+    /// The mask with the highest number of submissions is chosen. A tie between the best masks is
+    /// resolved wrt the tie breaking policy of the coordinator state, which is recorded as the
+    /// broken tie of the round if it succeeds.
     async fn freeze_mask_dict(
         &mut self,
-        mut best_masks: Vec<(MaskObject, u64)>,
+        best_masks: Vec<(MaskObject, u64)>,
     ) -> Result<MaskObject, UnmaskError> {
-        let mask = best_masks
-            .drain(0..)
-            .fold(
-                (None, 0),
-                |(unique_mask, unique_count), (mask, count)| match unique_count.cmp(&count) {
-                    Ordering::Less => (Some(mask), count),
-                    Ordering::Greater => (unique_mask, unique_count),
-                    Ordering::Equal => (None, unique_count),
-                },
-            )
-            .0
-            .ok_or(UnmaskError::AmbiguousMasks)?;
+        let (mut best, count) = most_submitted(best_masks);
+        if best.len() < 2 {
+            return best.pop().ok_or(UnmaskError::AmbiguousMasks);
+        }
 
+        let tie_breaking = self.shared.state.tie_breaking;
+        let suite = self.shared.state.round_params.crypto_suite;
+        let mask = match tie_breaking {
+            TieBreaking::Fail => None,
+            TieBreaking::LowestHash => lowest_hash(suite, best),
+            TieBreaking::RequireMajorityFraction(fraction) => {
+                if count as f64 >= fraction * self.private.nb_masks as f64 {
+                    lowest_hash(suite, best)
+                } else {
+                    None
+                }
+            }
+        }
+        .ok_or(UnmaskError::AmbiguousMasks)?;
+
+        warn!(
+            "resolved a tie between masks with {} submissions each by {:?}",
+            count, tie_breaking
+        );
+        self.shared.state.broken_tie = Some(tie_breaking);
         Ok(mask)
     }
 
-    /// Ends the round by unmasking the global model.
+    /// Ends the round by unmasking the global model and describing it by its metadata.
     async fn end_round(&mut self, best_masks: Vec<(MaskObject, u64)>) -> Result<(), UnmaskError> {
         let mask = self.freeze_mask_dict(best_masks).await?;
 
@@ -124,12 +210,55 @@ impl<T> PhaseState<Unmask, T> {
         model_agg
             .validate_unmasking(&mask)
             .map_err(UnmaskError::from)?;
-        self.private.global_model = Some(Arc::new(model_agg.unmask(mask)));
+        self.private.global_model_metadata = Some(GlobalModelMetadata::new(
+            self.shared.state.round_id,
+            model_agg.config(),
+            model_agg.len(),
+            model_agg.nb_models(),
+        ));
+        let global_model = match self.private.trimmed_mean.take() {
+            Some(trimmed_mean) => trimmed_mean,
+            None => model_agg.unmask(mask),
+        };
+        self.private.global_model = Some(Arc::new(global_model));
 
         Ok(())
     }
 }
 
+/// Gets the masks with the highest number of submissions and their number of submissions.
+///
+/// The masks are scanned in a single pass and only the currently best masks are kept, i.e. the
+/// masks are neither sorted nor collected. Several masks are returned if they are tied.
+fn most_submitted(masks: impl IntoIterator<Item = (MaskObject, u64)>) -> (Vec<MaskObject>, u64) {
+    masks.into_iter().fold(
+        (Vec::new(), 0),
+        |(mut best, best_count), (mask, count)| match best_count.cmp(&count) {
+            Ordering::Less => (vec![mask], count),
+            Ordering::Greater => (best, best_count),
+            Ordering::Equal => {
+                best.push(mask);
+                (best, best_count)
+            }
+        },
+    )
+}
+
+/// Chooses the mask with the lexicographically smallest hash of its serialization, wrt the hash
+/// function of the crypto `suite` of the round.
+fn lowest_hash(suite: CryptoSuite, masks: Vec<MaskObject>) -> Option<MaskObject> {
+    masks
+        .into_iter()
+        .map(|mask| {
+            let mut bytes = vec![0; mask.buffer_length()];
+            mask.to_bytes(&mut bytes);
+            // safe unwrap: the crypto suite is validated by the settings
+            (suite.hash(&bytes).unwrap(), mask)
+        })
+        .min_by(|(hash1, _), (hash2, _)| hash1.cmp(hash2))
+        .map(|(_, mask)| mask)
+}
+
 impl<T> PhaseState<Unmask, T>
 where
     T: Storage,
@@ -226,24 +355,49 @@ mod tests {
     use std::sync::Arc;
 
     use anyhow::anyhow;
+    use tokio::time::{timeout, Duration};
+    use xaynet_core::{
+        crypto::{ByteObject, PublicEncryptKey, Sha256, SigningKeyPair},
+        mask::{
+            BoundType,
+            DataType,
+            Endianness,
+            FromPrimitives,
+            GroupType,
+            MaskConfig,
+            Masker,
+            ModelType,
+            Scalar,
+        },
+    };
 
     use crate::{
+        metrics::Recorder,
         state_machine::{
-            coordinator::CoordinatorState,
+            coordinator::{CoordinatorState, TieBreaking},
             events::{DictionaryUpdate, EventPublisher, EventSubscriber, ModelUpdate},
             tests::{
-                utils::{assert_event_updated, enable_logging, init_shared, EventSnapshot},
+                utils::{
+                    assert_event_updated,
+                    assert_state_eq_except_round_stats,
+                    enable_logging,
+                    init_shared,
+                    EventSnapshot,
+                },
                 CoordinatorStateBuilder,
                 EventBusBuilder,
             },
         },
         storage::{
+            coordinator_storage::memory::MemoryStorage,
             tests::{
                 utils::{create_global_model, create_mask},
                 MockCoordinatorStore,
                 MockModelStore,
                 MockTrustAnchor,
             },
+            CoordinatorStorage,
+            MaskScoreIncrError,
             Store,
         },
     };
@@ -253,7 +407,7 @@ mod tests {
             .broadcast_phase(PhaseName::Sum2)
             .broadcast_sum_dict(DictionaryUpdate::Invalidate)
             .broadcast_seed_dict(DictionaryUpdate::Invalidate)
-            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1))))
+            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1)), None))
             .build()
     }
 
@@ -285,7 +439,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_eq!(events_after.keys, events_before.keys);
@@ -356,7 +510,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -371,6 +525,408 @@ mod tests {
         );
 
         assert!(state_machine.is_idle());
+        assert_eq!(events_after_sum2.model_delta.event, None);
+    }
+
+    #[tokio::test]
+    async fn test_unmask_broadcasts_model_delta() {
+        // No Storage errors
+        // lets pretend we come from the sum2 phase, where the global model of the previous round
+        // is known
+        //
+        // What should happen:
+        // 1. unmask the masked global model
+        // 2. broadcast the delta from the previous to the new global model
+        // 3. broadcast the new global model
+        // 4. move into idle phase
+        enable_logging();
+
+        let state = CoordinatorStateBuilder::new().with_round_id(1).build();
+        let model_length = state.round_params.model_length;
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_best_masks()
+            .returning(move || Ok(Some(vec![(create_mask(model_length, 1), 1)])));
+        #[cfg(feature = "model-persistence")]
+        {
+            cs.expect_set_latest_global_model_id()
+                .returning(move |_| Ok(()));
+        }
+        let ms = {
+            #[cfg(not(feature = "model-persistence"))]
+            {
+                MockModelStore::new()
+            }
+            #[cfg(feature = "model-persistence")]
+            {
+                let mut ms = MockModelStore::new();
+                ms.expect_set_global_model()
+                    .returning(move |_, _, _| Ok("id".to_string()));
+                ms
+            }
+        };
+        let store = Store::new(cs, ms);
+
+        let previous_model = Model::from_primitives(vec![1_i32; model_length].into_iter()).unwrap();
+        let previous_metadata =
+            GlobalModelMetadata::new(0, state.round_params.mask_config, model_length, 1);
+        let (event_publisher, event_subscriber) = EventBusBuilder::new(&state)
+            .broadcast_phase(PhaseName::Sum2)
+            .broadcast_model(ModelUpdate::New(
+                Arc::new(previous_model.clone()),
+                Some(previous_metadata),
+            ))
+            .build();
+
+        let aggregator = init_aggregator(&state);
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_idle());
+
+        let global_model = match event_subscriber.model_listener().get_latest().event {
+            ModelUpdate::New(global_model, _) => global_model,
+            update => panic!("unexpected model update: {:?}", update),
+        };
+        let delta = event_subscriber
+            .model_delta_listener()
+            .get_latest()
+            .event
+            .unwrap();
+        assert_eq!(delta.base_round_id, 0);
+        assert_eq!(delta.round_id, 1);
+        assert_eq!(delta.apply(&previous_model).unwrap(), *global_model);
+    }
+
+    #[tokio::test]
+    async fn test_unmask_to_idle_phase_metrics_unavailable() {
+        // No Storage errors, but the metrics backend is unreachable
+        // lets pretend we come from the sum2 phase
+        //
+        // What should happen:
+        // 1. the round is completed and the state machine moves into the idle phase
+        // 2. the metrics which failed to be dispatched are counted as dropped
+        enable_logging();
+        let recorder = GlobalRecorder::install_local(Recorder::failing());
+
+        let state = CoordinatorStateBuilder::new().with_round_id(1).build();
+        let model_length = state.round_params.model_length;
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_best_masks()
+            .returning(move || Ok(Some(vec![(create_mask(model_length, 1), 1)])));
+        cs.expect_clone().returning(|| {
+            let mut cs = MockCoordinatorStore::new();
+            cs.expect_number_of_unique_masks().returning(|| Ok(1));
+            cs
+        });
+        #[cfg(feature = "model-persistence")]
+        {
+            cs.expect_set_latest_global_model_id()
+                .returning(move |_| Ok(()));
+        }
+        let mut ms = MockModelStore::new();
+        ms.expect_clone().returning(MockModelStore::new);
+        #[cfg(feature = "model-persistence")]
+        {
+            ms.expect_set_global_model()
+                .returning(move |_, _, _| Ok("id".to_string()));
+        }
+
+        let store = Store::new(cs, ms);
+
+        let (event_publisher, _event_subscriber) = events_from_sum2_phase(&state);
+        let aggregator = init_aggregator(&state);
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_idle());
+
+        let dropped = async {
+            while recorder.metrics_dropped() == 0 {
+                tokio::task::yield_now().await;
+            }
+        };
+        timeout(Duration::from_secs(1), dropped).await.unwrap();
+        assert!(recorder.metrics_dropped() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_unmask_publishes_global_model_metadata() {
+        // No Storage errors
+        // lets pretend we come from the sum2 phase of a round with an i64 model
+        //
+        // What should happen:
+        // 1. unmask the masked global model
+        // 2. broadcast the unmasked global model together with its metadata
+        // 3. the integer weights can be reconstructed exactly from the metadata
+        enable_logging();
+
+        let mask_config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::I64,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+        };
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_mask_config(mask_config)
+            .with_model_length(3)
+            .build();
+        let config = state.round_params.mask_config;
+
+        let weights = [-1_i64, 0, 1];
+        let local_model = Model::from_primitives(weights.iter().cloned()).unwrap();
+        let (mask_seed, masked_model) = Masker::new(config).mask(Scalar::unit(), &local_model);
+        let mask = mask_seed.derive_mask(weights.len(), config);
+        let mut aggregator = Aggregation::new(config, weights.len());
+        aggregator.aggregate(masked_model);
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_best_masks()
+            .returning(move || Ok(Some(vec![(mask.clone(), 1)])));
+        #[cfg(feature = "model-persistence")]
+        {
+            cs.expect_set_latest_global_model_id()
+                .returning(move |_| Ok(()));
+        }
+        let ms = {
+            #[cfg(not(feature = "model-persistence"))]
+            {
+                MockModelStore::new()
+            }
+            #[cfg(feature = "model-persistence")]
+            {
+                let mut ms = MockModelStore::new();
+                ms.expect_set_global_model()
+                    .returning(move |_, _, _| Ok("id".to_string()));
+                ms
+            }
+        };
+        let store = Store::new(cs, ms);
+
+        let (event_publisher, event_subscriber) = events_from_sum2_phase(&state);
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_idle());
+
+        let (global_model, metadata) = match event_subscriber.model_listener().get_latest().event {
+            ModelUpdate::New(global_model, Some(metadata)) => (global_model, metadata),
+            update => panic!("unexpected model update: {:?}", update),
+        };
+        assert_eq!(
+            metadata,
+            GlobalModelMetadata {
+                round_id: 1,
+                data_type: DataType::I64,
+                model_length: 3,
+                mask_config: config,
+                nb_models: 1,
+                error_bound: metadata.error_bound,
+            }
+        );
+        let bytes = metadata
+            .to_primitives_bytes(&global_model, Endianness::Little)
+            .unwrap();
+        let global_model =
+            Model::from_primitives_bytes(&bytes, metadata.data_type, Endianness::Little).unwrap();
+        assert_eq!(global_model, local_model);
+    }
+
+    /// Creates an unmask state for `nb_masks` submitted masks which resolves ties wrt the
+    /// `tie_breaking` policy.
+    fn unmask_with_tie_breaking(
+        tie_breaking: TieBreaking,
+        nb_masks: u64,
+    ) -> PhaseState<Unmask, ()> {
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_tie_breaking(tie_breaking)
+            .build();
+        let aggregator = init_aggregator(&state);
+        let (event_publisher, _) = events_from_sum2_phase(&state);
+        let (shared, _) = init_shared(state, (), event_publisher);
+        PhaseState::<Unmask, _>::new(shared, aggregator, nb_masks)
+    }
+
+    #[test]
+    fn test_most_submitted_matches_sorting() {
+        // a large mask dict with pseudo-random submission counts and several tied best masks
+        let masks = (0..10_000_u32)
+            .map(|i| (create_mask(1, i), u64::from(i * 7_919 % 1_000)))
+            .collect::<Vec<_>>();
+
+        let mut sorted = masks.clone();
+        sorted.sort_by(|(_, count1), (_, count2)| count2.cmp(count1));
+        let max_count = sorted[0].1;
+        let mut expected = sorted
+            .into_iter()
+            .take_while(|(_, count)| *count == max_count)
+            .map(|(mask, _)| mask.vect.data[0].clone())
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        let (best, count) = most_submitted(masks);
+        let mut best = best
+            .into_iter()
+            .map(|mask| mask.vect.data[0].clone())
+            .collect::<Vec<_>>();
+        best.sort();
+        assert_eq!(count, max_count);
+        assert_eq!(best, expected);
+        assert!(best.len() > 1);
+
+        assert_eq!(most_submitted(Vec::new()), (Vec::new(), 0));
+    }
+
+    #[tokio::test]
+    async fn test_freeze_mask_dict_unique_mask() {
+        let model_length = 4;
+        let best_masks = vec![
+            (create_mask(model_length, 1), 3),
+            (create_mask(model_length, 2), 2),
+        ];
+
+        let mut unmask = unmask_with_tie_breaking(TieBreaking::Fail, 5);
+        let mask = unmask.freeze_mask_dict(best_masks).await.unwrap();
+        assert_eq!(mask, create_mask(model_length, 1));
+        assert!(unmask.shared.state.broken_tie.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_freeze_mask_dict_tie_fails() {
+        let model_length = 4;
+        let best_masks = vec![
+            (create_mask(model_length, 1), 2),
+            (create_mask(model_length, 2), 2),
+        ];
+
+        let mut unmask = unmask_with_tie_breaking(TieBreaking::Fail, 4);
+        assert!(matches!(
+            unmask.freeze_mask_dict(best_masks).await,
+            Err(UnmaskError::AmbiguousMasks)
+        ));
+        assert!(unmask.shared.state.broken_tie.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_freeze_mask_dict_tie_lowest_hash() {
+        let model_length = 4;
+        let masks = [create_mask(model_length, 1), create_mask(model_length, 2)];
+        let hash = |mask: &MaskObject| {
+            let mut bytes = vec![0; mask.buffer_length()];
+            mask.to_bytes(&mut bytes);
+            Sha256::hash(&bytes)
+        };
+        let lowest = masks
+            .iter()
+            .min_by(|mask1, mask2| hash(mask1).as_slice().cmp(hash(mask2).as_slice()))
+            .unwrap()
+            .clone();
+
+        // the choice doesn't depend on the order of the best masks
+        for best_masks in &[
+            vec![(masks[0].clone(), 2), (masks[1].clone(), 2)],
+            vec![(masks[1].clone(), 2), (masks[0].clone(), 2)],
+        ] {
+            let mut unmask = unmask_with_tie_breaking(TieBreaking::LowestHash, 4);
+            let mask = unmask.freeze_mask_dict(best_masks.clone()).await.unwrap();
+            assert_eq!(mask, lowest);
+            assert_eq!(
+                unmask.shared.state.broken_tie,
+                Some(TieBreaking::LowestHash)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_freeze_imported_mask_dict() {
+        let model_length = 4;
+        let masks = [create_mask(model_length, 1), create_mask(model_length, 2)];
+        let sum_pks = (0..5)
+            .map(|_| SigningKeyPair::generate().public)
+            .collect::<Vec<_>>();
+        let mut storage = MemoryStorage::new();
+        let submit = |storage: &MemoryStorage, i: usize| {
+            let mut storage = storage.clone();
+            let sum_pk = sum_pks[i];
+            let mask = masks[i % 2].clone();
+            async move {
+                storage
+                    .add_sum_participant(&sum_pk, &PublicEncryptKey::generate())
+                    .await
+                    .unwrap();
+                storage.incr_mask_score(&sum_pk, &mask).await.unwrap()
+            }
+        };
+        // a tie between the masks, which is broken by the lowest hash
+        for i in 0..4 {
+            submit(&storage, i).await.into_inner().unwrap();
+        }
+
+        let snapshot = storage.export_mask_dict().unwrap();
+        let bytes = bincode::serialize(&snapshot).unwrap();
+        let mut restored = MemoryStorage::new();
+        restored
+            .import_mask_dict(bincode::deserialize(&bytes).unwrap())
+            .unwrap();
+        assert_eq!(restored.number_of_unique_masks().await.unwrap(), 2);
+        let mut best_masks = storage.best_masks().await.unwrap().unwrap();
+        let mut restored_best_masks = restored.best_masks().await.unwrap().unwrap();
+        let expected = unmask_with_tie_breaking(TieBreaking::LowestHash, 4)
+            .freeze_mask_dict(best_masks.clone())
+            .await
+            .unwrap();
+        let mask = unmask_with_tie_breaking(TieBreaking::LowestHash, 4)
+            .freeze_mask_dict(restored_best_masks.clone())
+            .await
+            .unwrap();
+        assert_eq!(mask, expected);
+        best_masks.sort_by_key(|(mask, _)| mask.vect.data.clone());
+        restored_best_masks.sort_by_key(|(mask, _)| mask.vect.data.clone());
+        assert_eq!(restored_best_masks, best_masks);
+
+        // the submissions are restored as well
+        assert!(matches!(
+            submit(&restored, 0).await.into_inner(),
+            Err(MaskScoreIncrError::AlreadyRecorded)
+        ));
+        submit(&restored, 4).await.into_inner().unwrap();
+        let mask = unmask_with_tie_breaking(TieBreaking::Fail, 5)
+            .freeze_mask_dict(restored.best_masks().await.unwrap().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(mask, masks[0]);
+
+        // the snapshot of another round is rejected
+        let mut other_round = snapshot;
+        other_round.round_id += 1;
+        assert!(MemoryStorage::new().import_mask_dict(other_round).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_freeze_mask_dict_tie_require_majority_fraction() {
+        let model_length = 4;
+        let best_masks = vec![
+            (create_mask(model_length, 1), 2),
+            (create_mask(model_length, 2), 2),
+        ];
+        let tie_breaking = TieBreaking::RequireMajorityFraction(0.5);
+
+        // the tied masks have half of the 4 submitted masks each
+        let mut unmask = unmask_with_tie_breaking(tie_breaking, 4);
+        assert!(unmask.freeze_mask_dict(best_masks.clone()).await.is_ok());
+        assert_eq!(unmask.shared.state.broken_tie, Some(tie_breaking));
+
+        // the tied masks have less than half of the 5 submitted masks each
+        let mut unmask = unmask_with_tie_breaking(tie_breaking, 5);
+        assert!(matches!(
+            unmask.freeze_mask_dict(best_masks).await,
+            Err(UnmaskError::AmbiguousMasks)
+        ));
+        assert!(unmask.shared.state.broken_tie.is_none());
     }
 
     #[tokio::test]
@@ -402,7 +958,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -451,7 +1007,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -508,7 +1064,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -564,7 +1120,7 @@ mod tests {
             state_before_sum2.round_params.mask_config,
             state_before_sum2.round_params.model_length,
         );
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -648,7 +1204,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -710,7 +1266,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
@@ -772,7 +1328,7 @@ mod tests {
 
         let (shared, _request_tx) = init_shared(state, store, event_publisher);
         let aggregator = init_aggregator(&state_before_sum2);
-        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator));
+        let state_machine = StateMachine::from(PhaseState::<Unmask, _>::new(shared, aggregator, 1));
         assert!(state_machine.is_unmask());
 
         let state_machine = state_machine.next().await.unwrap();
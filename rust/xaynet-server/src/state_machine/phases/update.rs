@@ -1,21 +1,40 @@
-use std::sync::Arc;
+use std::{collections::HashMap, mem, sync::Arc};
 
 use async_trait::async_trait;
 use displaydoc::Display;
 use thiserror::Error;
-use tracing::{debug, info, warn};
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
 
 use crate::{
+    metric,
+    metrics::Measurement,
+    settings::SeedDictMismatchPolicy,
     state_machine::{
-        events::DictionaryUpdate,
-        phases::{Handler, Phase, PhaseError, PhaseName, PhaseState, Shared, Sum2},
+        coordinator::{CheckpointParameters, CoordinatorState, PhaseParameters, SeedDictMismatch},
+        events::{DictionaryUpdate, VersionedSeedDict},
+        phases::{Handler, Idle, Phase, PhaseError, PhaseName, PhaseState, Shared, Sum2, Unmask},
         requests::{RequestError, StateMachineRequest, UpdateRequest},
         StateMachine,
     },
-    storage::{Storage, StorageError},
+    storage::{
+        AggregationCheckpoint,
+        CoordinatorStorage,
+        LocalSeedDictAddError,
+        Storage,
+        StorageError,
+    },
 };
 use xaynet_core::{
-    mask::{Aggregation, MaskObject},
+    crypto::{ByteObject, Sha256},
+    mask::{
+        Aggregation,
+        AggregationError,
+        MaskObject,
+        Model,
+        TrimmedMeanAggregation,
+        TrimmedMeanError,
+    },
     LocalSeedDict,
     SeedDict,
     UpdateParticipantPublicKey,
@@ -28,15 +47,237 @@ pub enum UpdateError {
     NoSeedDict,
     /// Fetching seed dictionary failed: {0}.
     FetchSeedDict(StorageError),
+    /// Decrypting a mask seed as the trusted aggregator failed.
+    DecryptSeed,
+    /// Aggregating the masks as the trusted aggregator failed: {0}.
+    AggregateMasks(AggregationError),
+    /// Computing the trimmed mean of the masked models as the trusted aggregator failed: {0}.
+    TrimmedMean(TrimmedMeanError),
+    /// Collecting the seed dictionary failed: {0}.
+    CollectSeedDict(StorageError),
+    /// Counting the update participants of the seed dictionary failed: {0}.
+    CountUpdateParticipants(StorageError),
+    /// The seed dictionary contains {seed_dict_participants} update participants, but {aggregated_models} masked models have been aggregated.
+    SeedDictMismatch {
+        seed_dict_participants: u64,
+        aggregated_models: u64,
+    },
 }
 
 /// The update state.
+///
+/// # Checkpoints
+///
+/// The aggregation of the masked models is only kept in memory, whereas the seed dictionary is
+/// persisted. If checkpoints are enabled, the aggregation is written to the store as an
+/// [`AggregationCheckpoint`] together with the update participants it covers, at the start of the
+/// phase and then periodically after an update message has been accepted.
+///
+/// The local seed dict of an update participant is always added before its masked model is
+/// aggregated and checkpointed. Hence, the seed dict covers at least the update participants of
+/// the latest checkpoint, no matter when the coordinator crashes. A restored coordinator rolls
+/// the seed dict back to exactly these update participants and resumes the phase with the
+/// aggregation of the checkpoint, see [`RestoredAggregation`]. Then the aggregation and the seed
+/// dict include the same update participants, and the rolled back ones may submit their update
+/// messages again. Once no more update messages are accepted, a final checkpoint of the complete
+/// aggregation is written, such that a crash in the sum2 phase can resume the sum2 phase with it,
+/// see [`RestoredAggregation::load_final()`].
+///
+/// Checkpoints are skipped in collect only mode, because the masked models are collected instead
+/// of aggregated.
 #[derive(Debug)]
 pub struct Update {
     /// The aggregator for masked models.
     model_agg: Aggregation,
+    /// The update participants whose masked models have been aggregated.
+    update_pks: Vec<UpdateParticipantPublicKey>,
+    /// The number of aggregated masked models and the time of the latest checkpoint, if any.
+    checkpointed: Option<(usize, Instant)>,
     /// The seed dictionary which gets assembled during the update phase.
     seed_dict: Option<SeedDict>,
+    /// The version of the seed dictionary, which is bumped whenever a local seed dict is added.
+    seed_dict_version: u64,
+    /// The aggregated mask, if the coordinator runs in trusted mode.
+    mask: Option<MaskObject>,
+    /// The masked models in the order of `update_pks`, if the global model is their trimmed
+    /// mean. They are only kept in memory, hence they are never checkpointed.
+    masked_models: Vec<MaskObject>,
+    /// The trimmed mean of the masked models, if the coordinator runs in trusted mode with the
+    /// trimmed mean aggregation.
+    trimmed_mean: Option<Model>,
+}
+
+/// An aggregation of an interrupted update phase, which was restored from a checkpoint.
+#[derive(Debug)]
+pub struct RestoredAggregation {
+    /// The restored aggregator for masked models.
+    model_agg: Aggregation,
+    /// The update participants whose masked models have been aggregated.
+    update_pks: Vec<UpdateParticipantPublicKey>,
+}
+
+impl RestoredAggregation {
+    /// Restores the aggregation of the current round from the latest [`AggregationCheckpoint`].
+    ///
+    /// The seed dict is rolled back to the update participants of the checkpoint, such that both
+    /// include the same update participants again.
+    ///
+    /// Returns `None` if there is no checkpoint of the round or if the checkpoint doesn't match
+    /// the round parameters. The round can't be resumed then.
+    ///
+    /// # Errors
+    /// Fails on storage errors.
+    pub async fn load<S>(
+        store: &mut S,
+        state: &CoordinatorState,
+    ) -> Result<Option<Self>, StorageError>
+    where
+        S: CoordinatorStorage,
+    {
+        let AggregationCheckpoint {
+            round_id,
+            aggregated_models,
+            nb_models,
+            update_pks,
+        } = match store.aggregation_checkpoint(state.round_id).await? {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(None),
+        };
+
+        let config = state.round_params.mask_config;
+        let len = state.round_params.model_length;
+        let model_agg = Aggregation::new(config, len);
+        if nb_models != update_pks.len()
+            || (nb_models > 0 && model_agg.validate_aggregation(&aggregated_models).is_err())
+        {
+            warn!("the aggregation checkpoint doesn't match the round parameters");
+            return Ok(None);
+        }
+
+        let removed = store.retain_update_participants(&update_pks).await?;
+        info!(
+            "restored the aggregation of {} masked models, rolled back {} update participants",
+            nb_models,
+            removed.len(),
+        );
+        let model_agg = Aggregation::from_checkpoint(config, len, aggregated_models, nb_models)
+            .with_round_id(round_id);
+        Ok(Some(Self {
+            model_agg,
+            update_pks,
+        }))
+    }
+
+    /// Restores the aggregation of the current round like [`load()`], but falls back to an empty
+    /// aggregation if there is no usable checkpoint.
+    ///
+    /// Without a checkpoint, the seed dict is rolled back to no update participants at all, such
+    /// that all update participants may submit their update messages again.
+    ///
+    /// # Errors
+    /// Fails on storage errors.
+    ///
+    /// [`load()`]: RestoredAggregation::load
+    pub async fn load_or_empty<S>(
+        store: &mut S,
+        state: &CoordinatorState,
+    ) -> Result<Self, StorageError>
+    where
+        S: CoordinatorStorage,
+    {
+        if let Some(restored) = Self::load(store, state).await? {
+            return Ok(restored);
+        }
+
+        let removed = store.retain_update_participants(&[]).await?;
+        info!(
+            "no aggregation to restore, rolled back {} update participants",
+            removed.len(),
+        );
+        let model_agg = Aggregation::new(
+            state.round_params.mask_config,
+            state.round_params.model_length,
+        )
+        .with_round_id(state.round_id);
+        Ok(Self {
+            model_agg,
+            update_pks: Vec::new(),
+        })
+    }
+
+    /// Restores the complete aggregation of the current round from the final
+    /// [`AggregationCheckpoint`] of the update phase, e.g. to resume the sum2 phase.
+    ///
+    /// Unlike [`load()`], the seed dict is never rolled back, since it has already been published
+    /// to the sum participants.
+    ///
+    /// Returns `None` if there is no checkpoint of the round, if the checkpoint doesn't match the
+    /// round parameters or if it doesn't cover all update participants of the seed dict, e.g.
+    /// because the final checkpoint failed. The round can't be resumed then.
+    ///
+    /// # Errors
+    /// Fails on storage errors.
+    ///
+    /// [`load()`]: RestoredAggregation::load
+    pub async fn load_final<S>(
+        store: &mut S,
+        state: &CoordinatorState,
+    ) -> Result<Option<Self>, StorageError>
+    where
+        S: CoordinatorStorage,
+    {
+        let AggregationCheckpoint {
+            round_id,
+            aggregated_models,
+            nb_models,
+            update_pks,
+        } = match store.aggregation_checkpoint(state.round_id).await? {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(None),
+        };
+
+        let config = state.round_params.mask_config;
+        let len = state.round_params.model_length;
+        let model_agg = Aggregation::new(config, len);
+        if nb_models == 0
+            || nb_models != update_pks.len()
+            || model_agg.validate_aggregation(&aggregated_models).is_err()
+        {
+            warn!("the aggregation checkpoint doesn't match the round parameters");
+            return Ok(None);
+        }
+
+        // the checkpoint covers a subset of the update participants of the seed dict
+        let seed_dict_participants = store.number_of_unique_update_participants().await?;
+        if seed_dict_participants != nb_models as u64 {
+            warn!(
+                "the aggregation checkpoint covers {} of {} update participants",
+                nb_models, seed_dict_participants
+            );
+            return Ok(None);
+        }
+
+        info!(
+            "restored the final aggregation of {} masked models",
+            nb_models
+        );
+        let model_agg = Aggregation::from_checkpoint(config, len, aggregated_models, nb_models)
+            .with_round_id(round_id);
+        Ok(Some(Self {
+            model_agg,
+            update_pks,
+        }))
+    }
+
+    /// Gets the number of aggregated masked models.
+    pub fn nb_models(&self) -> usize {
+        self.model_agg.nb_models()
+    }
+
+    /// Gets the restored aggregator for masked models.
+    pub(in crate::state_machine) fn into_aggregation(self) -> Aggregation {
+        self.model_agg
+    }
 }
 
 #[async_trait]
@@ -48,26 +289,73 @@ where
     const NAME: PhaseName = PhaseName::Update;
 
     async fn process(&mut self) -> Result<(), PhaseError> {
-        self.process(self.shared.state.update).await?;
+        let params = self.update_params();
+        if self.private.checkpointed.is_none() {
+            self.checkpoint_aggregation().await;
+        }
+        self.process(params).await?;
+        if self.is_checkpoint_outdated() {
+            self.checkpoint_aggregation().await;
+        }
         self.seed_dict().await?;
+        self.check_seed_dict().await?;
+        self.aggregate_masks()?;
+        self.collect_seed_dict().await?;
 
         Ok(())
     }
 
     fn broadcast(&mut self) {
+        if self.shared.state.is_trusted() || self.shared.state.is_collect_only() {
+            // there is no sum2 phase which needs the dictionaries, hence they aren't published
+            info!("broadcasting invalidation of sum dictionary");
+            self.shared
+                .events
+                .broadcast_sum_dict(DictionaryUpdate::Invalidate);
+
+            info!("broadcasting invalidation of seed dictionary");
+            self.shared
+                .events
+                .broadcast_seed_dict(DictionaryUpdate::Invalidate);
+            return;
+        }
+
         info!("broadcasting the global seed dictionary");
         let seed_dict = self
             .private
             .seed_dict
             .take()
             .expect("unreachable: never fails when `broadcast()` is called after `process()`");
+        let seed_dict = VersionedSeedDict {
+            version: self.private.seed_dict_version,
+            seeds: seed_dict,
+        };
         self.shared
             .events
             .broadcast_seed_dict(DictionaryUpdate::New(Arc::new(seed_dict)));
     }
 
-    async fn next(self) -> Option<StateMachine<T>> {
-        Some(PhaseState::<Sum2, _>::new(self.shared, self.private.model_agg).into())
+    async fn next(mut self) -> Option<StateMachine<T>> {
+        let Update {
+            model_agg,
+            mask,
+            seed_dict_version,
+            trimmed_mean,
+            ..
+        } = self.private;
+        if let Some(mask) = mask {
+            // the trusted aggregator replaces the sum participants of the sum2 phase
+            Some(
+                PhaseState::<Unmask, _>::new_trusted(self.shared, model_agg, mask, trimmed_mean)
+                    .into(),
+            )
+        } else if self.shared.state.is_collect_only() {
+            info!("ending the round after collecting the masked models");
+            self.shared.stage_next_round();
+            Some(PhaseState::<Idle, _>::new(self.shared).into())
+        } else {
+            Some(PhaseState::<Sum2, _>::new(self.shared, model_agg, seed_dict_version).into())
+        }
     }
 }
 
@@ -80,12 +368,15 @@ where
         if let StateMachineRequest::Update(UpdateRequest {
             participant_pk,
             local_seed_dict,
+            model_checksum,
             masked_model,
         }) = req
         {
+            self.check_denylist(&participant_pk)?;
             self.update_seed_dict_and_aggregate_mask(
                 &participant_pk,
                 &local_seed_dict,
+                &model_checksum,
                 masked_model,
             )
             .await
@@ -101,15 +392,167 @@ impl<T> PhaseState<Update, T> {
         let model_agg = Aggregation::new(
             shared.state.round_params.mask_config,
             shared.state.round_params.model_length,
-        );
+        )
+        .with_round_id(shared.state.round_id);
+        Self {
+            private: Update {
+                model_agg,
+                update_pks: Vec::new(),
+                checkpointed: None,
+                seed_dict: None,
+                seed_dict_version: 0,
+                mask: None,
+                masked_models: Vec::new(),
+                trimmed_mean: None,
+            },
+            shared,
+        }
+    }
+
+    /// Creates an update state which resumes an interrupted update phase with the restored
+    /// aggregation.
+    ///
+    /// The restored masked models count towards the accepted update messages of the phase,
+    /// whereas the time of the phase starts over. The version of the seed dict continues from the
+    /// number of restored masked models, since the seed dict was never published before.
+    pub fn resume(shared: Shared<T>, restored: RestoredAggregation) -> Self {
+        let RestoredAggregation {
+            model_agg,
+            update_pks,
+        } = restored;
+        // the checkpoint of the restored aggregation is still in the store, if anything has been
+        // restored at all
+        let checkpointed =
+            Some((model_agg.nb_models(), Instant::now())).filter(|_| !update_pks.is_empty());
+        let seed_dict_version = model_agg.nb_models() as u64;
         Self {
             private: Update {
                 model_agg,
+                update_pks,
+                checkpointed,
                 seed_dict: None,
+                seed_dict_version,
+                mask: None,
+                masked_models: Vec::new(),
+                trimmed_mean: None,
             },
             shared,
         }
     }
+
+    /// Gets the update phase parameters, where the restored masked models count as accepted.
+    fn update_params(&self) -> PhaseParameters {
+        let mut params = self.shared.state.update_params();
+        let restored = self.private.model_agg.nb_models() as u64;
+        params.count.min = params.count.min.saturating_sub(restored);
+        params.count.max = params.count.max.saturating_sub(restored);
+        params
+    }
+
+    /// Checks whether the aggregation is checkpointed at all.
+    fn checkpoints_enabled(&self) -> Option<CheckpointParameters> {
+        self.shared
+            .state
+            .aggregation_checkpoint
+            .filter(|_| !self.shared.state.is_collect_only())
+    }
+
+    /// Checks whether a checkpoint of the aggregation is due wrt the checkpoint parameters.
+    fn is_checkpoint_due(&self) -> bool {
+        let CheckpointParameters { updates, interval } = match self.checkpoints_enabled() {
+            Some(params) => params,
+            None => return false,
+        };
+        let (nb_models, at) = match self.private.checkpointed {
+            Some(checkpointed) => checkpointed,
+            None => return true,
+        };
+        let new_models = (self.private.model_agg.nb_models() - nb_models) as u64;
+        matches!(updates, Some(updates) if new_models >= updates)
+            || matches!(
+                interval,
+                Some(interval) if new_models > 0 && at.elapsed() >= Duration::from_secs(interval)
+            )
+    }
+
+    /// Checks whether the latest checkpoint doesn't cover all aggregated masked models.
+    fn is_checkpoint_outdated(&self) -> bool {
+        !matches!(
+            self.private.checkpointed,
+            Some((nb_models, _)) if nb_models == self.private.model_agg.nb_models()
+        )
+    }
+
+    /// Derives and aggregates the masks of the update participants, if the coordinator runs in
+    /// trusted mode.
+    ///
+    /// This does what the sum participants do in the sum2 phase otherwise: the seeds of the
+    /// trusted aggregator are decrypted and the masks are derived from them wrt the round
+    /// parameters. With the trimmed mean aggregation, each masked model is also unmasked with
+    /// the mask of its update participant to compute the trimmed mean of the models.
+    fn aggregate_masks(&mut self) -> Result<(), UpdateError> {
+        let trusted_aggregator = match self.shared.state.trusted_aggregator.as_ref() {
+            Some(trusted_aggregator) => trusted_aggregator,
+            None => return Ok(()),
+        };
+        let seeds = self
+            .private
+            .seed_dict
+            .as_ref()
+            .and_then(|seed_dict| seed_dict.get(&trusted_aggregator.pk))
+            .ok_or(UpdateError::NoSeedDict)?;
+
+        info!("aggregating the masks as the trusted aggregator");
+        let round_params = &self.shared.state.round_params;
+        let config = round_params.mask_config;
+        let len = round_params.model_length;
+        let keys = &trusted_aggregator.ephm_keys;
+        let mut mask_agg = Aggregation::new(config, len);
+        let mut masked_models = self
+            .private
+            .update_pks
+            .iter()
+            .copied()
+            .zip(mem::take(&mut self.private.masked_models))
+            .collect::<HashMap<_, _>>();
+        let mut trimmed_mean = self
+            .shared
+            .state
+            .trimmed_mean
+            .map(|trim| TrimmedMeanAggregation::new(trim as usize));
+        for (pk, seed) in seeds {
+            let seed = seed
+                .decrypt(&keys.public, &keys.secret)
+                .map_err(|_| UpdateError::DecryptSeed)?;
+            let mask = if round_params.round_bound_masks {
+                seed.derive_mask_for_round(len, config, round_params.seed.as_slice())
+            } else {
+                seed.derive_mask(len, config)
+            };
+            if let (Some(trimmed_mean), Some(masked_model)) =
+                (trimmed_mean.as_mut(), masked_models.remove(pk))
+            {
+                trimmed_mean
+                    .aggregate_masked(masked_model, mask.clone())
+                    .map_err(UpdateError::TrimmedMean)?;
+            }
+            mask_agg
+                .validate_aggregation(&mask)
+                .map_err(UpdateError::AggregateMasks)?;
+            mask_agg.aggregate(mask);
+        }
+        self.private.mask = Some(mask_agg.into());
+        if let Some(trimmed_mean) = trimmed_mean {
+            info!("computing the trimmed mean of the masked models as the trusted aggregator");
+            self.private.trimmed_mean = Some(
+                trimmed_mean
+                    .trimmed_mean()
+                    .map_err(UpdateError::TrimmedMean)?,
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl<T> PhaseState<Update, T>
@@ -117,10 +560,13 @@ where
     T: Storage,
 {
     /// Updates the local seed dict and aggregates the masked model.
+    ///
+    /// In collect only mode, the masked model is collected instead of aggregated.
     async fn update_seed_dict_and_aggregate_mask(
         &mut self,
         pk: &UpdateParticipantPublicKey,
         local_seed_dict: &LocalSeedDict,
+        model_checksum: &Sha256,
         mask_object: MaskObject,
     ) -> Result<(), RequestError> {
         // Check if aggregation can be performed. It is important to
@@ -136,21 +582,84 @@ where
                 RequestError::AggregationFailed
             })?;
 
+        // The checksum can't be verified against the masked model, but it becomes part of the
+        // auditable record of the round. It is added before the local seed dict, which commits
+        // the update participant to the round, such that a failed write doesn't leave a
+        // committed update participant without a checksum. A checksum without a local seed dict
+        // is overwritten when the update participant submits its update message again.
+        debug!("adding the model checksum");
+        self.shared
+            .store
+            .add_model_checksum(self.shared.state.round_id, pk, model_checksum)
+            .await?;
+
+        // Likewise, the masked model is collected before the local seed dict is added, such that
+        // a collected update participant is never missing its masked model.
+        if self.shared.state.is_collect_only() {
+            info!("collecting the masked model and scalar");
+            self.shared
+                .store
+                .add_collected_masked_model(self.shared.state.round_id, pk, &mask_object)
+                .await?;
+        }
+
         // Try to update local seed dict first. If this fail, we do
         // not want to aggregate the model.
         info!("updating the global seed dictionary");
         self.add_local_seed_dict(pk, local_seed_dict)
             .await
             .map_err(|err| {
-                warn!("invalid local seed dictionary, ignoring update message");
+                if !err.is_already_recorded() {
+                    warn!("invalid local seed dictionary, ignoring update message");
+                }
                 err
             })?;
+        self.private.seed_dict_version += 1;
 
-        info!("aggregating the masked model and scalar");
-        self.private.model_agg.aggregate(mask_object);
+        if !self.shared.state.is_collect_only() {
+            info!("aggregating the masked model and scalar");
+            if self.shared.state.trimmed_mean.is_some() {
+                self.private.masked_models.push(mask_object.clone());
+            }
+            self.private.model_agg.aggregate(mask_object);
+            self.private.update_pks.push(*pk);
+            if self.is_checkpoint_due() {
+                self.checkpoint_aggregation().await;
+            }
+        }
         Ok(())
     }
 
+    /// Writes a checkpoint of the aggregation to the store, if checkpoints are enabled.
+    ///
+    /// A failed checkpoint is only logged, since the previous checkpoint is still consistent
+    /// with the seed dict.
+    async fn checkpoint_aggregation(&mut self) {
+        if self.checkpoints_enabled().is_none() {
+            return;
+        }
+
+        let checkpoint = AggregationCheckpoint {
+            round_id: self.shared.state.round_id,
+            aggregated_models: self.private.model_agg.to_mask_object(),
+            nb_models: self.private.model_agg.nb_models(),
+            update_pks: self.private.update_pks.clone(),
+        };
+        debug!(
+            "checkpointing the aggregation of {} masked models",
+            checkpoint.nb_models
+        );
+        match self
+            .shared
+            .store
+            .set_aggregation_checkpoint(&checkpoint)
+            .await
+        {
+            Ok(()) => self.private.checkpointed = Some((checkpoint.nb_models, Instant::now())),
+            Err(err) => warn!("failed to checkpoint the aggregation: {}", err),
+        }
+    }
+
     /// Adds a local seed dictionary to the global seed dictionary.
     ///
     /// # Error
@@ -166,7 +675,17 @@ where
             .add_local_seed_dict(pk, local_seed_dict)
             .await?
             .into_inner()
-            .map_err(RequestError::from)
+            .map_err(|err| {
+                if let LocalSeedDictAddError::DuplicateSeed = err {
+                    warn!("local seed dictionary repeats a seed of another update participant");
+                    metric!(
+                        Measurement::DuplicateSeedRejected,
+                        1,
+                        ("round_id", self.shared.state.round_id),
+                    );
+                }
+                RequestError::from(err)
+            })
     }
 
     /// Gets the global seed dict from the store.
@@ -182,22 +701,119 @@ where
 
         Ok(())
     }
+
+    /// Checks that the seed dict covers as many update participants as masked models have been
+    /// aggregated.
+    ///
+    /// A mismatch is recorded in the round stats and either fails the round or only raises a
+    /// warning, depending on the configured policy. The check is skipped in collect only mode,
+    /// because the masked models are collected instead of aggregated.
+    async fn check_seed_dict(&mut self) -> Result<(), UpdateError> {
+        if self.shared.state.is_collect_only() {
+            return Ok(());
+        }
+
+        let seed_dict_participants = self
+            .shared
+            .store
+            .number_of_unique_update_participants()
+            .await
+            .map_err(UpdateError::CountUpdateParticipants)?;
+        let aggregated_models = self.private.model_agg.nb_models() as u64;
+        if seed_dict_participants == aggregated_models {
+            return Ok(());
+        }
+
+        error!(
+            seed_dict_participants,
+            aggregated_models, "the seed dictionary doesn't match the aggregated masked models"
+        );
+        self.shared.state.seed_dict_mismatch = Some(SeedDictMismatch {
+            seed_dict_participants,
+            aggregated_models,
+        });
+        match self.shared.state.seed_dict_mismatch_policy {
+            SeedDictMismatchPolicy::Fail => Err(UpdateError::SeedDictMismatch {
+                seed_dict_participants,
+                aggregated_models,
+            }),
+            SeedDictMismatchPolicy::Warn => {
+                warn!("proceeding with the round despite the seed dictionary mismatch");
+                Ok(())
+            }
+        }
+    }
+
+    /// Persists the global seed dict for offline processing, if the coordinator runs in collect
+    /// only mode.
+    async fn collect_seed_dict(&mut self) -> Result<(), UpdateError> {
+        if !self.shared.state.is_collect_only() {
+            return Ok(());
+        }
+
+        info!("collecting the global seed dictionary");
+        let seed_dict = self
+            .private
+            .seed_dict
+            .as_ref()
+            .expect("unreachable: never fails when called after `seed_dict()`");
+        self.shared
+            .store
+            .set_collected_seed_dict(self.shared.state.round_id, seed_dict)
+            .await
+            .map_err(UpdateError::CollectSeedDict)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    };
+
     use anyhow::anyhow;
-    use xaynet_core::{SeedDict, SumDict};
+    use xaynet_core::{
+        common::GlobalModelMetadata,
+        crypto::{PublicEncryptKey, PublicSigningKey, SigningKeyPair},
+        mask::{
+            BoundType,
+            DataType,
+            Endianness,
+            FromPrimitives,
+            GroupType,
+            MaskConfig,
+            MaskSeed,
+            Masker,
+            Model,
+            ModelType,
+            Scalar,
+            SealedBox,
+            SeedCipher,
+        },
+        message::{Message, Update as UpdateMessage},
+        ParticipantTaskSignature,
+        SeedDict,
+        SumDict,
+        UpdateSeedDict,
+    };
 
     use crate::{
+        settings::CoordinatorMode,
         state_machine::{
             coordinator::CoordinatorState,
+            denylist::{Denylist, DenylistManager},
             events::{EventPublisher, EventSubscriber, ModelUpdate},
             tests::{
                 utils::{
                     assert_event_updated,
+                    assert_state_eq_except_round_stats,
+                    compose_update_message,
                     enable_logging,
                     init_shared,
                     send_update_messages,
@@ -209,14 +825,17 @@ mod tests {
             },
         },
         storage::{
+            coordinator_storage::memory::MemoryStorage,
             tests::{
-                utils::{create_global_model, create_mask},
+                utils::{create_and_add_sum_participant_entries, create_global_model, create_mask},
                 MockCoordinatorStore,
                 MockModelStore,
             },
+            trust_anchor::noop::NoOp,
             LocalSeedDictAdd,
             LocalSeedDictAddError,
             Store,
+            SumPartAdd,
         },
     };
 
@@ -225,7 +844,7 @@ mod tests {
             .broadcast_phase(PhaseName::Sum)
             .broadcast_sum_dict(DictionaryUpdate::New(Arc::new(SumDict::new())))
             .broadcast_seed_dict(DictionaryUpdate::Invalidate)
-            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1))))
+            .broadcast_model(ModelUpdate::New(Arc::new(create_global_model(1)), None))
             .build()
     }
 
@@ -235,7 +854,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_event_updated(&events_after.seed_dict, &events_before.seed_dict);
@@ -252,7 +871,7 @@ mod tests {
         state_after: &CoordinatorState,
         events_after: &EventSnapshot,
     ) {
-        assert_eq!(state_after, state_before);
+        assert_state_eq_except_round_stats(state_after, state_before);
 
         assert_event_updated(&events_after.phase, &events_before.phase);
         assert_eq!(events_after.keys, events_before.keys);
@@ -272,7 +891,7 @@ mod tests {
         // 1. broadcast Update phase
         // 2. accept 10 update messages
         // 3. fetch seed dict
-        // 4. broadcast seed dict
+        // 4. broadcast seed dict (version 10)
         // 5. move into sum2 phase
         //
         // What should not happen:
@@ -282,11 +901,17 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_local_seed_dict()
             .times(10)
             .returning(move |_, _| Ok(LocalSeedDictAdd(Ok(()))));
+        cs.expect_add_model_checksum()
+            .times(10)
+            .returning(move |_, _, _| Ok(()));
         cs.expect_seed_dict()
             .return_once(move || Ok(Some(SeedDict::new())));
+        cs.expect_number_of_unique_update_participants()
+            .return_once(move || Ok(10));
         let store = Store::new(cs, MockModelStore::new());
         let state = CoordinatorStateBuilder::new()
             .with_round_id(1)
@@ -316,6 +941,9 @@ mod tests {
             &events_after_update,
         );
 
+        // the seed dict version counts the accepted local seed dicts
+        assert_eq!(events_after_update.seed_dict.event.unwrap().version, 10);
+
         assert!(state_machine.is_sum2());
     }
 
@@ -338,9 +966,13 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_local_seed_dict()
             .times(1)
             .returning(move |_, _| Ok(LocalSeedDictAdd(Ok(()))));
+        cs.expect_add_model_checksum()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
         cs.expect_seed_dict().return_once(move || Err(anyhow!("")));
         let store = Store::new(cs, MockModelStore::new());
         let state = CoordinatorStateBuilder::new()
@@ -396,9 +1028,13 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_local_seed_dict()
             .times(1)
             .returning(move |_, _| Ok(LocalSeedDictAdd(Ok(()))));
+        cs.expect_add_model_checksum()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
         cs.expect_seed_dict().return_once(move || Ok(None));
         let store = Store::new(cs, MockModelStore::new());
         let state = CoordinatorStateBuilder::new()
@@ -455,11 +1091,17 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
         cs.expect_add_local_seed_dict()
             .times(3)
             .returning(move |_, _| Ok(LocalSeedDictAdd(Ok(()))));
+        cs.expect_add_model_checksum()
+            .times(3)
+            .returning(move |_, _, _| Ok(()));
         cs.expect_seed_dict()
             .return_once(move || Ok(Some(SeedDict::new())));
+        cs.expect_number_of_unique_update_participants()
+            .return_once(move || Ok(3));
         let store = Store::new(cs, MockModelStore::new());
         let state = CoordinatorStateBuilder::new()
             .with_round_id(1)
@@ -493,6 +1135,147 @@ mod tests {
         assert!(state_machine.is_sum2());
     }
 
+    /// Creates a store which counts the added local seed dicts as the update participants of the
+    /// seed dict. The returned counter allows to inject seed dict writes.
+    fn store_counting_update_participants(
+        nb_updates: usize,
+    ) -> (Store<MockCoordinatorStore, MockModelStore, NoOp>, Arc<AtomicU64>) {
+        let written = Arc::new(AtomicU64::new(0));
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        let counter = written.clone();
+        cs.expect_add_local_seed_dict()
+            .times(nb_updates)
+            .returning(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(LocalSeedDictAdd(Ok(())))
+            });
+        cs.expect_add_model_checksum()
+            .times(nb_updates)
+            .returning(move |_, _, _| Ok(()));
+        cs.expect_seed_dict()
+            .return_once(move || Ok(Some(SeedDict::new())));
+        let counter = written.clone();
+        cs.expect_number_of_unique_update_participants()
+            .return_once(move || Ok(counter.load(Ordering::SeqCst)));
+        (Store::new(cs, MockModelStore::new()), written)
+    }
+
+    #[tokio::test]
+    async fn test_update_seed_dict_mismatch_fail() {
+        // No Storage errors
+        // a seed dict write without a corresponding aggregation is injected
+        //
+        // What should happen:
+        // 1. broadcast Update phase
+        // 2. accept 3 update messages
+        // 3. fetch seed dict
+        // 4. count 4 update participants in the seed dict but 3 aggregated masked models
+        // 5. record the mismatch
+        // 6. move into error phase
+        //
+        // What should not happen:
+        // - the seed dict has been broadcasted
+        // - the sum2 phase has been entered
+        enable_logging();
+
+        let (store, written) = store_counting_update_participants(3);
+        written.fetch_add(1, Ordering::SeqCst);
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_seed_dict_mismatch_policy(SeedDictMismatchPolicy::Fail)
+            .with_update_count_min(3)
+            .with_update_count_max(3)
+            .with_update_time_min(1)
+            .build();
+
+        let (event_publisher, event_subscriber) = events_from_sum_phase(&state);
+        let events_before_update = EventSnapshot::from(&event_subscriber);
+        let state_before_update = state.clone();
+
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Update, _>::new(shared));
+        send_update_messages(3, request_tx.clone());
+        let state_machine = state_machine.next().await.unwrap();
+
+        let state_after_update = state_machine.as_ref().clone();
+        let events_after_update = EventSnapshot::from(&event_subscriber);
+        assert_after_phase_failure(
+            &state_before_update,
+            &events_before_update,
+            &state_after_update,
+            &events_after_update,
+        );
+        let mismatch = SeedDictMismatch {
+            seed_dict_participants: 4,
+            aggregated_models: 3,
+        };
+        assert_eq!(state_after_update.seed_dict_mismatch, Some(mismatch));
+
+        assert!(state_machine.is_failure());
+        assert!(matches!(
+            state_machine.into_failure_phase_state().private.error,
+            PhaseError::Update(UpdateError::SeedDictMismatch {
+                seed_dict_participants: 4,
+                aggregated_models: 3,
+            })
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_update_seed_dict_mismatch_warn() {
+        // No Storage errors
+        // a seed dict write without a corresponding aggregation is injected
+        //
+        // What should happen:
+        // 1. broadcast Update phase
+        // 2. accept 3 update messages
+        // 3. fetch seed dict
+        // 4. count 4 update participants in the seed dict but 3 aggregated masked models
+        // 5. record the mismatch
+        // 6. broadcast seed dict
+        // 7. move into sum2 phase
+        //
+        // What should not happen:
+        // - the round has failed
+        enable_logging();
+
+        let (store, written) = store_counting_update_participants(3);
+        written.fetch_add(1, Ordering::SeqCst);
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_seed_dict_mismatch_policy(SeedDictMismatchPolicy::Warn)
+            .with_update_count_min(3)
+            .with_update_count_max(3)
+            .with_update_time_min(1)
+            .build();
+
+        let (event_publisher, event_subscriber) = events_from_sum_phase(&state);
+        let events_before_update = EventSnapshot::from(&event_subscriber);
+        let state_before_update = state.clone();
+
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Update, _>::new(shared));
+        send_update_messages(3, request_tx.clone());
+        let state_machine = state_machine.next().await.unwrap();
+
+        let state_after_update = state_machine.as_ref().clone();
+        let events_after_update = EventSnapshot::from(&event_subscriber);
+        assert_after_phase_success(
+            &state_before_update,
+            &events_before_update,
+            &state_after_update,
+            &events_after_update,
+        );
+        let mismatch = SeedDictMismatch {
+            seed_dict_participants: 4,
+            aggregated_models: 3,
+        };
+        assert_eq!(state_after_update.seed_dict_mismatch, Some(mismatch));
+
+        assert!(state_machine.is_sum2());
+    }
+
     #[tokio::test]
     async fn test_rejected_messages_pet_error() {
         // No Storage errors
@@ -511,6 +1294,10 @@ mod tests {
         enable_logging();
 
         let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_model_checksum()
+            .times(3)
+            .returning(move |_, _, _| Ok(()));
         cs.expect_add_local_seed_dict()
             .times(3)
             .returning(move |_, _| {
@@ -552,4 +1339,517 @@ mod tests {
             PhaseError::PhaseTimeout(_)
         ))
     }
+
+    #[tokio::test]
+    async fn test_model_checksum_write_failed() {
+        // Storage error while adding the model checksum
+        //
+        // What should happen:
+        // 1. reject the update message
+        //
+        // What should not happen:
+        // - the local seed dict has been added
+        // - the masked model has been aggregated
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_add_model_checksum()
+            .times(1)
+            .returning(move |_, _, _| Err(anyhow!("")));
+        cs.expect_add_local_seed_dict().never();
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new().with_round_id(1).build();
+
+        let (event_publisher, _event_subscriber) = events_from_sum_phase(&state);
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let mut update = PhaseState::<Update, _>::new(shared);
+
+        let req = StateMachineRequest::Update(UpdateRequest {
+            participant_pk: SigningKeyPair::generate().public,
+            local_seed_dict: LocalSeedDict::default(),
+            model_checksum: Sha256::zeroed(),
+            masked_model: create_mask(1, 1),
+        });
+        assert!(update.handle_request(req).await.is_err());
+        assert_eq!(update.private.model_agg.nb_models(), 0);
+        assert!(update.private.update_pks.is_empty());
+        assert_eq!(update.private.seed_dict_version, 0);
+    }
+
+    #[tokio::test]
+    async fn test_collected_masked_model_write_failed() {
+        // Storage error while collecting the masked model in collect only mode
+        //
+        // What should happen:
+        // 1. reject the update message
+        //
+        // What should not happen:
+        // - the local seed dict has been added
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_add_model_checksum()
+            .times(1)
+            .returning(move |_, _, _| Ok(()));
+        cs.expect_add_collected_masked_model()
+            .times(1)
+            .returning(move |_, _, _| Err(anyhow!("")));
+        cs.expect_add_local_seed_dict().never();
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_coordinator_mode(CoordinatorMode::CollectOnly)
+            .build();
+
+        let (event_publisher, _event_subscriber) = events_from_sum_phase(&state);
+        let (shared, _request_tx) = init_shared(state, store, event_publisher);
+        let mut update = PhaseState::<Update, _>::new(shared);
+
+        let req = StateMachineRequest::Update(UpdateRequest {
+            participant_pk: SigningKeyPair::generate().public,
+            local_seed_dict: LocalSeedDict::default(),
+            model_checksum: Sha256::zeroed(),
+            masked_model: create_mask(1, 1),
+        });
+        assert!(update.handle_request(req).await.is_err());
+        assert_eq!(update.private.seed_dict_version, 0);
+    }
+
+    #[tokio::test]
+    async fn test_banned_participant() {
+        // No Storage errors
+        //
+        // What should happen:
+        // 1. reject the otherwise valid update message of a banned participant
+        // 2. lift the ban
+        // 3. accept the update message of the participant
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_add_denied_participant().return_once(|_| Ok(true));
+        cs.expect_remove_denied_participant()
+            .return_once(|_| Ok(true));
+        let denylist = Denylist::new(1);
+        let mut manager = DenylistManager::new(cs, denylist.clone());
+
+        let (store, written) = store_counting_update_participants(1);
+        let state = CoordinatorStateBuilder::new().with_round_id(1).build();
+        let (event_publisher, _event_subscriber) = events_from_sum_phase(&state);
+        let (mut shared, _request_tx) = init_shared(state, store, event_publisher);
+        shared.denylist = denylist;
+        let mut update = PhaseState::<Update, _>::new(shared);
+        let request = || StateMachineRequest::from(compose_update_message(create_mask(1, 1)));
+
+        let pk = PublicSigningKey::zeroed();
+        assert!(manager.ban(pk).await.unwrap());
+        assert!(matches!(
+            update.handle_request(request()).await,
+            Err(RequestError::Banned)
+        ));
+        assert_eq!(written.load(Ordering::SeqCst), 0);
+
+        assert!(manager.unban(&pk).await.unwrap());
+        assert!(update.handle_request(request()).await.is_ok());
+        assert_eq!(written.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_to_idle_phase_collect_only() {
+        // No Storage errors
+        // lets pretend we come from the sum phase in collect only mode
+        //
+        // What should happen:
+        // 1. broadcast Update phase
+        // 2. accept 3 update messages and collect their masked models
+        // 3. fetch and collect the seed dict
+        // 4. broadcast the invalidation of the sum and seed dicts
+        // 5. move into idle phase
+        //
+        // What should not happen:
+        // - the masked models have been aggregated
+        // - the seed dict has been broadcasted
+        // - the sum2 phase has been broadcasted
+        // - the global model has been invalidated
+        enable_logging();
+
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_local_seed_dict()
+            .times(3)
+            .returning(move |_, _| Ok(LocalSeedDictAdd(Ok(()))));
+        cs.expect_add_model_checksum()
+            .times(3)
+            .withf(|round_id, _, _| *round_id == 1)
+            .returning(move |_, _, _| Ok(()));
+        cs.expect_add_collected_masked_model()
+            .times(3)
+            .withf(|round_id, _, _| *round_id == 1)
+            .returning(move |_, _, _| Ok(()));
+        cs.expect_seed_dict()
+            .return_once(move || Ok(Some(SeedDict::new())));
+        cs.expect_set_collected_seed_dict()
+            .times(1)
+            .withf(|round_id, _| *round_id == 1)
+            .returning(move |_, _| Ok(()));
+        let store = Store::new(cs, MockModelStore::new());
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_coordinator_mode(CoordinatorMode::CollectOnly)
+            .with_update_count_min(3)
+            .with_update_count_max(3)
+            .with_update_time_min(1)
+            .build();
+
+        let (event_publisher, event_subscriber) = events_from_sum_phase(&state);
+        let events_before_update = EventSnapshot::from(&event_subscriber);
+        let state_before_update = state.clone();
+
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Update, _>::new(shared));
+        assert!(state_machine.is_update());
+
+        send_update_messages(3, request_tx.clone());
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_idle());
+
+        let mut state_after_update = state_machine.as_ref().clone();
+        // the round id has been increased when entering the idle phase
+        state_after_update.round_id -= 1;
+        assert_state_eq_except_round_stats(&state_after_update, &state_before_update);
+
+        let events_after_update = EventSnapshot::from(&event_subscriber);
+        assert_event_updated(&events_after_update.phase, &events_before_update.phase);
+        assert_eq!(events_after_update.phase.event, PhaseName::Update);
+        assert_event_updated(
+            &events_after_update.sum_dict,
+            &events_before_update.sum_dict,
+        );
+        assert_eq!(
+            events_after_update.sum_dict.event,
+            DictionaryUpdate::Invalidate
+        );
+        assert_eq!(
+            events_after_update.seed_dict,
+            events_before_update.seed_dict
+        );
+        assert_eq!(events_after_update.keys, events_before_update.keys);
+        assert_eq!(events_after_update.params, events_before_update.params);
+        assert_eq!(events_after_update.model, events_before_update.model);
+    }
+
+    /// Runs a round in trusted mode with the update messages of the `local_models` and returns
+    /// the global model.
+    async fn run_trusted_round(trimmed_mean: Option<u64>, local_models: &[[i64; 3]]) -> Model {
+        enable_logging();
+
+        let mask_config = MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::I64,
+            bound_type: BoundType::B2,
+            model_type: ModelType::M3,
+        };
+        let nb_models = local_models.len();
+        let mut state = CoordinatorStateBuilder::new()
+            .with_trusted_aggregator()
+            .with_mask_config(mask_config)
+            .with_model_length(3)
+            .with_update_count_min(nb_models as u64)
+            .with_update_count_max(nb_models as u64)
+            .with_update_time_min(1);
+        if let Some(trim) = trimmed_mean {
+            state = state.with_trimmed_mean(trim);
+        }
+        let state = state.build();
+        let config = state.round_params.mask_config;
+
+        let local_seed_dicts = Arc::new(Mutex::new(Vec::new()));
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts().return_once(move || Ok(()));
+        cs.expect_set_coordinator_state().returning(move |_| Ok(()));
+        cs.expect_add_sum_participant()
+            .times(1)
+            .returning(move |_, _| Ok(SumPartAdd(Ok(()))));
+        let dicts = local_seed_dicts.clone();
+        cs.expect_add_local_seed_dict().times(nb_models).returning(
+            move |update_pk, local_seed_dict| {
+                dicts
+                    .lock()
+                    .unwrap()
+                    .push((*update_pk, local_seed_dict.clone()));
+                Ok(LocalSeedDictAdd(Ok(())))
+            });
+        cs.expect_add_model_checksum()
+            .times(nb_models)
+            .returning(move |_, _, _| Ok(()));
+        let dicts = local_seed_dicts.clone();
+        cs.expect_seed_dict().return_once(move || {
+            let dicts = dicts.lock().unwrap();
+            let sum_pk = *dicts[0].1.keys().next().unwrap();
+            let update_seed_dict =
+                UpdateSeedDict::project(&sum_pk, dicts.iter().map(|(pk, dict)| (pk, dict)));
+            let mut seed_dict = SeedDict::new();
+            seed_dict.insert(sum_pk, update_seed_dict);
+            Ok(Some(seed_dict))
+        });
+        cs.expect_number_of_unique_update_participants()
+            .return_once(move || Ok(nb_models as u64));
+        #[cfg(feature = "model-persistence")]
+        {
+            cs.expect_set_latest_global_model_id()
+                .returning(move |_| Ok(()));
+        }
+        let ms = {
+            #[cfg(not(feature = "model-persistence"))]
+            {
+                MockModelStore::new()
+            }
+            #[cfg(feature = "model-persistence")]
+            {
+                let mut ms = MockModelStore::new();
+                ms.expect_set_global_model()
+                    .returning(move |_, _, _| Ok("id".to_string()));
+                ms
+            }
+        };
+        let store = Store::new(cs, ms);
+
+        let (event_publisher, event_subscriber) = EventBusBuilder::new(&state).build();
+        let (shared, request_tx) = init_shared(state, store, event_publisher);
+        let state_machine = StateMachine::from(PhaseState::<Idle, _>::new(shared));
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_update());
+
+        let sum_dict = match event_subscriber.sum_dict_listener().get_latest().event {
+            DictionaryUpdate::New(sum_dict) => sum_dict,
+            update => panic!("unexpected sum dict update: {:?}", update),
+        };
+        let trusted_aggregator = state_machine.as_ref().trusted_aggregator.clone().unwrap();
+        assert_eq!(*sum_dict, trusted_aggregator.sum_dict());
+
+        for weights in local_models.iter() {
+            let local_model = Model::from_primitives(weights.iter().cloned()).unwrap();
+            let (mask_seed, masked_model) = Masker::new(config).mask(Scalar::unit(), &local_model);
+            let payload = UpdateMessage {
+                sum_signature: ParticipantTaskSignature::zeroed(),
+                update_signature: ParticipantTaskSignature::zeroed(),
+                model_checksum: local_model.checksum(),
+                seed_cipher: SealedBox::ID,
+                masked_model,
+                local_seed_dict: LocalSeedDict::new(&sum_dict, &mask_seed),
+            };
+            let message = Message::new_update(
+                SigningKeyPair::generate().public,
+                PublicEncryptKey::zeroed(),
+                payload,
+            );
+            let request = request_tx.clone();
+            tokio::spawn(async move { request.msg(&message).await });
+        }
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_unmask());
+        assert_eq!(
+            event_subscriber.sum_dict_listener().get_latest().event,
+            DictionaryUpdate::Invalidate
+        );
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_idle());
+
+        let (global_model, metadata) = match event_subscriber.model_listener().get_latest().event {
+            ModelUpdate::New(global_model, Some(metadata)) => (global_model, metadata),
+            update => panic!("unexpected model update: {:?}", update),
+        };
+        assert_eq!(
+            metadata,
+            GlobalModelMetadata {
+                round_id: 1,
+                data_type: DataType::I64,
+                model_length: 3,
+                mask_config: config,
+                nb_models,
+                error_bound: metadata.error_bound,
+            }
+        );
+        let bytes = metadata
+            .to_primitives_bytes(&global_model, Endianness::Little)
+            .unwrap();
+        Model::from_primitives_bytes(&bytes, metadata.data_type, Endianness::Little).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_trusted_round() {
+        // No Storage errors
+        // lets pretend the coordinator runs in trusted mode
+        //
+        // What should happen:
+        // 1. add the trusted aggregator as the only sum participant
+        // 2. broadcast a sum dict with the trusted aggregator as the only entry
+        // 3. skip the sum phase
+        // 4. accept 3 update messages
+        // 5. decrypt the seeds and aggregate the masks by the coordinator
+        // 6. skip the sum2 phase
+        // 7. unmask and broadcast the averaged global model
+        //
+        // What should not happen:
+        // - the best masks have been fetched from the store
+        let global_model = run_trusted_round(None, &[[1_i64, 2, 3], [3, 4, 5], [5, 6, 7]]).await;
+        assert_eq!(
+            global_model,
+            Model::from_primitives(vec![3_i64, 4, 5].into_iter()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trusted_round_trimmed_mean() {
+        // No Storage errors
+        // lets pretend the coordinator runs in trusted mode with the trimmed mean aggregation
+        //
+        // What should happen:
+        // 1. accept 5 update messages, one of them with an outlier model
+        // 2. decrypt the seeds and unmask each masked model by the coordinator
+        // 3. broadcast the trimmed mean as the global model, which removes the outlier
+        let global_model = run_trusted_round(
+            Some(1),
+            &[
+                [1_i64, 2, 3],
+                [3, 4, 5],
+                [5, 6, 7],
+                [7, 8, 9],
+                [100, -100, 100],
+            ],
+        )
+        .await;
+        assert_eq!(
+            global_model,
+            Model::from_primitives(vec![5_i64, 4, 7].into_iter()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_aggregation_checkpoint_after_crash() {
+        // The storage crashes after any number of writes during the update phase
+        //
+        // What should happen:
+        // 1. accept update messages until the storage crashes
+        // 2. restore the aggregation from the latest checkpoint and roll back the seed dict
+        // 3. the seed dict and the restored aggregation include the same update participants
+        // 4. resume the update phase and accept all update messages again
+        // 5. the aggregation includes the masked models of all update participants
+        enable_logging();
+
+        let state = CoordinatorStateBuilder::new()
+            .with_round_id(1)
+            .with_aggregation_checkpoint(Some(2), None)
+            .build();
+        let aggregate = |masks: &[&MaskObject]| {
+            let mut model_agg = Aggregation::new(
+                state.round_params.mask_config,
+                state.round_params.model_length,
+            );
+            for mask in masks {
+                model_agg.aggregate((*mask).clone());
+            }
+            model_agg.to_mask_object()
+        };
+        let update_participants = |seed_dict: SeedDict| {
+            let mut columns = seed_dict
+                .values()
+                .map(|column| column.keys().copied().collect::<HashSet<_>>());
+            let update_pks = columns.next().unwrap();
+            assert!(columns.all(|column| column == update_pks));
+            update_pks
+        };
+
+        for writes in 0.. {
+            let mut storage = MemoryStorage::new();
+            create_and_add_sum_participant_entries(&mut storage, 2).await;
+            let sum_dict = storage.sum_dict().await.unwrap().unwrap();
+            let updates = (1..=5)
+                .map(|number| {
+                    let pk = SigningKeyPair::generate().public;
+                    let local_seed_dict = LocalSeedDict::new(&sum_dict, &MaskSeed::generate());
+                    (pk, (local_seed_dict, create_mask(1, number)))
+                })
+                .collect::<HashMap<_, _>>();
+            let request = |pk: &UpdateParticipantPublicKey| {
+                let (local_seed_dict, masked_model) = updates[pk].clone();
+                StateMachineRequest::Update(UpdateRequest {
+                    participant_pk: *pk,
+                    local_seed_dict,
+                    model_checksum: Sha256::zeroed(),
+                    masked_model,
+                })
+            };
+
+            // the update phase runs until the injected crash
+            storage.crash_after(writes);
+            let (event_publisher, _event_subscriber) = events_from_sum_phase(&state);
+            let store = Store::new(storage.clone(), MockModelStore::new());
+            let (shared, _request_tx) = init_shared(state.clone(), store, event_publisher);
+            let mut update = PhaseState::<Update, _>::new(shared);
+            update.checkpoint_aggregation().await;
+            for pk in updates.keys() {
+                let _ = update.handle_request(request(pk)).await;
+            }
+            let crashed = storage.has_crashed();
+            storage.revive();
+
+            // the restarted coordinator restores the aggregation
+            let restored = match RestoredAggregation::load(&mut storage, &state)
+                .await
+                .unwrap()
+            {
+                Some(restored) => restored,
+                None => {
+                    // the initial checkpoint failed, hence nothing has been written afterwards
+                    assert!(crashed);
+                    assert!(
+                        update_participants(storage.seed_dict().await.unwrap().unwrap()).is_empty()
+                    );
+                    continue;
+                }
+            };
+            let restored_pks = restored.update_pks.iter().copied().collect::<HashSet<_>>();
+            assert_eq!(
+                update_participants(storage.seed_dict().await.unwrap().unwrap()),
+                restored_pks
+            );
+            let restored_masks = restored
+                .update_pks
+                .iter()
+                .map(|pk| &updates[pk].1)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                restored.model_agg.to_mask_object(),
+                aggregate(&restored_masks)
+            );
+
+            // the resumed update phase accepts the update messages of the rolled back participants
+            let (event_publisher, _event_subscriber) = events_from_sum_phase(&state);
+            let store = Store::new(storage.clone(), MockModelStore::new());
+            let (shared, _request_tx) = init_shared(state.clone(), store, event_publisher);
+            let mut update = PhaseState::<Update, _>::resume(shared, restored);
+            for pk in updates.keys() {
+                let res = update.handle_request(request(pk)).await;
+                assert_eq!(res.is_ok(), !restored_pks.contains(pk));
+            }
+            assert_eq!(
+                update_participants(storage.seed_dict().await.unwrap().unwrap()),
+                updates.keys().copied().collect::<HashSet<_>>()
+            );
+            let masks = update
+                .private
+                .update_pks
+                .iter()
+                .map(|pk| &updates[pk].1)
+                .collect::<Vec<_>>();
+            assert_eq!(masks.len(), updates.len());
+            assert_eq!(update.private.model_agg.to_mask_object(), aggregate(&masks));
+
+            if !crashed {
+                break;
+            }
+        }
+    }
 }
@@ -8,13 +8,18 @@ use std::{
 
 use derive_more::From;
 use displaydoc::Display;
-use futures::{future::FutureExt, Stream};
+use futures::{
+    future::{Future, FutureExt},
+    Stream,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{trace, Span};
 
 use crate::storage::{LocalSeedDictAddError, MaskScoreIncrError, StorageError, SumPartAddError};
 use xaynet_core::{
+    crypto::Sha256,
     mask::MaskObject,
     message::{Message, Payload, Update},
     LocalSeedDict,
@@ -43,6 +48,103 @@ pub enum RequestError {
     SumPartAdd(#[from] SumPartAddError),
     /// Incrementing a mask score failed: {0}.
     MaskScoreIncr(#[from] MaskScoreIncrError),
+    /// Outdated seed dictionary (version {0}, expected {1}): refetch it and recompute the mask.
+    StaleSeedDict(u64, u64),
+    /// The participant is banned.
+    Banned,
+    /// The client certificate identity of the sum participant is unknown.
+    MissingIdentity,
+    /// Another sum participant with the same client certificate identity is already present.
+    DuplicateIdentity,
+    /// The acceptance of messages is paused.
+    Paused,
+}
+
+impl RequestError {
+    /// Checks whether the request resubmitted a message which is already recorded.
+    ///
+    /// Resubmitting a recorded message is benign, e.g. if the participant didn't receive the
+    /// response to the original message. Unlike a conflicting resubmission, it isn't rejected.
+    pub fn is_already_recorded(&self) -> bool {
+        matches!(
+            self,
+            Self::SumPartAdd(SumPartAddError::AlreadyRecorded)
+                | Self::LocalSeedDictAdd(LocalSeedDictAddError::AlreadyRecorded)
+                | Self::MaskScoreIncr(MaskScoreIncrError::AlreadyRecorded)
+        )
+    }
+
+    /// Gets the reason why the request was rejected or `None` if it was discarded or already
+    /// recorded.
+    pub fn rejection_reason(&self) -> Option<RejectionReason> {
+        let reason = match self {
+            Self::MessageDiscarded
+            | Self::SumPartAdd(SumPartAddError::AlreadyRecorded)
+            | Self::LocalSeedDictAdd(LocalSeedDictAddError::AlreadyRecorded)
+            | Self::MaskScoreIncr(MaskScoreIncrError::AlreadyRecorded) => return None,
+            Self::MessageRejected => RejectionReason::UnexpectedMessage,
+            Self::AggregationFailed => RejectionReason::InvalidModel,
+            Self::InternalError(_) | Self::CoordinatorStorage(_) => RejectionReason::Internal,
+            Self::LocalSeedDictAdd(LocalSeedDictAddError::LengthMisMatch)
+            | Self::LocalSeedDictAdd(LocalSeedDictAddError::UnknownSumParticipant) => {
+                RejectionReason::InvalidSeedDict
+            }
+            Self::LocalSeedDictAdd(LocalSeedDictAddError::UpdatePkAlreadySubmitted)
+            | Self::LocalSeedDictAdd(
+                LocalSeedDictAddError::UpdatePkAlreadyExistsInUpdateSeedDict,
+            )
+            | Self::SumPartAdd(SumPartAddError::AlreadyExists)
+            | Self::MaskScoreIncr(MaskScoreIncrError::MaskAlreadySubmitted) => {
+                RejectionReason::Duplicate
+            }
+            Self::LocalSeedDictAdd(LocalSeedDictAddError::DuplicateSeed) => {
+                RejectionReason::DuplicateSeed
+            }
+            Self::SumPartAdd(SumPartAddError::EphemeralKeyCollision) => {
+                RejectionReason::EphemeralKeyCollision
+            }
+            Self::MaskScoreIncr(MaskScoreIncrError::UnknownSumPk) => {
+                RejectionReason::UnknownSumParticipant
+            }
+            Self::StaleSeedDict(..) => RejectionReason::StaleSeedDict,
+            Self::Banned => RejectionReason::Banned,
+            Self::MissingIdentity => RejectionReason::MissingIdentity,
+            Self::DuplicateIdentity => RejectionReason::DuplicateIdentity,
+            Self::Paused => RejectionReason::Paused,
+        };
+        Some(reason)
+    }
+}
+
+/// The reasons why the state machine rejects a request.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum RejectionReason {
+    /// The message is not expected in the current phase.
+    UnexpectedMessage,
+    /// The masked model or scalar can't be aggregated.
+    InvalidModel,
+    /// The local seed dictionary doesn't match the sum dictionary.
+    InvalidSeedDict,
+    /// The sum participant is unknown.
+    UnknownSumParticipant,
+    /// The participant already sent a message in this phase.
+    Duplicate,
+    /// The local seed dictionary contains a seed which another participant already submitted.
+    DuplicateSeed,
+    /// The ephemeral key of the sum participant is already used by another sum participant.
+    EphemeralKeyCollision,
+    /// The mask is derived from an outdated seed dictionary.
+    StaleSeedDict,
+    /// The participant is on the denylist.
+    Banned,
+    /// The client certificate identity of the participant is unknown.
+    MissingIdentity,
+    /// The client certificate identity is already used by another sum participant.
+    DuplicateIdentity,
+    /// The acceptance of messages is paused.
+    Paused,
+    /// The request failed due to an internal or storage error.
+    Internal,
 }
 
 /// A sum request.
@@ -52,6 +154,8 @@ pub struct SumRequest {
     pub participant_pk: SumParticipantPublicKey,
     /// The ephemeral public key of the participant.
     pub ephm_pk: SumParticipantEphemeralPublicKey,
+    /// The identity of the client certificate of the participant, if known.
+    pub identity: Option<String>,
 }
 
 /// An update request.
@@ -61,6 +165,8 @@ pub struct UpdateRequest {
     pub participant_pk: UpdateParticipantPublicKey,
     /// The local seed dict that contains the seed used to mask `masked_model`.
     pub local_seed_dict: LocalSeedDict,
+    /// The checksum of the local model of the participant before it was masked.
+    pub model_checksum: Sha256,
     /// The masked model trained by the participant.
     pub masked_model: MaskObject,
 }
@@ -70,6 +176,10 @@ pub struct UpdateRequest {
 pub struct Sum2Request {
     /// The public key of the participant.
     pub participant_pk: ParticipantPublicKey,
+    /// The version of the seed dictionary the model mask was derived from.
+    ///
+    /// This is `None` for sum2 messages of legacy participants.
+    pub seed_dict_version: Option<u64>,
     /// The model mask computed by the participant.
     pub model_mask: MaskObject,
 }
@@ -84,6 +194,18 @@ pub enum StateMachineRequest {
     Sum2(Sum2Request),
 }
 
+impl StateMachineRequest {
+    /// Attaches the identity of the client certificate of the participant to a sum request.
+    ///
+    /// The identity is ignored for all other requests.
+    pub fn with_identity(mut self, identity: Option<String>) -> Self {
+        if let Self::Sum(ref mut req) = self {
+            req.identity = identity;
+        }
+        self
+    }
+}
+
 impl From<Message> for StateMachineRequest {
     fn from(message: Message) -> Self {
         let participant_pk = message.participant_pk;
@@ -91,21 +213,25 @@ impl From<Message> for StateMachineRequest {
             Payload::Sum(sum) => StateMachineRequest::Sum(SumRequest {
                 participant_pk,
                 ephm_pk: sum.ephm_pk,
+                identity: None,
             }),
             Payload::Update(update) => {
                 let Update {
                     local_seed_dict,
+                    model_checksum,
                     masked_model,
                     ..
                 } = update;
                 StateMachineRequest::Update(UpdateRequest {
                     participant_pk,
                     local_seed_dict,
+                    model_checksum,
                     masked_model,
                 })
             }
             Payload::Sum2(sum2) => StateMachineRequest::Sum2(Sum2Request {
                 participant_pk,
+                seed_dict_version: sum2.seed_dict_version,
                 model_mask: sum2.model_mask,
             }),
             Payload::Chunk(_) => unimplemented!(),
@@ -128,15 +254,33 @@ impl RequestSender {
     ///
     /// [`StateMachine`]: crate::state_machine
     pub async fn request(&self, req: StateMachineRequest, span: Span) -> Result<(), RequestError> {
+        self.send(req, span).await
+    }
+
+    /// Sends a request to the [`StateMachine`] right away and returns a future of its response.
+    ///
+    /// Unlike [`request()`], the request is enqueued before the returned future is polled, hence
+    /// the order of the calls is the order in which the requests are processed.
+    ///
+    /// [`StateMachine`]: crate::state_machine
+    /// [`request()`]: RequestSender::request
+    pub fn send(
+        &self,
+        req: StateMachineRequest,
+        span: Span,
+    ) -> impl Future<Output = Result<(), RequestError>> + Send + Sync + 'static {
         let (resp_tx, resp_rx) = oneshot::channel::<Result<(), RequestError>>();
-        self.0.send((req, span, resp_tx)).map_err(|_| {
+        let sent = self.0.send((req, span, resp_tx)).map_err(|_| {
             RequestError::InternalError(
                 "failed to send request to the state machine: state machine is shutting down",
             )
-        })?;
-        resp_rx.await.map_err(|_| {
-            RequestError::InternalError("failed to receive response from the state machine")
-        })?
+        });
+        async move {
+            sent?;
+            resp_rx.await.map_err(|_| {
+                RequestError::InternalError("failed to receive response from the state machine")
+            })?
+        }
     }
 
     #[cfg(test)]
@@ -1,6 +1,16 @@
 use xaynet_core::{common::RoundSeed, crypto::EncryptKeyPair, mask::MaskConfig};
 
-use crate::state_machine::coordinator::CoordinatorState;
+use crate::{
+    settings::{CoordinatorMode, SeedDictMismatchPolicy},
+    state_machine::coordinator::{
+        CheckpointParameters,
+        CoordinatorState,
+        ProcessingBudget,
+        TieBreaking,
+        TrustedAggregator,
+        WarmUpParameters,
+    },
+};
 
 use super::utils::{mask_settings, model_settings, pet_settings};
 
@@ -46,6 +56,44 @@ impl CoordinatorStateBuilder {
         self
     }
 
+    pub fn with_commit_round_params(mut self, commit: bool) -> Self {
+        self.state.commit_round_params = commit;
+        self
+    }
+
+    pub fn with_phase_soft_deadline(mut self, soft_deadline: u64) -> Self {
+        self.state.phase_soft_deadline = Some(soft_deadline);
+        self
+    }
+
+    pub fn with_inter_round_delay(mut self, delay: u64) -> Self {
+        self.state.inter_round_delay = Some(delay);
+        self
+    }
+
+    pub fn with_processing_budget(mut self, requests: u64, slow_request: u64) -> Self {
+        self.state.processing_budget = Some(ProcessingBudget {
+            requests,
+            slow_request,
+        });
+        self
+    }
+
+    pub fn with_sum_dict_capacity(mut self, capacity: u64) -> Self {
+        self.state.sum_dict_capacity = Some(capacity);
+        self
+    }
+
+    pub fn with_require_multiparty(mut self, require: bool) -> Self {
+        self.state.require_multiparty = require;
+        self
+    }
+
+    pub fn with_distinct_sum_identities(mut self, distinct: bool) -> Self {
+        self.state.distinct_sum_identities = distinct;
+        self
+    }
+
     pub fn with_sum_count_min(mut self, min: u64) -> Self {
         self.state.sum.count.min = min;
         self
@@ -71,6 +119,30 @@ impl CoordinatorStateBuilder {
         self
     }
 
+    pub fn with_aggregation_memory_limit(mut self, limit: u64) -> Self {
+        self.state.aggregation_memory_limit = Some(limit);
+        self
+    }
+
+    pub fn with_aggregation_checkpoint(
+        mut self,
+        updates: Option<u64>,
+        interval: Option<u64>,
+    ) -> Self {
+        self.state.aggregation_checkpoint = Some(CheckpointParameters { updates, interval });
+        self
+    }
+
+    pub fn with_trimmed_mean(mut self, trim: u64) -> Self {
+        self.state.trimmed_mean = Some(trim);
+        self
+    }
+
+    pub fn with_seed_dict_mismatch_policy(mut self, policy: SeedDictMismatchPolicy) -> Self {
+        self.state.seed_dict_mismatch_policy = policy;
+        self
+    }
+
     pub fn with_sum2_count_min(mut self, min: u64) -> Self {
         self.state.sum2.count.min = min;
         self
@@ -115,4 +187,29 @@ impl CoordinatorStateBuilder {
         self.state.sum2.time.max = max;
         self
     }
+
+    pub fn with_warm_up(mut self, rounds: u64, sum_min: u64, update_min: u64) -> Self {
+        self.state.warm_up = Some(WarmUpParameters {
+            rounds,
+            sum_min,
+            update_min,
+        });
+        self
+    }
+
+    pub fn with_tie_breaking(mut self, tie_breaking: TieBreaking) -> Self {
+        self.state.tie_breaking = tie_breaking;
+        self
+    }
+
+    pub fn with_trusted_aggregator(mut self) -> Self {
+        self.state.round_params.sum = 0.;
+        self.state.trusted_aggregator = Some(TrustedAggregator::generate());
+        self
+    }
+
+    pub fn with_coordinator_mode(mut self, mode: CoordinatorMode) -> Self {
+        self.state.mode = mode;
+        self
+    }
 }
@@ -1,8 +1,8 @@
-use xaynet_core::{SeedDict, SumDict};
+use xaynet_core::SumDict;
 
 use crate::state_machine::{
     coordinator::CoordinatorState,
-    events::{DictionaryUpdate, EventPublisher, EventSubscriber, ModelUpdate},
+    events::{DictionaryUpdate, EventPublisher, EventSubscriber, ModelUpdate, VersionedSeedDict},
     phases::PhaseName,
 };
 
@@ -44,7 +44,7 @@ impl EventBusBuilder {
         self
     }
 
-    pub fn broadcast_seed_dict(mut self, update: DictionaryUpdate<SeedDict>) -> Self {
+    pub fn broadcast_seed_dict(mut self, update: DictionaryUpdate<VersionedSeedDict>) -> Self {
         self.event_publisher.broadcast_seed_dict(update);
         self
     }
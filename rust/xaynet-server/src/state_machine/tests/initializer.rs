@@ -6,20 +6,38 @@ use serial_test::serial;
 use crate::{
     settings::RestoreSettings,
     state_machine::{
-        events::{DictionaryUpdate, ModelUpdate},
-        initializer::StateMachineInitializationError,
+        events::ModelUpdate,
+        initializer::{StandbySnapshot, StateMachineInitializationError},
         phases::PhaseName,
     },
     storage::tests::utils::create_global_model,
     storage::ModelStorage,
 };
 use crate::{
+    settings::{PetSettingsCount, PetSettingsTime, PetSettingsUpdateCheckpoint},
     state_machine::{
         coordinator::CoordinatorState,
+        events::DictionaryUpdate,
         initializer::StateMachineInitializer,
-        tests::utils::{mask_settings, model_settings, pet_settings},
+        requests::{StateMachineRequest, Sum2Request, SumRequest, UpdateRequest},
+        tests::utils::{enable_logging, mask_settings, model_settings, pet_settings},
     },
-    storage::{tests::init_store, CoordinatorStorage},
+    storage::{
+        coordinator_storage::memory::MemoryStorage,
+        tests::{init_store, utils::create_mask, MockModelStore},
+        CoordinatorStorage,
+        LeaderLock,
+        Store,
+    },
+};
+use std::time::Duration;
+use tracing::Span;
+use xaynet_core::{
+    crypto::{ByteObject, EncryptKeyPair, Sha256, SigningKeyPair},
+    mask::MaskSeed,
+    LocalSeedDict,
+    SumDict,
+    SumParticipantPublicKey,
 };
 
 #[cfg(feature = "model-persistence")]
@@ -104,7 +122,8 @@ async fn integration_state_machine_initializer_without_global_model() {
     // if we don't update the round_id we can't check if the state in the store was used or if the state was reset
     // because in both cases the round id will be 0
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state =
+        CoordinatorState::new(pet_settings.clone(), mask_settings, model_settings.clone());
     let new_round_id = 5;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -147,7 +166,8 @@ async fn integration_state_machine_initializer_with_global_model() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state =
+        CoordinatorState::new(pet_settings.clone(), mask_settings, model_settings.clone());
     let new_round_id = 7;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -190,7 +210,7 @@ async fn integration_state_machine_initializer_with_global_model() {
 
     let global_model = event_subscriber.model_listener().get_latest().event;
     assert!(
-        matches!(global_model, ModelUpdate::New(broadcasted_model) if uploaded_global_model == *broadcasted_model)
+        matches!(global_model, ModelUpdate::New(broadcasted_model, _) if uploaded_global_model == *broadcasted_model)
     );
 
     let round_id = event_subscriber.params_listener().get_latest().round_id;
@@ -207,7 +227,8 @@ async fn integration_state_machine_initializer_failed_because_of_wrong_size() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state =
+        CoordinatorState::new(pet_settings.clone(), mask_settings, model_settings.clone());
     let new_round_id = 9;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -253,7 +274,8 @@ async fn integration_state_machine_initializer_failed_to_find_global_model() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state =
+        CoordinatorState::new(pet_settings.clone(), mask_settings, model_settings.clone());
     let new_round_id = 11;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -281,6 +303,56 @@ async fn integration_state_machine_initializer_failed_to_find_global_model() {
     ));
 }
 
+#[cfg(feature = "model-persistence")]
+#[tokio::test]
+#[serial]
+#[ignore]
+async fn integration_state_machine_initializer_with_standby_snapshot() {
+    let pet_settings = pet_settings();
+    let mask_settings = mask_settings();
+    let model_settings = model_settings();
+
+    let mut store = init_store().await;
+    let mut state =
+        CoordinatorState::new(pet_settings.clone(), mask_settings, model_settings.clone());
+    let new_round_id = 13;
+    state.round_id = new_round_id;
+    store.set_coordinator_state(&state).await.unwrap();
+
+    // set a model id without storing the model, the model has been restored on standby
+    let global_model_id = "13_412957050209fcfa733b1fb4ad51f321";
+    store
+        .set_latest_global_model_id(global_model_id)
+        .await
+        .unwrap();
+    let restored_global_model = create_global_model(state.round_params.model_length);
+    let snapshot = StandbySnapshot {
+        round_id: Some(new_round_id),
+        global_model: Some((global_model_id.to_string(), restored_global_model.clone())),
+    };
+
+    let smi = StateMachineInitializer::new(
+        pet_settings,
+        mask_settings,
+        model_settings,
+        RestoreSettings { enable: true },
+        store,
+    )
+    .with_standby_snapshot(snapshot);
+
+    let (state_machine, _request_sender, event_subscriber) = smi.init().await.unwrap();
+
+    assert!(state_machine.is_idle());
+
+    let global_model = event_subscriber.model_listener().get_latest().event;
+    assert!(
+        matches!(global_model, ModelUpdate::New(broadcasted_model, _) if restored_global_model == *broadcasted_model)
+    );
+
+    let round_id = event_subscriber.params_listener().get_latest().round_id;
+    assert_eq!(round_id, new_round_id);
+}
+
 #[tokio::test]
 #[serial]
 #[ignore]
@@ -290,7 +362,7 @@ async fn integration_state_machine_initializer_reset_state() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let state = CoordinatorState::new(pet_settings.clone(), mask_settings, model_settings.clone());
     store.set_coordinator_state(&state).await.unwrap();
 
     let mut smi = StateMachineInitializer::new(
@@ -311,3 +383,230 @@ async fn integration_state_machine_initializer_reset_state() {
     assert!(store.latest_global_model_id().await.unwrap().is_none());
     assert_eq!(store.number_of_unique_masks().await.unwrap(), 0);
 }
+
+// Elects a new leader and fences its storage.
+async fn elect_leader(storage: &MemoryStorage, address: &str) -> MemoryStorage {
+    let token = storage
+        .clone()
+        .acquire_leadership(address, Duration::from_secs(5))
+        .await
+        .unwrap()
+        .unwrap();
+    storage.clone().with_fencing_token(token)
+}
+
+fn sum_request() -> StateMachineRequest {
+    StateMachineRequest::Sum(SumRequest {
+        participant_pk: SigningKeyPair::generate().public,
+        ephm_pk: EncryptKeyPair::generate().public,
+        identity: None,
+    })
+}
+
+fn update_request(sum_dict: &SumDict, number: u32) -> StateMachineRequest {
+    StateMachineRequest::Update(UpdateRequest {
+        participant_pk: SigningKeyPair::generate().public,
+        local_seed_dict: LocalSeedDict::new(sum_dict, &MaskSeed::generate()),
+        model_checksum: Sha256::zeroed(),
+        masked_model: create_mask(1, number),
+    })
+}
+
+#[tokio::test]
+async fn test_state_machine_initializer_take_over() {
+    enable_logging();
+    let storage = MemoryStorage::new();
+    let mut pet_settings = pet_settings();
+    pet_settings.sum.count = PetSettingsCount { min: 2, max: 2 };
+    pet_settings.update.time = PetSettingsTime { min: 0, max: 60 };
+    pet_settings.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+        updates: Some(1),
+        interval: None,
+    });
+    let initializer = |store: MemoryStorage| {
+        StateMachineInitializer::new(
+            pet_settings.clone(),
+            mask_settings(),
+            model_settings(),
+            #[cfg(feature = "model-persistence")]
+            RestoreSettings { enable: false },
+            Store::new(store, MockModelStore::new()),
+        )
+    };
+
+    // the old leader starts a round and accepts the sum participants
+    let old_leader = elect_leader(&storage, "old").await;
+    let (state_machine, old_request_tx, _) = initializer(old_leader).init().await.unwrap();
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_sum());
+    let round_id = state_machine.as_ref().round_id;
+    let sum_requests = (0..2)
+        .map(|_| {
+            let request_tx = old_request_tx.clone();
+            tokio::spawn(async move { request_tx.request(sum_request(), Span::none()).await })
+        })
+        .collect::<Vec<_>>();
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_update());
+    for sum_request in sum_requests {
+        assert!(sum_request.await.unwrap().is_ok());
+    }
+
+    // the old leader accepts an update participant in the update phase
+    let old_leader_phase = tokio::spawn(state_machine.next());
+    let sum_dict = storage.clone().sum_dict().await.unwrap().unwrap();
+    let res = old_request_tx
+        .request(update_request(&sum_dict, 1), Span::none())
+        .await;
+    assert!(res.is_ok());
+
+    // the lock expires and the standby takes over the round in the update phase
+    storage.expire_leadership();
+    let new_leader = elect_leader(&storage, "new").await;
+    let (state_machine, new_request_tx, event_subscriber) =
+        initializer(new_leader).take_over().await.unwrap();
+    assert!(state_machine.is_update());
+    assert_eq!(state_machine.as_ref().round_id, round_id);
+    assert_eq!(
+        event_subscriber.params_listener().get_latest().round_id,
+        round_id
+    );
+
+    // the late update of the deposed leader is fenced
+    let res = old_request_tx
+        .request(update_request(&sum_dict, 2), Span::none())
+        .await;
+    assert!(res.is_err());
+    old_leader_phase.abort();
+    let seed_dict = storage.clone().seed_dict().await.unwrap().unwrap();
+    assert!(seed_dict
+        .values()
+        .all(|update_seeds| update_seeds.len() == 1));
+
+    // the new leader accepts the next update participant in the same round
+    let new_leader_phase = tokio::spawn(state_machine.next());
+    let res = new_request_tx
+        .request(update_request(&sum_dict, 3), Span::none())
+        .await;
+    assert!(res.is_ok());
+    let seed_dict = storage.clone().seed_dict().await.unwrap().unwrap();
+    assert!(seed_dict
+        .values()
+        .all(|update_seeds| update_seeds.len() == 2));
+    let state = storage.clone().coordinator_state().await.unwrap().unwrap();
+    assert_eq!(state.round_id, round_id);
+    new_leader_phase.abort();
+}
+
+fn sum2_request(sum_pk: SumParticipantPublicKey) -> StateMachineRequest {
+    StateMachineRequest::Sum2(Sum2Request {
+        participant_pk: sum_pk,
+        seed_dict_version: None,
+        model_mask: create_mask(1, 1),
+    })
+}
+
+#[tokio::test]
+async fn test_state_machine_initializer_take_over_sum2_with_mask_dict() {
+    enable_logging();
+    let storage = MemoryStorage::new();
+    let mut pet_settings = pet_settings();
+    pet_settings.sum.count = PetSettingsCount { min: 2, max: 2 };
+    pet_settings.update.count = PetSettingsCount { min: 2, max: 2 };
+    pet_settings.update.time = PetSettingsTime { min: 0, max: 60 };
+    pet_settings.update.checkpoint = Some(PetSettingsUpdateCheckpoint {
+        updates: Some(1),
+        interval: None,
+    });
+    pet_settings.sum2.count = PetSettingsCount { min: 2, max: 2 };
+    pet_settings.sum2.time = PetSettingsTime { min: 0, max: 60 };
+    let initializer = |store: MemoryStorage| {
+        StateMachineInitializer::new(
+            pet_settings.clone(),
+            mask_settings(),
+            model_settings(),
+            #[cfg(feature = "model-persistence")]
+            RestoreSettings { enable: false },
+            Store::new(store, MockModelStore::new()),
+        )
+    };
+
+    // the old coordinator runs the round up to the sum2 phase
+    let old_leader = elect_leader(&storage, "old").await;
+    let (state_machine, old_request_tx, _) = initializer(old_leader).init().await.unwrap();
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_sum());
+    let round_id = state_machine.as_ref().round_id;
+    let sum_requests = (0..2)
+        .map(|_| {
+            let request_tx = old_request_tx.clone();
+            tokio::spawn(async move { request_tx.request(sum_request(), Span::none()).await })
+        })
+        .collect::<Vec<_>>();
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_update());
+    for sum_request in sum_requests {
+        assert!(sum_request.await.unwrap().is_ok());
+    }
+    let sum_dict = storage.clone().sum_dict().await.unwrap().unwrap();
+    let update_requests = (1..=2)
+        .map(|number| {
+            let request_tx = old_request_tx.clone();
+            let request = update_request(&sum_dict, number);
+            tokio::spawn(async move { request_tx.request(request, Span::none()).await })
+        })
+        .collect::<Vec<_>>();
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_sum2());
+    for update_request in update_requests {
+        assert!(update_request.await.unwrap().is_ok());
+    }
+
+    // the old coordinator accepts the first mask before it shuts down and loses the masks
+    let sum_pks = sum_dict.keys().copied().collect::<Vec<_>>();
+    let old_leader_phase = tokio::spawn(state_machine.next());
+    let res = old_request_tx
+        .request(sum2_request(sum_pks[0]), Span::none())
+        .await;
+    assert!(res.is_ok());
+    old_leader_phase.abort();
+    let snapshot = storage.export_mask_dict().unwrap();
+    assert_eq!(snapshot.round_id, round_id);
+    storage.drop_mask_dict();
+    assert_eq!(storage.clone().number_of_unique_masks().await.unwrap(), 0);
+
+    // the restarted coordinator resumes the sum2 phase with the imported masks
+    storage.expire_leadership();
+    let new_leader = elect_leader(&storage, "new").await;
+    let (state_machine, new_request_tx, event_subscriber) = initializer(new_leader)
+        .with_mask_dict(snapshot)
+        .take_over()
+        .await
+        .unwrap();
+    assert!(state_machine.is_sum2());
+    assert_eq!(state_machine.as_ref().round_id, round_id);
+    match event_subscriber.seed_dict_listener().get_latest().event {
+        DictionaryUpdate::New(seed_dict) => assert_eq!(seed_dict.version, 2),
+        DictionaryUpdate::Invalidate => panic!("the seed dict is not published"),
+    }
+
+    // the imported mask is recognized and the second mask completes the phase
+    let new_leader_phase = tokio::spawn(state_machine.next());
+    let err = new_request_tx
+        .request(sum2_request(sum_pks[0]), Span::none())
+        .await
+        .unwrap_err();
+    assert!(err.is_already_recorded());
+    let res = new_request_tx
+        .request(sum2_request(sum_pks[1]), Span::none())
+        .await;
+    assert!(res.is_ok());
+    let state_machine = new_leader_phase.await.unwrap().unwrap();
+    assert!(state_machine.is_unmask());
+    assert!(storage
+        .clone()
+        .aggregation_checkpoint(round_id)
+        .await
+        .unwrap()
+        .is_none());
+}
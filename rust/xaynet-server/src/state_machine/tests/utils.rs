@@ -1,36 +1,57 @@
 //! State machine misc test utilities.
 
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 use tokio::sync::mpsc;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 use xaynet_core::{
     common::RoundParameters,
-    crypto::{ByteObject, EncryptKeyPair, PublicEncryptKey, PublicSigningKey},
-    mask::{BoundType, DataType, GroupType, MaskObject, ModelType},
+    crypto::{ByteObject, CryptoSuite, EncryptKeyPair, PublicEncryptKey, PublicSigningKey, Sha256},
+    mask::{
+        BoundType,
+        DataType,
+        GroupType,
+        MaskObject,
+        ModelDelta,
+        ModelType,
+        SealedBox,
+        SeedCipher,
+    },
     message::{Message, Sum, Sum2, Update},
     LocalSeedDict,
     ParticipantTaskSignature,
-    SeedDict,
     SumDict,
 };
 
 use crate::{
     settings::{
+        AggregationStrategy,
+        CoordinatorMode,
         MaskSettings,
+        MaskTiePolicy,
         ModelSettings,
+        PetMode,
         PetSettings,
         PetSettingsCount,
         PetSettingsSum,
         PetSettingsSum2,
         PetSettingsTime,
         PetSettingsUpdate,
+        SeedDictMismatchPolicy,
     },
     state_machine::{
         coordinator::CoordinatorState,
-        events::{DictionaryUpdate, Event, EventPublisher, EventSubscriber, ModelUpdate},
+        events::{
+            DictionaryUpdate,
+            Event,
+            EventPublisher,
+            EventSubscriber,
+            ModelUpdate,
+            VersionedSeedDict,
+        },
         phases::{PhaseName, Shared},
         requests::{RequestReceiver, RequestSender},
+        timings::RoundTimings,
     },
     storage::tests::utils::create_mask,
 };
@@ -46,20 +67,40 @@ pub fn enable_logging() {
 
 pub fn pet_settings() -> PetSettings {
     PetSettings {
+        mode: PetMode::Pet,
+        coordinator_mode: CoordinatorMode::Full,
         sum: PetSettingsSum {
             prob: 0.4,
             count: PetSettingsCount { min: 1, max: 100 },
             time: PetSettingsTime { min: 1, max: 2 },
+            dict_capacity: None,
+            require_multiparty: false,
+            distinct_identities: false,
         },
         update: PetSettingsUpdate {
             prob: 0.5,
             count: PetSettingsCount { min: 3, max: 1000 },
             time: PetSettingsTime { min: 1, max: 2 },
+            aggregation_memory_limit: None,
+            on_seed_dict_mismatch: SeedDictMismatchPolicy::Warn,
+            aggregation: AggregationStrategy::Mean,
+            trim: 0,
+            allow_sum_eligible_updates: false,
+            checkpoint: None,
         },
         sum2: PetSettingsSum2 {
             count: PetSettingsCount { min: 1, max: 100 },
             time: PetSettingsTime { min: 1, max: 2 },
+            on_tie: MaskTiePolicy::Fail,
+            majority_fraction: 0.5,
         },
+        warm_up: None,
+        commit_round_params: false,
+        phase_soft_deadline: None,
+        inter_round_delay: None,
+        processing_budget: None,
+        eligibility_salt: None,
+        crypto_suite: CryptoSuite::SHA256,
     }
 }
 
@@ -69,6 +110,7 @@ pub fn mask_settings() -> MaskSettings {
         data_type: DataType::F32,
         bound_type: BoundType::B0,
         model_type: ModelType::M3,
+        round_bound_masks: false,
     }
 }
 
@@ -94,8 +136,9 @@ pub struct EventSnapshot {
     pub params: Event<RoundParameters>,
     pub phase: Event<PhaseName>,
     pub model: Event<ModelUpdate>,
+    pub model_delta: Event<Option<Arc<ModelDelta>>>,
     pub sum_dict: Event<DictionaryUpdate<SumDict>>,
-    pub seed_dict: Event<DictionaryUpdate<SeedDict>>,
+    pub seed_dict: Event<DictionaryUpdate<VersionedSeedDict>>,
 }
 
 impl From<&EventSubscriber> for EventSnapshot {
@@ -105,12 +148,27 @@ impl From<&EventSubscriber> for EventSnapshot {
             params: event_subscriber.params_listener().get_latest(),
             phase: event_subscriber.phase_listener().get_latest(),
             model: event_subscriber.model_listener().get_latest(),
+            model_delta: event_subscriber.model_delta_listener().get_latest(),
             sum_dict: event_subscriber.sum_dict_listener().get_latest(),
             seed_dict: event_subscriber.seed_dict_listener().get_latest(),
         }
     }
 }
 
+/// Asserts that the coordinator states are equal apart from the timings of the phase
+/// transitions and the rejection stats, which are recorded by every phase.
+pub fn assert_state_eq_except_round_stats(state1: &CoordinatorState, state2: &CoordinatorState) {
+    let without_round_stats = |state: &CoordinatorState| CoordinatorState {
+        timings: RoundTimings::default(),
+        last_timings: None,
+        rejections: HashMap::new(),
+        broken_tie: None,
+        seed_dict_mismatch: None,
+        ..state.clone()
+    };
+    assert_eq!(without_round_stats(state1), without_round_stats(state2));
+}
+
 pub fn assert_event_updated_with_id<T: Debug + PartialEq>(event1: &Event<T>, event2: &Event<T>) {
     assert_ne!(event1.round_id, event2.round_id);
     assert_ne!(event1.event, event2.event);
@@ -137,8 +195,10 @@ pub fn compose_update_message(masked_model: MaskObject) -> Message {
     let payload = Update {
         sum_signature: ParticipantTaskSignature::zeroed(),
         update_signature: ParticipantTaskSignature::zeroed(),
+        model_checksum: Sha256::zeroed(),
+        seed_cipher: SealedBox::ID,
         masked_model,
-        local_seed_dict: LocalSeedDict::new(),
+        local_seed_dict: LocalSeedDict::default(),
     };
     Message::new_update(
         PublicSigningKey::zeroed(),
@@ -148,8 +208,13 @@ pub fn compose_update_message(masked_model: MaskObject) -> Message {
 }
 
 pub fn compose_sum2_message() -> Message {
+    compose_sum2_message_with_seed_dict_version(Some(0))
+}
+
+pub fn compose_sum2_message_with_seed_dict_version(seed_dict_version: Option<u64>) -> Message {
     let payload = Sum2 {
         sum_signature: ParticipantTaskSignature::zeroed(),
+        seed_dict_version,
         model_mask: create_mask(1, 1),
     };
     Message::new_sum2(
@@ -239,20 +304,40 @@ impl Latch {
 #[test]
 fn test_initial_settings() {
     let pet = PetSettings {
+        mode: PetMode::Pet,
+        coordinator_mode: CoordinatorMode::Full,
         sum: PetSettingsSum {
             prob: 0.4,
             count: PetSettingsCount { min: 1, max: 100 },
             time: PetSettingsTime { min: 1, max: 2 },
+            dict_capacity: None,
+            require_multiparty: false,
+            distinct_identities: false,
         },
         update: PetSettingsUpdate {
             prob: 0.5,
             count: PetSettingsCount { min: 3, max: 1000 },
             time: PetSettingsTime { min: 1, max: 2 },
+            aggregation_memory_limit: None,
+            on_seed_dict_mismatch: SeedDictMismatchPolicy::Warn,
+            aggregation: AggregationStrategy::Mean,
+            trim: 0,
+            allow_sum_eligible_updates: false,
+            checkpoint: None,
         },
         sum2: PetSettingsSum2 {
             count: PetSettingsCount { min: 1, max: 100 },
             time: PetSettingsTime { min: 1, max: 2 },
+            on_tie: MaskTiePolicy::Fail,
+            majority_fraction: 0.5,
         },
+        warm_up: None,
+        commit_round_params: false,
+        phase_soft_deadline: None,
+        inter_round_delay: None,
+        processing_budget: None,
+        eligibility_salt: None,
+        crypto_suite: CryptoSuite::SHA256,
     };
 
     assert_eq!(
@@ -267,6 +352,7 @@ fn test_initial_settings() {
         data_type: DataType::F32,
         bound_type: BoundType::B0,
         model_type: ModelType::M3,
+        round_bound_masks: false,
     };
 
     assert_eq!(
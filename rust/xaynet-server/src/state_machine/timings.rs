@@ -0,0 +1,197 @@
+//! Timestamps of the phase transitions of the state machine.
+//!
+//! The [`StateMachine`] records when it enters and leaves each phase of a round, which allows to
+//! track how long a round and its phases take. See [`RoundTimings`] for details.
+//!
+//! [`StateMachine`]: crate::state_machine::StateMachine
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::state_machine::phases::PhaseName;
+
+/// A source of the current time.
+///
+/// The clock follows the system time, unless it is [mocked] in tests.
+///
+/// [mocked]: Clock::mocked
+#[derive(Clone, Default)]
+pub struct Clock {
+    mocked: Option<Arc<Mutex<SystemTime>>>,
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("mocked", &self.mocked.is_some())
+            .finish()
+    }
+}
+
+impl Clock {
+    /// Gets the current time.
+    pub fn now(&self) -> SystemTime {
+        match self.mocked {
+            // safe unwrap: the lock is never held while panicking
+            Some(ref now) => *now.lock().unwrap(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// Creates a clock which stands still at `now` until it is [advanced].
+    ///
+    /// [advanced]: Clock::advance
+    #[cfg(test)]
+    pub fn mocked(now: SystemTime) -> Self {
+        Self {
+            mocked: Some(Arc::new(Mutex::new(now))),
+        }
+    }
+
+    /// Advances a mocked clock by the given `duration`.
+    ///
+    /// # Panics
+    /// Panics if the clock is not mocked.
+    #[cfg(test)]
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.mocked.as_ref().unwrap().lock().unwrap();
+        *now += duration;
+    }
+}
+
+/// Converts a point in time to milliseconds since the unix epoch.
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// The timestamps of a phase.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    /// The name of the phase.
+    pub phase: PhaseName,
+    /// When the phase was entered, in milliseconds since the unix epoch.
+    pub entered_at: u64,
+    /// When the phase was left, in milliseconds since the unix epoch, or `None` if the phase
+    /// is still running.
+    pub left_at: Option<u64>,
+    /// Whether the phase exceeded the soft deadline.
+    pub soft_deadline_exceeded: bool,
+}
+
+impl PhaseTiming {
+    /// Gets the duration of the phase, or `None` if the phase is still running.
+    pub fn duration(&self) -> Option<Duration> {
+        self.left_at
+            .map(|left_at| Duration::from_millis(left_at.saturating_sub(self.entered_at)))
+    }
+}
+
+/// The timestamps of the phases of a round, in the order in which they were entered.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundTimings {
+    /// The id of the round.
+    pub round_id: u64,
+    /// The timestamps of the phases.
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl RoundTimings {
+    /// Creates empty timings for the round with the given id.
+    pub fn new(round_id: u64) -> Self {
+        Self {
+            round_id,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Records that the `phase` was entered at the given time.
+    pub fn enter(&mut self, phase: PhaseName, now: SystemTime) {
+        self.phases.push(PhaseTiming {
+            phase,
+            entered_at: unix_millis(now),
+            left_at: None,
+            soft_deadline_exceeded: false,
+        });
+    }
+
+    /// Records that the current phase was left at the given time.
+    ///
+    /// Returns the timing of the left phase or `None` if no phase is running. The phase is
+    /// flagged if it took longer than the `soft_deadline`.
+    pub fn leave(
+        &mut self,
+        now: SystemTime,
+        soft_deadline: Option<Duration>,
+    ) -> Option<&PhaseTiming> {
+        let timing = self
+            .phases
+            .last_mut()
+            .filter(|timing| timing.left_at.is_none())?;
+        timing.left_at = Some(unix_millis(now).max(timing.entered_at));
+        // safe unwrap: `left_at` has just been set
+        let duration = timing.duration().unwrap();
+        timing.soft_deadline_exceeded =
+            matches!(soft_deadline, Some(deadline) if duration > deadline);
+        Some(timing)
+    }
+
+    /// Gets the duration of the round from entering the first phase until leaving the last phase,
+    /// or `None` if the last phase is still running.
+    pub fn duration(&self) -> Option<Duration> {
+        let first = self.phases.first()?;
+        let left_at = self.phases.last()?.left_at?;
+        Some(Duration::from_millis(
+            left_at.saturating_sub(first.entered_at),
+        ))
+    }
+
+    /// Gets the phase which is still running, or `None` if the last phase has been left.
+    pub fn running_phase(&self) -> Option<PhaseName> {
+        self.phases
+            .last()
+            .filter(|timing| timing.left_at.is_none())
+            .map(|timing| timing.phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_timings() {
+        let clock = Clock::mocked(UNIX_EPOCH + Duration::from_secs(1_000));
+        let mut timings = RoundTimings::new(1);
+        assert!(timings.leave(clock.now(), None).is_none());
+        assert!(timings.duration().is_none());
+        assert!(timings.running_phase().is_none());
+
+        timings.enter(PhaseName::Idle, clock.now());
+        assert_eq!(timings.running_phase(), Some(PhaseName::Idle));
+        clock.advance(Duration::from_millis(1_500));
+        let idle = timings.leave(clock.now(), None).unwrap();
+        assert_eq!(idle.entered_at, 1_000_000);
+        assert_eq!(idle.left_at, Some(1_001_500));
+        assert_eq!(idle.duration(), Some(Duration::from_millis(1_500)));
+        assert!(!idle.soft_deadline_exceeded);
+        // the phase has already been left
+        assert!(timings.leave(clock.now(), None).is_none());
+        assert!(timings.running_phase().is_none());
+
+        timings.enter(PhaseName::Sum, clock.now());
+        assert!(timings.duration().is_none());
+        clock.advance(Duration::from_secs(60));
+        let sum = timings
+            .leave(clock.now(), Some(Duration::from_secs(30)))
+            .unwrap();
+        assert!(sum.soft_deadline_exceeded);
+        assert_eq!(timings.duration(), Some(Duration::from_millis(61_500)));
+    }
+}
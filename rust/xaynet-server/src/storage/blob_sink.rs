@@ -0,0 +1,91 @@
+//! Sinks for blobs which are served to the participants out-of-band, i.e. independently of the
+//! REST API of the coordinator.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use tokio::fs;
+
+use crate::{settings::SeedExportSettings, storage::StorageResult};
+
+#[async_trait]
+/// An abstract sink for blobs.
+pub trait BlobSink
+where
+    Self: Clone + Send + Sync + 'static,
+{
+    /// Writes the `data` as the blob `name` and returns the location of the blob.
+    ///
+    /// # Behavior
+    ///
+    /// - The `name` is a relative path of `/` separated segments, which consist of URL-safe
+    ///   characters only.
+    /// - If the blob already exists, override it.
+    async fn put(&self, name: &str, data: Vec<u8>) -> StorageResult<String>;
+}
+
+/// A [`BlobSink`] which writes the blobs as files into a directory.
+#[derive(Clone, Debug)]
+pub struct FsBlobSink {
+    /// The directory of the blobs.
+    path: PathBuf,
+    /// The location under which the directory is available to the participants.
+    base_location: String,
+}
+
+impl FsBlobSink {
+    /// Creates a sink which writes the blobs into the directory `path`.
+    ///
+    /// The location of a blob is its name appended to the `base_location`.
+    pub fn new(path: impl Into<PathBuf>, base_location: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            base_location: base_location.into(),
+        }
+    }
+}
+
+impl From<SeedExportSettings> for FsBlobSink {
+    fn from(settings: SeedExportSettings) -> Self {
+        Self::new(settings.path, settings.base_location)
+    }
+}
+
+#[async_trait]
+impl BlobSink for FsBlobSink {
+    async fn put(&self, name: &str, data: Vec<u8>) -> StorageResult<String> {
+        let path = self.path.join(name);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(&path, data).await?;
+        Ok(format!(
+            "{}/{}",
+            self.base_location.trim_end_matches('/'),
+            name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    #[tokio::test]
+    async fn test_fs_blob_sink() {
+        let path = env::temp_dir().join(format!("xaynet_blob_sink_{}", std::process::id()));
+        let sink = FsBlobSink::new(&path, "https://blobs.example.com/");
+
+        let location = sink.put("7/blob", vec![1, 2, 3]).await.unwrap();
+        assert_eq!(location, "https://blobs.example.com/7/blob");
+        assert_eq!(fs::read(path.join("7/blob")).await.unwrap(), vec![1, 2, 3]);
+
+        // an existing blob is overridden
+        sink.put("7/blob", vec![4]).await.unwrap();
+        assert_eq!(fs::read(path.join("7/blob")).await.unwrap(), vec![4]);
+
+        fs::remove_dir_all(&path).await.unwrap();
+    }
+}
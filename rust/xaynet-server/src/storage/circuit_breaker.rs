@@ -0,0 +1,654 @@
+//! A circuit breaker for the coordinator storage.
+//!
+//! See [`CircuitBreaker`] for more details.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use displaydoc::Display;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::{
+    metric,
+    metrics::Measurement,
+    settings::CircuitBreakerSettings,
+    state_machine::{coordinator::CoordinatorState, timings::Clock},
+    storage::{
+        coordinator_storage::redis::RedisError,
+        AggregationCheckpoint,
+        CoordinatorStorage,
+        LocalSeedDictAdd,
+        MaskScoreIncr,
+        StorageError,
+        StorageResult,
+        SumPartAdd,
+    },
+};
+use xaynet_core::{
+    common::SubmissionStatus,
+    crypto::Sha256,
+    mask::MaskObject,
+    LocalSeedDict,
+    ParticipantPublicKey,
+    SeedDict,
+    SumDict,
+    SumParticipantEphemeralPublicKey,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+};
+
+#[derive(Debug, Display, Error)]
+/// Transient storage error of a backend which isn't classified by `is_transient()`: {0}.
+pub struct TransientError(pub String);
+
+#[derive(Debug, Display, Error)]
+/// The storage is unavailable: the circuit breaker is open.
+pub struct CircuitOpen;
+
+/// Checks whether a storage error is transient, i.e. whether the operation may succeed if it is
+/// retried.
+///
+/// Redis errors are transient if the connection failed, dropped or timed out or if the server is
+/// temporarily unable to serve requests. Errors of other backends are transient if they are a
+/// [`TransientError`].
+pub fn is_transient(error: &StorageError) -> bool {
+    if error.is::<TransientError>() {
+        return true;
+    }
+    match error.downcast_ref::<RedisError>() {
+        Some(error) => {
+            error.is_io_error()
+                || error.is_timeout()
+                || error.is_connection_dropped()
+                || error.is_connection_refusal()
+                || matches!(
+                    error.kind(),
+                    redis::ErrorKind::BusyLoadingError
+                        | redis::ErrorKind::TryAgain
+                        | redis::ErrorKind::ClusterDown
+                        | redis::ErrorKind::MasterDown
+                )
+        }
+        None => false,
+    }
+}
+
+/// Checks whether a storage operation may succeed if the request is repeated later on.
+///
+/// This is the case for transient errors and for operations which are refused because the
+/// circuit breaker is open.
+pub fn is_retryable(error: &StorageError) -> bool {
+    error.is::<CircuitOpen>() || is_transient(error)
+}
+
+/// The state of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// Operations are passed to the storage.
+    Closed,
+    /// Operations are refused since the breaker (re)opened.
+    Open { since: SystemTime },
+    /// A single probe operation is passed to the storage, while other operations are refused.
+    HalfOpen,
+}
+
+/// The shared state of a circuit breaker.
+#[derive(Debug)]
+struct Breaker {
+    /// The state of the breaker.
+    state: State,
+    /// The outcomes of the operations within the window, where `true` marks a transient error.
+    outcomes: VecDeque<(SystemTime, bool)>,
+    /// The time at which the breaker opened after it was closed.
+    opened: SystemTime,
+}
+
+/// A [`CoordinatorStorage`] wrapper which retries transient errors and stops calling the storage
+/// while it is unavailable.
+///
+/// Each operation is retried with an exponential backoff if it fails with a transient error (see
+/// [`is_transient()`]). If the rate of transient errors within a sliding window exceeds the
+/// configured threshold, the breaker opens and operations fail with a [`CircuitOpen`] error right
+/// away. Once the breaker has been open for a while, the next operation is let through as a
+/// probe: if it succeeds the breaker closes again, otherwise it stays open.
+///
+/// Operations are retried as a whole, hence a non-idempotent operation whose reply was lost may
+/// report a protocol error on retry, e.g. an already submitted local seed dict.
+///
+/// The state of the breaker is shared between all clones of the wrapper.
+#[derive(Clone)]
+pub struct CircuitBreaker<C> {
+    inner: C,
+    settings: CircuitBreakerSettings,
+    breaker: Arc<Mutex<Breaker>>,
+    clock: Clock,
+}
+
+impl<C> CircuitBreaker<C> {
+    /// Wraps the coordinator storage `inner` in a circuit breaker.
+    pub fn new(inner: C, settings: CircuitBreakerSettings) -> Self {
+        Self {
+            inner,
+            settings,
+            breaker: Arc::new(Mutex::new(Breaker {
+                state: State::Closed,
+                outcomes: VecDeque::new(),
+                opened: SystemTime::UNIX_EPOCH,
+            })),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Sets the clock of the breaker.
+    #[cfg(test)]
+    fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Checks whether the breaker lets an operation through.
+    fn permit(&self) -> StorageResult<()> {
+        let now = self.clock.now();
+        // safe unwrap: the lock is never held while panicking
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.state {
+            State::Closed => Ok(()),
+            State::Open { since } if self.elapsed(since, now) >= self.open_duration() => {
+                debug!("letting a probe through the open circuit breaker");
+                breaker.state = State::HalfOpen;
+                Ok(())
+            }
+            State::Open { .. } | State::HalfOpen => Err(CircuitOpen.into()),
+        }
+    }
+
+    /// Records the outcome of an operation, which may open or close the breaker.
+    fn record(&self, transient_error: bool) {
+        let now = self.clock.now();
+        // safe unwrap: the lock is never held while panicking
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.state {
+            State::Closed => {
+                breaker.outcomes.push_back((now, transient_error));
+                let window = Duration::from_secs(self.settings.window);
+                while let Some((time, _)) = breaker.outcomes.front() {
+                    if self.elapsed(*time, now) > window {
+                        breaker.outcomes.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+
+                let operations = breaker.outcomes.len();
+                let errors = breaker.outcomes.iter().filter(|(_, error)| *error).count();
+                if operations >= self.settings.min_operations as usize
+                    && errors as f64 > self.settings.error_rate * operations as f64
+                {
+                    warn!(
+                        "opening the storage circuit breaker: {} of {} operations failed",
+                        errors, operations,
+                    );
+                    metric!(Measurement::StorageCircuitOpened, 1);
+                    breaker.state = State::Open { since: now };
+                    breaker.opened = now;
+                    breaker.outcomes.clear();
+                }
+            }
+            State::HalfOpen if transient_error => {
+                warn!("the probe of the storage circuit breaker failed");
+                breaker.state = State::Open { since: now };
+            }
+            State::HalfOpen => {
+                let duration = self.elapsed(breaker.opened, now);
+                info!("closing the storage circuit breaker after {:?}", duration);
+                metric!(
+                    Measurement::StorageCircuitOpenDuration,
+                    duration.as_millis() as u64,
+                );
+                breaker.state = State::Closed;
+            }
+            // the outcome of an operation which was let through before the breaker opened
+            State::Open { .. } => {}
+        }
+    }
+
+    /// Gets the backoff before the given retry.
+    fn backoff(&self, retry: u32) -> Duration {
+        Duration::from_millis(self.settings.backoff.saturating_mul(1 << (retry - 1).min(16)))
+    }
+
+    fn open_duration(&self) -> Duration {
+        Duration::from_secs(self.settings.open_duration)
+    }
+
+    fn elapsed(&self, earlier: SystemTime, now: SystemTime) -> Duration {
+        now.duration_since(earlier).unwrap_or_default()
+    }
+}
+
+/// Calls a storage operation of the inner storage guarded by the circuit breaker.
+macro_rules! guarded {
+    ($self: ident, $name: literal, $call: expr $(,)?) => {{
+        let mut retry = 0;
+        loop {
+            $self.permit()?;
+            match $call.await {
+                Ok(value) => {
+                    $self.record(false);
+                    break Ok(value);
+                }
+                Err(error) if is_transient(&error) => {
+                    $self.record(true);
+                    if retry >= $self.settings.retries {
+                        warn!("storage operation {} failed: {}", $name, error);
+                        break Err(error);
+                    }
+                    retry += 1;
+                    debug!(
+                        "retrying storage operation {} ({}/{}): {}",
+                        $name, retry, $self.settings.retries, error,
+                    );
+                    metric!(Measurement::StorageRetry, 1, ("operation", $name));
+                    tokio::time::sleep($self.backoff(retry)).await;
+                }
+                Err(error) => {
+                    $self.record(false);
+                    break Err(error);
+                }
+            }
+        }
+    }};
+}
+
+#[async_trait]
+impl<C> CoordinatorStorage for CircuitBreaker<C>
+where
+    C: CoordinatorStorage,
+{
+    async fn set_coordinator_state(&mut self, state: &CoordinatorState) -> StorageResult<()> {
+        guarded!(
+            self,
+            "set_coordinator_state",
+            self.inner.set_coordinator_state(state),
+        )
+    }
+
+    async fn coordinator_state(&mut self) -> StorageResult<Option<CoordinatorState>> {
+        guarded!(self, "coordinator_state", self.inner.coordinator_state())
+    }
+
+    async fn add_sum_participant(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+        ephm_pk: &SumParticipantEphemeralPublicKey,
+    ) -> StorageResult<SumPartAdd> {
+        guarded!(
+            self,
+            "add_sum_participant",
+            self.inner.add_sum_participant(pk, ephm_pk),
+        )
+    }
+
+    async fn sum_dict(&mut self) -> StorageResult<Option<SumDict>> {
+        guarded!(self, "sum_dict", self.inner.sum_dict())
+    }
+
+    async fn evict_sum_participants(
+        &mut self,
+        capacity: u64,
+    ) -> StorageResult<Vec<SumParticipantPublicKey>> {
+        guarded!(
+            self,
+            "evict_sum_participants",
+            self.inner.evict_sum_participants(capacity),
+        )
+    }
+
+    async fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> StorageResult<LocalSeedDictAdd> {
+        guarded!(
+            self,
+            "add_local_seed_dict",
+            self.inner.add_local_seed_dict(update_pk, local_seed_dict),
+        )
+    }
+
+    async fn seed_dict(&mut self) -> StorageResult<Option<SeedDict>> {
+        guarded!(self, "seed_dict", self.inner.seed_dict())
+    }
+
+    async fn number_of_unique_update_participants(&mut self) -> StorageResult<u64> {
+        guarded!(
+            self,
+            "number_of_unique_update_participants",
+            self.inner.number_of_unique_update_participants(),
+        )
+    }
+
+    async fn add_collected_masked_model(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        masked_model: &MaskObject,
+    ) -> StorageResult<()> {
+        guarded!(
+            self,
+            "add_collected_masked_model",
+            self.inner
+                .add_collected_masked_model(round_id, update_pk, masked_model),
+        )
+    }
+
+    async fn add_model_checksum(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        model_checksum: &Sha256,
+    ) -> StorageResult<()> {
+        guarded!(
+            self,
+            "add_model_checksum",
+            self.inner
+                .add_model_checksum(round_id, update_pk, model_checksum),
+        )
+    }
+
+    async fn set_collected_seed_dict(
+        &mut self,
+        round_id: u64,
+        seed_dict: &SeedDict,
+    ) -> StorageResult<()> {
+        guarded!(
+            self,
+            "set_collected_seed_dict",
+            self.inner.set_collected_seed_dict(round_id, seed_dict),
+        )
+    }
+
+    async fn incr_mask_score(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+        mask: &MaskObject,
+    ) -> StorageResult<MaskScoreIncr> {
+        guarded!(self, "incr_mask_score", self.inner.incr_mask_score(pk, mask))
+    }
+
+    async fn best_masks(&mut self) -> StorageResult<Option<Vec<(MaskObject, u64)>>> {
+        guarded!(self, "best_masks", self.inner.best_masks())
+    }
+
+    async fn number_of_unique_masks(&mut self) -> StorageResult<u64> {
+        guarded!(
+            self,
+            "number_of_unique_masks",
+            self.inner.number_of_unique_masks(),
+        )
+    }
+
+    async fn submission_status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<SubmissionStatus> {
+        guarded!(self, "submission_status", self.inner.submission_status(pk))
+    }
+
+    async fn set_aggregation_checkpoint(
+        &mut self,
+        checkpoint: &AggregationCheckpoint,
+    ) -> StorageResult<()> {
+        guarded!(
+            self,
+            "set_aggregation_checkpoint",
+            self.inner.set_aggregation_checkpoint(checkpoint),
+        )
+    }
+
+    async fn aggregation_checkpoint(
+        &mut self,
+        round_id: u64,
+    ) -> StorageResult<Option<AggregationCheckpoint>> {
+        guarded!(
+            self,
+            "aggregation_checkpoint",
+            self.inner.aggregation_checkpoint(round_id),
+        )
+    }
+
+    async fn delete_aggregation_checkpoint(&mut self) -> StorageResult<()> {
+        guarded!(
+            self,
+            "delete_aggregation_checkpoint",
+            self.inner.delete_aggregation_checkpoint(),
+        )
+    }
+
+    async fn retain_update_participants(
+        &mut self,
+        update_pks: &[UpdateParticipantPublicKey],
+    ) -> StorageResult<Vec<UpdateParticipantPublicKey>> {
+        guarded!(
+            self,
+            "retain_update_participants",
+            self.inner.retain_update_participants(update_pks),
+        )
+    }
+
+    async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
+        guarded!(
+            self,
+            "delete_coordinator_data",
+            self.inner.delete_coordinator_data(),
+        )
+    }
+
+    async fn delete_dicts(&mut self) -> StorageResult<()> {
+        guarded!(self, "delete_dicts", self.inner.delete_dicts())
+    }
+
+    async fn add_denied_participant(&mut self, pk: &ParticipantPublicKey) -> StorageResult<bool> {
+        guarded!(
+            self,
+            "add_denied_participant",
+            self.inner.add_denied_participant(pk),
+        )
+    }
+
+    async fn remove_denied_participant(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<bool> {
+        guarded!(
+            self,
+            "remove_denied_participant",
+            self.inner.remove_denied_participant(pk),
+        )
+    }
+
+    async fn denied_participants(&mut self) -> StorageResult<Vec<ParticipantPublicKey>> {
+        guarded!(
+            self,
+            "denied_participants",
+            self.inner.denied_participants(),
+        )
+    }
+
+    async fn set_latest_global_model_id(&mut self, id: &str) -> StorageResult<()> {
+        guarded!(
+            self,
+            "set_latest_global_model_id",
+            self.inner.set_latest_global_model_id(id),
+        )
+    }
+
+    async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>> {
+        guarded!(
+            self,
+            "latest_global_model_id",
+            self.inner.latest_global_model_id(),
+        )
+    }
+
+    /// Checks the readiness of the inner storage regardless of the breaker, which reports the
+    /// actual availability of the storage.
+    async fn is_ready(&mut self) -> StorageResult<()> {
+        self.inner.is_ready().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::UNIX_EPOCH;
+
+    use anyhow::anyhow;
+    use mockall::Sequence;
+
+    use crate::storage::{tests::MockCoordinatorStore, LocalSeedDictAddError};
+    use xaynet_core::crypto::ByteObject;
+
+    fn settings() -> CircuitBreakerSettings {
+        CircuitBreakerSettings {
+            retries: 2,
+            backoff: 0,
+            window: 10,
+            min_operations: 4,
+            error_rate: 0.5,
+            open_duration: 5,
+        }
+    }
+
+    fn transient() -> StorageError {
+        TransientError("connection dropped".to_string()).into()
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&transient()));
+        let io_error = RedisError::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+        assert!(is_transient(&anyhow!(io_error)));
+        let type_error = RedisError::from((redis::ErrorKind::TypeError, "invalid"));
+        assert!(!is_transient(&anyhow!(type_error)));
+        assert!(!is_transient(&anyhow!("permanent")));
+
+        assert!(is_retryable(&CircuitOpen.into()));
+        assert!(!is_retryable(&anyhow!("permanent")));
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        // a blip within the retry budget doesn't reject the local seed dict
+        let mut cs = MockCoordinatorStore::new();
+        let mut seq = Sequence::new();
+        cs.expect_add_local_seed_dict()
+            .times(2)
+            .in_sequence(&mut seq)
+            .returning(|_, _| Err(transient()));
+        cs.expect_add_local_seed_dict()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _| Ok(LocalSeedDictAdd(Ok(()))));
+
+        let mut store = CircuitBreaker::new(cs, settings());
+        let res = store
+            .add_local_seed_dict(&ParticipantPublicKey::zeroed(), &LocalSeedDict::default())
+            .await;
+        assert!(res.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_exceeded() {
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts()
+            .times(3)
+            .returning(|| Err(transient()));
+
+        let mut store = CircuitBreaker::new(
+            cs,
+            CircuitBreakerSettings {
+                min_operations: 10,
+                ..settings()
+            },
+        );
+        let err = store.delete_dicts().await.unwrap_err();
+        assert!(is_transient(&err));
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_of_permanent_errors() {
+        let mut cs = MockCoordinatorStore::new();
+        cs.expect_delete_dicts()
+            .times(1)
+            .returning(|| Err(anyhow!("permanent")));
+        cs.expect_add_local_seed_dict()
+            .times(1)
+            .returning(|_, _| Ok(LocalSeedDictAdd(Err(LocalSeedDictAddError::LengthMisMatch))));
+
+        let mut store = CircuitBreaker::new(cs, settings());
+        let err = store.delete_dicts().await.unwrap_err();
+        assert!(!is_retryable(&err));
+        let res = store
+            .add_local_seed_dict(&ParticipantPublicKey::zeroed(), &LocalSeedDict::default())
+            .await;
+        assert!(res.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_closes() {
+        let clock = Clock::mocked(UNIX_EPOCH);
+        let mut cs = MockCoordinatorStore::new();
+        let mut seq = Sequence::new();
+        // a success and 3 transient errors within the window open the breaker
+        cs.expect_sum_dict()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(None));
+        cs.expect_delete_dicts()
+            .times(3)
+            .in_sequence(&mut seq)
+            .returning(|| Err(transient()));
+        // the first probe fails, the second one succeeds
+        cs.expect_sum_dict()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Err(transient()));
+        cs.expect_sum_dict()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(None));
+        cs.expect_sum_dict()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(None));
+
+        let mut store = CircuitBreaker::new(cs, settings()).with_clock(clock.clone());
+        assert!(store.sum_dict().await.is_ok());
+        assert!(is_transient(&store.delete_dicts().await.unwrap_err()));
+
+        // the breaker is open and the storage isn't called
+        let err = store.sum_dict().await.unwrap_err();
+        assert!(err.is::<CircuitOpen>());
+        assert!(is_retryable(&err));
+        clock.advance(Duration::from_secs(4));
+        assert!(store.sum_dict().await.unwrap_err().is::<CircuitOpen>());
+
+        // the failed probe reopens the breaker, which refuses the retry
+        clock.advance(Duration::from_secs(1));
+        assert!(store.sum_dict().await.unwrap_err().is::<CircuitOpen>());
+        clock.advance(Duration::from_secs(4));
+        assert!(store.sum_dict().await.unwrap_err().is::<CircuitOpen>());
+
+        // the successful probe closes the breaker
+        clock.advance(Duration::from_secs(1));
+        assert!(store.sum_dict().await.is_ok());
+        assert!(store.sum_dict().await.is_ok());
+    }
+}
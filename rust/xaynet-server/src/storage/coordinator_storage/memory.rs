@@ -0,0 +1,964 @@
+//! An in-memory [`CoordinatorStorage`] backend.
+//!
+//! The data is kept in the memory of the coordinator process and is lost when the process stops,
+//! hence the backend is meant for tests and for coordinators which don't need to be restored.
+//! Its behavior mirrors the [Redis backend], including the checks of the PET protocol.
+//!
+//! [Redis backend]: crate::storage::coordinator_storage::redis
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    state_machine::coordinator::CoordinatorState,
+    storage::{
+        leader::{Deposed, FencingToken, LeaderLock},
+        AggregationCheckpoint,
+        CoordinatorStorage,
+        LocalSeedDictAdd,
+        LocalSeedDictAddError,
+        MaskScoreIncr,
+        MaskScoreIncrError,
+        StorageResult,
+        SumPartAdd,
+        SumPartAddError,
+    },
+};
+use xaynet_core::{
+    common::SubmissionStatus,
+    crypto::Sha256,
+    mask::{EncryptedMaskSeed, MaskObject},
+    LocalSeedDict,
+    ParticipantPublicKey,
+    SeedDict,
+    SumDict,
+    SumParticipantEphemeralPublicKey,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+    UpdateSeedDict,
+};
+
+/// The data of the in-memory backend.
+#[derive(Default)]
+struct Data {
+    coordinator_state: Option<CoordinatorState>,
+    sum_dict: SumDict,
+    /// The sum participants in the order in which they were added, the oldest first.
+    sum_participants: VecDeque<SumParticipantPublicKey>,
+    /// The sum participants which have been evicted from the bounded sum dict.
+    evicted_sum_participants: HashSet<SumParticipantPublicKey>,
+    update_participants: HashSet<UpdateParticipantPublicKey>,
+    /// The columns of the seed dict, one per sum participant.
+    seeds: HashMap<SumParticipantPublicKey, HashMap<UpdateParticipantPublicKey, EncryptedMaskSeed>>,
+    /// The masks submitted by the sum participants.
+    mask_submitted: HashMap<SumParticipantPublicKey, MaskObject>,
+    /// The scores of the submitted masks.
+    mask_dict: HashMap<MaskObject, u64>,
+    aggregation_checkpoint: Option<AggregationCheckpoint>,
+    collected_masked_models: HashMap<u64, HashMap<UpdateParticipantPublicKey, MaskObject>>,
+    collected_seed_dicts: HashMap<u64, SeedDict>,
+    model_checksums: HashMap<u64, HashMap<UpdateParticipantPublicKey, Sha256>>,
+    denylist: HashSet<ParticipantPublicKey>,
+    latest_global_model_id: Option<String>,
+    /// The address of the leader, its fencing token and the expiry of the leader lock, if it is
+    /// held.
+    leader: Option<(String, FencingToken, Instant)>,
+    /// The latest fencing token.
+    latest_fencing_token: u64,
+    /// The number of writes until the injected crash, if any.
+    #[cfg(test)]
+    writes_until_crash: Option<usize>,
+    /// Whether the injected crash has happened.
+    #[cfg(test)]
+    crashed: bool,
+}
+
+impl Data {
+    /// Checks whether the backend is still available.
+    fn read(&self) -> StorageResult<()> {
+        #[cfg(test)]
+        if self.crashed {
+            return Err(anyhow!("the in-memory storage crashed"));
+        }
+        Ok(())
+    }
+
+    /// Checks whether the backend is still available, whether the `fencing` token of the writer
+    /// is still the latest one and counts down to the injected crash.
+    ///
+    /// A write which fails has no effects, i.e. a crash happens in between two writes.
+    fn write(&mut self, fencing: Option<FencingToken>) -> StorageResult<()> {
+        self.read()?;
+        if let Some(token) = fencing {
+            if token.0 != self.latest_fencing_token {
+                return Err(Deposed(token).into());
+            }
+        }
+        #[cfg(test)]
+        if let Some(writes) = self.writes_until_crash.as_mut() {
+            if *writes == 0 {
+                self.crashed = true;
+                return self.read();
+            }
+            *writes -= 1;
+        }
+        Ok(())
+    }
+
+    /// Gets the round of the coordinator state, or `0` if no state has been set yet.
+    fn round_id(&self) -> u64 {
+        self.coordinator_state
+            .as_ref()
+            .map(|state| state.round_id)
+            .unwrap_or_default()
+    }
+
+    /// Gets the leader, after the leader lock expired.
+    fn leader(&mut self) -> &mut Option<(String, FencingToken, Instant)> {
+        if matches!(self.leader, Some((_, _, expiry)) if expiry <= Instant::now()) {
+            self.leader = None;
+        }
+        &mut self.leader
+    }
+
+    fn delete_dicts(&mut self) {
+        self.sum_dict.clear();
+        self.sum_participants.clear();
+        self.evicted_sum_participants.clear();
+        self.update_participants.clear();
+        self.seeds.clear();
+        self.mask_submitted.clear();
+        self.mask_dict.clear();
+        self.aggregation_checkpoint = None;
+    }
+
+    /// Checks whether the update participant has a seed for any sum participant.
+    fn has_seeds(&self, update_pk: &UpdateParticipantPublicKey) -> bool {
+        self.sum_dict.keys().any(|sum_pk| {
+            self.seeds
+                .get(sum_pk)
+                .map(|seeds| seeds.contains_key(update_pk))
+                .unwrap_or(false)
+        })
+    }
+
+    fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> Result<(), LocalSeedDictAddError> {
+        if local_seed_dict.len() != self.sum_dict.len() {
+            return Err(LocalSeedDictAddError::LengthMisMatch);
+        }
+        if !local_seed_dict
+            .keys()
+            .all(|sum_pk| self.sum_dict.contains_key(sum_pk))
+        {
+            return Err(LocalSeedDictAddError::UnknownSumParticipant);
+        }
+
+        let seeds_of = |sum_pk: &SumParticipantPublicKey| self.seeds.get(sum_pk);
+        let duplicate_seed = local_seed_dict.iter().any(|(sum_pk, seed)| {
+            seeds_of(sum_pk)
+                .map(|seeds| {
+                    seeds
+                        .iter()
+                        .any(|(pk, other)| pk != update_pk && other == seed)
+                })
+                .unwrap_or(false)
+        });
+        if duplicate_seed {
+            return Err(LocalSeedDictAddError::DuplicateSeed);
+        }
+
+        if self.update_participants.contains(update_pk) {
+            // resubmitting the same local seed dict is benign, another one is a conflict
+            let same_seeds = local_seed_dict.iter().all(|(sum_pk, seed)| {
+                seeds_of(sum_pk).and_then(|seeds| seeds.get(update_pk)) == Some(seed)
+            });
+            return Err(if same_seeds {
+                LocalSeedDictAddError::AlreadyRecorded
+            } else {
+                LocalSeedDictAddError::UpdatePkAlreadySubmitted
+            });
+        }
+        if local_seed_dict.keys().any(|sum_pk| {
+            seeds_of(sum_pk)
+                .map(|seeds| seeds.contains_key(update_pk))
+                .unwrap_or(false)
+        }) {
+            return Err(LocalSeedDictAddError::UpdatePkAlreadyExistsInUpdateSeedDict);
+        }
+
+        self.update_participants.insert(*update_pk);
+        for (sum_pk, seed) in local_seed_dict.iter() {
+            self.seeds
+                .entry(*sum_pk)
+                .or_default()
+                .insert(*update_pk, seed.clone());
+        }
+        Ok(())
+    }
+}
+
+/// A snapshot of the mask dictionary of a round, see [`MemoryStorage::export_mask_dict()`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MaskDictSnapshot {
+    /// The id of the round of the mask dictionary.
+    pub round_id: u64,
+    /// The distinct submitted masks and their scores.
+    pub masks: Vec<(MaskObject, u64)>,
+    /// The sum participants which submitted a mask, together with the index of their mask in
+    /// `masks`.
+    pub submissions: Vec<(SumParticipantPublicKey, usize)>,
+}
+
+/// An in-memory coordinator storage.
+///
+/// The clones of a storage share the same data. The storage doubles as [`LeaderLock`] for
+/// coordinator replicas within the same process.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    data: Arc<Mutex<Data>>,
+    fencing: Option<FencingToken>,
+}
+
+impl MemoryStorage {
+    /// Creates a new, empty in-memory storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fences the state mutating operations of this storage with the fencing `token` of the
+    /// leader.
+    ///
+    /// A state mutating operation fails with [`Deposed`] once the token has been superseded.
+    pub fn with_fencing_token(mut self, token: FencingToken) -> Self {
+        self.fencing = Some(token);
+        self
+    }
+
+    fn data(&self) -> MutexGuard<'_, Data> {
+        // a poisoned lock doesn't invalidate the data, because every write is applied at once
+        self.data
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Exports the mask dictionary of the current round.
+    ///
+    /// Unlike the Redis backend, this backend loses the received masks when the coordinator
+    /// stops. A coordinator which persists the snapshot during the sum2 phase can resume the phase
+    /// after a restart with [`import_mask_dict()`], without waiting for the masks again. The round
+    /// is the one of the coordinator state, or `0` if no state has been set yet.
+    ///
+    /// [`import_mask_dict()`]: MemoryStorage::import_mask_dict
+    pub fn export_mask_dict(&self) -> StorageResult<MaskDictSnapshot> {
+        debug!("export mask dictionary");
+        let data = self.data();
+        data.read()?;
+        let masks = data
+            .mask_dict
+            .iter()
+            .map(|(mask, score)| (mask.clone(), *score))
+            .collect::<Vec<_>>();
+        let index = masks
+            .iter()
+            .enumerate()
+            .map(|(i, (mask, _))| (mask, i))
+            .collect::<HashMap<_, _>>();
+        let submissions = data
+            .mask_submitted
+            .iter()
+            // a submitted mask is always scored, because scores are never decremented
+            .filter_map(|(sum_pk, mask)| index.get(mask).map(|i| (*sum_pk, *i)))
+            .collect();
+        Ok(MaskDictSnapshot {
+            round_id: data.round_id(),
+            masks,
+            submissions,
+        })
+    }
+
+    /// Imports a mask dictionary which has been exported with [`export_mask_dict()`], replacing
+    /// the mask dictionary of the current round.
+    ///
+    /// The scores of the masks and the submissions of the sum participants are restored, hence a
+    /// sum participant which submits its mask again is recognized as before the export.
+    ///
+    /// # Errors
+    /// Fails if the snapshot belongs to another round than the coordinator state or if a
+    /// submission refers to a mask which is not part of the snapshot.
+    ///
+    /// [`export_mask_dict()`]: MemoryStorage::export_mask_dict
+    pub fn import_mask_dict(&self, snapshot: MaskDictSnapshot) -> StorageResult<()> {
+        debug!("import mask dictionary of round {}", snapshot.round_id);
+        let mut data = self.data();
+        if snapshot.round_id != data.round_id() {
+            return Err(anyhow!(
+                "the mask dictionary of round {} doesn't belong to round {}",
+                snapshot.round_id,
+                data.round_id()
+            ));
+        }
+        let mask_submitted = snapshot
+            .submissions
+            .iter()
+            .map(|(sum_pk, i)| {
+                snapshot
+                    .masks
+                    .get(*i)
+                    .map(|(mask, _)| (*sum_pk, mask.clone()))
+                    .ok_or_else(|| anyhow!("invalid mask index {}", i))
+            })
+            .collect::<StorageResult<HashMap<_, _>>>()?;
+        data.write(self.fencing)?;
+        data.mask_submitted = mask_submitted;
+        data.mask_dict = snapshot.masks.into_iter().collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl MemoryStorage {
+    /// Injects a crash of the storage after the given number of successful writes.
+    ///
+    /// Afterwards, every operation of the storage and its clones fails until it is [revived].
+    ///
+    /// [revived]: MemoryStorage::revive
+    pub fn crash_after(&self, writes: usize) {
+        let mut data = self.data();
+        data.writes_until_crash = Some(writes);
+        data.crashed = false;
+    }
+
+    /// Checks whether the injected crash has happened.
+    pub fn has_crashed(&self) -> bool {
+        self.data().crashed
+    }
+
+    /// Makes the storage available again after a crash, e.g. for a restarted coordinator.
+    pub fn revive(&self) {
+        let mut data = self.data();
+        data.writes_until_crash = None;
+        data.crashed = false;
+    }
+
+    /// Drops the mask dictionary, as if the coordinator had been restarted.
+    pub fn drop_mask_dict(&self) {
+        let mut data = self.data();
+        data.mask_submitted.clear();
+        data.mask_dict.clear();
+    }
+
+    /// Lets the leader lock expire immediately, as if its holder failed to renew it.
+    pub fn expire_leadership(&self) {
+        self.data().leader = None;
+    }
+}
+
+#[async_trait]
+impl CoordinatorStorage for MemoryStorage {
+    async fn set_coordinator_state(&mut self, state: &CoordinatorState) -> StorageResult<()> {
+        debug!("set coordinator state");
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.coordinator_state = Some(state.clone());
+        Ok(())
+    }
+
+    async fn coordinator_state(&mut self) -> StorageResult<Option<CoordinatorState>> {
+        let data = self.data();
+        data.read()?;
+        Ok(data.coordinator_state.clone())
+    }
+
+    async fn add_sum_participant(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+        ephm_pk: &SumParticipantEphemeralPublicKey,
+    ) -> StorageResult<SumPartAdd> {
+        debug!("add sum participant with pk {:?}", pk);
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        let collision = data
+            .sum_dict
+            .iter()
+            .any(|(other_pk, other_ephm_pk)| other_pk != pk && other_ephm_pk == ephm_pk);
+        let result = match data.sum_dict.get(pk) {
+            // an ephemeral pk of another sum participant indicates a broken rng of a client
+            _ if collision => Err(SumPartAddError::EphemeralKeyCollision),
+            None => {
+                data.sum_dict.insert(*pk, *ephm_pk);
+                // remember the order in which the sum participants were added
+                data.sum_participants.push_back(*pk);
+                data.evicted_sum_participants.remove(pk);
+                Ok(())
+            }
+            // resubmitting the same ephemeral pk is benign, another one is a conflict
+            Some(other) if other == ephm_pk => Err(SumPartAddError::AlreadyRecorded),
+            Some(_) => Err(SumPartAddError::AlreadyExists),
+        };
+        Ok(SumPartAdd(result))
+    }
+
+    async fn sum_dict(&mut self) -> StorageResult<Option<SumDict>> {
+        debug!("get sum dictionary");
+        let data = self.data();
+        data.read()?;
+        if data.sum_dict.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(data.sum_dict.clone()))
+    }
+
+    async fn evict_sum_participants(
+        &mut self,
+        capacity: u64,
+    ) -> StorageResult<Vec<SumParticipantPublicKey>> {
+        debug!(
+            "evict sum participants exceeding a capacity of {}",
+            capacity
+        );
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        let mut evicted = Vec::new();
+        while data.sum_dict.len() as u64 > capacity {
+            // the oldest sum participant is at the front of the queue
+            let sum_pk = match data.sum_participants.pop_front() {
+                Some(sum_pk) => sum_pk,
+                None => break,
+            };
+            if data.sum_dict.remove(&sum_pk).is_some() {
+                // delete the seed dict entry and the mask submission of the sum pk
+                data.seeds.remove(&sum_pk);
+                data.mask_submitted.remove(&sum_pk);
+                data.evicted_sum_participants.insert(sum_pk);
+                evicted.push(sum_pk);
+            }
+        }
+        Ok(evicted)
+    }
+
+    async fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> StorageResult<LocalSeedDictAdd> {
+        debug!(
+            "update seed dictionary for update participant with pk {:?}",
+            update_pk
+        );
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        Ok(LocalSeedDictAdd(
+            data.add_local_seed_dict(update_pk, local_seed_dict),
+        ))
+    }
+
+    async fn seed_dict(&mut self) -> StorageResult<Option<SeedDict>> {
+        debug!("get seed dictionary");
+        let data = self.data();
+        data.read()?;
+        if data.sum_dict.is_empty() {
+            return Ok(None);
+        }
+        let seed_dict = data
+            .sum_dict
+            .keys()
+            .map(|sum_pk| {
+                let seeds = data
+                    .seeds
+                    .get(sum_pk)
+                    .map(|seeds| {
+                        UpdateSeedDict::from_storage(
+                            seeds
+                                .iter()
+                                .map(|(update_pk, seed)| (*update_pk, seed.clone())),
+                        )
+                    })
+                    .unwrap_or_default();
+                (*sum_pk, seeds)
+            })
+            .collect();
+        Ok(Some(seed_dict))
+    }
+
+    async fn number_of_unique_update_participants(&mut self) -> StorageResult<u64> {
+        debug!("get number of unique update participants");
+        let data = self.data();
+        data.read()?;
+        let update_pks = data
+            .sum_dict
+            .keys()
+            .filter_map(|sum_pk| data.seeds.get(sum_pk))
+            .flat_map(|seeds| seeds.keys())
+            .collect::<HashSet<_>>();
+        Ok(update_pks.len() as u64)
+    }
+
+    async fn add_collected_masked_model(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        masked_model: &MaskObject,
+    ) -> StorageResult<()> {
+        debug!(
+            "collect masked model of update participant with pk {:?} in round {}",
+            update_pk, round_id
+        );
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.collected_masked_models
+            .entry(round_id)
+            .or_default()
+            .insert(*update_pk, masked_model.clone());
+        Ok(())
+    }
+
+    async fn add_model_checksum(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        model_checksum: &Sha256,
+    ) -> StorageResult<()> {
+        debug!(
+            "add model checksum of update participant with pk {:?} in round {}",
+            update_pk, round_id
+        );
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.model_checksums
+            .entry(round_id)
+            .or_default()
+            .insert(*update_pk, *model_checksum);
+        Ok(())
+    }
+
+    async fn set_collected_seed_dict(
+        &mut self,
+        round_id: u64,
+        seed_dict: &SeedDict,
+    ) -> StorageResult<()> {
+        debug!("collect seed dictionary of round {}", round_id);
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.collected_seed_dicts
+            .insert(round_id, seed_dict.clone());
+        Ok(())
+    }
+
+    async fn incr_mask_score(
+        &mut self,
+        sum_pk: &SumParticipantPublicKey,
+        mask: &MaskObject,
+    ) -> StorageResult<MaskScoreIncr> {
+        debug!("increment mask count");
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        if !data.sum_dict.contains_key(sum_pk) {
+            return Ok(MaskScoreIncr(Err(MaskScoreIncrError::UnknownSumPk)));
+        }
+        if let Some(submitted) = data.mask_submitted.get(sum_pk) {
+            // resubmitting the same mask is benign, another one is a conflict
+            return Ok(MaskScoreIncr(Err(if submitted == mask {
+                MaskScoreIncrError::AlreadyRecorded
+            } else {
+                MaskScoreIncrError::MaskAlreadySubmitted
+            })));
+        }
+        data.mask_submitted.insert(*sum_pk, mask.clone());
+        *data.mask_dict.entry(mask.clone()).or_default() += 1;
+        Ok(MaskScoreIncr(Ok(())))
+    }
+
+    async fn best_masks(&mut self) -> StorageResult<Option<Vec<(MaskObject, u64)>>> {
+        debug!("get best masks");
+        let data = self.data();
+        data.read()?;
+        if data.mask_dict.is_empty() {
+            return Ok(None);
+        }
+        let mut masks = data
+            .mask_dict
+            .iter()
+            .map(|(mask, score)| (mask.clone(), *score))
+            .collect::<Vec<_>>();
+        masks.sort_by(|(_, score1), (_, score2)| score2.cmp(score1));
+        masks.truncate(2);
+        Ok(Some(masks))
+    }
+
+    async fn number_of_unique_masks(&mut self) -> StorageResult<u64> {
+        debug!("get number of unique masks");
+        let data = self.data();
+        data.read()?;
+        Ok(data.mask_dict.len() as u64)
+    }
+
+    async fn submission_status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<SubmissionStatus> {
+        debug!("get submission status of participant with pk {:?}", pk);
+        let data = self.data();
+        data.read()?;
+        Ok(SubmissionStatus {
+            sum: data.sum_dict.contains_key(pk),
+            update: data.has_seeds(pk),
+            sum2: data.mask_submitted.contains_key(pk),
+            evicted: data.evicted_sum_participants.contains(pk),
+        })
+    }
+
+    async fn set_aggregation_checkpoint(
+        &mut self,
+        checkpoint: &AggregationCheckpoint,
+    ) -> StorageResult<()> {
+        debug!(
+            "set aggregation checkpoint of {} masked models in round {}",
+            checkpoint.nb_models, checkpoint.round_id
+        );
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.aggregation_checkpoint = Some(checkpoint.clone());
+        Ok(())
+    }
+
+    async fn aggregation_checkpoint(
+        &mut self,
+        round_id: u64,
+    ) -> StorageResult<Option<AggregationCheckpoint>> {
+        debug!("get aggregation checkpoint of round {}", round_id);
+        let data = self.data();
+        data.read()?;
+        Ok(data
+            .aggregation_checkpoint
+            .clone()
+            .filter(|checkpoint| checkpoint.round_id == round_id))
+    }
+
+    async fn delete_aggregation_checkpoint(&mut self) -> StorageResult<()> {
+        debug!("delete aggregation checkpoint");
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.aggregation_checkpoint = None;
+        Ok(())
+    }
+
+    async fn retain_update_participants(
+        &mut self,
+        update_pks: &[UpdateParticipantPublicKey],
+    ) -> StorageResult<Vec<UpdateParticipantPublicKey>> {
+        debug!(
+            "retain {} update participants in the seed dictionary",
+            update_pks.len()
+        );
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        let retained = update_pks.iter().collect::<HashSet<_>>();
+        let mut removed = data
+            .update_participants
+            .iter()
+            .chain(data.seeds.values().flat_map(|seeds| seeds.keys()))
+            .filter(|update_pk| !retained.contains(update_pk))
+            .copied()
+            .collect::<HashSet<_>>();
+
+        // remove the seeds from all columns of the seed dict at once
+        for seeds in data.seeds.values_mut() {
+            seeds.retain(|update_pk, _| retained.contains(update_pk));
+        }
+        // the removed update participants may submit their local seed dicts again
+        data.update_participants
+            .retain(|update_pk| retained.contains(update_pk));
+
+        Ok(removed.drain().collect())
+    }
+
+    async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
+        debug!("flush coordinator data");
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.delete_dicts();
+        data.coordinator_state = None;
+        data.latest_global_model_id = None;
+        Ok(())
+    }
+
+    async fn delete_dicts(&mut self) -> StorageResult<()> {
+        debug!("flush all dictionaries");
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.delete_dicts();
+        Ok(())
+    }
+
+    async fn add_denied_participant(&mut self, pk: &ParticipantPublicKey) -> StorageResult<bool> {
+        debug!("add participant with pk {:?} to the denylist", pk);
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        Ok(data.denylist.insert(*pk))
+    }
+
+    async fn remove_denied_participant(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<bool> {
+        debug!("remove participant with pk {:?} from the denylist", pk);
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        Ok(data.denylist.remove(pk))
+    }
+
+    async fn denied_participants(&mut self) -> StorageResult<Vec<ParticipantPublicKey>> {
+        debug!("get denylist");
+        let data = self.data();
+        data.read()?;
+        Ok(data.denylist.iter().copied().collect())
+    }
+
+    async fn set_latest_global_model_id(&mut self, global_model_id: &str) -> StorageResult<()> {
+        debug!("set latest global model with id {}", global_model_id);
+        let mut data = self.data();
+        data.write(self.fencing)?;
+        data.latest_global_model_id = Some(global_model_id.to_string());
+        Ok(())
+    }
+
+    async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>> {
+        debug!("get latest global model id");
+        let data = self.data();
+        data.read()?;
+        Ok(data.latest_global_model_id.clone())
+    }
+
+    async fn is_ready(&mut self) -> StorageResult<()> {
+        self.data().read()
+    }
+}
+
+#[async_trait]
+impl LeaderLock for MemoryStorage {
+    async fn acquire_leadership(
+        &mut self,
+        address: &str,
+        ttl: Duration,
+    ) -> StorageResult<Option<FencingToken>> {
+        debug!("try to acquire the leader lock for {}", address);
+        let mut data = self.data();
+        data.read()?;
+        if data.leader().is_some() {
+            return Ok(None);
+        }
+        data.latest_fencing_token += 1;
+        let token = FencingToken(data.latest_fencing_token);
+        data.leader = Some((address.to_string(), token, Instant::now() + ttl));
+        Ok(Some(token))
+    }
+
+    async fn renew_leadership(
+        &mut self,
+        address: &str,
+        token: FencingToken,
+        ttl: Duration,
+    ) -> StorageResult<bool> {
+        debug!("renew the leader lock for {}", address);
+        let mut data = self.data();
+        data.read()?;
+        match data.leader() {
+            Some((ref leader, leader_token, ref mut expiry))
+                if leader == address && *leader_token == token =>
+            {
+                *expiry = Instant::now() + ttl;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn leader(&mut self) -> StorageResult<Option<String>> {
+        debug!("get the leader");
+        let mut data = self.data();
+        data.read()?;
+        Ok(data
+            .leader()
+            .as_ref()
+            .map(|(address, _, _)| address.clone()))
+    }
+
+    async fn is_latest_token(&mut self, token: FencingToken) -> StorageResult<bool> {
+        debug!("check the fencing token {:?}", token);
+        let data = self.data();
+        data.read()?;
+        Ok(data.latest_fencing_token == token.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::utils::{
+        add_local_seed_entries,
+        create_and_add_sum_participant_entries,
+        create_local_seed_entries,
+        create_mask_zeroed,
+        create_seed_dict,
+        create_sum_participant_entry,
+    };
+    use xaynet_core::crypto::SigningKeyPair;
+
+    #[tokio::test]
+    async fn test_sum_dict_ephemeral_key_collision() {
+        let mut storage = MemoryStorage::new();
+        let (pk, ephm_pk) = create_sum_participant_entry();
+        let result = storage.add_sum_participant(&pk, &ephm_pk).await.unwrap();
+        assert!(result.is_ok());
+
+        // the same ephemeral key under another sum pk is rejected
+        let (other_pk, _) = create_sum_participant_entry();
+        let result = storage
+            .add_sum_participant(&other_pk, &ephm_pk)
+            .await
+            .unwrap();
+        assert!(matches!(
+            result.into_inner().unwrap_err(),
+            SumPartAddError::EphemeralKeyCollision
+        ));
+        let sum_dict = storage.sum_dict().await.unwrap().unwrap();
+        assert_eq!(sum_dict.len(), 1);
+        assert!(!sum_dict.contains_key(&other_pk));
+
+        // the ephemeral key is free again after the sum participant has been evicted
+        assert_eq!(storage.evict_sum_participants(0).await.unwrap(), vec![pk]);
+        let result = storage
+            .add_sum_participant(&other_pk, &ephm_pk)
+            .await
+            .unwrap();
+        assert!(result.is_ok());
+
+        // the eviction is recorded, until the sum participant is added again
+        assert!(storage.submission_status(&pk).await.unwrap().evicted);
+        storage.evict_sum_participants(0).await.unwrap();
+        let (_, new_ephm_pk) = create_sum_participant_entry();
+        let result = storage
+            .add_sum_participant(&pk, &new_ephm_pk)
+            .await
+            .unwrap();
+        assert!(result.is_ok());
+        assert!(!storage.submission_status(&pk).await.unwrap().evicted);
+    }
+
+    #[tokio::test]
+    async fn test_seed_dict() {
+        let mut storage = MemoryStorage::new();
+        assert!(storage.seed_dict().await.unwrap().is_none());
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        let sum_dict = storage.sum_dict().await.unwrap().unwrap();
+        let seed_dict = create_seed_dict(sum_dict, &local_seed_dicts);
+        assert_eq!(storage.seed_dict().await.unwrap().unwrap(), seed_dict);
+        assert_eq!(
+            storage
+                .number_of_unique_update_participants()
+                .await
+                .unwrap(),
+            2
+        );
+
+        // resubmitting is benign, an identical local seed dict under a new update pk isn't
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::AlreadyRecorded
+            ))
+        });
+        let update_pk = SigningKeyPair::generate().public;
+        let (_, local_seed_dict) = local_seed_dicts[0].clone();
+        let update_result =
+            add_local_seed_entries(&mut storage, &[(update_pk, local_seed_dict)]).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::DuplicateSeed
+            ))
+        });
+    }
+
+    #[tokio::test]
+    async fn test_aggregation_checkpoint() {
+        let mut storage = MemoryStorage::new();
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+
+        let checkpoint = AggregationCheckpoint {
+            round_id: 1,
+            aggregated_models: create_mask_zeroed(10),
+            nb_models: 1,
+            update_pks: vec![local_seed_dicts[0].0],
+        };
+        storage
+            .set_aggregation_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.aggregation_checkpoint(1).await.unwrap(),
+            Some(checkpoint.clone())
+        );
+        assert!(storage.aggregation_checkpoint(2).await.unwrap().is_none());
+
+        let removed = storage
+            .retain_update_participants(&checkpoint.update_pks)
+            .await
+            .unwrap();
+        assert_eq!(removed, vec![local_seed_dicts[1].0]);
+        assert_eq!(
+            storage
+                .number_of_unique_update_participants()
+                .await
+                .unwrap(),
+            1
+        );
+        let status = storage
+            .submission_status(&local_seed_dicts[1].0)
+            .await
+            .unwrap();
+        assert_eq!(status, SubmissionStatus::default());
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts[1..]).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        storage.delete_dicts().await.unwrap();
+        assert!(storage.aggregation_checkpoint(1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_crash_after() {
+        let mut storage = MemoryStorage::new();
+        storage.crash_after(1);
+        storage.set_latest_global_model_id("1").await.unwrap();
+        assert!(!storage.has_crashed());
+        assert!(storage.set_latest_global_model_id("2").await.is_err());
+        assert!(storage.has_crashed());
+        assert!(storage.latest_global_model_id().await.is_err());
+
+        storage.revive();
+        assert_eq!(
+            storage.latest_global_model_id().await.unwrap(),
+            Some("1".to_string())
+        );
+    }
+}
@@ -1,3 +1,4 @@
 //! Storage backends to manage the coordinator state.
 
+pub mod memory;
 pub mod redis;
@@ -0,0 +1,114 @@
+//! Batched writes of local seed dicts.
+//!
+//! See [`SeedDictBatcher`] for more details.
+
+use std::time::Duration;
+
+use tokio::{
+    sync::{mpsc, oneshot},
+    time,
+};
+use tracing::{debug, warn};
+
+use super::Client;
+use crate::storage::{LocalSeedDictAdd, StorageResult};
+use xaynet_core::{LocalSeedDict, UpdateParticipantPublicKey};
+
+/// A local seed dict which waits to be written, together with the sender of its result.
+type Entry = (
+    UpdateParticipantPublicKey,
+    LocalSeedDict,
+    oneshot::Sender<StorageResult<LocalSeedDictAdd>>,
+);
+
+/// A handle to a background task which writes local seed dicts in batches.
+///
+/// The task accumulates the local seed dicts until either `size` of them are pending or the
+/// `interval` since the first pending local seed dict has elapsed. The batch is then written in a
+/// single pipelined transaction. Every local seed dict is still checked on its own, hence each
+/// writer receives the same [`LocalSeedDictAdd`] as for an unbatched write.
+///
+/// The task stops once all handles are dropped.
+#[derive(Clone)]
+pub struct SeedDictBatcher {
+    sender: mpsc::UnboundedSender<Entry>,
+}
+
+impl SeedDictBatcher {
+    /// Spawns the batching task which writes through the given `client`.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero or if it is called outside of a tokio runtime.
+    pub fn spawn(client: Client, size: usize, interval: Duration) -> Self {
+        assert!(size > 0, "the batch size must be at least one");
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run(client, receiver, size, interval));
+        Self { sender }
+    }
+
+    /// Writes the local seed dict of an update participant with the next batch.
+    pub async fn add_local_seed_dict(
+        &self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> StorageResult<LocalSeedDictAdd> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send((*update_pk, local_seed_dict.clone(), tx))
+            .map_err(|_| anyhow::anyhow!("the seed dict batcher stopped"))?;
+        rx.await
+            .map_err(|_| anyhow::anyhow!("the seed dict batcher dropped the local seed dict"))?
+    }
+}
+
+/// Collects the pending local seed dicts into batches and writes them.
+async fn run(
+    mut client: Client,
+    mut receiver: mpsc::UnboundedReceiver<Entry>,
+    size: usize,
+    interval: Duration,
+) {
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let deadline = time::sleep(interval);
+        tokio::pin!(deadline);
+        while batch.len() < size {
+            tokio::select! {
+                entry = receiver.recv() => match entry {
+                    Some(entry) => batch.push(entry),
+                    None => break,
+                },
+                _ = &mut deadline => break,
+            }
+        }
+        flush(&mut client, batch).await;
+    }
+    debug!("seed dict batcher stopped");
+}
+
+/// Writes a batch of local seed dicts and sends the results to their writers.
+async fn flush(client: &mut Client, batch: Vec<Entry>) {
+    debug!("writing a batch of {} local seed dicts", batch.len());
+    let (local_seed_dicts, senders): (Vec<_>, Vec<_>) = batch
+        .into_iter()
+        .map(|(update_pk, local_seed_dict, tx)| ((update_pk, local_seed_dict), tx))
+        .unzip();
+
+    match client.add_local_seed_dicts(&local_seed_dicts).await {
+        Ok(results) => {
+            for (tx, result) in senders.into_iter().zip(results) {
+                // the writer may have given up waiting
+                let _ = tx.send(Ok(result));
+            }
+        }
+        Err(err) => {
+            warn!("failed to write a batch of local seed dicts: {}", err);
+            for tx in senders {
+                let _ = tx.send(Err(anyhow::anyhow!(
+                    "failed to write a batch of local seed dicts: {}",
+                    err
+                )));
+            }
+        }
+    }
+}
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     state_machine::coordinator::CoordinatorState,
     storage::{
+        AggregationCheckpoint,
         LocalSeedDictAdd,
         LocalSeedDictAddError,
         MaskScoreIncr,
@@ -17,9 +18,10 @@ use crate::{
     },
 };
 use xaynet_core::{
-    crypto::{ByteObject, PublicEncryptKey, PublicSigningKey},
+    crypto::{ByteObject, PublicEncryptKey, PublicSigningKey, Sha256},
     mask::{EncryptedMaskSeed, MaskObject},
     LocalSeedDict,
+    SeedDict,
 };
 
 pub fn redis_type_error(desc: &'static str, details: Option<String>) -> RedisError {
@@ -58,9 +60,15 @@ fn error_code_type_error(response: &Value) -> RedisError {
 /// }
 /// ```
 ///
+/// The bytes read from Redis are converted via [`ByteObject::from_slice()`], unless a conversion
+/// is given as second argument.
+///
 /// [`Client`]: crate::storage::redis::Client
 macro_rules! impl_byte_object_redis_traits {
     ($ty: ty) => {
+        impl_byte_object_redis_traits!($ty, <$ty>::from_slice);
+    };
+    ($ty: ty, $from_slice: expr) => {
         paste! {
             #[derive(Into, Hash, Eq, PartialEq)]
             pub(crate) struct [<$ty Read>]($ty);
@@ -69,7 +77,7 @@ macro_rules! impl_byte_object_redis_traits {
                 fn from_redis_value(v: &Value) -> RedisResult<[<$ty Read>]> {
                     match *v {
                         Value::Data(ref bytes) => {
-                            let inner = <$ty>::from_slice(bytes).ok_or_else(|| {
+                            let inner = ($from_slice)(bytes.as_slice()).ok_or_else(|| {
                                 redis_type_error(concat!("Invalid ", stringify!($ty)), None)
                             })?;
                             Ok([<$ty Read>](inner))
@@ -108,7 +116,15 @@ macro_rules! impl_byte_object_redis_traits {
 
 impl_byte_object_redis_traits!(PublicEncryptKey);
 impl_byte_object_redis_traits!(PublicSigningKey);
-impl_byte_object_redis_traits!(EncryptedMaskSeed);
+// the length of an encrypted mask seed depends on the seed cipher of the update participant
+impl_byte_object_redis_traits!(EncryptedMaskSeed, |bytes: &[u8]| {
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(EncryptedMaskSeed::from(bytes.to_vec()))
+    }
+});
+impl_byte_object_redis_traits!(Sha256);
 
 /// Implements ['FromRedisValue'] and ['ToRedisArgs'] for types that implement
 /// ['Serialize`] and [`Deserialize']. The data is de/serialized via bincode.
@@ -150,6 +166,7 @@ macro_rules! impl_bincode_redis_traits {
 // - no untagged enum
 // so bincode will not panic.
 impl_bincode_redis_traits!(CoordinatorState);
+impl_bincode_redis_traits!(AggregationCheckpoint);
 
 #[derive(From, Into, Serialize, Deserialize)]
 pub(crate) struct MaskObjectRead(MaskObject);
@@ -169,6 +186,24 @@ impl ToRedisArgs for MaskObjectWrite<'_> {
     }
 }
 
+#[derive(From, Into, Serialize, Deserialize)]
+pub(crate) struct SeedDictRead(SeedDict);
+
+impl_bincode_redis_traits!(SeedDictRead);
+
+#[derive(From, Serialize)]
+pub(crate) struct SeedDictWrite<'a>(&'a SeedDict);
+
+impl ToRedisArgs for SeedDictWrite<'_> {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        let data = bincode::serialize(self).unwrap();
+        data.write_redis_args(out)
+    }
+}
+
 #[derive(From)]
 pub(crate) struct LocalSeedDictWrite<'a>(&'a LocalSeedDict);
 
@@ -11,6 +11,14 @@
 //!         "SumParticipantPublicKey_1": SumParticipantEphemeralPublicKey_1,
 //!         "SumParticipantPublicKey_2": SumParticipantEphemeralPublicKey_2
 //!     },
+//!     "sum_participants": [ // list, the oldest sum participant first
+//!         SumParticipantPublicKey_1,
+//!         SumParticipantPublicKey_2
+//!     ],
+//!     "sum_ephm_pks": { // hash
+//!         "SumParticipantEphemeralPublicKey_1": SumParticipantPublicKey_1,
+//!         "SumParticipantEphemeralPublicKey_2": SumParticipantPublicKey_2
+//!     },
 //!     // Seed dict
 //!     "update_participants": [ // set
 //!         UpdateParticipantPublicKey_1,
@@ -29,23 +37,64 @@
 //!         SumParticipantPublicKey_1,
 //!         SumParticipantPublicKey_2
 //!     ],
+//!     "mask_hashes": { // hash
+//!         "SumParticipantPublicKey_1": sha1(mask_object_1),
+//!         "SumParticipantPublicKey_2": sha1(mask_object_1)
+//!     },
 //!     "mask_dict": [ // sorted set
 //!         (mask_object_1, 2), // (mask: bincode encoded string, score/counter: number)
 //!         (mask_object_2, 1)
 //!     ],
-//!     "latest_global_model_id": global_model_id
+//!     "latest_global_model_id": global_model_id,
+//!     // Checkpoint of the aggregated masked models of the update phase
+//!     "aggregation_checkpoint": "...", // bincode encoded string, tagged with the round id
+//!     // Collected data of the rounds in collect only mode
+//!     "collected_masked_models:{round_id}": { // hash
+//!         "UpdateParticipantPublicKey_1": mask_object_1, // bincode encoded string
+//!         "UpdateParticipantPublicKey_2": mask_object_2
+//!     },
+//!     "collected_seed_dict:{round_id}": "...", // bincode encoded string
+//!     // Model checksums of the update participants of the rounds
+//!     "model_checksums:{round_id}": { // hash
+//!         "UpdateParticipantPublicKey_1": Sha256,
+//!         "UpdateParticipantPublicKey_2": Sha256
+//!     },
+//!     // Banned participants
+//!     "denylist": [ // set
+//!         ParticipantPublicKey_1,
+//!         ParticipantPublicKey_2
+//!     ],
+//!     // Leader election
+//!     "leader_lock": address, // the advertised address of the leader, expires
+//!     "leader_fencing_token": token // incremented with each acquisition of the lock
 //! }
 //! ```
+//!
+//! # Fencing
+//!
+//! A client of the leader is fenced with its fencing token, see [`Client::with_fencing_token()`].
+//! Each state mutating operation of a fenced client checks the token within the same script as
+//! the operation, hence a deposed leader can't modify the data once another replica acquired the
+//! lock.
 
+mod batch;
 pub(in crate::storage) mod impls;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use async_trait::async_trait;
-use redis::{aio::ConnectionManager, AsyncCommands, IntoConnectionInfo, Pipeline, Script};
+use redis::{
+    aio::ConnectionManager,
+    AsyncCommands,
+    Cmd,
+    FromRedisValue,
+    IntoConnectionInfo,
+    Script,
+};
 pub use redis::{RedisError, RedisResult};
 use tracing::debug;
 
+pub use self::batch::SeedDictBatcher;
 use self::impls::{
     EncryptedMaskSeedRead,
     LocalSeedDictWrite,
@@ -55,10 +104,14 @@ use self::impls::{
     PublicEncryptKeyWrite,
     PublicSigningKeyRead,
     PublicSigningKeyWrite,
+    SeedDictWrite,
+    Sha256Write,
 };
 use crate::{
     state_machine::coordinator::CoordinatorState,
     storage::{
+        leader::{Deposed, FencingToken, LeaderLock},
+        AggregationCheckpoint,
         CoordinatorStorage,
         LocalSeedDictAdd,
         MaskScoreIncr,
@@ -68,22 +121,153 @@ use crate::{
     },
 };
 use xaynet_core::{
+    common::SubmissionStatus,
+    crypto::{ByteObject, Sha256},
     mask::MaskObject,
     LocalSeedDict,
+    ParticipantPublicKey,
     SeedDict,
     SumDict,
     SumParticipantEphemeralPublicKey,
     SumParticipantPublicKey,
     UpdateParticipantPublicKey,
+    UpdateSeedDict,
 };
 
+/// Expands to the Lua prelude of the state mutating scripts, which refuses the operation of a
+/// deposed leader.
+///
+/// The last argument of the script is the fencing token of the client or an empty string if the
+/// client isn't fenced. The error reply carries the superseded token, see [`to_storage_err()`].
+macro_rules! fencing_check {
+    () => {
+        r#"
+    -- the last argument is the fencing token of the leader, an empty token disables the check
+    local fencing_token = ARGV[#ARGV]
+    if fencing_token ~= "" and redis.call("GET", "leader_fencing_token") ~= fencing_token then
+        return redis.error_reply("DEPOSED " .. fencing_token)
+    end
+"#
+    };
+}
+
+/// Calls a state mutating command of a fenced client.
+///
+/// The arguments are the command and its arguments, followed by the fencing token.
+const FENCED_COMMAND_SCRIPT: &str = concat!(
+    fencing_check!(),
+    r#"
+    return redis.call(unpack(ARGV, 1, #ARGV - 1))
+"#
+);
+
+/// Deletes the keys.
+const DELETE_KEYS_SCRIPT: &str = concat!(
+    fencing_check!(),
+    r#"
+    for _, key in ipairs(KEYS) do
+        redis.call("DEL", key)
+    end
+"#
+);
+
+/// The prefix of the keys of the hashes of the accepted encrypted seeds of a sum participant.
+///
+/// The key of a sum participant is the prefix followed by its public key. Each hash maps the
+/// SHA1 hex digest of an encrypted seed to the public key of the update participant which
+/// submitted it.
+const SEED_HASHES_PREFIX: &[u8] = b"seed_hashes:";
+
+/// Gets the key of the hashes of the accepted encrypted seeds of the sum participant `sum_pk`.
+fn seed_hashes_key(sum_pk: &SumParticipantPublicKey) -> Vec<u8> {
+    [SEED_HASHES_PREFIX, sum_pk.as_slice()].concat()
+}
+
+/// Adds a local seed dict to the seed dict.
+///
+/// The keys are the flattened pairs of the local seed dict and the argument is the public key of
+/// the update participant. The script returns `0` on success and a negative error code of
+/// [`LocalSeedDictAddError`] otherwise.
+///
+/// A local seed dict is rejected if any of its encrypted seeds exactly matches a seed which
+/// another update participant submitted for the same sum participant, see
+/// [`SEED_HASHES_PREFIX`].
+///
+/// [`LocalSeedDictAddError`]: crate::storage::LocalSeedDictAddError
+const ADD_LOCAL_SEED_DICT_SCRIPT: &str = concat!(
+    fencing_check!(),
+    r#"
+    -- lua lists (tables) start at 1
+    local update_pk = ARGV[1]
+
+    -- check if the local seed dict has the same length as the sum_dict
+
+    -- KEYS is a list (table) of key value pairs ([sum_pk_1, seed_1, sum_pk_2, seed_2, ...])
+    local seed_dict_len = #KEYS / 2
+    local sum_dict_len = redis.call("HLEN", "sum_dict")
+    if seed_dict_len ~= sum_dict_len then
+        return -1
+    end
+
+    -- check if all pks of the local seed dict exists in sum_dict
+    for i = 1, #KEYS, 2 do
+        local exist_in_sum_dict = redis.call("HEXISTS", "sum_dict", KEYS[i])
+        if exist_in_sum_dict == 0 then
+            return -2
+        end
+    end
+
+    -- check if another update participant already submitted any of the seeds
+    for i = 1, #KEYS, 2 do
+        local submitter = redis.call("HGET", "seed_hashes:" .. KEYS[i], redis.sha1hex(KEYS[i + 1]))
+        -- HGET returns false if the seed hash doesn't exist
+        if submitter and submitter ~= update_pk then
+            return -5
+        end
+    end
+
+    -- check if the update pk already exists (i.e. the local seed dict has already been submitted)
+    local exist_in_seed_dict = redis.call("SADD", "update_participants", update_pk)
+    -- SADD returns 0 if the key already exists
+    if exist_in_seed_dict == 0 then
+        -- resubmitting the same local seed dict is benign, another one is a conflict
+        for i = 1, #KEYS, 2 do
+            if redis.call("HGET", KEYS[i], update_pk) ~= KEYS[i + 1] then
+                return -3
+            end
+        end
+        return -6
+    end
+
+    -- update the seed dict
+    for i = 1, #KEYS, 2 do
+        local exist_in_update_seed_dict = redis.call("HSETNX", KEYS[i], update_pk, KEYS[i + 1])
+        -- HSETNX returns 0 if the update pk already exists
+        if exist_in_update_seed_dict == 0 then
+            -- This condition should never apply.
+            -- If this condition is true, it is an indication that the data in redis is corrupted.
+            return -4
+        end
+        redis.call("HSET", "seed_hashes:" .. KEYS[i], redis.sha1hex(KEYS[i + 1]), update_pk)
+    end
+
+    return 0
+"#
+);
+
 /// Redis client.
 #[derive(Clone)]
 pub struct Client {
     connection: ConnectionManager,
+    batcher: Option<SeedDictBatcher>,
+    fencing: Option<FencingToken>,
 }
 
 fn to_storage_err(e: RedisError) -> StorageError {
+    // the state mutating scripts of a deposed leader reply with its superseded fencing token
+    if let (Some("DEPOSED"), Some(Ok(token))) = (e.code(), e.detail().map(str::parse)) {
+        return Deposed(FencingToken(token)).into();
+    }
     anyhow::anyhow!(e)
 }
 
@@ -98,36 +282,148 @@ impl Client {
     pub async fn new<T: IntoConnectionInfo>(url: T) -> Result<Self, RedisError> {
         let client = redis::Client::open(url)?;
         let connection = client.get_tokio_connection_manager().await?;
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            batcher: None,
+            fencing: None,
+        })
+    }
+
+    /// Fences the state mutating operations with the `token` of the leader.
+    ///
+    /// Each state mutating operation checks atomically that the `token` is still the latest
+    /// fencing token and fails with [`Deposed`] otherwise. Reading operations aren't fenced.
+    ///
+    /// # Panics
+    /// Panics if the local seed dicts are already written in batches, because the batcher writes
+    /// them with its own client. Call [`with_seed_dict_batching()`] afterwards instead.
+    ///
+    /// [`with_seed_dict_batching()`]: Client::with_seed_dict_batching
+    pub fn with_fencing_token(mut self, token: FencingToken) -> Self {
+        assert!(
+            self.batcher.is_none(),
+            "the fencing token must be set before the seed dict batching"
+        );
+        self.fencing = Some(token);
+        self
+    }
+
+    /// Gets the last argument of the state mutating scripts, see [`fencing_check!`].
+    fn fencing_arg(&self) -> String {
+        self.fencing
+            .map(|token| token.0.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Queries a state mutating command.
+    ///
+    /// If the client is fenced, the command is called by a script which checks the fencing token
+    /// first.
+    async fn query_mutation<T: FromRedisValue>(&mut self, cmd: &Cmd) -> StorageResult<T> {
+        let result = match self.fencing {
+            Some(token) => {
+                let script = Script::new(FENCED_COMMAND_SCRIPT);
+                let mut invocation = script.prepare_invoke();
+                for arg in cmd.args_iter() {
+                    if let redis::Arg::Simple(arg) = arg {
+                        invocation.arg(arg);
+                    }
+                }
+                invocation
+                    .arg(token.0)
+                    .invoke_async(&mut self.connection)
+                    .await
+            }
+            None => cmd.query_async(&mut self.connection).await,
+        };
+        result.map_err(to_storage_err)
+    }
+
+    /// Writes the local seed dicts in batches of up to `size` entries, which are flushed at
+    /// the latest after `interval`.
+    ///
+    /// See [`SeedDictBatcher`] for more details.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero or if it is called outside of a tokio runtime.
+    pub fn with_seed_dict_batching(mut self, size: usize, interval: Duration) -> Self {
+        self.batcher = Some(SeedDictBatcher::spawn(self.clone(), size, interval));
+        self
+    }
+
+    /// Adds several local seed dicts in a single pipelined transaction.
+    ///
+    /// The local seed dicts are added in order and each of them is checked on its own, i.e. the
+    /// results are the same as for consecutive calls of [`add_local_seed_dict()`].
+    ///
+    /// [`add_local_seed_dict()`]: CoordinatorStorage::add_local_seed_dict
+    pub async fn add_local_seed_dicts(
+        &mut self,
+        local_seed_dicts: &[(UpdateParticipantPublicKey, LocalSeedDict)],
+    ) -> StorageResult<Vec<LocalSeedDictAdd>> {
+        debug!(
+            "update seed dictionary for {} update participants",
+            local_seed_dicts.len()
+        );
+        if local_seed_dicts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for (update_pk, local_seed_dict) in local_seed_dicts {
+            // https://redis.io/commands/eval
+            pipe.cmd("EVAL")
+                .arg(ADD_LOCAL_SEED_DICT_SCRIPT)
+                .arg(local_seed_dict.len() * 2)
+                .arg(LocalSeedDictWrite::from(local_seed_dict))
+                .arg(PublicSigningKeyWrite::from(update_pk))
+                .arg(self.fencing_arg());
+        }
+        pipe.query_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)
     }
 
-    async fn create_flush_dicts_pipeline(&mut self) -> RedisResult<Pipeline> {
+    /// Gets the keys of all dictionaries.
+    async fn flush_dicts_keys(&mut self) -> RedisResult<Vec<Vec<u8>>> {
         // https://redis.io/commands/hkeys
         // > Return value:
         //   Array reply: list of fields in the hash, or an empty list when key does not exist.
         let sum_pks: Vec<PublicSigningKeyRead> = self.connection.hkeys("sum_dict").await?;
-        let mut pipe = redis::pipe();
-
-        // https://redis.io/commands/del
-        // > Return value:
-        //   The number of keys that were removed.
-        //
-        // Returns `0` if the key does not exist.
-        // We ignore the return value because we are not interested in it.
-
-        // delete sum dict
-        pipe.del("sum_dict").ignore();
-
-        // delete seed dict
-        pipe.del("update_participants").ignore();
+        let mut keys = vec![
+            // delete sum dict
+            b"sum_dict".to_vec(),
+            b"sum_participants".to_vec(),
+            b"sum_ephm_pks".to_vec(),
+            b"evicted_sum_participants".to_vec(),
+            // delete seed dict
+            b"update_participants".to_vec(),
+        ];
         for sum_pk in sum_pks {
-            pipe.del(sum_pk).ignore();
+            let sum_pk = SumParticipantPublicKey::from(sum_pk);
+            keys.push(seed_hashes_key(&sum_pk));
+            keys.push(sum_pk.as_slice().to_vec());
         }
 
         // delete mask dict
-        pipe.del("mask_submitted").ignore();
-        pipe.del("mask_dict").ignore();
-        Ok(pipe)
+        keys.push(b"mask_submitted".to_vec());
+        keys.push(b"mask_hashes".to_vec());
+        keys.push(b"mask_dict".to_vec());
+
+        // delete aggregation checkpoint
+        keys.push(b"aggregation_checkpoint".to_vec());
+        Ok(keys)
+    }
+
+    /// Deletes the `keys` at once.
+    async fn delete_keys(&mut self, keys: Vec<Vec<u8>>) -> StorageResult<()> {
+        Script::new(DELETE_KEYS_SCRIPT)
+            .key(keys)
+            .arg(self.fencing_arg())
+            .invoke_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)
     }
 }
 
@@ -140,10 +436,8 @@ impl CoordinatorStorage for Client {
         //   it is overwritten, regardless of its type.
         // Possible return value in our case:
         // > Simple string reply: OK if SET was executed correctly.
-        self.connection
-            .set("coordinator_state", state)
+        self.query_mutation(redis::cmd("SET").arg("coordinator_state").arg(state))
             .await
-            .map_err(to_storage_err)
     }
 
     async fn coordinator_state(&mut self) -> StorageResult<Option<CoordinatorState>> {
@@ -165,18 +459,44 @@ impl CoordinatorStorage for Client {
         ephm_pk: &SumParticipantEphemeralPublicKey,
     ) -> StorageResult<SumPartAdd> {
         debug!("add sum participant with pk {:?}", pk);
-        // https://redis.io/commands/hsetnx
-        // > If field already exists, this operation has no effect.
-        // > Return value
-        //   Integer reply, specifically:
-        //   1 if field is a new field in the hash and value was set.
-        //   0 if field already exists in the hash and no operation was performed.
-        self.connection
-            .hset_nx(
-                "sum_dict",
-                PublicSigningKeyWrite::from(pk),
-                PublicEncryptKeyWrite::from(ephm_pk),
-            )
+        let script = Script::new(concat!(
+            fencing_check!(),
+            r#"
+                -- lua lists (tables) start at 1
+                local sum_pk = KEYS[1]
+                local ephm_pk = ARGV[1]
+
+                -- an ephemeral pk of another sum participant is a collision, entries of
+                -- evicted or removed sum participants are stale and ignored
+                local owner_pk = redis.call("HGET", "sum_ephm_pks", ephm_pk)
+                if owner_pk and owner_pk ~= sum_pk
+                    and redis.call("HGET", "sum_dict", owner_pk) == ephm_pk then
+                    return 3
+                end
+
+                -- HSETNX returns 0 if the sum pk already exists
+                local added = redis.call("HSETNX", "sum_dict", sum_pk, ephm_pk)
+                if added == 1 then
+                    -- remember the order in which the sum participants were added
+                    redis.call("RPUSH", "sum_participants", sum_pk)
+                    redis.call("HSET", "sum_ephm_pks", ephm_pk, sum_pk)
+                    redis.call("SREM", "evicted_sum_participants", sum_pk)
+                    return 1
+                end
+
+                -- resubmitting the same ephemeral pk is benign, another one is a conflict
+                if redis.call("HGET", "sum_dict", sum_pk) == ephm_pk then
+                    return 2
+                end
+                return 0
+            "#
+        ));
+
+        script
+            .key(PublicSigningKeyWrite::from(pk))
+            .arg(PublicEncryptKeyWrite::from(ephm_pk))
+            .arg(self.fencing_arg())
+            .invoke_async(&mut self.connection)
             .await
             .map_err(to_storage_err)
     }
@@ -205,62 +525,76 @@ impl CoordinatorStorage for Client {
         Ok(Some(sum_dict))
     }
 
-    async fn add_local_seed_dict(
+    async fn evict_sum_participants(
         &mut self,
-        update_pk: &UpdateParticipantPublicKey,
-        local_seed_dict: &LocalSeedDict,
-    ) -> StorageResult<LocalSeedDictAdd> {
+        capacity: u64,
+    ) -> StorageResult<Vec<SumParticipantPublicKey>> {
         debug!(
-            "update seed dictionary for update participant with pk {:?}",
-            update_pk
+            "evict sum participants exceeding a capacity of {}",
+            capacity
         );
-        let script = Script::new(
+        let script = Script::new(concat!(
+            fencing_check!(),
             r#"
-                -- lua lists (tables) start at 1
-                local update_pk = ARGV[1]
-
-                -- check if the local seed dict has the same length as the sum_dict
-
-                -- KEYS is a list (table) of key value pairs ([sum_pk_1, seed_1, sum_pk_2, seed_2, ...])
-                local seed_dict_len = #KEYS / 2
-                local sum_dict_len = redis.call("HLEN", "sum_dict")
-                if seed_dict_len ~= sum_dict_len then
-                    return -1
-                end
+                local capacity = tonumber(ARGV[1])
+                local evicted = {}
+
+                while redis.call("HLEN", "sum_dict") > capacity do
+                    -- the oldest sum participant is at the head of the list
+                    local sum_pk = redis.call("LPOP", "sum_participants")
+                    if not sum_pk then
+                        break
+                    end
 
-                -- check if all pks of the local seed dict exists in sum_dict
-                for i = 1, #KEYS, 2 do
-                    local exist_in_sum_dict = redis.call("HEXISTS", "sum_dict", KEYS[i])
-                    if exist_in_sum_dict == 0 then
-                        return -2
+                    -- HDEL returns 0 if the sum pk has already been removed otherwise
+                    local ephm_pk = redis.call("HGET", "sum_dict", sum_pk)
+                    if redis.call("HDEL", "sum_dict", sum_pk) == 1 then
+                        redis.call("HDEL", "sum_ephm_pks", ephm_pk)
+                        -- delete the seed dict entry and the mask submission of the sum pk
+                        redis.call("DEL", sum_pk)
+                        redis.call("DEL", "seed_hashes:" .. sum_pk)
+                        redis.call("SREM", "mask_submitted", sum_pk)
+                        redis.call("HDEL", "mask_hashes", sum_pk)
+                        redis.call("SADD", "evicted_sum_participants", sum_pk)
+                        table.insert(evicted, sum_pk)
                     end
                 end
 
-                -- check if the update pk already exists (i.e. the local seed dict has already been submitted)
-                local exist_in_seed_dict = redis.call("SADD", "update_participants", update_pk)
-                -- SADD returns 0 if the key already exists
-                if exist_in_seed_dict == 0 then
-                    return -3
-                end
+                return evicted
+            "#
+        ));
 
-                -- update the seed dict
-                for i = 1, #KEYS, 2 do
-                    local exist_in_update_seed_dict = redis.call("HSETNX", KEYS[i], update_pk, KEYS[i + 1])
-                    -- HSETNX returns 0 if the update pk already exists
-                    if exist_in_update_seed_dict == 0 then
-                        -- This condition should never apply.
-                        -- If this condition is true, it is an indication that the data in redis is corrupted.
-                        return -4
-                    end
-                end
+        let evicted: Vec<PublicSigningKeyRead> = script
+            .arg(capacity)
+            .arg(self.fencing_arg())
+            .invoke_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)?;
 
-                return 0
-            "#,
+        Ok(evicted.into_iter().map(|pk| pk.into()).collect())
+    }
+
+    async fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> StorageResult<LocalSeedDictAdd> {
+        debug!(
+            "update seed dictionary for update participant with pk {:?}",
+            update_pk
         );
+        if let Some(batcher) = self.batcher.as_ref() {
+            return batcher
+                .add_local_seed_dict(update_pk, local_seed_dict)
+                .await;
+        }
+
+        let script = Script::new(ADD_LOCAL_SEED_DICT_SCRIPT);
 
         script
             .key(LocalSeedDictWrite::from(local_seed_dict))
             .arg(PublicSigningKeyWrite::from(update_pk))
+            .arg(self.fencing_arg())
             .invoke_async(&mut self.connection)
             .await
             .map_err(to_storage_err)
@@ -289,16 +623,118 @@ impl CoordinatorStorage for Client {
                 self.connection.hgetall(&sum_pk).await?;
             seed_dict.insert(
                 sum_pk.into(),
-                sum_pk_seed_dict
-                    .into_iter()
-                    .map(|(pk, seed)| (pk.into(), seed.into()))
-                    .collect(),
+                UpdateSeedDict::from_storage(
+                    sum_pk_seed_dict
+                        .into_iter()
+                        .map(|(pk, seed)| (pk.into(), seed.into())),
+                ),
             );
         }
 
         Ok(Some(seed_dict))
     }
 
+    async fn number_of_unique_update_participants(&mut self) -> StorageResult<u64> {
+        debug!("get number of unique update participants");
+        // the seed dict is stored as one hash per sum participant, hence the update participants
+        // are collected from all of them instead of relying on the `update_participants` set
+        let script = Script::new(
+            r#"
+                local update_pks = {}
+                local count = 0
+
+                local sum_pks = redis.call("HKEYS", "sum_dict")
+                for _, sum_pk in ipairs(sum_pks) do
+                    local pks = redis.call("HKEYS", sum_pk)
+                    for _, update_pk in ipairs(pks) do
+                        if not update_pks[update_pk] then
+                            update_pks[update_pk] = true
+                            count = count + 1
+                        end
+                    end
+                end
+
+                return count
+            "#,
+        );
+
+        script
+            .prepare_invoke()
+            .invoke_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)
+    }
+
+    /// The maximum length of a serialized mask is 512 Megabytes.
+    async fn add_collected_masked_model(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        masked_model: &MaskObject,
+    ) -> StorageResult<()> {
+        debug!(
+            "collect masked model of update participant with pk {:?} in round {}",
+            update_pk, round_id
+        );
+        // https://redis.io/commands/hset
+        // > If field already exists in the hash, it is overwritten.
+        // > Return value
+        //   Integer reply: The number of fields that were added.
+        // We ignore the return value because we are not interested in it.
+        self.query_mutation(
+            redis::cmd("HSET")
+                .arg(format!("collected_masked_models:{}", round_id))
+                .arg(PublicSigningKeyWrite::from(update_pk))
+                .arg(MaskObjectWrite::from(masked_model)),
+        )
+        .await
+        .map(|_: u64| ())
+    }
+
+    async fn add_model_checksum(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        model_checksum: &Sha256,
+    ) -> StorageResult<()> {
+        debug!(
+            "add model checksum of update participant with pk {:?} in round {}",
+            update_pk, round_id
+        );
+        // https://redis.io/commands/hset
+        // > If field already exists in the hash, it is overwritten.
+        // > Return value
+        //   Integer reply: The number of fields that were added.
+        // We ignore the return value because we are not interested in it.
+        self.query_mutation(
+            redis::cmd("HSET")
+                .arg(format!("model_checksums:{}", round_id))
+                .arg(PublicSigningKeyWrite::from(update_pk))
+                .arg(Sha256Write::from(model_checksum)),
+        )
+        .await
+        .map(|_: u64| ())
+    }
+
+    async fn set_collected_seed_dict(
+        &mut self,
+        round_id: u64,
+        seed_dict: &SeedDict,
+    ) -> StorageResult<()> {
+        debug!("collect seed dictionary of round {}", round_id);
+        // https://redis.io/commands/set
+        // > Set key to hold the string value. If key already holds a value,
+        //   it is overwritten, regardless of its type.
+        // Possible return value in our case:
+        // > Simple string reply: OK if SET was executed correctly.
+        self.query_mutation(
+            redis::cmd("SET")
+                .arg(format!("collected_seed_dict:{}", round_id))
+                .arg(SeedDictWrite::from(seed_dict)),
+        )
+        .await
+    }
+
     /// The maximum length of a serialized mask is 512 Megabytes.
     async fn incr_mask_score(
         &mut self,
@@ -306,7 +742,8 @@ impl CoordinatorStorage for Client {
         mask: &MaskObject,
     ) -> StorageResult<MaskScoreIncr> {
         debug!("increment mask count");
-        let script = Script::new(
+        let script = Script::new(concat!(
+            fencing_check!(),
             r#"
                 -- lua lists (tables) start at 1
                 local sum_pk = ARGV[1]
@@ -324,18 +761,24 @@ impl CoordinatorStorage for Client {
                 local mask_already_submitted = redis.call("SADD", "mask_submitted", sum_pk)
                 -- SADD returns 0 if the key already exists
                 if mask_already_submitted == 0 then
+                    -- resubmitting the same mask is benign, another one is a conflict
+                    if redis.call("HGET", "mask_hashes", sum_pk) == redis.sha1hex(KEYS[1]) then
+                        return -3
+                    end
                     return -2
                 end
 
+                redis.call("HSET", "mask_hashes", sum_pk, redis.sha1hex(KEYS[1]))
                 redis.call("ZINCRBY", "mask_dict", 1, KEYS[1])
 
                 return 0
-            "#,
-        );
+            "#
+        ));
 
         script
             .key(MaskObjectWrite::from(mask))
             .arg(PublicSigningKeyWrite::from(sum_pk))
+            .arg(self.fencing_arg())
             .invoke_async(&mut self.connection)
             .await
             .map_err(to_storage_err)
@@ -378,28 +821,222 @@ impl CoordinatorStorage for Client {
             .map_err(to_storage_err)
     }
 
+    async fn submission_status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<SubmissionStatus> {
+        debug!("get submission status of participant with pk {:?}", pk);
+        let script = Script::new(
+            r#"
+                local pk = ARGV[1]
+
+                local sum = redis.call("HEXISTS", "sum_dict", pk)
+
+                -- the participant is an update participant if any column of the seed dict
+                -- contains one of its seeds
+                local update = 0
+                local sum_pks = redis.call("HKEYS", "sum_dict")
+                for _, sum_pk in ipairs(sum_pks) do
+                    if redis.call("HEXISTS", sum_pk, pk) == 1 then
+                        update = 1
+                        break
+                    end
+                end
+
+                local sum2 = redis.call("SISMEMBER", "mask_submitted", pk)
+                local evicted = redis.call("SISMEMBER", "evicted_sum_participants", pk)
+
+                return { sum, update, sum2, evicted }
+            "#,
+        );
+
+        let (sum, update, sum2, evicted) = script
+            .arg(PublicSigningKeyWrite::from(pk))
+            .invoke_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)?;
+
+        Ok(SubmissionStatus {
+            sum,
+            update,
+            sum2,
+            evicted,
+        })
+    }
+
+    /// The maximum length of a serialized checkpoint is 512 Megabytes.
+    async fn set_aggregation_checkpoint(
+        &mut self,
+        checkpoint: &AggregationCheckpoint,
+    ) -> StorageResult<()> {
+        debug!(
+            "set aggregation checkpoint of {} masked models in round {}",
+            checkpoint.nb_models, checkpoint.round_id
+        );
+        // https://redis.io/commands/set
+        // > Set key to hold the string value. If key already holds a value,
+        //   it is overwritten, regardless of its type.
+        // Possible return value in our case:
+        // > Simple string reply: OK if SET was executed correctly.
+        self.query_mutation(
+            redis::cmd("SET")
+                .arg("aggregation_checkpoint")
+                .arg(checkpoint),
+        )
+        .await
+    }
+
+    async fn aggregation_checkpoint(
+        &mut self,
+        round_id: u64,
+    ) -> StorageResult<Option<AggregationCheckpoint>> {
+        debug!("get aggregation checkpoint of round {}", round_id);
+        // https://redis.io/commands/get
+        // > Return value
+        //   Bulk string reply: the value of key, or nil when key does not exist.
+        let checkpoint: Option<AggregationCheckpoint> = self
+            .connection
+            .get("aggregation_checkpoint")
+            .await
+            .map_err(to_storage_err)?;
+        Ok(checkpoint.filter(|checkpoint| checkpoint.round_id == round_id))
+    }
+
+    async fn delete_aggregation_checkpoint(&mut self) -> StorageResult<()> {
+        debug!("delete aggregation checkpoint");
+        // https://redis.io/commands/del
+        // > Return value:
+        //   The number of keys that were removed.
+        // We ignore the return value because we are not interested in it.
+        self.query_mutation(redis::cmd("DEL").arg("aggregation_checkpoint"))
+            .await
+            .map(|_: u64| ())
+    }
+
+    async fn retain_update_participants(
+        &mut self,
+        update_pks: &[UpdateParticipantPublicKey],
+    ) -> StorageResult<Vec<UpdateParticipantPublicKey>> {
+        debug!(
+            "retain {} update participants in the seed dictionary",
+            update_pks.len()
+        );
+        let script = Script::new(concat!(
+            fencing_check!(),
+            r#"
+                -- ARGV is a list (table) of the update pks to retain, followed by the fencing token
+                local retained = {}
+                for i = 1, #ARGV - 1 do
+                    retained[ARGV[i]] = true
+                end
+
+                local removed = {}
+                local is_removed = {}
+                local function remove(update_pk)
+                    if not is_removed[update_pk] then
+                        is_removed[update_pk] = true
+                        table.insert(removed, update_pk)
+                    end
+                end
+
+                -- remove the seeds and seed hashes from all columns of the seed dict at once
+                local sum_pks = redis.call("HKEYS", "sum_dict")
+                for _, sum_pk in ipairs(sum_pks) do
+                    local seeds = redis.call("HGETALL", sum_pk)
+                    for i = 1, #seeds, 2 do
+                        local update_pk = seeds[i]
+                        if not retained[update_pk] then
+                            redis.call("HDEL", sum_pk, update_pk)
+                            redis.call("HDEL", "seed_hashes:" .. sum_pk, redis.sha1hex(seeds[i + 1]))
+                            remove(update_pk)
+                        end
+                    end
+                end
+
+                -- the removed update participants may submit their local seed dicts again
+                local update_pks = redis.call("SMEMBERS", "update_participants")
+                for _, update_pk in ipairs(update_pks) do
+                    if not retained[update_pk] then
+                        redis.call("SREM", "update_participants", update_pk)
+                        remove(update_pk)
+                    end
+                end
+
+                return removed
+            "#
+        ));
+
+        let mut invocation = script.prepare_invoke();
+        for update_pk in update_pks {
+            invocation.arg(PublicSigningKeyWrite::from(update_pk));
+        }
+        invocation.arg(self.fencing_arg());
+        let removed: Vec<PublicSigningKeyRead> = invocation
+            .invoke_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)?;
+
+        Ok(removed.into_iter().map(|pk| pk.into()).collect())
+    }
+
     /// # Note
     /// This method is **not** an atomic operation.
     async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
         debug!("flush coordinator data");
-        let mut pipe = self.create_flush_dicts_pipeline().await?;
-        pipe.del("coordinator_state").ignore();
-        pipe.del("latest_global_model_id").ignore();
-        pipe.atomic()
-            .query_async(&mut self.connection)
-            .await
-            .map_err(to_storage_err)
+        let mut keys = self.flush_dicts_keys().await?;
+        keys.push(b"coordinator_state".to_vec());
+        keys.push(b"latest_global_model_id".to_vec());
+        self.delete_keys(keys).await
     }
 
     /// # Note
     /// This method is **not** an atomic operation.
     async fn delete_dicts(&mut self) -> StorageResult<()> {
         debug!("flush all dictionaries");
-        let mut pipe = self.create_flush_dicts_pipeline().await?;
-        pipe.atomic()
-            .query_async(&mut self.connection)
-            .await
-            .map_err(to_storage_err)
+        let keys = self.flush_dicts_keys().await?;
+        self.delete_keys(keys).await
+    }
+
+    async fn add_denied_participant(&mut self, pk: &ParticipantPublicKey) -> StorageResult<bool> {
+        debug!("add participant with pk {:?} to the denylist", pk);
+        // https://redis.io/commands/sadd
+        // > Return value
+        //   Integer reply: the number of elements that were added to the set, not including all
+        //   the elements already present in the set.
+        self.query_mutation(
+            redis::cmd("SADD")
+                .arg("denylist")
+                .arg(PublicSigningKeyWrite::from(pk)),
+        )
+        .await
+        .map(|added: u64| added == 1)
+    }
+
+    async fn remove_denied_participant(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<bool> {
+        debug!("remove participant with pk {:?} from the denylist", pk);
+        // https://redis.io/commands/srem
+        // > Return value
+        //   Integer reply: the number of members that were removed from the set, not including
+        //   non existing members.
+        self.query_mutation(
+            redis::cmd("SREM")
+                .arg("denylist")
+                .arg(PublicSigningKeyWrite::from(pk)),
+        )
+        .await
+        .map(|removed: u64| removed == 1)
+    }
+
+    async fn denied_participants(&mut self) -> StorageResult<Vec<ParticipantPublicKey>> {
+        debug!("get denylist");
+        // https://redis.io/commands/smembers
+        // > Return value
+        //   Array reply: all elements of the set.
+        let result: Vec<PublicSigningKeyRead> = self.connection.smembers("denylist").await?;
+        Ok(result.into_iter().map(|pk| pk.into()).collect())
     }
 
     async fn set_latest_global_model_id(&mut self, global_model_id: &str) -> StorageResult<()> {
@@ -409,10 +1046,12 @@ impl CoordinatorStorage for Client {
         //   it is overwritten, regardless of its type.
         // Possible return value in our case:
         // > Simple string reply: OK if SET was executed correctly.
-        self.connection
-            .set("latest_global_model_id", global_model_id)
-            .await
-            .map_err(to_storage_err)
+        self.query_mutation(
+            redis::cmd("SET")
+                .arg("latest_global_model_id")
+                .arg(global_model_id),
+        )
+        .await
     }
 
     async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>> {
@@ -424,16 +1063,94 @@ impl CoordinatorStorage for Client {
         // > Return value
         //   Bulk string reply: the value of key, or nil when key does not exist.
         self.connection
-            .get("latest_global_model_id")
+            .get("latest_global_model_id")
+            .await
+            .map_err(to_storage_err)
+    }
+
+    async fn is_ready(&mut self) -> StorageResult<()> {
+        // https://redis.io/commands/ping
+        redis::cmd("PING")
+            .query_async(&mut self.connection)
+            .await
+            .map_err(to_storage_err)
+    }
+}
+
+#[async_trait]
+impl LeaderLock for Client {
+    async fn acquire_leadership(
+        &mut self,
+        address: &str,
+        ttl: Duration,
+    ) -> StorageResult<Option<FencingToken>> {
+        debug!("try to acquire the leader lock for {}", address);
+        let script = Script::new(
+            r#"
+                -- SET NX returns false if the lock is held by another replica
+                if redis.call("SET", KEYS[1], ARGV[1], "NX", "PX", ARGV[2]) then
+                    return redis.call("INCR", KEYS[2])
+                end
+                return false
+            "#,
+        );
+
+        script
+            .key("leader_lock")
+            .key("leader_fencing_token")
+            .arg(address)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut self.connection)
+            .await
+            .map(|token: Option<u64>| token.map(FencingToken))
+            .map_err(to_storage_err)
+    }
+
+    async fn renew_leadership(
+        &mut self,
+        address: &str,
+        token: FencingToken,
+        ttl: Duration,
+    ) -> StorageResult<bool> {
+        debug!("renew the leader lock for {}", address);
+        let script = Script::new(
+            r#"
+                -- the lock must still be held by this replica with the latest token
+                if redis.call("GET", KEYS[1]) == ARGV[1]
+                    and redis.call("GET", KEYS[2]) == ARGV[2]
+                then
+                    return redis.call("PEXPIRE", KEYS[1], ARGV[3])
+                end
+                return 0
+            "#,
+        );
+
+        script
+            .key("leader_lock")
+            .key("leader_fencing_token")
+            .arg(address)
+            .arg(token.0)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut self.connection)
+            .await
+            .map(|renewed: u64| renewed == 1)
+            .map_err(to_storage_err)
+    }
+
+    async fn leader(&mut self) -> StorageResult<Option<String>> {
+        debug!("get the leader");
+        self.connection
+            .get("leader_lock")
             .await
             .map_err(to_storage_err)
     }
 
-    async fn is_ready(&mut self) -> StorageResult<()> {
-        // https://redis.io/commands/ping
-        redis::cmd("PING")
-            .query_async(&mut self.connection)
+    async fn is_latest_token(&mut self, token: FencingToken) -> StorageResult<bool> {
+        debug!("check the fencing token {:?}", token);
+        self.connection
+            .get("leader_fencing_token")
             .await
+            .map(|latest: Option<u64>| latest == Some(token.0))
             .map_err(to_storage_err)
     }
 }
@@ -511,8 +1228,7 @@ impl Client {
     pub async fn seed_dict_for_sum_pk(
         &mut self,
         sum_pk: &SumParticipantPublicKey,
-    ) -> RedisResult<HashMap<UpdateParticipantPublicKey, xaynet_core::mask::EncryptedMaskSeed>>
-    {
+    ) -> RedisResult<UpdateSeedDict> {
         debug!(
             "get seed dictionary for sum participant with pk {:?}",
             sum_pk
@@ -527,10 +1243,55 @@ impl Client {
             .await?;
         let seed_dict = result
             .into_iter()
-            .map(|(pk, seed)| (pk.into(), seed.into()))
+            .map(|(pk, seed)| (pk.into(), seed.into()));
+
+        Ok(UpdateSeedDict::from_storage(seed_dict))
+    }
+
+    /// Returns the collected masked models of the given round or an empty map when no masked
+    /// models have been collected for the round.
+    pub async fn collected_masked_models(
+        &mut self,
+        round_id: u64,
+    ) -> RedisResult<HashMap<UpdateParticipantPublicKey, MaskObject>> {
+        let result: Vec<(PublicSigningKeyRead, MaskObjectRead)> = self
+            .connection
+            .hgetall(format!("collected_masked_models:{}", round_id))
+            .await?;
+        let masked_models = result
+            .into_iter()
+            .map(|(pk, masked_model)| (pk.into(), masked_model.into()))
+            .collect();
+
+        Ok(masked_models)
+    }
+
+    /// Returns the model checksums of the given round or an empty map when no model checksums
+    /// have been added for the round.
+    pub async fn model_checksums(
+        &mut self,
+        round_id: u64,
+    ) -> RedisResult<HashMap<UpdateParticipantPublicKey, Sha256>> {
+        let result: Vec<(PublicSigningKeyRead, impls::Sha256Read)> = self
+            .connection
+            .hgetall(format!("model_checksums:{}", round_id))
+            .await?;
+        let model_checksums = result
+            .into_iter()
+            .map(|(pk, checksum)| (pk.into(), checksum.into()))
             .collect();
 
-        Ok(seed_dict)
+        Ok(model_checksums)
+    }
+
+    /// Returns the collected [`SeedDict`] of the given round.
+    pub async fn collected_seed_dict(&mut self, round_id: u64) -> RedisResult<Option<SeedDict>> {
+        let result: Option<self::impls::SeedDictRead> = self
+            .connection
+            .get(format!("collected_seed_dict:{}", round_id))
+            .await?;
+
+        Ok(result.map(Into::into))
     }
 
     /// Deletes all data in the current database.
@@ -546,7 +1307,7 @@ impl Client {
 }
 
 #[cfg(test)]
-pub(in crate) mod tests {
+pub(crate) mod tests {
     use self::impls::SumDictDeleteError;
     use super::*;
     use crate::{
@@ -554,6 +1315,10 @@ pub(in crate) mod tests {
         storage::{tests::utils::*, LocalSeedDictAddError, MaskScoreIncrError, SumPartAddError},
     };
     use serial_test::serial;
+    use xaynet_core::{
+        crypto::{ByteObject, SigningKeyPair},
+        mask::{EncryptedMaskSeed, MaskSeed},
+    };
 
     async fn create_redis_client() -> Client {
         Client::new("redis://127.0.0.1/").await.unwrap()
@@ -580,6 +1345,87 @@ pub(in crate) mod tests {
         assert_eq!(set_state, get_state)
     }
 
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_leader_lock() {
+        let mut leader = init_client().await;
+        let mut standby = create_redis_client().await;
+        let ttl = Duration::from_millis(200);
+
+        let token = leader
+            .acquire_leadership("leader", ttl)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(standby
+            .acquire_leadership("standby", ttl)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(standby.leader().await.unwrap().unwrap(), "leader");
+        assert!(leader.renew_leadership("leader", token, ttl).await.unwrap());
+        assert!(leader.is_latest_token(token).await.unwrap());
+
+        // the standby takes over after the lock expired and the old token is superseded
+        tokio::time::sleep(2 * ttl).await;
+        let new_token = standby
+            .acquire_leadership("standby", ttl)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(new_token > token);
+        assert!(!leader.renew_leadership("leader", token, ttl).await.unwrap());
+        assert!(!leader.is_latest_token(token).await.unwrap());
+        assert!(standby.is_latest_token(new_token).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_failover_fences_deposed_leader() {
+        let mut lock = init_client().await;
+        let ttl = Duration::from_millis(200);
+
+        // the old leader persists the state of the round and a sum participant
+        let old_token = lock.acquire_leadership("old", ttl).await.unwrap().unwrap();
+        let mut old_leader = create_redis_client().await.with_fencing_token(old_token);
+        let mut state = CoordinatorState::new(pet_settings(), mask_settings(), model_settings());
+        state.round_id = 3;
+        old_leader.set_coordinator_state(&state).await.unwrap();
+        let sum_pks = create_and_add_sum_participant_entries(&mut old_leader, 1).await;
+        let (update_pk, local_seed_dict) = create_local_seed_entries(&sum_pks).pop().unwrap();
+
+        // the standby takes over after the lock expired
+        tokio::time::sleep(2 * ttl).await;
+        let new_token = lock.acquire_leadership("new", ttl).await.unwrap().unwrap();
+        let mut new_leader = create_redis_client().await.with_fencing_token(new_token);
+
+        // the late writes of the deposed leader are refused atomically
+        let err = old_leader.set_coordinator_state(&state).await.unwrap_err();
+        assert!(err.is::<Deposed>());
+        let res = old_leader
+            .add_local_seed_dict(&update_pk, &local_seed_dict)
+            .await;
+        assert!(matches!(res, Err(err) if err.is::<Deposed>()));
+        let err = old_leader.delete_dicts().await.unwrap_err();
+        assert!(err.is::<Deposed>());
+
+        // the new leader continues the same round and accepts the update
+        let restored = new_leader.coordinator_state().await.unwrap().unwrap();
+        assert_eq!(restored.round_id, 3);
+        let res = new_leader
+            .add_local_seed_dict(&update_pk, &local_seed_dict)
+            .await;
+        assert!(res.unwrap().into_inner().is_ok());
+        let seed_dict = new_leader.seed_dict().await.unwrap().unwrap();
+        assert!(seed_dict[&sum_pks[0]].contains_key(&update_pk));
+
+        // an unfenced client isn't affected by the fencing
+        let mut unfenced = create_redis_client().await;
+        unfenced.set_coordinator_state(&state).await.unwrap();
+    }
+
     #[tokio::test]
     #[serial]
     #[ignore]
@@ -653,12 +1499,23 @@ pub(in crate) mod tests {
         let result = client.incr_mask_score(&sum_pk, &mask).await.unwrap();
         assert!(result.is_ok());
 
-        let already_submitted = client.incr_mask_score(&sum_pk, &mask).await.unwrap();
+        // resubmitting the same mask is benign
+        let already_recorded = client.incr_mask_score(&sum_pk, &mask).await.unwrap();
+        assert!(matches!(
+            already_recorded.into_inner().unwrap_err(),
+            MaskScoreIncrError::AlreadyRecorded
+        ));
 
+        // submitting another mask is a conflict
+        let other_mask = create_mask(10, 1);
+        let already_submitted = client.incr_mask_score(&sum_pk, &other_mask).await.unwrap();
         assert!(matches!(
             already_submitted.into_inner().unwrap_err(),
             MaskScoreIncrError::MaskAlreadySubmitted
         ));
+
+        let best_masks = client.best_masks().await.unwrap().unwrap();
+        assert_eq!(best_masks, vec![(mask, 1)]);
     }
 
     #[tokio::test]
@@ -781,13 +1638,34 @@ pub(in crate) mod tests {
             entries.push((pk, epk));
         }
 
-        // ensure that add_sum_participant returns SumPartAddError::AlreadyExists if the key already exist
+        // ensure that add_sum_participant returns SumPartAddError::AlreadyRecorded if the key
+        // already exists with the same ephemeral key
         let (pk, epk) = entries.get(0).unwrap();
-        let key_already_exist = client.add_sum_participant(pk, epk).await.unwrap();
+        let already_recorded = client.add_sum_participant(pk, epk).await.unwrap();
+        assert!(matches!(
+            already_recorded.into_inner().unwrap_err(),
+            SumPartAddError::AlreadyRecorded
+        ));
+
+        // ensure that add_sum_participant returns SumPartAddError::AlreadyExists if the key
+        // already exists with another ephemeral key
+        let (_, other_epk) = create_sum_participant_entry();
+        let key_already_exist = client.add_sum_participant(pk, &other_epk).await.unwrap();
         assert!(matches!(
             key_already_exist.into_inner().unwrap_err(),
             SumPartAddError::AlreadyExists
         ));
+        assert_eq!(client.sum_dict().await.unwrap().unwrap().get(pk), Some(epk));
+
+        // ensure that add_sum_participant returns SumPartAddError::EphemeralKeyCollision if
+        // another sum participant already uses the ephemeral key
+        let (other_pk, _) = create_sum_participant_entry();
+        let collision = client.add_sum_participant(&other_pk, epk).await.unwrap();
+        assert!(matches!(
+            collision.into_inner().unwrap_err(),
+            SumPartAddError::EphemeralKeyCollision
+        ));
+        assert!(!client.sum_pks().await.unwrap().contains(&other_pk));
 
         // ensure that get_sum_dict_len returns 2
         let len_of_sum_dict = client.sum_dict_len().await.unwrap();
@@ -820,6 +1698,170 @@ pub(in crate) mod tests {
         assert!(sum_dict.is_none());
     }
 
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_evict_sum_participants() {
+        let mut client = init_client().await;
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 3).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+        let mask = create_mask_zeroed(10);
+        client.incr_mask_score(&sum_pks[0], &mask).await.unwrap();
+
+        // nothing is evicted within the capacity
+        let evicted = client.evict_sum_participants(3).await.unwrap();
+        assert!(evicted.is_empty());
+
+        // the oldest sum participant is evicted together with its seed dict entry
+        let evicted = client.evict_sum_participants(2).await.unwrap();
+        assert_eq!(evicted, vec![sum_pks[0]]);
+
+        let sum_dict = client.sum_dict().await.unwrap().unwrap();
+        assert_eq!(sum_dict.len(), 2);
+        assert!(!sum_dict.contains_key(&sum_pks[0]));
+
+        let seed_dict = client.seed_dict().await.unwrap().unwrap();
+        assert_eq!(seed_dict.len(), 2);
+        assert!(!seed_dict.contains_key(&sum_pks[0]));
+        let update_seed_dict = client.seed_dict_for_sum_pk(&sum_pks[0]).await.unwrap();
+        assert!(update_seed_dict.is_empty());
+
+        // the eviction is recorded in the submission status
+        let status = client.submission_status(&sum_pks[0]).await.unwrap();
+        assert_eq!(
+            status,
+            SubmissionStatus {
+                evicted: true,
+                ..SubmissionStatus::default()
+            }
+        );
+
+        // the evicted sum participant can't submit a mask anymore
+        let unknown_sum_pk = client.incr_mask_score(&sum_pks[0], &mask).await.unwrap();
+        assert!(matches!(
+            unknown_sum_pk.into_inner().unwrap_err(),
+            MaskScoreIncrError::UnknownSumPk
+        ));
+
+        // the next oldest sum participant is evicted next
+        let evicted = client.evict_sum_participants(1).await.unwrap();
+        assert_eq!(evicted, vec![sum_pks[1]]);
+        assert_eq!(client.sum_dict_len().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_submission_status_after_restore() {
+        let mut client = init_client().await;
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+        let mask = create_mask_zeroed(10);
+        let res = client.incr_mask_score(&sum_pks[0], &mask).await.unwrap();
+        assert!(res.is_ok());
+
+        // a restored coordinator reads the submissions through a new connection
+        let mut restored = create_redis_client().await;
+        let status = restored.submission_status(&sum_pks[0]).await.unwrap();
+        assert_eq!(
+            status,
+            SubmissionStatus {
+                sum: true,
+                update: false,
+                sum2: true,
+                evicted: false,
+            }
+        );
+        let status = restored.submission_status(&sum_pks[1]).await.unwrap();
+        assert_eq!(
+            status,
+            SubmissionStatus {
+                sum: true,
+                update: false,
+                sum2: false,
+                evicted: false,
+            }
+        );
+        let update_pk = local_seed_dicts[0].0;
+        let status = restored.submission_status(&update_pk).await.unwrap();
+        assert_eq!(
+            status,
+            SubmissionStatus {
+                sum: false,
+                update: true,
+                sum2: false,
+                evicted: false,
+            }
+        );
+        let (unknown_pk, _) = create_sum_participant_entry();
+        let status = restored.submission_status(&unknown_pk).await.unwrap();
+        assert_eq!(status, SubmissionStatus::default());
+
+        // the submissions are deleted together with the dictionaries
+        restored.delete_dicts().await.unwrap();
+        let status = restored.submission_status(&sum_pks[0]).await.unwrap();
+        assert_eq!(status, SubmissionStatus::default());
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_aggregation_checkpoint() {
+        let mut client = init_client().await;
+        assert!(client.aggregation_checkpoint(1).await.unwrap().is_none());
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        // the checkpoint only covers the first update participant
+        let checkpoint = AggregationCheckpoint {
+            round_id: 1,
+            aggregated_models: create_mask_zeroed(10),
+            nb_models: 1,
+            update_pks: vec![local_seed_dicts[0].0],
+        };
+        client
+            .set_aggregation_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        assert_eq!(
+            client.aggregation_checkpoint(1).await.unwrap(),
+            Some(checkpoint.clone())
+        );
+        assert!(client.aggregation_checkpoint(2).await.unwrap().is_none());
+
+        // the seed dict is rolled back to the checkpoint
+        let removed = client
+            .retain_update_participants(&checkpoint.update_pks)
+            .await
+            .unwrap();
+        assert_eq!(removed, vec![local_seed_dicts[1].0]);
+        assert_eq!(
+            client.number_of_unique_update_participants().await.unwrap(),
+            1
+        );
+        let status = client
+            .submission_status(&local_seed_dicts[1].0)
+            .await
+            .unwrap();
+        assert_eq!(status, SubmissionStatus::default());
+
+        // the removed update participant may submit the same local seed dict again
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts[1..]).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        client.delete_dicts().await.unwrap();
+        assert!(client.aggregation_checkpoint(1).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     #[serial]
     #[ignore]
@@ -839,6 +1881,57 @@ pub(in crate) mod tests {
         assert_eq!(seed_dict, redis_seed_dict)
     }
 
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_seed_dict_large_seeds() {
+        let mut client = init_client().await;
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+        // the seeds of a seed cipher with larger ciphertexts than the default sealed box
+        let local_seed_dicts = create_local_seed_entries(&sum_pks)
+            .into_iter()
+            .map(|(update_pk, local_seed_dict)| {
+                let local_seed_dict =
+                    LocalSeedDict::from_decoded(local_seed_dict.keys().map(|sum_pk| {
+                        let seed = MaskSeed::generate().as_slice().repeat(32);
+                        (*sum_pk, EncryptedMaskSeed::from(seed))
+                    }));
+                (update_pk, local_seed_dict)
+            })
+            .collect::<Vec<_>>();
+
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        let redis_sum_dict = client.sum_dict().await.unwrap().unwrap();
+        let seed_dict = create_seed_dict(redis_sum_dict, &local_seed_dicts);
+
+        let redis_seed_dict = client.seed_dict().await.unwrap().unwrap();
+        assert_eq!(seed_dict, redis_seed_dict);
+        for seeds in redis_seed_dict.values() {
+            assert!(seeds.values().all(|seed| seed.len() == 1024));
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_number_of_unique_update_participants() {
+        let mut client = init_client().await;
+
+        let number = client.number_of_unique_update_participants().await.unwrap();
+        assert_eq!(number, 0);
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        let number = client.number_of_unique_update_participants().await.unwrap();
+        assert_eq!(number, local_seed_dicts.len() as u64);
+    }
+
     #[tokio::test]
     #[serial]
     #[ignore]
@@ -894,13 +1987,32 @@ pub(in crate) mod tests {
         let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
         update_result.iter().for_each(|res| assert!(res.is_ok()));
 
+        // resubmitting the same local seed dicts is benign
         let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::AlreadyRecorded
+            ))
+        });
+
+        // submitting other local seed dicts is a conflict
+        let other_local_seed_dicts = local_seed_dicts
+            .iter()
+            .zip(create_local_seed_entries(&sum_pks))
+            .map(|((update_pk, _), (_, local_seed_dict))| (*update_pk, local_seed_dict))
+            .collect::<Vec<_>>();
+        let update_result = add_local_seed_entries(&mut client, &other_local_seed_dicts).await;
         update_result.into_iter().for_each(|res| {
             assert!(matches!(
                 res.into_inner().unwrap_err(),
                 LocalSeedDictAddError::UpdatePkAlreadySubmitted
             ))
         });
+
+        let redis_sum_dict = client.sum_dict().await.unwrap().unwrap();
+        let seed_dict = create_seed_dict(redis_sum_dict, &local_seed_dicts);
+        assert_eq!(client.seed_dict().await.unwrap().unwrap(), seed_dict);
     }
 
     #[tokio::test]
@@ -931,6 +2043,104 @@ pub(in crate) mod tests {
         });
     }
 
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_seed_dict_duplicate_seed() {
+        let mut client = init_client().await;
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        // an identical local seed dict under a new update pk is rejected
+        let (_, local_seed_dict) = local_seed_dicts.get(0).unwrap().clone();
+        let update_pk = SigningKeyPair::generate().public;
+        let update_result =
+            add_local_seed_entries(&mut client, &[(update_pk, local_seed_dict)]).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::DuplicateSeed
+            ))
+        });
+        let seed_dict = client.seed_dict().await.unwrap().unwrap();
+        assert!(seed_dict
+            .values()
+            .all(|seeds| !seeds.contains_key(&update_pk)));
+
+        // distinct local seed dicts are unaffected
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_add_local_seed_dicts() {
+        let mut client = init_client().await;
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = client.add_local_seed_dicts(&local_seed_dicts).await.unwrap();
+        assert_eq!(update_result.len(), local_seed_dicts.len());
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        let redis_sum_dict = client.sum_dict().await.unwrap().unwrap();
+        let seed_dict = create_seed_dict(redis_sum_dict, &local_seed_dicts);
+        let redis_seed_dict = client.seed_dict().await.unwrap().unwrap();
+        assert_eq!(seed_dict, redis_seed_dict);
+
+        // a batch with one resubmitted entry
+        let mut resubmitted = create_local_seed_entries(&sum_pks);
+        resubmitted.insert(1, local_seed_dicts[0].clone());
+        let update_result = client.add_local_seed_dicts(&resubmitted).await.unwrap();
+        assert_eq!(update_result.len(), resubmitted.len());
+        for (i, res) in update_result.into_iter().enumerate() {
+            if i == 1 {
+                assert!(matches!(
+                    res.into_inner().unwrap_err(),
+                    LocalSeedDictAddError::AlreadyRecorded
+                ));
+            } else {
+                assert!(res.is_ok());
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_seed_dict_batching() {
+        let mut client = init_client()
+            .await
+            .with_seed_dict_batching(4, Duration::from_millis(10));
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let writes = local_seed_dicts.iter().map(|(update_pk, local_seed_dict)| {
+            let mut client = client.clone();
+            async move { client.add_local_seed_dict(update_pk, local_seed_dict).await }
+        });
+        let update_result = futures::future::join_all(writes).await;
+        update_result
+            .into_iter()
+            .for_each(|res| assert!(res.unwrap().is_ok()));
+
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts[..1]).await;
+        assert!(matches!(
+            update_result
+                .into_iter()
+                .next()
+                .unwrap()
+                .into_inner()
+                .unwrap_err(),
+            LocalSeedDictAddError::AlreadyRecorded
+        ));
+    }
+
     #[tokio::test]
     #[serial]
     #[ignore]
@@ -963,6 +2173,99 @@ pub(in crate) mod tests {
         assert!(result.is_empty())
     }
 
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_collected_round() {
+        let mut client = init_client().await;
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        for (number, (update_pk, _)) in local_seed_dicts.iter().enumerate() {
+            let masked_model = create_mask(10, number as u32);
+            client
+                .add_collected_masked_model(1, update_pk, &masked_model)
+                .await
+                .unwrap();
+        }
+        let seed_dict = client.seed_dict().await.unwrap().unwrap();
+        client.set_collected_seed_dict(1, &seed_dict).await.unwrap();
+
+        // the collected data survives the deletion of the dictionaries
+        client.delete_dicts().await.unwrap();
+
+        let masked_models = client.collected_masked_models(1).await.unwrap();
+        assert_eq!(masked_models.len(), local_seed_dicts.len());
+        for (number, (update_pk, _)) in local_seed_dicts.iter().enumerate() {
+            assert_eq!(masked_models[update_pk], create_mask(10, number as u32));
+        }
+        assert_eq!(
+            client.collected_seed_dict(1).await.unwrap(),
+            Some(seed_dict)
+        );
+
+        assert!(client.collected_masked_models(2).await.unwrap().is_empty());
+        assert!(client.collected_seed_dict(2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_model_checksums() {
+        let mut client = init_client().await;
+
+        let update_pks: Vec<_> = (0..2).map(|_| SigningKeyPair::generate().public).collect();
+        for (number, update_pk) in update_pks.iter().enumerate() {
+            let checksum = Sha256::fill_with(number as u8);
+            client
+                .add_model_checksum(1, update_pk, &checksum)
+                .await
+                .unwrap();
+        }
+
+        // the model checksums survive the deletion of the dictionaries and coordinator data
+        client.delete_dicts().await.unwrap();
+        client.delete_coordinator_data().await.unwrap();
+
+        let model_checksums = client.model_checksums(1).await.unwrap();
+        assert_eq!(model_checksums.len(), update_pks.len());
+        for (number, update_pk) in update_pks.iter().enumerate() {
+            assert_eq!(model_checksums[update_pk], Sha256::fill_with(number as u8));
+        }
+
+        assert!(client.model_checksums(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn integration_denylist() {
+        let mut client = init_client().await;
+
+        let pks: Vec<_> = (0..2).map(|_| SigningKeyPair::generate().public).collect();
+        for pk in pks.iter() {
+            assert!(client.add_denied_participant(pk).await.unwrap());
+        }
+        assert!(!client.add_denied_participant(&pks[0]).await.unwrap());
+
+        // the denylist survives the deletion of the dictionaries and coordinator data
+        client.delete_dicts().await.unwrap();
+        client.delete_coordinator_data().await.unwrap();
+
+        let mut denied = client.denied_participants().await.unwrap();
+        denied.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+        let mut expected = pks.clone();
+        expected.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+        assert_eq!(denied, expected);
+
+        assert!(client.remove_denied_participant(&pks[0]).await.unwrap());
+        assert!(!client.remove_denied_participant(&pks[0]).await.unwrap());
+        assert_eq!(client.denied_participants().await.unwrap(), vec![pks[1]]);
+    }
+
     #[tokio::test]
     #[serial]
     #[ignore]
@@ -1011,6 +2314,9 @@ pub(in crate) mod tests {
 
         let res = client.best_masks().await;
         assert!(res.unwrap().is_none());
+
+        let keys = client.keys().await.unwrap();
+        assert!(keys.iter().all(|key| !key.starts_with("seed_hashes:")));
     }
 
     #[tokio::test]
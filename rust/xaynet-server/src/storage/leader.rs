@@ -0,0 +1,279 @@
+//! Leader election between coordinator replicas which share the same storage.
+//!
+//! The replicas compete for a [`LeaderLock`] with a time to live. The replica which holds the
+//! lock is the leader and renews the lock in an interval, the other replicas stand by and try to
+//! acquire the lock in the same interval. Each acquisition of the lock hands out a new, strictly
+//! increasing [`FencingToken`]. The coordinator storage of the leader is fenced with its token, see
+//! e.g. [`Client::with_fencing_token()`]. The storage checks the token atomically with each state
+//! mutating operation and refuses the operation once the token has been superseded, such that a
+//! deposed leader which didn't notice yet that its lock expired can't overwrite the state of the
+//! new leader.
+//!
+//! See [`LeaderElection`] for more details.
+//!
+//! [`Client::with_fencing_token()`]: crate::storage::coordinator_storage::redis::Client::with_fencing_token
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use displaydoc::Display;
+use thiserror::Error;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::{settings::LeaderSettings, storage::StorageResult};
+
+/// A token which is handed out with each acquisition of the leader lock.
+///
+/// The tokens are strictly increasing, hence a token which is lower than the latest token
+/// belongs to a deposed leader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FencingToken(pub u64);
+
+#[derive(Debug, Display, Error)]
+/// The coordinator is no longer the leader: the fencing token {0:?} has been superseded.
+pub struct Deposed(pub FencingToken);
+
+/// A lock in the storage which elects the leader among the coordinator replicas.
+#[async_trait]
+pub trait LeaderLock
+where
+    Self: Clone + Send + Sync + 'static,
+{
+    /// Tries to acquire the lock for the replica with the advertised `address`, which expires
+    /// after `ttl` unless it is renewed.
+    ///
+    /// Returns the new fencing token if the lock has been acquired and `None` if another replica
+    /// holds the lock.
+    ///
+    /// # Errors
+    /// Fails if an error occurs in the storage layer.
+    async fn acquire_leadership(
+        &mut self,
+        address: &str,
+        ttl: Duration,
+    ) -> StorageResult<Option<FencingToken>>;
+
+    /// Renews the lock which has been acquired with the `token` for another `ttl`.
+    ///
+    /// Returns `false` if the lock expired in the meantime.
+    ///
+    /// # Errors
+    /// Fails if an error occurs in the storage layer.
+    async fn renew_leadership(
+        &mut self,
+        address: &str,
+        token: FencingToken,
+        ttl: Duration,
+    ) -> StorageResult<bool>;
+
+    /// Gets the advertised address of the current leader, if any.
+    ///
+    /// # Errors
+    /// Fails if an error occurs in the storage layer.
+    async fn leader(&mut self) -> StorageResult<Option<String>>;
+
+    /// Checks whether the `token` is the latest fencing token.
+    ///
+    /// # Errors
+    /// Fails if an error occurs in the storage layer.
+    async fn is_latest_token(&mut self, token: FencingToken) -> StorageResult<bool>;
+}
+
+/// The role of a coordinator replica.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// The replica is the leader and holds the lock with the fencing token.
+    Leader(FencingToken),
+    /// The replica stands by, the advertised address of the current leader is attached if known.
+    Standby(Option<String>),
+}
+
+/// The election of the leader among the coordinator replicas.
+///
+/// The election runs as a background task, which renews the lock while the replica is the
+/// leader and tries to acquire it otherwise. The current [`Role`] of the replica is published
+/// via a watch channel.
+pub struct LeaderElection<L> {
+    lock: L,
+    settings: LeaderSettings,
+    role_tx: watch::Sender<Role>,
+}
+
+impl<L> LeaderElection<L>
+where
+    L: LeaderLock,
+{
+    /// Creates a new election for a replica which stands by initially.
+    pub fn new(lock: L, settings: LeaderSettings) -> (Self, watch::Receiver<Role>) {
+        let (role_tx, role_rx) = watch::channel(Role::Standby(None));
+        let election = Self {
+            lock,
+            settings,
+            role_tx,
+        };
+        (election, role_rx)
+    }
+
+    /// Runs the election until all receivers of the role are dropped.
+    pub async fn run(mut self) {
+        let renew_interval = Duration::from_millis(self.settings.renew_interval);
+        let mut interval = tokio::time::interval(renew_interval);
+        loop {
+            interval.tick().await;
+            let role = self.step().await;
+            if *self.role_tx.borrow() != role {
+                match role {
+                    Role::Leader(token) => info!("became the leader with token {:?}", token),
+                    Role::Standby(ref leader) => info!("standing by for the leader {:?}", leader),
+                }
+            }
+            if self.role_tx.send(role).is_err() {
+                debug!("stopping the leader election: all receivers dropped");
+                break;
+            }
+        }
+    }
+
+    /// Renews or acquires the lock and gets the resulting role.
+    ///
+    /// A storage error demotes a leader to a standby replica, since it can't be sure that it
+    /// still holds the lock.
+    pub async fn step(&mut self) -> Role {
+        let LeaderSettings { address, ttl, .. } = &self.settings;
+        let ttl = Duration::from_millis(*ttl);
+
+        let current = self.role_tx.borrow().clone();
+        if let Role::Leader(token) = current {
+            match self.lock.renew_leadership(address, token, ttl).await {
+                Ok(true) => return Role::Leader(token),
+                Ok(false) => warn!("lost the leadership: the leader lock expired"),
+                Err(err) => warn!("lost the leadership: failed to renew the lock: {}", err),
+            }
+        }
+
+        match self.lock.acquire_leadership(address, ttl).await {
+            Ok(Some(token)) => Role::Leader(token),
+            Ok(None) => Role::Standby(self.lock.leader().await.unwrap_or_default()),
+            Err(err) => {
+                warn!("failed to acquire the leader lock: {}", err);
+                Role::Standby(None)
+            }
+        }
+    }
+}
+
+/// Waits until the replica becomes the leader and gets its fencing token.
+///
+/// Returns `None` if the election stopped.
+pub async fn leadership(role_rx: &mut watch::Receiver<Role>) -> Option<FencingToken> {
+    loop {
+        if let Role::Leader(token) = *role_rx.borrow() {
+            return Some(token);
+        }
+        role_rx.changed().await.ok()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        state_machine::tests::CoordinatorStateBuilder,
+        storage::{
+            coordinator_storage::memory::MemoryStorage,
+            tests::utils::{create_local_seed_entries, create_sum_participant_entry},
+            CoordinatorStorage,
+        },
+    };
+
+    fn settings(address: &str) -> LeaderSettings {
+        LeaderSettings {
+            address: address.to_string(),
+            ttl: 5000,
+            renew_interval: 1000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_election() {
+        let store = MemoryStorage::new();
+        let (mut leader, _leader_rx) = LeaderElection::new(store.clone(), settings("leader"));
+        let (mut standby, _standby_rx) = LeaderElection::new(store.clone(), settings("standby"));
+
+        let token = match leader.step().await {
+            Role::Leader(token) => token,
+            role => panic!("unexpected role {:?}", role),
+        };
+        leader.role_tx.send(Role::Leader(token)).unwrap();
+        assert_eq!(
+            standby.step().await,
+            Role::Standby(Some("leader".to_string()))
+        );
+
+        // the leader keeps the lock while it renews it
+        assert_eq!(leader.step().await, Role::Leader(token));
+
+        // the standby takes over after the lock expired
+        store.expire_leadership();
+        let new_token = match standby.step().await {
+            Role::Leader(new_token) => new_token,
+            role => panic!("unexpected role {:?}", role),
+        };
+        assert!(new_token > token);
+        assert_eq!(
+            leader.step().await,
+            Role::Standby(Some("standby".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failover_fences_deposed_leader() {
+        let store = MemoryStorage::new();
+        let state = CoordinatorStateBuilder::new().with_round_id(3).build();
+
+        // the old leader acquires the lock and persists the state of the round and its sum
+        // participant
+        let old_token = store
+            .clone()
+            .acquire_leadership("old", Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut old_leader = store.clone().with_fencing_token(old_token);
+        old_leader.set_coordinator_state(&state).await.unwrap();
+        let (sum_pk, ephm_pk) = create_sum_participant_entry();
+        let res = old_leader.add_sum_participant(&sum_pk, &ephm_pk).await;
+        assert!(res.unwrap().into_inner().is_ok());
+        let (update_pk, local_seed_dict) = create_local_seed_entries(&[sum_pk]).pop().unwrap();
+
+        // the lock expires and the standby takes over
+        store.expire_leadership();
+        let new_token = store
+            .clone()
+            .acquire_leadership("new", Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut new_leader = store.clone().with_fencing_token(new_token);
+
+        // the late writes of the deposed leader are fenced
+        let err = old_leader.set_coordinator_state(&state).await.unwrap_err();
+        assert!(err.is::<Deposed>());
+        let res = old_leader
+            .add_local_seed_dict(&update_pk, &local_seed_dict)
+            .await;
+        assert!(matches!(res, Err(err) if err.is::<Deposed>()));
+
+        // the new leader continues the same round and accepts an update
+        let restored = new_leader.coordinator_state().await.unwrap().unwrap();
+        assert_eq!(restored.round_id, 3);
+        let res = new_leader
+            .add_local_seed_dict(&update_pk, &local_seed_dict)
+            .await;
+        assert!(res.unwrap().into_inner().is_ok());
+        let seed_dict = new_leader.seed_dict().await.unwrap().unwrap();
+        assert!(seed_dict[&sum_pk].contains_key(&update_pk));
+    }
+}
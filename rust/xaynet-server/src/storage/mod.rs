@@ -1,6 +1,9 @@
 //! Storage backends for the coordinator.
 
+pub mod blob_sink;
+pub mod circuit_breaker;
 pub mod coordinator_storage;
+pub mod leader;
 pub mod model_storage;
 pub mod store;
 #[cfg(test)]
@@ -9,8 +12,12 @@ pub mod traits;
 pub mod trust_anchor;
 
 pub use self::{
+    blob_sink::{BlobSink, FsBlobSink},
+    circuit_breaker::CircuitBreaker,
+    leader::{LeaderElection, LeaderLock},
     store::Store,
     traits::{
+        AggregationCheckpoint,
         CoordinatorStorage,
         LocalSeedDictAdd,
         LocalSeedDictAddError,
@@ -6,6 +6,7 @@ use crate::{
     state_machine::coordinator::CoordinatorState,
     storage::{
         trust_anchor::noop::NoOp,
+        AggregationCheckpoint,
         CoordinatorStorage,
         LocalSeedDictAdd,
         MaskScoreIncr,
@@ -17,9 +18,11 @@ use crate::{
     },
 };
 use xaynet_core::{
-    common::RoundSeed,
+    common::{RoundSeed, SubmissionStatus},
+    crypto::Sha256,
     mask::{MaskObject, Model},
     LocalSeedDict,
+    ParticipantPublicKey,
     SeedDict,
     SumDict,
     SumParticipantEphemeralPublicKey,
@@ -56,6 +59,11 @@ where
             trust_anchor,
         }
     }
+
+    /// Gets the coordinator store.
+    pub fn coordinator(&self) -> &C {
+        &self.coordinator
+    }
 }
 
 impl<C, M> Store<C, M, NoOp>
@@ -100,6 +108,13 @@ where
         self.coordinator.sum_dict().await
     }
 
+    async fn evict_sum_participants(
+        &mut self,
+        capacity: u64,
+    ) -> StorageResult<Vec<SumParticipantPublicKey>> {
+        self.coordinator.evict_sum_participants(capacity).await
+    }
+
     async fn add_local_seed_dict(
         &mut self,
         update_pk: &UpdateParticipantPublicKey,
@@ -114,6 +129,38 @@ where
         self.coordinator.seed_dict().await
     }
 
+    async fn add_collected_masked_model(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        masked_model: &MaskObject,
+    ) -> StorageResult<()> {
+        self.coordinator
+            .add_collected_masked_model(round_id, update_pk, masked_model)
+            .await
+    }
+
+    async fn add_model_checksum(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        model_checksum: &Sha256,
+    ) -> StorageResult<()> {
+        self.coordinator
+            .add_model_checksum(round_id, update_pk, model_checksum)
+            .await
+    }
+
+    async fn set_collected_seed_dict(
+        &mut self,
+        round_id: u64,
+        seed_dict: &SeedDict,
+    ) -> StorageResult<()> {
+        self.coordinator
+            .set_collected_seed_dict(round_id, seed_dict)
+            .await
+    }
+
     async fn incr_mask_score(
         &mut self,
         pk: &SumParticipantPublicKey,
@@ -126,10 +173,52 @@ where
         self.coordinator.best_masks().await
     }
 
+    async fn number_of_unique_update_participants(&mut self) -> StorageResult<u64> {
+        self.coordinator
+            .number_of_unique_update_participants()
+            .await
+    }
+
     async fn number_of_unique_masks(&mut self) -> StorageResult<u64> {
         self.coordinator.number_of_unique_masks().await
     }
 
+    async fn submission_status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<SubmissionStatus> {
+        self.coordinator.submission_status(pk).await
+    }
+
+    async fn set_aggregation_checkpoint(
+        &mut self,
+        checkpoint: &AggregationCheckpoint,
+    ) -> StorageResult<()> {
+        self.coordinator
+            .set_aggregation_checkpoint(checkpoint)
+            .await
+    }
+
+    async fn aggregation_checkpoint(
+        &mut self,
+        round_id: u64,
+    ) -> StorageResult<Option<AggregationCheckpoint>> {
+        self.coordinator.aggregation_checkpoint(round_id).await
+    }
+
+    async fn delete_aggregation_checkpoint(&mut self) -> StorageResult<()> {
+        self.coordinator.delete_aggregation_checkpoint().await
+    }
+
+    async fn retain_update_participants(
+        &mut self,
+        update_pks: &[UpdateParticipantPublicKey],
+    ) -> StorageResult<Vec<UpdateParticipantPublicKey>> {
+        self.coordinator
+            .retain_update_participants(update_pks)
+            .await
+    }
+
     async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
         self.coordinator.delete_coordinator_data().await
     }
@@ -138,6 +227,21 @@ where
         self.coordinator.delete_dicts().await
     }
 
+    async fn add_denied_participant(&mut self, pk: &ParticipantPublicKey) -> StorageResult<bool> {
+        self.coordinator.add_denied_participant(pk).await
+    }
+
+    async fn remove_denied_participant(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<bool> {
+        self.coordinator.remove_denied_participant(pk).await
+    }
+
+    async fn denied_participants(&mut self) -> StorageResult<Vec<ParticipantPublicKey>> {
+        self.coordinator.denied_participants().await
+    }
+
     async fn set_latest_global_model_id(&mut self, id: &str) -> StorageResult<()> {
         self.coordinator.set_latest_global_model_id(id).await
     }
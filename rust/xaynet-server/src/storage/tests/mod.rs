@@ -3,6 +3,7 @@ use crate::{
     storage::{
         coordinator_storage::redis,
         model_storage,
+        AggregationCheckpoint,
         CoordinatorStorage,
         LocalSeedDictAdd,
         MaskScoreIncr,
@@ -17,9 +18,11 @@ use crate::{
 use async_trait::async_trait;
 use mockall::*;
 use xaynet_core::{
-    common::RoundSeed,
+    common::{RoundSeed, SubmissionStatus},
+    crypto::Sha256,
     mask::{MaskObject, Model},
     LocalSeedDict,
+    ParticipantPublicKey,
     SeedDict,
     SumDict,
     SumParticipantEphemeralPublicKey,
@@ -60,12 +63,34 @@ mock! {
             ephm_pk: &SumParticipantEphemeralPublicKey,
         ) -> StorageResult<SumPartAdd>;
         async fn sum_dict(&mut self) -> StorageResult<Option<SumDict>>;
+        async fn evict_sum_participants(
+            &mut self,
+            capacity: u64,
+        ) -> StorageResult<Vec<SumParticipantPublicKey>>;
         async fn add_local_seed_dict(
             &mut self,
             update_pk: &UpdateParticipantPublicKey,
             local_seed_dict: &LocalSeedDict,
         ) -> StorageResult<LocalSeedDictAdd>;
         async fn seed_dict(&mut self) -> StorageResult<Option<SeedDict>>;
+        async fn number_of_unique_update_participants(&mut self) -> StorageResult<u64>;
+        async fn add_collected_masked_model(
+            &mut self,
+            round_id: u64,
+            update_pk: &UpdateParticipantPublicKey,
+            masked_model: &MaskObject,
+        ) -> StorageResult<()>;
+        async fn add_model_checksum(
+            &mut self,
+            round_id: u64,
+            update_pk: &UpdateParticipantPublicKey,
+            model_checksum: &Sha256,
+        ) -> StorageResult<()>;
+        async fn set_collected_seed_dict(
+            &mut self,
+            round_id: u64,
+            seed_dict: &SeedDict,
+        ) -> StorageResult<()>;
         async fn incr_mask_score(
             &mut self,
             pk: &SumParticipantPublicKey,
@@ -73,8 +98,34 @@ mock! {
         ) -> StorageResult<MaskScoreIncr>;
         async fn best_masks(&mut self) -> StorageResult<Option<Vec<(MaskObject, u64)>>>;
         async fn number_of_unique_masks(&mut self) -> StorageResult<u64>;
+        async fn submission_status(
+            &mut self,
+            pk: &ParticipantPublicKey,
+        ) -> StorageResult<SubmissionStatus>;
+        async fn set_aggregation_checkpoint(
+            &mut self,
+            checkpoint: &AggregationCheckpoint,
+        ) -> StorageResult<()>;
+        async fn aggregation_checkpoint(
+            &mut self,
+            round_id: u64,
+        ) -> StorageResult<Option<AggregationCheckpoint>>;
+        async fn delete_aggregation_checkpoint(&mut self) -> StorageResult<()>;
+        async fn retain_update_participants(
+            &mut self,
+            update_pks: &[UpdateParticipantPublicKey],
+        ) -> StorageResult<Vec<UpdateParticipantPublicKey>>;
         async fn delete_coordinator_data(&mut self) -> StorageResult<()>;
         async fn delete_dicts(&mut self) -> StorageResult<()>;
+        async fn add_denied_participant(
+            &mut self,
+            pk: &ParticipantPublicKey,
+        ) -> StorageResult<bool>;
+        async fn remove_denied_participant(
+            &mut self,
+            pk: &ParticipantPublicKey,
+        ) -> StorageResult<bool>;
+        async fn denied_participants(&mut self) -> StorageResult<Vec<ParticipantPublicKey>>;
         async fn set_latest_global_model_id(&mut self, id: &str) -> StorageResult<()>;
         async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>>;
         async fn is_ready(&mut self) -> StorageResult<()>;
@@ -6,13 +6,14 @@ use crate::{
 };
 use xaynet_core::{
     crypto::{ByteObject, EncryptKeyPair, SigningKeyPair},
-    mask::{EncryptedMaskSeed, MaskConfig, MaskObject},
+    mask::{MaskConfig, MaskObject, MaskSeed},
     LocalSeedDict,
     SeedDict,
     SumDict,
     SumParticipantEphemeralPublicKey,
     SumParticipantPublicKey,
     UpdateParticipantPublicKey,
+    UpdateSeedDict,
 };
 
 pub fn create_sum_participant_entry() -> (SumParticipantPublicKey, SumParticipantEphemeralPublicKey)
@@ -34,11 +35,11 @@ pub fn create_local_seed_entries(
             public: update_pk, ..
         } = SigningKeyPair::generate();
 
-        let mut local_seed_dict = LocalSeedDict::new();
-        for sum_pk in sum_pks {
-            let seed = EncryptedMaskSeed::zeroed();
-            local_seed_dict.insert(*sum_pk, seed);
-        }
+        let sum_dict = sum_pks
+            .iter()
+            .map(|sum_pk| (*sum_pk, EncryptKeyPair::generate().public))
+            .collect::<SumDict>();
+        let local_seed_dict = LocalSeedDict::new(&sum_dict, &MaskSeed::generate());
         entries.push((update_pk, local_seed_dict))
     }
 
@@ -67,18 +68,13 @@ pub fn create_seed_dict(
     sum_dict: SumDict,
     seed_updates: &[(UpdateParticipantPublicKey, LocalSeedDict)],
 ) -> SeedDict {
-    let mut seed_dict: SeedDict = sum_dict
+    sum_dict
         .keys()
-        .map(|pk| (*pk, LocalSeedDict::new()))
-        .collect();
-
-    for (pk, local_seed_dict) in seed_updates {
-        for (sum_pk, seed) in local_seed_dict {
-            seed_dict.get_mut(sum_pk).unwrap().insert(*pk, seed.clone());
-        }
-    }
-
-    seed_dict
+        .map(|sum_pk| {
+            let local_seed_dicts = seed_updates.iter().map(|(pk, dict)| (pk, dict));
+            (*sum_pk, UpdateSeedDict::project(sum_pk, local_seed_dicts))
+        })
+        .collect()
 }
 
 pub async fn create_and_add_sum_participant_entries(
@@ -4,14 +4,16 @@ use async_trait::async_trait;
 use derive_more::Deref;
 use displaydoc::Display;
 use num_enum::TryFromPrimitive;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::state_machine::coordinator::CoordinatorState;
 use xaynet_core::{
-    common::RoundSeed,
-    crypto::ByteObject,
+    common::{RoundSeed, SubmissionStatus},
+    crypto::{ByteObject, Sha256},
     mask::{MaskObject, Model},
     LocalSeedDict,
+    ParticipantPublicKey,
     SeedDict,
     SumDict,
     SumParticipantEphemeralPublicKey,
@@ -57,6 +59,12 @@ where
     /// - If the participant could not be added due to a PET protocol error, return
     ///   the corresponding `StorageResult::Ok(SumPartAdd)` containing a
     ///   `Result::Err(SumPartAddError)`.
+    /// - If the participant has already been added with the same ephemeral key, return
+    ///   `StorageResult::Ok(SumPartAdd)` containing a
+    ///   `Result::Err(SumPartAddError::AlreadyRecorded)`.
+    /// - If another participant has already been added with the same ephemeral key, return
+    ///   `StorageResult::Ok(SumPartAdd)` containing a
+    ///   `Result::Err(SumPartAddError::EphemeralKeyCollision)`.
     async fn add_sum_participant(
         &mut self,
         pk: &SumParticipantPublicKey,
@@ -71,6 +79,20 @@ where
     /// - If the sum dict exists, return `StorageResult::Ok(Option::Some(SumDict))`.
     async fn sum_dict(&mut self) -> StorageResult<Option<SumDict>>;
 
+    /// Evicts the oldest sum participants from the [`SumDict`] until it contains at most
+    /// `capacity` entries.
+    ///
+    /// # Behavior
+    ///
+    /// - If the [`SumDict`] exceeds the capacity, remove the oldest sum participants together
+    ///   with their [`SeedDict`] entries and return `StorageResult::Ok(Vec<SumParticipantPublicKey>)`
+    ///   containing the evicted sum participants, the oldest first.
+    /// - If the [`SumDict`] does not exceed the capacity, return `StorageResult::Ok(Vec::new())`.
+    async fn evict_sum_participants(
+        &mut self,
+        capacity: u64,
+    ) -> StorageResult<Vec<SumParticipantPublicKey>>;
+
     /// Adds a local [`LocalSeedDict`] of the given [`UpdateParticipantPublicKey`] to the [`SeedDict`].
     ///
     /// # Behavior
@@ -80,6 +102,13 @@ where
     /// - If the local seed dict could not be added due to a PET protocol error, return
     ///   the corresponding `StorageResult::Ok(LocalSeedDictAdd)` containing a
     ///   `Result::Err(LocalSeedDictAddError)`.
+    /// - If the same local seed dict has already been added for the update participant, return
+    ///   `StorageResult::Ok(LocalSeedDictAdd)` containing a
+    ///   `Result::Err(LocalSeedDictAddError::AlreadyRecorded)`.
+    ///
+    /// The local seed dict is added for all sum participants at once or not at all. Hence, every
+    /// sum participant of the [`SeedDict`] holds the seeds of the same update participants and the
+    /// number of accepted update messages covers all of them.
     async fn add_local_seed_dict(
         &mut self,
         update_pk: &UpdateParticipantPublicKey,
@@ -94,6 +123,70 @@ where
     /// - If the seed dict exists, return `StorageResult::Ok(Option::Some(SeedDict))`.
     async fn seed_dict(&mut self) -> StorageResult<Option<SeedDict>>;
 
+    /// Returns the number of distinct update participants in the [`SeedDict`].
+    ///
+    /// # Behavior
+    ///
+    /// - If the seed dict does not exist, return `StorageResult::Ok(0)`.
+    /// - If the seed dict exists, return the number of distinct
+    ///   [`UpdateParticipantPublicKey`]s over all sum participants.
+    async fn number_of_unique_update_participants(&mut self) -> StorageResult<u64>;
+
+    /// Adds the masked model of an update participant to the collected masked models of the
+    /// given round.
+    ///
+    /// The collected data of a round is kept for offline processing, hence it is neither deleted
+    /// with the dictionaries nor with the coordinator data.
+    ///
+    /// # Behavior
+    ///
+    /// - If no masked model of the update participant has been collected for the round yet, add
+    ///   the masked model and return `StorageResult::Ok(())`.
+    /// - If a masked model of the update participant has already been collected for the round,
+    ///   override the masked model and return `StorageResult::Ok(())`.
+    async fn add_collected_masked_model(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        masked_model: &MaskObject,
+    ) -> StorageResult<()>;
+
+    /// Adds the model checksum of an update participant to the model checksums of the given
+    /// round.
+    ///
+    /// The model checksums of a round are kept as an auditable record, hence they are neither
+    /// deleted with the dictionaries nor with the coordinator data.
+    ///
+    /// # Behavior
+    ///
+    /// - If no model checksum of the update participant has been added for the round yet, add
+    ///   the model checksum and return `StorageResult::Ok(())`.
+    /// - If a model checksum of the update participant has already been added for the round,
+    ///   override the model checksum and return `StorageResult::Ok(())`.
+    async fn add_model_checksum(
+        &mut self,
+        round_id: u64,
+        update_pk: &UpdateParticipantPublicKey,
+        model_checksum: &Sha256,
+    ) -> StorageResult<()>;
+
+    /// Sets the collected [`SeedDict`] of the given round.
+    ///
+    /// The collected data of a round is kept for offline processing, hence it is neither deleted
+    /// with the dictionaries nor with the coordinator data.
+    ///
+    /// # Behavior
+    ///
+    /// - If no seed dict has been collected for the round yet, set the seed dict and return
+    ///   `StorageResult::Ok(())`.
+    /// - If a seed dict has already been collected for the round, override the seed dict and
+    ///   return `StorageResult::Ok(())`.
+    async fn set_collected_seed_dict(
+        &mut self,
+        round_id: u64,
+        seed_dict: &SeedDict,
+    ) -> StorageResult<()>;
+
     /// Increments the mask score with the given [`MaskObject`]b by one.
     ///
     /// # Behavior
@@ -103,6 +196,9 @@ where
     /// - If the mask score could not be incremented due to a PET protocol error,
     ///   return the corresponding `Result::Ok(MaskScoreIncr)` containing a
     ///   `Result::Err(MaskScoreIncrError)`.
+    /// - If the sum participant has already submitted the same mask, return
+    ///   `StorageResult::Ok(MaskScoreIncr)` containing a
+    ///   `Result::Err(MaskScoreIncrError::AlreadyRecorded)`.
     async fn incr_mask_score(
         &mut self,
         pk: &SumParticipantPublicKey,
@@ -125,13 +221,102 @@ where
     /// Returns the number of unique masks.
     async fn number_of_unique_masks(&mut self) -> StorageResult<u64>;
 
+    /// Returns which messages of the given participant are recorded in the dictionaries.
+    ///
+    /// # Behavior
+    ///
+    /// - `sum` is set if the [`SumDict`] contains the participant.
+    /// - `update` is set if the [`SeedDict`] contains a seed of the participant for any sum
+    ///   participant.
+    /// - `sum2` is set if the participant has submitted a mask to the `mask` dictionary.
+    async fn submission_status(
+        &mut self,
+        pk: &ParticipantPublicKey,
+    ) -> StorageResult<SubmissionStatus>;
+
+    /// Sets the [`AggregationCheckpoint`] of the current round.
+    ///
+    /// The checkpoint must only be set after the local seed dicts of all its update participants
+    /// have been added to the [`SeedDict`].
+    ///
+    /// # Behavior
+    ///
+    /// - If no checkpoint has been set yet, set the checkpoint and return `StorageResult::Ok(())`.
+    /// - If a checkpoint already exists, override the checkpoint and return
+    ///   `StorageResult::Ok(())`.
+    async fn set_aggregation_checkpoint(
+        &mut self,
+        checkpoint: &AggregationCheckpoint,
+    ) -> StorageResult<()>;
+
+    /// Returns the [`AggregationCheckpoint`] of the given round.
+    ///
+    /// # Behavior
+    ///
+    /// - If no checkpoint exists or the checkpoint belongs to another round, return
+    ///   `StorageResult::Ok(Option::None)`.
+    /// - If a checkpoint of the round exists, return
+    ///   `StorageResult::Ok(Option::Some(AggregationCheckpoint))`.
+    async fn aggregation_checkpoint(
+        &mut self,
+        round_id: u64,
+    ) -> StorageResult<Option<AggregationCheckpoint>>;
+
+    /// Deletes the [`AggregationCheckpoint`].
+    async fn delete_aggregation_checkpoint(&mut self) -> StorageResult<()>;
+
+    /// Removes all update participants from the [`SeedDict`] except for the given ones.
+    ///
+    /// This rolls the seed dict back to the update participants of an [`AggregationCheckpoint`].
+    ///
+    /// # Behavior
+    ///
+    /// - Remove the seeds of every other update participant for all sum participants at once and
+    ///   return `StorageResult::Ok(Vec<UpdateParticipantPublicKey>)` containing the removed
+    ///   update participants.
+    /// - If the seed dict does not exist or only contains the given update participants, return
+    ///   `StorageResult::Ok(Vec::new())`.
+    async fn retain_update_participants(
+        &mut self,
+        update_pks: &[UpdateParticipantPublicKey],
+    ) -> StorageResult<Vec<UpdateParticipantPublicKey>>;
+
     /// Deletes all coordinator data. This includes the coordinator
-    /// state as well as the [`SumDict`], [`SeedDict`] and `mask` dictionary.
+    /// state as well as the [`SumDict`], [`SeedDict`], `mask` dictionary and
+    /// [`AggregationCheckpoint`].
     async fn delete_coordinator_data(&mut self) -> StorageResult<()>;
 
-    /// Deletes the [`SumDict`], [`SeedDict`] and `mask` dictionary.
+    /// Deletes the [`SumDict`], [`SeedDict`], `mask` dictionary and [`AggregationCheckpoint`].
     async fn delete_dicts(&mut self) -> StorageResult<()>;
 
+    /// Adds a participant to the denylist of banned participants.
+    ///
+    /// The denylist is kept across restarts of the coordinator, hence it is neither deleted with
+    /// the dictionaries nor with the coordinator data.
+    ///
+    /// # Behavior
+    ///
+    /// - If the participant has been added, return `StorageResult::Ok(true)`.
+    /// - If the participant is already banned, return `StorageResult::Ok(false)`.
+    async fn add_denied_participant(&mut self, pk: &ParticipantPublicKey) -> StorageResult<bool>;
+
+    /// Removes a participant from the denylist of banned participants.
+    ///
+    /// # Behavior
+    ///
+    /// - If the participant has been removed, return `StorageResult::Ok(true)`.
+    /// - If the participant is not banned, return `StorageResult::Ok(false)`.
+    async fn remove_denied_participant(&mut self, pk: &ParticipantPublicKey)
+        -> StorageResult<bool>;
+
+    /// Returns the denylist of banned participants.
+    ///
+    /// # Behavior
+    ///
+    /// - If the denylist does not exist, return `StorageResult::Ok(Vec::new())`.
+    /// - If the denylist exists, return `StorageResult::Ok(Vec<ParticipantPublicKey>)`.
+    async fn denied_participants(&mut self) -> StorageResult<Vec<ParticipantPublicKey>>;
+
     /// Sets the latest global model id.
     ///
     /// # Behavior
@@ -245,6 +430,25 @@ pub trait Storage: CoordinatorStorage + ModelStorage + TrustAnchor {
     async fn is_ready(&mut self) -> StorageResult<()>;
 }
 
+/// A checkpoint of the aggregated masked models of the update phase.
+///
+/// The checkpoint covers exactly the masked models of its update participants. Since it is set
+/// only after their local seed dicts have been added to the [`SeedDict`], the seed dict may
+/// contain further update participants whose masked models are missing from the checkpoint, but
+/// never the other way around. Rolling back the seed dict to the update participants of the
+/// checkpoint makes both mutually consistent again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregationCheckpoint {
+    /// The round of the aggregation.
+    pub round_id: u64,
+    /// The aggregated masked models.
+    pub aggregated_models: MaskObject,
+    /// The number of aggregated masked models.
+    pub nb_models: usize,
+    /// The update participants whose masked models are aggregated.
+    pub update_pks: Vec<UpdateParticipantPublicKey>,
+}
+
 /// A wrapper that contains the result of the "add sum participant" operation.
 #[derive(Deref)]
 pub struct SumPartAdd(pub(crate) Result<(), SumPartAddError>);
@@ -262,6 +466,10 @@ impl SumPartAdd {
 pub enum SumPartAddError {
     /// sum participant already exists
     AlreadyExists = 0,
+    /// sum participant already exists with the same ephemeral key
+    AlreadyRecorded = 2,
+    /// ephemeral key is already used by another sum participant
+    EphemeralKeyCollision = 3,
 }
 
 /// A wrapper that contains the result of the "add local seed dict" operation.
@@ -287,6 +495,10 @@ pub enum LocalSeedDictAddError {
     UpdatePkAlreadySubmitted = -3,
     /// update participant already exists in the inner update seed dict
     UpdatePkAlreadyExistsInUpdateSeedDict = -4,
+    /// local dict contains a seed which another update participant already submitted
+    DuplicateSeed = -5,
+    /// update participant already submitted the same local dict
+    AlreadyRecorded = -6,
 }
 
 /// A wrapper that contains the result of the "increment mask score" operation.
@@ -308,4 +520,6 @@ pub enum MaskScoreIncrError {
     UnknownSumPk = -1,
     /// sum participant submitted a mask already
     MaskAlreadySubmitted = -2,
+    /// sum participant submitted the same mask already
+    AlreadyRecorded = -3,
 }
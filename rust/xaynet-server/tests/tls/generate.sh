@@ -0,0 +1,38 @@
+#!/bin/sh
+# Generates the certificates used by the TLS tests of the REST API:
+# - ca.pem: the CA which signs the server and the trusted client certificate
+# - server.pem/server.key: the server certificate for `localhost`
+# - client.pem/client.key: a client certificate signed by the trusted CA
+# - untrusted_client.pem/untrusted_client.key: a client certificate signed by another CA
+set -e
+cd "$(dirname "$0")"
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+key() {
+    openssl ecparam -name prime256v1 -genkey -noout -out "$tmp/$1.ec.key"
+    openssl pkcs8 -topk8 -nocrypt -in "$tmp/$1.ec.key" -out "$2"
+}
+
+ca() {
+    key "$1" "$tmp/$1.key"
+    openssl req -x509 -new -key "$tmp/$1.key" -sha256 -days 36500 -subj "/CN=$2" \
+        -addext "basicConstraints=critical,CA:TRUE" \
+        -addext "keyUsage=critical,keyCertSign,cRLSign" \
+        -out "$3"
+}
+
+leaf() {
+    key "$1" "$1.key"
+    openssl req -new -key "$1.key" -subj "/CN=$2" -out "$tmp/$1.csr"
+    printf "basicConstraints=critical,CA:FALSE\nkeyUsage=critical,digitalSignature\nextendedKeyUsage=%s\nsubjectAltName=DNS:localhost\n" \
+        "$5" > "$tmp/$1.ext"
+    openssl x509 -req -in "$tmp/$1.csr" -CA "$3" -CAkey "$4" -CAcreateserial \
+        -CAserial "$tmp/$1.srl" -sha256 -days 36500 -extfile "$tmp/$1.ext" -out "$1.pem"
+}
+
+ca ca "Xaynet Test CA" ca.pem
+ca untrusted_ca "Untrusted Test CA" "$tmp/untrusted_ca.pem"
+leaf server localhost ca.pem "$tmp/ca.key" serverAuth
+leaf client "Xaynet Test Participant" ca.pem "$tmp/ca.key" clientAuth
+leaf untrusted_client "Untrusted Test Participant" "$tmp/untrusted_ca.pem" "$tmp/untrusted_ca.key" clientAuth